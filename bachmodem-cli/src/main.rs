@@ -0,0 +1,332 @@
+use bachmodem::*;
+use burn::backend::Wgpu;
+use bachmodem::write_wav;
+
+type MyBackend = Wgpu;
+
+/// Persisted soundcard selection (working directory)
+#[cfg(feature = "audio")]
+const AUDIO_SETTINGS_FILE: &str = "bachmodem-audio.conf";
+
+/// Handle --list-devices / --input NAME / --output NAME / --calibrate-level
+///
+/// Returns true if an audio command ran (and the demo should be skipped).
+#[cfg(feature = "audio")]
+fn audio_commands() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut settings = AudioSettings::load(AUDIO_SETTINGS_FILE).unwrap_or_else(|e| {
+        eprintln!("Ignoring {}: {}", AUDIO_SETTINGS_FILE, e);
+        AudioSettings::default()
+    });
+
+    if args.iter().any(|a| a == "--list-devices") {
+        match list_devices() {
+            Ok(devices) => {
+                for device in &devices {
+                    let mut tags = Vec::new();
+                    if device.default_input { tags.push("default in"); }
+                    if device.default_output { tags.push("default out"); }
+                    println!("[{}] {} {}", device.host, device.name,
+                             if tags.is_empty() { String::new() } else { format!("({})", tags.join(", ")) });
+
+                    for (label, direction) in [("in ", Direction::Input), ("out", Direction::Output)] {
+                        for f in device.formats(direction) {
+                            println!("    {} {} ch {} {}-{} Hz", label, f.channels, f.sample_format, f.min_rate, f.max_rate);
+                        }
+                    }
+                }
+                println!("\nSelected: input {:?}, output {:?} at {} Hz",
+                         settings.input_device, settings.output_device, settings.sample_rate);
+            }
+            Err(e) => eprintln!("Error listing audio devices: {}", e),
+        }
+        return true;
+    }
+
+    if args.iter().any(|a| a == "--calibrate-level") {
+        let config = LevelCalConfig::default();
+        println!("Sending {} test tones at {} Hz, {} to {} dBFS (key the transmitter into a dummy load)",
+                 config.drive_levels().len(), config.tone_hz, config.start_dbfs, config.stop_dbfs);
+
+        match calibrate_tx_level(&settings, &config, 2.0) {
+            Ok(cal) => {
+                for p in &cal.points {
+                    println!("    {:6.1} dBFS -> {:6.1} dB ({:+.1} dB)", p.drive_dbfs, p.measured_db,
+                             p.measured_db - p.drive_dbfs - cal.gain_db);
+                }
+                match cal.compression_dbfs {
+                    Some(c) => println!("Compression ({} dB) at {:.1} dBFS", config.compression_db, c),
+                    None => println!("No compression up to {} dBFS", config.stop_dbfs),
+                }
+                println!("Transmit level {:.1} dBFS ({:.3})", cal.recommended_dbfs, cal.recommended_scale);
+
+                settings.tx_level = cal.recommended_scale;
+                match settings.save(AUDIO_SETTINGS_FILE) {
+                    Ok(()) => println!("Saved to {}", AUDIO_SETTINGS_FILE),
+                    Err(e) => eprintln!("Error writing {}: {}", AUDIO_SETTINGS_FILE, e),
+                }
+            }
+            Err(e) => eprintln!("Level calibration failed: {}", e),
+        }
+        return true;
+    }
+
+    let mut changed = false;
+    for (flag, direction) in [("--input", Direction::Input), ("--output", Direction::Output)] {
+        let Some(pos) = args.iter().position(|a| a == flag) else { continue };
+        let Some(name) = args.get(pos + 1) else {
+            eprintln!("{} needs a device name", flag);
+            return true;
+        };
+
+        // Validate against the present devices before saving
+        let mut candidate = settings.clone();
+        match direction {
+            Direction::Input => candidate.input_device = Some(name.clone()),
+            Direction::Output => candidate.output_device = Some(name.clone()),
+        }
+        match open_device(&candidate, direction) {
+            Ok(_) => {
+                println!("{} device: {}", flag.trim_start_matches("--"), name);
+                settings = candidate;
+                changed = true;
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return true;
+            }
+        }
+    }
+
+    if changed {
+        if let Err(e) = settings.save(AUDIO_SETTINGS_FILE) {
+            eprintln!("Error writing {}: {}", AUDIO_SETTINGS_FILE, e);
+        } else {
+            println!("Saved to {}", AUDIO_SETTINGS_FILE);
+        }
+    }
+    changed
+}
+
+/// Receiver profile written by --calibrate (working directory)
+const PROFILE_FILE: &str = "receiver_profile.txt";
+
+/// Handle --calibrate NOISE.WAV [PROFILE]
+///
+/// Measures a noise-only recording and writes the station defaults (notches,
+/// squelch, CFAR factor) into the receiver profile, keeping an existing
+/// profile's tuning as the base. Returns true if the command ran.
+fn calibrate_command() -> bool {
+    // Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+    type CalBackend = burn::backend::wgpu::CubeBackend<burn::backend::wgpu::WgpuRuntime, f32, i32, u32>;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(pos) = args.iter().position(|a| a == "--calibrate") else {
+        return false;
+    };
+    let Some(wav_path) = args.get(pos + 1) else {
+        eprintln!("--calibrate needs a noise recording (WAV)");
+        return true;
+    };
+    let profile_path = args.get(pos + 2).filter(|a| !a.starts_with("--")).map_or(PROFILE_FILE, |a| a.as_str());
+
+    let base = if std::path::Path::new(profile_path).exists() {
+        match ReceiverTuning::load(profile_path) {
+            Ok(tuning) => tuning,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", profile_path, e);
+                return true;
+            }
+        }
+    } else {
+        ReceiverTuning::default()
+    };
+
+    let device = Default::default();
+    let noise = match read_wav::<CalBackend>(&device, std::path::Path::new(wav_path)) {
+        Ok(noise) => noise,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", wav_path, e);
+            return true;
+        }
+    };
+    let config = NoiseCalibrationConfig::default();
+    if noise.dims()[0] < config.fft_len {
+        eprintln!("{} is too short for a calibration (use about a minute of noise)", wav_path);
+        return true;
+    }
+
+    let calibration = analyze_noise(&device, &noise, &ModemConfig::default(), &config);
+    print!("{}", calibration.summary());
+    for (centre, level) in &calibration.shape_db {
+        println!("    {:6.0} Hz {:+5.1} dB", centre, level);
+    }
+
+    let (tuning, front_end) = calibration.station_profile(&base, &config);
+    println!("Squelch {:.4}, CFAR {:.2}, {} notches",
+             tuning.sync.min_correlation, tuning.sync.min_peak_to_noise, front_end.notches_hz.len());
+    match calibration.save_profile(profile_path, &base, &config) {
+        Ok(()) => println!("Saved to {}", profile_path),
+        Err(e) => eprintln!("Error writing {}: {}", profile_path, e),
+    }
+    true
+}
+
+/// Handle --decode WAV... [--profile PROFILE]
+///
+/// Decodes recordings with the station profile written by --calibrate
+/// (tuning and front end), one worker per available core. Returns true if
+/// the command ran.
+fn decode_command() -> bool {
+    // Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+    type RxBackend = burn::backend::wgpu::CubeBackend<burn::backend::wgpu::WgpuRuntime, f32, i32, u32>;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(pos) = args.iter().position(|a| a == "--decode") else {
+        return false;
+    };
+    let profile_arg = args.iter().position(|a| a == "--profile").and_then(|p| args.get(p + 1));
+    let wav_paths: Vec<&String> = args[pos + 1..].iter().take_while(|a| !a.starts_with("--")).collect();
+    if wav_paths.is_empty() {
+        eprintln!("--decode needs one or more recordings (WAV)");
+        return true;
+    }
+
+    let mut config = ReceiverPoolConfig::default();
+    let profile_path = profile_arg.map_or(PROFILE_FILE, |p| p.as_str());
+    if profile_arg.is_some() || std::path::Path::new(profile_path).exists() {
+        if let Err(e) = config.load_profile(profile_path) {
+            eprintln!("Error reading {}: {}", profile_path, e);
+            return true;
+        }
+        println!("Profile {}", profile_path);
+    }
+
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get()).min(wav_paths.len());
+    let mut pool = match ReceiverPool::<RxBackend>::new(config, &Default::default(), workers) {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Error in {}: {}", profile_path, e);
+            return true;
+        }
+    };
+    let sources = wav_paths.iter().map(|p| DecodeSource::WavFile(p.into())).collect();
+    for (path, result) in wav_paths.iter().zip(pool.decode_all(sources)) {
+        match result.frame {
+            Ok(frame) => println!("{}: {:?} ({:.1} dB)", path, String::from_utf8_lossy(&frame.payload).trim_end_matches('\0'), frame.snr_db),
+            Err(e) => println!("{}: {}", path, e),
+        }
+    }
+    true
+}
+
+fn main() {
+    #[cfg(feature = "audio")]
+    if audio_commands() {
+        return;
+    }
+
+    if calibrate_command() {
+        return;
+    }
+
+    if decode_command() {
+        return;
+    }
+
+    println!("=======================================================");
+    println!("   BachModem - Musical Wavelet Modem for HF Radio");
+    println!("=======================================================");
+    println!();
+    println!("Physical Layer:");
+    println!("  - Sampling Rate: 8000 Hz");
+    println!("  - Symbol Duration: 2.0 seconds");
+    println!("  - Carrier Frequencies: C4 (261.63 Hz) to D6 (1174.66 Hz)");
+    println!("  - Modulation: FH-DPSK (Frequency-Hopping DPSK)");
+    println!("  - Bandwidth: 200 Hz - 2.8 kHz (SSB compatible)");
+    println!();
+    
+    // Initialize device
+    let device = Default::default();
+    
+    // Message to transmit
+    let message = "Hello from BachModem! This is a deep-space wavelet transmission using Bach themes. The music you hear encodes digital data using Morlet wavelets mapped to the C-Major scale. Each symbol is 2 seconds long, allowing detection at -30 dB SNR over HF channels. 73 de AI";
+    
+    println!("Message to transmit:");
+    println!("  \"{}\"", message);
+    println!();
+    println!("Encoding {} bytes into wavelet sequence...", message.len());
+    
+    // Modulate the message with musical flourishes
+    let data_bytes = message.as_bytes();
+    println!("Generating FH-DPSK signal with Bach Preamble and musical flourishes...");
+    
+    let signal = modulation::modulate_fhdpsk_with_flourishes::<MyBackend>(
+        &device,
+        data_bytes,
+        true, // Add preamble
+        128,  // Add flourish every 128 symbols (every ~4 minutes)
+    );
+    
+    let signal_len = signal.dims()[0];
+    let duration_seconds = signal_len as f64 / wavelet::FS;
+    
+    println!("Signal generated:");
+    println!("  - Length: {} samples", signal_len);
+    println!("  - Duration: {:.2} seconds", duration_seconds);
+    println!();
+    
+    // Calculate transmission details
+    let bits = data_bytes.len() * 8;
+    let padded_bits = ((bits + 15) / 16) * 16; // Padded to multiple of 16
+    let total_bits = padded_bits + 16; // Include reference block
+    let num_symbols = total_bits;
+    let data_duration = num_symbols as f64 * wavelet::SYMBOL_DURATION;
+    let preamble_duration = duration_seconds - data_duration;
+    
+    println!("Transmission structure:");
+    println!("  - Preamble: {:.1} seconds (Bach Sweep for sync)", preamble_duration);
+    println!("  - Data: {:.1} seconds ({} symbols)", data_duration, num_symbols);
+    println!("  - Bit rate: {:.2} bits/second", bits as f64 / data_duration);
+    println!();
+    
+    // Save to WAV file
+    let output_path = "bachmodem_output.wav";
+    println!("Writing WAV file to '{}'...", output_path);
+    
+    match write_wav(&signal, output_path) {
+        Ok(_) => {
+            println!("✓ WAV file written successfully!");
+            println!();
+            println!("Listen to the beautiful wavelet music!");
+            println!("Each frequency sweep encodes data bits differentially.");
+            println!();
+            println!("Frequency mapping (C-Major scale):");
+            for (i, freq) in wavelet::BACH_FREQUENCIES.iter().enumerate() {
+                let note_names = ["C4", "D4", "E4", "F4", "G4", "A4", "B4", "C5",
+                                  "D5", "E5", "F5", "G5", "A5", "B5", "C6", "D6"];
+                println!("  {:2X}: {} = {:.2} Hz", i, note_names[i], freq);
+            }
+            println!();
+            println!("Hopping pattern (melodic intervals):");
+            print!("  ");
+            for (i, &idx) in wavelet::HOPPING_PATTERN.iter().enumerate() {
+                print!("{:X}", idx);
+                if i < wavelet::HOPPING_PATTERN.len() - 1 {
+                    print!(" -> ");
+                }
+                if (i + 1) % 8 == 0 && i < wavelet::HOPPING_PATTERN.len() - 1 {
+                    print!("\n  ");
+                }
+            }
+            println!();
+            println!();
+            println!("=======================================================");
+            println!("Transmission complete! 73 de BachModem");
+            println!("=======================================================");
+        }
+        Err(e) => {
+            eprintln!("Error writing WAV file: {}", e);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Bit Packing
+//!
+//! MSB-first conversion between bytes and one-bit-per-byte vectors.
+
+use alloc::vec::Vec;
+
+/// Encodes bytes into a sequence of bits
+pub fn encode_bits(data_bytes: &[u8]) -> Vec<u8> {
+    data_bytes
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
+        .collect()
+}
+
+/// Packs bits back into bytes
+pub fn pack_bits(bits: &[u8]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0u8, |acc, (i, &bit)| {
+                acc | (bit << (7 - i))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_roundtrip() {
+        let data = b"BachModem";
+        assert_eq!(pack_bits(&encode_bits(data)), data.to_vec());
+    }
+}
@@ -0,0 +1,79 @@
+//! CRC-8 Error Detection
+//!
+//! Appended to the data bits so list decoders can pick the right path.
+
+use alloc::vec::Vec;
+
+/// CRC-8 polynomial for error detection
+const CRC8_POLY: u8 = 0x07; // x^8 + x^2 + x + 1
+
+/// Compute CRC-8 checksum
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            if crc & 0x80 != 0 {
+                crc = (crc << 1) ^ CRC8_POLY;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+/// Encode data with CRC-8
+pub fn encode_with_crc(data: &[u8]) -> Vec<u8> {
+    let crc = crc8(data);
+    let mut result = data.to_vec();
+    // Append CRC bits
+    for i in 0..8 {
+        result.push((crc >> (7 - i)) & 1);
+    }
+    result
+}
+
+/// Verify CRC-8
+pub fn verify_crc(data_with_crc: &[u8]) -> bool {
+    if data_with_crc.len() < 8 {
+        return false;
+    }
+    
+    let data_len = data_with_crc.len() - 8;
+    
+    // Convert bits to bytes
+    let mut data_bytes = Vec::new();
+    for chunk in data_with_crc[..data_len].chunks(8) {
+        let mut byte = 0u8;
+        for (i, &bit) in chunk.iter().enumerate() {
+            byte |= bit << (7 - i);
+        }
+        data_bytes.push(byte);
+    }
+    
+    // Extract CRC
+    let mut received_crc = 0u8;
+    for i in 0..8 {
+        received_crc |= data_with_crc[data_len + i] << (7 - i);
+    }
+    
+    let computed_crc = crc8(&data_bytes);
+    computed_crc == received_crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::encode_bits;
+
+    #[test]
+    fn test_verify_crc() {
+        let mut bits = encode_bits(b"BWV");
+        bits.extend(encode_bits(&[crc8(b"BWV")]));
+        assert!(verify_crc(&bits));
+
+        bits[5] ^= 1;
+        assert!(!verify_crc(&bits));
+    }
+}
@@ -0,0 +1,255 @@
+//! Fixed-Point (Q15) Demodulator
+//!
+//! Integer-only receive path for devices without FPU or GPU: the matched
+//! filter runs directly on i16 PCM against Q15 wavelet tables, and the
+//! differential decode uses integer arithmetic plus an integer square root.
+//!
+//! Scaling:
+//! - PCM sample p represents p / 32768 (same as `read_wav`)
+//! - All tone tables share one Q15 scale (largest tap -> 32767), with the
+//!   inverse per-tone gain folded in before quantization
+//! - Correlations are accumulated in i64 and shifted back by 15 bits
+//!
+//! The resulting LLRs equal the float path's LLRs times `llr_scale()`.
+//! Decoders using min-sum (polar SC) are scale invariant, so the i32 LLRs
+//! can be fed to `decode_frame` with a plain `as f32` cast.
+//!
+//! Tables are built once at startup (soft-float is fine there); the
+//! per-sample work is integer multiply-accumulate only.
+
+use alloc::vec::Vec;
+use crate::matched_filter::{BankDesign, WaveletShape};
+use crate::scrambler::descramble_llrs;
+
+/// Q15 FH-DPSK soft demodulator
+pub struct Q15Demodulator {
+    /// Per-tone Q15 taps (real part)
+    bank_real: Vec<Vec<i16>>,
+
+    /// Per-tone Q15 taps (conjugated imaginary part)
+    bank_imag: Vec<Vec<i16>>,
+
+    /// Melodic hopping pattern (tone index per symbol slot)
+    hopping: Vec<usize>,
+
+    /// Samples per data symbol
+    symbol_len: usize,
+
+    /// Remove the coded-bit whitening from the LLRs
+    descramble: bool,
+
+    /// Float-to-Q15 factor applied to the taps
+    table_scale: f32,
+
+    design: BankDesign,
+}
+
+impl Q15Demodulator {
+    /// Quantize the wavelet bank for a tone alphabet
+    ///
+    /// Same parameters and builders as `ScalarDemodulator::new`.
+    pub fn new(
+        frequencies: &[f64],
+        hopping: &[usize],
+        tone_gains: Option<&[f64]>,
+        symbol_duration: f64,
+        fs: f64,
+    ) -> Self {
+        assert_eq!(hopping.len(), frequencies.len(), "Hopping pattern must cover the alphabet");
+
+        let design = BankDesign {
+            frequencies: frequencies.to_vec(),
+            tone_gains: tone_gains.map(<[f64]>::to_vec),
+            symbol_duration,
+            shape: WaveletShape::default(),
+            fs,
+        };
+        Self {
+            bank_real: Vec::new(),
+            bank_imag: Vec::new(),
+            hopping: hopping.to_vec(),
+            symbol_len: 0,
+            descramble: false,
+            table_scale: 1.0,
+            design,
+        }
+        .with_bank()
+    }
+
+    /// Symbol window length in Gaussian widths (`ModemConfig::wavelet_sigmas`)
+    pub fn with_wavelet_sigmas(mut self, sigmas: f64) -> Self {
+        self.design.shape.sigmas = sigmas;
+        self.with_bank()
+    }
+
+    /// Pulse shape of the transmitter (`ModemConfig::wavelet_shape`)
+    pub fn with_wavelet_shape(mut self, shape: WaveletShape) -> Self {
+        self.design.shape = shape;
+        self.with_bank()
+    }
+
+    /// Float taps with inverse gain, then one shared scale for all tones
+    fn with_bank(mut self) -> Self {
+        let taps = self.design.taps();
+
+        let peak = taps.iter()
+            .flat_map(|(real, imag)| real.iter().chain(imag.iter()))
+            .fold(0.0f32, |acc, &t| acc.max(libm::fabsf(t)));
+        let table_scale = 32767.0 / peak;
+
+        let quantize = |t: &[f32]| -> Vec<i16> {
+            t.iter().map(|&x| libm::roundf(x * table_scale) as i16).collect()
+        };
+
+        self.symbol_len = taps[0].0.len();
+        (self.bank_real, self.bank_imag) = taps.iter()
+            .map(|(real, imag)| (quantize(real), quantize(imag)))
+            .unzip();
+        self.table_scale = table_scale;
+        self
+    }
+
+    /// Remove the coded-bit whitening (`scrambler`) from the LLRs
+    pub fn with_descrambler(mut self) -> Self {
+        self.descramble = true;
+        self
+    }
+
+    /// Differential lag (one block visits every tone once)
+    pub fn lag(&self) -> usize {
+        self.hopping.len()
+    }
+
+    /// Samples per data symbol
+    pub fn symbol_samples(&self) -> usize {
+        self.symbol_len
+    }
+
+    /// Ratio between these LLRs and the float path's LLRs
+    pub fn llr_scale(&self) -> f32 {
+        self.table_scale
+    }
+
+    /// Matched-filter output (real, imag) of every complete symbol
+    pub fn correlate(&self, pcm: &[i16]) -> Vec<(i32, i32)> {
+        pcm.chunks_exact(self.symbol_len)
+            .enumerate()
+            .map(|(i, symbol)| {
+                let tone = self.hopping[i % self.hopping.len()];
+                let real = dot_q15(symbol, &self.bank_real[tone]);
+                let imag = dot_q15(symbol, &self.bank_imag[tone]);
+                (real, imag)
+            })
+            .collect()
+    }
+
+    /// Soft LLRs of the differential bits (positive -> bit 0)
+    ///
+    /// Uses whole differential blocks only; returns an empty vector if fewer
+    /// than two blocks of symbols are present.
+    pub fn demodulate(&self, pcm: &[i16]) -> Vec<i32> {
+        let lag = self.lag();
+        let corr = self.correlate(pcm);
+
+        let trunc_len = (corr.len() / lag) * lag;
+        if trunc_len < 2 * lag {
+            return Vec::new();
+        }
+
+        let mut llrs: Vec<i32> = (0..trunc_len - lag)
+            .map(|j| {
+                let (real_prev, imag_prev) = corr[j];
+                let (real_curr, imag_curr) = corr[j + lag];
+
+                let dot = real_curr as i64 * real_prev as i64 + imag_curr as i64 * imag_prev as i64;
+                let power_prev = real_prev as i64 * real_prev as i64 + imag_prev as i64 * imag_prev as i64;
+                let amp_prev = (power_prev as u64).isqrt().max(1) as i64;
+
+                (dot / amp_prev) as i32
+            })
+            .collect();
+
+        if self.descramble {
+            descramble_llrs(&mut llrs);
+        }
+        llrs
+    }
+}
+
+/// Q15 dot product: sum(pcm · tap) >> 15, accumulated in i64
+fn dot_q15(pcm: &[i16], taps: &[i16]) -> i32 {
+    let acc: i64 = pcm.iter().zip(taps).map(|(&p, &t)| p as i64 * t as i64).sum();
+    (acc >> 15) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matched_filter::{ScalarDemodulator, morlet_wavelet_f32};
+    use core::f64::consts::PI;
+
+    const FREQS: [f64; 8] = [261.63, 293.66, 329.63, 349.23, 392.00, 440.00, 493.88, 523.25];
+    const HOPPING: [usize; 8] = [0, 3, 6, 1, 4, 7, 2, 5];
+
+    /// Lag-differential DPSK test signal at a given amplitude
+    fn dpsk_signal(bits: &[u8], amplitude: f32) -> Vec<f32> {
+        let mut phases = vec![0.0f64; 8];
+        for (j, &bit) in bits.iter().enumerate() {
+            phases.push(phases[j] + if bit == 1 { PI } else { 0.0 });
+        }
+
+        let mut signal = Vec::new();
+        for (i, &phase) in phases.iter().enumerate() {
+            let (real, imag) = morlet_wavelet_f32(FREQS[HOPPING[i % 8]], 0.1, 8000.0);
+            signal.extend(real.iter().zip(&imag)
+                .map(|(&r, &im)| amplitude * (r * libm::cos(phase) as f32 - im * libm::sin(phase) as f32)));
+        }
+        signal
+    }
+
+    fn to_pcm(signal: &[f32]) -> Vec<i16> {
+        signal.iter().map(|&s| (s * 32768.0).round().clamp(-32768.0, 32767.0) as i16).collect()
+    }
+
+    #[test]
+    fn test_q15_matches_float_path() {
+        let float_demod = ScalarDemodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0);
+        let q15_demod = Q15Demodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0);
+        let bits: Vec<u8> = (0..48).map(|i| ((i * 5) % 3 == 0) as u8).collect();
+
+        // Peak near -6 dBFS
+        let signal = dpsk_signal(&bits, 0.05);
+        let peak = signal.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
+        let signal: Vec<f32> = signal.iter().map(|&s| s * 0.5 / peak).collect();
+
+        let pcm = to_pcm(&signal);
+        let reference: Vec<f32> = pcm.iter().map(|&p| p as f32 / 32768.0).collect();
+
+        let llrs_float = float_demod.demodulate(&reference);
+        let llrs_q15 = q15_demod.demodulate(&pcm);
+        assert_eq!(llrs_q15.len(), llrs_float.len());
+
+        // Within 0.1% of the largest LLR after rescaling
+        let max_llr = llrs_float.iter().fold(0.0f32, |acc, &l| acc.max(l.abs()));
+        for (&q, &f) in llrs_q15.iter().zip(&llrs_float) {
+            let rescaled = q as f32 / q15_demod.llr_scale();
+            assert!((rescaled - f).abs() < 1e-3 * max_llr, "q15 {} vs float {}", rescaled, f);
+        }
+
+        let decided: Vec<u8> = llrs_q15.iter().map(|&l| (l < 0) as u8).collect();
+        assert_eq!(decided, bits);
+    }
+
+    #[test]
+    fn test_q15_weak_input() {
+        let demod = Q15Demodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0);
+        let bits: Vec<u8> = (0..24).map(|i| (i % 4 == 1) as u8).collect();
+
+        // Peak swing of ~40 LSB: decisions must survive quantization
+        let signal = dpsk_signal(&bits, 2e-4);
+        let llrs = demod.demodulate(&to_pcm(&signal));
+
+        let decided: Vec<u8> = llrs.iter().map(|&l| (l < 0) as u8).collect();
+        assert_eq!(decided, bits);
+    }
+}
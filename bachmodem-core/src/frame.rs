@@ -0,0 +1,203 @@
+//! Frame Encode / Parse
+//!
+//! One BachModem frame is a single Polar (256, 128) codeword:
+//!   [version][payload (≤ 15 bytes, zero padded)] -> polar encode -> block interleave
+//!
+//! `FrameCode::Convolutional` swaps the polar code for the tail-biting
+//! convolutional code of the same size (see `convolutional`); everything
+//! else about the frame is unchanged.
+//!
+//! `decode_frame()` is the receive side on plain slices: deinterleave the
+//! demodulated LLRs, SC-decode, pack the information bits back to bytes and
+//! check the wire-format version.
+
+use alloc::vec::Vec;
+use core::fmt;
+use crate::bits::{encode_bits, pack_bits};
+use crate::convolutional;
+use crate::interleaver::{interleave, deinterleave};
+use crate::polar::PolarCode;
+use crate::wire_format::{is_supported, WIRE_FORMAT_VERSION};
+
+/// Polar codeword length
+pub const CODE_N: usize = 256;
+
+/// Polar information bits per codeword
+pub const CODE_K: usize = 128;
+
+/// Header bytes in front of the payload (wire-format version)
+pub const FRAME_HEADER_LEN: usize = 1;
+
+/// Maximum payload per frame (bytes)
+pub const MAX_PAYLOAD: usize = CODE_K / 8 - FRAME_HEADER_LEN;
+
+/// Frame parsing errors
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameError {
+    /// Fewer LLRs than one codeword
+    ShortInput { llrs: usize, expected: usize },
+
+    /// No header byte to check
+    MissingHeader,
+
+    /// Header carries a wire-format version this receiver can't decode
+    UnsupportedVersion { version: u8 },
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::ShortInput { llrs, expected } => {
+                write!(f, "got {} LLRs, expected {}", llrs, expected)
+            }
+            FrameError::MissingHeader => write!(f, "frame has no header byte"),
+            FrameError::UnsupportedVersion { version } => {
+                write!(f, "unsupported wire-format version {}", version)
+            }
+        }
+    }
+}
+
+impl core::error::Error for FrameError {}
+
+/// Inner code of a frame: `CODE_K` information bits -> `CODE_N` coded bits
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrameCode {
+    /// Polar (256, 128), SC or list decoded
+    #[default]
+    Polar,
+
+    /// Tail-biting K = 7 convolutional code, Viterbi decoded
+    Convolutional,
+}
+
+impl FrameCode {
+    /// Encode `CODE_K` information bits
+    pub fn encode(self, info_bits: &[u8]) -> Vec<u8> {
+        match self {
+            FrameCode::Polar => PolarCode::new(CODE_N, CODE_K).encode(info_bits),
+            FrameCode::Convolutional => convolutional::encode(info_bits),
+        }
+    }
+
+    /// Decode `CODE_N` deinterleaved LLRs (positive -> bit 0) to information bits
+    ///
+    /// Polar frames use the SC decoder here; the GPU list decoder lives in
+    /// the `bachmodem` crate.
+    pub fn decode(self, codeword_llrs: &[f32]) -> Vec<u8> {
+        match self {
+            FrameCode::Polar => PolarCode::new(CODE_N, CODE_K).decode_sc(codeword_llrs),
+            FrameCode::Convolutional => convolutional::decode(codeword_llrs),
+        }
+    }
+}
+
+/// FEC-encode and interleave a payload into transmit bytes
+///
+/// Panics if the payload exceeds `MAX_PAYLOAD` bytes.
+pub fn encode_frame(payload: &[u8], interleaver_columns: usize) -> Vec<u8> {
+    encode_frame_with_code(payload, interleaver_columns, FrameCode::Polar)
+}
+
+/// `encode_frame` with the given inner code
+pub fn encode_frame_with_code(payload: &[u8], interleaver_columns: usize, code: FrameCode) -> Vec<u8> {
+    encode_frame_with_version(payload, interleaver_columns, code, WIRE_FORMAT_VERSION)
+}
+
+/// `encode_frame_with_code` in wire-format `version`, e.g. the one
+/// `negotiate_version` agreed with an older receiver
+///
+/// Panics if this build can't produce `version`.
+pub fn encode_frame_with_version(payload: &[u8], interleaver_columns: usize, code: FrameCode, version: u8) -> Vec<u8> {
+    assert!(payload.len() <= MAX_PAYLOAD, "Payload exceeds one codeword");
+    assert!(is_supported(version), "Unsupported wire-format version");
+
+    let mut info_bits = encode_bits(&[version]);
+    info_bits.extend(encode_bits(payload));
+    info_bits.resize(CODE_K, 0);
+
+    let encoded = code.encode(&info_bits);
+    let interleaved = interleave(&encoded, interleaver_columns);
+
+    pack_bits(&interleaved)
+}
+
+/// Check the header of decoded information bytes
+///
+/// Returns the `MAX_PAYLOAD` payload bytes (payload plus zero padding).
+pub fn parse_frame(info_bytes: &[u8]) -> Result<Vec<u8>, FrameError> {
+    let &version = info_bytes.first().ok_or(FrameError::MissingHeader)?;
+    if !is_supported(version) {
+        return Err(FrameError::UnsupportedVersion { version });
+    }
+
+    Ok(info_bytes[FRAME_HEADER_LEN..].to_vec())
+}
+
+/// Parse a frame from demodulated LLRs (positive -> bit 0)
+///
+/// Returns the `MAX_PAYLOAD` payload bytes (payload plus zero padding).
+pub fn decode_frame(llrs: &[f32], interleaver_columns: usize) -> Result<Vec<u8>, FrameError> {
+    decode_frame_with_code(llrs, interleaver_columns, FrameCode::Polar)
+}
+
+/// `decode_frame` with the given inner code
+pub fn decode_frame_with_code(llrs: &[f32], interleaver_columns: usize, code: FrameCode) -> Result<Vec<u8>, FrameError> {
+    if llrs.len() < CODE_N {
+        return Err(FrameError::ShortInput { llrs: llrs.len(), expected: CODE_N });
+    }
+
+    let codeword_llrs = deinterleave(&llrs[..CODE_N], interleaver_columns);
+    let info_bits = code.decode(&codeword_llrs);
+
+    parse_frame(&pack_bits(&info_bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bpsk_llrs(frame: &[u8]) -> Vec<f32> {
+        encode_bits(frame).iter().map(|&b| if b == 0 { 1.0 } else { -1.0 }).collect()
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let llrs = bpsk_llrs(&encode_frame(b"T=21.5C", 16));
+        let decoded = decode_frame(&llrs, 16).unwrap();
+
+        assert_eq!(decoded.len(), MAX_PAYLOAD);
+        assert_eq!(&decoded[..7], b"T=21.5C");
+        assert!(decoded[7..].iter().all(|&b| b == 0));
+        assert_eq!(
+            decode_frame(&llrs[..100], 16),
+            Err(FrameError::ShortInput { llrs: 100, expected: CODE_N })
+        );
+    }
+
+    #[test]
+    fn test_convolutional_frame_roundtrip() {
+        let frame = encode_frame_with_code(b"CHAT 73", 16, FrameCode::Convolutional);
+        assert_eq!(frame.len(), CODE_N / 8);
+        assert_ne!(frame, encode_frame(b"CHAT 73", 16));
+
+        let decoded = decode_frame_with_code(&bpsk_llrs(&frame), 16, FrameCode::Convolutional).unwrap();
+        assert_eq!(&decoded[..7], b"CHAT 73");
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        // Hand-build a frame from a future wire format
+        let mut info_bits = encode_bits(&[WIRE_FORMAT_VERSION + 1]);
+        info_bits.extend(encode_bits(b"v2"));
+        info_bits.resize(CODE_K, 0);
+        let codeword = PolarCode::new(CODE_N, CODE_K).encode(&info_bits);
+        let frame = pack_bits(&interleave(&codeword, 16));
+
+        assert_eq!(
+            decode_frame(&bpsk_llrs(&frame), 16),
+            Err(FrameError::UnsupportedVersion { version: WIRE_FORMAT_VERSION + 1 })
+        );
+        assert_eq!(parse_frame(&[]), Err(FrameError::MissingHeader));
+    }
+}
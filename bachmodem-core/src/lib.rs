@@ -0,0 +1,49 @@
+//! BachModem Core - Embedded Receiver Path
+//! 
+//! The non-tensor parts of the BachModem receive chain, usable without
+//! `std` (only `alloc` is required):
+//! 
+//! - Bit packing, block interleaver, CRC-8
+//! - Polar (256, 128) encoder and successive-cancellation decoder
+//! - Tail-biting K = 7 convolutional code with a soft Viterbi decoder
+//! - Frame encode/parse (payload <-> interleaved codeword) with a
+//!   versioned wire format
+//! - Reed-Solomon outer code over the payloads of multi-frame messages
+//! - x^7 + x^4 + 1 whitening of the coded bits
+//! - Scalar f32 Morlet / chirp matched filter and differential demodulator
+//! - Q15 fixed-point matched filter on i16 PCM (no FPU needed)
+//! - int8 LLR export and merging for combining across receivers
+//! 
+//! Intended for microcontroller-class receivers of slow-rate telemetry.
+//! The `bachmodem` crate re-exports these items and adds the GPU paths.
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod bits;
+pub mod interleaver;
+pub mod crc;
+pub mod polar;
+pub mod convolutional;
+pub mod wire_format;
+pub mod frame;
+pub mod reed_solomon;
+pub mod outer_code;
+pub mod scrambler;
+pub mod matched_filter;
+pub mod fixed_point;
+pub mod llr_exchange;
+
+pub use bits::{encode_bits, pack_bits};
+pub use interleaver::{interleave, deinterleave};
+pub use crc::{crc8, encode_with_crc, verify_crc};
+pub use polar::PolarCode;
+pub use wire_format::{WIRE_FORMAT_VERSION, SUPPORTED_WIRE_VERSIONS, negotiate_version};
+pub use frame::{encode_frame, encode_frame_with_code, encode_frame_with_version, decode_frame, decode_frame_with_code, parse_frame, FrameCode, FrameError, CODE_N, CODE_K, MAX_PAYLOAD};
+pub use reed_solomon::{ReedSolomon, RsError};
+pub use outer_code::{OuterCode, OuterDecode, OUTER_HEADER_LEN};
+pub use scrambler::{whitening_sequence, scramble_bits, descramble_llrs};
+pub use matched_filter::{ScalarDemodulator, WaveletShape, morlet_wavelet_f32, shaped_wavelet_f32, DEFAULT_WAVELET_SIGMAS};
+pub use fixed_point::Q15Demodulator;
+pub use llr_exchange::{LlrContribution, LlrExchangeError, merge_contributions, LLR_EXCHANGE_VERSION};
@@ -0,0 +1,291 @@
+//! Compressed LLR Exchange for Distributed Combining
+//!
+//! Receivers at different sites see independent fading and noise, so a
+//! frame that none of them can decode alone often decodes from their summed
+//! LLRs. Each receiver exports the soft output of a slot as an
+//! `LlrContribution`: int8 LLRs with one float scale, plus the blind SNR the
+//! demodulator measured. A central node parses the contributions of one
+//! slot and `merge_contributions` combines them into a single LLR vector
+//! for the usual deinterleave and polar decode.
+//!
+//! Wire format (little endian, 286 bytes for one frame and a 6-character
+//! callsign):
+//!
+//! ```text
+//! ["BLLR"][version: u8][station len: u8][station: utf-8][slot: u64]
+//! [snr_db: f32][scale: f32][count: u16][llrs: i8 × count]
+//! ```
+//!
+//! LLRs are clipped at `LLR_CLIP_RMS` times their RMS before quantization;
+//! zero stays zero, so erasures survive the round trip. Scales differ
+//! between receivers (each has its own gain and noise), so the merge
+//! normalizes every contribution to unit RMS and weights it by
+//! sqrt(snr · (1 + snr)), the maximum-ratio weight of a unit-RMS BPSK
+//! observation at that SNR.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Leading bytes of a serialized contribution
+pub const LLR_EXCHANGE_MAGIC: [u8; 4] = *b"BLLR";
+
+/// Serialization format version
+pub const LLR_EXCHANGE_VERSION: u8 = 1;
+
+/// Quantizer clip level in multiples of the LLR RMS
+pub const LLR_CLIP_RMS: f32 = 4.0;
+
+/// Errors parsing or merging contributions
+#[derive(Clone, Debug, PartialEq)]
+pub enum LlrExchangeError {
+    /// Input does not start with `LLR_EXCHANGE_MAGIC`
+    BadMagic,
+
+    /// Serialization version this node can't read
+    UnsupportedVersion { version: u8 },
+
+    /// Input ends before the declared contents
+    Truncated,
+
+    /// Station name is not valid UTF-8
+    InvalidStation,
+
+    /// Nothing to merge
+    NoContributions,
+
+    /// Contributions from different slots
+    SlotMismatch { expected: u64, found: u64 },
+
+    /// The same station contributed twice
+    DuplicateStation { station: String },
+}
+
+impl fmt::Display for LlrExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlrExchangeError::BadMagic => write!(f, "not an LLR contribution"),
+            LlrExchangeError::UnsupportedVersion { version } => {
+                write!(f, "unsupported LLR exchange version {}", version)
+            }
+            LlrExchangeError::Truncated => write!(f, "LLR contribution truncated"),
+            LlrExchangeError::InvalidStation => write!(f, "station name is not valid UTF-8"),
+            LlrExchangeError::NoContributions => write!(f, "no contributions to merge"),
+            LlrExchangeError::SlotMismatch { expected, found } => {
+                write!(f, "contribution for slot {} in a merge of slot {}", found, expected)
+            }
+            LlrExchangeError::DuplicateStation { station } => {
+                write!(f, "station {} contributed twice", station)
+            }
+        }
+    }
+}
+
+impl core::error::Error for LlrExchangeError {}
+
+/// One receiver's quantized LLRs for one slot
+#[derive(Clone, Debug, PartialEq)]
+pub struct LlrContribution {
+    /// Reporting receiver (callsign or site name, at most 255 bytes)
+    pub station: String,
+
+    /// Slot identifier the receivers agree on (e.g. slot start, Unix seconds)
+    pub slot: u64,
+
+    /// Blind matched-filter SNR of the slot at this receiver (dB)
+    pub snr_db: f32,
+
+    /// LLR per quantization step
+    pub scale: f32,
+
+    /// Quantized LLRs (positive -> bit 0, 0 = erasure)
+    pub values: Vec<i8>,
+}
+
+impl LlrContribution {
+    /// Quantize demodulated LLRs to int8
+    ///
+    /// Panics if `station` is longer than 255 bytes or there are more than
+    /// 65535 LLRs.
+    pub fn quantize(station: &str, slot: u64, snr_db: f32, llrs: &[f32]) -> Self {
+        assert!(station.len() <= u8::MAX as usize, "Station name exceeds 255 bytes");
+        assert!(llrs.len() <= u16::MAX as usize, "More than 65535 LLRs");
+
+        let (sum_sq, count) = llrs.iter()
+            .filter(|&&l| l != 0.0)
+            .fold((0.0f32, 0usize), |(s, n), &l| (s + l * l, n + 1));
+        let rms = if count > 0 { libm::sqrtf(sum_sq / count as f32) } else { 0.0 };
+        let scale = if rms > 0.0 { LLR_CLIP_RMS * rms / i8::MAX as f32 } else { 1.0 };
+
+        let values = llrs.iter()
+            .map(|&l| libm::roundf(l / scale).clamp(-(i8::MAX as f32), i8::MAX as f32) as i8)
+            .collect();
+
+        Self { station: station.to_string(), slot, snr_db, scale, values }
+    }
+
+    /// Dequantized LLRs
+    pub fn llrs(&self) -> Vec<f32> {
+        self.values.iter().map(|&v| v as f32 * self.scale).collect()
+    }
+
+    /// Serialize in the format above
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(24 + self.station.len() + self.values.len());
+        bytes.extend_from_slice(&LLR_EXCHANGE_MAGIC);
+        bytes.push(LLR_EXCHANGE_VERSION);
+        bytes.push(self.station.len() as u8);
+        bytes.extend_from_slice(self.station.as_bytes());
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(&self.snr_db.to_le_bytes());
+        bytes.extend_from_slice(&self.scale.to_le_bytes());
+        bytes.extend_from_slice(&(self.values.len() as u16).to_le_bytes());
+        bytes.extend(self.values.iter().map(|&v| v as u8));
+        bytes
+    }
+
+    /// Parse a serialized contribution
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LlrExchangeError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != LLR_EXCHANGE_MAGIC {
+            return Err(LlrExchangeError::BadMagic);
+        }
+        let version = reader.take(1)?[0];
+        if version != LLR_EXCHANGE_VERSION {
+            return Err(LlrExchangeError::UnsupportedVersion { version });
+        }
+
+        let station_len = reader.take(1)?[0] as usize;
+        let station = core::str::from_utf8(reader.take(station_len)?)
+            .map_err(|_| LlrExchangeError::InvalidStation)?
+            .to_string();
+        let slot = u64::from_le_bytes(reader.array()?);
+        let snr_db = f32::from_le_bytes(reader.array()?);
+        let scale = f32::from_le_bytes(reader.array()?);
+        let count = u16::from_le_bytes(reader.array()?) as usize;
+        let values = reader.take(count)?.iter().map(|&b| b as i8).collect();
+
+        Ok(Self { station, slot, snr_db, scale, values })
+    }
+
+    /// Maximum-ratio weight of the unit-RMS LLRs
+    pub fn weight(&self) -> f32 {
+        let snr = libm::powf(10.0, self.snr_db / 10.0);
+        libm::sqrtf(snr * (1.0 + snr))
+    }
+}
+
+/// Combine the contributions of one slot into one LLR vector
+///
+/// Each contribution is normalized to unit RMS (over its non-erased LLRs)
+/// and weighted by `LlrContribution::weight`. The result is as long as the
+/// longest contribution; shorter ones add nothing past their end.
+pub fn merge_contributions(contributions: &[LlrContribution]) -> Result<Vec<f32>, LlrExchangeError> {
+    let first = contributions.first().ok_or(LlrExchangeError::NoContributions)?;
+    for (i, c) in contributions.iter().enumerate() {
+        if c.slot != first.slot {
+            return Err(LlrExchangeError::SlotMismatch { expected: first.slot, found: c.slot });
+        }
+        if contributions[..i].iter().any(|other| other.station == c.station) {
+            return Err(LlrExchangeError::DuplicateStation { station: c.station.clone() });
+        }
+    }
+
+    let len = contributions.iter().map(|c| c.values.len()).max().unwrap_or(0);
+    let mut merged = vec![0.0f32; len];
+    for c in contributions {
+        let (sum_sq, count) = c.values.iter()
+            .filter(|&&v| v != 0)
+            .fold((0.0f32, 0usize), |(s, n), &v| (s + (v as f32) * (v as f32), n + 1));
+        if count == 0 {
+            continue;
+        }
+        let gain = c.weight() / libm::sqrtf(sum_sq / count as f32);
+        for (m, &v) in merged.iter_mut().zip(&c.values) {
+            *m += v as f32 * gain;
+        }
+    }
+    Ok(merged)
+}
+
+/// Cursor over a serialized contribution
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], LlrExchangeError> {
+        if self.bytes.len() < n {
+            return Err(LlrExchangeError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LlrExchangeError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bits::encode_bits;
+    use crate::frame::{decode_frame, encode_frame};
+
+    #[test]
+    fn test_contribution_round_trip() {
+        let llrs: Vec<f32> = (0..256).map(|i| if i % 7 == 0 { 0.0 } else { ((i as f32) * 0.37).sin() * 3.0 }).collect();
+        let contribution = LlrContribution::quantize("DL1ABC", 1_700_000_040, -17.5, &llrs);
+
+        let bytes = contribution.to_bytes();
+        assert_eq!(bytes.len(), 24 + 6 + 256);
+        assert_eq!(LlrContribution::from_bytes(&bytes), Ok(contribution.clone()));
+
+        // Erasures stay exact, the rest within half a step
+        for (&l, q) in llrs.iter().zip(contribution.llrs()) {
+            if l == 0.0 {
+                assert_eq!(q, 0.0);
+            } else {
+                assert!((l - q).abs() <= contribution.scale / 2.0 + 1e-6);
+            }
+        }
+
+        assert_eq!(LlrContribution::from_bytes(&bytes[..bytes.len() - 1]), Err(LlrExchangeError::Truncated));
+        assert_eq!(LlrContribution::from_bytes(b"WAVE"), Err(LlrExchangeError::BadMagic));
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert_eq!(LlrContribution::from_bytes(&future), Err(LlrExchangeError::UnsupportedVersion { version: 2 }));
+    }
+
+    #[test]
+    fn test_merge_recovers_complementary_erasures() {
+        let frame = encode_frame(b"DIVERSITY", 16);
+        let llrs: Vec<f32> = encode_bits(&frame).iter().map(|&b| if b == 0 { 2.0 } else { -2.0 }).collect();
+
+        // Each site lost every other tone (16 bits per tone slot)
+        let first: Vec<f32> = llrs.iter().enumerate().map(|(i, &l)| if i % 2 == 0 { l } else { 0.0 }).collect();
+        let second: Vec<f32> = llrs.iter().enumerate().map(|(i, &l)| if i % 2 == 1 { l * 40.0 } else { 0.0 }).collect();
+        let a = LlrContribution::quantize("K1ABC", 7, -12.0, &first);
+        let b = LlrContribution::quantize("VK2AB", 7, -15.0, &second);
+        for alone in [&a, &b] {
+            assert!(decode_frame(&alone.llrs(), 16).map_or(true, |payload| &payload[..9] != b"DIVERSITY"));
+        }
+
+        let merged = merge_contributions(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(&decode_frame(&merged, 16).unwrap()[..9], b"DIVERSITY");
+
+        // Where two sites disagree the one with the better SNR wins,
+        // however loud its receiver is
+        let flipped: Vec<f32> = first.iter().map(|&l| -l * 100.0).collect();
+        let c = LlrContribution::quantize("JA1XYZ", 7, -20.0, &flipped);
+        let merged = merge_contributions(&[a.clone(), c]).unwrap();
+        assert!(merged.iter().zip(&first).all(|(&m, &l)| m * l >= 0.0));
+
+        assert_eq!(merge_contributions(&[]), Err(LlrExchangeError::NoContributions));
+        assert_eq!(merge_contributions(&[a.clone(), LlrContribution { slot: 8, ..b.clone() }]), Err(LlrExchangeError::SlotMismatch { expected: 7, found: 8 }));
+        assert_eq!(merge_contributions(&[a.clone(), a]), Err(LlrExchangeError::DuplicateStation { station: "K1ABC".to_string() }));
+    }
+}
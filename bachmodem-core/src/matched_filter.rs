@@ -0,0 +1,300 @@
+//! Scalar Matched Filter (f32, no_std)
+//!
+//! CPU fallback for the GPU soft demodulator: correlates each symbol with the
+//! conjugate Morlet wavelet of its hopping tone, then decodes lag-differential
+//! phase (lag = number of tones) into soft LLRs:
+//!
+//!   LLR_j = Re(z_{j+lag} · conj(z_j)) / |z_j|
+//!
+//! Same conventions as `demodulate_fhdpsk_soft_with_config` (positive -> bit 0,
+//! inverse per-tone gain in the filters), so the output feeds `decode_frame`.
+//!
+//! The input must start at the first data symbol (after the preamble) and
+//! carry no flourishes - the slow-rate telemetry case.
+//!
+//! Memory: the wavelet bank holds 2 × num_tones × symbol_samples f32
+//! (e.g. 8 tones × 800 samples = 51 KB).
+
+use alloc::vec::Vec;
+use core::f64::consts::PI;
+use crate::scrambler::descramble_llrs;
+
+/// Default symbol window length in Gaussian widths (6-sigma fits in window)
+pub const DEFAULT_WAVELET_SIGMAS: f64 = 6.0;
+
+/// Symbol pulse shape: Gaussian width and optional linear FM sweep
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WaveletShape {
+    /// Symbol window length in Gaussian widths (s = duration / sigmas)
+    pub sigmas: f64,
+
+    /// Linear FM sweep across the symbol window (Hz), 0 = pure tone
+    pub sweep_hz: f64,
+}
+
+impl Default for WaveletShape {
+    fn default() -> Self {
+        Self::morlet(DEFAULT_WAVELET_SIGMAS)
+    }
+}
+
+impl WaveletShape {
+    /// Pure-tone Morlet wavelet
+    pub fn morlet(sigmas: f64) -> Self {
+        Self { sigmas, sweep_hz: 0.0 }
+    }
+
+    /// Chirp sweeping `sweep_hz` upwards across the window, centred on the tone
+    pub fn chirp(sigmas: f64, sweep_hz: f64) -> Self {
+        Self { sigmas, sweep_hz }
+    }
+}
+
+/// Morlet (Gabor) wavelet as f32 samples
+///
+/// ψ(t; f, s) = A · exp(-t²/2s²) · exp(i·2πf·t), with s = duration / 6 and
+/// A = (s√π)^(-1/2), t ∈ [-duration/2, duration/2] - identical to the
+/// tensor version in `bachmodem::wavelet::morlet_wavelet`.
+///
+/// Returns (real, imag).
+pub fn morlet_wavelet_f32(frequency: f64, duration: f64, fs: f64) -> (Vec<f32>, Vec<f32>) {
+    shaped_wavelet_f32(frequency, duration, WaveletShape::default(), fs)
+}
+
+/// Morlet wavelet with an optional linear FM sweep (Gaussian chirp)
+///
+/// ψ(t) = A · exp(-t²/2s²) · exp(i·2π(f·t + k·t²/2)), k = sweep_hz / duration;
+/// the instantaneous frequency passes through f at the window centre.
+pub fn shaped_wavelet_f32(frequency: f64, duration: f64, shape: WaveletShape, fs: f64) -> (Vec<f32>, Vec<f32>) {
+    let num_samples = (duration * fs) as usize;
+    let s = duration / shape.sigmas;
+    let norm_factor = libm::pow(s * libm::sqrt(PI), -0.5);
+    let omega = 2.0 * PI * frequency;
+    let rate = PI * shape.sweep_hz / duration;
+
+    (0..num_samples)
+        .map(|i| {
+            let t = (i as f64) / fs - duration / 2.0;
+            let envelope = norm_factor * libm::exp(-(t * t) / (2.0 * s * s));
+            let phase = omega * t + rate * t * t;
+            ((libm::cos(phase) * envelope) as f32, (libm::sin(phase) * envelope) as f32)
+        })
+        .unzip()
+}
+
+/// Inputs of a wavelet bank, kept so the pulse shape can change after `new`
+#[derive(Clone, Debug)]
+pub(crate) struct BankDesign {
+    pub(crate) frequencies: Vec<f64>,
+    pub(crate) tone_gains: Option<Vec<f64>>,
+    pub(crate) symbol_duration: f64,
+    pub(crate) shape: WaveletShape,
+    pub(crate) fs: f64,
+}
+
+impl BankDesign {
+    /// Conjugate filter taps (real, imag) per tone, divided by the tone gain
+    pub(crate) fn taps(&self) -> Vec<(Vec<f32>, Vec<f32>)> {
+        self.frequencies.iter()
+            .enumerate()
+            .map(|(i, &freq)| {
+                let (real, imag) = shaped_wavelet_f32(freq, self.symbol_duration, self.shape, self.fs);
+                let inv_gain = (1.0 / self.tone_gains.as_ref().map_or(1.0, |g| g[i])) as f32;
+                (
+                    real.iter().map(|&r| r * inv_gain).collect(),
+                    imag.iter().map(|&im| -im * inv_gain).collect(), // Conjugate
+                )
+            })
+            .collect()
+    }
+}
+
+/// Scalar FH-DPSK soft demodulator
+pub struct ScalarDemodulator {
+    /// Per-tone filter taps (real part, divided by the tone gain)
+    bank_real: Vec<Vec<f32>>,
+
+    /// Per-tone filter taps (conjugated imaginary part, divided by the tone gain)
+    bank_imag: Vec<Vec<f32>>,
+
+    /// Melodic hopping pattern (tone index per symbol slot)
+    hopping: Vec<usize>,
+
+    /// Samples per data symbol
+    symbol_len: usize,
+
+    /// Remove the coded-bit whitening from the LLRs
+    descramble: bool,
+
+    design: BankDesign,
+}
+
+impl ScalarDemodulator {
+    /// Build the Morlet wavelet bank for a tone alphabet
+    ///
+    /// `tone_gains` are the transmit pre-emphasis amplitudes (None = flat).
+    /// The pulse shape is `WaveletShape::default()` unless the modem config
+    /// changes it (`with_wavelet_sigmas`, `with_wavelet_shape`).
+    pub fn new(
+        frequencies: &[f64],
+        hopping: &[usize],
+        tone_gains: Option<&[f64]>,
+        symbol_duration: f64,
+        fs: f64,
+    ) -> Self {
+        assert_eq!(hopping.len(), frequencies.len(), "Hopping pattern must cover the alphabet");
+
+        let design = BankDesign {
+            frequencies: frequencies.to_vec(),
+            tone_gains: tone_gains.map(<[f64]>::to_vec),
+            symbol_duration,
+            shape: WaveletShape::default(),
+            fs,
+        };
+        Self { bank_real: Vec::new(), bank_imag: Vec::new(), hopping: hopping.to_vec(), symbol_len: 0, descramble: false, design }
+            .with_bank()
+    }
+
+    /// Symbol window length in Gaussian widths (`ModemConfig::wavelet_sigmas`)
+    pub fn with_wavelet_sigmas(mut self, sigmas: f64) -> Self {
+        self.design.shape.sigmas = sigmas;
+        self.with_bank()
+    }
+
+    /// Pulse shape of the transmitter (`ModemConfig::wavelet_shape`)
+    pub fn with_wavelet_shape(mut self, shape: WaveletShape) -> Self {
+        self.design.shape = shape;
+        self.with_bank()
+    }
+
+    fn with_bank(mut self) -> Self {
+        let taps = self.design.taps();
+        self.symbol_len = taps[0].0.len();
+        (self.bank_real, self.bank_imag) = taps.into_iter().unzip();
+        self
+    }
+
+    /// Remove the coded-bit whitening (`scrambler`) from the LLRs
+    pub fn with_descrambler(mut self) -> Self {
+        self.descramble = true;
+        self
+    }
+
+    /// Differential lag (one block visits every tone once)
+    pub fn lag(&self) -> usize {
+        self.hopping.len()
+    }
+
+    /// Samples per data symbol
+    pub fn symbol_samples(&self) -> usize {
+        self.symbol_len
+    }
+
+    /// Matched-filter output (real, imag) of every complete symbol
+    pub fn correlate(&self, samples: &[f32]) -> Vec<(f32, f32)> {
+        samples
+            .chunks_exact(self.symbol_len)
+            .enumerate()
+            .map(|(i, symbol)| {
+                let tone = self.hopping[i % self.hopping.len()];
+                let real = dot(symbol, &self.bank_real[tone]);
+                let imag = dot(symbol, &self.bank_imag[tone]);
+                (real, imag)
+            })
+            .collect()
+    }
+
+    /// Soft LLRs of the differential bits
+    ///
+    /// Uses whole differential blocks only; returns an empty vector if fewer
+    /// than two blocks of symbols are present.
+    pub fn demodulate(&self, samples: &[f32]) -> Vec<f32> {
+        let lag = self.lag();
+        let corr = self.correlate(samples);
+
+        let trunc_len = (corr.len() / lag) * lag;
+        if trunc_len < 2 * lag {
+            return Vec::new();
+        }
+
+        let mut llrs: Vec<f32> = (0..trunc_len - lag)
+            .map(|j| {
+                let (real_prev, imag_prev) = corr[j];
+                let (real_curr, imag_curr) = corr[j + lag];
+                let amp_prev = libm::sqrtf(real_prev * real_prev + imag_prev * imag_prev);
+                (real_curr * real_prev + imag_curr * imag_prev) / (amp_prev + 1e-6)
+            })
+            .collect();
+
+        if self.descramble {
+            descramble_llrs(&mut llrs);
+        }
+        llrs
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FREQS: [f64; 8] = [261.63, 293.66, 329.63, 349.23, 392.00, 440.00, 493.88, 523.25];
+    const HOPPING: [usize; 8] = [0, 3, 6, 1, 4, 7, 2, 5];
+
+    #[test]
+    fn test_scalar_demodulator_recovers_dpsk_bits() {
+        let demod = ScalarDemodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0);
+        let bits: Vec<u8> = (0..24).map(|i| ((i * 5) % 3 == 0) as u8).collect();
+
+        // Reference block at phase 0, then phase flips of π for bit 1
+        let mut phases = vec![0.0f64; 8];
+        for (j, &bit) in bits.iter().enumerate() {
+            phases.push(phases[j] + if bit == 1 { PI } else { 0.0 });
+        }
+
+        // Transmit Re(ψ · e^{iφ}) for each symbol
+        let mut signal = Vec::new();
+        for (i, &phase) in phases.iter().enumerate() {
+            let (real, imag) = morlet_wavelet_f32(FREQS[HOPPING[i % 8]], 0.1, 8000.0);
+            signal.extend(real.iter().zip(&imag)
+                .map(|(&r, &im)| r * libm::cos(phase) as f32 - im * libm::sin(phase) as f32));
+        }
+
+        let llrs = demod.demodulate(&signal);
+        let decided: Vec<u8> = llrs.iter().map(|&l| (l < 0.0) as u8).collect();
+
+        assert_eq!(decided, bits);
+        assert!(demod.demodulate(&signal[..8 * 800]).is_empty());
+    }
+
+    #[test]
+    fn test_wavelet_sigmas_rebuild_the_bank() {
+        let bits: Vec<u8> = (0..16).map(|i| (i % 3 == 1) as u8).collect();
+        let mut phases = vec![0.0f64; 8];
+        for (j, &bit) in bits.iter().enumerate() {
+            phases.push(phases[j] + if bit == 1 { PI } else { 0.0 });
+        }
+
+        // Wide pulses (3 widths per window) on the air
+        let shape = WaveletShape::morlet(3.0);
+        let mut signal = Vec::new();
+        for (i, &phase) in phases.iter().enumerate() {
+            let (real, imag) = shaped_wavelet_f32(FREQS[HOPPING[i % 8]], 0.1, shape, 8000.0);
+            signal.extend(real.iter().zip(&imag)
+                .map(|(&r, &im)| r * libm::cos(phase) as f32 - im * libm::sin(phase) as f32));
+        }
+
+        let matched = ScalarDemodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0).with_wavelet_sigmas(3.0);
+        let default = ScalarDemodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0);
+        let energy = |demod: &ScalarDemodulator| demod.demodulate(&signal).iter().map(|l| l.abs()).sum::<f32>();
+
+        let decided: Vec<u8> = matched.demodulate(&signal).iter().map(|&l| (l < 0.0) as u8).collect();
+        assert_eq!(decided, bits);
+        assert!(energy(&matched) > energy(&default));
+        assert_eq!(ScalarDemodulator::new(&FREQS, &HOPPING, None, 0.1, 8000.0).with_wavelet_shape(shape).demodulate(&signal),
+                   matched.demodulate(&signal));
+    }
+}
@@ -0,0 +1,153 @@
+//! Reed-Solomon Outer Code over Frame Payloads
+//!
+//! Concatenated scheme: a message is RS-encoded once and the codeword is cut
+//! into frame payloads, each then protected by the polar inner code:
+//!
+//! ```text
+//! [len: u8][message][zero pad][RS parity] -> MAX_PAYLOAD-byte chunks -> one frame each
+//! ```
+//!
+//! The codeword is shortened to fill its frames exactly, so the receiver
+//! derives it from the frame count alone. Residual byte errors left by the
+//! polar decoder near the SNR cliff are corrected as errors; a frame whose
+//! header failed to parse (or never arrived) is passed as `None` and its
+//! bytes are treated as erasures, which cost half as much parity.
+//!
+//! With 16 parity bytes one lost frame, or 8 stray bytes, are recovered.
+//! The outer code is not signaled on air: both ends select it through the
+//! same profile.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::frame::MAX_PAYLOAD;
+use crate::reed_solomon::{ReedSolomon, RsError};
+
+/// Header bytes in front of the message (message length)
+pub const OUTER_HEADER_LEN: usize = 1;
+
+/// Decoded message with its correction statistics
+#[derive(Clone, Debug, PartialEq)]
+pub struct OuterDecode {
+    pub message: Vec<u8>,
+
+    /// Bytes the RS decoder changed (errors and erased bytes)
+    pub corrected: usize,
+
+    /// Frames passed as missing
+    pub erased_frames: usize,
+}
+
+/// RS outer code spanning the frames of one message
+#[derive(Clone, Debug, PartialEq)]
+pub struct OuterCode {
+    rs: ReedSolomon,
+}
+
+impl OuterCode {
+    pub fn new(parity: usize) -> Self {
+        Self { rs: ReedSolomon::new(parity) }
+    }
+
+    pub fn parity(&self) -> usize {
+        self.rs.parity()
+    }
+
+    /// Largest message (bytes): 255-byte codeword, or the 255-byte length limit
+    pub fn max_message(&self) -> usize {
+        let frames = 255 / MAX_PAYLOAD;
+        (frames * MAX_PAYLOAD - self.rs.parity() - OUTER_HEADER_LEN).min(u8::MAX as usize)
+    }
+
+    /// Frames needed for a message of `len` bytes
+    pub fn num_frames(&self, len: usize) -> usize {
+        (OUTER_HEADER_LEN + len + self.rs.parity()).div_ceil(MAX_PAYLOAD)
+    }
+
+    /// RS-encode a message into frame payloads of `MAX_PAYLOAD` bytes
+    ///
+    /// Panics if the message exceeds `max_message()`.
+    pub fn encode_message(&self, message: &[u8]) -> Vec<Vec<u8>> {
+        assert!(message.len() <= self.max_message(), "message exceeds {} bytes", self.max_message());
+
+        let n = self.num_frames(message.len()) * MAX_PAYLOAD;
+        let mut data = vec![message.len() as u8];
+        data.extend_from_slice(message);
+        data.resize(n - self.rs.parity(), 0);
+
+        self.rs.encode(&data).chunks(MAX_PAYLOAD).map(|c| c.to_vec()).collect()
+    }
+
+    /// Recover the message from received frame payloads, in transmit order
+    ///
+    /// `None` marks a frame that didn't decode; its bytes become erasures.
+    pub fn decode_message(&self, payloads: &[Option<Vec<u8>>]) -> Result<OuterDecode, RsError> {
+        let n = payloads.len() * MAX_PAYLOAD;
+        let mut codeword = Vec::with_capacity(n);
+        let mut erasures = Vec::new();
+        for payload in payloads {
+            match payload {
+                Some(bytes) => {
+                    let start = codeword.len();
+                    codeword.extend(bytes.iter().take(MAX_PAYLOAD));
+                    codeword.resize(start + MAX_PAYLOAD, 0);
+                }
+                None => {
+                    erasures.extend(codeword.len()..codeword.len() + MAX_PAYLOAD);
+                    codeword.resize(codeword.len() + MAX_PAYLOAD, 0);
+                }
+            }
+        }
+
+        let corrected = self.rs.decode(&mut codeword, &erasures)?;
+
+        // A length that runs into the parity means a miscorrection
+        let len = codeword[0] as usize;
+        if OUTER_HEADER_LEN + len + self.rs.parity() > n {
+            return Err(RsError::TooManyErrors);
+        }
+
+        Ok(OuterDecode {
+            message: codeword[OUTER_HEADER_LEN..OUTER_HEADER_LEN + len].to_vec(),
+            corrected,
+            erased_frames: erasures.len() / MAX_PAYLOAD,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lost_frame_and_stray_bytes() {
+        let outer = OuterCode::new(16);
+        let message = b"CQ CQ DE BACH73 BWV 1080 K";
+        let frames = outer.encode_message(message);
+        assert_eq!(frames.len(), outer.num_frames(message.len()));
+        assert!(frames.iter().all(|f| f.len() == MAX_PAYLOAD));
+
+        // Lost second frame
+        let mut received: Vec<Option<Vec<u8>>> = frames.iter().cloned().map(Some).collect();
+        received[1] = None;
+        let decoded = outer.decode_message(&received).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.erased_frames, 1);
+
+        // Byte errors spread over the frames
+        let mut received: Vec<Option<Vec<u8>>> = frames.iter().cloned().map(Some).collect();
+        for (i, frame) in received.iter_mut().enumerate() {
+            let bytes = frame.as_mut().unwrap();
+            bytes[i * 3] ^= 0x40;
+            bytes[14 - i] ^= 0x01;
+        }
+        let decoded = outer.decode_message(&received).unwrap();
+        assert_eq!(decoded.message, message);
+        assert_eq!(decoded.corrected, 2 * frames.len());
+
+        // Two lost frames exceed 16 parity bytes
+        let mut received: Vec<Option<Vec<u8>>> = frames.into_iter().map(Some).collect();
+        received[0] = None;
+        received[2] = None;
+        assert!(outer.decode_message(&received).is_err());
+    }
+}
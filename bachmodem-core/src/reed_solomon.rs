@@ -0,0 +1,304 @@
+//! Reed-Solomon Codec over GF(256)
+//!
+//! Systematic RS(n, n - parity) byte code, shortened to any n ≤ 255:
+//! - Field polynomial x^8 + x^4 + x^3 + x^2 + 1 (0x11d), primitive element 2
+//! - Generator roots 2^0 .. 2^(parity-1)
+//! - Codeword = data bytes followed by parity bytes
+//!
+//! The decoder corrects `e` byte errors and `f` erasures (known bad
+//! positions) as long as 2e + f ≤ parity: Berlekamp-Massey seeded with the
+//! erasure locator, Chien search, Forney magnitudes.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Field polynomial, including the x^8 term
+const GF_POLY: u16 = 0x11d;
+
+/// Antilog table, doubled so products of logs need no reduction
+const GF_EXP: [u8; 512] = {
+    let mut table = [0u8; 512];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        table[i] = x as u8;
+        table[i + 255] = x as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF_POLY;
+        }
+        i += 1;
+    }
+    table
+};
+
+/// Log table (entry 0 unused)
+const GF_LOG: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 255 {
+        table[GF_EXP[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    GF_EXP[GF_LOG[a as usize] as usize + GF_LOG[b as usize] as usize]
+}
+
+fn gf_inv(a: u8) -> u8 {
+    GF_EXP[255 - GF_LOG[a as usize] as usize]
+}
+
+/// 2^power
+fn gf_pow2(power: usize) -> u8 {
+    GF_EXP[power % 255]
+}
+
+/// Evaluate a polynomial with coefficients in ascending powers
+fn poly_eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, &c| gf_mul(acc, x) ^ c)
+}
+
+/// Reed-Solomon decoding errors
+#[derive(Clone, Debug, PartialEq)]
+pub enum RsError {
+    /// Codeword longer than 255 bytes or shorter than the parity
+    InvalidLength { len: usize, parity: usize },
+
+    /// More errors and erasures than the parity can correct
+    TooManyErrors,
+}
+
+impl fmt::Display for RsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RsError::InvalidLength { len, parity } => {
+                write!(f, "codeword of {} bytes invalid for {} parity bytes (max 255)", len, parity)
+            }
+            RsError::TooManyErrors => write!(f, "too many byte errors to correct"),
+        }
+    }
+}
+
+impl core::error::Error for RsError {}
+
+/// Systematic Reed-Solomon code with `parity` check bytes
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReedSolomon {
+    parity: usize,
+
+    /// Generator polynomial, descending powers, monic
+    generator: Vec<u8>,
+}
+
+impl ReedSolomon {
+    pub fn new(parity: usize) -> Self {
+        assert!((1..255).contains(&parity), "RS parity must be 1..=254 bytes");
+
+        let mut generator = vec![1u8];
+        for k in 0..parity {
+            // × (x + 2^k)
+            let root = gf_pow2(k);
+            let mut next = vec![0u8; generator.len() + 1];
+            for (i, &g) in generator.iter().enumerate() {
+                next[i] ^= g;
+                next[i + 1] ^= gf_mul(g, root);
+            }
+            generator = next;
+        }
+
+        Self { parity, generator }
+    }
+
+    /// Check bytes per codeword
+    pub fn parity(&self) -> usize {
+        self.parity
+    }
+
+    /// Largest data block per codeword (bytes)
+    pub fn max_data(&self) -> usize {
+        255 - self.parity
+    }
+
+    /// Data followed by its parity bytes
+    ///
+    /// Panics if the codeword would exceed 255 bytes.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        assert!(data.len() <= self.max_data(), "RS data block exceeds {} bytes", self.max_data());
+
+        // Remainder of data · x^parity divided by the generator
+        let mut codeword = data.to_vec();
+        codeword.resize(data.len() + self.parity, 0);
+        for i in 0..data.len() {
+            let coef = codeword[i];
+            if coef != 0 {
+                for (j, &g) in self.generator.iter().enumerate().skip(1) {
+                    codeword[i + j] ^= gf_mul(g, coef);
+                }
+            }
+        }
+        codeword[..data.len()].copy_from_slice(data);
+        codeword
+    }
+
+    /// Syndromes S_k = c(2^k), k = 0..parity
+    fn syndromes(&self, codeword: &[u8]) -> Vec<u8> {
+        (0..self.parity)
+            .map(|k| codeword.iter().fold(0, |acc, &c| gf_mul(acc, gf_pow2(k)) ^ c))
+            .collect()
+    }
+
+    /// Correct `codeword` in place; `erasures` are indices of bytes known bad
+    ///
+    /// Returns the number of bytes changed.
+    pub fn decode(&self, codeword: &mut [u8], erasures: &[usize]) -> Result<usize, RsError> {
+        let n = codeword.len();
+        if n > 255 || n < self.parity {
+            return Err(RsError::InvalidLength { len: n, parity: self.parity });
+        }
+        if erasures.len() > self.parity {
+            return Err(RsError::TooManyErrors);
+        }
+
+        let syndromes = self.syndromes(codeword);
+        if syndromes.iter().all(|&s| s == 0) {
+            return Ok(0);
+        }
+
+        // Erasure locator Γ(x) = Π (1 - X x), X = 2^(degree of the position)
+        let mut locator = vec![1u8];
+        for &pos in erasures {
+            let x = gf_pow2(n - 1 - pos);
+            let mut next = vec![0u8; locator.len() + 1];
+            for (i, &c) in locator.iter().enumerate() {
+                next[i] ^= c;
+                next[i + 1] ^= gf_mul(c, x);
+            }
+            locator = next;
+        }
+
+        // Berlekamp-Massey from the erasure locator, ascending powers
+        let rho = erasures.len();
+        let mut previous = locator.clone();
+        let mut degree = rho;
+        for r in rho..self.parity {
+            let delta = (0..locator.len())
+                .filter(|&i| i <= r)
+                .fold(0, |acc, i| acc ^ gf_mul(locator[i], syndromes[r - i]));
+
+            previous.insert(0, 0);
+            if delta == 0 {
+                continue;
+            }
+
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len()), 0);
+            for (i, &b) in previous.iter().enumerate() {
+                next[i] ^= gf_mul(delta, b);
+            }
+
+            if 2 * degree <= r + rho {
+                let inv = gf_inv(delta);
+                previous = locator.iter().map(|&c| gf_mul(c, inv)).collect();
+                degree = r + 1 + rho - degree;
+            }
+            locator = next;
+        }
+        while locator.len() > 1 && locator[locator.len() - 1] == 0 {
+            locator.pop();
+        }
+
+        let errata = locator.len() - 1;
+        if 2 * (errata - rho.min(errata)) + rho > self.parity {
+            return Err(RsError::TooManyErrors);
+        }
+
+        // Chien search over the positions of this (shortened) codeword
+        let positions: Vec<usize> = (0..n)
+            .filter(|&pos| poly_eval(&locator, gf_inv(gf_pow2(n - 1 - pos))) == 0)
+            .collect();
+        if positions.len() != errata {
+            return Err(RsError::TooManyErrors);
+        }
+
+        // Forney: Y = X Ω(X⁻¹) / Λ'(X⁻¹), Ω = S Λ mod x^parity
+        let mut evaluator = vec![0u8; self.parity];
+        for (i, &l) in locator.iter().enumerate() {
+            for (k, &s) in syndromes.iter().enumerate().take(self.parity - i.min(self.parity)) {
+                evaluator[i + k] ^= gf_mul(l, s);
+            }
+        }
+        let derivative: Vec<u8> = locator.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+            .collect();
+
+        let mut changed = 0;
+        for &pos in &positions {
+            let x = gf_pow2(n - 1 - pos);
+            let x_inv = gf_inv(x);
+            let denominator = poly_eval(&derivative, x_inv);
+            if denominator == 0 {
+                return Err(RsError::TooManyErrors);
+            }
+            let magnitude = gf_mul(gf_mul(x, poly_eval(&evaluator, x_inv)), gf_inv(denominator));
+            if magnitude != 0 {
+                codeword[pos] ^= magnitude;
+                changed += 1;
+            }
+        }
+
+        if self.syndromes(codeword).iter().any(|&s| s != 0) {
+            return Err(RsError::TooManyErrors);
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrects_errors_and_erasures() {
+        let rs = ReedSolomon::new(16);
+        let data: Vec<u8> = (0..40u8).map(|i| i.wrapping_mul(37) ^ 0x5a).collect();
+        let codeword = rs.encode(&data);
+        assert_eq!(codeword.len(), 56);
+        assert_eq!(&codeword[..40], data.as_slice());
+
+        // 5 errors + 6 erasures: 2·5 + 6 = 16
+        let mut received = codeword.clone();
+        for pos in [0, 7, 21, 39, 55] {
+            received[pos] ^= 0xa5;
+        }
+        let erasures = [2, 3, 4, 30, 31, 50];
+        for &pos in &erasures {
+            received[pos] = 0;
+        }
+        let changed = rs.decode(&mut received, &erasures).unwrap();
+        assert_eq!(received, codeword);
+        assert_eq!(changed, 5 + erasures.iter().filter(|&&p| codeword[p] != 0).count());
+
+        // A whole erased 15-byte frame plus nothing else still fits
+        let mut received = codeword.clone();
+        let frame: Vec<usize> = (15..30).collect();
+        frame.iter().for_each(|&p| received[p] = 0xff);
+        rs.decode(&mut received, &frame).unwrap();
+        assert_eq!(received, codeword);
+
+        // 9 errors exceed t = 8
+        let mut received = codeword.clone();
+        for pos in 0..9 {
+            received[pos * 5] ^= 0x11;
+        }
+        assert_eq!(rs.decode(&mut received, &[]), Err(RsError::TooManyErrors));
+    }
+}
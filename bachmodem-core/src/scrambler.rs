@@ -0,0 +1,78 @@
+//! Coded-Bit Whitening (Scrambler)
+//!
+//! Zero padding and repetitive payloads leave long runs of equal coded bits;
+//! each run becomes a run of identical phase steps on the hopping tones,
+//! which puts lines into the spectrum and weakens the differential phase
+//! references the receiver tracks. Scrambling XORs the coded bits (after
+//! polar encoding and interleaving) with the x^7 + x^4 + 1 maximal-length
+//! sequence (period 127, register seeded with all ones, the 802.11
+//! generator). Receivers undo it on the LLRs by flipping the sign wherever
+//! the sequence is 1.
+//!
+//! The scrambler is additive and restarts at the first data bit of every
+//! frame, so the preamble provides its synchronization. A self-synchronizing
+//! (multiplicative) descrambler needs hard decisions and triples every
+//! channel bit error, which would throw away the soft-decision decoding.
+
+use alloc::vec::Vec;
+use core::ops::Neg;
+
+/// Initial shift register state (7 bits, all ones)
+pub const SCRAMBLER_SEED: u8 = 0x7f;
+
+/// First `len` bits (0/1) of the whitening sequence
+pub fn whitening_sequence(len: usize) -> Vec<u8> {
+    let mut state = SCRAMBLER_SEED;
+    (0..len)
+        .map(|_| {
+            // Taps at x^7 and x^4
+            let bit = ((state >> 6) ^ (state >> 3)) & 1;
+            state = ((state << 1) | bit) & 0x7f;
+            bit
+        })
+        .collect()
+}
+
+/// XOR bits (0/1) with the whitening sequence; applying it twice restores them
+pub fn scramble_bits(bits: &mut [u8]) {
+    let sequence = whitening_sequence(bits.len());
+    for (bit, w) in bits.iter_mut().zip(sequence) {
+        *bit ^= w;
+    }
+}
+
+/// Remove the whitening from LLRs (positive -> bit 0)
+pub fn descramble_llrs<T: Copy + Neg<Output = T>>(llrs: &mut [T]) {
+    let sequence = whitening_sequence(llrs.len());
+    for (llr, w) in llrs.iter_mut().zip(sequence) {
+        if w == 1 {
+            *llr = -*llr;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitening_sequence() {
+        let seq = whitening_sequence(254);
+
+        // Maximal length: period 127 with 64 ones
+        assert_eq!(seq[..127], seq[127..]);
+        assert_eq!(seq[..127].iter().filter(|&&b| b == 1).count(), 64);
+
+        // An all-zero block comes out balanced and is restored
+        let mut bits = alloc::vec![0u8; 256];
+        scramble_bits(&mut bits);
+        assert!(bits.iter().filter(|&&b| b == 1).count() > 100);
+        scramble_bits(&mut bits);
+        assert!(bits.iter().all(|&b| b == 0));
+
+        let mut llrs = alloc::vec![1.0f32; 8];
+        descramble_llrs(&mut llrs);
+        let expected: Vec<f32> = whitening_sequence(8).iter().map(|&w| if w == 1 { -1.0 } else { 1.0 }).collect();
+        assert_eq!(llrs, expected);
+    }
+}
@@ -0,0 +1,59 @@
+//! Wire-Format Version
+//!
+//! One number covering everything a receiver must agree on with the
+//! transmitter to decode a frame. Any change to the items below bumps
+//! `WIRE_FORMAT_VERSION`; receivers list what they understand in
+//! `SUPPORTED_WIRE_VERSIONS` and reject anything else as "unsupported
+//! version" instead of emitting garbage.
+//!
+//! Version 1:
+//! - Preamble: 4-cycle up/down Bach sweep, 50 ms notes; flourish and
+//!   postamble = one up/down sweep shifted by n/2 and n/4 tones
+//! - Tone tables: `BACH_FREQUENCIES_8` / `BACH_FREQUENCIES` / `BACH_FREQUENCIES_32`
+//! - Hopping patterns: `HOPPING_PATTERN_8` / `HOPPING_PATTERN` / `HOPPING_PATTERN_32`
+//! - Modulation: lag-differential DPSK, lag = number of tones, one
+//!   reference block
+//! - FEC: Polar (256, 128), bit-reversal construction, block interleaver
+//!   with one column per tone (partial-band mode: half as many rows as tones)
+//! - Frame layout: [version: u8][payload: 15 bytes, zero padded], MSB first
+//!
+//! The version byte rides inside the codeword, so it is protected by the
+//! same FEC as the payload. It takes the first information byte, which is
+//! why `MAX_PAYLOAD` is 15 bytes (16 before versioning).
+//!
+//! Ends that talk to each other exchange their `SUPPORTED_WIRE_VERSIONS`
+//! (e.g. in a session handshake); `negotiate_version` picks the newest one
+//! both decode, and `encode_frame_with_version` transmits in it.
+//!
+//! The transmit waveform of every named profile is pinned per version in
+//! `bachmodem/tests/fixtures/waveforms.txt` (`waveform_regression` test).
+
+/// Current wire-format version emitted by transmitters
+pub const WIRE_FORMAT_VERSION: u8 = 1;
+
+/// Wire-format versions this receiver can decode
+pub const SUPPORTED_WIRE_VERSIONS: [u8; 1] = [1];
+
+/// Check whether a received version can be decoded
+pub fn is_supported(version: u8) -> bool {
+    SUPPORTED_WIRE_VERSIONS.contains(&version)
+}
+
+/// Newest wire-format version both this end and a peer advertising
+/// `peer_versions` decode (None = no common version)
+pub fn negotiate_version(peer_versions: &[u8]) -> Option<u8> {
+    SUPPORTED_WIRE_VERSIONS.iter().copied().filter(|v| peer_versions.contains(v)).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(&SUPPORTED_WIRE_VERSIONS), Some(WIRE_FORMAT_VERSION));
+        assert_eq!(negotiate_version(&[WIRE_FORMAT_VERSION, WIRE_FORMAT_VERSION + 1]), Some(WIRE_FORMAT_VERSION));
+        assert_eq!(negotiate_version(&[WIRE_FORMAT_VERSION + 1]), None);
+        assert_eq!(negotiate_version(&[]), None);
+    }
+}
@@ -9,9 +9,10 @@ use bachmodem::{
     write_wav, read_wav, WattersonChannel,
     interleave, deinterleave, PolarCode, soft_bits_to_llrs, compute_soft_bits,
     RakeReceiver, encode_bits, HOPPING_PATTERN, FS, SYMBOL_DURATION,
+    ChaCha20Rng, RngStream, SimSeed, gaussian_noise,
 };
 use burn::backend::Wgpu;
-use burn::tensor::{Tensor, Distribution};
+use burn::tensor::Tensor;
 use burn::tensor::ElementConversion;
 
 type Backend = Wgpu;
@@ -23,6 +24,7 @@ fn main() {
     println!("=======================================================\n");
     
     let device = Default::default();
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
     
     // Test configurations
     let test_snrs = vec![-30.0, -27.0, -25.0, -23.0, -20.0];
@@ -60,6 +62,7 @@ fn main() {
             for trial in 0..num_trials {
                 let success = run_single_test(
                     &device,
+                    &mut rng,
                     snr_db,
                     use_fading,
                     use_interleave,
@@ -96,6 +99,7 @@ fn main() {
 
 fn run_single_test(
     device: &<Backend as burn::tensor::backend::Backend>::Device,
+    rng: &mut ChaCha20Rng,
     target_snr_db: f32,
     use_fading: bool,
     use_interleave: bool,
@@ -158,17 +162,13 @@ fn run_single_test(
     // Apply fading if enabled
     let faded_signal = if use_fading {
         let channel = WattersonChannel::moderate();
-        channel.apply::<Backend>(device, &clean_signal)
+        channel.apply_with_rng::<Backend, _>(device, &clean_signal, rng)
     } else {
         clean_signal.clone()
     };
     
     // Add AWGN
-    let noise = Tensor::<Backend, 1>::random(
-        [num_samples],
-        Distribution::Normal(0.0, noise_std as f64),
-        device,
-    );
+    let noise = gaussian_noise::<Backend, _>(device, num_samples, noise_std, rng);
     
     let noisy_signal = faded_signal + noise;
     
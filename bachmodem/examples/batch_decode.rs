@@ -0,0 +1,77 @@
+//! Batch decoding with a `ReceiverPool`
+//!
+//! Decodes WAV recordings (one frame each) on a pool of worker threads, e.g.
+//! to re-process an archive after a receiver update. Without files it
+//! simulates a batch of Watterson-faded captures and compares the wall time
+//! of one worker with the requested pool size.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --example batch_decode -- [workers] [file.wav ...]
+//! ```
+
+use bachmodem::{
+    BachTransmitter, DecodeSource, ModemConfig, ReceiverPool, ReceiverPoolConfig, RngStream, SimSeed, WattersonChannel,
+    gaussian_noise,
+};
+use burn::tensor::ElementConversion;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use std::time::Instant;
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const SIMULATED_CAPTURES: usize = 8;
+const SNR_DB: f32 = -15.0;
+
+fn main() {
+    let device = Default::default();
+    let mut args = std::env::args().skip(1);
+    let workers: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(4);
+    let files: Vec<String> = args.collect();
+
+    if !files.is_empty() {
+        let mut pool = ReceiverPool::<Backend>::new(ReceiverPoolConfig::default(), &device, workers).unwrap();
+        let started = Instant::now();
+        let sources = files.iter().map(|f| DecodeSource::WavFile(f.into())).collect();
+
+        for (file, result) in files.iter().zip(pool.decode_all(sources)) {
+            match result.frame {
+                Ok(frame) => println!("{:<32} {:>6.1} dB  {:?}", file, frame.snr_db,
+                                      String::from_utf8_lossy(&frame.payload).trim_end_matches('\0')),
+                Err(err) => println!("{:<32} {}", file, err),
+            }
+        }
+        println!("\n{} files on {} workers in {:.2?}", files.len(), pool.workers(), started.elapsed());
+        return;
+    }
+
+    println!("Simulating {} captures at {} dB SNR, moderate Watterson\n", SIMULATED_CAPTURES, SNR_DB);
+    let tx = BachTransmitter::new(ModemConfig::default());
+    let channel = WattersonChannel::moderate();
+    let seed = SimSeed(0);
+    let captures: Vec<Vec<f32>> = (0..SIMULATED_CAPTURES)
+        .map(|i| {
+            let signal = tx.build::<Backend>(&device, format!("BATCH {:02}", i).as_bytes()).unwrap();
+            let faded = channel.apply_with_rng::<Backend, _>(&device, &signal, &mut seed.rng_for(RngStream::Fading, i as u32));
+            let power: f32 = faded.clone().powf_scalar(2.0).mean().into_scalar().elem();
+            let noise_std = (power / 10f32.powf(SNR_DB / 10.0)).sqrt();
+            let noise = gaussian_noise::<Backend, _>(&device, faded.dims()[0], noise_std, &mut seed.rng_for(RngStream::Noise, i as u32));
+            let rx = faded.clone() + noise;
+            rx.into_data().to_vec().unwrap()
+        })
+        .collect();
+
+    println!("{:>8} {:>10} {:>10} {:>12}", "workers", "decoded", "wall", "per capture");
+    for pool_size in [1, workers] {
+        let mut pool = ReceiverPool::<Backend>::new(ReceiverPoolConfig::default(), &device, pool_size).unwrap();
+        let started = Instant::now();
+        let results = pool.decode_all(captures.iter().cloned().map(DecodeSource::Samples).collect());
+        let wall = started.elapsed();
+
+        let decoded = results.iter().enumerate()
+            .filter(|(i, r)| r.frame.as_ref().is_ok_and(|f| f.payload.starts_with(format!("BATCH {:02}", i).as_bytes())))
+            .count();
+        println!("{:>8} {:>10} {:>10.2?} {:>12.2?}", pool_size, format!("{}/{}", decoded, captures.len()),
+                 wall, wall / captures.len() as u32);
+    }
+}
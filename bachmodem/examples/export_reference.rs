@@ -0,0 +1,45 @@
+/// Reference Waveform Export
+///
+/// Writes the wavelet bank, sync waveforms and interleaver permutations of a
+/// profile as `.npy` files (and a `.safetensors` file when built with the
+/// `export` feature) for FPGA/NPU implementations.
+///
+/// Usage: cargo run --example export_reference --features export [profile] [out_dir]
+
+use bachmodem::{ModemConfig, ReferenceData, ReferenceSet};
+use burn::backend::Wgpu;
+
+type MyBackend = Wgpu;
+
+fn main() {
+    let profile = std::env::args().nth(1).unwrap_or_else(|| "standard".to_string());
+    let out_dir = std::env::args().nth(2).unwrap_or_else(|| format!("reference_{}", profile));
+
+    let Some(config) = ModemConfig::profile(&profile) else {
+        eprintln!("Unknown profile '{}'", profile);
+        std::process::exit(1);
+    };
+
+    let device = Default::default();
+    let set = ReferenceSet::from_config::<MyBackend>(&device, &config);
+
+    println!("=== Reference export: {} ({} tones) ===\n", profile, config.num_tones);
+    for array in &set.arrays {
+        let dtype = match array.data {
+            ReferenceData::F32(_) => "f32",
+            ReferenceData::I32(_) => "i32",
+            ReferenceData::U8(_) => "u8",
+        };
+        println!("  {:<26} {:>4} {:?}", array.name, dtype, array.shape);
+    }
+
+    set.write_npy_dir(&out_dir).expect("Failed to write .npy files");
+    println!("\nWrote {}/*.npy", out_dir);
+
+    #[cfg(feature = "export")]
+    {
+        let path = format!("{}/reference.safetensors", out_dir);
+        set.write_safetensors(&path).expect("Failed to write .safetensors");
+        println!("Wrote {}", path);
+    }
+}
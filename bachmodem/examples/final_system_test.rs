@@ -19,9 +19,10 @@ use bachmodem::{
     TimeSlotConfig, generate_repetition_transmission,
     RakeReceiver,
     FftBackend,
+    RngStream, SimSeed, gaussian_noise,
 };
 use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
-use burn::tensor::{Tensor, Distribution, ElementConversion};
+use burn::tensor::{Tensor, ElementConversion};
 use hound;
 
 // Use raw CubeBackend to avoid Fusion wrapper which doesn't implement FftBackend yet
@@ -112,14 +113,11 @@ fn main() {
     
     println!("Applying Watterson Channel (Gentle)...");
     let channel = WattersonChannel::gentle();
-    let faded_signal = channel.apply::<Backend>(&device, &clean_signal);
+    let seed = SimSeed(0);
+    let faded_signal = channel.apply_with_rng::<Backend, _>(&device, &clean_signal, &mut seed.rng(RngStream::Fading));
     
     println!("Adding Noise (-30 dB)...");
-    let noise = Tensor::<Backend, 1>::random(
-        faded_signal.shape(),
-        Distribution::Normal(0.0, noise_std as f64),
-        &device,
-    );
+    let noise = gaussian_noise::<Backend, _>(&device, faded_signal.dims()[0], noise_std, &mut seed.rng(RngStream::Noise));
     
     let rx_signal = faded_signal + noise;
     
@@ -166,9 +164,10 @@ fn main() {
     
     println!("  Synchronizing (FFT)...");
     let time_offset = match bachmodem::modulation::synchronize_signal::<Backend>(&device, &search_window) {
-        Some(pos) => {
-            println!("  ✓ Sync found at sample {} ({:.2}s)", pos, pos as f32 / 8000.0);
-            pos
+        Some(sync) => {
+            println!("  ✓ Sync found at sample {} ({:.2}s), corr {:.3}, SNR {:.1} dB",
+                     sync.metrics.position, sync.metrics.position as f32 / 8000.0, sync.metrics.correlation, sync.snr_estimate_db);
+            sync.metrics.position
         },
         None => {
             println!("  ✗ Sync failed!");
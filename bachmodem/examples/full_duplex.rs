@@ -0,0 +1,102 @@
+//! Split-band full-duplex bench test
+//!
+//! Plays transmissions on the output device and decodes them from the input
+//! device at the same time, reporting per-frame path latency and decode
+//! status. Devices come from `bachmodem-audio.conf` (see `bachmodem
+//! --list-devices`, `--input NAME`, `--output NAME`), the transmit peak level
+//! from `bachmodem --calibrate-level`.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --features audio --example full_duplex -- [frames] [narrow500|narrowband|...]
+//! ```
+
+use bachmodem::{
+    AudioSettings, BachTransmitter, DuplexSession, ModemConfig, PolarCodeSCL,
+    deinterleave_gpu, demodulate_fhdpsk_soft_erasures_with_config, synchronize_signal_with_config,
+    pack_bits, parse_frame, FS,
+};
+use bachmodem::transmitter::{CODE_K, CODE_N};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::tensor::Tensor;
+use std::time::Duration;
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+/// Silence after each frame before the capture is decoded (seconds)
+const TAIL: f64 = 2.0;
+
+fn main() {
+    let device = Default::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let frames: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(5);
+    let config = match args.get(1) {
+        Some(name) => ModemConfig::profile(name).expect("unknown profile"),
+        None => ModemConfig::default(),
+    };
+
+    let settings = AudioSettings::load("bachmodem-audio.conf").expect("bad audio settings");
+    println!("Output: {:?}, input: {:?}, {} Hz, level {:.2}",
+             settings.output_device, settings.input_device, settings.sample_rate, settings.tx_level);
+
+    let mut session = DuplexSession::start(&settings, 1.0).expect("cannot open audio devices");
+    let tx = BachTransmitter::new(config.clone());
+    let decoder = PolarCodeSCL::new(CODE_N, CODE_K);
+
+    let (mut decoded, mut lost) = (0, 0);
+
+    for frame in 0..frames {
+        let payload = format!("DUPLEX {:04}", frame);
+        let signal = tx.build::<Backend>(&device, payload.as_bytes()).unwrap();
+        let mut samples = signal.into_data().to_vec::<f32>().unwrap();
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs())).max(1e-9);
+        samples.iter_mut().for_each(|s| *s *= settings.tx_level / peak);
+
+        let seq = session.transmit(&samples);
+        println!("\n[{}] Sent {:?} ({:.1} s)", seq, payload, samples.len() as f64 / FS);
+
+        // Let the frame play out and the tail arrive
+        std::thread::sleep(Duration::from_secs_f64(session.queued_seconds() + TAIL));
+
+        for alert in session.take_health_alerts() {
+            println!("    Input health: {:?}", alert);
+        }
+
+        let (capture_start, captured) = session.take_capture();
+        let rx = Tensor::<Backend, 1>::from_floats(captured.as_slice(), &device);
+
+        match synchronize_signal_with_config::<Backend>(&device, &rx, &config) {
+            Some(sync) => {
+                let detected = capture_start + sync.metrics.position as f64 / FS;
+                match session.match_rx(detected) {
+                    Some(m) => println!("    Preamble of #{} after {:.1} ms", m.seq, m.latency_s * 1e3),
+                    None => println!("    Preamble at {:.3} s matches no transmission", detected),
+                }
+
+                // A frame cut off by the capture window decodes from erasures
+                let llrs = demodulate_fhdpsk_soft_erasures_with_config::<Backend>(&device, &rx, true, &config, CODE_N);
+                let codeword = deinterleave_gpu::<Backend>(&device, &llrs, config.interleaver_columns());
+                let bits = decoder.decode_scl_gpu::<Backend>(&device, &codeword, 8).swap_remove(0);
+                match parse_frame(&pack_bits(&bits)) {
+                    Ok(bytes) if bytes.starts_with(payload.as_bytes()) => {
+                        println!("    ✓ Decoded {:?}", payload);
+                        decoded += 1;
+                    }
+                    Ok(bytes) => println!("    ✗ Decoded {:?}", String::from_utf8_lossy(&bytes)),
+                    Err(e) => println!("    ✗ Frame error: {:?}", e),
+                }
+            }
+            None => println!("    No preamble in {:.1} s of capture", captured.len() as f64 / FS),
+        }
+
+        for seq in session.expire() {
+            println!("    #{} lost", seq);
+            lost += 1;
+        }
+    }
+
+    println!("\n=== {} frames: {} decoded, {} unheard ===", frames, decoded, lost);
+    if let Some((mean, std)) = session.latency_stats() {
+        println!("Path latency {:.1} ms ± {:.1} ms", mean * 1e3, std * 1e3);
+    }
+}
@@ -5,7 +5,8 @@ use bachmodem::{
     write_wav, 
     watterson::WattersonChannel,
     repetition::{TimeSlotConfig, generate_repetition_transmission},
-    wavelet::FS
+    wavelet::FS,
+    modem_rng::{gaussian_noise, RngStream, SimSeed},
 };
 use std::path::Path;
 
@@ -38,11 +39,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Create noise tensor
     let signal_len = signal.dims()[0];
-    let noise = Tensor::<Wgpu, 1>::random(
-        [signal_len], 
-        burn::tensor::Distribution::Normal(0.0, 1.0), 
-        &device
-    );
+    let seed = SimSeed(0);
+    let mut noise_rng = seed.rng(RngStream::Noise);
+    let noise = gaussian_noise::<Wgpu, _>(&device, signal_len, 1.0, &mut noise_rng);
     
     // Calculate signal power
     let signal_power = signal.clone().powf_scalar(2.0).mean().into_scalar().elem::<f32>();
@@ -59,7 +58,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Apply Watterson Channel (Mild)
     let channel = WattersonChannel::gentle();
-    let faded_signal = channel.apply::<Wgpu>(&device, &signal);
+    let faded_signal = channel.apply_with_rng::<Wgpu, _>(&device, &signal, &mut seed.rng(RngStream::Fading));
     
     // Add noise
     let noisy_signal = faded_signal + (noise * noise_scale);
@@ -69,11 +68,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let start_delay_sec = 3.0; // Fixed for reproducibility, or use rand
     let start_delay_samples = (start_delay_sec * FS) as usize;
     
-    let initial_noise = Tensor::<Wgpu, 1>::random(
-        [start_delay_samples], 
-        burn::tensor::Distribution::Normal(0.0, 1.0), 
-        &device
-    ) * noise_scale;
+    let initial_noise = gaussian_noise::<Wgpu, _>(&device, start_delay_samples, 1.0, &mut noise_rng) * noise_scale;
     
     let final_signal = Tensor::cat(vec![initial_noise, noisy_signal], 0);
     
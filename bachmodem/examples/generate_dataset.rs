@@ -0,0 +1,42 @@
+//! Generate a simulated channel dataset
+//!
+//! Writes shards of received slots with their transmitted bits, payloads
+//! and channel metadata (see `bachmodem::dataset`).
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --example generate_dataset -- [examples] [per_shard] [dir] [profile]
+//! cargo run --release -p bachmodem --features export --example generate_dataset   # .safetensors shards
+//! ```
+
+use bachmodem::{DatasetConfig, DatasetGenerator, ModemConfig, ShardFormat, write_dataset_shards};
+use burn::backend::Wgpu;
+
+type Backend = Wgpu;
+
+fn main() {
+    let device = Default::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let num_examples: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(1000);
+    let per_shard: usize = args.get(1).and_then(|a| a.parse().ok()).unwrap_or(100);
+    let dir = args.get(2).cloned().unwrap_or_else(|| "bachmodem_dataset".to_string());
+    let modem = match args.get(3) {
+        Some(name) => ModemConfig::profile(name).expect("unknown profile"),
+        None => ModemConfig::default(),
+    };
+
+    #[cfg(feature = "export")]
+    let format = ShardFormat::Safetensors;
+    #[cfg(not(feature = "export"))]
+    let format = ShardFormat::Npy;
+
+    let config = DatasetConfig { modem, ..Default::default() };
+    println!("Generating {} examples ({} per shard), SNR {:?} dB, channels {:?}",
+             num_examples, per_shard, config.snr_db, config.channels);
+
+    let mut generator = DatasetGenerator::<Backend>::new(&device, config);
+    println!("Slot length: {} samples", generator.slot_samples());
+
+    let shards = write_dataset_shards(&mut generator, &dir, num_examples, per_shard, format)
+        .expect("Failed to write dataset");
+    println!("\nWrote {} shards ({:?}) to {}/", shards, format, dir);
+}
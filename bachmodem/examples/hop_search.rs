@@ -0,0 +1,41 @@
+/// Hopping Pattern Search
+///
+/// Anneals hopping themes for a tone alphabet from several seeds and prints
+/// the best ones with their cost terms next to the built-in pattern, as Rust
+/// arrays ready for `ModemConfig::with_hopping_pattern`.
+///
+/// Usage: cargo run --release --example hop_search [tones] [target_separation_hz] [seeds] [iterations]
+
+use bachmodem::{anneal_hopping_pattern, hopping_pattern_cost, AnnealConfig, HopCost, HopCostConfig, ModemConfig};
+
+fn describe(cost: &HopCost) -> String {
+    format!("cost {:6.3} (separation {:.3}, melody {:.2}, {} forbidden)", cost.total, cost.separation, cost.melody, cost.forbidden)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let tones: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(16);
+    let cost = HopCostConfig {
+        target_separation_hz: args.next().and_then(|a| a.parse().ok()).unwrap_or(250.0),
+        ..HopCostConfig::default()
+    };
+    let seeds: u64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(8);
+    let iterations: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(AnnealConfig::default().iterations);
+
+    let config = ModemConfig::new(tones);
+    let frequencies = config.frequencies();
+    let builtin = config.hopping_pattern();
+    println!("{} tones, separation target {} Hz, {} seeds x {} iterations\n", tones, cost.target_separation_hz, seeds, iterations);
+    println!("built-in  {:?}\n          {}\n", builtin, describe(&hopping_pattern_cost(&builtin, &frequencies, &cost)));
+
+    let mut results: Vec<_> = (0..seeds)
+        .map(|seed| anneal_hopping_pattern(&frequencies, &cost, &AnnealConfig { iterations, seed, ..AnnealConfig::default() }))
+        .collect();
+    results.sort_by(|a, b| a.cost.total.total_cmp(&b.cost.total));
+    results.dedup_by(|a, b| a.pattern == b.pattern);
+
+    for (rank, result) in results.iter().enumerate() {
+        let pattern: Vec<String> = result.pattern.iter().map(|t| t.to_string()).collect();
+        println!("theme {}   [{}]\n          {}, {} swaps accepted", rank + 1, pattern.join(", "), describe(&result.cost), result.accepted);
+    }
+}
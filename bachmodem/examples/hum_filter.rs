@@ -0,0 +1,68 @@
+/// Hum Filter
+///
+/// Measures what the power-line comb in the receiver front end buys: one
+/// frame at the given SNR with ground-loop buzz added (fundamental plus odd
+/// and even harmonics, as strong as the frame), demodulated without and with
+/// the comb (`front_end_report`), then decoded through the front end.
+///
+/// Usage: cargo run --release --example hum_filter [snr_db] [base_hz] [harmonics]
+
+use bachmodem::{
+    decode_capture, front_end_report, gaussian_noise, BachTransmitter, HumComb, ModemConfig, ReceiverPoolConfig,
+    ReceiverState, SplitMix64, FS,
+};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::tensor::{ElementConversion, Tensor};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const PAYLOAD: &[u8] = b"Hum test";
+
+fn main() {
+    let device = Default::default();
+    let mut args = std::env::args().skip(1);
+    let snr_db: f32 = args.next().and_then(|a| a.parse().ok()).unwrap_or(-10.0);
+    let base_hz: f64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(50.0);
+    let harmonics: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(20);
+
+    let frame = BachTransmitter::new(ModemConfig::default()).build::<Backend>(&device, PAYLOAD).unwrap();
+    let len = frame.dims()[0];
+    let frame_power: f32 = frame.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (frame_power / 10f32.powf(snr_db / 10.0)).sqrt();
+
+    // Buzz: harmonic k at 1/k amplitude, total power equal to the frame's
+    let buzz: Vec<f64> = (0..len)
+        .map(|t| {
+            let t = t as f64 / FS;
+            (1..=harmonics)
+                .map(|k| (2.0 * std::f64::consts::PI * k as f64 * base_hz * t + k as f64).sin() / k as f64)
+                .sum()
+        })
+        .collect();
+    let buzz_power = buzz.iter().map(|x| x * x).sum::<f64>() / len as f64;
+    let scale = (frame_power as f64 / buzz_power).sqrt();
+    let buzz: Vec<f32> = buzz.iter().map(|x| (x * scale) as f32).collect();
+
+    let received = frame
+        + Tensor::<Backend, 1>::from_floats(buzz.as_slice(), &device)
+        + gaussian_noise::<Backend, _>(&device, len, noise_std, &mut SplitMix64::new(0));
+    println!("{:.1} s frame at {} dB with {} Hz buzz ({} harmonics, 0 dB to the frame)", len as f64 / FS, snr_db, base_hz, harmonics);
+
+    let mut config = ReceiverPoolConfig::default();
+    config.front_end.hum = Some(HumComb { harmonics, ..HumComb::mains(base_hz) });
+    let mut state = ReceiverState::<Backend>::default();
+
+    match front_end_report(&device, &state, &config, &received) {
+        Ok(report) => println!(
+            "blind SNR {:+.1} dB without the comb, {:+.1} dB with it ({:+.1} dB)",
+            report.before_db, report.after_db, report.improvement_db(),
+        ),
+        Err(e) => println!("no report: {:?}", e),
+    }
+
+    match decode_capture(&device, &mut state, &config, &received) {
+        Ok(frame) => println!("decoded {:?}", String::from_utf8_lossy(&frame.payload).trim_end_matches('\0')),
+        Err(e) => println!("decode failed: {:?}", e),
+    }
+}
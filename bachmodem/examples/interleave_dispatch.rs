@@ -0,0 +1,82 @@
+/// Interleaver Dispatch Benchmark
+///
+/// Times the CPU deinterleave loop against the GPU transpose for growing
+/// LLR batches, both for host-resident data (upload included) and for data
+/// already on the device, and reports the crossover used to pick
+/// `InterleaveDispatch::gpu_min_len`.
+///
+/// Usage: cargo run --release --example interleave_dispatch
+
+use bachmodem::{deinterleave, deinterleave_gpu, InterleaveDispatch};
+use burn::backend::Wgpu;
+use burn::tensor::{Tensor, backend::Backend};
+use std::time::{Duration, Instant};
+
+type MyBackend = Wgpu;
+
+const NUM_COLS: usize = 16;
+const ITERATIONS: usize = 20;
+
+/// Median wall time of `f` over ITERATIONS runs (after one warm-up)
+fn median_time(mut f: impl FnMut()) -> Duration {
+    f();
+    let mut times: Vec<Duration> = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[ITERATIONS / 2]
+}
+
+fn main() {
+    let device = Default::default();
+    let sizes = [256, 1024, 4096, 16384, 65536, 262144, 1048576];
+
+    println!("=== Interleaver dispatch: CPU loop vs GPU transpose ({} columns) ===\n", NUM_COLS);
+    println!("{:>9} {:>12} {:>14} {:>14}", "LLRs", "CPU (host)", "GPU (upload)", "GPU (device)");
+
+    let mut crossover = None;
+
+    for &n in &sizes {
+        let llrs: Vec<f32> = (0..n).map(|i| (i % 7) as f32 - 3.0).collect();
+        let resident = Tensor::<MyBackend, 1>::from_floats(llrs.as_slice(), &device);
+        MyBackend::sync(&device).unwrap();
+
+        let cpu = median_time(|| {
+            std::hint::black_box(deinterleave(&llrs, NUM_COLS));
+        });
+
+        // Result stays on the device, as it does for the GPU decoders
+        let upload = median_time(|| {
+            let t = Tensor::<MyBackend, 1>::from_floats(llrs.as_slice(), &device);
+            let _ = deinterleave_gpu(&device, &t, NUM_COLS);
+            MyBackend::sync(&device).unwrap();
+        });
+
+        let on_device = median_time(|| {
+            let _ = deinterleave_gpu(&device, &resident, NUM_COLS);
+            MyBackend::sync(&device).unwrap();
+        });
+
+        println!("{:>9} {:>10.1}µs {:>12.1}µs {:>12.1}µs",
+                 n, cpu.as_secs_f64() * 1e6, upload.as_secs_f64() * 1e6, on_device.as_secs_f64() * 1e6);
+
+        if crossover.is_none() && upload < cpu {
+            crossover = Some(n);
+        }
+    }
+
+    println!();
+    match crossover {
+        Some(n) => println!("Host data: GPU wins from ~{} LLRs", n),
+        None => println!("Host data: CPU wins at every measured size"),
+    }
+    println!("Default gpu_min_len: {}", InterleaveDispatch::default().gpu_min_len);
+    match InterleaveDispatch::calibrate::<MyBackend>(&device, NUM_COLS).gpu_min_len {
+        usize::MAX => println!("Calibrated gpu_min_len: never (host data stays on the CPU)"),
+        len => println!("Calibrated gpu_min_len: {}", len),
+    }
+}
@@ -2,6 +2,7 @@ use bachmodem::{
     modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_soft,
     PolarCode, PolarCodeBP,
     interleave, deinterleave_gpu,
+    gaussian_noise, SplitMix64,
 };
 use burn::tensor::Tensor;
 use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
@@ -65,7 +66,7 @@ fn main() {
     println!("Signal length: {} samples", signal.dims()[0]);
     
     // Add minimal noise (high SNR)
-    let noise = Tensor::random(signal.shape(), burn::tensor::Distribution::Normal(0.0, 0.01), &device);
+    let noise = gaussian_noise(&device, signal.dims()[0], 0.01, &mut SplitMix64::new(0));
     let noisy_signal = signal + noise;
     
     // Demodulate
@@ -0,0 +1,114 @@
+//! Headless monitor daemon with the HTTP/WebSocket control API
+//!
+//! Simulates a monitoring station: one second of band noise per capture
+//! block goes through the noise-floor and input-health monitor, and messages
+//! queued with `POST /transmit` are sent over a -15 dB AWGN loopback and
+//! decoded with the current profile. Everything is visible through the API:
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --example monitor_daemon --features http -- [addr]
+//! curl -X POST localhost:8073/monitor/start
+//! curl -X POST -H 'Content-Type: application/json' -d '{"text": "CQ DE N0CALL"}' localhost:8073/transmit
+//! curl localhost:8073/status
+//! curl 'localhost:8073/decodes?since=0'
+//! ```
+
+use bachmodem::{
+    AsyncReceiver, BachTransmitter, CaptureBlock, DaemonControl, DecodeSource, ModemConfig, ModemRng,
+    ReceiverPoolConfig, ReceiverState, SplitMix64, gaussian_noise, monitor,
+};
+use bachmodem::http_api::serve;
+use bachmodem::wavelet::FS;
+use burn::tensor::ElementConversion;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+type Device = <Backend as burn::tensor::backend::Backend>::Device;
+
+const NOISE_STD: f32 = 0.01;
+const LOOPBACK_SNR_DB: f32 = -15.0;
+
+fn main() {
+    let addr = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:8073".to_string());
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    runtime.block_on(run(addr));
+}
+
+async fn run(addr: String) {
+    let device: Device = Default::default();
+    let control = DaemonControl::new("standard").unwrap();
+
+    let listener = tokio::net::TcpListener::bind(&addr).await.expect("bind");
+    println!("Monitor daemon on http://{} (stopped; POST /monitor/start)", addr);
+    tokio::spawn(serve(listener, control.clone()));
+
+    // Capture blocks -> monitor -> /status
+    let (block_tx, block_rx) = mpsc::channel(4);
+    let (mut updates, _) = monitor::<Backend>(device.clone(), ReceiverState::default(), block_rx);
+    let status = control.clone();
+    tokio::spawn(async move {
+        while let Some(update) = updates.recv().await {
+            status.update_monitor(update);
+        }
+    });
+
+    let (mut profile, mut config) = control.config();
+    let mut receiver = AsyncReceiver::<Backend>::new(device.clone(), pool_config(&config), ReceiverState::default());
+    let mut elapsed = 0.0;
+    let mut rng = SplitMix64::new(0);
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        if !control.is_running() {
+            continue;
+        }
+
+        let (current, current_config) = control.config();
+        if current != profile {
+            // Keep the warmed-up estimates across the profile change
+            let state = receiver.state().await;
+            (profile, config) = (current, current_config);
+            receiver = AsyncReceiver::new(device.clone(), pool_config(&config), state);
+            println!("Profile: {}", profile);
+        }
+
+        let noise = noise_block(&device, &mut rng);
+        let _ = block_tx.send(CaptureBlock { samples: noise, timestamp: elapsed }).await;
+        elapsed += 1.0;
+
+        for message in control.take_transmissions() {
+            let tx = BachTransmitter::new(ModemConfig::profile(&message.profile).unwrap());
+            for payload in message.payloads {
+                let tx = tx.clone();
+                let device = device.clone();
+                let seed = rng.seed();
+                let capture = tokio::task::spawn_blocking(move || loopback(&device, &tx, &payload, seed)).await.unwrap();
+
+                let frame = receiver.decode(DecodeSource::Samples(capture)).await;
+                let entry = control.record_decode(&frame);
+                println!("Decode #{}: {:?} {:?}", entry.seq, entry.text, entry.error);
+            }
+        }
+    }
+}
+
+fn pool_config(config: &ModemConfig) -> ReceiverPoolConfig {
+    ReceiverPoolConfig { modem: config.clone(), ..Default::default() }
+}
+
+fn noise_block(device: &Device, rng: &mut SplitMix64) -> Vec<f32> {
+    gaussian_noise::<Backend, _>(device, FS as usize, NOISE_STD, rng)
+        .into_data().to_vec().unwrap()
+}
+
+/// Transmission through AWGN at `LOOPBACK_SNR_DB`
+fn loopback(device: &Device, tx: &BachTransmitter, payload: &[u8], seed: u64) -> Vec<f32> {
+    let signal = tx.build::<Backend>(device, payload).unwrap();
+    let power: f32 = signal.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (power / 10f32.powf(LOOPBACK_SNR_DB / 10.0)).sqrt();
+    let rx = signal.clone() + gaussian_noise(device, signal.dims()[0], noise_std, &mut SplitMix64::new(seed));
+    rx.into_data().to_vec().unwrap()
+}
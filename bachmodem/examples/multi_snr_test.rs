@@ -1,13 +1,16 @@
-use bachmodem::{modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_ex, write_wav, WattersonChannel};
+use bachmodem::{
+    modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_ex, write_wav, gaussian_noise, ModemRng, RngStream, SimSeed,
+    WattersonChannel,
+};
 use burn::backend::Wgpu;
-use burn::tensor::{Tensor, Distribution};
-use rand::Rng;
+use burn::tensor::Tensor;
 
 type Backend = Wgpu;
 
 fn test_snr(target_snr_db: f32, message: &str, trial: usize, use_fading: bool) -> (bool, f64) {
     let device = Default::default();
-    let mut rng = rand::thread_rng();
+    let seed = SimSeed(trial as u64);
+    let mut rng = seed.rng(RngStream::Scenario);
     
     // Generate clean signal
     let clean_signal = modulate_fhdpsk_with_flourishes::<Backend>(
@@ -26,21 +29,17 @@ fn test_snr(target_snr_db: f32, message: &str, trial: usize, use_fading: bool) -
     let noise_std = noise_power.sqrt();
     
     // Random offset: 5-15 seconds
-    let offset_seconds = rng.gen_range(5.0..15.0);
+    let offset_seconds = 5.0 + 10.0 * rng.uniform();
     let offset_samples = (offset_seconds * 8000.0) as usize;
     
     // Create noisy channel
     let total_len = offset_samples + signal_len + 80000;
-    let noise = Tensor::<Backend, 1>::random(
-        [total_len],
-        Distribution::Normal(0.0, noise_std as f64),
-        &device,
-    );
+    let noise = gaussian_noise::<Backend, _>(&device, total_len, noise_std, &mut seed.rng(RngStream::Noise));
     
     // Apply Watterson fading if requested
     let faded_signal = if use_fading {
         let channel = WattersonChannel::moderate();
-        channel.apply::<Backend>(&device, &clean_signal)
+        channel.apply_with_rng::<Backend, _>(&device, &clean_signal, &mut seed.rng(RngStream::Fading))
     } else {
         clean_signal.clone()
     };
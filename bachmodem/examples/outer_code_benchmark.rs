@@ -0,0 +1,90 @@
+//! Polar-only vs concatenated polar + Reed-Solomon
+//!
+//! Sends the same multi-frame message with the `standard` profile (every
+//! frame must decode) and the `robust` profile (16-byte RS outer code across
+//! the frames) through the Watterson channel at a range of SNRs, and reports
+//! message success, frames lost and bytes repaired by the outer code.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --features channel-sim --example outer_code_benchmark -- [trials]
+//! ```
+
+use bachmodem::{
+    BachTransmitter, ChaCha20Rng, ModemConfig, PolarCodeSCL, RngStream, SimSeed, WattersonChannel, deinterleave_gpu,
+    demodulate_fhdpsk_soft_erasures_with_config, gaussian_noise, pack_bits, parse_frame,
+};
+use bachmodem::transmitter::{CODE_K, CODE_N};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::tensor::{ElementConversion, Tensor};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const MESSAGE: &[u8] = b"CQ DE BACH73 QTH JN58 RST 559 BWV 1080";
+const SNRS_DB: [f32; 5] = [-20.0, -18.0, -16.0, -14.0, -12.0];
+
+fn main() {
+    let device = Default::default();
+    let trials: usize = std::env::args().nth(1).and_then(|a| a.parse().ok()).unwrap_or(10);
+
+    let channel = WattersonChannel::moderate();
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
+    let decoder = PolarCodeSCL::new(CODE_N, CODE_K);
+    let profiles = ["standard", "robust"];
+
+    println!("Message: {} bytes, {} trials per point, moderate Watterson channel", MESSAGE.len(), trials);
+    for name in profiles {
+        let tx = BachTransmitter::new(ModemConfig::profile(name).unwrap());
+        println!("  {:>8}: {} frames", name, tx.message_payloads(MESSAGE).unwrap().len());
+    }
+
+    println!("\n{:>7} {:>8} {:>10} {:>12} {:>14}", "SNR dB", "profile", "messages", "frames lost", "bytes repaired");
+    for snr_db in SNRS_DB {
+        for name in profiles {
+            let tx = BachTransmitter::new(ModemConfig::profile(name).unwrap());
+            let outer = tx.config.outer_code();
+            let (mut ok, mut lost, mut frames, mut repaired) = (0, 0, 0, 0);
+
+            for _ in 0..trials {
+                let received: Vec<Option<Vec<u8>>> = tx.build_message::<Backend>(&device, MESSAGE).unwrap()
+                    .iter()
+                    .map(|signal| receive(&device, &tx.config, &decoder, &channel, signal, snr_db, &mut rng))
+                    .collect();
+                frames += received.len();
+                lost += received.iter().filter(|f| f.is_none()).count();
+
+                let message = match &outer {
+                    Some(outer) => outer.decode_message(&received).ok().map(|d| {
+                        repaired += d.corrected;
+                        d.message
+                    }),
+                    None => received.into_iter().collect::<Option<Vec<_>>>().map(|f| f.concat()),
+                };
+                ok += matches!(message, Some(m) if m.starts_with(MESSAGE)) as usize;
+            }
+
+            println!("{:>7.1} {:>8} {:>7}/{:<2} {:>8}/{:<3} {:>14}", snr_db, name, ok, trials, lost, frames, repaired);
+        }
+    }
+}
+
+/// One frame through fading and AWGN; None if the header didn't parse
+fn receive(
+    device: &<Backend as burn::tensor::backend::Backend>::Device,
+    config: &ModemConfig,
+    decoder: &PolarCodeSCL,
+    channel: &WattersonChannel,
+    signal: &Tensor<Backend, 1>,
+    snr_db: f32,
+    rng: &mut ChaCha20Rng,
+) -> Option<Vec<u8>> {
+    let faded = channel.apply_with_rng::<Backend, _>(device, signal, rng);
+    let signal_power: f32 = faded.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+    let rx = faded.clone() + gaussian_noise(device, faded.dims()[0], noise_std, rng);
+
+    let llrs = demodulate_fhdpsk_soft_erasures_with_config::<Backend>(device, &rx, true, config, CODE_N);
+    let codeword = deinterleave_gpu::<Backend>(device, &llrs, config.interleaver_columns());
+    let bits = decoder.decode_scl_gpu::<Backend>(device, &codeword, 8).swap_remove(0);
+    parse_frame(&pack_bits(&bits)).ok()
+}
@@ -0,0 +1,175 @@
+//! Visual receive console
+//!
+//! Scrolling waterfall of a WAV file (played in real time) or of the live
+//! input device, drawn with the fft_gpu waterfall (batched FFT kernel). Every
+//! second the last ten seconds are searched for BachModem preambles with
+//! `find_preamble_peaks`; each new detection is marked on the waterfall row
+//! where its preamble starts and printed. A peak is only taken once the whole
+//! preamble and one more sweep cycle are in the buffer, then moved to the
+//! true start with `resolve_sync_ambiguity` (a preamble still arriving peaks
+//! one cycle early).
+//!
+//! Data symbols of a loud frame correlate with the preamble too and can pass
+//! the score threshold: peaks below `STRONG_FACTOR` times the threshold are
+//! marked in dark green.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --example receive_console -- capture.wav [profile] [--min-score 100]
+//! cargo run --release -p bachmodem --example receive_console --features audio -- --live [profile]
+//! ```
+
+use bachmodem::{
+    find_preamble_peaks, preamble_cycle_samples, resample_linear, resolve_sync_ambiguity,
+    ModemConfig, FS, MULTI_SYNC_MIN_SCORE,
+};
+use bachmodem::wavelet::generate_bach_preamble_with_config;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::tensor::Tensor;
+use fft_gpu::waterfall::{Waterfall, WaterfallConfig};
+use minifb::{Key, Scale, Window, WindowOptions};
+use std::time::{Duration, Instant};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+/// Audio pushed per display update (seconds)
+const BLOCK_SECONDS: f64 = 0.1;
+/// Preamble search interval and span (seconds)
+const DETECT_EVERY: f64 = 1.0;
+const DETECT_SPAN: f64 = 10.0;
+const MAX_PEAKS: usize = 4;
+const SYNC_COLOR: u32 = 0x00FF00;
+const WEAK_SYNC_COLOR: u32 = 0x006000;
+/// Score (relative to the threshold) above which a peak is drawn bright
+const STRONG_FACTOR: f32 = 10.0;
+
+/// Where the audio comes from
+enum Input {
+    /// Whole file at `FS`, handed out in real time
+    File { samples: Vec<f32>, next: usize, started: Instant },
+    #[cfg(feature = "audio")]
+    Live(bachmodem::DuplexSession),
+}
+
+impl Input {
+    fn open(arg: &str) -> Input {
+        if arg == "--live" {
+            #[cfg(feature = "audio")]
+            {
+                let settings = bachmodem::AudioSettings::load("bachmodem-audio.conf").expect("bad audio settings");
+                return Input::Live(bachmodem::DuplexSession::start(&settings, 1.0).expect("cannot open audio devices"));
+            }
+            #[cfg(not(feature = "audio"))]
+            panic!("--live needs the `audio` feature");
+        }
+
+        let mut reader = hound::WavReader::open(arg).expect("cannot open WAV file");
+        let spec = reader.spec();
+        let interleaved: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Int => reader.samples::<i32>()
+                .map(|s| s.unwrap() as f32 / (1u32 << (spec.bits_per_sample - 1)) as f32)
+                .collect(),
+            hound::SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap()).collect(),
+        };
+        let first_channel: Vec<f32> = interleaved.iter().step_by(spec.channels as usize).copied().collect();
+        let samples = resample_linear(&first_channel, spec.sample_rate as f64, FS);
+        println!("{}: {:.1} s at {} Hz", arg, samples.len() as f64 / FS, spec.sample_rate);
+
+        Input::File { samples, next: 0, started: Instant::now() }
+    }
+
+    /// Samples that arrived since the last call (empty at the end of a file)
+    fn take(&mut self) -> Vec<f32> {
+        match self {
+            Input::File { samples, next, started } => {
+                let due = ((started.elapsed().as_secs_f64() * FS) as usize).min(samples.len());
+                let block = samples[*next..due.max(*next)].to_vec();
+                *next = due.max(*next);
+                block
+            }
+            #[cfg(feature = "audio")]
+            Input::Live(session) => session.take_capture().1,
+        }
+    }
+}
+
+fn main() {
+    let device = Default::default();
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut min_score = MULTI_SYNC_MIN_SCORE;
+    if let Some(i) = args.iter().position(|a| a == "--min-score") {
+        min_score = args.get(i + 1).and_then(|v| v.parse().ok()).expect("--min-score needs a number");
+        args.drain(i..i + 2);
+    }
+    let Some(source) = args.first() else {
+        eprintln!("usage: receive_console <capture.wav | --live> [profile] [--min-score S]");
+        return;
+    };
+    let config = match args.get(1) {
+        Some(name) => ModemConfig::profile(name).expect("unknown profile"),
+        None => ModemConfig::default(),
+    };
+
+    let mut input = Input::open(source);
+    let preamble = generate_bach_preamble_with_config::<Backend>(&device, &config);
+    let preamble_len = preamble.dims()[0];
+    let cycle = preamble_cycle_samples(&config);
+
+    let waterfall_config = WaterfallConfig::default();
+    let bin_hz = FS / waterfall_config.fft_len as f64;
+    let mut waterfall = Waterfall::<Backend>::new(&device, waterfall_config);
+    let (width, height) = (waterfall.width(), waterfall.height());
+    println!("Waterfall: 0-{:.0} Hz, {:.1} Hz per column", FS / 2.0, bin_hz);
+
+    let mut window = Window::new(
+        "BachModem receive console",
+        width,
+        height,
+        WindowOptions { scale: Scale::X2, ..WindowOptions::default() },
+    ).expect("cannot open window");
+
+    // Sliding search span and the stream position of its first sample
+    let span = (DETECT_SPAN * FS) as usize;
+    let mut history: Vec<f32> = Vec::with_capacity(2 * span);
+    let mut history_start = 0u64;
+    let mut last_search = Instant::now();
+    let mut reported: Vec<u64> = Vec::new();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let block = input.take();
+        waterfall.push(&block);
+        history.extend_from_slice(&block);
+        if history.len() > span {
+            let excess = history.len() - span;
+            history.drain(..excess);
+            history_start += excess as u64;
+        }
+
+        if last_search.elapsed().as_secs_f64() >= DETECT_EVERY && history.len() >= preamble_len {
+            last_search = Instant::now();
+            let signal = Tensor::<Backend, 1>::from_floats(history.as_slice(), &device);
+
+            // Later peaks may belong to a preamble that is still arriving
+            let complete = history.len().saturating_sub(preamble_len + cycle);
+
+            for peak in find_preamble_peaks::<Backend>(&device, &signal, &config, MAX_PEAKS) {
+                if peak.score < min_score || peak.position > complete {
+                    continue;
+                }
+                let data_start = resolve_sync_ambiguity(&device, &signal, &preamble, peak.position, &config);
+                let position = (history_start + data_start as u64).saturating_sub(preamble_len as u64);
+                if reported.iter().any(|&p| p.abs_diff(position) < preamble_len as u64) {
+                    continue;
+                }
+                reported.push(position);
+                let strong = peak.score >= STRONG_FACTOR * min_score;
+                waterfall.mark(position, if strong { SYNC_COLOR } else { WEAK_SYNC_COLOR });
+                println!("sync at {:7.2} s  score {:>8.0}{}", position as f64 / FS, peak.score, if strong { "" } else { "  (weak)" });
+            }
+            reported.retain(|&p| p + span as u64 >= history_start);
+        }
+
+        window.update_with_buffer(&waterfall.render(), width, height).unwrap();
+        std::thread::sleep(Duration::from_secs_f64(BLOCK_SECONDS));
+    }
+}
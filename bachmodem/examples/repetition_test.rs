@@ -1,11 +1,10 @@
 use bachmodem::{
     modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_ex, write_wav, 
     TimeSlotConfig, generate_repetition_transmission, combine_decoded_copies, DecodedCopy,
-    synchronize_signal, WattersonChannel
+    synchronize_signal, WattersonChannel, gaussian_noise, ModemRng, RngStream, SimSeed
 };
 use burn::backend::Wgpu;
-use burn::tensor::{Tensor, Distribution};
-use rand::Rng;
+use burn::tensor::Tensor;
 
 type Backend = Wgpu;
 
@@ -16,7 +15,8 @@ fn main() {
     println!("=======================================================\n");
     
     let device = Default::default();
-    let mut rng = rand::thread_rng();
+    let seed = SimSeed(0);
+    let mut rng = seed.rng(RngStream::Scenario);
     
     // Test configuration
     let test_message = "BachModem 73!";
@@ -65,17 +65,13 @@ fn main() {
     let faded_signal = if use_fading {
         println!("Applying Watterson multipath fading...");
         let channel = WattersonChannel::moderate();
-        channel.apply::<Backend>(&device, &clean_signal)
+        channel.apply_with_rng::<Backend, _>(&device, &clean_signal, &mut seed.rng(RngStream::Fading))
     } else {
         clean_signal.clone()
     };
     
     // Add AWGN noise
-    let noise = Tensor::<Backend, 1>::random(
-        [signal_len],
-        Distribution::Normal(0.0, noise_std as f64),
-        &device,
-    );
+    let noise = gaussian_noise::<Backend, _>(&device, signal_len, noise_std, &mut seed.rng(RngStream::Noise));
     
     // Combine signal + noise
     let mut noisy_signal_data = noise.to_data();
@@ -135,8 +131,9 @@ fn main() {
         }
         
         // Estimate SNR from preamble correlation (simplified)
-        let snr_estimate = if rep_idx % 2 == 0 { -25.0 + rng.gen_range(-2.0..2.0) } else { -25.0 + rng.gen_range(-5.0..5.0) };
-        let correlation = 0.5 + rng.gen_range(-0.2..0.2);
+        let spread = if rep_idx % 2 == 0 { 2.0 } else { 5.0 };
+        let snr_estimate = -25.0 + (spread * (2.0 * rng.uniform() - 1.0)) as f32;
+        let correlation = 0.5 + (0.2 * (2.0 * rng.uniform() - 1.0)) as f32;
         
         let copy = DecodedCopy {
             repetition: rep_idx,
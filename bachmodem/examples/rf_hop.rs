@@ -0,0 +1,54 @@
+/// RF Hop Plan
+///
+/// Prints the dial schedule of a repetition transmission hopping across
+/// several SSB channels. With a rigctld address the receiver side runs in
+/// real time: the rig follows the plan, retuning in every listening gap.
+///
+/// Usage: cargo run --release --example rf_hop [repetitions] [seed] [rigctld host:port]
+
+use bachmodem::{RfHopPlan, RigCtl, TimeSlotConfig};
+use std::time::{Duration, Instant};
+
+/// FT8-adjacent USB dial frequencies on 40/30/20/17 m
+const CHANNELS: [u64; 4] = [7_078_000, 10_140_000, 14_078_000, 18_104_000];
+
+/// Rig settle time before a slot (relays, AGC)
+const SETTLE: f64 = 2.0;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let repetitions: usize = args.first().and_then(|s| s.parse().ok()).unwrap_or(8);
+    let seed: u64 = args.get(1).and_then(|s| s.parse().ok()).unwrap_or(73);
+
+    let slots = TimeSlotConfig::new(13, repetitions, 10.0);
+    let plan = RfHopPlan::new(CHANNELS.to_vec()).with_seed(seed);
+
+    println!("=== RF Hop Plan: {} repetitions over {} channels (seed {}) ===\n", repetitions, plan.num_channels(), seed);
+    println!("{:>5} {:>10} {:>10} {:>12}", "slot", "retune (s)", "start (s)", "dial (kHz)");
+    for retune in plan.retunes(&slots, SETTLE) {
+        println!("{:>5} {:>10.1} {:>10.1} {:>12.3}",
+                 retune.slot, retune.at_s, slots.slot_starts[retune.slot], retune.dial_hz as f64 / 1e3);
+    }
+
+    let Some(addr) = args.get(2) else { return };
+    let mut rig = RigCtl::connect(addr.as_str()).expect("cannot reach rigctld");
+    rig.set_mode("USB", 0).expect("cannot set mode");
+
+    println!("\nFollowing the plan on {} for {:.0} s", addr, slots.total_duration());
+    let origin = Instant::now();
+    let mut current = None;
+    loop {
+        let t = origin.elapsed().as_secs_f64();
+        if t > slots.total_duration() {
+            break;
+        }
+
+        let (slot, dial) = plan.dial_at(&slots, t);
+        if current != Some(dial) {
+            rig.set_frequency(dial).expect("retune failed");
+            println!("  {:7.1} s  slot {}  -> {:.3} kHz", t, slot, dial as f64 / 1e3);
+            current = Some(dial);
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+}
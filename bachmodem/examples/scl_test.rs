@@ -11,14 +11,15 @@ use bachmodem::{
     interleave, deinterleave, 
     PolarCode, soft_bits_to_llrs, compute_soft_bits,
     TimeSlotConfig, generate_repetition_transmission,
-    soft_combine_gpu, estimate_snr_from_correlation,
+    mrc_combine_gpu, fft_cross_correlation,
     RakeReceiver,
     modulate_fhdpsk_with_flourishes,
     deinterleave_gpu,
     FftBackend,
+    RngStream, SimSeed, gaussian_noise,
 };
 use burn::backend::wgpu::{CubeBackend, WgpuRuntime, WgpuDevice};
-use burn::tensor::{Tensor, Distribution, ElementConversion};
+use burn::tensor::{Tensor, ElementConversion};
 
 // Use raw CubeBackend to avoid Fusion wrapper which doesn't implement FftBackend yet
 type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
@@ -83,14 +84,11 @@ fn main() {
                  signal_power, noise_std, snr_db);
         
         let channel = WattersonChannel::moderate();
-        let faded_signal = channel.apply::<Backend>(&device, &clean_signal);
+        let seed = SimSeed(0);
+        let faded_signal = channel.apply_with_rng::<Backend, _>(&device, &clean_signal, &mut seed.rng(RngStream::Fading));
         println!("  ✓ Channel effects applied. Adding noise...");
         
-        let noise = Tensor::<Backend, 1>::random(
-            faded_signal.shape(),
-            Distribution::Normal(0.0, noise_std as f64),
-            &device,
-        );
+        let noise = gaussian_noise::<Backend, _>(&device, faded_signal.dims()[0], noise_std, &mut seed.rng(RngStream::Noise));
         
         let rx_signal = faded_signal + noise;
         println!("  ✓ Noise added. Starting synchronization...");
@@ -101,9 +99,10 @@ fn main() {
         let search_window = rx_signal.clone().slice([0..search_window_len]);
         
         let time_offset = match bachmodem::modulation::synchronize_signal::<Backend>(&device, &search_window) {
-            Some(pos) => {
-                println!("  ✓ Sync found at sample {} ({:.2}s)", pos, pos as f32 / 8000.0);
-                pos
+            Some(sync) => {
+                println!("  ✓ Sync found at sample {} ({:.2}s), corr {:.3}, SNR {:.1} dB",
+                         sync.metrics.position, sync.metrics.position as f32 / 8000.0, sync.metrics.correlation, sync.snr_estimate_db);
+                sync.metrics.position
             },
             None => {
                 println!("  ✗ Sync failed!");
@@ -113,7 +112,7 @@ fn main() {
         
         // 5. Process each repetition
         let mut all_llrs: Vec<Tensor<Backend, 1>> = Vec::with_capacity(num_reps);
-        let mut snr_estimates = Vec::with_capacity(num_reps);
+        let mut correlations: Vec<Tensor<Backend, 1>> = Vec::with_capacity(num_reps);
         
         let slot_duration_samples = (config.transmission_duration * 8000.0) as usize;
         let gap_samples = (config.listening_gap * 8000.0) as usize;
@@ -139,9 +138,16 @@ fn main() {
             // RAKE combining (paths already detected, just combine)
             let processed_signal = rake.combine_paths::<Backend>(&device, &slot_signal);
             
-            // Estimate SNR from correlation peak
-            let snr_est = 10.0; // Simplified
-            snr_estimates.push(snr_est);
+            // Preamble correlation around the expected start weights this slot in MRC
+            let corr_len = 2 * margin + preamble.dims()[0];
+            let corr_window = processed_signal.clone().slice([0..corr_len.min(processed_signal.dims()[0])]);
+            let corr_window = if corr_window.dims()[0] < corr_len {
+                let pad = Tensor::zeros([corr_len - corr_window.dims()[0]], &device);
+                Tensor::cat(vec![corr_window, pad], 0)
+            } else {
+                corr_window
+            };
+            correlations.push(fft_cross_correlation(&device, &corr_window, &preamble));
             
             // Demodulate to soft bits
             let llrs = bachmodem::modulation::demodulate_fhdpsk_soft::<Backend>(
@@ -170,7 +176,7 @@ fn main() {
             } else {
                 println!("    Rep {}/{}: Failed (got {} bits)", i+1, num_reps, llrs_len);
                 all_llrs.push(Tensor::zeros([256], &device));
-                // snr_estimates already pushed above
+                // correlation already pushed above
             }
         }
         
@@ -182,8 +188,8 @@ fn main() {
         // 6. Soft combining with SNR weighting
         println!("  Combining {} repetitions with MRC...", all_llrs.len());
         let llr_stack = Tensor::stack(all_llrs, 0); // Move instead of clone to free memory
-        let weights = Tensor::from_floats(snr_estimates.as_slice(), &device);
-        let combined_llrs = soft_combine_gpu(&llr_stack, &weights);
+        let corr_stack = Tensor::stack(correlations, 0);
+        let combined_llrs = mrc_combine_gpu(&llr_stack, &corr_stack, 50);
         
         // 7. Decode with SCL
         let combined_data = combined_llrs.to_data();
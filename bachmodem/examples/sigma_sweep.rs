@@ -0,0 +1,96 @@
+//! Wavelet shape sweep: BER vs Morlet width over the Watterson channel
+//!
+//! The symbol window holds `wavelet_sigmas` Gaussian widths of the Morlet
+//! pulse (default 6). Fewer sigmas give a longer, spectrally narrower pulse
+//! with more overlap between consecutive hops under delay spread; more
+//! sigmas give a shorter pulse with more leakage between neighbouring tones.
+//! This sweep sends frames of a profile at a range of widths and SNRs and
+//! reports the raw (pre-FEC) bit error rate, the frame success rate and the
+//! occupied bandwidth, so profiles can pick their shape.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --example sigma_sweep -- [profile] [trials]
+//! ```
+
+use bachmodem::{
+    BachTransmitter, ChaCha20Rng, ModemConfig, PolarCodeSCL, RngStream, SimSeed, WattersonChannel, deinterleave_gpu,
+    demodulate_fhdpsk_soft_erasures_with_config, encode_bits, gaussian_noise, pack_bits, parse_frame,
+};
+use bachmodem::spectral_mask::{power_spectrum_gpu, SpectralMask};
+use bachmodem::transmitter::{CODE_K, CODE_N};
+use burn::tensor::{ElementConversion, Tensor};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const PAYLOAD: &[u8] = b"SIGMA SWEEP 73";
+const SIGMAS: [f64; 6] = [3.0, 4.0, 5.0, 6.0, 8.0, 10.0];
+const SNRS_DB: [f32; 3] = [-18.0, -15.0, -12.0];
+
+fn main() {
+    let device = Default::default();
+    let mut args = std::env::args().skip(1);
+    let profile = args.next().unwrap_or_else(|| "standard".to_string());
+    let trials: usize = args.next().and_then(|a| a.parse().ok()).unwrap_or(10);
+
+    let Some(base) = ModemConfig::profile(&profile) else {
+        eprintln!("Unknown profile '{}'", profile);
+        std::process::exit(1);
+    };
+
+    let channel = WattersonChannel::moderate();
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
+    let decoder = PolarCodeSCL::new(CODE_N, CODE_K);
+
+    println!("Profile {}: {} trials per point, moderate Watterson channel\n", profile, trials);
+    print!("{:>7} {:>9} {:>10}", "sigmas", "tone Hz", "occupied Hz");
+    for snr_db in SNRS_DB {
+        print!(" {:>17}", format!("{} dB BER/frames", snr_db));
+    }
+    println!();
+
+    for sigmas in SIGMAS {
+        let tx = BachTransmitter::new(base.clone().with_wavelet_sigmas(sigmas));
+        let signal = tx.build::<Backend>(&device, PAYLOAD).unwrap();
+
+        let spectrum = power_spectrum_gpu::<Backend>(&device, &signal);
+        let occupied = SpectralMask::cw_500hz(tx.config.center_frequency()).check(&spectrum).occupied_bandwidth();
+        print!("{:>7.1} {:>9.0} {:>10.0}", sigmas, tx.config.tone_bandwidth(), occupied);
+
+        // Sent coded bits in transmit order (LLRs come back descrambled)
+        let sent = encode_bits(&tx.encode_frame(PAYLOAD).unwrap());
+
+        for snr_db in SNRS_DB {
+            let (mut bit_errors, mut frames_ok) = (0, 0);
+            for _ in 0..trials {
+                let faded = channel.apply_with_rng::<Backend, _>(&device, &signal, &mut rng);
+                let rx = add_noise(&device, &faded, snr_db, &mut rng);
+                let llrs = demodulate_fhdpsk_soft_erasures_with_config::<Backend>(&device, &rx, true, &tx.config, CODE_N);
+
+                let values: Vec<f32> = llrs.clone().into_data().to_vec().unwrap();
+                bit_errors += values.iter().zip(&sent).filter(|&(&l, &b)| (l < 0.0) != (b == 1)).count();
+
+                let codeword = deinterleave_gpu::<Backend>(&device, &llrs, tx.config.interleaver_columns());
+                let bits = decoder.decode_scl_gpu::<Backend>(&device, &codeword, 8).swap_remove(0);
+                frames_ok += matches!(parse_frame(&pack_bits(&bits)), Ok(p) if p.starts_with(PAYLOAD)) as usize;
+            }
+
+            let ber = bit_errors as f64 / (trials * CODE_N) as f64;
+            print!(" {:>17}", format!("{:.3} {}/{}", ber, frames_ok, trials));
+        }
+        println!();
+    }
+}
+
+/// AWGN at `snr_db` relative to the faded signal's power
+fn add_noise(
+    device: &<Backend as burn::tensor::backend::Backend>::Device,
+    signal: &Tensor<Backend, 1>,
+    snr_db: f32,
+    rng: &mut ChaCha20Rng,
+) -> Tensor<Backend, 1> {
+    let signal_power: f32 = signal.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+    signal.clone() + gaussian_noise(device, signal.dims()[0], noise_std, rng)
+}
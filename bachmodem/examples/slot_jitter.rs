@@ -0,0 +1,33 @@
+/// Slot Jitter Collision Study
+///
+/// Several stations share one epoch-aligned repetition schedule. Without
+/// jitter their slots start together and every copy collides; with
+/// callsign-derived jitter collisions become independent per slot, so the
+/// repetition protocol recovers most messages.
+///
+/// Usage: cargo run --release --example slot_jitter [repetitions]
+
+use bachmodem::{simulate_slot_collisions, TimeSlotConfig};
+
+/// Starts closer than the preamble (16 tones) can't be separated by sync
+const GUARD: f64 = 3.2;
+const TRIALS: usize = 2000;
+
+fn main() {
+    let repetitions: usize = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(5);
+    let config = TimeSlotConfig::new(13, repetitions, 5.0);
+
+    println!("=== Slot Jitter: {} repetitions, {:.1} s slots, {:.1} s guard ===\n",
+             repetitions, config.transmission_duration, GUARD);
+    println!("{:>10} {:>9} {:>16} {:>14}", "jitter (s)", "stations", "copies collided", "messages lost");
+
+    for max_jitter in [0.0, 5.0, 10.0, 20.0, 40.0] {
+        for stations in [2, 3, 5] {
+            let stats = simulate_slot_collisions(&config, stations, max_jitter, GUARD, TRIALS);
+            println!("{:>10.1} {:>9} {:>15.1}% {:>13.1}%",
+                     max_jitter, stations, 100.0 * stats.copy_collision_rate, 100.0 * stats.message_loss_rate);
+        }
+    }
+
+    println!("\nJitter widens the slot pitch by its bound; receivers search that margin after each nominal start.");
+}
@@ -4,9 +4,10 @@
 use bachmodem::{
     write_wav, WattersonChannel,
     interleave, deinterleave, RakeReceiver,
+    ChaCha20Rng, RngStream, SimSeed, gaussian_noise,
 };
 use burn::backend::Wgpu;
-use burn::tensor::{Tensor, Distribution, ElementConversion};
+use burn::tensor::{Tensor, ElementConversion};
 
 type Backend = Wgpu;
 
@@ -77,6 +78,8 @@ fn test_configuration(
 ) {
     let test_snrs = vec![-20.0, -23.0, -25.0, -27.0];
     let trials = 20;
+    // Same channel draws for every configuration
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
     
     println!("Config: {}", name);
     println!("  Interleaving: {}", if use_interleave { "✓" } else { "✗" });
@@ -90,7 +93,7 @@ fn test_configuration(
         print!("  {} dB: ", snr_db);
         
         for _ in 0..trials {
-            let (success, ber) = run_trial(device, snr_db, use_interleave, use_rake);
+            let (success, ber) = run_trial(device, snr_db, use_interleave, use_rake, &mut rng);
             if success {
                 successes += 1;
             }
@@ -112,6 +115,7 @@ fn run_trial(
     snr_db: f32,
     use_interleave: bool,
     use_rake: bool,
+    rng: &mut ChaCha20Rng,
 ) -> (bool, f32) {
     let message = "BachModem 73!";
     let message_bytes = message.as_bytes();
@@ -152,14 +156,10 @@ fn run_trial(
     
     // Apply Watterson fading
     let channel = WattersonChannel::moderate();
-    let faded_signal = channel.apply::<Backend>(device, &clean_signal);
+    let faded_signal = channel.apply_with_rng::<Backend, _>(device, &clean_signal, rng);
     
     // Add noise
-    let noise = Tensor::<Backend, 1>::random(
-        [num_samples],
-        Distribution::Normal(0.0, noise_std as f64),
-        device,
-    );
+    let noise = gaussian_noise::<Backend, _>(device, num_samples, noise_std, rng);
     
     let noisy_signal = faded_signal + noise;
     
@@ -0,0 +1,51 @@
+/// Spectral Mask Check
+///
+/// Modulates a short message with every named profile and reports the 99%
+/// occupied bandwidth against a 500 Hz CW filter centered on the profile.
+/// An optional wavelet width (`ModemConfig::wavelet_sigmas`) overrides the
+/// profile's Morlet shape.
+///
+/// Usage: cargo run --example spectral_mask [profile|all] [sigmas]
+
+use bachmodem::{modulate_fhdpsk_with_config, ModemConfig, PROFILE_NAMES};
+use bachmodem::spectral_mask::{power_spectrum_gpu, SpectralMask};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+
+// Use raw CubeBackend to avoid Fusion wrapper which doesn't implement FftBackend yet
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+fn main() {
+    let device = Default::default();
+    let message = b"CQ CQ DE BACH";
+
+    let names: Vec<String> = match std::env::args().nth(1) {
+        Some(name) if name != "all" => vec![name],
+        _ => PROFILE_NAMES.iter().map(|s| s.to_string()).collect(),
+    };
+    let sigmas: Option<f64> = std::env::args().nth(2).and_then(|a| a.parse().ok());
+
+    println!("=== Spectral Mask: 500 Hz CW filter, 99% power ===\n");
+
+    for name in &names {
+        let Some(mut config) = ModemConfig::profile(name) else {
+            eprintln!("Unknown profile '{}' (expected one of {:?})", name, PROFILE_NAMES);
+            std::process::exit(1);
+        };
+        if let Some(sigmas) = sigmas {
+            config = config.with_wavelet_sigmas(sigmas);
+        }
+
+        let signal = modulate_fhdpsk_with_config::<Backend>(&device, message, true, &config);
+        let spectrum = power_spectrum_gpu::<Backend>(&device, &signal);
+
+        let mask = SpectralMask::cw_500hz(config.center_frequency());
+        let report = mask.check(&spectrum);
+
+        println!("{:<12} {:>2} tones, {:.2} s symbols, {} sigmas ({:.0} Hz per tone)",
+                 name, config.num_tones, config.symbol_duration, config.wavelet_sigmas, config.tone_bandwidth());
+        println!("  Occupied:  {:.0} - {:.0} Hz ({:.0} Hz)",
+                 report.occupied_low_hz, report.occupied_high_hz, report.occupied_bandwidth());
+        println!("  In filter: {:.2}% (center {:.0} Hz)", report.in_band_fraction * 100.0, mask.center_hz);
+        println!("  Result:    {}\n", if report.passes { "PASS" } else { "FAIL" });
+    }
+}
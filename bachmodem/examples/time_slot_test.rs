@@ -11,14 +11,15 @@ use bachmodem::{
     interleave, deinterleave, 
     PolarCode, soft_bits_to_llrs, compute_soft_bits,
     TimeSlotConfig, generate_repetition_transmission,
-    soft_combine_gpu, estimate_snr_from_correlation,
+    mrc_combine_gpu, fft_cross_correlation,
     RakeReceiver,
     modulate_fhdpsk_with_flourishes,
     deinterleave_gpu,
     FftBackend,
+    RngStream, SimSeed, gaussian_noise,
 };
 use burn::backend::wgpu::{CubeBackend, WgpuRuntime, WgpuDevice};
-use burn::tensor::{Tensor, Distribution, ElementConversion};
+use burn::tensor::{Tensor, ElementConversion};
 use hound;
 
 // Use raw CubeBackend to avoid Fusion wrapper which doesn't implement FftBackend yet
@@ -87,13 +88,10 @@ fn main() {
     println!("  Signal power: {:.6}, Noise std: {:.6}", signal_power, noise_std);
     
     let channel = WattersonChannel::moderate();
-    let faded_signal = channel.apply::<Backend>(&device, &clean_signal);
+    let seed = SimSeed(0);
+    let faded_signal = channel.apply_with_rng::<Backend, _>(&device, &clean_signal, &mut seed.rng(RngStream::Fading));
     
-    let noise = Tensor::<Backend, 1>::random(
-        faded_signal.shape(),
-        Distribution::Normal(0.0, noise_std as f64),
-        &device,
-    );
+    let noise = gaussian_noise::<Backend, _>(&device, faded_signal.dims()[0], noise_std, &mut seed.rng(RngStream::Noise));
     
     let rx_signal = faded_signal + noise;
     
@@ -146,9 +144,10 @@ fn main() {
     
     println!("  Synchronizing...");
     let time_offset = match bachmodem::modulation::synchronize_signal::<Backend>(&device, &search_window) {
-        Some(pos) => {
-            println!("  ✓ Sync found at sample {} ({:.2}s)", pos, pos as f32 / 8000.0);
-            pos
+        Some(sync) => {
+            println!("  ✓ Sync found at sample {} ({:.2}s), corr {:.3}, SNR {:.1} dB",
+                     sync.metrics.position, sync.metrics.position as f32 / 8000.0, sync.metrics.correlation, sync.snr_estimate_db);
+            sync.metrics.position
         },
         None => {
             println!("  ✗ Sync failed!");
@@ -158,7 +157,7 @@ fn main() {
     
     // 5. Process each repetition
     let mut all_llrs: Vec<Tensor<Backend, 1>> = Vec::with_capacity(num_reps);
-    let mut snr_estimates = Vec::with_capacity(num_reps);
+    let mut correlations: Vec<Tensor<Backend, 1>> = Vec::with_capacity(num_reps);
     
     let slot_duration_samples = (config.transmission_duration * 8000.0) as usize;
     let gap_samples = (config.listening_gap * 8000.0) as usize;
@@ -184,7 +183,16 @@ fn main() {
         // RAKE combining
         let processed_signal = rake.combine_paths::<Backend>(&device, &slot_signal);
         
-        snr_estimates.push(10.0); // Increased confidence for MRC
+        // Preamble correlation around the expected start weights this slot in MRC
+        let corr_len = 2 * margin + preamble.dims()[0];
+        let corr_window = processed_signal.clone().slice([0..corr_len.min(processed_signal.dims()[0])]);
+        let corr_window = if corr_window.dims()[0] < corr_len {
+            let pad = Tensor::zeros([corr_len - corr_window.dims()[0]], &device);
+            Tensor::cat(vec![corr_window, pad], 0)
+        } else {
+            corr_window
+        };
+        correlations.push(fft_cross_correlation(&device, &corr_window, &preamble));
         
         // Skip preamble manually to avoid second sync failure
         let preamble_len = preamble.dims()[0];
@@ -230,8 +238,8 @@ fn main() {
     // 6. Soft combining
     println!("  Combining {} repetitions...", all_llrs.len());
     let llr_stack = Tensor::stack(all_llrs, 0);
-    let weights = Tensor::from_floats(snr_estimates.as_slice(), &device);
-    let combined_llrs = soft_combine_gpu(&llr_stack, &weights);
+    let corr_stack = Tensor::stack(correlations, 0);
+    let combined_llrs = mrc_combine_gpu(&llr_stack, &corr_stack, 50);
     
     // 7. Decode
     let combined_data = combined_llrs.to_data();
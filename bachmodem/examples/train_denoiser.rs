@@ -0,0 +1,109 @@
+//! Train the 1-D convolutional denoiser on simulated HF traffic
+//!
+//! Noisy/clean pairs come from the Watterson channel simulator; the trained
+//! model is saved to `denoiser.mpk` and compared against the plain receive
+//! chain on fresh transmissions.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --features autodiff --example train_denoiser -- [steps] [snr_db]
+//! ```
+
+use bachmodem::{
+    BachTransmitter, ConvDenoiser, ConvDenoiserConfig, ModemConfig, NoEnhancer, PolarCodeSCL,
+    RngStream, SignalEnhancer, SimSeed, WattersonChannel, deinterleave_gpu, demodulate_fhdpsk_soft_enhanced_with_config,
+    denoiser_batch, gaussian_noise, pack_bits, parse_frame,
+};
+use bachmodem::transmitter::{CODE_K, CODE_N};
+use burn::backend::Autodiff;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::module::{AutodiffModule, Module};
+use burn::nn::loss::{MseLoss, Reduction};
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::record::CompactRecorder;
+use burn::tensor::{ElementConversion, Tensor};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+type TrainBackend = Autodiff<Backend>;
+
+const BATCH: usize = 16;
+const WINDOW: usize = 4000;
+const LEARNING_RATE: f64 = 1e-3;
+const EVAL_FRAMES: usize = 5;
+
+fn main() {
+    let device = Default::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let steps: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(500);
+    let snr_db: f32 = args.get(1).and_then(|a| a.parse().ok()).unwrap_or(-10.0);
+
+    let tx = BachTransmitter::new(ModemConfig::default());
+    let channel = WattersonChannel::moderate();
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
+
+    let mut model: ConvDenoiser<TrainBackend> = ConvDenoiserConfig::new().init(&device);
+    let mut optim = AdamConfig::new().init();
+
+    println!("Training denoiser: {} steps, {} × {} samples, {} dB", steps, BATCH, WINDOW, snr_db);
+
+    for step in 0..steps {
+        let (noisy, clean) = denoiser_batch::<TrainBackend, _>(&device, &tx, &channel, snr_db, BATCH, WINDOW, &mut rng);
+        let loss = MseLoss::new().forward(model.forward(noisy.clone()), clean.clone(), Reduction::Mean);
+
+        if step % 25 == 0 || step + 1 == steps {
+            // Error of the untouched input for reference
+            let baseline: f32 = MseLoss::new().forward(noisy, clean, Reduction::Mean).into_scalar().elem();
+            let value: f32 = loss.clone().into_scalar().elem();
+            println!("[{:4}] MSE {:.4} (input {:.4})", step, value, baseline);
+        }
+
+        let grads = GradientsParams::from_grads(loss.backward(), &model);
+        model = optim.step(LEARNING_RATE, model, grads);
+    }
+
+    model.clone().save_file("denoiser", &CompactRecorder::new()).expect("cannot save model");
+    println!("\nSaved denoiser.mpk");
+
+    // Decode comparison on the inference backend
+    let denoiser = model.valid();
+    let decoder = PolarCodeSCL::new(CODE_N, CODE_K);
+    let config = ModemConfig::default();
+    let (mut plain_ok, mut enhanced_ok) = (0, 0);
+
+    for frame in 0..EVAL_FRAMES {
+        let payload = format!("DENOISE {:02}", frame);
+        let signal = tx.build::<Backend>(&device, payload.as_bytes()).unwrap();
+        let faded = channel.apply_with_rng::<Backend, _>(&device, &signal, &mut rng);
+
+        let signal_power: f32 = faded.clone().powf_scalar(2.0).mean().into_scalar().elem();
+        let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+        let rx = faded.clone() + gaussian_noise(&device, faded.dims()[0], noise_std, &mut rng);
+
+        let plain = decode(&device, &config, &decoder, &rx, &NoEnhancer, &payload);
+        let enhanced = decode(&device, &config, &decoder, &rx, &denoiser, &payload);
+        println!("Frame {}: plain {}  denoised {}", frame, mark(plain), mark(enhanced));
+
+        plain_ok += plain as usize;
+        enhanced_ok += enhanced as usize;
+    }
+
+    println!("\nDecoded {}/{} plain, {}/{} denoised", plain_ok, EVAL_FRAMES, enhanced_ok, EVAL_FRAMES);
+}
+
+fn decode<E: SignalEnhancer<Backend>>(
+    device: &<Backend as burn::tensor::backend::Backend>::Device,
+    config: &ModemConfig,
+    decoder: &PolarCodeSCL,
+    rx: &Tensor<Backend, 1>,
+    enhancer: &E,
+    payload: &str,
+) -> bool {
+    let llrs = demodulate_fhdpsk_soft_enhanced_with_config::<Backend, E>(device, rx, enhancer, true, config, CODE_N);
+    let codeword = deinterleave_gpu::<Backend>(device, &llrs, config.interleaver_columns());
+    let bits = decoder.decode_scl_gpu::<Backend>(device, &codeword, 8).swap_remove(0);
+    matches!(parse_frame(&pack_bits(&bits)), Ok(bytes) if bytes.starts_with(payload.as_bytes()))
+}
+
+fn mark(ok: bool) -> &'static str {
+    if ok { "✓" } else { "✗" }
+}
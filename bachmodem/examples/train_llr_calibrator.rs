@@ -0,0 +1,158 @@
+//! Train the neural LLR calibrator on simulated HF traffic
+//!
+//! Frames go through the Watterson channel at random SNRs; the calibrator
+//! learns calibrated LLRs from the detector statistics. The trained model is
+//! saved to `llr_calibrator.mpk`, then compared against the analytic LLR
+//! formula: channel BER, logistic loss and BP decoder convergence.
+//!
+//! ```bash
+//! cargo run --release -p bachmodem --features autodiff --example train_llr_calibrator -- [frames] [eval_snr_db]
+//! ```
+
+use bachmodem::{
+    BachTransmitter, DemodStatistics, LlrCalibrator, LlrCalibratorConfig, LlrMapping, ModemConfig,
+    ModemRng, PolarCode, PolarCodeBP, RngStream, SimSeed, WattersonChannel, calibration_frame, calibrator_features, deinterleave_gpu,
+    llr_calibration_loss, pack_bits, parse_frame,
+};
+use bachmodem::transmitter::{CODE_K, CODE_N};
+use burn::backend::Autodiff;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::module::{AutodiffModule, Module};
+use burn::optim::{AdamConfig, GradientsParams, Optimizer};
+use burn::record::CompactRecorder;
+use burn::tensor::{ElementConversion, Tensor};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type Backend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+type TrainBackend = Autodiff<Backend>;
+
+/// Training SNR range (dB, full band)
+const SNR_RANGE: std::ops::Range<f32> = -22.0..-8.0;
+const LEARNING_RATE: f64 = 3e-3;
+const EVAL_FRAMES: usize = 10;
+
+/// BP iteration budgets tried in order; the first that decodes counts
+const BP_ITERATIONS: [usize; 4] = [5, 10, 20, 50];
+
+fn main() {
+    let device = Default::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let frames: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(200);
+    let eval_snr: f32 = args.get(1).and_then(|a| a.parse().ok()).unwrap_or(-16.0);
+
+    let tx = BachTransmitter::new(ModemConfig::default());
+    let channel = WattersonChannel::moderate();
+    let mut rng = SimSeed(0).rng(RngStream::Scenario);
+
+    let mut model: LlrCalibrator<TrainBackend> = LlrCalibratorConfig::new().init(&device);
+    let mut optim = AdamConfig::new().init();
+
+    println!("Training LLR calibrator on {} frames, {:?} dB", frames, SNR_RANGE);
+
+    for frame in 0..frames {
+        let payload = random_payload(&mut rng, tx.max_payload());
+        let snr_db = SNR_RANGE.start + (SNR_RANGE.end - SNR_RANGE.start) * rng.uniform() as f32;
+        let Some((stats, bits)) = calibration_frame::<Backend, _>(&device, &tx, &channel, &payload, snr_db, &mut rng) else {
+            continue;
+        };
+
+        // Statistics come from the FFT backend; only the MLP needs gradients
+        let features = Tensor::<TrainBackend, 2>::from_inner(calibrator_features(&stats));
+        let loss = llr_calibration_loss(model.forward(features), &bits);
+
+        if frame % 20 == 0 || frame + 1 == frames {
+            let value: f32 = loss.clone().into_scalar().elem();
+            println!("[{:4}] {:5.1} dB  loss {:.4}", frame, snr_db, value);
+        }
+
+        let grads = GradientsParams::from_grads(loss.backward(), &model);
+        model = optim.step(LEARNING_RATE, model, grads);
+    }
+
+    model.clone().save_file("llr_calibrator", &CompactRecorder::new()).expect("cannot save model");
+    println!("\nSaved llr_calibrator.mpk");
+
+    let mappings = [("analytic", LlrMapping::Analytic), ("neural", LlrMapping::Neural(model.valid()))];
+    let mut totals = [Evaluation::default(), Evaluation::default()];
+
+    println!("\nEvaluating {} frames at {} dB", EVAL_FRAMES, eval_snr);
+    for _ in 0..EVAL_FRAMES {
+        let payload = random_payload(&mut rng, tx.max_payload());
+        let Some((stats, bits)) = calibration_frame::<Backend, _>(&device, &tx, &channel, &payload, eval_snr, &mut rng) else {
+            println!("  sync failed");
+            continue;
+        };
+
+        for ((_, mapping), total) in mappings.iter().zip(totals.iter_mut()) {
+            total.add(evaluate(&device, &tx.config, mapping, &stats, &bits, &payload));
+        }
+    }
+
+    println!("\n{:>9} {:>8} {:>8} {:>10} {:>14}", "mapping", "BER", "loss", "BP decoded", "mean BP iters");
+    for ((name, _), total) in mappings.iter().zip(&totals) {
+        println!("{:>9} {:>8.4} {:>8.4} {:>7}/{:<2} {:>14}", name,
+                 total.bit_errors as f64 / total.bits.max(1) as f64,
+                 total.loss / total.frames.max(1) as f64,
+                 total.decoded, total.frames,
+                 if total.decoded > 0 { format!("{:.1}", total.iterations as f64 / total.decoded as f64) } else { "-".into() });
+    }
+}
+
+#[derive(Default)]
+struct Evaluation {
+    frames: usize,
+    bits: usize,
+    bit_errors: usize,
+    loss: f64,
+    decoded: usize,
+    iterations: usize,
+}
+
+impl Evaluation {
+    fn add(&mut self, other: Evaluation) {
+        self.frames += other.frames;
+        self.bits += other.bits;
+        self.bit_errors += other.bit_errors;
+        self.loss += other.loss;
+        self.decoded += other.decoded;
+        self.iterations += other.iterations;
+    }
+}
+
+fn evaluate(
+    device: &<Backend as burn::tensor::backend::Backend>::Device,
+    config: &ModemConfig,
+    mapping: &LlrMapping<Backend>,
+    stats: &DemodStatistics<Backend>,
+    bits: &[u8],
+    payload: &[u8],
+) -> Evaluation {
+    let llrs = mapping.llrs(stats);
+    let loss: f32 = llr_calibration_loss(llrs.clone(), bits).into_scalar().elem();
+
+    let values: Vec<f32> = llrs.clone().into_data().to_vec().unwrap();
+    let bit_errors = values.iter().zip(bits).filter(|&(&l, &b)| (l < 0.0) as u8 != b).count();
+
+    let codeword = deinterleave_gpu::<Backend>(device, &llrs, config.interleaver_columns());
+    let info_positions = PolarCode::new(CODE_N, CODE_K).info_positions;
+    let bp = PolarCodeBP::new(CODE_N, CODE_K);
+
+    let converged = BP_ITERATIONS.iter().find(|&&iterations| {
+        let u: Vec<f32> = bp.decode_bp::<Backend>(device, &codeword, iterations).into_data().to_vec().unwrap();
+        let info: Vec<u8> = info_positions.iter().map(|&p| (u[p] < 0.0) as u8).collect();
+        matches!(parse_frame(&pack_bits(&info)), Ok(bytes) if bytes.starts_with(payload))
+    });
+
+    Evaluation {
+        frames: 1,
+        bits: bits.len(),
+        bit_errors,
+        loss: loss as f64,
+        decoded: converged.is_some() as usize,
+        iterations: converged.copied().unwrap_or(0),
+    }
+}
+
+fn random_payload(rng: &mut impl ModemRng, len: usize) -> Vec<u8> {
+    (0..len).map(|_| b' ' + rng.below(95) as u8).collect()
+}
@@ -1,7 +1,6 @@
-use bachmodem::{modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_ex, write_wav, read_wav};
+use bachmodem::{modulate_fhdpsk_with_flourishes, demodulate_fhdpsk_ex, write_wav, read_wav, gaussian_noise, ModemRng, RngStream, SimSeed};
 use burn::backend::Wgpu;
-use burn::tensor::{Tensor, Distribution};
-use rand::Rng;
+use burn::tensor::Tensor;
 
 type Backend = Wgpu;
 
@@ -11,7 +10,8 @@ fn main() {
     println!("=======================================================\n");
     
     let device = Default::default();
-    let mut rng = rand::thread_rng();
+    let seed = SimSeed(0);
+    let mut rng = seed.rng(RngStream::Scenario);
     
     // Original message
     let original_message = "Test msg 73!";
@@ -51,7 +51,7 @@ fn main() {
     // Random offset: place signal somewhere between 5-15 seconds into the noise
     let min_offset_seconds = 5.0;
     let max_offset_seconds = 15.0;
-    let offset_seconds = rng.gen_range(min_offset_seconds..max_offset_seconds);
+    let offset_seconds = min_offset_seconds + (max_offset_seconds - min_offset_seconds) * rng.uniform();
     let offset_samples = (offset_seconds * 8000.0) as usize;
     
     println!("Random timing offset: {:.2} seconds ({} samples)\n", offset_seconds, offset_samples);
@@ -61,11 +61,7 @@ fn main() {
     println!("Generating noisy channel ({} samples, {:.1} seconds)...", total_len, total_len as f64 / 8000.0);
     
     // Generate white Gaussian noise
-    let noise = Tensor::<Backend, 1>::random(
-        [total_len],
-        Distribution::Normal(0.0, noise_std as f64),
-        &device,
-    );
+    let noise = gaussian_noise::<Backend, _>(&device, total_len, noise_std, &mut seed.rng(RngStream::Noise));
     
     // Insert clean signal at random offset
     println!("Inserting signal at offset {} samples...", offset_samples);
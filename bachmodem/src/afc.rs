@@ -0,0 +1,246 @@
+/// Automatic Frequency Control
+///
+/// SSB rigs are routinely 10-50 Hz off frequency. That moves every tone
+/// past its matched filter (the alphabet's semitones are 15-70 Hz apart),
+/// and even a fraction of a hertz is fatal to the phase: the differential
+/// reference is `lag` symbols old (1.6 s with 16 tones of 100 ms), so an
+/// offset f rotates every decision by 2π·f·lag·T - a third of a hertz
+/// already flips binary DPSK.
+///
+/// `estimate_frequency_offset` works in two stages:
+/// 1. Spectral, independent of timing: the capture's averaged power
+///    spectrum (≈1 Hz bins) is correlated with the preamble's over
+///    ±`afc_range_hz`. The tones form an uneven musical scale, so the comb
+///    only lines up at the true offset; the peak is interpolated to a
+///    fraction of a bin.
+/// 2. Preamble phase: with stage 1 removed the preamble syncs, and every
+///    note is correlated with its clean analytic tone. A tone recurs in the
+///    other sweeps at separations from one note (the turn at the top and
+///    bottom of the sweeps) to two whole sweeps, advancing in phase by
+///    2π·f·Δt. Separations are taken shortest first, each unwrapped against
+///    the estimate so far, so the result has the precision of the two-sweep
+///    baseline (hundredths of a hertz) without its ±0.3 Hz ambiguity.
+///
+/// `correct_frequency_offset` shifts the capture back with
+/// `shift_frequency`. The demodulators run it ahead of sync when
+/// `ModemConfig::afc_range_hz` is set (`with_afc`).
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::complex::{ComplexTensor, shift_frequency};
+use crate::config::ModemConfig;
+use crate::fft_correlation::{fft_cross_correlation, FftBackend};
+use crate::modulation::measure_sync;
+use crate::sync_ambiguity::resolve_sync_ambiguity;
+use crate::wavelet::{generate_bach_preamble_with_config, preamble_note_phases, preamble_tone_sequence, shaped_wavelet, WaveletShape};
+
+/// Offset search range for SSB rigs (Hz)
+pub const DEFAULT_AFC_RANGE_HZ: f64 = 50.0;
+
+/// Spectral segment: the power of two above one second (≈1 Hz bins)
+fn spectrum_len(sample_rate: f64) -> usize {
+    (sample_rate as usize).next_power_of_two()
+}
+
+/// Averaged power spectrum of Hann-windowed segments: [len / 2]
+///
+/// Signals shorter than one segment are zero-padded.
+///
+/// **NO SYNC POINT**
+fn power_spectrum<B: Backend + FftBackend>(device: &B::Device, signal: &Tensor<B, 1>, len: usize) -> Tensor<B, 1> {
+    let n = signal.dims()[0];
+    let segments = (n / len).max(1);
+    let samples = if n < len {
+        Tensor::cat(vec![signal.clone(), Tensor::zeros([len - n], device)], 0)
+    } else {
+        signal.clone().slice([0..segments * len])
+    };
+    let window: Vec<f32> = (0..len)
+        .map(|i| (0.5 - 0.5 * (2.0 * PI * i as f64 / len as f64).cos()) as f32)
+        .collect();
+    let window = Tensor::<B, 1>::from_floats(window.as_slice(), device).reshape([1, len]);
+
+    ComplexTensor::from_real(samples.reshape([segments, len]) * window)
+        .fft()
+        .norm_sqr()
+        .mean_dim(0)
+        .reshape([len])
+        .slice([0..len / 2])
+}
+
+/// Offset (Hz) of the tone comb in `signal` against the preamble's,
+/// within ±`range_hz`
+///
+/// ⚠️ **SYNC POINT**: Downloads the comb correlation (one value per bin of
+/// the range)
+pub fn spectral_frequency_offset<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    range_hz: f64,
+) -> f64 {
+    let len = spectrum_len(config.sample_rate);
+    let bin_hz = config.sample_rate / len as f64;
+    let lags = (range_hz / bin_hz).ceil() as usize;
+
+    // Zero-mean template: white noise adds the same to every lag
+    let template = power_spectrum(device, &generate_bach_preamble_with_config::<B>(device, config), len);
+    let template = template.clone() - template.mean();
+    let received = power_spectrum(device, signal, len);
+    let padded = Tensor::cat(vec![Tensor::zeros([lags], device), received, Tensor::zeros([lags], device)], 0);
+    let scores: Vec<f32> = fft_cross_correlation(device, &padded, &template).into_data().to_vec().unwrap();
+
+    let (peak, _) = scores.iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (i, &s)| if s > best.1 { (i, s) } else { best });
+
+    // Parabolic interpolation between the neighbouring lags
+    let mut fraction = 0.0;
+    if peak > 0 && peak + 1 < scores.len() {
+        let (left, center, right) = (scores[peak - 1] as f64, scores[peak] as f64, scores[peak + 1] as f64);
+        let curvature = left - 2.0 * center + right;
+        if curvature < 0.0 {
+            fraction = 0.5 * (left - right) / curvature;
+        }
+    }
+    (peak as f64 + fraction - lags as f64) * bin_hz
+}
+
+/// Offset (Hz) from the phase advance between recurring preamble notes;
+/// None if the preamble starting at `preamble_start` runs off the signal
+///
+/// Unambiguous to half a cycle per note (±10 Hz with 50 ms notes), so the
+/// spectral stage should have run first.
+///
+/// ⚠️ **SYNC POINT**: Downloads one correlation per note
+pub fn preamble_frequency_offset<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    preamble_start: usize,
+    config: &ModemConfig,
+) -> Option<f64> {
+    let tones = preamble_tone_sequence(config);
+    let note_phases = preamble_note_phases(config);
+    let note_len = config.preamble_note_samples();
+    let num_notes = tones.len();
+    if preamble_start + num_notes * note_len > signal.dims()[0] {
+        return None;
+    }
+
+    // Every note against its analytic tone: Σ r·conj(w)
+    let frequencies = config.frequencies();
+    let (re, im): (Vec<_>, Vec<_>) = tones.iter()
+        .map(|&slot| shaped_wavelet::<B>(device, frequencies[config.transmit_tone(slot)], config.preamble_note_duration, WaveletShape::morlet(config.wavelet_sigmas), config.sample_rate))
+        .unzip();
+    let received = signal.clone()
+        .slice([preamble_start..preamble_start + num_notes * note_len])
+        .reshape([num_notes, note_len]);
+    let corr = ComplexTensor::new(Tensor::stack(re, 0), Tensor::stack(im, 0))
+        .conj()
+        .map(|w| (received.clone() * w).sum_dim(1).reshape([num_notes]));
+    let corr_re: Vec<f32> = corr.re.into_data().to_vec().unwrap();
+    let corr_im: Vec<f32> = corr.im.into_data().to_vec().unwrap();
+
+    // Transmitted note phases removed
+    let notes: Vec<(f64, f64)> = (0..num_notes)
+        .map(|i| {
+            let (cos, sin) = (note_phases[i].cos(), note_phases[i].sin());
+            let (re, im) = (corr_re[i] as f64, corr_im[i] as f64);
+            (re * cos + im * sin, im * cos - re * sin)
+        })
+        .collect();
+
+    // Σ c_b·conj(c_a) over recurrences of a tone, per separation in notes
+    let mut baselines: BTreeMap<usize, (f64, f64)> = BTreeMap::new();
+    for a in 0..num_notes {
+        for b in a + 1..num_notes {
+            if tones[a] != tones[b] {
+                continue;
+            }
+            let ((re_a, im_a), (re_b, im_b)) = (notes[a], notes[b]);
+            let sum = baselines.entry(b - a).or_insert((0.0, 0.0));
+            sum.0 += re_b * re_a + im_b * im_a;
+            sum.1 += im_b * re_a - re_b * im_a;
+        }
+    }
+
+    // Shortest separation first, each unwrapped to the nearest candidate
+    let note_time = note_len as f64 / config.sample_rate;
+    let mut estimate = 0.0;
+    for (separation, (re, im)) in baselines {
+        let span = separation as f64 * note_time;
+        let measured = im.atan2(re) / (2.0 * PI * span);
+        estimate = measured + ((estimate - measured) * span).round() / span;
+    }
+    Some(estimate)
+}
+
+/// Carrier offset of `signal` (Hz, positive = received high) within
+/// ±`config.afc_range_hz` (`DEFAULT_AFC_RANGE_HZ` if unset)
+///
+/// The spectral estimate alone if the preamble can't be found once it is
+/// removed.
+///
+/// ⚠️ **SYNC POINT**
+pub fn estimate_frequency_offset<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> f64 {
+    let range_hz = if config.afc_range_hz > 0.0 { config.afc_range_hz } else { DEFAULT_AFC_RANGE_HZ };
+    let coarse = spectral_frequency_offset(device, signal, config, range_hz);
+
+    let shifted = shift_frequency(device, signal, -coarse, config.sample_rate);
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let fine = measure_sync::<B>(device, &shifted, config)
+        .filter(|metrics| config.sync.thresholds.accepts(metrics))
+        .and_then(|metrics| {
+            let data_start = resolve_sync_ambiguity(device, &shifted, &preamble, metrics.position, config);
+            let preamble_start = data_start.checked_sub(preamble.dims()[0])?;
+            preamble_frequency_offset(device, &shifted, preamble_start, config)
+        })
+        .unwrap_or(0.0);
+
+    println!("  [AFC] Spectral offset {:+.2} Hz, preamble residual {:+.3} Hz", coarse, fine);
+    coarse + fine
+}
+
+/// `signal` with its estimated carrier offset removed
+///
+/// ⚠️ **SYNC POINT**
+pub fn correct_frequency_offset<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Tensor<B, 1> {
+    let offset = estimate_frequency_offset(device, signal, config);
+    shift_frequency(device, signal, -offset, config.sample_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::{demodulate_fhdpsk_ex_with_config, modulate_fhdpsk_with_config};
+    use crate::TestBackend;
+
+    #[test]
+    fn test_estimates_ssb_mistuning() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_afc(DEFAULT_AFC_RANGE_HZ);
+        let data = b"Tuned by ear";
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, data, true, &config);
+        let mut rng = SplitMix64::new(9);
+
+        for offset_hz in [-37.3, -4.6, 0.0, 12.25, 48.0] {
+            let rx = shift_frequency(&device, &tx, offset_hz, config.sample_rate) + gaussian_noise(&device, tx.dims()[0], 0.05, &mut rng);
+            let estimate = estimate_frequency_offset(&device, &rx, &config);
+            assert!((estimate - offset_hz).abs() < 0.05, "offset {} estimated {}", offset_hz, estimate);
+
+            // The demodulator removes it ahead of sync
+            let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &rx, true, &config);
+            assert_eq!(&decoded[..data.len()], data, "offset {}", offset_hz);
+        }
+    }
+}
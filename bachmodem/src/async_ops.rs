@@ -0,0 +1,253 @@
+/// Async (tokio) Receiver Entry Points
+///
+/// Decoding a frame or absorbing a capture block keeps a GPU queue busy and
+/// waits on device readbacks for tens of milliseconds, far too long to run
+/// on an async executor thread. These wrappers move the work onto tokio's
+/// blocking pool (`spawn_blocking`) so a daemon, a web UI and the rigctld
+/// control loop can share one runtime:
+/// - `AsyncReceiver::decode` awaits one capture on a shared `ReceiverState`
+/// - `decode_batch` runs a `ReceiverPool` over many captures and streams a
+///   `DecodeProgress` per completed job
+/// - `monitor` feeds capture blocks through the noise-floor tracker and the
+///   input health monitor and streams a `MonitorUpdate` per block
+///
+/// Progress streams are bounded `tokio::sync::mpsc` channels: `recv().await`
+/// until `None`. A slow consumer holds up the producer instead of queueing
+/// without limit; dropping the receiver ends the operation early. The
+/// operations hand their state back through the returned `JoinHandle`
+/// (e.g. to `save_file` it).
+///
+/// Needs a runtime with the blocking pool (any tokio runtime); feature `async`.
+
+use std::sync::Arc;
+use burn::tensor::{Tensor, backend::Backend};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use crate::fft_correlation::FftBackend;
+use crate::input_health::{HealthAlert, InputHealthSnapshot};
+use crate::noise_floor::NoiseFloorSnapshot;
+use crate::receiver_pool::{decode_source, DecodeError, DecodeResult, DecodeSource, DecodedFrame, ReceiverPool, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+
+/// Progress events buffered ahead of a slow consumer
+pub const PROGRESS_BUFFER: usize = 32;
+
+/// Single receiver whose decodes run on the blocking pool
+///
+/// Concurrent `decode` calls queue on the state (one decode at a time); use
+/// a `ReceiverPool` through `decode_batch` for parallel decoding.
+pub struct AsyncReceiver<B: Backend> {
+    device: B::Device,
+    config: ReceiverPoolConfig,
+    state: Arc<Mutex<ReceiverState<B>>>,
+}
+
+impl<B: Backend> Clone for AsyncReceiver<B> {
+    fn clone(&self) -> Self {
+        Self { device: self.device.clone(), config: self.config.clone(), state: Arc::clone(&self.state) }
+    }
+}
+
+impl<B: Backend + FftBackend> AsyncReceiver<B> {
+    pub fn new(device: B::Device, config: ReceiverPoolConfig, state: ReceiverState<B>) -> Self {
+        Self { device, config, state: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Decode one capture (`receiver_pool::decode_capture`)
+    pub async fn decode(&self, source: DecodeSource) -> Result<DecodedFrame, DecodeError> {
+        let mut state = Arc::clone(&self.state).lock_owned().await;
+        let device = self.device.clone();
+        let config = self.config.clone();
+
+        tokio::task::spawn_blocking(move || decode_source(&device, &mut state, &config, source))
+            .await
+            .expect("decode task panicked")
+    }
+
+    /// Copy of the receiver state after the decodes so far
+    pub async fn state(&self) -> ReceiverState<B> {
+        self.state.lock().await.clone()
+    }
+}
+
+/// One completed job of `decode_batch`
+#[derive(Clone, Debug)]
+pub struct DecodeProgress {
+    /// Jobs finished so far, including this one
+    pub done: usize,
+
+    /// Jobs in the batch
+    pub total: usize,
+
+    pub result: DecodeResult,
+}
+
+/// Decode `sources` on `pool`, streaming results in completion order
+///
+/// The handle returns the pool once every job has finished, or as soon as
+/// the progress receiver is dropped.
+pub fn decode_batch<B: Backend + FftBackend>(
+    mut pool: ReceiverPool<B>,
+    sources: Vec<DecodeSource>,
+) -> (mpsc::Receiver<DecodeProgress>, JoinHandle<ReceiverPool<B>>) {
+    let (progress_tx, progress_rx) = mpsc::channel(PROGRESS_BUFFER);
+
+    let handle = tokio::task::spawn_blocking(move || {
+        let total = sources.len();
+        for source in sources {
+            pool.submit(source);
+        }
+
+        for done in 1..=total {
+            let Some(result) = pool.results().next() else { break };
+            if progress_tx.blocking_send(DecodeProgress { done, total, result }).is_err() {
+                break;
+            }
+        }
+        pool
+    });
+
+    (progress_rx, handle)
+}
+
+/// Capture block for `monitor`
+#[derive(Clone, Debug)]
+pub struct CaptureBlock {
+    pub samples: Vec<f32>,
+
+    /// Capture time of the first sample (seconds, monotonic clock)
+    pub timestamp: f64,
+}
+
+/// Monitor state after one block
+#[derive(Clone, Debug)]
+pub struct MonitorUpdate {
+    /// Timestamp of the block
+    pub timestamp: f64,
+
+    /// None until the tracker has absorbed a block
+    pub noise_floor: Option<NoiseFloorSnapshot>,
+
+    pub input_health: InputHealthSnapshot,
+
+    /// Faults that started in this block
+    pub alerts: Vec<HealthAlert>,
+}
+
+/// Track the noise floor and input health over a stream of capture blocks
+///
+/// Runs until `blocks` closes or the update receiver is dropped; the handle
+/// returns the state for the next session.
+pub fn monitor<B: Backend + FftBackend>(
+    device: B::Device,
+    mut state: ReceiverState<B>,
+    mut blocks: mpsc::Receiver<CaptureBlock>,
+) -> (mpsc::Receiver<MonitorUpdate>, JoinHandle<ReceiverState<B>>) {
+    let (update_tx, update_rx) = mpsc::channel(PROGRESS_BUFFER);
+
+    let handle = tokio::spawn(async move {
+        while let Some(block) = blocks.recv().await {
+            let device = device.clone();
+            let (returned, update) = tokio::task::spawn_blocking(move || {
+                let signal = Tensor::<B, 1>::from_floats(block.samples.as_slice(), &device);
+                state.noise_floor.update_gpu::<B>(&device, &signal);
+                let alerts = state.input_health.push_block(&block.samples, block.timestamp);
+
+                let update = MonitorUpdate {
+                    timestamp: block.timestamp,
+                    noise_floor: state.noise_floor.snapshot(),
+                    input_health: state.input_health.snapshot(),
+                    alerts,
+                };
+                (state, update)
+            })
+            .await
+            .expect("monitor task panicked");
+
+            state = returned;
+            if update_tx.send(update).await.is_err() {
+                break;
+            }
+        }
+        state
+    });
+
+    (update_rx, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::transmitter::BachTransmitter;
+    use crate::TestBackend;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+    }
+
+    #[test]
+    fn test_async_decode_and_batch_progress() {
+        let device: <TestBackend as Backend>::Device = Default::default();
+        let tx = BachTransmitter::new(ModemConfig::default());
+        let capture = |payload: &[u8]| -> Vec<f32> {
+            tx.build::<TestBackend>(&device, payload).unwrap().into_data().to_vec().unwrap()
+        };
+        let single = capture(b"ASYNC");
+        let batch: Vec<DecodeSource> = [b"BATCH A", b"BATCH B", b"BATCH C"].iter()
+            .map(|payload| DecodeSource::Samples(capture(*payload)))
+            .collect();
+
+        runtime().block_on(async {
+            let receiver = AsyncReceiver::<TestBackend>::new(device.clone(), ReceiverPoolConfig::default(), ReceiverState::default());
+            let frame = receiver.decode(DecodeSource::Samples(single)).await.unwrap();
+            assert_eq!(&frame.payload[..5], b"ASYNC");
+            assert!(receiver.state().await.noise_floor.snapshot().is_some());
+
+            let pool = ReceiverPool::<TestBackend>::new(ReceiverPoolConfig::default(), &device, 2).unwrap();
+            let (mut progress, handle) = decode_batch(pool, batch);
+
+            let mut done = Vec::new();
+            while let Some(event) = progress.recv().await {
+                assert_eq!(event.total, 3);
+                let frame = event.result.frame.unwrap();
+                assert!(frame.payload.starts_with(b"BATCH "));
+                done.push(event.done);
+            }
+            assert_eq!(done, vec![1, 2, 3]);
+            assert_eq!(handle.await.unwrap().shutdown().len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_monitor_streams_block_updates() {
+        let device: <TestBackend as Backend>::Device = Default::default();
+
+        runtime().block_on(async {
+            let (block_tx, block_rx) = mpsc::channel(4);
+            let (mut updates, handle) = monitor::<TestBackend>(device.clone(), ReceiverState::default(), block_rx);
+
+            let producer = tokio::spawn(async move {
+                let mut rng = SplitMix64::new(31);
+                for i in 0..5 {
+                    let noise: Vec<f32> = gaussian_noise::<TestBackend, _>(&device, 8000, 0.01, &mut rng)
+                        .into_data().to_vec().unwrap();
+                    block_tx.send(CaptureBlock { samples: noise, timestamp: i as f64 }).await.unwrap();
+                }
+            });
+
+            let mut count = 0;
+            while let Some(update) = updates.recv().await {
+                assert_eq!(update.timestamp, count as f64);
+                assert!(update.noise_floor.is_some());
+                count += 1;
+            }
+            producer.await.unwrap();
+            assert_eq!(count, 5);
+
+            let state = handle.await.unwrap();
+            assert_eq!(state.noise_floor.snapshot().unwrap().updates, 5);
+        });
+    }
+}
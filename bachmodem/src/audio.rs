@@ -0,0 +1,335 @@
+/// Audio Device Selection
+///
+/// Stations often have several soundcards (radio interface, headset, HDMI
+/// audio), and the OS default is rarely the radio. This module lists the
+/// devices of every audio host with their sample-format capabilities and
+/// resolves a configured device name to a device:
+/// - Exact name match first, then a unique case-insensitive substring
+///   ("CODEC" finds "USB Audio CODEC"), otherwise an error listing candidates
+/// - The chosen names, rate and transmit level persist in a small `key = value`
+///   settings file
+///
+/// Device access goes through cpal (feature `audio`); the settings file and
+/// name matching work without it.
+
+use std::fmt;
+use std::path::Path;
+use crate::tuning::parse_entries;
+use crate::wavelet::FS;
+
+/// Stream direction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Input,
+    Output,
+}
+
+/// One supported stream configuration range
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatRange {
+    pub channels: u16,
+    pub min_rate: u32,
+    pub max_rate: u32,
+
+    /// Sample format name ("i16", "f32", ...)
+    pub sample_format: String,
+}
+
+impl FormatRange {
+    pub fn supports_rate(&self, rate: u32) -> bool {
+        (self.min_rate..=self.max_rate).contains(&rate)
+    }
+}
+
+/// Device with its capabilities
+#[derive(Clone, Debug)]
+pub struct AudioDeviceInfo {
+    /// Audio host (ALSA, JACK, WASAPI, CoreAudio, ...)
+    pub host: String,
+
+    pub name: String,
+
+    pub input_formats: Vec<FormatRange>,
+    pub output_formats: Vec<FormatRange>,
+
+    pub default_input: bool,
+    pub default_output: bool,
+}
+
+impl AudioDeviceInfo {
+    pub fn formats(&self, direction: Direction) -> &[FormatRange] {
+        match direction {
+            Direction::Input => &self.input_formats,
+            Direction::Output => &self.output_formats,
+        }
+    }
+
+    /// True if some format of `direction` runs at `rate`
+    pub fn supports_rate(&self, direction: Direction, rate: u32) -> bool {
+        self.formats(direction).iter().any(|f| f.supports_rate(rate))
+    }
+}
+
+/// Audio device errors
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioError {
+    /// No device matches the configured name
+    NoSuchDevice { name: String, available: Vec<String> },
+
+    /// Several devices match the configured name
+    Ambiguous { name: String, matches: Vec<String> },
+
+    /// Audio backend failure
+    Backend(String),
+
+    /// Malformed settings file line
+    Settings { line: usize, message: String },
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::NoSuchDevice { name, available } => {
+                write!(f, "no audio device matches '{}' (available: {})", name, available.join(", "))
+            }
+            AudioError::Ambiguous { name, matches } => {
+                write!(f, "'{}' matches several devices: {}", name, matches.join(", "))
+            }
+            AudioError::Backend(message) => write!(f, "audio backend error: {}", message),
+            AudioError::Settings { line, message } => write!(f, "settings line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+/// Resolve a configured name against device names
+///
+/// Returns the index of the exact match, else of the only device whose name
+/// contains `query` (case-insensitive).
+pub fn match_device_name(names: &[String], query: &str) -> Result<usize, AudioError> {
+    if let Some(idx) = names.iter().position(|n| n == query) {
+        return Ok(idx);
+    }
+
+    let needle = query.to_lowercase();
+    let matches: Vec<usize> = names.iter()
+        .enumerate()
+        .filter(|(_, n)| n.to_lowercase().contains(&needle))
+        .map(|(i, _)| i)
+        .collect();
+
+    match matches.as_slice() {
+        [idx] => Ok(*idx),
+        [] => Err(AudioError::NoSuchDevice { name: query.to_string(), available: names.to_vec() }),
+        _ => Err(AudioError::Ambiguous {
+            name: query.to_string(),
+            matches: matches.iter().map(|&i| names[i].clone()).collect(),
+        }),
+    }
+}
+
+/// Transmit peak level before calibration (-6 dBFS)
+pub const DEFAULT_TX_LEVEL: f32 = 0.5;
+
+/// Persisted device selection
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioSettings {
+    /// Capture device name (None = host default)
+    pub input_device: Option<String>,
+
+    /// Playback device name (None = host default)
+    pub output_device: Option<String>,
+
+    /// Stream sample rate (Hz)
+    pub sample_rate: u32,
+
+    /// Transmit peak level (full scale = 1.0), set by `tx_level` calibration
+    pub tx_level: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            input_device: None,
+            output_device: None,
+            sample_rate: FS as u32,
+            tx_level: DEFAULT_TX_LEVEL,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Parse `key = value` lines; `#` lines are comments, unknown keys are ignored
+    pub fn parse(text: &str) -> Result<Self, AudioError> {
+        let mut settings = Self::default();
+
+        parse_entries(text, |key, value| {
+            match key {
+                "input_device" => settings.input_device = Some(value.to_string()),
+                "output_device" => settings.output_device = Some(value.to_string()),
+                "sample_rate" => {
+                    settings.sample_rate = value.parse()
+                        .map_err(|_| format!("invalid sample rate '{}'", value))?;
+                }
+                "tx_level" => {
+                    settings.tx_level = value.parse().ok()
+                        .filter(|level: &f32| *level > 0.0 && *level <= 1.0)
+                        .ok_or_else(|| format!("transmit level '{}' outside (0, 1]", value))?;
+                }
+                _ => {}
+            }
+            Ok(())
+        }).map_err(|err| AudioError::Settings { line: err.line, message: err.message })?;
+        Ok(settings)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# BachModem audio devices\n");
+        if let Some(name) = &self.input_device {
+            text.push_str(&format!("input_device = {}\n", name));
+        }
+        if let Some(name) = &self.output_device {
+            text.push_str(&format!("output_device = {}\n", name));
+        }
+        text.push_str(&format!("sample_rate = {}\n", self.sample_rate));
+        text.push_str(&format!("tx_level = {}\n", self.tx_level));
+        text
+    }
+
+    /// Load settings; a missing file yields the defaults
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text())
+    }
+
+    /// Configured device name for a direction
+    pub fn device(&self, direction: Direction) -> Option<&str> {
+        match direction {
+            Direction::Input => self.input_device.as_deref(),
+            Direction::Output => self.output_device.as_deref(),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+pub use device::{list_devices, open_device};
+
+#[cfg(feature = "audio")]
+mod device {
+    use super::*;
+    use cpal::traits::{DeviceTrait, HostTrait};
+
+    fn backend<E: fmt::Display>(e: E) -> AudioError {
+        AudioError::Backend(e.to_string())
+    }
+
+    fn format_ranges<I: Iterator<Item = cpal::SupportedStreamConfigRange>>(configs: I) -> Vec<FormatRange> {
+        configs
+            .map(|c| FormatRange {
+                channels: c.channels(),
+                min_rate: c.min_sample_rate().0,
+                max_rate: c.max_sample_rate().0,
+                sample_format: format!("{:?}", c.sample_format()).to_lowercase(),
+            })
+            .collect()
+    }
+
+    /// Every device of every available host, with capabilities
+    pub fn list_devices() -> Result<Vec<AudioDeviceInfo>, AudioError> {
+        let mut devices = Vec::new();
+
+        for host_id in cpal::available_hosts() {
+            let host = cpal::host_from_id(host_id).map_err(backend)?;
+            let default_input = host.default_input_device().and_then(|d| d.name().ok());
+            let default_output = host.default_output_device().and_then(|d| d.name().ok());
+
+            for device in host.devices().map_err(backend)? {
+                let Ok(name) = device.name() else { continue };
+                let input_formats = device.supported_input_configs()
+                    .map(format_ranges)
+                    .unwrap_or_default();
+                let output_formats = device.supported_output_configs()
+                    .map(format_ranges)
+                    .unwrap_or_default();
+
+                devices.push(AudioDeviceInfo {
+                    host: host_id.name().to_string(),
+                    default_input: default_input.as_deref() == Some(name.as_str()),
+                    default_output: default_output.as_deref() == Some(name.as_str()),
+                    name,
+                    input_formats,
+                    output_formats,
+                });
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Device for a direction from the default host
+    ///
+    /// Uses the configured name if set, else the host default device.
+    pub fn open_device(settings: &AudioSettings, direction: Direction) -> Result<cpal::Device, AudioError> {
+        let host = cpal::default_host();
+
+        let Some(query) = settings.device(direction) else {
+            let default = match direction {
+                Direction::Input => host.default_input_device(),
+                Direction::Output => host.default_output_device(),
+            };
+            return default.ok_or_else(|| AudioError::Backend("no default device".to_string()));
+        };
+
+        let mut devices: Vec<cpal::Device> = match direction {
+            Direction::Input => host.input_devices().map_err(backend)?.collect(),
+            Direction::Output => host.output_devices().map_err(backend)?.collect(),
+        };
+        let names: Vec<String> = devices.iter()
+            .map(|d| d.name().unwrap_or_default())
+            .collect();
+
+        let idx = match_device_name(&names, query)?;
+        Ok(devices.swap_remove(idx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_device_name() {
+        let names: Vec<String> = ["USB Audio CODEC", "HDA Intel PCH", "USB Audio CODEC #2", "default"]
+            .iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(match_device_name(&names, "USB Audio CODEC"), Ok(0));
+        assert_eq!(match_device_name(&names, "intel"), Ok(1));
+        assert!(matches!(match_device_name(&names, "codec"), Err(AudioError::Ambiguous { matches, .. }) if matches.len() == 2));
+        assert!(matches!(match_device_name(&names, "SignaLink"), Err(AudioError::NoSuchDevice { .. })));
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let settings = AudioSettings {
+            input_device: Some("USB Audio CODEC".to_string()),
+            output_device: None,
+            sample_rate: 48000,
+            tx_level: 0.35,
+        };
+        assert_eq!(AudioSettings::parse(&settings.to_text()), Ok(settings));
+
+        let parsed = AudioSettings::parse("# radio\noutput_device = USB Audio CODEC #2\n").unwrap();
+        assert_eq!(parsed.device(Direction::Output), Some("USB Audio CODEC #2"));
+        assert_eq!(parsed.sample_rate, 8000);
+        assert_eq!(parsed.tx_level, DEFAULT_TX_LEVEL);
+
+        assert!(matches!(AudioSettings::parse("sample_rate = fast"), Err(AudioError::Settings { line: 1, .. })));
+    }
+}
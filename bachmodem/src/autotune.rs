@@ -0,0 +1,440 @@
+/// Receiver Autotuner
+///
+/// Replaces hand-picked receive chain constants with a reproducible search:
+/// every candidate `ReceiverTuning` decodes a labelled corpus and the one
+/// with the highest decode rate at the target SNR wins, to be saved as a
+/// receiver profile (`ReceiverTuning::save`).
+///
+/// Corpus:
+/// - `LabelledCapture`: samples, the payload that was sent, its SNR if known
+/// - `load_corpus` (feature `wav`): a directory of WAV files listed in
+///   `labels.tsv`, one `file.wav <TAB> snr_db <TAB> payload text` line each
+///   (`-` for an unknown SNR, `#` lines are comments)
+/// - `simulate_corpus`: captures from a `DatasetGenerator`; `write_corpus`
+///   (feature `wav`) stores them in the same layout
+///
+/// Search, over any objective `FnMut(&ReceiverTuning) -> f32` (higher is
+/// better; `evaluate_tuning` gives the decode rate):
+/// - `grid_search`: every combination of the values in a `TuningGrid`
+/// - `differential_evolution`: DE/rand/1/bin within `TuningBounds`, seeded,
+///   starting from a population that includes the default tuning; the
+///   untuned receiver is kept unless a candidate beats it
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::dataset::{DatasetConfig, DatasetGenerator};
+use crate::fft_correlation::FftBackend;
+use crate::modem_rng::{ModemRng, RngStream, SimSeed};
+use crate::modulation::SyncThresholds;
+use crate::receiver_pool::{decode_capture, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+use crate::tuning::ReceiverTuning;
+
+/// One capture with its ground truth
+#[derive(Clone, Debug)]
+pub struct LabelledCapture {
+    pub name: String,
+    pub samples: Vec<f32>,
+
+    /// Payload that was transmitted
+    pub payload: Vec<u8>,
+
+    /// SNR of the capture (dB), if known
+    pub snr_db: Option<f32>,
+}
+
+/// Corpus label line: (file name, SNR, payload)
+///
+/// None for comments and blank lines.
+pub fn parse_label(line: &str) -> Option<Result<(String, Option<f32>, Vec<u8>), String>> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    // The payload is everything after the second tab, spaces included
+    let mut fields = line.splitn(3, '\t');
+    let (Some(file), Some(snr), Some(payload)) = (fields.next(), fields.next(), fields.next()) else {
+        return Some(Err(format!("expected file<TAB>snr_db<TAB>payload: '{}'", line)));
+    };
+    let snr_db = match snr.trim() {
+        "-" => None,
+        snr => match snr.parse() {
+            Ok(snr) => Some(snr),
+            Err(_) => return Some(Err(format!("invalid SNR '{}'", snr))),
+        },
+    };
+    Some(Ok((file.trim().to_string(), snr_db, payload.as_bytes().to_vec())))
+}
+
+/// Read `labels.tsv` and its WAV files from `dir`
+#[cfg(feature = "wav")]
+pub fn load_corpus<P: AsRef<std::path::Path>>(dir: P) -> Result<Vec<LabelledCapture>, Box<dyn std::error::Error>> {
+    let dir = dir.as_ref();
+    let labels = std::fs::read_to_string(dir.join("labels.tsv"))?;
+
+    let mut corpus = Vec::new();
+    for (i, line) in labels.lines().enumerate() {
+        let Some(label) = parse_label(line) else { continue };
+        let (name, snr_db, payload) = label.map_err(|e| format!("labels.tsv line {}: {}", i + 1, e))?;
+
+        let mut reader = hound::WavReader::open(dir.join(&name))?;
+        let samples = match reader.spec().sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+            hound::SampleFormat::Int => reader.samples::<i16>()
+                .map(|s| s.map(|s| s as f32 / 32768.0))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        corpus.push(LabelledCapture { name, samples, payload, snr_db });
+    }
+    Ok(corpus)
+}
+
+/// Store a corpus as float WAV files plus `labels.tsv` in `dir`
+#[cfg(feature = "wav")]
+pub fn write_corpus<P: AsRef<std::path::Path>>(dir: P, corpus: &[LabelledCapture]) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: crate::wavelet::FS as u32,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut labels = String::from("# file\tsnr_db\tpayload\n");
+    for capture in corpus {
+        let mut writer = hound::WavWriter::create(dir.join(&capture.name), spec)?;
+        for &sample in &capture.samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+
+        let snr = capture.snr_db.map_or("-".to_string(), |snr| format!("{:.2}", snr));
+        labels.push_str(&format!("{}\t{}\t{}\n", capture.name, snr, String::from_utf8_lossy(&capture.payload)));
+    }
+    std::fs::write(dir.join("labels.tsv"), labels)?;
+    Ok(())
+}
+
+/// Simulated corpus of `count` captures
+///
+/// ⚠️ **SYNC POINT**: Downloads every capture
+pub fn simulate_corpus<B: Backend>(device: &B::Device, config: DatasetConfig, count: usize) -> Vec<LabelledCapture> {
+    DatasetGenerator::<B>::new(device, config)
+        .take(count)
+        .enumerate()
+        .map(|(i, example)| LabelledCapture {
+            name: format!("sim_{:04}_{}.wav", i, example.channel.name()),
+            samples: example.samples,
+            payload: example.payload,
+            snr_db: Some(example.snr_db),
+        })
+        .collect()
+}
+
+/// Decode count of one tuning
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TuningScore {
+    pub decoded: usize,
+    pub total: usize,
+}
+
+impl TuningScore {
+    pub fn rate(&self) -> f32 {
+        if self.total == 0 { 0.0 } else { self.decoded as f32 / self.total as f32 }
+    }
+}
+
+/// Decode the captures within `window_db` of `target_snr_db` with `tuning`
+///
+/// Captures of unknown SNR always count. A capture is decoded if the frame
+/// starts with its labelled payload.
+///
+/// ⚠️ **SYNC POINT**: Runs the full receive chain per capture
+pub fn evaluate_tuning<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    corpus: &[LabelledCapture],
+    base: &ReceiverPoolConfig,
+    tuning: &ReceiverTuning,
+    target_snr_db: f32,
+    window_db: f32,
+) -> TuningScore {
+    let config = ReceiverPoolConfig { tuning: *tuning, ..base.clone() };
+    let mut score = TuningScore::default();
+
+    for capture in corpus {
+        if capture.snr_db.is_some_and(|snr| (snr - target_snr_db).abs() > window_db) {
+            continue;
+        }
+        let signal = Tensor::<B, 1>::from_floats(capture.samples.as_slice(), device);
+        score.total += 1;
+        if let Ok(frame) = decode_capture(device, state, &config, &signal) {
+            score.decoded += frame.payload.starts_with(&capture.payload) as usize;
+        }
+    }
+    score
+}
+
+/// Search outcome
+#[derive(Clone, Debug)]
+pub struct TuningResult {
+    pub best: ReceiverTuning,
+    pub score: f32,
+
+    /// Objective evaluations spent
+    pub evaluations: usize,
+
+    /// Best score after each grid point or generation
+    pub history: Vec<f32>,
+}
+
+/// Candidate values per parameter
+#[derive(Clone, Debug)]
+pub struct TuningGrid {
+    pub sync_min_correlation: Vec<f32>,
+    pub sync_min_peak_to_noise: Vec<f32>,
+    pub llr_scale: Vec<f32>,
+    pub bp_iterations: Vec<usize>,
+    pub rake_fingers: Vec<usize>,
+}
+
+impl Default for TuningGrid {
+    /// 144 points around the untuned receiver
+    fn default() -> Self {
+        Self {
+            sync_min_correlation: vec![0.015, 0.025, 0.04],
+            sync_min_peak_to_noise: vec![1.1, 1.3, 1.6],
+            llr_scale: vec![0.5, 1.0, 2.0, 4.0],
+            bp_iterations: vec![0, 50],
+            rake_fingers: vec![0, 3],
+        }
+    }
+}
+
+impl TuningGrid {
+    /// Every combination, first axis slowest
+    pub fn points(&self) -> Vec<ReceiverTuning> {
+        let mut points = Vec::new();
+        for &min_correlation in &self.sync_min_correlation {
+            for &min_peak_to_noise in &self.sync_min_peak_to_noise {
+                for &llr_scale in &self.llr_scale {
+                    for &bp_iterations in &self.bp_iterations {
+                        for &rake_fingers in &self.rake_fingers {
+                            points.push(ReceiverTuning {
+                                sync: SyncThresholds { min_correlation, min_peak_to_noise },
+                                llr_scale,
+                                bp_iterations,
+                                rake_fingers,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        points
+    }
+}
+
+/// Exhaustive search; the first of equally good points wins
+pub fn grid_search<F: FnMut(&ReceiverTuning) -> f32>(grid: &TuningGrid, mut objective: F) -> TuningResult {
+    let mut best = (ReceiverTuning::default(), f32::NEG_INFINITY);
+    let mut history = Vec::new();
+
+    for point in grid.points() {
+        let score = objective(&point);
+        if score > best.1 {
+            best = (point, score);
+        }
+        history.push(best.1);
+    }
+    TuningResult { best: best.0, score: best.1, evaluations: history.len(), history }
+}
+
+/// Search box of the differential evolution (inclusive)
+#[derive(Clone, Debug)]
+pub struct TuningBounds {
+    pub sync_min_correlation: (f32, f32),
+    pub sync_min_peak_to_noise: (f32, f32),
+    pub llr_scale: (f32, f32),
+    pub bp_iterations: (usize, usize),
+    pub rake_fingers: (usize, usize),
+}
+
+impl Default for TuningBounds {
+    fn default() -> Self {
+        Self {
+            sync_min_correlation: (0.005, 0.1),
+            sync_min_peak_to_noise: (1.0, 3.0),
+            llr_scale: (0.25, 8.0),
+            bp_iterations: (0, 100),
+            rake_fingers: (0, 4),
+        }
+    }
+}
+
+impl TuningBounds {
+    fn ranges(&self) -> [(f32, f32); 5] {
+        [
+            self.sync_min_correlation,
+            self.sync_min_peak_to_noise,
+            self.llr_scale,
+            (self.bp_iterations.0 as f32, self.bp_iterations.1 as f32),
+            (self.rake_fingers.0 as f32, self.rake_fingers.1 as f32),
+        ]
+    }
+
+    /// Tuning at a point of the search box (counts rounded, all clamped)
+    fn tuning(&self, x: &[f32; 5]) -> ReceiverTuning {
+        let x: Vec<f32> = x.iter().zip(self.ranges()).map(|(&v, (lo, hi))| v.clamp(lo, hi)).collect();
+        ReceiverTuning {
+            sync: SyncThresholds { min_correlation: x[0], min_peak_to_noise: x[1] },
+            llr_scale: x[2],
+            bp_iterations: x[3].round() as usize,
+            rake_fingers: x[4].round() as usize,
+        }
+    }
+
+    fn point(&self, tuning: &ReceiverTuning) -> [f32; 5] {
+        [
+            tuning.sync.min_correlation,
+            tuning.sync.min_peak_to_noise,
+            tuning.llr_scale,
+            tuning.bp_iterations as f32,
+            tuning.rake_fingers as f32,
+        ]
+    }
+}
+
+/// Differential evolution settings
+#[derive(Clone, Debug)]
+pub struct DeConfig {
+    pub bounds: TuningBounds,
+    pub population: usize,
+    pub generations: usize,
+
+    /// Differential weight F
+    pub mutation: f32,
+
+    /// Crossover probability CR
+    pub crossover: f32,
+    pub seed: u64,
+}
+
+impl Default for DeConfig {
+    fn default() -> Self {
+        Self {
+            bounds: TuningBounds::default(),
+            population: 12,
+            generations: 10,
+            mutation: 0.6,
+            crossover: 0.8,
+            seed: 0,
+        }
+    }
+}
+
+/// DE/rand/1/bin; a trial replaces its parent if it scores at least as well
+pub fn differential_evolution<F: FnMut(&ReceiverTuning) -> f32>(config: &DeConfig, mut objective: F) -> TuningResult {
+    assert!(config.population >= 4, "Differential evolution needs at least 4 members");
+    let mut rng = SimSeed(config.seed).rng(RngStream::Scenario);
+    let bounds = &config.bounds;
+    let ranges = bounds.ranges();
+
+    // Member 0 is the untuned receiver
+    let mut members: Vec<[f32; 5]> = vec![bounds.point(&ReceiverTuning::default())];
+    while members.len() < config.population {
+        members.push(ranges.map(|(lo, hi)| if hi > lo { lo + (hi - lo) * rng.uniform() as f32 } else { lo }));
+    }
+    let mut scores: Vec<f32> = members.iter().map(|x| objective(&bounds.tuning(x))).collect();
+    let untuned = scores[0];
+    let mut evaluations = members.len();
+    let mut history = Vec::new();
+
+    for _ in 0..config.generations {
+        for i in 0..members.len() {
+            let mut picks = [i; 3];
+            for k in 0..3 {
+                while picks[k] == i || picks[..k].contains(&picks[k]) {
+                    picks[k] = rng.below(members.len());
+                }
+            }
+            let [a, b, c] = picks.map(|p| members[p]);
+
+            let forced = rng.below(5);
+            let mut trial = members[i];
+            for d in 0..5 {
+                if d == forced || (rng.uniform() as f32) < config.crossover {
+                    trial[d] = (a[d] + config.mutation * (b[d] - c[d])).clamp(ranges[d].0, ranges[d].1);
+                }
+            }
+
+            let score = objective(&bounds.tuning(&trial));
+            evaluations += 1;
+            if score >= scores[i] {
+                members[i] = trial;
+                scores[i] = score;
+            }
+        }
+        history.push(scores.iter().copied().fold(f32::NEG_INFINITY, f32::max));
+    }
+
+    // The untuned receiver stays unless something beats it
+    let best = (0..members.len()).fold(0, |best, i| if scores[i] > scores[best] { i } else { best });
+    let best = if untuned >= scores[best] { ReceiverTuning::default() } else { bounds.tuning(&members[best]) };
+    TuningResult { best, score: scores.iter().copied().fold(untuned, f32::max), evaluations, history }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Peaks at correlation 0.04, peak/noise 2.0, scale 3, 30 BP iterations, 2 fingers
+    fn bowl(t: &ReceiverTuning) -> f32 {
+        -((t.sync.min_correlation - 0.04) * 50.0).powi(2)
+            - (t.sync.min_peak_to_noise - 2.0).powi(2)
+            - ((t.llr_scale - 3.0) / 4.0).powi(2)
+            - ((t.bp_iterations as f32 - 30.0) / 50.0).powi(2)
+            - (t.rake_fingers as f32 - 2.0).powi(2)
+    }
+
+    #[test]
+    fn test_differential_evolution_finds_optimum() {
+        let config = DeConfig { population: 20, generations: 60, ..Default::default() };
+        let result = differential_evolution(&config, bowl);
+
+        assert_eq!(result.evaluations, 20 * 61);
+        assert!(result.history.windows(2).all(|w| w[1] >= w[0]));
+        assert!(result.score > -0.01, "{:?}", result);
+        assert_eq!(result.best.rake_fingers, 2);
+        assert!((result.best.sync.min_peak_to_noise - 2.0).abs() < 0.1);
+
+        // Seeded: same search, same answer
+        assert_eq!(differential_evolution(&config, bowl).best, result.best);
+    }
+
+    #[test]
+    fn test_grid_search_and_default_tie() {
+        let result = grid_search(&TuningGrid::default(), bowl);
+        assert_eq!(result.evaluations, 144);
+        assert_eq!(result.best.sync.min_correlation, 0.04);
+        assert_eq!((result.best.bp_iterations, result.best.rake_fingers), (50, 3));
+
+        // A flat objective leaves the untuned receiver in place
+        let flat = differential_evolution(&DeConfig { generations: 3, ..Default::default() }, |_| 0.5);
+        assert_eq!(flat.best, ReceiverTuning::default());
+    }
+
+    #[test]
+    fn test_label_lines() {
+        assert!(parse_label("# file\tsnr_db\tpayload").is_none());
+        assert!(parse_label("  ").is_none());
+        assert_eq!(
+            parse_label("a.wav\t-21.5\tCQ N0CALL \r").unwrap(),
+            Ok(("a.wav".to_string(), Some(-21.5), b"CQ N0CALL ".to_vec())),
+        );
+        assert_eq!(parse_label("b.wav\t-\tx").unwrap().unwrap().1, None);
+        assert!(parse_label("c.wav\tloud\tx").unwrap().is_err());
+        assert!(parse_label("d.wav -20 x").unwrap().is_err());
+    }
+}
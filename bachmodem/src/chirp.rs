@@ -0,0 +1,186 @@
+/// Chirp (Linear FM) Symbols
+///
+/// A pure Morlet tone loses coherence once the receiver is mistuned by a
+/// sizeable fraction of its bandwidth (about -10 dB at 20 Hz for 0.1 s
+/// symbols). With `ModemConfig::chirp_span_hz` set, every data symbol is a
+/// Gaussian chirp sweeping that many Hz upwards across its window, centred on
+/// the Bach frequency. A frequency offset Δf then mostly turns into a time
+/// shift of the correlation peak, τ = -Δf / k with k = span / symbol_duration
+/// (a high signal reaches each frequency early), and the matched chirp
+/// correlator recovers it by sampling the symbols τ shifted. Offsets common
+/// to the whole transmission (SSB mistuning, Doppler shift of the path) give
+/// a common τ, which is searched once per frame (`estimate_chirp_offset`);
+/// the differential phase cancels the rest.
+///
+/// Preamble, flourishes and postamble stay pure tones, so synchronization and
+/// the musical character are unchanged; the occupied bandwidth per tone grows
+/// with the span (`ModemConfig::tone_bandwidth`).
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::complex::ComplexTensor;
+use crate::config::ModemConfig;
+
+/// Frequency offsets searched by the chirp correlator, as a fraction of the span
+pub const CHIRP_OFFSET_RANGE: f64 = 0.25;
+
+/// Symbols used for the offset search
+const SEARCH_SYMBOLS: usize = 64;
+
+/// Correlation peak shift (samples) of chirps received `offset_hz` high
+pub fn chirp_peak_shift(config: &ModemConfig, offset_hz: f64) -> f64 {
+    if config.chirp_span_hz == 0.0 {
+        return 0.0;
+    }
+    -offset_hz * config.symbol_duration / config.chirp_span_hz * config.sample_rate
+}
+
+/// Sample offset of the data symbols' chirp correlation peak
+///
+/// `signal` starts at the first data symbol. Tries shifts covering
+/// ±`CHIRP_OFFSET_RANGE` × span in 1 ms steps over the data symbols before
+/// the first flourish and returns the one with the most matched-filter
+/// energy. Pure-tone configs return 0.
+///
+/// ⚠️ **SYNC POINT**: Downloads one energy per candidate shift
+pub fn estimate_chirp_offset<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    bank: &ComplexTensor<B, 2>,
+    config: &ModemConfig,
+) -> isize {
+    if config.chirp_span_hz == 0.0 {
+        return 0;
+    }
+
+    let symbol_len = config.symbol_samples();
+    let signal_len = signal.dims()[0];
+    let num_tones = config.num_tones;
+    let max_shift = chirp_peak_shift(config, CHIRP_OFFSET_RANGE * config.chirp_span_hz).abs().ceil() as isize;
+    let step = ((config.sample_rate / 1000.0) as isize).max(1);
+
+    // Symbol 0 is skipped so negative shifts stay inside the signal
+    let mut last = SEARCH_SYMBOLS;
+    if config.flourish_interval > 0 {
+        last = last.min(config.flourish_interval - 1);
+    }
+    last = last.min((signal_len.saturating_sub(max_shift as usize + symbol_len)) / symbol_len);
+    if last == 0 {
+        return 0;
+    }
+
+    let melody = config.melody_indices(last + 1);
+    let candidates: Vec<isize> = (-max_shift / step..=max_shift / step).map(|i| i * step).collect();
+
+    // [candidates × symbols, symbol_len]
+    let mut segments = Vec::with_capacity(candidates.len() * last);
+    for &shift in &candidates {
+        for i in 1..=last {
+            let start = (i * symbol_len) as isize + shift;
+            segments.push(signal.clone().slice([start as usize..start as usize + symbol_len]));
+        }
+    }
+    let batch: Tensor<B, 2> = Tensor::stack(segments, 0);
+
+    // Energy of each symbol's own tone
+    let power = bank.clone().map(|b| batch.clone().matmul(b.transpose())).norm_sqr();
+    let mut mask = vec![0.0f32; candidates.len() * last * num_tones];
+    for c in 0..candidates.len() {
+        for i in 1..=last {
+            mask[(c * last + i - 1) * num_tones + melody[i]] = 1.0;
+        }
+    }
+    let mask = Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([candidates.len() * last, num_tones]);
+    let energy = (power * mask)
+        .reshape([candidates.len(), last * num_tones])
+        .sum_dim(1);
+
+    let energy: Vec<f32> = energy.into_data().to_vec().unwrap();
+    let best = (0..candidates.len())
+        .max_by(|&a, &b| energy[a].total_cmp(&energy[b]))
+        .unwrap_or(candidates.len() / 2);
+    candidates[best]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend as FftTestBackend;
+    use crate::wavelet::{matched_filter_bank, shaped_wavelet};
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    /// Symbols on the config's hopping pattern, every tone `offset_hz` high
+    fn offset_symbols(device: &<TestBackend as Backend>::Device, config: &ModemConfig, num_symbols: usize, offset_hz: f64) -> Tensor<TestBackend, 1> {
+        let freqs = config.frequencies();
+        let symbols: Vec<Tensor<TestBackend, 1>> = config.melody_indices(num_symbols).iter()
+            .map(|&tone| shaped_wavelet::<TestBackend>(device, freqs[tone] + offset_hz, config.symbol_duration, config.wavelet_shape(), config.sample_rate).0)
+            .collect();
+        Tensor::cat(symbols, 0)
+    }
+
+    /// Mean matched-filter magnitude of symbols 1.. at `shift`
+    fn mean_correlation(device: &<TestBackend as Backend>::Device, config: &ModemConfig, signal: &Tensor<TestBackend, 1>, shift: isize, num_symbols: usize) -> f32 {
+        let bank = matched_filter_bank::<TestBackend>(device, config);
+        let symbol_len = config.symbol_samples();
+        let melody = config.melody_indices(num_symbols);
+        (1..num_symbols - 1)
+            .map(|i| {
+                let start = ((i * symbol_len) as isize + shift) as usize;
+                let segment = signal.clone().slice([start..start + symbol_len]);
+                let tone = melody[i];
+                bank.clone()
+                    .map(|b| (segment.clone() * b.slice([tone..tone + 1]).reshape([symbol_len])).sum())
+                    .abs()
+                    .into_scalar()
+            })
+            .sum::<f32>() / (num_symbols - 2) as f32
+    }
+
+    #[test]
+    fn test_chirp_tolerates_frequency_offset() {
+        let device = Default::default();
+        let offset_hz = 20.0;
+        let num_symbols = 24;
+
+        let tone = ModemConfig::default();
+        let chirp = ModemConfig::profile("doppler").unwrap();
+
+        // Offset pure tones lose most of their correlation
+        let on_tune = mean_correlation(&device, &tone, &offset_symbols(&device, &tone, num_symbols, 0.0), 0, num_symbols);
+        let tone_loss = mean_correlation(&device, &tone, &offset_symbols(&device, &tone, num_symbols, offset_hz), 0, num_symbols) / on_tune;
+
+        // The chirp search finds the shifted peak
+        let signal = offset_symbols(&device, &chirp, num_symbols, offset_hz);
+        let bank = matched_filter_bank::<TestBackend>(&device, &chirp);
+        let shift = estimate_chirp_offset(&device, &signal, &bank, &chirp);
+        let expected = chirp_peak_shift(&chirp, offset_hz);
+        assert!((shift as f64 - expected).abs() <= 16.0, "shift {} expected {:.0}", shift, expected);
+
+        let chirp_on_tune = mean_correlation(&device, &chirp, &offset_symbols(&device, &chirp, num_symbols, 0.0), 0, num_symbols);
+        let chirp_loss = mean_correlation(&device, &chirp, &signal, shift, num_symbols) / chirp_on_tune;
+
+        assert!(tone_loss < 0.5, "tone keeps {}", tone_loss);
+        assert!(chirp_loss > 0.85, "chirp keeps {}", chirp_loss);
+    }
+
+    #[test]
+    fn test_chirp_frame_decodes_20_hz_high() {
+        use crate::complex::shift_frequency;
+        use crate::modulation::{demodulate_fhdpsk_ex_with_config, modulate_fhdpsk_with_config, synchronize_data_start_with_config};
+
+        let device = Default::default();
+        let message = b"Doppler 20 Hz";
+        let config = ModemConfig::profile("doppler").unwrap();
+        let frame = modulate_fhdpsk_with_config::<FftTestBackend>(&device, message, true, &config);
+
+        // Timing from the on-tune copy: the pure-tone preamble isn't under test
+        let data_start = synchronize_data_start_with_config::<FftTestBackend>(&device, &frame, &config).unwrap();
+        let mistuned = shift_frequency(&device, &frame, 20.0, config.sample_rate);
+        let data = mistuned.clone().slice([data_start..mistuned.dims()[0]]);
+
+        // Chirp offset search, demodulation, FEC and descrambling on the whole frame
+        let decoded = demodulate_fhdpsk_ex_with_config::<FftTestBackend>(&device, &data, false, &config);
+        assert_eq!(&decoded[..message.len()], message);
+    }
+}
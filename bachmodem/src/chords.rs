@@ -0,0 +1,288 @@
+/// Parallel Multi-Tone (Chord) Symbols
+///
+/// At moderate SNR one tone per symbol leaves most of the band idle. Chord
+/// mode sounds several tones of the alphabet at once, each an independent
+/// differential BPSK channel: voice k of symbol s carries one bit as a phase
+/// step of 0 or π against voice k of symbol s - 1. A chord of K voices moves
+/// K bits per symbol instead of one; there is no hopping, the voices stay
+/// on their tones.
+///
+/// Voices default to stacked thirds of the scale (`ChordConfig::tertian`:
+/// tones 0, 2, 4, ...), two scale steps apart, so their matched filters
+/// barely leak into each other. Each voice adds a fixed Newman phase
+/// (π k² / K) to keep the chord peaks apart, as the sounder comb does.
+///
+/// PAPR: K voices can add up to K times the peak of one tone. The data
+/// section is scaled so its peak equals a single-tone symbol's, keeping
+/// the transmitter's level setting (made on single-tone preambles) valid;
+/// each voice then gets roughly 1/K of the power of a single-tone symbol.
+///
+/// Frames use the FH-DPSK preamble, postamble and sync. One all-zero
+/// reference chord precedes the data.
+
+use std::f64::consts::PI;
+use std::fmt;
+use burn::tensor::{Tensor, Int, ElementConversion, backend::Backend};
+use crate::config::ModemConfig;
+use crate::dropout::{detect_dropouts_gpu, DROPOUT_THRESHOLD};
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{encode_bits, synchronize_data_start_with_config};
+use crate::wavelet::{generate_symbol_with_config, generate_bach_preamble_with_config, generate_bach_postamble_with_config, matched_filter_bank};
+use bachmodem_core::scrambler::{scramble_bits, whitening_sequence};
+
+/// Invalid chord
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChordError {
+    /// Fewer than two voices
+    TooFewVoices(usize),
+
+    /// Voice tone outside the alphabet
+    ToneOutOfRange { tone: usize, num_tones: usize },
+
+    /// The same tone twice
+    DuplicateTone(usize),
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChordError::TooFewVoices(n) => write!(f, "a chord needs at least 2 voices, got {}", n),
+            ChordError::ToneOutOfRange { tone, num_tones } => {
+                write!(f, "tone {} is outside the {}-tone alphabet", tone, num_tones)
+            }
+            ChordError::DuplicateTone(tone) => write!(f, "tone {} appears twice in the chord", tone),
+        }
+    }
+}
+
+impl std::error::Error for ChordError {}
+
+/// Tones sounded together in every chord symbol
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChordConfig {
+    tones: Vec<usize>,
+}
+
+impl ChordConfig {
+    /// Chord from explicit tone indices of the alphabet of `config`
+    pub fn new(tones: Vec<usize>, config: &ModemConfig) -> Result<Self, ChordError> {
+        if tones.len() < 2 {
+            return Err(ChordError::TooFewVoices(tones.len()));
+        }
+        for (i, &tone) in tones.iter().enumerate() {
+            if tone >= config.num_tones {
+                return Err(ChordError::ToneOutOfRange { tone, num_tones: config.num_tones });
+            }
+            if tones[..i].contains(&tone) {
+                return Err(ChordError::DuplicateTone(tone));
+            }
+        }
+        Ok(Self { tones })
+    }
+
+    /// `voices` stacked thirds from the lowest tone: tones 0, 2, 4, ...
+    pub fn tertian(voices: usize, config: &ModemConfig) -> Result<Self, ChordError> {
+        Self::new((0..voices).map(|k| 2 * k).collect(), config)
+    }
+
+    /// Tone index of every voice
+    pub fn tones(&self) -> &[usize] {
+        &self.tones
+    }
+
+    /// Voices (bits per chord symbol)
+    pub fn voices(&self) -> usize {
+        self.tones.len()
+    }
+
+    /// Data chords for `num_bits` bits (the last one zero-padded)
+    pub fn num_symbols(&self, num_bits: usize) -> usize {
+        num_bits.div_ceil(self.voices())
+    }
+
+    /// Newman phase offset of every voice (low crest factor)
+    fn voice_phases(&self) -> Vec<f64> {
+        let voices = self.voices();
+        (0..voices).map(|k| PI * (k * k) as f64 / voices as f64).collect()
+    }
+}
+
+impl Default for ChordConfig {
+    /// Four-voice tertian chord (C E G B on the default scale)
+    fn default() -> Self {
+        Self { tones: vec![0, 2, 4, 6] }
+    }
+}
+
+/// Peak-to-average power ratio of a waveform (dB)
+///
+/// ⚠️ **SYNC POINT**
+pub fn papr_db<B: Backend>(signal: &Tensor<B, 1>) -> f32 {
+    let peak = signal.clone().abs().max().into_scalar().elem::<f32>();
+    let mean_power = signal.clone().powf_scalar(2.0).mean().into_scalar().elem::<f32>();
+    10.0 * (peak * peak / mean_power.max(1e-20)).log10()
+}
+
+/// Modulates data as chord symbols on the tones of `chord`
+pub fn modulate_chords<B: Backend>(
+    device: &B::Device,
+    data_bytes: &[u8],
+    add_preamble: bool,
+    config: &ModemConfig,
+    chord: &ChordConfig,
+) -> Tensor<B, 1> {
+    let mut bits = encode_bits(data_bytes);
+    if config.scrambler {
+        scramble_bits(&mut bits);
+    }
+
+    // Reference chord, then each voice steps by bit * π
+    let voices = chord.voices();
+    let mut phases = chord.voice_phases();
+    let mut symbols = Vec::with_capacity(chord.num_symbols(bits.len()) + 1);
+    let chord_symbol = |phases: &[f64]| {
+        chord.tones().iter()
+            .zip(phases)
+            .map(|(&tone, &phase)| generate_symbol_with_config::<B>(device, tone, phase, config))
+            .reduce(|sum, voice| sum + voice)
+            .expect("a chord has voices")
+    };
+    symbols.push(chord_symbol(&phases));
+    for symbol_bits in bits.chunks(voices) {
+        for (phase, &bit) in phases.iter_mut().zip(symbol_bits) {
+            *phase += bit as f64 * PI;
+        }
+        symbols.push(chord_symbol(&phases));
+    }
+
+    // PAPR normalization: data peak = a single-tone symbol's peak
+    let data = Tensor::cat(symbols, 0);
+    let single_peak = chord.tones().iter().map(|&t| config.tone_gain(t)).fold(0.0, f64::max) as f32;
+    let data_peak = data.clone().abs().max().clamp_min(1e-20);
+    let data = data.div(data_peak).mul_scalar(single_peak);
+
+    let mut parts = Vec::new();
+    if add_preamble {
+        parts.push(generate_bach_preamble_with_config::<B>(device, config));
+    }
+    parts.push(data);
+    if add_preamble {
+        parts.push(generate_bach_postamble_with_config::<B>(device, config));
+    }
+    Tensor::cat(parts, 0)
+}
+
+/// Per-voice soft demodulation of chord symbols
+///
+/// Returns LLRs [data symbols, voices] (positive -> bit 0) for `num_bits`
+/// bits; bit i is row i / voices, column i % voices. Chords past the end of
+/// the signal or in a dropout (either chord of a differential pair) are
+/// erased (0.0); if synchronization fails everything is erased.
+///
+/// ⚠️ **SYNC POINT**: Synchronization and dropout flags
+pub fn demodulate_chords_soft<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    chord: &ChordConfig,
+    num_bits: usize,
+) -> Tensor<B, 2> {
+    let voices = chord.voices();
+    let data_symbols = chord.num_symbols(num_bits);
+    let erased = || Tensor::zeros([data_symbols, voices], device);
+
+    let start = if use_sync {
+        match synchronize_data_start_with_config::<B>(device, signal, config) {
+            Some(start) => start,
+            None => return erased(),
+        }
+    } else {
+        0
+    };
+
+    // Reference chord plus the data chords the signal covers
+    let symbol_len = config.symbol_samples();
+    let available = signal.dims()[0].saturating_sub(start) / symbol_len;
+    let num_symbols = available.min(data_symbols + 1);
+    if num_symbols < 2 {
+        return erased();
+    }
+    let symbols_batch = signal.clone()
+        .slice([start..start + num_symbols * symbol_len])
+        .reshape([num_symbols, symbol_len]);
+    let dropped = detect_dropouts_gpu(&symbols_batch, DROPOUT_THRESHOLD);
+
+    // Every chord against its voices' filters: [NumSymbols, Voices]
+    let tones: Vec<i32> = chord.tones().iter().map(|&t| t as i32).collect();
+    let tones = Tensor::<B, 1, Int>::from_ints(tones.as_slice(), device);
+    let corr = matched_filter_bank::<B>(device, config)
+        .map(|b| symbols_batch.clone().matmul(b.select(0, tones.clone()).transpose()));
+
+    // Differential per voice: LLR = Re(curr · conj(prev)) / |prev|
+    let pairs = num_symbols - 1;
+    let curr = corr.clone().map(|c| c.slice([1..num_symbols]));
+    let prev = corr.map(|c| c.slice([0..pairs]));
+    let amp_prev = prev.clone().abs();
+    let mut llrs = curr.dot_conj_re(prev) / (amp_prev + 1e-6);
+
+    if config.scrambler {
+        let signs: Vec<f32> = whitening_sequence(pairs * voices).iter()
+            .map(|&w| if w == 1 { -1.0 } else { 1.0 })
+            .collect();
+        llrs = llrs * Tensor::<B, 1>::from_floats(signs.as_slice(), device).reshape([pairs, voices]);
+    }
+
+    let mask: Vec<f32> = (0..pairs)
+        .map(|s| if dropped[s] || dropped[s + 1] { 0.0 } else { 1.0 })
+        .collect();
+    let llrs = llrs * Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([pairs, 1]);
+
+    if pairs < data_symbols {
+        Tensor::cat(vec![llrs, Tensor::zeros([data_symbols - pairs, voices], device)], 0)
+    } else {
+        llrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+
+    #[test]
+    fn test_chord_validation() {
+        let config = ModemConfig::default();
+        assert_eq!(ChordConfig::tertian(4, &config), Ok(ChordConfig::default()));
+        assert_eq!(ChordConfig::tertian(8, &config).unwrap().tones()[7], 14);
+        assert_eq!(ChordConfig::tertian(9, &config), Err(ChordError::ToneOutOfRange { tone: 16, num_tones: 16 }));
+        assert_eq!(ChordConfig::new(vec![3], &config), Err(ChordError::TooFewVoices(1)));
+        assert_eq!(ChordConfig::new(vec![3, 5, 3], &config), Err(ChordError::DuplicateTone(3)));
+        assert_eq!(ChordConfig::default().num_symbols(10), 3);
+    }
+
+    #[test]
+    fn test_chord_roundtrip_and_peak() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let chord = ChordConfig::default();
+        let payload = b"FOUR VOICES";
+        let num_bits = payload.len() * 8;
+
+        // Data section peak matches a single-tone symbol
+        let data = modulate_chords::<TestBackend>(&device, payload, false, &config, &chord);
+        let peak = data.clone().abs().max().into_scalar().elem::<f32>();
+        assert!((peak - 1.0).abs() < 1e-4, "peak {}", peak);
+        assert_eq!(data.dims()[0], (chord.num_symbols(num_bits) + 1) * config.symbol_samples());
+
+        let clean = modulate_chords::<TestBackend>(&device, payload, true, &config, &chord);
+        let noise = gaussian_noise::<TestBackend, _>(&device, clean.dims()[0], 0.05, &mut SplitMix64::new(19));
+        let llrs = demodulate_chords_soft::<TestBackend>(&device, &(clean + noise), true, &config, &chord, num_bits);
+        assert_eq!(llrs.dims(), [chord.num_symbols(num_bits), chord.voices()]);
+
+        let llrs: Vec<f32> = llrs.into_data().to_vec().unwrap();
+        let bits: Vec<u8> = llrs[..num_bits].iter().map(|&l| (l < 0.0) as u8).collect();
+        assert_eq!(bits, encode_bits(payload));
+    }
+}
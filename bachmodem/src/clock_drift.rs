@@ -0,0 +1,154 @@
+/// Sample Clock Drift Tracking
+///
+/// Transmitter and receiver soundcards each run off their own crystal,
+/// typically 10-100 ppm apart. Over a 30-second frame 50 ppm moves the
+/// last symbols 12 samples from where dead reckoning puts their windows,
+/// and what matters to DPSK is the slip between a symbol and its reference
+/// `lag` symbols earlier: a sample at 1 kHz is already 0.8 rad.
+///
+/// Flourish re-sync (see `modulation`) snaps the symbol clock back at each
+/// flourish, but the error grows again up to the next. `ClockDriftTracker`
+/// keeps the anchors instead: the transmitter-clock ("nominal") position of
+/// each, where it was found in the capture, and the clock rate fitted
+/// through them by least squares from the data start. Windows between
+/// anchors are then placed on the fitted rate from the last anchor before
+/// them, not on the nominal one.
+///
+/// When the frame length is known the postamble is one more anchor: it
+/// sits a known number of nominal samples after the data start, so a frame
+/// without flourishes still gets its rate (`measure_postamble_drift`),
+/// before the first window is cut.
+///
+/// Opt-in with `ModemConfig::with_clock_drift_tracking`; used by the soft
+/// demodulators.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modulation::measure_flourish_offset;
+use crate::wavelet::generate_bach_postamble_with_config;
+
+/// Largest clock rate error believed (ppm); anchors implying more are noise
+pub const MAX_CLOCK_DRIFT_PPM: f64 = 500.0;
+
+/// Symbol timing anchors of one frame and the clock rate through them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClockDriftTracker {
+    /// (nominal position, measured position) in samples after the data start
+    anchors: Vec<(f64, f64)>,
+}
+
+impl ClockDriftTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that nominal position `nominal` was found at `measured`
+    ///
+    /// Anchors implying more than `MAX_CLOCK_DRIFT_PPM` since the data
+    /// start are dropped; returns whether it was kept.
+    pub fn record(&mut self, nominal: usize, measured: usize) -> bool {
+        let (nominal, measured) = (nominal as f64, measured as f64);
+        if nominal <= 0.0 || ((measured - nominal) / nominal).abs() * 1e6 > MAX_CLOCK_DRIFT_PPM {
+            return false;
+        }
+        let index = self.anchors.partition_point(|&(n, _)| n < nominal);
+        self.anchors.insert(index, (nominal, measured));
+        true
+    }
+
+    pub fn num_anchors(&self) -> usize {
+        self.anchors.len()
+    }
+
+    /// Receiver samples per transmitter sample, least squares through the
+    /// data start (1.0 without anchors)
+    pub fn rate(&self) -> f64 {
+        let (nm, nn) = self.anchors.iter().fold((0.0, 0.0), |(nm, nn), &(n, m)| (nm + n * m, nn + n * n));
+        if nn > 0.0 { nm / nn } else { 1.0 }
+    }
+
+    /// Clock error of the receiver against the transmitter (ppm, positive
+    /// = receiver samples faster)
+    pub fn ppm(&self) -> f64 {
+        (self.rate() - 1.0) * 1e6
+    }
+
+    /// Capture position of nominal position `nominal`: the last anchor at
+    /// or before it (the data start if none), advanced at the fitted rate
+    pub fn position(&self, nominal: usize) -> usize {
+        let nominal = nominal as f64;
+        let (base_nominal, base_measured) = self.anchors.iter()
+            .take_while(|&&(n, _)| n <= nominal)
+            .last()
+            .copied()
+            .unwrap_or((0.0, 0.0));
+        (base_measured + self.rate() * (nominal - base_nominal)).round().max(0.0) as usize
+    }
+}
+
+/// Postamble position of a frame whose data ends `nominal_end` nominal
+/// samples after the start of `signal_data`, searched within the largest
+/// believed drift; None if it isn't distinguishable from noise
+///
+/// ⚠️ **SYNC POINT**: Downloads the correlation peak
+pub fn measure_postamble_drift<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal_data: &Tensor<B, 1>,
+    nominal_end: usize,
+    config: &ModemConfig,
+) -> Option<usize> {
+    let postamble = generate_bach_postamble_with_config::<B>(device, config);
+    let search = (nominal_end as f64 * MAX_CLOCK_DRIFT_PPM * 1e-6).ceil() as usize + config.preamble_note_samples() / 2;
+    let (offset, rho) = measure_flourish_offset(device, signal_data, &postamble, nominal_end, search)?;
+    let measured = (nominal_end as isize + offset).max(0) as usize;
+    println!("  [Drift] Postamble {:+} samples from nominal (ρ = {:.3})", offset, rho);
+    Some(measured)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duplex::resample_linear;
+    use crate::modulation::{demodulate_fhdpsk_soft_erasures_with_config, encode_bits, modulate_fhdpsk_with_config};
+    use crate::wavelet::FS;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_tracker_extrapolates_from_last_anchor() {
+        let mut drift = ClockDriftTracker::new();
+        assert_eq!(drift.position(8000), 8000);
+
+        // 100 ppm fast, with a 3-sample slip before the second anchor
+        assert!(drift.record(100_000, 100_010));
+        assert!(drift.record(200_000, 200_023));
+        assert!(!drift.record(300_000, 301_000));
+        assert_eq!(drift.num_anchors(), 2);
+        assert!((drift.ppm() - 112.0).abs() < 1.0, "{} ppm", drift.ppm());
+
+        assert_eq!(drift.position(50_000), 50_006);
+        assert_eq!(drift.position(250_000), 250_029);
+    }
+
+    #[test]
+    fn test_drifting_clock_decodes_with_postamble_anchor() {
+        let device = Default::default();
+        let data = b"Crystal drift";
+        let bits = encode_bits(data);
+        let base = ModemConfig::default();
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, data, true, &base);
+        let samples: Vec<f32> = tx.into_data().to_vec().unwrap();
+
+        // Receiver clock 150 ppm fast: the last symbols land ~40 samples late
+        let drifted = resample_linear(&samples, FS, FS * (1.0 + 150e-6));
+        let rx = Tensor::<TestBackend, 1>::from_floats(drifted.as_slice(), &device);
+
+        let errors = |config: &ModemConfig| {
+            let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<TestBackend>(&device, &rx, true, config, bits.len())
+                .into_data().to_vec().unwrap();
+            bits.iter().zip(&llrs).filter(|(&b, &l)| (l <= 0.0) as u8 != b).count()
+        };
+        assert!(errors(&base) > 0);
+        assert_eq!(errors(&base.clone().with_clock_drift_tracking(true)), 0);
+    }
+}
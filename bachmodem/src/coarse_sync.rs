@@ -0,0 +1,313 @@
+/// Two-Stage Coarse/Fine Synchronization
+///
+/// `measure_sync` correlates the whole capture against the preamble at the
+/// full rate. Decimating first was tried and dropped: the top tone (1175 Hz)
+/// is above the Nyquist frequency of a naively decimated signal, so noise
+/// and tones folded onto each other. For an hour-long recording the full
+/// rate correlation is a 32M-point FFT, though.
+///
+/// The coarse stage decimates properly. The tones only occupy a few hundred
+/// Hz, so the capture is brought down to complex baseband around them:
+/// - the capture is cut into overlapping `COARSE_BLOCK`-sample blocks,
+///   transformed in batches on the device
+/// - each block keeps only the FFT bins of the tone band (with a raised
+///   cosine taper to the edges of the kept span) and an inverse FFT over
+///   just those bins yields the band at the sample rate / `factor`: an ideal low-pass and
+///   decimation in one step, nothing outside the band can alias
+/// - the block edges, where the circular filtering wraps, are dropped
+///
+/// The preamble goes through the same plan, the envelope of their complex
+/// correlation peaks within a few decimated samples of the preamble, and
+/// the fine stage runs the ordinary full-rate `measure_sync` on a window of
+/// ± `FINE_SEARCH_STEPS` decimated samples around it. Its correlation is
+/// reported as is, so the detection thresholds keep their meaning; the
+/// peak-to-noise ratio is the coarse stage's, over the whole capture.
+///
+/// Opt-in with `ModemConfig::with_two_stage_sync`.
+
+use std::f64::consts::PI;
+use burn::tensor::{Tensor, ElementConversion, backend::Backend};
+use crate::complex::ComplexTensor;
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{measure_sync_full_rate, SyncMetrics};
+use crate::wavelet::{generate_bach_preamble_with_config, FS};
+
+/// Block length of the decimating FFTs (samples, power of two)
+pub const COARSE_BLOCK: usize = 65536;
+
+/// Largest decimation factor
+pub const MAX_DECIMATION: usize = 16;
+
+/// Fine search half-width (decimated samples)
+pub const FINE_SEARCH_STEPS: usize = 4;
+
+/// Band kept around the tones (Hz): chirps, filter transition
+const COARSE_BAND_MARGIN_HZ: f64 = 50.0;
+
+/// Decimated rate over kept bandwidth, room for the taper
+const COARSE_TRANSITION: f64 = 1.25;
+
+/// Blocks transformed per batch
+const COARSE_BLOCK_BATCH: usize = 32;
+
+/// Samples dropped at each end of a block (circular filter wrap-around)
+const BLOCK_EDGE: usize = COARSE_BLOCK / 8;
+
+/// Samples each block contributes
+const BLOCK_HOP: usize = COARSE_BLOCK - 2 * BLOCK_EDGE;
+
+/// How a capture is brought down to complex baseband
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecimationPlan {
+    /// Input samples per output sample (power of two)
+    pub factor: usize,
+
+    /// First block FFT bin kept: baseband DC
+    first_bin: usize,
+
+    /// Gain of each kept bin
+    taper: Vec<f32>,
+
+    /// Input sample rate (Hz)
+    sample_rate: f64,
+}
+
+impl DecimationPlan {
+    /// Plan keeping `low_hz`..`high_hz` flat at `FS`; None if the band is
+    /// too wide to decimate by at least 2
+    pub fn for_band(low_hz: f64, high_hz: f64) -> Option<Self> {
+        Self::for_band_up_to(low_hz, high_hz, MAX_DECIMATION, FS)
+    }
+
+    /// `for_band` at `sample_rate`, decimating by at most `max_factor`
+    /// (rounded down to a power of two)
+    pub fn for_band_up_to(low_hz: f64, high_hz: f64, max_factor: usize, sample_rate: f64) -> Option<Self> {
+        let width = high_hz - low_hz;
+        let max_factor = max_factor.min(MAX_DECIMATION);
+        let factor = (1..=max_factor.checked_ilog2()?)
+            .map(|shift| 1usize << shift)
+            .filter(|&factor| sample_rate / factor as f64 >= width * COARSE_TRANSITION)
+            .last()?;
+        let bins = COARSE_BLOCK / factor;
+
+        // A multiple of 4 keeps the baseband phase continuous across blocks
+        // (each hop is 3/4 of a block)
+        let center = (low_hz + high_hz) / 2.0;
+        let first_bin = (((center - sample_rate / (2.0 * factor as f64)) * COARSE_BLOCK as f64 / sample_rate).floor().max(0.0) as usize)
+            .min(COARSE_BLOCK / 2 - bins);
+        let first_bin = first_bin - first_bin % 4;
+
+        let bin_hz = sample_rate / COARSE_BLOCK as f64;
+        let (first_hz, last_hz) = (first_bin as f64 * bin_hz, (first_bin + bins - 1) as f64 * bin_hz);
+        let taper = (0..bins)
+            .map(|i| {
+                let f = (first_bin + i) as f64 * bin_hz;
+                let gain = if f < low_hz {
+                    0.5 * (1.0 - (PI * (f - first_hz) / (low_hz - first_hz)).cos())
+                } else if f > high_hz {
+                    0.5 * (1.0 - (PI * (last_hz - f) / (last_hz - high_hz)).cos())
+                } else {
+                    1.0
+                };
+                gain as f32
+            })
+            .collect();
+        Some(Self { factor, first_bin, taper, sample_rate })
+    }
+
+    /// Plan for the tone band of `config`, with room for chirps and AFC,
+    /// decimating by at most `config.sync.max_decimation`
+    pub fn for_config(config: &ModemConfig) -> Option<Self> {
+        let frequencies = config.frequencies();
+        let low = frequencies.iter().cloned().fold(f64::INFINITY, f64::min);
+        let high = frequencies.iter().cloned().fold(0.0, f64::max);
+        let margin = COARSE_BAND_MARGIN_HZ + config.chirp_span_hz / 2.0 + config.afc_range_hz;
+        Self::for_band_up_to((low - margin).max(0.0), (high + margin).min(config.sample_rate / 2.0), config.sync.max_decimation, config.sample_rate)
+    }
+
+    /// Frequency that lands on baseband DC (Hz)
+    pub fn center_offset_hz(&self) -> f64 {
+        self.first_bin as f64 * self.sample_rate / COARSE_BLOCK as f64
+    }
+}
+
+/// `signal` as complex baseband at the plan's sample rate / `plan.factor`: [ceil(N / factor)]
+///
+/// **NO SYNC POINT**
+pub fn decimate_to_baseband<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    plan: &DecimationPlan,
+) -> ComplexTensor<B, 1> {
+    let n = signal.dims()[0];
+    let factor = plan.factor;
+    let bins = COARSE_BLOCK / factor;
+    let (edge, hop) = (BLOCK_EDGE / factor, BLOCK_HOP / factor);
+
+    let num_blocks = n.div_ceil(BLOCK_HOP).max(1);
+    let padded_len = num_blocks * BLOCK_HOP + 2 * BLOCK_EDGE;
+    let padded = Tensor::cat(vec![
+        Tensor::zeros([BLOCK_EDGE], device),
+        signal.clone(),
+        Tensor::zeros([padded_len - BLOCK_EDGE - n], device),
+    ], 0);
+    let taper = Tensor::<B, 1>::from_floats(plan.taper.as_slice(), device).reshape([1, bins]);
+
+    let (re, im): (Vec<_>, Vec<_>) = (0..num_blocks)
+        .step_by(COARSE_BLOCK_BATCH)
+        .map(|first| {
+            let rows: Vec<Tensor<B, 1>> = (first..(first + COARSE_BLOCK_BATCH).min(num_blocks))
+                .map(|b| padded.clone().slice([b * BLOCK_HOP..b * BLOCK_HOP + COARSE_BLOCK]))
+                .collect();
+            let num_rows = rows.len();
+            ComplexTensor::from_real(Tensor::stack::<2>(rows, 0))
+                .fft()
+                .map(|t| t.slice([0..num_rows, plan.first_bin..plan.first_bin + bins]))
+                .mul_real(taper.clone())
+                .ifft()
+                .map(|t| t.slice([0..num_rows, edge..edge + hop]).reshape([num_rows * hop]))
+                .into_parts()
+        })
+        .unzip();
+
+    let out_len = n.div_ceil(factor);
+    ComplexTensor::new(Tensor::cat(re, 0), Tensor::cat(im, 0)).map(|t| t.slice([0..out_len]))
+}
+
+/// Squared envelope of the complex cross-correlation of `signal` with
+/// `reference`, valid lags only: [N - M + 1]
+///
+/// **NO SYNC POINT**
+pub(crate) fn complex_correlation_power<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: ComplexTensor<B, 1>,
+    reference: ComplexTensor<B, 1>,
+) -> Tensor<B, 1> {
+    let (n, m) = (signal.dims()[0], reference.dims()[0]);
+    let fft_len = n.next_power_of_two();
+    let pad = |c: ComplexTensor<B, 1>, len: usize| {
+        c.map(|t| Tensor::cat(vec![t, Tensor::zeros([fft_len - len], device)], 0).reshape([1, fft_len]))
+    };
+    (pad(signal, n).fft() * pad(reference, m).fft().conj())
+        .ifft()
+        .norm_sqr()
+        .reshape([fft_len])
+        .slice([0..n - m + 1])
+}
+
+/// Strongest preamble peak of the coarse stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoarsePeak {
+    /// Preamble start (full-rate samples, within `factor`)
+    pub position: usize,
+
+    /// Squared-correlation peak over its mean, on the real-correlation scale
+    pub peak_to_noise: f32,
+}
+
+/// Coarse preamble search on the decimated baseband; None if the signal is
+/// shorter than the preamble
+///
+/// ⚠️ **SYNC POINT**: Downloads the peak
+pub fn coarse_sync<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    preamble: &Tensor<B, 1>,
+    plan: &DecimationPlan,
+) -> Option<CoarsePeak> {
+    let baseband = decimate_to_baseband(device, signal, plan);
+    let reference = decimate_to_baseband(device, preamble, plan);
+    if baseband.dims()[0] < reference.dims()[0] {
+        return None;
+    }
+
+    let power = complex_correlation_power(device, baseband, reference);
+    let (peak, index) = power.clone().max_dim_with_indices(0);
+    let stats: Vec<f32> = Tensor::cat(vec![peak, power.mean()], 0).into_data().to_vec().unwrap();
+    let position = index.into_scalar().elem::<i32>() as usize * plan.factor;
+
+    // The envelope's noise power is the in-phase plus the quadrature part:
+    // twice the real correlation's for the same peak
+    let peak_to_noise = 2.0 * stats[0] / (stats[1] + 1e-10);
+    println!("    [Sync] Coarse: decimation {}, peak at {}, P/N: {:.2}", plan.factor, position, peak_to_noise);
+    Some(CoarsePeak { position, peak_to_noise })
+}
+
+/// `measure_sync` in two stages: `coarse_sync`, then the full-rate search
+/// around its peak (full rate throughout if the band doesn't decimate)
+///
+/// ⚠️ **SYNC POINT**: Downloads the coarse and the fine peak
+pub(crate) fn measure_sync_two_stage<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<SyncMetrics> {
+    let Some(plan) = DecimationPlan::for_config(config) else {
+        return measure_sync_full_rate::<B>(device, signal, config);
+    };
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let (n, m) = (signal.dims()[0], preamble.dims()[0]);
+    if n < m {
+        return None;
+    }
+
+    let coarse = coarse_sync(device, signal, &preamble, &plan)?;
+    let search = FINE_SEARCH_STEPS * plan.factor;
+    let start = coarse.position.saturating_sub(search);
+    let end = (coarse.position + search + m).min(n);
+    let fine = measure_sync_full_rate::<B>(device, &signal.clone().slice([start..end]), config)?;
+
+    Some(SyncMetrics {
+        position: start + fine.position,
+        correlation: fine.correlation,
+        peak_to_noise: coarse.peak_to_noise,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::{synchronize_data_start_with_config, modulate_fhdpsk_with_config};
+    use crate::TestBackend;
+
+    #[test]
+    fn test_decimation_rejects_out_of_band_tones() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let plan = DecimationPlan::for_config(&config).unwrap();
+        assert!(plan.factor >= 2);
+
+        let tone = |hz: f64| {
+            let samples: Vec<f32> = (0..200_000).map(|t| (2.0 * PI * hz * t as f64 / FS).sin() as f32).collect();
+            let baseband = decimate_to_baseband(&device, &Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device), &plan);
+            assert_eq!(baseband.dims()[0], 200_000usize.div_ceil(plan.factor));
+            baseband.norm_sqr().mean().into_scalar().elem::<f32>()
+        };
+
+        // A tone at 3 kHz would fold into the band with plain decimation
+        let middle = config.frequencies().iter().sum::<f64>() / config.num_tones as f64;
+        let (in_band, out_of_band) = (tone(middle), tone(3000.0));
+        assert!(out_of_band < 1e-4 * in_band, "in band {} out of band {}", in_band, out_of_band);
+    }
+
+    #[test]
+    fn test_two_stage_sync_matches_full_rate() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let frame = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Needle in a haystack", true, &config);
+        let frame_len = frame.dims()[0];
+
+        // 20 s of noise either side, the frame off any block boundary
+        let lead_in = 160_123;
+        let total = lead_in + frame_len + 160_000;
+        let mut rng = SplitMix64::new(3);
+        let rx = Tensor::cat(vec![Tensor::zeros([lead_in], &device), frame, Tensor::zeros([160_000], &device)], 0)
+            + gaussian_noise::<TestBackend, _>(&device, total, 0.5, &mut rng);
+
+        let full_rate = synchronize_data_start_with_config::<TestBackend>(&device, &rx, &config).unwrap();
+        let two_stage = synchronize_data_start_with_config::<TestBackend>(&device, &rx, &config.clone().with_two_stage_sync(true)).unwrap();
+        assert_eq!(two_stage, full_rate);
+    }
+}
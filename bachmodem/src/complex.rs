@@ -0,0 +1,291 @@
+/// Complex Tensors
+///
+/// Wavelets, matched filters, FFTs and correlations all work on complex
+/// values, which used to travel as separate real and imaginary tensors with
+/// the arithmetic spelled out at every use (and a conjugate sign easy to
+/// lose). `ComplexTensor` keeps the pair together:
+/// - arithmetic (`+`, `-`, `*`), `conj`, `abs`, `norm_sqr`, `angle`
+/// - `map` applies a real-linear operation (slice, reshape, sum, a product
+///   with a real matrix) to both parts
+/// - `fft` / `ifft` along the last axis of a batch, on the `FftBackend`
+///   kernels
+/// - `shift_frequency` moves a real signal up or down in frequency through
+///   its analytic signal (AFC, offset search, retry ladder)
+///
+/// The parts stay public, so code that needs a single part (or a kernel
+/// that takes the pair) doesn't need conversions.
+
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+use burn::tensor::{Tensor, TensorPrimitive, backend::Backend};
+use crate::fft_correlation::FftBackend;
+
+/// Complex tensor stored as real and imaginary parts of equal shape
+#[derive(Clone, Debug)]
+pub struct ComplexTensor<B: Backend, const D: usize> {
+    pub re: Tensor<B, D>,
+    pub im: Tensor<B, D>,
+}
+
+impl<B: Backend, const D: usize> ComplexTensor<B, D> {
+    pub fn new(re: Tensor<B, D>, im: Tensor<B, D>) -> Self {
+        debug_assert_eq!(re.dims(), im.dims(), "real and imaginary parts differ in shape");
+        Self { re, im }
+    }
+
+    /// Real tensor with a zero imaginary part
+    pub fn from_real(re: Tensor<B, D>) -> Self {
+        let im = re.zeros_like();
+        Self { re, im }
+    }
+
+    pub fn zeros(shape: [usize; D], device: &B::Device) -> Self {
+        Self { re: Tensor::zeros(shape, device), im: Tensor::zeros(shape, device) }
+    }
+
+    pub fn dims(&self) -> [usize; D] {
+        self.re.dims()
+    }
+
+    pub fn into_parts(self) -> (Tensor<B, D>, Tensor<B, D>) {
+        (self.re, self.im)
+    }
+
+    /// Apply a real-linear operation (slice, reshape, sum, transpose,
+    /// product with a real tensor, ...) to both parts
+    pub fn map<const D2: usize>(self, f: impl Fn(Tensor<B, D>) -> Tensor<B, D2>) -> ComplexTensor<B, D2> {
+        ComplexTensor { re: f(self.re), im: f(self.im) }
+    }
+
+    pub fn conj(self) -> Self {
+        Self { re: self.re, im: self.im.neg() }
+    }
+
+    /// |z|²
+    pub fn norm_sqr(self) -> Tensor<B, D> {
+        self.re.powf_scalar(2.0) + self.im.powf_scalar(2.0)
+    }
+
+    /// |z|
+    pub fn abs(self) -> Tensor<B, D> {
+        self.norm_sqr().sqrt()
+    }
+
+    /// Argument in (-π, π]
+    pub fn angle(self) -> Tensor<B, D> {
+        self.im.atan2(self.re)
+    }
+
+    /// Product with a real tensor (e.g. a mask or gains)
+    pub fn mul_real(self, rhs: Tensor<B, D>) -> Self {
+        Self { re: self.re * rhs.clone(), im: self.im * rhs }
+    }
+
+    pub fn mul_scalar(self, rhs: f32) -> Self {
+        self.map(|t| t.mul_scalar(rhs))
+    }
+
+    /// Re(a · conj(b)): the real part of the correlation of two phasors
+    pub fn dot_conj_re(self, rhs: Self) -> Tensor<B, D> {
+        self.re * rhs.re + self.im * rhs.im
+    }
+
+    /// Im(a · conj(b)): the quadrature part of the same correlation
+    pub fn dot_conj_im(self, rhs: Self) -> Tensor<B, D> {
+        self.im * rhs.re - self.re * rhs.im
+    }
+}
+
+impl<B: Backend> ComplexTensor<B, 2> {
+    /// Complex matrix product
+    pub fn matmul(self, rhs: Self) -> Self {
+        Self {
+            re: self.re.clone().matmul(rhs.re.clone()) - self.im.clone().matmul(rhs.im.clone()),
+            im: self.re.matmul(rhs.im) + self.im.matmul(rhs.re),
+        }
+    }
+
+    pub fn transpose(self) -> Self {
+        self.map(|t| t.transpose())
+    }
+}
+
+impl<B: Backend + FftBackend> ComplexTensor<B, 2> {
+    /// Forward FFT of every row; the row length must be a power of two
+    ///
+    /// **NO SYNC POINT**
+    pub fn fft(self) -> Self {
+        let n = self.dims()[1];
+        let (re, im) = B::fft_1d_batch_impl(float_primitive(self.re), float_primitive(self.im), n);
+        Self {
+            re: Tensor::from_primitive(TensorPrimitive::Float(re)),
+            im: Tensor::from_primitive(TensorPrimitive::Float(im)),
+        }
+    }
+
+    /// Inverse FFT of every row (scaled by 1/N)
+    ///
+    /// **NO SYNC POINT**
+    pub fn ifft(self) -> Self {
+        let n = self.dims()[1];
+        // IFFT(x) = conj(FFT(conj(x))) / N
+        self.conj().fft().conj().mul_scalar(1.0 / n as f32)
+    }
+}
+
+impl<B: Backend + FftBackend> ComplexTensor<B, 1> {
+    /// Analytic signal of a real signal
+    ///
+    /// Negative frequencies are removed with a zero-padded FFT (DC and
+    /// Nyquist kept once, positive frequencies doubled), so the real part
+    /// is the signal again and the imaginary part its Hilbert transform.
+    ///
+    /// **NO SYNC POINT**
+    pub fn analytic(signal: Tensor<B, 1>) -> Self {
+        let device = signal.device();
+        let n = signal.dims()[0];
+        let fft_len = n.next_power_of_two();
+
+        let gain: Vec<f32> = (0..fft_len)
+            .map(|k| if k == 0 || k == fft_len / 2 { 1.0 } else if k < fft_len / 2 { 2.0 } else { 0.0 })
+            .collect();
+        let gain = Tensor::<B, 1>::from_floats(gain.as_slice(), &device).reshape([1, fft_len]);
+
+        let padded = Tensor::cat(vec![signal, Tensor::zeros([fft_len - n], &device)], 0).reshape([1, fft_len]);
+        ComplexTensor::from_real(padded).fft()
+            .mul_real(gain)
+            .ifft()
+            .map(|t| t.slice([0..1, 0..n]).reshape([n]))
+    }
+}
+
+/// `signal`, sampled at `sample_rate` (Hz), moved up by `offset_hz`
+///
+/// Single-sideband shift: the analytic signal (`ComplexTensor::analytic`)
+/// is multiplied by exp(j2π·offset·t) and its real
+/// part kept. Phases are reduced modulo one cycle in f64 on the host.
+///
+/// **NO SYNC POINT**
+pub fn shift_frequency<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    offset_hz: f64,
+    sample_rate: f64,
+) -> Tensor<B, 1> {
+    if offset_hz == 0.0 {
+        return signal.clone();
+    }
+    let n = signal.dims()[0];
+    let (re, im) = ComplexTensor::analytic(signal.clone()).into_parts();
+
+    let (cos, sin): (Vec<f32>, Vec<f32>) = (0..n)
+        .map(|i| {
+            let phase = 2.0 * PI * (offset_hz * i as f64 / sample_rate).rem_euclid(1.0);
+            (phase.cos() as f32, phase.sin() as f32)
+        })
+        .unzip();
+    re * Tensor::from_floats(cos.as_slice(), device) - im * Tensor::from_floats(sin.as_slice(), device)
+}
+
+fn float_primitive<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> B::FloatTensorPrimitive {
+    match tensor.into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    }
+}
+
+impl<B: Backend, const D: usize> Add for ComplexTensor<B, D> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self { re: self.re + rhs.re, im: self.im + rhs.im }
+    }
+}
+
+impl<B: Backend, const D: usize> Sub for ComplexTensor<B, D> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+impl<B: Backend, const D: usize> Mul for ComplexTensor<B, D> {
+    type Output = Self;
+
+    // (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            re: self.re.clone() * rhs.re.clone() - self.im.clone() * rhs.im.clone(),
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+    use crate::wavelet::FS;
+
+    fn values(tensor: Tensor<TestBackend, 1>) -> Vec<f32> {
+        tensor.into_data().to_vec().unwrap()
+    }
+
+    #[test]
+    fn test_complex_arithmetic() {
+        let device = Default::default();
+        let z = |re: [f32; 2], im: [f32; 2]| ComplexTensor::<TestBackend, 1>::new(
+            Tensor::from_floats(re, &device),
+            Tensor::from_floats(im, &device),
+        );
+        let a = z([1.0, 0.0], [2.0, -1.0]);
+        let b = z([3.0, 2.0], [-1.0, 0.0]);
+
+        // (1 + 2i)(3 - i) = 5 + 5i, (-i)(2) = -2i
+        let product = a.clone() * b.clone();
+        assert_eq!(values(product.re), vec![5.0, 0.0]);
+        assert_eq!(values(product.im), vec![5.0, -2.0]);
+        assert_eq!(values(a.clone().dot_conj_re(b.clone())), values((a.clone() * b.conj()).re));
+
+        assert_eq!(values(a.clone().norm_sqr()), vec![5.0, 1.0]);
+        let angle = values(a.angle());
+        assert!((angle[0] - 2f32.atan()).abs() < 1e-6 && (angle[1] + std::f32::consts::FRAC_PI_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fft_round_trip() {
+        let device = Default::default();
+        let n = 64;
+
+        // A single complex exponential lands in one bin
+        let k = 5;
+        let phase: Vec<f32> = (0..n).map(|t| 2.0 * std::f32::consts::PI * (k * t) as f32 / n as f32).collect();
+        let phase = Tensor::<TestBackend, 1>::from_floats(phase.as_slice(), &device).reshape([1, n]);
+        let tone = ComplexTensor::new(phase.clone().cos(), phase.sin());
+
+        let spectrum = tone.clone().fft();
+        let magnitude = values(spectrum.clone().abs().reshape([n]));
+        assert!((magnitude[k] - n as f32).abs() < 1e-3);
+        assert!(magnitude.iter().enumerate().all(|(i, &m)| i == k || m < 1e-3));
+
+        let back = spectrum.ifft() - tone;
+        assert!(values(back.norm_sqr().reshape([n])).iter().all(|&e| e < 1e-9));
+    }
+
+    #[test]
+    fn test_frequency_shift_moves_tone() {
+        let device = Default::default();
+        let samples: Vec<f32> = (0..FS as usize)
+            .map(|t| (2.0 * PI * 600.0 * t as f64 / FS).sin() as f32)
+            .collect();
+        let signal = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device);
+
+        let shifted: Vec<f32> = shift_frequency(&device, &signal, 7.0, FS).into_data().to_vec().unwrap();
+        let expected = |t: usize| (2.0 * PI * 607.0 * t as f64 / FS).sin() as f32;
+
+        // Away from the edges, where the zero padding wraps around
+        let max_err = (1000..7000).map(|t| (shifted[t] - expected(t)).abs()).fold(0.0f32, f32::max);
+        assert!(max_err < 0.01, "max error {}", max_err);
+    }
+}
@@ -0,0 +1,1017 @@
+/// Modem configuration
+///
+/// Collects the physical-layer parameters that used to be baked into
+/// constants, so several alphabets can be used from the same binary.
+///
+/// Tone alphabets:
+/// - 8 tones:  Narrowband (C-Major, C4 to C5)
+/// - 16 tones: Standard (C-Major, C4 to D6) - the original BachModem
+/// - 32 tones: Wideband (Chromatic, C4 to G6)
+///
+/// The differential lag and interleaver width follow the tone count, so one
+/// differential block always visits every tone of the alphabet exactly once.
+///
+/// Named profiles bundle these parameters per operating mode:
+/// - `standard`:   16 tones, 0.1 s symbols
+/// - `narrowband`: 8 tones, 0.1 s symbols
+/// - `wideband`:   32 tones, 0.1 s symbols
+/// - `narrow500`:  8 tones, 0.2 s symbols - fits a 500 Hz CW filter
+/// - `robust`:     standard plus a 16-byte Reed-Solomon outer code per message
+///   and coded-bit whitening
+/// - `doppler`:    standard with 200 Hz chirp symbols and whitening, for
+///   mistuned or high-Doppler paths
+/// - `lowband`:    standard on the tones below 1 kHz, with whitening
+/// - `deep_space`: standard with 2.0 s symbols and a 0.25 s-note preamble,
+///   with whitening, for the weakest paths (about 9 minutes per frame)
+/// - `chat`:       standard with 12.5 ms symbols, a single-sweep preamble and
+///   the convolutional inner code, for keyboard chat at decent SNR (under
+///   5 s per frame)
+///
+/// Optional per-tone gains (pre-emphasis) compensate non-flat transmit chains.
+///
+/// The preamble is `preamble_sweeps` alternating up/down sweeps over the
+/// alphabet with `preamble_note_duration` notes (default 4 × 50 ms); the
+/// flourish and postamble use the same note length. Slower profiles stretch
+/// it so the preamble keeps pace with the symbols; `chat` cuts it to one up
+/// sweep, which has no repeated cycle and so no sync ambiguity, at a quarter
+/// of the sync energy.
+///
+/// `sample_rate` is the rate every waveform is generated and demodulated at
+/// (default `FS`, 8 kHz; the audio layer resamples soundcards to it), and
+/// `flourish_interval` inserts a flourish every that many data symbols
+/// (0 = none). The modulators, demodulators and sync take both from the
+/// config. Transmitter and receiver must agree on them.
+///
+/// `wavelet_sigmas` sets the Morlet shape: the symbol window holds that many
+/// Gaussian widths (s = symbol_duration / wavelet_sigmas, default 6). More
+/// sigmas give a shorter pulse - less inter-symbol overlap under delay spread,
+/// but a wider spectrum and more inter-tone leakage. The window's
+/// time-bandwidth product is symbol_duration · σ_f = wavelet_sigmas / 2π.
+///
+/// `chirp_span_hz` turns the data symbols into Gaussian chirps (see `chirp`)
+/// that trade bandwidth for frequency-offset tolerance.
+///
+/// `tone_mapping` (natural or Gray) assigns data values to tones for
+/// tone-keyed modes; FH-DPSK carries data in phase and ignores it.
+///
+/// `leakage_compensation` removes neighbouring tones' leakage from the
+/// matched filter outputs (see `leakage`).
+///
+/// `preamble_phase_code` flips the phase of preamble notes by π wherever
+/// the x^7 + x^4 + 1 m-sequence is 1. The tones are unchanged, but the
+/// repeated sweep cycles no longer line up coherently, so the preamble
+/// autocorrelation keeps a single dominant peak instead of sidelobes one and
+/// two sweep cycles out. Transmitter and receiver must agree on it.
+///
+/// `usable_tones` restricts the transmitter to the lowest tones, for audio
+/// chains or filters that cut the top of the alphabet: hopping slots (and
+/// preamble notes) on a higher tone are folded onto the usable ones, so the
+/// frame layout and differential lag are unchanged. `partial_band` is the
+/// counterpart for links whose filter neither end knows: tones the preamble
+/// shows missing are erased, and the interleaver spreads every tone's bits
+/// across the codeword so the FEC fills them in (see `partial_band`).
+/// Transmitter and receiver must agree on it.
+///
+/// `afc_range_hz` makes the receiver estimate the carrier offset of a
+/// mistuned transmitter and shift the capture back before sync (see `afc`).
+///
+/// `clock_drift_tracking` places the soft demodulators' symbol windows on
+/// the sample clock rate measured at the flourishes and the postamble
+/// instead of the nominal one (see `clock_drift`). `timing_recovery` then
+/// moves each window to its symbol's peak at sub-sample resolution, in the
+/// hard-decision demodulator too (see `timing_recovery`).
+///
+/// `two_stage_sync` searches the preamble on a low-passed, decimated copy
+/// of the capture first and at the full rate only around the coarse peak
+/// (see `coarse_sync`), so hour-long recordings can be searched.
+///
+/// `hopping_theme` replaces the alphabet's built-in hopping pattern with
+/// another permutation, e.g. one found by `hop_search`. Transmitter and
+/// receiver must agree on it.
+///
+/// `hopping_seed` draws the pattern from a seed instead
+/// (`seeded_hopping_pattern`), so several stations can share a band on
+/// patterns that rarely land on the same tone in the same slot. It is a
+/// fixed permutation per seed, not a new one per block: the differential
+/// lag must keep comparing symbols on the same tone. Both ends configure
+/// the same seed.
+///
+/// `tone_plan` replaces the built-in frequency table of the alphabet with
+/// another scale or an arbitrary list (see `tone_plan`). Transmitter and
+/// receiver must agree on it.
+///
+/// `reference_blocks` sets the length of the known reference group at the
+/// start of the data (in differential blocks of `lag` symbols), and
+/// `reference_interval` repeats the group every that many data blocks, so
+/// the receiver restarts its differential baseline before phase noise has
+/// built up (see `reference_blocks`). Transmitter and receiver must agree
+/// on both.
+///
+/// `frame_code` selects the inner code of a frame: the polar code, or the
+/// tail-biting convolutional code of the same size (see
+/// `bachmodem_core::convolutional`), which decodes in one Viterbi pass.
+/// `wire_version` is the frame header the transmitter writes, e.g. the one
+/// `negotiate_version` agreed with an older receiver.
+///
+/// `dpsk_order` packs 2 (DQPSK) or 3 (8-DPSK) coded bits into each data
+/// symbol instead of 1 (see `dpsk`), shortening the data part of a frame
+/// at the cost of SNR margin. Transmitter and receiver must agree on it.
+///
+/// Whitening (`scrambler`) is off in the original four profiles so they stay
+/// compatible with deployed receivers; new profiles enable it.
+
+use std::fmt;
+use bachmodem_core::fixed_point::Q15Demodulator;
+use bachmodem_core::frame::{FrameCode, CODE_N};
+use bachmodem_core::wire_format::{SUPPORTED_WIRE_VERSIONS, WIRE_FORMAT_VERSION};
+use bachmodem_core::matched_filter::ScalarDemodulator;
+use bachmodem_core::outer_code::OuterCode;
+use crate::dpsk::DpskOrder;
+use crate::reference_blocks::ReferenceLayout;
+use crate::modulation::SyncConfig;
+use crate::tone_mapping::ToneMapping;
+use crate::tone_plan::TonePlan;
+use crate::wavelet::{FS, SYMBOL_DURATION, PREAMBLE_NOTE_DURATION, DEFAULT_WAVELET_SIGMAS, WaveletShape};
+use crate::wavelet::{HOPPING_PATTERN, HOPPING_PATTERN_8, HOPPING_PATTERN_32, get_hopping_indices, seeded_hopping_pattern};
+
+/// Supported tone alphabet sizes
+pub const SUPPORTED_TONE_COUNTS: [usize; 3] = [8, 16, 32];
+
+/// Names accepted by `ModemConfig::profile`
+pub const PROFILE_NAMES: [&str; 9] = ["standard", "narrowband", "wideband", "narrow500", "robust", "doppler", "lowband", "deep_space", "chat"];
+
+/// Parity bytes of the `robust` profile's outer code
+pub const ROBUST_RS_PARITY: usize = 16;
+
+/// Chirp sweep of the `doppler` profile (Hz across one symbol)
+pub const DOPPLER_CHIRP_SPAN_HZ: f64 = 200.0;
+
+/// Highest frequency of the `lowband` profile (Hz)
+pub const LOWBAND_MAX_FREQUENCY_HZ: f64 = 1000.0;
+
+/// Data symbol duration of the `deep_space` profile (seconds)
+pub const DEEP_SPACE_SYMBOL_DURATION: f64 = 2.0;
+
+/// Preamble note duration of the `deep_space` profile (seconds)
+pub const DEEP_SPACE_NOTE_DURATION: f64 = 0.25;
+
+/// Data symbol duration of the `chat` profile (seconds)
+pub const CHAT_SYMBOL_DURATION: f64 = 0.0125;
+
+/// Preamble note duration of the `chat` profile (seconds)
+pub const CHAT_NOTE_DURATION: f64 = 0.025;
+
+/// Sweeps in the default preamble (up, down, up, down)
+pub const DEFAULT_PREAMBLE_SWEEPS: usize = 4;
+
+/// Why a configuration setting was rejected
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// Hopping pattern is not a permutation of the alphabet's tones
+    NotAPermutation { num_tones: usize },
+
+    /// Fewer than two tones fit below a `with_max_frequency` limit
+    BandTooNarrow { max_hz: f64 },
+
+    /// The filter bank's cross-correlation can't be inverted for leakage
+    /// compensation (tones closer than the wavelet resolution)
+    SingularFilterBank { num_tones: usize },
+
+    /// An explicit tone plan fixes the alphabet; it can't change size
+    FixedTonePlan { num_tones: usize },
+
+    /// No built-in tone plan for this alphabet size
+    UnsupportedToneCount { num_tones: usize },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NotAPermutation { num_tones } => {
+                write!(f, "hopping pattern must be a permutation of the {} tones", num_tones)
+            }
+            ConfigError::BandTooNarrow { max_hz } => write!(f, "fewer than two tones fit below {} Hz", max_hz),
+            ConfigError::SingularFilterBank { num_tones } => {
+                write!(f, "the {} wavelet filters overlap too much to compensate their leakage", num_tones)
+            }
+            ConfigError::FixedTonePlan { num_tones } => write!(f, "the tone plan fixes the alphabet at {} tones", num_tones),
+            ConfigError::UnsupportedToneCount { num_tones } => {
+                write!(f, "unsupported tone count {} (expected one of {:?})", num_tones, SUPPORTED_TONE_COUNTS)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Physical layer configuration
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModemConfig {
+    /// Number of Bach tones in the alphabet (8, 16 or 32)
+    pub num_tones: usize,
+
+    /// Sample rate of generated and received waveforms (Hz)
+    pub sample_rate: f64,
+
+    /// Data symbol duration (seconds)
+    pub symbol_duration: f64,
+
+    /// Flourish every N data symbols (0 = disabled)
+    pub flourish_interval: usize,
+
+    /// Alternating up/down preamble sweeps (at least 1)
+    pub preamble_sweeps: usize,
+
+    /// Preamble, flourish and postamble note duration (seconds)
+    pub preamble_note_duration: f64,
+
+    /// Symbol window length in Morlet Gaussian widths (time-bandwidth shape)
+    pub wavelet_sigmas: f64,
+
+    /// Linear FM sweep of the data symbols (Hz across the window), 0 = pure tones
+    pub chirp_span_hz: f64,
+
+    /// Per-tone transmit amplitude (pre-emphasis), None = flat
+    ///
+    /// The receiver applies the inverse weighting in its matched filters.
+    pub tone_gains: Option<Vec<f64>>,
+
+    /// Reed-Solomon outer code parity bytes per message, 0 = polar only
+    pub rs_parity: usize,
+
+    /// XOR the coded bits with the x^7 + x^4 + 1 whitening sequence
+    pub scrambler: bool,
+
+    /// Data value <-> tone assignment of tone-keyed modes
+    pub tone_mapping: ToneMapping,
+
+    /// Invert the filter bank's cross-correlation before phase extraction
+    pub leakage_compensation: bool,
+
+    /// BPSK-code the preamble notes with the whitening m-sequence
+    pub preamble_phase_code: bool,
+
+    /// Transmit on the lowest N tones only, None = all
+    pub usable_tones: Option<usize>,
+
+    /// Spread tone bits across the codeword and erase the tones missing from
+    /// the received preamble
+    pub partial_band: bool,
+
+    /// Hopping pattern replacing the built-in one, None = built-in
+    pub hopping_theme: Option<Vec<usize>>,
+
+    /// Seed of a pseudo-random hopping pattern, None = theme or built-in
+    pub hopping_seed: Option<u64>,
+
+    /// Inner code of a frame (polar or convolutional)
+    pub frame_code: FrameCode,
+
+    /// Wire-format version written into the frame header
+    pub wire_version: u8,
+
+    /// Known reference blocks per group (at least 1)
+    pub reference_blocks: usize,
+
+    /// Data blocks between reference groups, 0 = only at the start
+    pub reference_interval: usize,
+
+    /// Frequency table replacing the built-in one, None = built-in
+    pub tone_plan: Option<TonePlan>,
+
+    /// Differential phase points per data symbol (bits per symbol)
+    pub dpsk_order: DpskOrder,
+
+    /// Reference groups are pilots: coherent instead of differential detection
+    pub pilots: bool,
+
+    /// Carrier offset search range of the receiver's AFC (Hz), 0 = off
+    pub afc_range_hz: f64,
+
+    /// Track the transmitter's sample clock across the frame
+    pub clock_drift_tracking: bool,
+
+    /// Fractional symbol timing recovery in the demodulators
+    pub timing_recovery: bool,
+
+    /// Coarse preamble search on a decimated capture, then a fine one
+    pub two_stage_sync: bool,
+
+    /// Preamble detection thresholds and search limits
+    pub sync: SyncConfig,
+}
+
+impl Default for ModemConfig {
+    fn default() -> Self {
+        Self {
+            num_tones: 16,
+            sample_rate: FS,
+            symbol_duration: SYMBOL_DURATION,
+            flourish_interval: 0,
+            preamble_sweeps: DEFAULT_PREAMBLE_SWEEPS,
+            preamble_note_duration: PREAMBLE_NOTE_DURATION,
+            wavelet_sigmas: DEFAULT_WAVELET_SIGMAS,
+            chirp_span_hz: 0.0,
+            tone_gains: None,
+            rs_parity: 0,
+            scrambler: false,
+            tone_mapping: ToneMapping::Natural,
+            leakage_compensation: false,
+            preamble_phase_code: false,
+            usable_tones: None,
+            partial_band: false,
+            hopping_theme: None,
+            hopping_seed: None,
+            frame_code: FrameCode::Polar,
+            wire_version: WIRE_FORMAT_VERSION,
+            reference_blocks: 1,
+            reference_interval: 0,
+            tone_plan: None,
+            dpsk_order: DpskOrder::Binary,
+            pilots: false,
+            afc_range_hz: 0.0,
+            clock_drift_tracking: false,
+            timing_recovery: false,
+            two_stage_sync: false,
+            sync: SyncConfig::default(),
+        }
+    }
+}
+
+impl ModemConfig {
+    /// Create configuration for a given tone alphabet size
+    pub fn new(num_tones: usize) -> Self {
+        assert!(
+            SUPPORTED_TONE_COUNTS.contains(&num_tones),
+            "Unsupported tone count {} (expected one of {:?})",
+            num_tones,
+            SUPPORTED_TONE_COUNTS
+        );
+        Self {
+            num_tones,
+            ..Self::default()
+        }
+    }
+
+    /// Look up a named mode profile
+    pub fn profile(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::default()),
+            "narrowband" => Some(Self::narrowband()),
+            "wideband" => Some(Self::wideband()),
+            "narrow500" => Some(Self::narrowband_500hz()),
+            "robust" => Some(Self::default().with_outer_code(ROBUST_RS_PARITY).with_scrambler(true)),
+            "doppler" => Some(Self::default().with_chirp(DOPPLER_CHIRP_SPAN_HZ).with_scrambler(true)),
+            "lowband" => Self::default().with_max_frequency(LOWBAND_MAX_FREQUENCY_HZ).ok().map(|config| config.with_scrambler(true)),
+            "deep_space" => Some(Self::deep_space()),
+            "chat" => Some(Self::chat()),
+            _ => None,
+        }
+    }
+
+    /// Narrowband mode fitting a 500 Hz CW filter
+    /// 
+    /// One octave of C-Major (C4-C5, 262 Hz span) with 0.2 s symbols.
+    /// Doubling the symbol length halves each wavelet's bandwidth, keeping
+    /// the 99% occupied bandwidth around 300 Hz. Set the filter (IF shift)
+    /// centered on `center_frequency()`, i.e. ~392 Hz audio.
+    pub fn narrowband_500hz() -> Self {
+        Self {
+            num_tones: 8,
+            symbol_duration: 0.2,
+            ..Self::default()
+        }
+    }
+
+    /// Deep-space mode: 2.0 s symbols behind a 16 s preamble
+    ///
+    /// Twenty times the symbol energy of `standard`; the longer notes keep
+    /// the preamble detectable at the SNRs the data decodes at.
+    pub fn deep_space() -> Self {
+        Self {
+            symbol_duration: DEEP_SPACE_SYMBOL_DURATION,
+            ..Self::default()
+        }
+        .with_preamble(DEFAULT_PREAMBLE_SWEEPS, DEEP_SPACE_NOTE_DURATION)
+        .with_scrambler(true)
+    }
+
+    /// Keyboard chat mode: one frame in under 5 s
+    ///
+    /// 12.5 ms symbols behind a single 0.4 s up sweep, with the
+    /// convolutional inner code so the receiver answers as soon as the
+    /// postamble ends. Meant for single transmissions between stations with
+    /// decent SNR (no repetition slots).
+    pub fn chat() -> Self {
+        Self {
+            symbol_duration: CHAT_SYMBOL_DURATION,
+            ..Self::default()
+        }
+        .with_preamble(1, CHAT_NOTE_DURATION)
+        .with_frame_code(FrameCode::Convolutional)
+        .with_scrambler(true)
+    }
+
+    /// 8-tone narrowband alphabet
+    pub fn narrowband() -> Self {
+        Self::new(8)
+    }
+
+    /// 32-tone wideband alphabet
+    pub fn wideband() -> Self {
+        Self::new(32)
+    }
+
+    /// Set explicit per-tone transmit amplitudes
+    pub fn with_tone_gains(mut self, gains: Vec<f64>) -> Self {
+        assert_eq!(gains.len(), self.num_tones, "Need one gain per tone");
+        assert!(gains.iter().all(|&g| g > 0.0), "Tone gains must be positive");
+        self.tone_gains = Some(gains);
+        self
+    }
+
+    /// Tilt the tone amplitudes by `db_per_octave` above the lowest tone
+    ///
+    /// Compensates SSB audio chains and speakers that roll off above 1 kHz.
+    /// Gains are normalized to unit RMS so average transmit power is unchanged.
+    pub fn with_pre_emphasis(self, db_per_octave: f64) -> Self {
+        let freqs = self.frequencies();
+        let gains: Vec<f64> = freqs.iter()
+            .map(|f| 10f64.powf(db_per_octave * (f / freqs[0]).log2() / 20.0))
+            .collect();
+        let rms = (gains.iter().map(|g| g * g).sum::<f64>() / gains.len() as f64).sqrt();
+        self.with_tone_gains(gains.iter().map(|g| g / rms).collect())
+    }
+
+    /// Generate and demodulate at `hz` samples per second
+    pub fn with_sample_rate(mut self, hz: f64) -> Self {
+        assert!(self.preamble_note_duration * hz >= 1.0, "Preamble notes must be at least one sample long");
+        assert!(2.0 * self.frequencies().last().copied().unwrap_or(0.0) < hz, "Tones must lie below the Nyquist frequency");
+        self.sample_rate = hz;
+        self
+    }
+
+    /// Insert a flourish every `interval` data symbols (0 = none)
+    pub fn with_flourish_interval(mut self, interval: usize) -> Self {
+        self.flourish_interval = interval;
+        self
+    }
+
+    /// Set the preamble to `sweeps` alternating up/down sweeps of `note_duration` notes
+    pub fn with_preamble(mut self, sweeps: usize, note_duration: f64) -> Self {
+        assert!(sweeps >= 1, "The preamble needs at least one sweep");
+        assert!(note_duration * self.sample_rate >= 1.0, "Preamble notes must be at least one sample long");
+        self.preamble_sweeps = sweeps;
+        self.preamble_note_duration = note_duration;
+        self
+    }
+
+    /// Set the Morlet width as symbol window / `sigmas`
+    pub fn with_wavelet_sigmas(mut self, sigmas: f64) -> Self {
+        assert!(sigmas > 0.0, "Wavelet sigmas must be positive");
+        self.wavelet_sigmas = sigmas;
+        self
+    }
+
+    /// Sweep the data symbols across `span_hz` (0 = pure tones, see `chirp`)
+    pub fn with_chirp(mut self, span_hz: f64) -> Self {
+        assert!(span_hz >= 0.0, "Chirp span must not be negative");
+        self.chirp_span_hz = span_hz;
+        self
+    }
+
+    /// Pulse shape of the data symbols
+    pub fn wavelet_shape(&self) -> WaveletShape {
+        WaveletShape::chirp(self.wavelet_sigmas, self.chirp_span_hz)
+    }
+
+    /// 99% occupied bandwidth of one tone (Hz)
+    ///
+    /// The wavelet's power spectrum is Gaussian with variance σ_f² + (k·s)²/2,
+    /// where σ_f = 1 / (2√2·π·s) is the pure tone's spread, s the Gaussian
+    /// width and k the chirp rate; 99% of it lies within ±2.576σ. Window
+    /// truncation is ignored.
+    pub fn tone_bandwidth(&self) -> f64 {
+        let s = self.symbol_duration / self.wavelet_sigmas;
+        let sigma_f = 1.0 / (2.0 * std::f64::consts::SQRT_2 * std::f64::consts::PI * s);
+        let sweep = self.chirp_span_hz / self.symbol_duration * s;
+        2.0 * 2.576 * (sigma_f * sigma_f + sweep * sweep / 2.0).sqrt()
+    }
+
+    /// Concatenate a Reed-Solomon outer code of `parity` bytes (0 = none)
+    pub fn with_outer_code(mut self, parity: usize) -> Self {
+        assert!(parity < 255, "RS parity must be below 255 bytes");
+        self.rs_parity = parity;
+        self
+    }
+
+    /// Outer code of multi-frame messages, if enabled
+    pub fn outer_code(&self) -> Option<OuterCode> {
+        (self.rs_parity > 0).then(|| OuterCode::new(self.rs_parity))
+    }
+
+    /// Enable or disable coded-bit whitening (see `bachmodem_core::scrambler`)
+    pub fn with_scrambler(mut self, enabled: bool) -> Self {
+        self.scrambler = enabled;
+        self
+    }
+
+    /// Select the data value <-> tone mapping of tone-keyed modes
+    pub fn with_tone_mapping(mut self, mapping: ToneMapping) -> Self {
+        self.tone_mapping = mapping;
+        self
+    }
+
+    /// Enable or disable inter-tone leakage compensation (see `leakage`)
+    ///
+    /// Set the tones, gains and pulse shape first: enabling fails if their
+    /// filter bank is singular.
+    pub fn with_leakage_compensation(mut self, enabled: bool) -> Result<Self, ConfigError> {
+        if enabled {
+            crate::leakage::leakage_compensation_matrix(&self)?;
+        }
+        self.leakage_compensation = enabled;
+        Ok(self)
+    }
+
+    /// Enable or disable m-sequence phase coding of the preamble notes
+    pub fn with_preamble_phase_code(mut self, enabled: bool) -> Self {
+        self.preamble_phase_code = enabled;
+        self
+    }
+
+    /// Transmit only on the tones whose 99% band ends below `max_hz`
+    ///
+    /// Set it after the pulse shape, which determines the tone bandwidth.
+    pub fn with_max_frequency(mut self, max_hz: f64) -> Result<Self, ConfigError> {
+        let half_band = self.tone_bandwidth() / 2.0;
+        let usable = self.frequencies().iter().filter(|&&f| f + half_band <= max_hz).count();
+        if usable < 2 {
+            return Err(ConfigError::BandTooNarrow { max_hz });
+        }
+        self.usable_tones = (usable < self.num_tones).then_some(usable);
+        Ok(self)
+    }
+
+    /// Hop over `pattern` instead of the built-in pattern
+    ///
+    /// The pattern must visit every tone of the alphabet exactly once.
+    pub fn with_hopping_pattern(mut self, pattern: Vec<usize>) -> Result<Self, ConfigError> {
+        let mut sorted = pattern.clone();
+        sorted.sort_unstable();
+        if !sorted.into_iter().eq(0..self.num_tones) {
+            return Err(ConfigError::NotAPermutation { num_tones: self.num_tones });
+        }
+        self.hopping_theme = Some(pattern);
+        self.hopping_seed = None;
+        Ok(self)
+    }
+
+    /// Hop over the pseudo-random pattern drawn from `seed`
+    ///
+    /// Replaces a hopping pattern set earlier. Set it after the tone plan,
+    /// which fixes the alphabet size. Simulations draw the seed from the
+    /// `RngStream::Hopping` stream of their `SimSeed` (`ModemRng::seed`).
+    pub fn with_hopping_seed(mut self, seed: u64) -> Self {
+        self.hopping_seed = Some(seed);
+        self.hopping_theme = None;
+        self
+    }
+
+    /// Transmit on the tones of `plan` instead of the built-in scale
+    ///
+    /// The plan's size becomes the tone count, so set it before per-tone
+    /// gains, a hopping pattern or `with_max_frequency`.
+    pub fn with_tone_plan(mut self, plan: TonePlan) -> Self {
+        self.num_tones = plan.len();
+        self.tone_plan = Some(plan);
+        self
+    }
+
+    /// Encode frames with `code` (transmitter and receiver must agree)
+    pub fn with_frame_code(mut self, code: FrameCode) -> Self {
+        self.frame_code = code;
+        self
+    }
+
+    /// Write wire-format `version` into the frame header
+    pub fn with_wire_version(mut self, version: u8) -> Self {
+        assert!(SUPPORTED_WIRE_VERSIONS.contains(&version), "Unsupported wire-format version {}", version);
+        self.wire_version = version;
+        self
+    }
+
+    /// Start with `blocks` reference blocks and repeat them every `interval`
+    /// data blocks (0 = never)
+    pub fn with_reference_blocks(mut self, blocks: usize, interval: usize) -> Self {
+        assert!(blocks >= 1, "Differential decoding needs at least one reference block");
+        self.reference_blocks = blocks;
+        self.reference_interval = interval;
+        self
+    }
+
+    /// Carry `order.bits_per_symbol()` bits per data symbol
+    pub fn with_dpsk_order(mut self, order: DpskOrder) -> Self {
+        self.dpsk_order = order;
+        self
+    }
+
+    /// Where the reference blocks sit among the transmitted blocks, and
+    /// how many bits each data symbol carries
+    pub fn reference_layout(&self) -> ReferenceLayout {
+        ReferenceLayout {
+            lag: self.lag(),
+            blocks: self.reference_blocks,
+            interval: self.reference_interval,
+            order: self.dpsk_order,
+            pilots: self.pilots,
+        }
+    }
+
+    /// Coherent detection: a pilot group every `interval` data blocks
+    ///
+    /// The reference groups (`reference_blocks` long) become pilots the
+    /// receiver interpolates the phase reference from (see
+    /// `reference_blocks`). Longer groups average more pilot noise out.
+    pub fn with_pilots(mut self, interval: usize) -> Self {
+        assert!(interval >= 1, "Pilots need at least one data block between groups");
+        self.pilots = true;
+        self.reference_interval = interval;
+        self
+    }
+
+    /// Estimate and remove carrier offsets up to ±`range_hz` before
+    /// demodulating (see `afc`); `DEFAULT_AFC_RANGE_HZ` covers SSB rigs
+    pub fn with_afc(mut self, range_hz: f64) -> Self {
+        assert!(range_hz >= 0.0, "AFC range must not be negative");
+        self.afc_range_hz = range_hz;
+        self
+    }
+
+    /// Enable or disable sample clock drift tracking (see `clock_drift`)
+    pub fn with_clock_drift_tracking(mut self, enabled: bool) -> Self {
+        self.clock_drift_tracking = enabled;
+        self
+    }
+
+    /// Enable or disable fractional symbol timing recovery (see `timing_recovery`)
+    pub fn with_timing_recovery(mut self, enabled: bool) -> Self {
+        self.timing_recovery = enabled;
+        self
+    }
+
+    /// Enable or disable coarse/fine two-stage sync (see `coarse_sync`)
+    pub fn with_two_stage_sync(mut self, enabled: bool) -> Self {
+        self.two_stage_sync = enabled;
+        self
+    }
+
+    /// Detect preambles with `sync` instead of the -30 dB defaults
+    pub fn with_sync(mut self, sync: SyncConfig) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    /// Enable or disable partial-band erasures (see `partial_band`)
+    pub fn with_partial_band(mut self, enabled: bool) -> Self {
+        self.partial_band = enabled;
+        self
+    }
+
+    /// Tone sent for a slot of the alphabet
+    ///
+    /// Slots above `usable_tones` wrap around onto the lowest tones.
+    pub fn transmit_tone(&self, tone_idx: usize) -> usize {
+        match self.usable_tones {
+            Some(usable) if tone_idx >= usable => tone_idx % usable,
+            _ => tone_idx,
+        }
+    }
+
+    /// Data bits per symbol when the tone choice carries the data
+    pub fn bits_per_tone(&self) -> usize {
+        self.num_tones.trailing_zeros() as usize
+    }
+
+    /// Transmit amplitude of a tone (1.0 when flat)
+    pub fn tone_gain(&self, tone_idx: usize) -> f64 {
+        self.tone_gains.as_ref().map_or(1.0, |g| g[tone_idx])
+    }
+
+    /// `ConfigError::UnsupportedToneCount` when the alphabet has neither a
+    /// tone plan nor a built-in one (set through the public field, which
+    /// `new` would have rejected)
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.tone_plan.is_none() && !SUPPORTED_TONE_COUNTS.contains(&self.num_tones) {
+            return Err(ConfigError::UnsupportedToneCount { num_tones: self.num_tones });
+        }
+        Ok(())
+    }
+
+    /// Carrier frequencies of the tone alphabet (Hz)
+    pub fn frequencies(&self) -> Vec<f64> {
+        self.active_tone_plan().frequencies().to_vec()
+    }
+
+    /// The configured tone plan, or the built-in one of the alphabet size
+    pub fn active_tone_plan(&self) -> TonePlan {
+        match &self.tone_plan {
+            Some(plan) => plan.clone(),
+            None => TonePlan::builtin(self.num_tones)
+                .unwrap_or_else(|_| panic!("Unsupported tone count {}", self.num_tones)),
+        }
+    }
+
+    /// Melodic hopping pattern over the tone alphabet
+    ///
+    /// With `usable_tones` set, a block no longer visits every tone once:
+    /// the folded slots repeat low tones.
+    pub fn hopping_pattern(&self) -> Vec<usize> {
+        let pattern = match (&self.hopping_theme, self.hopping_seed, self.num_tones) {
+            (Some(theme), _, _) => theme.clone(),
+            (None, Some(seed), n) => seeded_hopping_pattern(n, seed),
+            (None, None, 8) => HOPPING_PATTERN_8.to_vec(),
+            (None, None, 16) => HOPPING_PATTERN.to_vec(),
+            (None, None, 32) => HOPPING_PATTERN_32.to_vec(),
+            (None, None, n) => panic!("Unsupported tone count {}", n),
+        };
+        pattern.into_iter().map(|tone| self.transmit_tone(tone)).collect()
+    }
+
+    /// Melody hopping sequence (tone indices) for a given number of symbols
+    pub fn melody_indices(&self, num_symbols: usize) -> Vec<usize> {
+        get_hopping_indices(&self.hopping_pattern(), num_symbols)
+    }
+
+    /// Differential encoding lag (symbols between phase references)
+    pub fn lag(&self) -> usize {
+        self.num_tones
+    }
+
+    /// Block interleaver width matching the differential block
+    ///
+    /// With one column per tone every tone slot carries one aligned row of
+    /// the codeword. Partial-band mode uses half as many rows as tones, so
+    /// each tone slot carries every other bit of a row and a missing tone
+    /// costs the polar code at most one bit of each pair.
+    pub fn interleaver_columns(&self) -> usize {
+        if self.partial_band {
+            2 * CODE_N / self.num_tones
+        } else {
+            self.num_tones
+        }
+    }
+
+    /// Samples per data symbol
+    pub fn symbol_samples(&self) -> usize {
+        (self.symbol_duration * self.sample_rate) as usize
+    }
+
+    /// Samples per preamble (flourish, postamble) note
+    pub fn preamble_note_samples(&self) -> usize {
+        (self.preamble_note_duration * self.sample_rate) as usize
+    }
+
+    /// Airtime of one frame without flourishes (seconds)
+    ///
+    /// Preamble, one codeword of data symbols with its reference blocks and
+    /// the postamble (one up/down sweep).
+    pub fn frame_duration(&self) -> f64 {
+        let notes = (self.preamble_sweeps + 2) * self.num_tones;
+        let symbols = self.reference_layout().num_symbols(CODE_N);
+        (notes * self.preamble_note_samples() + symbols * self.symbol_samples()) as f64 / self.sample_rate
+    }
+
+    /// Scalar f32 matched filter for this alphabet (CPU / embedded fallback)
+    pub fn scalar_demodulator(&self) -> ScalarDemodulator {
+        let demod = ScalarDemodulator::new(
+            &self.frequencies(),
+            &self.hopping_pattern(),
+            self.tone_gains.as_deref(),
+            self.symbol_duration,
+            self.sample_rate,
+        )
+        .with_wavelet_shape(self.wavelet_shape());
+        if self.scrambler { demod.with_descrambler() } else { demod }
+    }
+
+    /// Q15 fixed-point matched filter on i16 PCM (no FPU / GPU)
+    pub fn q15_demodulator(&self) -> Q15Demodulator {
+        let demod = Q15Demodulator::new(
+            &self.frequencies(),
+            &self.hopping_pattern(),
+            self.tone_gains.as_deref(),
+            self.symbol_duration,
+            self.sample_rate,
+        )
+        .with_wavelet_shape(self.wavelet_shape());
+        if self.scrambler { demod.with_descrambler() } else { demod }
+    }
+
+    /// Midpoint between the lowest and highest transmitted tone (Hz)
+    pub fn center_frequency(&self) -> f64 {
+        let freqs = self.frequencies();
+        let highest = self.usable_tones.unwrap_or(freqs.len()) - 1;
+        (freqs[0] + freqs[highest]) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabets_are_consistent() {
+        for &num_tones in &SUPPORTED_TONE_COUNTS {
+            let config = ModemConfig::new(num_tones);
+            let freqs = config.frequencies();
+
+            assert_eq!(freqs.len(), num_tones);
+            assert_eq!(config.lag(), num_tones);
+
+            // Strictly ascending, inside the SSB audio passband
+            assert!(freqs.windows(2).all(|w| w[0] < w[1]));
+            assert!(freqs[0] > 200.0 && freqs[num_tones - 1] < 2800.0);
+
+            // Hopping pattern is a permutation of the alphabet
+            let mut pattern = config.hopping_pattern();
+            pattern.sort();
+            assert_eq!(pattern, (0..num_tones).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_hopping_theme_replaces_pattern() {
+        let theme = vec![0, 3, 6, 1, 4, 7, 2, 5].into_iter().rev().collect::<Vec<_>>();
+        let config = ModemConfig::narrowband().with_hopping_pattern(theme.clone()).unwrap();
+        assert_eq!(config.hopping_pattern(), theme);
+        assert_eq!(config.melody_indices(10)[8..], theme[..2]);
+        assert_eq!(
+            ModemConfig::narrowband().with_hopping_pattern(vec![0, 1, 2, 3, 4, 5, 6, 6]),
+            Err(ConfigError::NotAPermutation { num_tones: 8 })
+        );
+    }
+
+    #[test]
+    fn test_hopping_seed_selects_station_pattern() {
+        let a = ModemConfig::default().with_hopping_seed(1);
+        let b = ModemConfig::default().with_hopping_seed(2);
+
+        // Same seed, same pattern on both ends; a permutation of the alphabet
+        assert_eq!(a.hopping_pattern(), ModemConfig::default().with_hopping_seed(1).hopping_pattern());
+        let mut sorted = a.hopping_pattern();
+        sorted.sort();
+        assert_eq!(sorted, (0..16).collect::<Vec<_>>());
+        assert_ne!(a.hopping_pattern(), b.hopping_pattern());
+        assert_eq!(a.melody_indices(20)[16..], a.hopping_pattern()[..4]);
+
+        // A later explicit pattern wins, and vice versa
+        let theme: Vec<usize> = (0..16).rev().collect();
+        assert_eq!(a.clone().with_hopping_pattern(theme.clone()).unwrap().hopping_pattern(), theme);
+        assert_eq!(
+            ModemConfig::default().with_hopping_pattern(theme).unwrap().with_hopping_seed(1).hopping_pattern(),
+            a.hopping_pattern()
+        );
+    }
+
+    #[test]
+    fn test_named_profiles() {
+        for name in PROFILE_NAMES {
+            assert!(ModemConfig::profile(name).is_some(), "missing profile {}", name);
+        }
+        assert!(ModemConfig::profile("unknown").is_none());
+
+        let narrow = ModemConfig::profile("narrow500").unwrap();
+        assert_eq!(narrow.num_tones, 8);
+        assert_eq!(narrow.symbol_samples(), 1600);
+
+        let robust = ModemConfig::profile("robust").unwrap();
+        assert_eq!(robust.outer_code().map(|c| c.parity()), Some(ROBUST_RS_PARITY));
+        assert!(robust.scrambler);
+        assert_eq!(ModemConfig::profile("doppler").unwrap().wavelet_shape().sweep_hz, DOPPLER_CHIRP_SPAN_HZ);
+        assert!(ModemConfig::default().outer_code().is_none());
+        assert!(PROFILE_NAMES[..4].iter().all(|name| !ModemConfig::profile(name).unwrap().scrambler));
+    }
+
+    #[test]
+    fn test_reference_refresh_costs_airtime() {
+        let base = ModemConfig::default();
+        assert_eq!(base.reference_layout(), ReferenceLayout::single(16));
+
+        // 16 data blocks: 2 + 4 × (4 data) + 3 × 2 refreshes = 24 blocks instead of 17
+        let refreshed = base.clone().with_reference_blocks(2, 4);
+        assert_eq!(refreshed.reference_layout().num_symbols(CODE_N), 24 * 16);
+        assert!((refreshed.frame_duration() - base.frame_duration() - 7.0 * 1.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_chat_profile_fits_five_seconds() {
+        let chat = ModemConfig::profile("chat").unwrap();
+        assert_eq!(chat.preamble_sweeps, 1);
+        assert_eq!(chat.frame_code, FrameCode::Convolutional);
+        assert!(chat.frame_duration() < 5.0, "{} s", chat.frame_duration());
+
+        // 96 notes of 50 ms and 272 symbols of 100 ms
+        assert!((ModemConfig::default().frame_duration() - 32.0).abs() < 1e-9);
+        assert_eq!(ModemConfig::default().frame_code, FrameCode::Polar);
+    }
+
+    #[test]
+    fn test_max_frequency_folds_top_tones() {
+        // B5 (988 Hz) is below 1 kHz, but its band spreads past it
+        let lowband = ModemConfig::profile("lowband").unwrap();
+        assert_eq!(lowband.usable_tones, Some(13));
+        assert_eq!(lowband.transmit_tone(12), 12);
+        assert_eq!((lowband.transmit_tone(13), lowband.transmit_tone(15)), (0, 2));
+
+        let pattern = lowband.hopping_pattern();
+        assert_eq!(pattern.len(), lowband.lag());
+        assert!(pattern.iter().all(|&tone| tone < 13));
+        assert!(lowband.center_frequency() < ModemConfig::default().center_frequency());
+
+        // A limit above the alphabet changes nothing
+        assert_eq!(ModemConfig::default().with_max_frequency(3000.0), Ok(ModemConfig::default()));
+        assert_eq!(ModemConfig::default().with_max_frequency(100.0), Err(ConfigError::BandTooNarrow { max_hz: 100.0 }));
+    }
+
+    #[test]
+    fn test_tone_bandwidth_follows_shape() {
+        use bachmodem_core::matched_filter::shaped_wavelet_f32;
+
+        for (sigmas, span_hz) in [(4.0, 0.0), (DEFAULT_WAVELET_SIGMAS, 0.0), (9.0, 0.0), (DEFAULT_WAVELET_SIGMAS, DOPPLER_CHIRP_SPAN_HZ)] {
+            let config = ModemConfig::default().with_wavelet_sigmas(sigmas).with_chirp(span_hz);
+            let (real, _) = shaped_wavelet_f32(1000.0, config.symbol_duration, config.wavelet_shape(), config.sample_rate);
+
+            // 1 Hz power spectrum around the carrier, then the 99% band
+            let power: Vec<f64> = (-400..=400)
+                .map(|df| {
+                    let w = 2.0 * std::f64::consts::PI * (1000.0 + df as f64) / config.sample_rate;
+                    let (re, im) = real.iter().enumerate().fold((0.0, 0.0), |(re, im), (n, &x)| {
+                        (re + x as f64 * (w * n as f64).cos(), im - x as f64 * (w * n as f64).sin())
+                    });
+                    re * re + im * im
+                })
+                .collect();
+            let total: f64 = power.iter().sum();
+            let mut inside = power[400];
+            let mut half = 0;
+            while inside < 0.99 * total {
+                half += 1;
+                inside += power[400 - half] + power[400 + half];
+            }
+
+            let predicted = config.tone_bandwidth();
+            assert!(((2 * half) as f64 - predicted).abs() < 0.1 * predicted, "{:?}: {} vs {}", config.wavelet_shape(), 2 * half, predicted);
+        }
+    }
+
+    #[test]
+    fn test_pre_emphasis_gains() {
+        let config = ModemConfig::default().with_pre_emphasis(6.0);
+
+        // Rising with frequency, unit RMS
+        let gains: Vec<f64> = (0..16).map(|i| config.tone_gain(i)).collect();
+        assert!(gains.windows(2).all(|w| w[0] < w[1]));
+        let rms = (gains.iter().map(|g| g * g).sum::<f64>() / 16.0).sqrt();
+        assert!((rms - 1.0).abs() < 1e-9);
+
+        // C4 -> C5 is one octave: +6 dB ~ 2x amplitude
+        assert!((gains[7] / gains[0] - 2.0).abs() < 0.01);
+
+        assert_eq!(ModemConfig::default().tone_gain(3), 1.0);
+    }
+
+    #[test]
+    fn test_default_matches_legacy_constants() {
+        let config = ModemConfig::default();
+
+        assert_eq!(config.frequencies(), crate::wavelet::BACH_FREQUENCIES.to_vec());
+        assert_eq!(config.melody_indices(20), crate::wavelet::get_melody_indices(20));
+    }
+
+    #[test]
+    fn test_dpsk_order_shortens_frame() {
+        let binary = ModemConfig::default();
+        let dqpsk = ModemConfig::default().with_dpsk_order(DpskOrder::Quaternary);
+        let d8psk = ModemConfig::default().with_dpsk_order(DpskOrder::Octal);
+
+        assert_eq!(binary.reference_layout().num_symbols(CODE_N), 17 * 16);
+        assert_eq!(dqpsk.reference_layout().num_symbols(CODE_N), 9 * 16);
+        // 86 symbols: 6 data blocks
+        assert_eq!(d8psk.reference_layout().num_symbols(CODE_N), 7 * 16);
+        assert!(dqpsk.frame_duration() < binary.frame_duration() - 12.0);
+    }
+
+    #[test]
+    fn test_tone_plan_replaces_frequencies() {
+        let plan = TonePlan::minor(8).unwrap();
+        let config = ModemConfig::default().with_tone_plan(plan.clone());
+
+        assert_eq!(config.num_tones, 8);
+        assert_eq!(config.frequencies(), plan.frequencies().to_vec());
+        assert_eq!(config.lag(), 8);
+        assert_eq!(config.hopping_pattern().len(), 8);
+
+        assert_eq!(ModemConfig::wideband().active_tone_plan(), TonePlan::builtin(32).unwrap());
+    }
+}
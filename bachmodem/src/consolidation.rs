@@ -0,0 +1,266 @@
+/// Message Consolidation
+///
+/// The same frame often decodes more than once: on several receivers or
+/// sessions, in several repetition slots, or on both the list (SCL) and BP
+/// decoders. Each CRC-passing decode becomes a `Vote` with a source label;
+/// `MessageConsolidator` deduplicates them into one message per frame and
+/// majority-votes every byte across the copies, recording which sources
+/// backed the winning value.
+///
+/// A CRC pass is not proof: one wrong decode in 256 passes the CRC-8, and a
+/// byte that loses the vote marks a copy that was wrong anyway. Votes
+/// join the message whose current consensus they match in at least
+/// `min_agreement` of the bytes (positions where both are zero padding don't
+/// count), otherwise they start a new message.
+///
+/// Votes weigh `weight` each (1 for a plain majority); a tie goes to the value
+/// whose best supporter has the higher SNR. `combine_decoded_copies` is the
+/// same vote weighted by the repetition SNR estimates.
+
+use crate::receiver_pool::DecodedFrame;
+
+/// Default share of bytes a vote must match to join a message
+pub const DEFAULT_MIN_AGREEMENT: f32 = 0.75;
+
+/// One successful decode
+#[derive(Clone, Debug, PartialEq)]
+pub struct Vote {
+    /// Where the decode came from, e.g. "rx2/scl" or "slot 4"
+    pub source: String,
+
+    pub payload: Vec<u8>,
+
+    /// Vote weight (1 for a plain majority)
+    pub weight: f32,
+
+    /// Blind SNR of the decode (dB), breaks ties
+    pub snr_db: f32,
+}
+
+impl Vote {
+    /// Unit-weight vote for a decoded frame
+    pub fn from_frame(source: impl Into<String>, frame: &DecodedFrame) -> Self {
+        Self { source: source.into(), payload: frame.payload.clone(), weight: 1.0, snr_db: frame.snr_db }
+    }
+}
+
+/// Outcome of the vote at one byte position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteVote {
+    /// Winning value
+    pub value: u8,
+
+    /// Indices (into the voters) that voted for `value`
+    pub supporters: Vec<usize>,
+
+    /// Winning weight over the total weight at this position
+    pub support: f32,
+}
+
+impl ByteVote {
+    /// True if some voter disagreed
+    pub fn is_contested(&self) -> bool {
+        self.support < 1.0
+    }
+}
+
+/// Weighted majority vote per byte over (bytes, weight, snr_db) voters
+///
+/// Shorter voters abstain past their end; the result is as long as the
+/// longest voter.
+pub fn vote_bytes(voters: &[(&[u8], f32, f32)]) -> Vec<ByteVote> {
+    let len = voters.iter().map(|(bytes, _, _)| bytes.len()).max().unwrap_or(0);
+
+    (0..len)
+        .map(|i| {
+            // (value, weight, best SNR, supporters), first-seen order
+            let mut tallies: Vec<(u8, f32, f32, Vec<usize>)> = Vec::new();
+            let mut total = 0.0;
+            for (voter, &(bytes, weight, snr_db)) in voters.iter().enumerate() {
+                let Some(&value) = bytes.get(i) else { continue };
+                total += weight;
+                match tallies.iter_mut().find(|tally| tally.0 == value) {
+                    Some(tally) => {
+                        tally.1 += weight;
+                        tally.2 = tally.2.max(snr_db);
+                        tally.3.push(voter);
+                    }
+                    None => tallies.push((value, weight, snr_db, vec![voter])),
+                }
+            }
+
+            let (value, weight, _, supporters) = tallies.into_iter()
+                .reduce(|best, tally| if (tally.1, tally.2) > (best.1, best.2) { tally } else { best })
+                .expect("at least one voter reaches the longest length");
+            ByteVote { value, supporters, support: if total > 0.0 { weight / total } else { 1.0 } }
+        })
+        .collect()
+}
+
+/// Voted copy of one frame
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConsolidatedMessage {
+    /// Consensus payload
+    pub payload: Vec<u8>,
+
+    /// The votes merged into this message, in arrival order
+    pub votes: Vec<Vote>,
+
+    /// Per byte of `payload`: value, supporting votes and support
+    pub provenance: Vec<ByteVote>,
+}
+
+impl ConsolidatedMessage {
+    fn new(vote: Vote) -> Self {
+        let mut message = Self { payload: Vec::new(), votes: vec![vote], provenance: Vec::new() };
+        message.revote();
+        message
+    }
+
+    fn revote(&mut self) {
+        let voters: Vec<(&[u8], f32, f32)> = self.votes.iter()
+            .map(|vote| (vote.payload.as_slice(), vote.weight, vote.snr_db))
+            .collect();
+        self.provenance = vote_bytes(&voters);
+        self.payload = self.provenance.iter().map(|byte| byte.value).collect();
+    }
+
+    /// Sources of the votes, in arrival order
+    pub fn sources(&self) -> Vec<&str> {
+        self.votes.iter().map(|vote| vote.source.as_str()).collect()
+    }
+
+    /// Sources that voted for the consensus at byte `index`
+    pub fn byte_sources(&self, index: usize) -> Vec<&str> {
+        self.provenance.get(index)
+            .map(|byte| byte.supporters.iter().map(|&v| self.votes[v].source.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Positions where some vote disagreed with the consensus
+    pub fn contested_bytes(&self) -> Vec<usize> {
+        self.provenance.iter().enumerate()
+            .filter(|(_, byte)| byte.is_contested())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Share of the non-padding bytes where `payload` matches the consensus
+    pub fn agreement(&self, payload: &[u8]) -> f32 {
+        let len = self.payload.len().max(payload.len());
+        let (mut compared, mut matching) = (0, 0);
+        for i in 0..len {
+            let (a, b) = (self.payload.get(i).copied().unwrap_or(0), payload.get(i).copied().unwrap_or(0));
+            if a == 0 && b == 0 {
+                continue;
+            }
+            compared += 1;
+            if a == b {
+                matching += 1;
+            }
+        }
+        if compared == 0 { 1.0 } else { matching as f32 / compared as f32 }
+    }
+}
+
+/// Deduplicates decodes into messages and votes their bytes
+#[derive(Clone, Debug)]
+pub struct MessageConsolidator {
+    /// Share of bytes a vote must match to join a message
+    pub min_agreement: f32,
+
+    messages: Vec<ConsolidatedMessage>,
+}
+
+impl Default for MessageConsolidator {
+    fn default() -> Self {
+        Self::new(DEFAULT_MIN_AGREEMENT)
+    }
+}
+
+impl MessageConsolidator {
+    pub fn new(min_agreement: f32) -> Self {
+        Self { min_agreement, messages: Vec::new() }
+    }
+
+    /// Merge one decode; returns the index of the message it joined or started
+    ///
+    /// Joins the best-agreeing message if it reaches `min_agreement`.
+    pub fn add(&mut self, vote: Vote) -> usize {
+        let best = self.messages.iter().enumerate()
+            .map(|(i, message)| (i, message.agreement(&vote.payload)))
+            .filter(|&(_, agreement)| agreement >= self.min_agreement)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((i, _)) => {
+                self.messages[i].votes.push(vote);
+                self.messages[i].revote();
+                i
+            }
+            None => {
+                self.messages.push(ConsolidatedMessage::new(vote));
+                self.messages.len() - 1
+            }
+        }
+    }
+
+    /// `add` for a decoded frame with a unit-weight vote
+    pub fn add_frame(&mut self, source: impl Into<String>, frame: &DecodedFrame) -> usize {
+        self.add(Vote::from_frame(source, frame))
+    }
+
+    /// Messages in order of their first vote
+    pub fn messages(&self) -> &[ConsolidatedMessage] {
+        &self.messages
+    }
+
+    /// Take the messages and start over
+    pub fn drain(&mut self) -> Vec<ConsolidatedMessage> {
+        std::mem::take(&mut self.messages)
+    }
+
+    /// Keep the messages `keep` accepts (given the index and the message),
+    /// in order; later votes no longer join the dropped ones
+    pub fn retain(&mut self, mut keep: impl FnMut(usize, &ConsolidatedMessage) -> bool) {
+        let mut index = 0;
+        self.messages.retain(|message| {
+            index += 1;
+            keep(index - 1, message)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(source: &str, payload: &[u8], snr_db: f32) -> Vote {
+        Vote { source: source.to_string(), payload: payload.to_vec(), weight: 1.0, snr_db }
+    }
+
+    #[test]
+    fn test_majority_with_provenance() {
+        let mut consolidator = MessageConsolidator::default();
+        assert_eq!(consolidator.add(vote("rx1/scl", b"CQ DE N0CALL FN42\0\0", -18.0)), 0);
+        assert_eq!(consolidator.add(vote("rx1/bp", b"CQ DE N0CALL FN42\0\0", -18.0)), 0);
+        // Undetected error in one copy, outvoted
+        assert_eq!(consolidator.add(vote("rx2/scl", b"CQ DE N0CALX FN42\0\0", -12.0)), 0);
+        // A different frame starts its own message
+        assert_eq!(consolidator.add(vote("rx2/scl", b"73 TU\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0", -12.0)), 1);
+
+        let message = &consolidator.messages()[0];
+        assert_eq!(message.payload, b"CQ DE N0CALL FN42\0\0");
+        assert_eq!(message.sources(), vec!["rx1/scl", "rx1/bp", "rx2/scl"]);
+        assert_eq!(message.contested_bytes(), vec![11]);
+        assert_eq!(message.byte_sources(11), vec!["rx1/scl", "rx1/bp"]);
+        assert_eq!(message.byte_sources(0).len(), 3);
+        assert!((message.provenance[11].support - 2.0 / 3.0).abs() < 1e-6);
+
+        // Tie: the stronger decode wins
+        let tie = vote_bytes(&[(&b"AB"[..], 1.0, -20.0), (&b"AC"[..], 1.0, -10.0)]);
+        assert_eq!(tie[1].value, b'C');
+        assert_eq!(tie[1].supporters, vec![1]);
+        assert!(!tie[0].is_contested());
+    }
+}
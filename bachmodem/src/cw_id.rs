@@ -0,0 +1,428 @@
+/// CW Station Identification
+///
+/// Amateur rules require the callsign at the end of a transmission and at
+/// regular intervals, in a mode a listener can copy by ear. The modem keys a
+/// slow Morse ID as a pure tone above the Bach alphabet:
+/// - `CwIdPlacement::Gaps`: keyed into the listening gap after a slot, so the
+///   data is never overlapped
+/// - `CwIdPlacement::Overlay`: mixed over the start of a slot, well below the
+///   data level (for schedules without usable gaps)
+///
+/// Timing follows the PARIS standard: one dot = 1.2 / WPM seconds, dash = 3
+/// dots, 1 dot between elements, 3 between letters, 7 between words.
+/// Raised-cosine edges (5 ms) keep key clicks out of the data band.
+///
+/// The default 2 kHz tone sits above every alphabet (highest tone: 1568 Hz).
+/// Receivers remove it with `notch_cw_id_gpu` before synchronization (set
+/// `FrontEnd::cw_id_hz`), so an overlaid ID costs the decoder nothing.
+
+use burn::tensor::{Tensor, TensorPrimitive, backend::Backend, ElementConversion};
+use std::f64::consts::PI;
+use std::fmt;
+use crate::fft_correlation::FftBackend;
+use crate::repetition::TimeSlotConfig;
+use crate::wavelet::FS;
+
+/// Default ID tone (Hz, audio), above the 32-tone alphabet
+pub const DEFAULT_CW_ID_TONE_HZ: f64 = 2000.0;
+
+/// Width of the receiver notch around the ID tone (Hz)
+pub const CW_ID_NOTCH_WIDTH_HZ: f64 = 200.0;
+
+/// Raised-cosine key edge (seconds)
+const KEY_EDGE: f64 = 0.005;
+
+/// Silence between the end of a slot and a gap ID (seconds)
+const GAP_GUARD: f64 = 0.2;
+
+/// FFT block length of the receiver notch (0.5 s at 8 kHz, ~2 Hz bins)
+const NOTCH_BLOCK: usize = 4096;
+
+/// Why an ID can't be placed on a schedule
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CwIdError {
+    /// The ID plus its guard is longer than the listening gap (seconds)
+    DoesNotFitGap { duration: f64, gap: f64 },
+}
+
+impl fmt::Display for CwIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CwIdError::DoesNotFitGap { duration, gap } => write!(
+                f,
+                "CW ID ({:.1} s plus {:.1} s guard) does not fit the {:.1} s listening gap; use a shorter callsign, higher WPM or CwIdPlacement::Overlay",
+                duration, GAP_GUARD, gap
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CwIdError {}
+
+/// Where the ID goes relative to the data slots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CwIdPlacement {
+    /// In the listening gap following the slot
+    Gaps,
+
+    /// Over the beginning of the slot, at reduced level
+    Overlay,
+}
+
+/// CW identifier settings
+#[derive(Clone, Debug)]
+pub struct CwIdConfig {
+    /// Station callsign (A-Z, 0-9, '/', '?'; other characters are skipped)
+    pub callsign: String,
+
+    /// Keying speed (words per minute, PARIS)
+    pub wpm: f64,
+
+    /// ID tone frequency (Hz, audio)
+    pub tone_hz: f64,
+
+    /// Key-down RMS level relative to the data RMS (dB)
+    pub level_db: f64,
+
+    pub placement: CwIdPlacement,
+
+    /// Identify after every N slots (the last slot always carries an ID)
+    pub interval_slots: usize,
+}
+
+impl CwIdConfig {
+    /// ID at 20 WPM on 2 kHz, 15 dB below the data, in the gaps
+    pub fn new(callsign: &str) -> Self {
+        Self {
+            callsign: callsign.to_uppercase(),
+            wpm: 20.0,
+            tone_hz: DEFAULT_CW_ID_TONE_HZ,
+            level_db: -15.0,
+            placement: CwIdPlacement::Gaps,
+            interval_slots: 4,
+        }
+    }
+
+    pub fn with_wpm(mut self, wpm: f64) -> Self {
+        assert!(wpm > 0.0, "WPM must be positive");
+        self.wpm = wpm;
+        self
+    }
+
+    pub fn with_tone_hz(mut self, tone_hz: f64) -> Self {
+        assert!(tone_hz > 0.0 && tone_hz < FS / 2.0, "ID tone must lie below Nyquist");
+        self.tone_hz = tone_hz;
+        self
+    }
+
+    pub fn with_level_db(mut self, level_db: f64) -> Self {
+        self.level_db = level_db;
+        self
+    }
+
+    pub fn with_placement(mut self, placement: CwIdPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn with_interval_slots(mut self, interval_slots: usize) -> Self {
+        assert!(interval_slots > 0, "ID interval must be at least one slot");
+        self.interval_slots = interval_slots;
+        self
+    }
+
+    /// Length of one dot (seconds)
+    pub fn dot_duration(&self) -> f64 {
+        1.2 / self.wpm
+    }
+
+    /// Length of the keyed callsign (seconds)
+    pub fn duration(&self) -> f64 {
+        keying_pattern(&self.callsign).len() as f64 * self.dot_duration()
+    }
+
+    /// Slots followed (Gaps) or overlaid (Overlay) by an ID
+    pub fn id_slots(&self, num_slots: usize) -> Vec<usize> {
+        (0..num_slots)
+            .filter(|&i| (i + 1) % self.interval_slots == 0 || i + 1 == num_slots)
+            .collect()
+    }
+
+    /// Keying envelope in [0, 1] at the modem sample rate
+    pub fn envelope(&self) -> Vec<f32> {
+        let dot_len = (self.dot_duration() * FS).round() as usize;
+        let edge_len = ((KEY_EDGE * FS) as usize).min(dot_len / 2).max(1);
+
+        let ramp = |from_edge: usize| -> f32 {
+            if from_edge < edge_len {
+                (0.5 - 0.5 * (PI * (from_edge as f64 + 0.5) / edge_len as f64).cos()) as f32
+            } else {
+                1.0
+            }
+        };
+
+        // Key-down runs get a half raised cosine at both ends
+        let mut envelope = Vec::new();
+        for run in keying_pattern(&self.callsign).chunk_by(|a, b| a == b) {
+            let run_len = run.len() * dot_len;
+            if run[0] {
+                envelope.extend((0..run_len).map(|n| ramp(n.min(run_len - 1 - n))));
+            } else {
+                envelope.extend(std::iter::repeat(0.0f32).take(run_len));
+            }
+        }
+        envelope
+    }
+
+    /// Keyed ID tone with key-down RMS `rms`
+    pub fn generate<B: Backend>(&self, device: &B::Device, rms: f32) -> Tensor<B, 1> {
+        let amplitude = rms * std::f32::consts::SQRT_2;
+        let samples: Vec<f32> = self.envelope().iter()
+            .enumerate()
+            .map(|(n, &e)| amplitude * e * (2.0 * PI * self.tone_hz * n as f64 / FS).sin() as f32)
+            .collect();
+
+        Tensor::from_floats(samples.as_slice(), device)
+    }
+}
+
+/// Morse code of a character (dots and dashes)
+pub fn morse_code(c: char) -> Option<&'static str> {
+    let code = match c.to_ascii_uppercase() {
+        'A' => ".-", 'B' => "-...", 'C' => "-.-.", 'D' => "-..", 'E' => ".",
+        'F' => "..-.", 'G' => "--.", 'H' => "....", 'I' => "..", 'J' => ".---",
+        'K' => "-.-", 'L' => ".-..", 'M' => "--", 'N' => "-.", 'O' => "---",
+        'P' => ".--.", 'Q' => "--.-", 'R' => ".-.", 'S' => "...", 'T' => "-",
+        'U' => "..-", 'V' => "...-", 'W' => ".--", 'X' => "-..-", 'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----", '1' => ".----", '2' => "..---", '3' => "...--", '4' => "....-",
+        '5' => ".....", '6' => "-....", '7' => "--...", '8' => "---..", '9' => "----.",
+        '/' => "-..-.", '?' => "..--..",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Key-down state per dot unit (no trailing space)
+pub fn keying_pattern(text: &str) -> Vec<bool> {
+    let mut pattern = Vec::new();
+
+    for (w, word) in text.split_whitespace().enumerate() {
+        if w > 0 {
+            pattern.extend([false; 7]);
+        }
+        for (c, code) in word.chars().filter_map(morse_code).enumerate() {
+            if c > 0 {
+                pattern.extend([false; 3]);
+            }
+            for (e, element) in code.chars().enumerate() {
+                if e > 0 {
+                    pattern.push(false);
+                }
+                let units = if element == '-' { 3 } else { 1 };
+                pattern.extend(std::iter::repeat(true).take(units));
+            }
+        }
+    }
+    pattern
+}
+
+/// Add the station ID to a time-slotted transmission
+///
+/// The level is referenced to the RMS of the first slot. A gap ID that would
+/// run past the end of the signal (after the last slot) extends it. Gap IDs
+/// longer than the listening gap are rejected.
+///
+/// ⚠️ **SYNC POINT**: Reads back the data RMS
+pub fn add_cw_id<B: Backend>(
+    device: &B::Device,
+    signal: Tensor<B, 1>,
+    slots: &TimeSlotConfig,
+    id: &CwIdConfig,
+) -> Result<Tensor<B, 1>, CwIdError> {
+    if id.placement == CwIdPlacement::Gaps && GAP_GUARD + id.duration() > slots.listening_gap {
+        return Err(CwIdError::DoesNotFitGap { duration: id.duration(), gap: slots.listening_gap });
+    }
+
+    let tx_len = ((slots.transmission_duration * FS) as usize).min(signal.dims()[0]);
+    let data_rms: f32 = signal.clone().slice([0..tx_len])
+        .powf_scalar(2.0)
+        .mean()
+        .sqrt()
+        .into_scalar()
+        .elem();
+
+    let tone = id.generate::<B>(device, data_rms * 10f64.powf(id.level_db / 20.0) as f32);
+    let id_len = tone.dims()[0];
+
+    let mut output = signal;
+    for slot in id.id_slots(slots.num_repetitions) {
+        let start = match id.placement {
+            CwIdPlacement::Gaps => slots.slot_starts[slot] + slots.transmission_duration + GAP_GUARD,
+            CwIdPlacement::Overlay => slots.slot_starts[slot],
+        };
+        let start = (start * FS) as usize;
+        let end = start + id_len;
+
+        let len = output.dims()[0];
+        if end > len {
+            output = Tensor::cat(vec![output, Tensor::zeros([end - len], device)], 0);
+        }
+
+        let mixed = output.clone().slice([start..end]).add(tone.clone());
+        output = output.slice_assign([start..end], mixed);
+    }
+    Ok(output)
+}
+
+/// Remove the ID tone before demodulation
+///
+/// Block FFT notch: bins within `CW_ID_NOTCH_WIDTH_HZ / 2` of the tone (and
+/// their negative-frequency mirrors) are zeroed, everything else passes
+/// unchanged. The data alphabet has no energy there, so the decoder sees
+/// the same signal as without an ID.
+pub fn notch_cw_id_gpu<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    tone_hz: f64,
+) -> Tensor<B, 1> {
+    let sig_len = signal.dims()[0];
+    let num_blocks = sig_len.div_ceil(NOTCH_BLOCK).max(1);
+    let padded_len = num_blocks * NOTCH_BLOCK;
+
+    let padded = if sig_len < padded_len {
+        Tensor::cat(vec![signal.clone(), Tensor::zeros([padded_len - sig_len], device)], 0)
+    } else {
+        signal.clone()
+    };
+
+    // Pass/stop mask shared by all blocks
+    let bin_hz = FS / NOTCH_BLOCK as f64;
+    let mask: Vec<f32> = (0..NOTCH_BLOCK)
+        .map(|k| {
+            let freq = k.min(NOTCH_BLOCK - k) as f64 * bin_hz;
+            if (freq - tone_hz).abs() <= CW_ID_NOTCH_WIDTH_HZ / 2.0 { 0.0 } else { 1.0 }
+        })
+        .collect();
+    let mask = Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([1, NOTCH_BLOCK]);
+
+    let real = match padded.reshape([num_blocks, NOTCH_BLOCK]).into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+    let imag = match Tensor::<B, 2>::zeros([num_blocks, NOTCH_BLOCK], device).into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+
+    let (fft_real_t, fft_imag_t) = B::fft_1d_batch_impl(real, imag, NOTCH_BLOCK);
+    let fft_real: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_real_t));
+    let fft_imag: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_imag_t));
+
+    // IFFT = FFT with negated imaginary part, then scale by 1/N
+    let notched_real = match fft_real.mul(mask.clone()).into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+    let notched_imag_neg = match fft_imag.mul(mask).neg().into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+
+    let (ifft_real_t, _ifft_imag_t) = B::fft_1d_batch_impl(notched_real, notched_imag_neg, NOTCH_BLOCK);
+    let filtered: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(ifft_real_t));
+
+    filtered.div_scalar(NOTCH_BLOCK as f32)
+        .reshape([padded_len])
+        .slice([0..sig_len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    fn energy(signal: Tensor<TestBackend, 1>) -> f32 {
+        signal.powf_scalar(2.0).sum().into_scalar()
+    }
+
+    #[test]
+    fn test_paris_timing() {
+        // PARIS plus a word space is exactly 50 dot units
+        assert_eq!(keying_pattern("PARIS").len() + 7, 50);
+        assert_eq!(keying_pattern("ET"), [true, false, false, false, true, true, true]);
+        assert_eq!(keying_pattern("E T").len(), 1 + 7 + 3);
+
+        let id = CwIdConfig::new("e");
+        assert!((id.dot_duration() - 0.06).abs() < 1e-12);
+        assert_eq!(id.envelope().len(), 480);
+    }
+
+    #[test]
+    fn test_gap_id_leaves_slots_untouched() {
+        let device = Default::default();
+        let slots = TimeSlotConfig {
+            transmission_duration: 1.0,
+            listening_gap: 5.0,
+            num_repetitions: 2,
+            slot_starts: vec![0.0, 6.0],
+            flourish_interval: 0,
+        };
+        let signal = Tensor::<TestBackend, 1>::ones([56000], &device)
+            .slice_assign([8000..48000], Tensor::zeros([40000], &device));
+
+        let id = CwIdConfig::new("DL1ABC").with_interval_slots(1);
+        let output = add_cw_id::<TestBackend>(&device, signal.clone(), &slots, &id).unwrap();
+
+        // Last gap ID runs past the end of the signal
+        let out_len = output.dims()[0];
+        assert!(out_len > 56000);
+
+        // Slots unchanged, both gaps keyed
+        let diff = output.clone().slice([0..56000]).sub(signal);
+        assert_eq!(energy(diff.clone().slice([0..8000])), 0.0);
+        assert_eq!(energy(diff.clone().slice([48000..56000])), 0.0);
+        assert!(energy(output.clone().slice([8000..48000])) > 0.0);
+        assert!(energy(output.slice([56000..out_len])) > 0.0);
+
+        // A long callsign at slow speed doesn't fit a 5 s gap
+        let long_id = CwIdConfig::new("DL1ABC/P").with_wpm(5.0);
+        let signal = Tensor::<TestBackend, 1>::zeros([56000], &device);
+        assert!(matches!(
+            add_cw_id::<TestBackend>(&device, signal, &slots, &long_id),
+            Err(CwIdError::DoesNotFitGap { gap, .. }) if gap == 5.0
+        ));
+    }
+
+    #[test]
+    fn test_notch_removes_overlay_id() {
+        let device = Default::default();
+        let config = ModemConfig::wideband();
+        let data = b"DL1ABC k";
+        let clean = modulate_fhdpsk_with_config::<TestBackend>(&device, data, false, &config);
+        let len = clean.dims()[0];
+
+        let slots = TimeSlotConfig {
+            transmission_duration: len as f64 / FS,
+            listening_gap: 0.0,
+            num_repetitions: 1,
+            slot_starts: vec![0.0],
+            flourish_interval: 0,
+        };
+        let id = CwIdConfig::new("DL1ABC")
+            .with_placement(CwIdPlacement::Overlay)
+            .with_level_db(0.0);
+        let with_id = add_cw_id::<TestBackend>(&device, clean.clone(), &slots, &id).unwrap().slice([0..len]);
+
+        let data_energy = energy(clean.clone());
+        let id_energy = energy(with_id.clone().sub(clean.clone()));
+        assert!(id_energy > 0.1 * data_energy);
+
+        // What's left of the ID plus notch distortion stays ~27 dB below the data
+        let notched = notch_cw_id_gpu::<TestBackend>(&device, &with_id, id.tone_hz);
+        let residual = energy(notched.sub(clean));
+        assert!(residual < 2e-3 * data_energy, "residual {} vs data {}", residual, data_energy);
+    }
+}
@@ -0,0 +1,344 @@
+/// Decision Traces: Record and Replay of Receiver Decisions
+///
+/// When a change to the DSP or the decoder makes a capture stop decoding,
+/// the end result says nothing about where the two builds parted ways: the
+/// sync may have moved by a sample, the RAKE may have picked another path,
+/// or BP may have wandered off on iteration 12. A `DecisionTrace` keeps
+/// every discrete decision the receive chain makes on one capture:
+///
+/// - the data start found by sync
+/// - the RAKE fingers combined (delay and weight)
+/// - the window offset of every data symbol (flourish re-syncs, drift
+///   tracking, timing recovery)
+/// - the blind SNR and the hard decisions of the LLRs
+/// - the hard decisions on u after every BP iteration
+/// - the decoded info bits
+///
+/// `decode_capture_traced` records it on the same code path as
+/// `decode_capture`. Given a previous trace it replays
+/// instead: sync and finger detection are skipped and the recorded data
+/// start and fingers are pinned, so the demodulator and the decoder see
+/// exactly the samples they saw then. `first_divergence` names the first
+/// stage whose decisions differ, which makes a `git bisect` over a stored
+/// trace deterministic.
+///
+/// Traces are stored as compact `key = value` text, bits as hex:
+///
+/// ```text
+/// # BachModem decision trace
+/// data_start = 23456
+/// rake_fingers = 0:1 37:0.41
+/// symbol_offsets = 0 0 0.25 ... -1.5
+/// snr_db = 4.8173
+/// llr_signs = 9f03...
+/// bp = 9f07...        (one line per iteration, in order)
+/// info_bits = 4849...
+/// ```
+
+use std::fmt;
+use std::path::Path;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{encode_bits, pack_bits};
+use crate::rake::RakeFinger;
+use crate::receiver_pool::{decode_capture_impl, DecodeError, DecodedFrame, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+use crate::tuning::parse_entries;
+
+/// Decisions of the receive chain on one capture
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecisionTrace {
+    /// Data start found by sync (None = unsynced capture)
+    pub data_start: Option<usize>,
+
+    /// RAKE fingers combined: (delay in samples, weight)
+    pub rake_fingers: Vec<(usize, f32)>,
+
+    /// Window offset of every data symbol from its nominal position (samples)
+    pub symbol_offsets: Vec<f32>,
+
+    /// Blind matched-filter SNR (dB)
+    pub snr_db: f32,
+
+    /// Hard decisions of the interleaved LLRs (1 = negative LLR)
+    pub llr_signs: Vec<u8>,
+
+    /// Hard decisions on u after each BP iteration (empty for list decoding)
+    pub bp_decisions: Vec<Vec<u8>>,
+
+    /// Decoded info bits
+    pub info_bits: Vec<u8>,
+}
+
+/// First stage where two traces of one capture disagree
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceDivergence {
+    DataStart { recorded: Option<usize>, replayed: Option<usize> },
+    RakeFingers,
+
+    /// First data symbol whose window moved
+    SymbolOffsets { symbol: usize },
+
+    SnrDb { recorded: f32, replayed: f32 },
+
+    /// Number of LLR signs that flipped
+    LlrSigns { flipped: usize },
+
+    /// First BP iteration (0-based) whose decisions differ
+    BpIteration { iteration: usize, flipped: usize },
+
+    /// The traces agree up to the shorter one's last BP iteration
+    BpIterations { recorded: usize, replayed: usize },
+
+    InfoBits { flipped: usize },
+}
+
+impl fmt::Display for TraceDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceDivergence::DataStart { recorded, replayed } => write!(f, "data start {:?} -> {:?}", recorded, replayed),
+            TraceDivergence::RakeFingers => write!(f, "RAKE fingers differ"),
+            TraceDivergence::SymbolOffsets { symbol } => write!(f, "symbol {} window moved", symbol),
+            TraceDivergence::SnrDb { recorded, replayed } => write!(f, "SNR {} dB -> {} dB", recorded, replayed),
+            TraceDivergence::LlrSigns { flipped } => write!(f, "{} LLR signs flipped", flipped),
+            TraceDivergence::BpIteration { iteration, flipped } => write!(f, "BP iteration {}: {} decisions flipped", iteration, flipped),
+            TraceDivergence::BpIterations { recorded, replayed } => write!(f, "{} BP iterations -> {}", recorded, replayed),
+            TraceDivergence::InfoBits { flipped } => write!(f, "{} info bits flipped", flipped),
+        }
+    }
+}
+
+/// Trace parse error
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "decision trace line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+/// Bits as hex, padded to whole bytes
+fn bits_to_hex(bits: &[u8]) -> String {
+    pack_bits(bits).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hex_to_bits(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some(encode_bits(&bytes))
+}
+
+fn count_flipped(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).filter(|(x, y)| x != y).count() + a.len().abs_diff(b.len())
+}
+
+impl DecisionTrace {
+    /// Fingers to pin on replay (`RakeReceiver::combine_paths` only uses
+    /// delay and weight)
+    pub fn pinned_fingers(&self) -> Vec<RakeFinger> {
+        self.rake_fingers.iter()
+            .map(|&(delay, weight)| RakeFinger { delay, amplitude: weight, phase: 0.0, weight })
+            .collect()
+    }
+
+    /// Parse `key = value` lines; `#` lines are comments, unknown keys are ignored
+    pub fn parse(text: &str) -> Result<Self, TraceError> {
+        let mut trace = Self::default();
+
+        parse_entries(text, |key, value| {
+            let bits = |value: &str| hex_to_bits(value)
+                .ok_or_else(|| format!("{} must be hex, got '{}'", key, value));
+
+            match key {
+                "data_start" => {
+                    trace.data_start = match value {
+                        "none" => None,
+                        _ => Some(value.parse().map_err(|_| format!("data_start must be a sample index, got '{}'", value))?),
+                    }
+                }
+                "rake_fingers" => {
+                    trace.rake_fingers = value.split_whitespace()
+                        .map(|finger| {
+                            let (delay, weight) = finger.split_once(':')?;
+                            Some((delay.parse().ok()?, weight.parse().ok()?))
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| format!("rake_fingers must be delay:weight pairs, got '{}'", value))?;
+                }
+                "symbol_offsets" => {
+                    trace.symbol_offsets = value.split_whitespace()
+                        .map(|offset| offset.parse().ok())
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| format!("symbol_offsets must be numbers, got '{}'", value))?;
+                }
+                "snr_db" => trace.snr_db = value.parse().map_err(|_| format!("snr_db must be a number, got '{}'", value))?,
+                "llr_signs" => trace.llr_signs = bits(value)?,
+                "bp" => trace.bp_decisions.push(bits(value)?),
+                "info_bits" => trace.info_bits = bits(value)?,
+                _ => {}
+            }
+            Ok(())
+        }).map_err(|err| TraceError { line: err.line, message: err.message })?;
+        Ok(trace)
+    }
+
+    /// Trace text; `comment` lines go into the header
+    ///
+    /// Floats are written in their shortest exact form, so a trace read
+    /// back compares equal.
+    pub fn to_text(&self, comment: &str) -> String {
+        let mut text = String::from("# BachModem decision trace\n");
+        for line in comment.lines() {
+            text.push_str(&format!("# {}\n", line));
+        }
+        match self.data_start {
+            Some(start) => text.push_str(&format!("data_start = {}\n", start)),
+            None => text.push_str("data_start = none\n"),
+        }
+        let fingers: Vec<String> = self.rake_fingers.iter().map(|(delay, weight)| format!("{}:{}", delay, weight)).collect();
+        text.push_str(&format!("rake_fingers = {}\n", fingers.join(" ")));
+        let offsets: Vec<String> = self.symbol_offsets.iter().map(|offset| offset.to_string()).collect();
+        text.push_str(&format!("symbol_offsets = {}\n", offsets.join(" ")));
+        text.push_str(&format!("snr_db = {}\n", self.snr_db));
+        text.push_str(&format!("llr_signs = {}\n", bits_to_hex(&self.llr_signs)));
+        for decisions in &self.bp_decisions {
+            text.push_str(&format!("bp = {}\n", bits_to_hex(decisions)));
+        }
+        text.push_str(&format!("info_bits = {}\n", bits_to_hex(&self.info_bits)));
+        text
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, comment: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text(comment))
+    }
+}
+
+/// First stage, in pipeline order, where `replayed` departs from `recorded`
+///
+/// The SNR is compared exactly: on one device a replay is bit-exact, so
+/// any difference is a code change.
+pub fn first_divergence(recorded: &DecisionTrace, replayed: &DecisionTrace) -> Option<TraceDivergence> {
+    if recorded.data_start != replayed.data_start {
+        return Some(TraceDivergence::DataStart { recorded: recorded.data_start, replayed: replayed.data_start });
+    }
+    if recorded.rake_fingers != replayed.rake_fingers {
+        return Some(TraceDivergence::RakeFingers);
+    }
+    let (a, b) = (&recorded.symbol_offsets, &replayed.symbol_offsets);
+    let offset = |offsets: &[f32], i: usize| offsets.get(i).map(|o| o.to_bits());
+    if let Some(symbol) = (0..a.len().max(b.len())).find(|&i| offset(a, i) != offset(b, i)) {
+        return Some(TraceDivergence::SymbolOffsets { symbol });
+    }
+    if recorded.snr_db.to_bits() != replayed.snr_db.to_bits() {
+        return Some(TraceDivergence::SnrDb { recorded: recorded.snr_db, replayed: replayed.snr_db });
+    }
+    let flipped = count_flipped(&recorded.llr_signs, &replayed.llr_signs);
+    if flipped > 0 {
+        return Some(TraceDivergence::LlrSigns { flipped });
+    }
+    for (iteration, (a, b)) in recorded.bp_decisions.iter().zip(&replayed.bp_decisions).enumerate() {
+        let flipped = count_flipped(a, b);
+        if flipped > 0 {
+            return Some(TraceDivergence::BpIteration { iteration, flipped });
+        }
+    }
+    if recorded.bp_decisions.len() != replayed.bp_decisions.len() {
+        return Some(TraceDivergence::BpIterations { recorded: recorded.bp_decisions.len(), replayed: replayed.bp_decisions.len() });
+    }
+    let flipped = count_flipped(&recorded.info_bits, &replayed.info_bits);
+    (flipped > 0).then_some(TraceDivergence::InfoBits { flipped })
+}
+
+/// `decode_capture`, recording its decisions; with a `replay` trace the
+/// recorded data start and RAKE fingers are pinned instead of searched
+///
+/// Runs the same chain as `decode_capture` (front end, noise floor update,
+/// frame SNR), so a trace describes what the pool did on the capture.
+///
+/// The trace comes back even when the frame doesn't decode (as far as the
+/// chain got), which is the case worth tracing.
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR, the LLR signs and the decoder decisions
+pub fn decode_capture_traced<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    replay: Option<&DecisionTrace>,
+) -> (Result<DecodedFrame, DecodeError>, DecisionTrace) {
+    let mut trace = DecisionTrace::default();
+    let frame = decode_capture_impl(device, state, config, signal, replay, Some(&mut trace));
+    (frame, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::transmitter::BachTransmitter;
+    use crate::tuning::ReceiverTuning;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_trace_replays_and_names_divergence() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig {
+            tuning: ReceiverTuning { bp_iterations: 20, rake_fingers: 2, ..ReceiverTuning::default() },
+            ..ReceiverPoolConfig::default()
+        };
+        let frame = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"TRACE ME").unwrap();
+        let frame_len = frame.dims()[0];
+        let mut rng = SplitMix64::new(7);
+        let rx = Tensor::cat(vec![Tensor::zeros([3000], &device), frame, Tensor::zeros([1000], &device)], 0)
+            + gaussian_noise::<TestBackend, _>(&device, frame_len + 4000, 0.1, &mut rng);
+
+        let mut state = ReceiverState::<TestBackend>::default();
+        let (decoded, recorded) = decode_capture_traced(&device, &mut state, &config, &rx, None);
+        assert!(decoded.unwrap().payload.starts_with(b"TRACE ME"));
+        assert_eq!(recorded.bp_decisions.len(), 20);
+        assert!(recorded.data_start.is_some());
+
+        // The text form reads back identical
+        let stored = DecisionTrace::parse(&recorded.to_text("test capture")).unwrap();
+        assert_eq!(stored, recorded);
+
+        // Replay on the same code reproduces every decision
+        let mut state = ReceiverState::<TestBackend>::default();
+        let (_, replayed) = decode_capture_traced(&device, &mut state, &config, &rx, Some(&stored));
+        assert_eq!(first_divergence(&stored, &replayed), None);
+
+        // The text form keeps the symbol timing
+        assert!(!stored.symbol_offsets.is_empty());
+        assert_eq!(stored.symbol_offsets, recorded.symbol_offsets);
+
+        // A sync that lands elsewhere shows up downstream of the data start
+        let mut moved = stored.clone();
+        moved.data_start = moved.data_start.map(|start| start + 40);
+        let mut state = ReceiverState::<TestBackend>::default();
+        let (_, replayed) = decode_capture_traced(&device, &mut state, &config, &rx, Some(&moved));
+        assert!(matches!(first_divergence(&stored, &replayed), Some(TraceDivergence::DataStart { .. })));
+        assert!(matches!(first_divergence(&moved, &replayed), Some(TraceDivergence::SymbolOffsets { .. } | TraceDivergence::SnrDb { .. })));
+
+        // Fewer BP iterations agree up to the last one
+        let shorter = ReceiverPoolConfig { tuning: ReceiverTuning { bp_iterations: 10, ..config.tuning }, ..config.clone() };
+        let mut state = ReceiverState::<TestBackend>::default();
+        let (_, replayed) = decode_capture_traced(&device, &mut state, &shorter, &rx, Some(&stored));
+        assert_eq!(first_divergence(&stored, &replayed), Some(TraceDivergence::BpIterations { recorded: 20, replayed: 10 }));
+    }
+}
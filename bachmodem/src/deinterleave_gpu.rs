@@ -1,16 +1,196 @@
 /// GPU-based deinterleaving operations
 /// 
 /// Keeps data on GPU instead of downloading to CPU for deinterleaving
+/// 
+/// `deinterleave_auto` / `interleave_auto` pick the CPU loop or the GPU
+/// transpose per call:
+/// - Data already on the device stays there (a download is a sync point)
+/// - Host data below `gpu_min_len` is permuted on the CPU: for one 256-LLR
+///   codeword the upload + kernel launch + download dwarfs the loop
+/// - Large host batches (many codewords) are uploaded and permuted on GPU,
+///   and the result stays on the device for the GPU decoders
+/// - Lengths not divisible by the column count always use the CPU loop,
+///   which handles the partial last row
+/// 
+/// The crossover depends on upload latency of the machine:
+/// `InterleaveDispatch::calibrate` measures it on the running device, and
+/// `cargo run --release --example interleave_dispatch` prints the timings
+/// behind it.
+///
+/// The receive chain (`decode_llrs`, the transmitter self-check, the soak
+/// decoder) deinterleaves through `deinterleave_auto`: device-resident LLRs
+/// stay on the GPU, and the convolutional path, which Viterbi-decodes on
+/// the host, downloads first and permutes on the CPU.
 
 use burn::tensor::{Tensor, backend::Backend};
+use std::time::{Duration, Instant};
+use crate::transmitter::CODE_N;
+
+/// Host batches at least this long go to the GPU by default (64 codewords)
+///
+/// A conservative guess, not a measurement: one codeword must stay on the
+/// CPU on any machine, and few GPUs upload, launch and sync in less time
+/// than the CPU loop needs for 64. Use `InterleaveDispatch::calibrate` for
+/// the crossover of the actual device.
+pub const DEFAULT_GPU_MIN_LEN: usize = 64 * CODE_N;
+
+/// Batch sizes tried by `InterleaveDispatch::calibrate` (codewords)
+const CALIBRATION_CODEWORDS: [usize; 6] = [1, 4, 16, 64, 256, 1024];
+
+/// Timed runs per path and size in `InterleaveDispatch::calibrate` (median)
+const CALIBRATION_RUNS: usize = 5;
+
+/// LLRs on the host or already on the device
+pub enum Llrs<B: Backend> {
+    Host(Vec<f32>),
+    Device(Tensor<B, 1>),
+}
+
+impl<B: Backend> Llrs<B> {
+    /// Number of LLRs
+    pub fn len(&self) -> usize {
+        match self {
+            Llrs::Host(v) => v.len(),
+            Llrs::Device(t) => t.dims()[0],
+        }
+    }
+
+    /// True if there are no LLRs
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True if the LLRs live on the device
+    pub fn on_device(&self) -> bool {
+        matches!(self, Llrs::Device(_))
+    }
+
+    /// Host copy of the LLRs
+    /// 
+    /// ⚠️ **SYNC POINT**: Downloads device-resident LLRs
+    pub fn into_vec(self) -> Vec<f32> {
+        match self {
+            Llrs::Host(v) => v,
+            Llrs::Device(t) => t.into_data().to_vec().unwrap(),
+        }
+    }
+
+    /// Device tensor of the LLRs (uploads host data)
+    pub fn into_tensor(self, device: &B::Device) -> Tensor<B, 1> {
+        match self {
+            Llrs::Host(v) => Tensor::from_floats(v.as_slice(), device),
+            Llrs::Device(t) => t,
+        }
+    }
+}
+
+/// Where an interleaver permutation runs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InterleavePath {
+    Cpu,
+    Gpu,
+}
+
+/// Size heuristic for CPU/GPU interleaver selection
+#[derive(Clone, Copy, Debug)]
+pub struct InterleaveDispatch {
+    /// Minimum host-resident length worth uploading
+    pub gpu_min_len: usize,
+}
+
+impl Default for InterleaveDispatch {
+    fn default() -> Self {
+        Self { gpu_min_len: DEFAULT_GPU_MIN_LEN }
+    }
+}
+
+impl InterleaveDispatch {
+    /// Dispatch with `gpu_min_len` measured on `device`
+    ///
+    /// The smallest batch of `CALIBRATION_CODEWORDS` where uploading and
+    /// transposing on the GPU beats the CPU loop (median of
+    /// `CALIBRATION_RUNS` after a warm-up); host data never goes to the GPU
+    /// if the CPU wins everywhere.
+    ///
+    /// ⚠️ **SYNC POINT**: Syncs the device after every GPU run
+    pub fn calibrate<B: Backend>(device: &B::Device, num_cols: usize) -> Self {
+        let gpu_min_len = CALIBRATION_CODEWORDS.iter()
+            .map(|&codewords| codewords * CODE_N)
+            .find(|&len| {
+                let llrs: Vec<f32> = (0..len).map(|i| (i % 7) as f32 - 3.0).collect();
+                let cpu = median_time(|| {
+                    std::hint::black_box(crate::interleaver::deinterleave(&llrs, num_cols));
+                });
+                let gpu = median_time(|| {
+                    let tensor = Tensor::<B, 1>::from_floats(llrs.as_slice(), device);
+                    let _ = deinterleave_gpu(device, &tensor, num_cols);
+                    B::sync(device).unwrap();
+                });
+                gpu < cpu
+            })
+            .unwrap_or(usize::MAX);
+        Self { gpu_min_len }
+    }
+
+    /// Pick the path for `len` LLRs over `num_cols` columns
+    pub fn select(&self, len: usize, num_cols: usize, on_device: bool) -> InterleavePath {
+        if num_cols == 0 || len % num_cols != 0 {
+            InterleavePath::Cpu
+        } else if on_device || len >= self.gpu_min_len {
+            InterleavePath::Gpu
+        } else {
+            InterleavePath::Cpu
+        }
+    }
+
+    /// Deinterleave on the selected path
+    pub fn deinterleave<B: Backend>(&self, device: &B::Device, llrs: Llrs<B>, num_cols: usize) -> Llrs<B> {
+        match self.select(llrs.len(), num_cols, llrs.on_device()) {
+            InterleavePath::Cpu => Llrs::Host(crate::interleaver::deinterleave(&llrs.into_vec(), num_cols)),
+            InterleavePath::Gpu => Llrs::Device(deinterleave_gpu(device, &llrs.into_tensor(device), num_cols)),
+        }
+    }
+
+    /// Interleave on the selected path
+    pub fn interleave<B: Backend>(&self, device: &B::Device, llrs: Llrs<B>, num_cols: usize) -> Llrs<B> {
+        match self.select(llrs.len(), num_cols, llrs.on_device()) {
+            InterleavePath::Cpu => Llrs::Host(crate::interleaver::interleave(&llrs.into_vec(), num_cols)),
+            InterleavePath::Gpu => Llrs::Device(interleave_gpu(device, &llrs.into_tensor(device), num_cols)),
+        }
+    }
+}
+
+/// Median wall time of `run` over `CALIBRATION_RUNS` runs, after a warm-up
+fn median_time(mut run: impl FnMut()) -> Duration {
+    run();
+    let mut times: Vec<Duration> = (0..CALIBRATION_RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[CALIBRATION_RUNS / 2]
+}
+
+/// Deinterleave with the default CPU/GPU heuristic
+pub fn deinterleave_auto<B: Backend>(device: &B::Device, llrs: Llrs<B>, num_cols: usize) -> Llrs<B> {
+    InterleaveDispatch::default().deinterleave(device, llrs, num_cols)
+}
+
+/// Interleave with the default CPU/GPU heuristic
+pub fn interleave_auto<B: Backend>(device: &B::Device, llrs: Llrs<B>, num_cols: usize) -> Llrs<B> {
+    InterleaveDispatch::default().interleave(device, llrs, num_cols)
+}
 
 /// Deinterleave LLRs on GPU using gather operation
 /// 
 /// Input: [N] interleaved LLRs
 /// Output: [N] deinterleaved LLRs
 /// 
-/// Interleaving writes column-wise, reads row-wise (16x16 grid)
-/// Deinterleaving reverses this: write row-wise, read column-wise
+/// Interleaving writes row-wise, reads column-wise ([Rows, Cols] grid)
+/// Deinterleaving reverses this: the interleaved stream is [Cols, Rows]
 pub fn deinterleave_gpu<B: Backend>(
     device: &B::Device,
     interleaved: &Tensor<B, 1>,
@@ -24,10 +204,10 @@ pub fn deinterleave_gpu<B: Backend>(
         return interleaved.clone();
     }
     
-    // Reshape to matrix: [Rows, Cols]
-    let matrix = interleaved.clone().reshape([num_rows, num_cols]);
+    // Reshape to matrix: [Cols, Rows] (column-major read order)
+    let matrix = interleaved.clone().reshape([num_cols, num_rows]);
     
-    // Transpose: [Cols, Rows]
+    // Transpose: [Rows, Cols]
     let transposed = matrix.swap_dims(0, 1);
     
     // Flatten back: [N]
@@ -84,4 +264,50 @@ mod tests {
         
         println!("GPU deinterleave test passed!");
     }
+    
+    #[test]
+    fn test_deinterleave_gpu_matches_cpu_non_square() {
+        let device = Default::default();
+        
+        // 256 bits over 8 columns (8-tone alphabet): 32 x 8 grid
+        let bits: Vec<u8> = (0..256).map(|i| ((i * 7) % 3 == 0) as u8).collect();
+        let interleaved = crate::interleaver::interleave(&bits, 8);
+        
+        let llrs: Vec<f32> = interleaved.iter().map(|&b| b as f32).collect();
+        let tensor = Tensor::<TestBackend, 1>::from_floats(llrs.as_slice(), &device);
+        let restored: Vec<f32> = deinterleave_gpu::<TestBackend>(&device, &tensor, 8)
+            .into_data().to_vec().unwrap();
+        
+        let expected: Vec<f32> = bits.iter().map(|&b| b as f32).collect();
+        assert_eq!(restored, expected);
+    }
+    
+    #[test]
+    fn test_dispatch_paths_agree() {
+        let device = Default::default();
+        let llrs: Vec<f32> = (0..256).map(|i| i as f32).collect();
+        let expected = crate::interleaver::deinterleave(&llrs, 8);
+        
+        let dispatch = InterleaveDispatch::default();
+        assert_eq!(dispatch.select(256, 8, false), InterleavePath::Cpu);
+        assert_eq!(dispatch.select(256, 8, true), InterleavePath::Gpu);
+        assert_eq!(dispatch.select(DEFAULT_GPU_MIN_LEN, 8, false), InterleavePath::Gpu);
+        assert_eq!(dispatch.select(250, 8, true), InterleavePath::Cpu);
+        
+        let host = deinterleave_auto::<TestBackend>(&device, Llrs::Host(llrs.clone()), 8);
+        assert!(!host.on_device());
+        assert_eq!(host.into_vec(), expected);
+        
+        let on_device = Tensor::<TestBackend, 1>::from_floats(llrs.as_slice(), &device);
+        let gpu = deinterleave_auto::<TestBackend>(&device, Llrs::Device(on_device), 8);
+        assert!(gpu.on_device());
+        assert_eq!(gpu.into_vec(), expected);
+        
+        // The measured crossover is one of the tried batch sizes (or never)
+        let calibrated = InterleaveDispatch::calibrate::<TestBackend>(&device, 8);
+        assert!(calibrated.gpu_min_len == usize::MAX
+            || CALIBRATION_CODEWORDS.iter().any(|&codewords| codewords * 256 == calibrated.gpu_min_len));
+        let host = calibrated.deinterleave::<TestBackend>(&device, Llrs::Host(llrs.clone()), 8);
+        assert_eq!(host.into_vec(), expected);
+    }
 }
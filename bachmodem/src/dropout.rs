@@ -0,0 +1,191 @@
+/// Dropout Detection and LLR Erasure
+///
+/// Short audio dropouts (USB buffer glitches, RX muting) show up as stretches
+/// of near-zero samples. Phases measured inside such a gap are meaningless,
+/// and since every bit is the phase difference between a symbol and the one
+/// `lag` symbols earlier, the gap also poisons the bits of the following block.
+///
+/// The detector measures energy in the central sub-windows of each symbol
+/// (where the Morlet envelope carries the energy) and flags symbols whose
+/// quietest sub-window falls far below the median. Every LLR that touches a
+/// flagged symbol is nulled to an erasure (0.0), which the polar BP decoder
+/// treats as "no information" instead of a confident wrong bit.
+///
+/// Re-anchoring: the first intact symbol of each tone slot after the gap is
+/// the new phase reference for that slot (dead-reckoning across the gap), so
+/// the erasures stay bounded to the gap plus the one block referencing it.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::gpu_ops::median_gpu;
+
+/// Sub-window energy below this fraction of the median marks a dropout
+pub const DROPOUT_THRESHOLD: f32 = 0.1;
+
+/// Number of sub-windows measured across the central half of each symbol
+pub const DROPOUT_SUBWINDOWS: usize = 4;
+
+/// A run of consecutive dropped symbols
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dropout {
+    /// First dropped symbol
+    pub start_symbol: usize,
+
+    /// One past the last dropped symbol
+    pub end_symbol: usize,
+}
+
+impl Dropout {
+    /// Number of dropped symbols
+    pub fn len(&self) -> usize {
+        self.end_symbol - self.start_symbol
+    }
+
+    /// True if the run is empty
+    pub fn is_empty(&self) -> bool {
+        self.end_symbol == self.start_symbol
+    }
+}
+
+/// Energy of each central sub-window of each symbol
+///
+/// symbols: [NumSymbols, SymbolLen]
+/// Returns: [NumSymbols, DROPOUT_SUBWINDOWS]
+///
+/// **NO SYNC POINT**: Stays on GPU
+pub fn symbol_subwindow_energy_gpu<B: Backend>(symbols: Tensor<B, 2>) -> Tensor<B, 2> {
+    let [num_symbols, symbol_len] = symbols.dims();
+
+    // Central half of the symbol: [-1.5s, 1.5s] of the Morlet envelope
+    let sub_len = (symbol_len / 2) / DROPOUT_SUBWINDOWS;
+    let start = symbol_len / 4;
+    let end = start + sub_len * DROPOUT_SUBWINDOWS;
+
+    symbols
+        .slice([0..num_symbols, start..end])
+        .reshape([num_symbols, DROPOUT_SUBWINDOWS, sub_len])
+        .powf_scalar(2.0)
+        .sum_dim(2)
+        .reshape([num_symbols, DROPOUT_SUBWINDOWS])
+}
+
+/// Flag symbols hit by an energy gap
+///
+/// symbols: [NumSymbols, SymbolLen]
+/// Returns: one flag per symbol (true = dropped)
+///
+/// The median sub-window energy is taken on the device (`median_gpu`).
+///
+/// ⚠️ **SYNC POINT**: Downloads one flag per symbol
+pub fn detect_dropouts_gpu<B: Backend>(symbols: &Tensor<B, 2>, threshold: f32) -> Vec<bool> {
+    let num_symbols = symbols.dims()[0];
+    let energies = symbol_subwindow_energy_gpu(symbols.clone());
+    let median = median_gpu(energies.clone().reshape([num_symbols * DROPOUT_SUBWINDOWS]));
+
+    // Silence everywhere (zero median) flags nothing: no energy is below zero
+    let flags: Vec<f32> = (energies.min_dim(1).reshape([num_symbols]) - median.mul_scalar(threshold))
+        .lower_elem(0.0)
+        .float()
+        .into_data()
+        .to_vec()
+        .unwrap();
+
+    flags.iter().map(|&f| f > 0.5).collect()
+}
+
+/// Group per-symbol flags into runs
+pub fn group_dropouts(flags: &[bool]) -> Vec<Dropout> {
+    let mut dropouts = Vec::new();
+    let mut start = None;
+
+    for (i, &dropped) in flags.iter().enumerate() {
+        match (dropped, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                dropouts.push(Dropout { start_symbol: s, end_symbol: i });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        dropouts.push(Dropout { start_symbol: s, end_symbol: flags.len() });
+    }
+
+    dropouts
+}
+
+/// LLR erasure mask for lag-differential decoding
+///
+/// LLR j compares symbol j + lag against symbol j, so it is erased (0.0)
+/// if either symbol was dropped.
+pub fn llr_erasure_mask(flags: &[bool], lag: usize, num_llrs: usize) -> Vec<f32> {
+    (0..num_llrs)
+        .map(|j| {
+            let prev_dropped = flags.get(j).copied().unwrap_or(false);
+            let curr_dropped = flags.get(j + lag).copied().unwrap_or(false);
+            if prev_dropped || curr_dropped { 0.0 } else { 1.0 }
+        })
+        .collect()
+}
+
+/// Null the LLRs touched by dropped symbols
+///
+/// **NO SYNC POINT**: Mask is uploaded, multiplication stays on GPU
+pub fn null_dropout_llrs<B: Backend>(
+    device: &B::Device,
+    llrs: Tensor<B, 1>,
+    flags: &[bool],
+    lag: usize,
+) -> Tensor<B, 1> {
+    let num_llrs = llrs.dims()[0];
+    let mask = llr_erasure_mask(flags, lag, num_llrs);
+    llrs * Tensor::<B, 1>::from_floats(mask.as_slice(), device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_detect_dropout_gap() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let symbol_len = config.symbol_samples();
+
+        let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Dropout!", false, &config);
+        let num_symbols = signal.dims()[0] / symbol_len;
+        let mut samples: Vec<f32> = signal.into_data().to_vec().unwrap();
+
+        // Glitch: zero out 2.5 symbols starting mid-symbol 20
+        let gap_start = 20 * symbol_len + symbol_len / 2;
+        let gap_end = gap_start + 2 * symbol_len + symbol_len / 2;
+        samples[gap_start..gap_end].iter_mut().for_each(|s| *s = 0.0);
+
+        let symbols = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device)
+            .reshape([num_symbols, symbol_len]);
+        let flags = detect_dropouts_gpu(&symbols, DROPOUT_THRESHOLD);
+
+        assert_eq!(group_dropouts(&flags), vec![Dropout { start_symbol: 20, end_symbol: 23 }]);
+
+        // Silence everywhere is not a dropout
+        let silence = Tensor::<TestBackend, 2>::zeros([8, symbol_len], &device);
+        assert!(detect_dropouts_gpu(&silence, DROPOUT_THRESHOLD).iter().all(|&f| !f));
+    }
+
+    #[test]
+    fn test_llr_erasure_mask() {
+        let mut flags = vec![false; 12];
+        flags[5] = true;
+
+        let mask = llr_erasure_mask(&flags, 4, 8);
+
+        // LLR 1 compares symbol 5 against symbol 1; LLR 5 uses symbol 5 as reference
+        assert_eq!(mask, vec![1.0, 0.0, 1.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+    }
+}
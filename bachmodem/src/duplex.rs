@@ -0,0 +1,508 @@
+/// Split-Band Full Duplex Bench Mode
+///
+/// Bench testing through real hardware: transmissions play on one soundcard
+/// while a second soundcard captures, both streams running at once. The loop
+/// can be an audio cable, a pair of radios into a dummy load, or acoustic
+/// coupling, so the converters, the USB stack and the whole receive chain are
+/// exercised instead of a software channel.
+///
+/// Both streams are timestamped against one monotonic clock (the session
+/// origin), with the callback-to-DAC and ADC-to-callback delays reported by
+/// the audio host folded in:
+/// - The output callback records when the first sample of each queued
+///   transmission reaches the DAC (`TimestampCorrelator::record_tx`)
+/// - Captured samples carry the time of their first sample, so a preamble
+///   found at index `i` started at `capture_start + i / FS`
+/// - `match_rx` pairs a detected preamble with the transmission it belongs to
+///   and accumulates path latency; `expire` reports transmissions never heard
+///
+/// The correlator and resampler work without an audio device; the streams
+/// themselves (`DuplexSession`) need feature `audio`. There is no ARQ layer
+/// in the crate yet: the bench loop (`--example full_duplex`) counts decoded
+/// and lost frames, which is the hook a retransmission protocol would use.
+
+use std::collections::VecDeque;
+
+/// One played transmission
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxRecord {
+    pub seq: u64,
+
+    /// First sample at the DAC (session seconds)
+    pub start_s: f64,
+
+    pub duration_s: f64,
+}
+
+/// Transmission matched to a reception
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkMatch {
+    pub seq: u64,
+
+    /// Detected start minus played start (seconds)
+    pub latency_s: f64,
+}
+
+/// Pairs played transmissions with detected receptions
+#[derive(Clone, Debug)]
+pub struct TimestampCorrelator {
+    /// Largest plausible |detected - played| (seconds)
+    pub max_latency: f64,
+
+    /// Played, not yet matched or expired
+    pending: VecDeque<TxRecord>,
+
+    latencies: Vec<f64>,
+}
+
+impl TimestampCorrelator {
+    pub fn new(max_latency: f64) -> Self {
+        assert!(max_latency > 0.0);
+        Self { max_latency, pending: VecDeque::new(), latencies: Vec::new() }
+    }
+
+    pub fn record_tx(&mut self, seq: u64, start_s: f64, duration_s: f64) {
+        self.pending.push_back(TxRecord { seq, start_s, duration_s });
+    }
+
+    /// Match a preamble detected at `detected_s` to the closest pending transmission
+    pub fn match_rx(&mut self, detected_s: f64) -> Option<LinkMatch> {
+        let (idx, latency_s) = self.pending.iter()
+            .enumerate()
+            .map(|(i, tx)| (i, detected_s - tx.start_s))
+            .filter(|(_, latency)| latency.abs() <= self.max_latency)
+            .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?;
+
+        let tx = self.pending.remove(idx)?;
+        self.latencies.push(latency_s);
+        Some(LinkMatch { seq: tx.seq, latency_s })
+    }
+
+    /// Drop transmissions that can no longer be matched at `now_s`
+    ///
+    /// Returns their sequence numbers (frames lost on the link).
+    pub fn expire(&mut self, now_s: f64) -> Vec<u64> {
+        let mut lost = Vec::new();
+        self.pending.retain(|tx| {
+            let alive = now_s <= tx.start_s + tx.duration_s + self.max_latency;
+            if !alive {
+                lost.push(tx.seq);
+            }
+            alive
+        });
+        lost
+    }
+
+    /// Played transmissions awaiting a match
+    pub fn pending(&self) -> impl Iterator<Item = &TxRecord> {
+        self.pending.iter()
+    }
+
+    /// Mean and standard deviation of the matched latencies (seconds)
+    pub fn latency_stats(&self) -> Option<(f64, f64)> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let n = self.latencies.len() as f64;
+        let mean = self.latencies.iter().sum::<f64>() / n;
+        let var = self.latencies.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / n;
+        Some((mean, var.sqrt()))
+    }
+}
+
+/// Linear-interpolation resampler between the modem rate and a device rate
+///
+/// Downsampling first averages over one output period (boxcar), which is
+/// enough anti-aliasing for a band that ends well below the new Nyquist.
+pub fn resample_linear(samples: &[f32], from_rate: f64, to_rate: f64) -> Vec<f32> {
+    if samples.is_empty() || from_rate == to_rate {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate / to_rate;
+    let smoothed: Vec<f32> = if ratio > 1.0 {
+        let width = ratio.round() as usize;
+        let mut acc = 0.0f32;
+        samples.iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                acc += s;
+                if i >= width {
+                    acc -= samples[i - width];
+                }
+                acc / (i + 1).min(width) as f32
+            })
+            .collect()
+    } else {
+        samples.to_vec()
+    };
+
+    // Boxcar output is delayed by (width - 1) / 2 input samples
+    let delay = if ratio > 1.0 { (ratio.round() - 1.0) / 2.0 } else { 0.0 };
+    let out_len = ((samples.len() as f64) / ratio).floor() as usize;
+
+    (0..out_len)
+        .map(|n| {
+            let t = (n as f64 * ratio + delay).min((smoothed.len() - 1) as f64);
+            let i = t.floor() as usize;
+            let frac = (t - i as f64) as f32;
+            let next = smoothed[(i + 1).min(smoothed.len() - 1)];
+            smoothed[i] * (1.0 - frac) + next * frac
+        })
+        .collect()
+}
+
+/// `resample_linear` for a stream arriving in chunks
+///
+/// Resampling each chunk on its own restarts the interpolation grid and the
+/// boxcar at every chunk, and rounds away the fraction of an output sample
+/// a chunk ends on. Capture chunks are a few hundred samples, so at 44.1 kHz
+/// that drift walks the symbol timing off within one frame. The resampler
+/// keeps the boxcar state, the input the next output still interpolates
+/// from and the fractional grid position, so chunked output matches one
+/// `resample_linear` over the whole stream (minus the outputs still waiting
+/// for input).
+#[derive(Clone, Debug)]
+pub struct StreamingResampler {
+    /// Input samples per output sample
+    ratio: f64,
+
+    /// Boxcar width (1 = no smoothing)
+    width: usize,
+    window: VecDeque<f32>,
+    sum: f32,
+
+    /// Smoothed input from the sample before the next output on
+    smoothed: Vec<f32>,
+
+    /// Position of the next output in `smoothed`
+    position: f64,
+}
+
+impl StreamingResampler {
+    pub fn new(from_rate: f64, to_rate: f64) -> Self {
+        let ratio = from_rate / to_rate;
+        let width = if ratio > 1.0 { ratio.round() as usize } else { 1 };
+        Self {
+            ratio,
+            width,
+            window: VecDeque::with_capacity(width),
+            sum: 0.0,
+            smoothed: Vec::new(),
+            // Boxcar output is delayed by (width - 1) / 2 input samples
+            position: (width - 1) as f64 / 2.0,
+        }
+    }
+
+    /// Resample the next chunk; returns the outputs it completes
+    pub fn push(&mut self, chunk: &[f32]) -> Vec<f32> {
+        for &sample in chunk {
+            if self.window.len() == self.width {
+                self.sum -= self.window.pop_front().unwrap_or(0.0);
+            }
+            self.window.push_back(sample);
+            self.sum += sample;
+            self.smoothed.push(self.sum / self.window.len() as f32);
+        }
+
+        let mut out = Vec::new();
+        while self.position + 1.0 < self.smoothed.len() as f64 {
+            let i = self.position.floor() as usize;
+            let frac = (self.position - i as f64) as f32;
+            out.push(self.smoothed[i] * (1.0 - frac) + self.smoothed[i + 1] * frac);
+            self.position += self.ratio;
+        }
+
+        // Keep the input from the next output's left neighbour on
+        let consumed = (self.position.floor() as usize).min(self.smoothed.len());
+        self.smoothed.drain(..consumed);
+        self.position -= consumed as f64;
+        out
+    }
+}
+
+#[cfg(feature = "audio")]
+pub use session::DuplexSession;
+
+#[cfg(feature = "audio")]
+mod session {
+    use super::*;
+    use crate::audio::{AudioError, AudioSettings, Direction, open_device};
+    use crate::input_health::{HealthAlert, InputHealthConfig, InputHealthMonitor};
+    use crate::wavelet::FS;
+    use cpal::traits::{DeviceTrait, StreamTrait};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    fn backend<E: std::fmt::Display>(e: E) -> AudioError {
+        AudioError::Backend(e.to_string())
+    }
+
+    /// Samples waiting for the DAC, with transmission start markers
+    struct Playback {
+        queue: VecDeque<f32>,
+
+        /// (seq, stream sample index of the first sample, duration)
+        markers: VecDeque<(u64, u64, f64)>,
+
+        /// Samples handed to the device so far
+        played: u64,
+    }
+
+    /// Captured samples since the last `take_capture`
+    struct Capture {
+        /// Time of the first captured sample (session seconds)
+        start_s: Option<f64>,
+
+        samples: Vec<f32>,
+
+        /// Device rate to `FS`, continuous across `take_capture` calls
+        resampler: StreamingResampler,
+
+        /// Samples at `FS` returned so far
+        returned: u64,
+
+        health: InputHealthMonitor,
+        alerts: Vec<HealthAlert>,
+    }
+
+    /// f32 stream config of `device` at `rate`, fewest channels
+    fn stream_config(device: &cpal::Device, direction: Direction, rate: u32) -> Result<cpal::StreamConfig, AudioError> {
+        let ranges: Vec<cpal::SupportedStreamConfigRange> = match direction {
+            Direction::Input => device.supported_input_configs().map_err(backend)?.collect(),
+            Direction::Output => device.supported_output_configs().map_err(backend)?.collect(),
+        };
+
+        ranges.into_iter()
+            .filter(|r| r.sample_format() == cpal::SampleFormat::F32)
+            .filter(|r| (r.min_sample_rate().0..=r.max_sample_rate().0).contains(&rate))
+            .min_by_key(|r| r.channels())
+            .map(|r| r.with_sample_rate(cpal::SampleRate(rate)).config())
+            .ok_or_else(|| AudioError::Backend(format!("no f32 {:?} stream at {} Hz", direction, rate)))
+    }
+
+    /// Playback and capture running at once on the configured devices
+    ///
+    /// Transmissions are queued at the modem rate (`FS`) and resampled to
+    /// `settings.sample_rate`; capture is returned at `FS`. Only the first
+    /// channel is captured, playback duplicates to every channel.
+    pub struct DuplexSession {
+        origin: Instant,
+        rate: u32,
+        playback: Arc<Mutex<Playback>>,
+        capture: Arc<Mutex<Capture>>,
+        correlator: Arc<Mutex<TimestampCorrelator>>,
+        next_seq: u64,
+        _output: cpal::Stream,
+        _input: cpal::Stream,
+    }
+
+    impl DuplexSession {
+        /// Open both devices and start the streams
+        pub fn start(settings: &AudioSettings, max_latency: f64) -> Result<Self, AudioError> {
+            let rate = settings.sample_rate;
+            let origin = Instant::now();
+            let playback = Arc::new(Mutex::new(Playback { queue: VecDeque::new(), markers: VecDeque::new(), played: 0 }));
+            let capture = Arc::new(Mutex::new(Capture {
+                start_s: None,
+                samples: Vec::new(),
+                resampler: StreamingResampler::new(rate as f64, FS),
+                returned: 0,
+                health: InputHealthMonitor::new(InputHealthConfig { nominal_rate: rate as f64, ..Default::default() }),
+                alerts: Vec::new(),
+            }));
+            let correlator = Arc::new(Mutex::new(TimestampCorrelator::new(max_latency)));
+
+            let output_device = open_device(settings, Direction::Output)?;
+            let output_config = stream_config(&output_device, Direction::Output, rate)?;
+            let out_channels = output_config.channels as usize;
+            let (pb, corr) = (playback.clone(), correlator.clone());
+
+            let output = output_device.build_output_stream(
+                &output_config,
+                move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+                    let ts = info.timestamp();
+                    let latency = ts.playback.duration_since(&ts.callback).map_or(0.0, |d| d.as_secs_f64());
+                    let now = origin.elapsed().as_secs_f64();
+                    let frames = (data.len() / out_channels) as u64;
+
+                    let mut pb = pb.lock().unwrap();
+                    while let Some(&(seq, first, duration)) = pb.markers.front() {
+                        if first >= pb.played + frames {
+                            break;
+                        }
+                        let start_s = now + latency + (first - pb.played) as f64 / rate as f64;
+                        corr.lock().unwrap().record_tx(seq, start_s, duration);
+                        pb.markers.pop_front();
+                    }
+                    for frame in data.chunks_mut(out_channels) {
+                        frame.fill(pb.queue.pop_front().unwrap_or(0.0));
+                    }
+                    pb.played += frames;
+                },
+                |e| eprintln!("Output stream error: {}", e),
+                None,
+            ).map_err(backend)?;
+
+            let input_device = open_device(settings, Direction::Input)?;
+            let input_config = stream_config(&input_device, Direction::Input, rate)?;
+            let in_channels = input_config.channels as usize;
+            let cap = capture.clone();
+
+            let input = input_device.build_input_stream(
+                &input_config,
+                move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                    let ts = info.timestamp();
+                    let delay = ts.callback.duration_since(&ts.capture).map_or(0.0, |d| d.as_secs_f64());
+                    let first_s = origin.elapsed().as_secs_f64() - delay;
+                    let mono: Vec<f32> = data.iter().step_by(in_channels).copied().collect();
+
+                    let mut cap = cap.lock().unwrap();
+                    let alerts = cap.health.push_block(&mono, first_s);
+                    cap.alerts.extend(alerts);
+                    if cap.start_s.is_none() {
+                        cap.start_s = Some(first_s);
+                    }
+                    cap.samples.extend(mono);
+                },
+                |e| eprintln!("Input stream error: {}", e),
+                None,
+            ).map_err(backend)?;
+
+            output.play().map_err(backend)?;
+            input.play().map_err(backend)?;
+
+            Ok(Self { origin, rate, playback, capture, correlator, next_seq: 0, _output: output, _input: input })
+        }
+
+        /// Seconds since the session started
+        pub fn now(&self) -> f64 {
+            self.origin.elapsed().as_secs_f64()
+        }
+
+        /// Device sample rate (Hz)
+        pub fn rate(&self) -> u32 {
+            self.rate
+        }
+
+        /// Queue a transmission (samples at `FS`); returns its sequence number
+        pub fn transmit(&mut self, signal: &[f32]) -> u64 {
+            let seq = self.next_seq;
+            self.next_seq += 1;
+
+            let samples = resample_linear(signal, FS, self.rate as f64);
+            let duration = samples.len() as f64 / self.rate as f64;
+
+            let mut pb = self.playback.lock().unwrap();
+            let first = pb.played + pb.queue.len() as u64;
+            pb.markers.push_back((seq, first, duration));
+            pb.queue.extend(samples);
+            seq
+        }
+
+        /// Seconds of transmit audio not yet played
+        pub fn queued_seconds(&self) -> f64 {
+            self.playback.lock().unwrap().queue.len() as f64 / self.rate as f64
+        }
+
+        /// Drain the capture: (time of the first sample, samples at `FS`)
+        ///
+        /// Consecutive calls return a continuous stream: a poll loop can
+        /// hand every chunk straight to a `StreamingDemodulator`.
+        pub fn take_capture(&self) -> (f64, Vec<f32>) {
+            let mut cap = self.capture.lock().unwrap();
+            let origin = *cap.start_s.get_or_insert_with(|| self.now());
+            let start_s = origin + cap.returned as f64 / FS;
+            let samples = std::mem::take(&mut cap.samples);
+            let resampled = cap.resampler.push(&samples);
+            cap.returned += resampled.len() as u64;
+
+            (start_s, resampled)
+        }
+
+        /// Capture faults seen since the last call
+        pub fn take_health_alerts(&self) -> Vec<HealthAlert> {
+            std::mem::take(&mut self.capture.lock().unwrap().alerts)
+        }
+
+        /// Match a preamble detected at `detected_s` to its transmission
+        pub fn match_rx(&self, detected_s: f64) -> Option<LinkMatch> {
+            self.correlator.lock().unwrap().match_rx(detected_s)
+        }
+
+        /// Transmissions that went unheard (see `TimestampCorrelator::expire`)
+        pub fn expire(&self) -> Vec<u64> {
+            let now = self.now();
+            self.correlator.lock().unwrap().expire(now)
+        }
+
+        /// Mean and standard deviation of the path latency (seconds)
+        pub fn latency_stats(&self) -> Option<(f64, f64)> {
+            self.correlator.lock().unwrap().latency_stats()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlator_matches_and_expires() {
+        let mut corr = TimestampCorrelator::new(0.5);
+        corr.record_tx(0, 1.0, 10.0);
+        corr.record_tx(1, 15.0, 10.0);
+        corr.record_tx(2, 30.0, 10.0);
+
+        // Out of order and with path latency
+        assert_eq!(corr.match_rx(15.12).map(|m| m.seq), Some(1));
+        assert_eq!(corr.match_rx(1.08).map(|m| m.seq), Some(0));
+        assert_eq!(corr.match_rx(22.0), None);
+
+        let (mean, std) = corr.latency_stats().unwrap();
+        assert!((mean - 0.10).abs() < 1e-9 && (std - 0.02).abs() < 1e-9);
+
+        assert!(corr.expire(40.0).is_empty());
+        assert_eq!(corr.expire(40.6), vec![2]);
+        assert_eq!(corr.pending().count(), 0);
+    }
+
+    #[test]
+    fn test_streaming_resampler_matches_whole_stream() {
+        let tone: Vec<f32> = (0..44100)
+            .map(|i| (2.0 * std::f32::consts::PI * 700.0 * i as f32 / 44100.0).sin())
+            .collect();
+
+        for (from, to) in [(44100.0, 8000.0), (48000.0, 8000.0), (8000.0, 44100.0)] {
+            let whole = resample_linear(&tone, from, to);
+            let mut resampler = StreamingResampler::new(from, to);
+            let chunked: Vec<f32> = tone.chunks(441).flat_map(|chunk| resampler.push(chunk)).collect();
+
+            // All but the outputs past the last input, which wait for more
+            let waiting = (to / from).ceil() as usize + 1;
+            assert!(chunked.len() <= whole.len() && chunked.len() + waiting >= whole.len(), "{} -> {}", from, to);
+            for (a, b) in chunked.iter().zip(&whole) {
+                assert!((a - b).abs() < 1e-4, "{} -> {}", from, to);
+            }
+        }
+    }
+
+    #[test]
+    fn test_resample_round_trip() {
+        let tone: Vec<f32> = (0..8000)
+            .map(|i| (2.0 * std::f32::consts::PI * 700.0 * i as f32 / 8000.0).sin())
+            .collect();
+
+        let up = resample_linear(&tone, 8000.0, 48000.0);
+        assert_eq!(up.len(), 48000);
+        let down = resample_linear(&up, 48000.0, 8000.0);
+        assert_eq!(down.len(), 8000);
+
+        // Linear interpolation and boxcar droop leave the error near -29 dB
+        let err = tone.iter().zip(&down)
+            .skip(10)
+            .map(|(a, b)| (a - b).powi(2))
+            .sum::<f32>() / tone.iter().map(|a| a * a).sum::<f32>();
+        assert!(err < 3e-3, "relative error {}", err);
+    }
+}
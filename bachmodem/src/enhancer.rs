@@ -0,0 +1,194 @@
+/// Learned Signal Enhancement
+///
+/// Hook for a trained model that cleans up the received slot tensor before
+/// preamble sync and matched filtering. `demodulate_fhdpsk_soft_enhanced_with_config`
+/// runs the enhancer first, then the regular soft demodulator.
+///
+/// `ConvDenoiser` is the reference model: a small stack of 1-D convolutions
+/// that estimates the noise and subtracts it (residual learning). Its last
+/// layer starts at zero, so an untrained denoiser passes the signal through
+/// unchanged. Input is scaled to unit RMS before the network, which makes it
+/// independent of the soundcard level.
+///
+/// Training pairs come from `denoiser_batch` (feature `channel-sim`): the
+/// Watterson-faded transmission is the target, the same window with AWGN
+/// added is the input. The denoiser learns to remove noise, not to undo the
+/// fading. See `--example train_denoiser`.
+
+use burn::config::Config;
+use burn::module::{Initializer, Module};
+use burn::nn::{PaddingConfig1d, Relu, conv::{Conv1d, Conv1dConfig}};
+use burn::tensor::{Tensor, backend::Backend};
+
+#[cfg(feature = "channel-sim")]
+use crate::{modem_rng::{gaussian_noise, ModemRng}, transmitter::BachTransmitter, watterson::WattersonChannel};
+
+/// Receive-side signal enhancement before matched filtering
+pub trait SignalEnhancer<B: Backend> {
+    /// Enhanced signal, same length as the input
+    fn enhance(&self, signal: Tensor<B, 1>) -> Tensor<B, 1>;
+}
+
+/// Closures work as enhancers (e.g. a fixed band-pass filter)
+impl<B: Backend, F> SignalEnhancer<B> for F
+where
+    F: Fn(Tensor<B, 1>) -> Tensor<B, 1>,
+{
+    fn enhance(&self, signal: Tensor<B, 1>) -> Tensor<B, 1> {
+        self(signal)
+    }
+}
+
+/// Pass-through enhancer (the plain receive chain)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoEnhancer;
+
+impl<B: Backend> SignalEnhancer<B> for NoEnhancer {
+    fn enhance(&self, signal: Tensor<B, 1>) -> Tensor<B, 1> {
+        signal
+    }
+}
+
+/// Residual 1-D convolutional denoiser configuration
+#[derive(Config, Debug)]
+pub struct ConvDenoiserConfig {
+    /// Feature channels of the hidden layers
+    #[config(default = 16)]
+    pub channels: usize,
+
+    /// Kernel length in samples (odd, keeps the signal length)
+    #[config(default = 31)]
+    pub kernel_size: usize,
+
+    /// Number of convolution layers (at least 2)
+    #[config(default = 4)]
+    pub num_layers: usize,
+}
+
+impl ConvDenoiserConfig {
+    pub fn init<B: Backend>(&self, device: &B::Device) -> ConvDenoiser<B> {
+        assert!(self.kernel_size % 2 == 1, "kernel_size must be odd");
+        assert!(self.num_layers >= 2, "need at least input and output layers");
+
+        let layers = (0..self.num_layers)
+            .map(|i| {
+                let channels_in = if i == 0 { 1 } else { self.channels };
+                let last = i + 1 == self.num_layers;
+                let channels_out = if last { 1 } else { self.channels };
+
+                let config = Conv1dConfig::new(channels_in, channels_out, self.kernel_size)
+                    .with_padding(PaddingConfig1d::Same);
+                // Zero output layer: untrained model is the identity
+                let config = if last { config.with_initializer(Initializer::Zeros) } else { config };
+                config.init(device)
+            })
+            .collect();
+
+        ConvDenoiser { layers, activation: Relu::new() }
+    }
+}
+
+/// Residual 1-D convolutional denoiser
+#[derive(Module, Debug)]
+pub struct ConvDenoiser<B: Backend> {
+    layers: Vec<Conv1d<B>>,
+    activation: Relu,
+}
+
+impl<B: Backend> ConvDenoiser<B> {
+    /// Denoise a batch of RMS-normalized windows
+    ///
+    /// Input/returns: [batch, 1, samples]
+    pub fn forward(&self, input: Tensor<B, 3>) -> Tensor<B, 3> {
+        let last = self.layers.len() - 1;
+        let mut x = input.clone();
+        for (i, layer) in self.layers.iter().enumerate() {
+            x = layer.forward(x);
+            if i < last {
+                x = self.activation.forward(x);
+            }
+        }
+
+        // x is the noise estimate
+        input - x
+    }
+}
+
+impl<B: Backend> SignalEnhancer<B> for ConvDenoiser<B> {
+    fn enhance(&self, signal: Tensor<B, 1>) -> Tensor<B, 1> {
+        let len = signal.dims()[0];
+        let rms = signal.clone().powf_scalar(2.0).mean().sqrt().add_scalar(1e-12);
+
+        let normalized = (signal / rms.clone()).reshape([1, 1, len]);
+        self.forward(normalized).reshape([len]) * rms
+    }
+}
+
+/// Paired training windows from the channel simulator
+///
+/// Builds one random-payload transmission, passes it through `channel` and
+/// cuts `batch` random windows of `window` samples. Payload, fading, window
+/// positions and noise all come from `rng`. The noisy input has AWGN
+/// at `snr_db` (relative to the faded signal power); both tensors are scaled
+/// by the RMS of the noisy window, as `ConvDenoiser::enhance` does.
+///
+/// Returns: (noisy, clean) [batch, 1, window]
+#[cfg(feature = "channel-sim")]
+pub fn denoiser_batch<B: Backend, R: ModemRng + ?Sized>(
+    device: &B::Device,
+    tx: &BachTransmitter,
+    channel: &WattersonChannel,
+    snr_db: f32,
+    batch: usize,
+    window: usize,
+    rng: &mut R,
+) -> (Tensor<B, 3>, Tensor<B, 3>) {
+    use burn::tensor::ElementConversion;
+
+    let payload: Vec<u8> = (0..tx.max_payload()).map(|_| b' ' + rng.below(95) as u8).collect();
+
+    let signal = tx.build::<B>(device, &payload).expect("payload fits the frame");
+    let faded = channel.apply_with_rng::<B, R>(device, &signal, rng);
+    let len = faded.dims()[0];
+    assert!(len > window, "window longer than the transmission");
+
+    let signal_power: f32 = faded.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+
+    let clean: Vec<Tensor<B, 1>> = (0..batch)
+        .map(|_| {
+            let start = rng.below(len - window);
+            faded.clone().slice([start..start + window])
+        })
+        .collect();
+    let clean = Tensor::stack::<2>(clean, 0);
+    let noisy = clean.clone() + gaussian_noise::<B, R>(device, batch * window, noise_std, rng).reshape([batch, window]);
+
+    let rms = noisy.clone().powf_scalar(2.0).mean_dim(1).sqrt().add_scalar(1e-12);
+    let noisy = noisy / rms.clone();
+    let clean = clean / rms;
+
+    (noisy.reshape([batch, 1, window]), clean.reshape([batch, 1, window]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_untrained_denoiser_is_identity() {
+        let device = Default::default();
+        let denoiser = ConvDenoiserConfig::new().init::<TestBackend>(&device);
+
+        let signal = gaussian_noise::<TestBackend, _>(&device, 4000, 3.0, &mut SplitMix64::new(37));
+        let enhanced = denoiser.enhance(signal.clone());
+        assert_eq!(enhanced.dims(), signal.dims());
+
+        let max_err: f32 = (enhanced - signal).abs().max().into_scalar();
+        assert!(max_err < 1e-4, "max error {}", max_err);
+    }
+}
@@ -0,0 +1,246 @@
+/// Reference Waveform Export
+///
+/// Dumps the constants a hardware receiver needs, generated by the same code
+/// the Rust modem runs, so FPGA/NPU implementations can be checked bit for
+/// bit against the reference instead of re-deriving waveforms:
+/// - `wavelets_real`, `wavelets_imag` [tones, samples]: Morlet bank (unit gain)
+/// - `tone_gains`, `frequencies` [tones]: pre-emphasis and carrier frequencies
+/// - `hopping_pattern` [tones] (i32): tone index per symbol slot
+/// - `preamble`, `flourish`, `postamble` [samples]: sync waveforms
+/// - `interleave_permutation`, `deinterleave_permutation` [CODE_N] (i32):
+///   output position i takes input element perm[i]
+///
+/// Formats:
+/// - `.npy` files in a directory (always available, no dependencies)
+/// - one `.safetensors` file with the modem parameters as metadata
+///   (feature `export`)
+
+use burn::tensor::{Tensor, backend::Backend};
+use std::io::Write;
+use std::path::Path;
+use crate::config::ModemConfig;
+use crate::interleaver::{interleave, deinterleave};
+use crate::transmitter::CODE_N;
+use crate::wavelet::{
+    FS, shaped_wavelet,
+    generate_bach_preamble_with_config, generate_bach_flourish_with_config, generate_bach_postamble_with_config,
+};
+
+/// Element data of an exported array
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReferenceData {
+    F32(Vec<f32>),
+    I32(Vec<i32>),
+    U8(Vec<u8>),
+}
+
+/// One named array of the reference set
+#[derive(Clone, Debug)]
+pub struct ReferenceArray {
+    pub name: String,
+    pub shape: Vec<usize>,
+    pub data: ReferenceData,
+}
+
+impl ReferenceArray {
+    pub(crate) fn f32(name: &str, shape: Vec<usize>, data: Vec<f32>) -> Self {
+        debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+        Self { name: name.to_string(), shape, data: ReferenceData::F32(data) }
+    }
+
+    pub(crate) fn i32(name: &str, shape: Vec<usize>, data: Vec<i32>) -> Self {
+        debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+        Self { name: name.to_string(), shape, data: ReferenceData::I32(data) }
+    }
+
+    pub(crate) fn u8(name: &str, shape: Vec<usize>, data: Vec<u8>) -> Self {
+        debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+        Self { name: name.to_string(), shape, data: ReferenceData::U8(data) }
+    }
+
+    /// Little-endian element bytes
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match &self.data {
+            ReferenceData::F32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ReferenceData::I32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            ReferenceData::U8(v) => v.clone(),
+        }
+    }
+
+    /// NumPy `.npy` (format 1.0) encoding
+    pub fn to_npy(&self) -> Vec<u8> {
+        let descr = match self.data {
+            ReferenceData::F32(_) => "<f4",
+            ReferenceData::I32(_) => "<i4",
+            ReferenceData::U8(_) => "|u1",
+        };
+        let shape = match self.shape.as_slice() {
+            [n] => format!("({},)", n),
+            dims => format!("({})", dims.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+
+        // Magic (6) + version (2) + length (2) + header, padded to 64 bytes, ending in '\n'
+        let total = (10 + header.len() + 1).div_ceil(64) * 64;
+        header.push_str(&" ".repeat(total - 10 - header.len() - 1));
+        header.push('\n');
+
+        let mut out = b"\x93NUMPY\x01\x00".to_vec();
+        out.extend((header.len() as u16).to_le_bytes());
+        out.extend(header.as_bytes());
+        out.extend(self.to_le_bytes());
+        out
+    }
+}
+
+/// Reference constants of one modem configuration
+#[derive(Clone, Debug)]
+pub struct ReferenceSet {
+    pub arrays: Vec<ReferenceArray>,
+
+    /// Scalar parameters (sample rate, symbol duration, ...) as strings
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ReferenceSet {
+    /// Generate every reference array for `config`
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads the generated waveforms
+    pub fn from_config<B: Backend>(device: &B::Device, config: &ModemConfig) -> Self {
+        let num_tones = config.num_tones;
+        let freqs = config.frequencies();
+        let symbol_len = config.symbol_samples();
+
+        let (mut bank_real, mut bank_imag) = (Vec::new(), Vec::new());
+        for &freq in &freqs {
+            let (real, imag) = shaped_wavelet::<B>(device, freq, config.symbol_duration, config.wavelet_shape(), FS);
+            bank_real.extend(download(real));
+            bank_imag.extend(download(imag));
+        }
+
+        let waveform = |name: &str, signal: Tensor<B, 1>| {
+            let samples = download(signal);
+            ReferenceArray::f32(name, vec![samples.len()], samples)
+        };
+
+        let positions: Vec<i32> = (0..CODE_N as i32).collect();
+        let columns = config.interleaver_columns();
+
+        let arrays = vec![
+            ReferenceArray::f32("wavelets_real", vec![num_tones, symbol_len], bank_real),
+            ReferenceArray::f32("wavelets_imag", vec![num_tones, symbol_len], bank_imag),
+            ReferenceArray::f32("tone_gains", vec![num_tones], (0..num_tones).map(|i| config.tone_gain(i) as f32).collect()),
+            ReferenceArray::f32("frequencies", vec![num_tones], freqs.iter().map(|&f| f as f32).collect()),
+            ReferenceArray::i32("hopping_pattern", vec![num_tones], config.hopping_pattern().iter().map(|&t| t as i32).collect()),
+            waveform("preamble", generate_bach_preamble_with_config::<B>(device, config)),
+            waveform("flourish", generate_bach_flourish_with_config::<B>(device, config)),
+            waveform("postamble", generate_bach_postamble_with_config::<B>(device, config)),
+            ReferenceArray::i32("interleave_permutation", vec![CODE_N], interleave(&positions, columns)),
+            ReferenceArray::i32("deinterleave_permutation", vec![CODE_N], deinterleave(&positions, columns)),
+        ];
+
+        let metadata = vec![
+            ("sample_rate".to_string(), FS.to_string()),
+            ("num_tones".to_string(), num_tones.to_string()),
+            ("symbol_duration".to_string(), config.symbol_duration.to_string()),
+            ("wavelet_sigmas".to_string(), config.wavelet_sigmas.to_string()),
+            ("chirp_span_hz".to_string(), config.chirp_span_hz.to_string()),
+            ("preamble_phase_code".to_string(), config.preamble_phase_code.to_string()),
+            ("symbol_samples".to_string(), symbol_len.to_string()),
+            ("differential_lag".to_string(), config.lag().to_string()),
+            ("interleaver_columns".to_string(), columns.to_string()),
+        ];
+
+        Self { arrays, metadata }
+    }
+
+    /// Look up an array by name
+    pub fn get(&self, name: &str) -> Option<&ReferenceArray> {
+        self.arrays.iter().find(|a| a.name == name)
+    }
+
+    /// Write one `<name>.npy` per array plus `metadata.txt` into `dir`
+    pub fn write_npy_dir<P: AsRef<Path>>(&self, dir: P) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        for array in &self.arrays {
+            std::fs::write(dir.join(format!("{}.npy", array.name)), array.to_npy())?;
+        }
+
+        let mut meta = std::fs::File::create(dir.join("metadata.txt"))?;
+        for (key, value) in &self.metadata {
+            writeln!(meta, "{} = {}", key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Write all arrays into one `.safetensors` file
+    #[cfg(feature = "export")]
+    pub fn write_safetensors<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        use safetensors::tensor::{Dtype, TensorView};
+
+        let bytes: Vec<Vec<u8>> = self.arrays.iter().map(|a| a.to_le_bytes()).collect();
+        let views = self.arrays.iter()
+            .zip(&bytes)
+            .map(|(array, data)| {
+                let dtype = match array.data {
+                    ReferenceData::F32(_) => Dtype::F32,
+                    ReferenceData::I32(_) => Dtype::I32,
+                    ReferenceData::U8(_) => Dtype::U8,
+                };
+                Ok((array.name.clone(), TensorView::new(dtype, array.shape.clone(), data)?))
+            })
+            .collect::<Result<Vec<_>, safetensors::SafeTensorError>>()?;
+
+        let metadata = self.metadata.iter().cloned().collect();
+        safetensors::serialize_to_file(views, Some(metadata), path.as_ref())?;
+        Ok(())
+    }
+}
+
+fn download<B: Backend>(signal: Tensor<B, 1>) -> Vec<f32> {
+    signal.into_data().to_vec::<f32>().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_npy_header() {
+        let array = ReferenceArray::i32("perm", vec![2, 3], vec![0, 1, 2, 3, 4, 5]);
+        let npy = array.to_npy();
+
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+
+        let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<i4', 'fortran_order': False, 'shape': (2, 3), }"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(npy.len(), 10 + header_len + 24);
+    }
+
+    #[test]
+    fn test_reference_set_matches_modem() {
+        let device = Default::default();
+        let config = ModemConfig::narrowband();
+        let set = ReferenceSet::from_config::<TestBackend>(&device, &config);
+
+        let bank = set.get("wavelets_real").unwrap();
+        assert_eq!(bank.shape, vec![8, config.symbol_samples()]);
+
+        // Permutations invert each other
+        let (Some(ReferenceArray { data: ReferenceData::I32(fwd), .. }),
+             Some(ReferenceArray { data: ReferenceData::I32(inv), .. })) =
+            (set.get("interleave_permutation"), set.get("deinterleave_permutation")) else {
+            panic!("Missing permutations");
+        };
+        let round_trip: Vec<i32> = inv.iter().map(|&i| fwd[i as usize]).collect();
+        assert_eq!(round_trip, (0..CODE_N as i32).collect::<Vec<_>>());
+    }
+}
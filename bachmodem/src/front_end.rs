@@ -0,0 +1,288 @@
+/// Receiver Front End
+///
+/// Station-specific conditioning applied to every capture before the noise
+/// floor, sync and demodulation see it: notches for the carriers and hum
+/// lines a station always has (found by `noise_calibration`), and a comb on
+/// the power-line frequency and its harmonics for ground-loop hum.
+///
+/// Notches are applied on the whole capture in the frequency domain: one
+/// zero-padded FFT, a gain that dips to zero at each notch frequency (and
+/// its mirror), one inverse FFT. The dip is `1 - exp(-ln2·(Δf / (w/2))²)`,
+/// half depth at ±w/2, so neighbouring tones a few widths away are untouched.
+/// The comb is the same dip at `k × base` for k = 1..=harmonics. Stations
+/// that key a CW ID (`cw_id`) set `cw_id_hz` to have the ID tone removed with
+/// `notch_cw_id_gpu` after the notches.
+///
+/// The settings live in the receiver profile next to the `ReceiverTuning`
+/// keys (unknown keys are ignored by both parsers):
+///
+/// ```text
+/// notches_hz = 1379.5
+/// notch_width_hz = 4
+/// hum_base_hz = 50
+/// hum_harmonics = 20
+/// hum_width_hz = 2
+/// cw_id_hz = 2000
+/// ```
+///
+/// `front_end_report` (in `receiver_pool`) measures what the front end buys
+/// on a capture: the blind SNR demodulated without and with it.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::complex::ComplexTensor;
+use crate::cw_id::notch_cw_id_gpu;
+use crate::fft_correlation::FftBackend;
+use crate::tuning::{parse_entries, ProfileError};
+use crate::wavelet::FS;
+
+/// Default notch width (Hz)
+pub const DEFAULT_NOTCH_WIDTH_HZ: f64 = 4.0;
+
+/// Default number of notched hum harmonics (the fundamental included)
+pub const DEFAULT_HUM_HARMONICS: usize = 20;
+
+/// Default width of the hum notches (Hz)
+pub const DEFAULT_HUM_WIDTH_HZ: f64 = 2.0;
+
+/// Comb of notches on a power-line frequency and its harmonics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HumComb {
+    /// Fundamental (50 or 60 Hz mains)
+    pub base_hz: f64,
+
+    /// Notched harmonics, the fundamental included
+    pub harmonics: usize,
+
+    /// Notch width at half depth (Hz)
+    pub width_hz: f64,
+}
+
+impl HumComb {
+    /// Default comb on `base_hz`
+    pub fn mains(base_hz: f64) -> Self {
+        Self { base_hz, harmonics: DEFAULT_HUM_HARMONICS, width_hz: DEFAULT_HUM_WIDTH_HZ }
+    }
+
+    /// Notched frequencies below Nyquist, fundamental first
+    pub fn frequencies(&self) -> impl Iterator<Item = f64> + '_ {
+        (1..=self.harmonics)
+            .map(move |k| k as f64 * self.base_hz)
+            .take_while(|&f| f < FS / 2.0)
+    }
+}
+
+/// Blind SNR of one capture without and with the front end
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrontEndReport {
+    pub before_db: f32,
+    pub after_db: f32,
+}
+
+impl FrontEndReport {
+    /// SNR gained by the front end (dB)
+    pub fn improvement_db(&self) -> f32 {
+        self.after_db - self.before_db
+    }
+}
+
+/// Conditioning applied to captures ahead of the receive chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrontEnd {
+    /// Notch centre frequencies (Hz)
+    pub notches_hz: Vec<f64>,
+
+    /// Notch width at half depth (Hz)
+    pub notch_width_hz: f64,
+
+    /// Power-line hum comb, off by default
+    pub hum: Option<HumComb>,
+
+    /// Tone of an on-air CW ID to strip, off by default
+    pub cw_id_hz: Option<f64>,
+}
+
+impl Default for FrontEnd {
+    fn default() -> Self {
+        Self { notches_hz: Vec::new(), notch_width_hz: DEFAULT_NOTCH_WIDTH_HZ, hum: None, cw_id_hz: None }
+    }
+}
+
+impl FrontEnd {
+    /// True if captures pass through unchanged
+    pub fn is_bypass(&self) -> bool {
+        self.notches_hz.is_empty() && self.hum.is_none() && self.cw_id_hz.is_none()
+    }
+
+    /// Condition one capture
+    ///
+    /// **NO SYNC POINT**
+    pub fn apply<B: Backend + FftBackend>(&self, device: &B::Device, signal: &Tensor<B, 1>) -> Tensor<B, 1> {
+        if self.is_bypass() {
+            return signal.clone();
+        }
+        let mut notches: Vec<(f64, f64)> = self.notches_hz.iter().map(|&f| (f, self.notch_width_hz)).collect();
+        if let Some(hum) = &self.hum {
+            notches.extend(hum.frequencies().map(|f| (f, hum.width_hz)));
+        }
+        let notched = if notches.is_empty() { signal.clone() } else { apply_notches(device, signal, &notches) };
+        match self.cw_id_hz {
+            Some(tone_hz) => notch_cw_id_gpu(device, &notched, tone_hz),
+            None => notched,
+        }
+    }
+
+    /// Parse the front-end keys of a profile; other keys are ignored
+    pub fn parse(text: &str) -> Result<Self, ProfileError> {
+        let mut front_end = Self::default();
+        let (mut hum_base, mut hum_harmonics, mut hum_width) = (None, DEFAULT_HUM_HARMONICS, DEFAULT_HUM_WIDTH_HZ);
+
+        parse_entries(text, |key, value| {
+            let frequency = |value: &str| value.trim().parse::<f64>().ok()
+                .filter(|v| v.is_finite() && *v > 0.0 && *v < FS / 2.0)
+                .ok_or_else(|| format!("{} must be between 0 and {} Hz, got '{}'", key, FS / 2.0, value.trim()));
+
+            match key {
+                "notches_hz" => {
+                    front_end.notches_hz = value.split(',')
+                        .filter(|v| !v.trim().is_empty())
+                        .map(frequency)
+                        .collect::<Result<_, _>>()?;
+                }
+                "notch_width_hz" => front_end.notch_width_hz = frequency(value)?,
+                "hum_base_hz" => hum_base = Some(frequency(value)?),
+                "hum_harmonics" => {
+                    hum_harmonics = value.parse().ok().filter(|&k| k > 0)
+                        .ok_or_else(|| format!("hum_harmonics must be a positive integer, got '{}'", value))?;
+                }
+                "hum_width_hz" => hum_width = frequency(value)?,
+                "cw_id_hz" => front_end.cw_id_hz = Some(frequency(value)?),
+                _ => {}
+            }
+            Ok(())
+        })?;
+        front_end.hum = hum_base.map(|base_hz| HumComb { base_hz, harmonics: hum_harmonics, width_hz: hum_width });
+        Ok(front_end)
+    }
+
+    /// Profile lines (no header), appended after `ReceiverTuning::to_text`
+    pub fn to_text(&self) -> String {
+        let notches: Vec<String> = self.notches_hz.iter().map(|f| format!("{}", f)).collect();
+        let mut text = format!("notches_hz = {}\nnotch_width_hz = {}\n", notches.join(", "), self.notch_width_hz);
+        if let Some(hum) = &self.hum {
+            text.push_str(&format!("hum_base_hz = {}\nhum_harmonics = {}\nhum_width_hz = {}\n", hum.base_hz, hum.harmonics, hum.width_hz));
+        }
+        if let Some(tone_hz) = self.cw_id_hz {
+            text.push_str(&format!("cw_id_hz = {}\n", tone_hz));
+        }
+        text
+    }
+
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// `signal` with a notch of width `width_hz` (half depth) at each of `notches_hz`
+///
+/// **NO SYNC POINT**
+pub fn notch_filter<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    notches_hz: &[f64],
+    width_hz: f64,
+) -> Tensor<B, 1> {
+    let notches: Vec<(f64, f64)> = notches_hz.iter().map(|&f| (f, width_hz)).collect();
+    apply_notches(device, signal, &notches)
+}
+
+/// `signal` with a notch at each (frequency, half-depth width) in Hz
+///
+/// **NO SYNC POINT**
+fn apply_notches<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    notches: &[(f64, f64)],
+) -> Tensor<B, 1> {
+    let n = signal.dims()[0];
+    let fft_len = n.next_power_of_two();
+    let bin_hz = FS / fft_len as f64;
+
+    // Real gain over both halves of the spectrum
+    let gain: Vec<f32> = (0..fft_len)
+        .map(|k| {
+            let f = k.min(fft_len - k) as f64 * bin_hz;
+            notches.iter()
+                .map(|&(notch, width)| 1.0 - (-std::f64::consts::LN_2 * ((f - notch) / (width / 2.0)).powi(2)).exp())
+                .product::<f64>() as f32
+        })
+        .collect();
+    let gain = Tensor::<B, 1>::from_floats(gain.as_slice(), device).reshape([1, fft_len]);
+
+    let padded = Tensor::cat(vec![signal.clone(), Tensor::zeros([fft_len - n], device)], 0).reshape([1, fft_len]);
+    let (re, _) = ComplexTensor::from_real(padded).fft()
+        .mul_real(gain)
+        .ifft()
+        .into_parts();
+    re.slice([0..1, 0..n]).reshape([n])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+    use burn::tensor::ElementConversion;
+
+    #[test]
+    fn test_notch_removes_hum_keeps_tone() {
+        let device = Default::default();
+        let tone = |f: f64, a: f64| (0..FS as usize)
+            .map(move |t| (a * (2.0 * std::f64::consts::PI * f * t as f64 / FS).sin()) as f32);
+        let hum: Vec<f32> = tone(150.0, 0.5).collect();
+        let wanted: Vec<f32> = tone(261.63, 0.1).collect();
+        let mixed: Vec<f32> = hum.iter().zip(&wanted).map(|(a, b)| a + b).collect();
+
+        let front_end = FrontEnd { notches_hz: vec![150.0], ..FrontEnd::default() };
+        let filtered = front_end.apply(&device, &Tensor::<TestBackend, 1>::from_floats(mixed.as_slice(), &device));
+
+        // Away from the edges, only the tone is left
+        let wanted = Tensor::<TestBackend, 1>::from_floats(wanted.as_slice(), &device);
+        let residual: f32 = (filtered - wanted).slice([1000..7000]).powf_scalar(2.0).mean().sqrt().into_scalar().elem();
+        assert!(residual < 0.005, "residual rms {}", residual);
+
+        // Profile round trip
+        let parsed = FrontEnd::parse(&format!("# station\nrake_fingers = 2\n{}", front_end.to_text())).unwrap();
+        assert_eq!(parsed, front_end);
+        assert!(FrontEnd::parse("notches_hz = 50, 9000").is_err());
+    }
+
+    #[test]
+    fn test_hum_comb_removes_harmonics() {
+        let device = Default::default();
+        // 2 Hz notches ring for about half a second: 4 s, middle second checked
+        let tone = |f: f64, a: f64| (0..4 * FS as usize)
+            .map(move |t| (a * (2.0 * std::f64::consts::PI * f * t as f64 / FS).sin()) as f32);
+
+        // 60 Hz buzz, odd harmonics strongest, under a tone between two of them
+        let mut mixed: Vec<f32> = tone(1530.0, 0.1).collect();
+        let wanted = mixed.clone();
+        for (k, amplitude) in [(1, 0.3), (2, 0.05), (3, 0.2), (5, 0.1), (7, 0.05)] {
+            for (sample, hum) in mixed.iter_mut().zip(tone(60.0 * k as f64, amplitude)) {
+                *sample += hum;
+            }
+        }
+
+        let front_end = FrontEnd { hum: Some(HumComb::mains(60.0)), cw_id_hz: Some(2000.0), ..FrontEnd::default() };
+        assert!(!front_end.is_bypass());
+        let filtered = front_end.apply(&device, &Tensor::<TestBackend, 1>::from_floats(mixed.as_slice(), &device));
+
+        let wanted = Tensor::<TestBackend, 1>::from_floats(wanted.as_slice(), &device);
+        let residual: f32 = (filtered - wanted).slice([12000..20000]).powf_scalar(2.0).mean().sqrt().into_scalar().elem();
+        assert!(residual < 0.01, "residual rms {}", residual);
+
+        // Profile round trip; the comb stops below Nyquist
+        let parsed = FrontEnd::parse(&front_end.to_text()).unwrap();
+        assert_eq!(parsed, front_end);
+        assert_eq!(HumComb { harmonics: 100, ..HumComb::mains(50.0) }.frequencies().count(), 79);
+        assert!(FrontEnd::parse("hum_base_hz = 50\nhum_harmonics = 0").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use burn::tensor::{Tensor, backend::Backend, ElementConversion};
+use burn::tensor::{Tensor, Int, IndexingUpdateOp, backend::Backend, ElementConversion};
 
 /// Compute cross-correlation using GPU-accelerated matrix multiplication
 /// 
@@ -162,6 +162,79 @@ pub fn estimate_snr_from_correlation_gpu<B: Backend>(
     snr_linear.log() * 10.0 / 2.302585 // log10(x) = ln(x) / ln(10)
 }
 
+/// Estimate SNR of every repetition slot at once - GPU-only version
+/// 
+/// correlations: [NumSlots, CorrLen] per-slot correlation outputs
+/// peak_indices: [NumSlots] peak position of each slot
+/// Returns: [NumSlots] SNR in dB
+/// 
+/// Same estimator as `estimate_snr_from_correlation_gpu` (peak power over
+/// mean power outside ±noise_window), evaluated for all slots in one pass
+/// with a position mask instead of per-slot slicing. Slots without any
+/// noise samples report the same 10 dB default.
+/// 
+/// **NO SYNC POINT**: Peak indices may come straight from `argmax`
+pub fn estimate_snr_from_correlation_batch_gpu<B: Backend>(
+    correlations: &Tensor<B, 2>,
+    peak_indices: &Tensor<B, 1, Int>,
+    noise_window: usize,
+) -> Tensor<B, 1> {
+    let [num_slots, corr_len] = correlations.dims();
+    let device = correlations.device();
+    let peaks = peak_indices.clone().reshape([num_slots, 1]);
+    
+    // Signal power: squared peak of each slot -> [NumSlots, 1]
+    let signal_power = correlations.clone().gather(1, peaks.clone()).powf_scalar(2.0);
+    
+    // Noise positions: i < peak - w or i >= peak + w
+    let offsets = Tensor::<B, 1, Int>::arange(0..corr_len as i64, &device)
+        .reshape([1, corr_len])
+        .sub(peaks);
+    let w = noise_window as i64;
+    let noise_mask = offsets.clone().lower_elem(-w).float() + offsets.greater_equal_elem(w).float();
+    
+    let noise_count = noise_mask.clone().sum_dim(1);
+    let noise_sum = (correlations.clone().powf_scalar(2.0) * noise_mask).sum_dim(1);
+    let noise_power = (noise_sum / noise_count.clone().clamp_min(1.0)).clamp_min(1e-10);
+    
+    let snr_db = (signal_power / noise_power).log() * 10.0 / 2.302585;
+    
+    snr_db
+        .mask_fill(noise_count.equal_elem(0.0), 10.0)
+        .reshape([num_slots])
+}
+
+/// Linear MRC weights from per-slot SNR in dB
+/// 
+/// **NO SYNC POINT**: Feed directly into `soft_combine_gpu`
+pub fn mrc_weights_from_snr_db_gpu<B: Backend>(snr_db: Tensor<B, 1>) -> Tensor<B, 1> {
+    // 10^(dB/10) = exp(dB · ln(10) / 10)
+    snr_db.mul_scalar(2.302585 / 10.0).exp()
+}
+
+/// Maximum Ratio Combining weighted by each slot's preamble correlation
+/// 
+/// llrs: [NumSlots, NumBits]
+/// correlations: [NumSlots, CorrLen] preamble correlation of each slot
+/// Returns: [NumBits] combined LLRs
+/// 
+/// Each slot's peak is its |correlation| argmax; the batched SNR estimate
+/// becomes a linear weight, so a faded slot counts for little instead of
+/// as much as a clean one.
+/// 
+/// **NO SYNC POINT**
+pub fn mrc_combine_gpu<B: Backend>(
+    llrs: &Tensor<B, 2>,
+    correlations: &Tensor<B, 2>,
+    noise_window: usize,
+) -> Tensor<B, 1> {
+    let num_slots = correlations.dims()[0];
+    let peak_indices = correlations.clone().abs().argmax(1).reshape([num_slots]);
+    let snr_db = estimate_snr_from_correlation_batch_gpu(correlations, &peak_indices, noise_window);
+    
+    soft_combine_gpu(llrs, &mrc_weights_from_snr_db_gpu(snr_db))
+}
+
 /// Estimate SNR from correlation peaks
 /// ⚠️ **SYNC POINT**: Returns scalar f32, downloads from GPU
 /// 
@@ -174,3 +247,264 @@ pub fn estimate_snr_from_correlation<B: Backend>(
     let snr_tensor = estimate_snr_from_correlation_gpu(correlation, peak_idx, noise_window);
     snr_tensor.into_scalar().elem()
 }
+
+/// Buckets per histogram pass of `percentile_gpu`
+pub const PERCENTILE_BINS: usize = 256;
+
+/// Histogram passes of `percentile_gpu` (resolution: range / bins^passes)
+const PERCENTILE_PASSES: usize = 2;
+
+/// Values bucketed per scatter row; rows run in parallel and are summed
+const HISTOGRAM_ROW: usize = 1024;
+
+/// Counts of bucket indices 0..bins (index `bins` and up are dropped)
+fn bucket_counts<B: Backend>(indices: Tensor<B, 1, Int>, bins: usize) -> Tensor<B, 1> {
+    let device = indices.device();
+    let n = indices.dims()[0];
+    let rows = n.div_ceil(HISTOGRAM_ROW).max(1);
+    let padding = rows * HISTOGRAM_ROW - n;
+
+    // Pad into the overflow bucket so every row is full
+    let indices = if padding > 0 {
+        Tensor::cat(vec![indices, Tensor::full([padding], bins as i64, &device)], 0)
+    } else {
+        indices
+    };
+
+    Tensor::<B, 2>::zeros([rows, bins + 1], &device)
+        .scatter(1, indices.reshape([rows, HISTOGRAM_ROW]), Tensor::ones([rows, HISTOGRAM_ROW], &device), IndexingUpdateOp::Add)
+        .sum_dim(0)
+        .reshape([bins + 1])
+        .slice([0..bins])
+}
+
+/// Bucket index of every value in [low, low + width), `bins` outside
+fn bucket_indices<B: Backend>(values: Tensor<B, 1>, low: Tensor<B, 1>, width: Tensor<B, 1>, bins: usize) -> Tensor<B, 1, Int> {
+    let position = (values - low).div(width).mul_scalar(bins as f32).floor();
+    let outside = position.clone().lower_elem(0.0).bool_or(position.clone().greater_equal_elem(bins as f32));
+    position.mask_fill(outside, bins as f32).int()
+}
+
+/// Histogram of `values` in `bins` equal buckets over [low, high)
+///
+/// Values outside the range are not counted.
+///
+/// **NO SYNC POINT**: Returns [bins] counts
+pub fn histogram_gpu<B: Backend>(values: Tensor<B, 1>, low: f32, high: f32, bins: usize) -> Tensor<B, 1> {
+    assert!(high > low && bins > 0, "empty histogram range");
+    let device = values.device();
+    let low = Tensor::<B, 1>::from_floats([low], &device);
+    let width = Tensor::<B, 1>::from_floats([high], &device) - low.clone();
+    bucket_counts(bucket_indices(values, low, width, bins), bins)
+}
+
+/// Percentile of `values` (`q` in 0..=1) without a sort
+///
+/// Matches the sorted-order pick `sorted[floor(q·n)]` (so q = 0.5 is the
+/// upper median) to within (max − min) / 65536: a histogram over the value
+/// range finds the bucket holding that rank, a second one over that bucket
+/// refines it. Cost is two scatter passes whatever the distribution.
+///
+/// **NO SYNC POINT**: Returns a [1] tensor
+pub fn percentile_gpu<B: Backend>(values: Tensor<B, 1>, q: f32) -> Tensor<B, 1> {
+    let n = values.dims()[0];
+    assert!(n > 0, "percentile of no values");
+    let device = values.device();
+    let target = ((q.clamp(0.0, 1.0) * n as f32).floor() + 1.0).min(n as f32);
+
+    let min = values.clone().min();
+    let max = values.clone().max();
+    // The top bucket's upper edge sits just above the maximum
+    let mut width = (max.clone() - min.clone()).mul_scalar(1.0 + 1.0 / PERCENTILE_BINS as f32).clamp_min(f32::MIN_POSITIVE);
+    let mut low = min.clone();
+    let mut below = Tensor::<B, 1>::zeros([1], &device);
+
+    for _ in 0..PERCENTILE_PASSES {
+        let counts = bucket_counts(bucket_indices(values.clone(), low.clone(), width.clone(), PERCENTILE_BINS), PERCENTILE_BINS);
+
+        // Buckets that end before the target rank lie entirely below it
+        let before = (counts.clone().cumsum(0) + below.clone()).lower_elem(target).float();
+        let bucket = before.clone().sum().clamp_max((PERCENTILE_BINS - 1) as f32);
+        below = below + (counts * before).sum();
+        width = width.div_scalar(PERCENTILE_BINS as f32);
+        low = low + bucket * width.clone();
+    }
+
+    (low + width.mul_scalar(0.5)).max_pair(min).min_pair(max)
+}
+
+/// Median of `values` (upper median for even counts), see `percentile_gpu`
+///
+/// **NO SYNC POINT**: Returns a [1] tensor
+pub fn median_gpu<B: Backend>(values: Tensor<B, 1>) -> Tensor<B, 1> {
+    percentile_gpu(values, 0.5)
+}
+
+/// Percentile of non-negative powers or energies
+///
+/// Taken over the logarithm, so the resolution is relative: a burst a
+/// million times the noise power doesn't coarsen the median of the rest.
+/// Zeros come out as about 1e-38.
+///
+/// **NO SYNC POINT**: Returns a [1] tensor
+pub fn power_percentile_gpu<B: Backend>(powers: Tensor<B, 1>, q: f32) -> Tensor<B, 1> {
+    percentile_gpu(powers.clamp_min(f32::MIN_POSITIVE).log(), q).exp()
+}
+
+/// The `k` largest values and their indices, largest first
+///
+/// Same as `topk_separated_gpu` with separation 1 (no suppression).
+///
+/// **NO SYNC POINT**: Returns [k] tensors, k clamped to the input length
+pub fn topk_gpu<B: Backend>(values: &Tensor<B, 1>, k: usize) -> (Tensor<B, 1>, Tensor<B, 1, Int>) {
+    topk_separated_gpu(values, k, 1)
+}
+
+/// The `k` largest values at least `min_separation` indices apart, largest
+/// first, with their indices
+///
+/// Iterative suppression: take the maximum, mask every index closer than
+/// `min_separation` to it, repeat. The picks are those of walking the sorted
+/// order and keeping each index far enough from all kept ones (ties go to
+/// the lower index), so peak pickers need no host-side sort. One
+/// `max_dim_with_indices` pass per pick: meant for small `k`.
+///
+/// Picks past the last unmasked index come back as -inf (index 0).
+///
+/// **NO SYNC POINT**: Returns [k] tensors, k clamped to the input length
+pub fn topk_separated_gpu<B: Backend>(
+    values: &Tensor<B, 1>,
+    k: usize,
+    min_separation: usize,
+) -> (Tensor<B, 1>, Tensor<B, 1, Int>) {
+    let n = values.dims()[0];
+    let k = k.min(n);
+    let device = values.device();
+    if k == 0 {
+        return (Tensor::zeros([0], &device), Tensor::zeros([0], &device));
+    }
+
+    let positions = Tensor::<B, 1, Int>::arange(0..n as i64, &device).float();
+    let mut remaining = values.clone();
+    let mut top_values = Vec::with_capacity(k);
+    let mut top_indices = Vec::with_capacity(k);
+    for _ in 0..k {
+        let (value, index) = remaining.clone().max_dim_with_indices(0);
+        let near = (positions.clone() - index.clone().float()).abs().lower_elem(min_separation.max(1) as f32);
+        remaining = remaining.mask_fill(near, f32::NEG_INFINITY);
+        top_values.push(value);
+        top_indices.push(index);
+    }
+    (Tensor::cat(top_values, 0), Tensor::cat(top_indices, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::Wgpu;
+    
+    type TestBackend = Wgpu;
+    
+    #[test]
+    fn test_batch_snr_matches_per_slot() {
+        let device = Default::default();
+        let corr_len = 64;
+        
+        // Three slots with different peak heights and positions
+        let peaks = [10usize, 40, 2];
+        let heights = [8.0f32, 3.0, 20.0];
+        let mut data = Vec::new();
+        for slot in 0..3 {
+            for i in 0..corr_len {
+                let noise = ((i * 37 + slot * 11) % 13) as f32 / 13.0 - 0.5;
+                data.push(if i == peaks[slot] { heights[slot] } else { noise });
+            }
+        }
+        
+        let batch = Tensor::<TestBackend, 1>::from_floats(data.as_slice(), &device).reshape([3, corr_len]);
+        let peak_tensor = Tensor::<TestBackend, 1, Int>::from_ints([10, 40, 2], &device);
+        let snr_batch: Vec<f32> = estimate_snr_from_correlation_batch_gpu(&batch, &peak_tensor, 5)
+            .into_data().to_vec().unwrap();
+        
+        for slot in 0..3 {
+            let row = batch.clone().slice([slot..slot + 1, 0..corr_len]).reshape([corr_len]);
+            let expected = estimate_snr_from_correlation(&row, peaks[slot], 5);
+            assert!((snr_batch[slot] - expected).abs() < 1e-3, "slot {}: {} vs {}", slot, snr_batch[slot], expected);
+        }
+        
+        let weights: Vec<f32> = mrc_weights_from_snr_db_gpu(Tensor::<TestBackend, 1>::from_floats([0.0, 10.0], &device))
+            .into_data().to_vec().unwrap();
+        assert!((weights[0] - 1.0).abs() < 1e-4 && (weights[1] - 10.0).abs() < 1e-3);
+        
+        // MRC finds the peaks itself and weights each slot by its SNR
+        let llrs = Tensor::<TestBackend, 1>::from_floats([1.0, -1.0, 2.0, 2.0, -1.0, 1.0], &device).reshape([3, 2]);
+        let combined: Vec<f32> = mrc_combine_gpu(&llrs, &batch, 5).into_data().to_vec().unwrap();
+        let w: Vec<f32> = snr_batch.iter().map(|db| 10f32.powf(db / 10.0)).collect();
+        let expected = [w[0] + 2.0 * w[1] - w[2], -w[0] + 2.0 * w[1] + w[2]];
+        for (got, want) in combined.iter().zip(expected) {
+            assert!((got - want).abs() < 1e-3 * want.abs().max(1.0), "{} vs {}", got, want);
+        }
+    }
+
+    #[test]
+    fn test_percentile_matches_sort() {
+        let device = Default::default();
+
+        // Noise-like values with a few huge outliers, not a multiple of the row length
+        let values: Vec<f32> = (0..3001usize)
+            .map(|i| if i % 500 == 7 { 1e4 } else { ((i * 7919) % 1000) as f32 / 10.0 + 5.0 })
+            .collect();
+        let mut sorted = values.clone();
+        sorted.sort_by(f32::total_cmp);
+        let tensor = Tensor::<TestBackend, 1>::from_floats(values.as_slice(), &device);
+
+        for q in [0.0f32, 0.1, 0.5, 0.9, 1.0] {
+            let expected = sorted[((q * sorted.len() as f32) as usize).min(sorted.len() - 1)];
+            let linear: f32 = percentile_gpu(tensor.clone(), q).into_scalar().elem();
+            let log: f32 = power_percentile_gpu(tensor.clone(), q).into_scalar().elem();
+            assert!((linear - expected).abs() <= 1e4 / 65536.0 * 1.01, "q {}: {} vs {}", q, linear, expected);
+            assert!((log / expected - 1.0).abs() < 1e-3, "q {}: {} vs {}", q, log, expected);
+        }
+
+        // Constant input, and counts over a fixed range
+        let constant = Tensor::<TestBackend, 1>::full([10], 3.0, &device);
+        let median: f32 = median_gpu(constant).into_scalar().elem();
+        assert_eq!(median, 3.0);
+
+        let counts: Vec<f32> = histogram_gpu(tensor, 5.0, 105.0, 4).into_data().to_vec().unwrap();
+        assert_eq!(counts.iter().sum::<f32>(), 2995.0);
+        assert!(counts.iter().all(|&c| (c - 2995.0 / 4.0).abs() < 10.0), "{:?}", counts);
+    }
+
+    #[test]
+    fn test_topk_matches_sort() {
+        let device = Default::default();
+        let values: Vec<f32> = (0..997usize).map(|i| ((i * 7919 + 13) % 1009) as f32 - 500.0).collect();
+        let tensor = Tensor::<TestBackend, 1>::from_floats(values.as_slice(), &device);
+
+        let mut order: Vec<usize> = (0..values.len()).collect();
+        order.sort_by(|&a, &b| values[b].total_cmp(&values[a]).then(a.cmp(&b)));
+
+        let (top, indices) = topk_gpu(&tensor, 10);
+        let top: Vec<f32> = top.into_data().to_vec().unwrap();
+        let indices: Vec<i64> = indices.into_data().convert::<i64>().to_vec().unwrap();
+        assert_eq!(indices, order[..10].iter().map(|&i| i as i64).collect::<Vec<_>>());
+        assert_eq!(top, order[..10].iter().map(|&i| values[i]).collect::<Vec<_>>());
+
+        // Separated picks equal the greedy walk over the sorted order
+        let mut expected: Vec<usize> = Vec::new();
+        for &i in &order {
+            if expected.len() < 6 && expected.iter().all(|&p| p.abs_diff(i) >= 50) {
+                expected.push(i);
+            }
+        }
+        let (_, indices) = topk_separated_gpu(&tensor, 6, 50);
+        let indices: Vec<i64> = indices.into_data().convert::<i64>().to_vec().unwrap();
+        assert_eq!(indices, expected.iter().map(|&i| i as i64).collect::<Vec<_>>());
+
+        // More picks than room: the rest are -inf
+        let (top, _) = topk_separated_gpu(&tensor, 5, 400);
+        let top: Vec<f32> = top.into_data().to_vec().unwrap();
+        assert!(top[..3].iter().all(|v| v.is_finite()) && top[3..].iter().all(|&v| v == f32::NEG_INFINITY), "{:?}", top);
+    }
+}
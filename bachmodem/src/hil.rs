@@ -0,0 +1,265 @@
+/// Simulated Hardware-in-the-Loop over a Virtual Audio Cable
+///
+/// The full-duplex bench (`duplex`) needs two soundcards and a cable. A
+/// virtual audio device gives the same live path with no hardware: the
+/// output device is a loopback sink and the input device its monitor.
+/// - PipeWire / PulseAudio: `pactl load-module module-null-sink
+///   sink_name=bachmodem_hil`, then its "Monitor of" source
+/// - ALSA: `modprobe snd-aloop`; play on `hw:Loopback,0`, capture `hw:Loopback,1`
+/// - macOS BlackHole, Windows VB-CABLE
+///
+/// `run_loopback` plays a schedule of frames and runs the live receive path
+/// on the capture, as a monitoring station would: cpal callbacks at the
+/// device's buffer size, the streaming resampler from the device rate, a
+/// poll loop handing every chunk to a `StreamingDemodulator`, and the frame
+/// scheduler queueing each transmission at its slot. Every frame must come
+/// back with its own payload, matched to its transmission by the
+/// `TimestampCorrelator`; the report also records the longest poll stall
+/// and the most audio the demodulator held.
+///
+/// The plan and the report work without a device; the run needs feature
+/// `audio`. The ignored `hil_loopback` test drives it (see
+/// `tests/hil_loopback.rs`).
+
+use std::fmt;
+use crate::config::ModemConfig;
+
+/// Silence between frames (seconds)
+pub const DEFAULT_HIL_GAP: f64 = 2.0;
+
+/// Capture poll period, i.e. the chunk size the receive path sees (seconds)
+pub const DEFAULT_HIL_POLL_INTERVAL: f64 = 0.05;
+
+/// Frames of a loopback run and their slots
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoopbackPlan {
+    pub config: ModemConfig,
+
+    pub frames: usize,
+
+    /// Silence between frames (seconds)
+    pub gap_s: f64,
+
+    /// Capture poll period (seconds)
+    pub poll_interval_s: f64,
+
+    /// Silence before the first frame while the streams settle (seconds)
+    pub lead_in_s: f64,
+
+    /// Largest plausible path latency of the virtual device (seconds)
+    pub max_latency_s: f64,
+}
+
+impl LoopbackPlan {
+    pub fn new(config: ModemConfig, frames: usize) -> Self {
+        Self {
+            config,
+            frames,
+            gap_s: DEFAULT_HIL_GAP,
+            poll_interval_s: DEFAULT_HIL_POLL_INTERVAL,
+            lead_in_s: 1.0,
+            max_latency_s: 1.0,
+        }
+    }
+
+    pub fn with_gap(mut self, gap_s: f64) -> Self {
+        self.gap_s = gap_s;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval_s: f64) -> Self {
+        assert!(poll_interval_s > 0.0, "Poll interval must be positive");
+        self.poll_interval_s = poll_interval_s;
+        self
+    }
+
+    /// Payload of frame `frame` (distinct per frame, so a frame decoded
+    /// into the wrong slot is caught)
+    pub fn payload(&self, frame: usize) -> Vec<u8> {
+        format!("HIL {:04}", frame).into_bytes()
+    }
+
+    /// Slot start of frame `frame` (seconds after the session start)
+    pub fn slot_start(&self, frame: usize) -> f64 {
+        self.lead_in_s + frame as f64 * (self.config.frame_duration() + self.gap_s)
+    }
+
+    /// Session time by which the last frame has played out
+    pub fn end(&self) -> f64 {
+        self.slot_start(self.frames) + self.max_latency_s
+    }
+}
+
+/// Outcome of a loopback run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoopbackReport {
+    /// Frames queued for playback
+    pub sent: usize,
+
+    /// Frames decoded to their own payload
+    pub decoded: usize,
+
+    /// Syncs that matched no transmission or didn't decode to its payload
+    pub failed: usize,
+
+    /// Transmissions never heard
+    pub lost: Vec<u64>,
+
+    /// Mean and standard deviation of the path latency (seconds)
+    pub latency: Option<(f64, f64)>,
+
+    /// Longest time between two capture polls (seconds)
+    pub max_poll_gap_s: f64,
+
+    /// Most samples the streaming demodulator held at once
+    pub max_buffered: usize,
+}
+
+impl LoopbackReport {
+    /// Every frame came back, nothing else did
+    pub fn passed(&self) -> bool {
+        self.decoded == self.sent && self.failed == 0 && self.lost.is_empty()
+    }
+}
+
+impl fmt::Display for LoopbackReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} frames decoded, {} failed, {} unheard", self.decoded, self.sent, self.failed, self.lost.len())?;
+        if let Some((mean, std)) = self.latency {
+            write!(f, ", latency {:.1} ± {:.1} ms", mean * 1e3, std * 1e3)?;
+        }
+        write!(f, ", longest poll gap {:.0} ms, demodulator buffer ≤ {} samples", self.max_poll_gap_s * 1e3, self.max_buffered)
+    }
+}
+
+#[cfg(feature = "audio")]
+pub use session::run_loopback;
+
+#[cfg(feature = "audio")]
+mod session {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use burn::tensor::{Tensor, backend::Backend};
+    use crate::audio::{AudioError, AudioSettings};
+    use crate::duplex::DuplexSession;
+    use crate::fft_correlation::FftBackend;
+    use crate::modulation::{StreamEvent, StreamingDemodulator};
+    use crate::receiver_pool::{decode_llrs, ReceiverPoolConfig};
+    use crate::transmitter::{BachTransmitter, CODE_N};
+    use crate::wavelet::{generate_bach_preamble_with_config, FS};
+
+    /// Frame being received: the transmission it matched, LLRs so far
+    struct Reception {
+        seq: Option<u64>,
+        llrs: Vec<f32>,
+    }
+
+    /// Play `plan` on the output device of `settings` and receive it live
+    /// from the input device
+    ///
+    /// Blocks for the whole schedule (`plan.end()` plus the last frame's
+    /// decode).
+    pub fn run_loopback<B: Backend + FftBackend>(
+        device: &B::Device,
+        settings: &AudioSettings,
+        plan: &LoopbackPlan,
+    ) -> Result<LoopbackReport, AudioError> {
+        // Frames are built up front: the scheduler only queues audio
+        let tx = BachTransmitter::new(plan.config.clone());
+        let frames: Vec<Vec<f32>> = (0..plan.frames)
+            .map(|frame| {
+                let signal = tx.build::<B>(device, &plan.payload(frame)).expect("HIL payloads fit one frame");
+                let mut samples: Vec<f32> = signal.into_data().to_vec().unwrap();
+                let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs())).max(1e-9);
+                samples.iter_mut().for_each(|s| *s *= settings.tx_level / peak);
+                samples
+            })
+            .collect();
+
+        let pool = ReceiverPoolConfig { modem: plan.config.clone(), ..Default::default() };
+        let preamble_len = generate_bach_preamble_with_config::<B>(device, &plan.config).dims()[0];
+        let mut demod = StreamingDemodulator::<B>::new(device, plan.config.clone(), CODE_N);
+
+        let mut session = DuplexSession::start(settings, plan.max_latency_s)?;
+        let mut report = LoopbackReport { sent: plan.frames, ..Default::default() };
+        let mut payloads = HashMap::new();
+        let mut next_frame = 0;
+        let mut stream_origin = None;
+        let mut reception: Option<Reception> = None;
+        let mut last_poll = session.now();
+
+        loop {
+            let now = session.now();
+
+            // Scheduler: queue each frame at its slot
+            if next_frame < plan.frames && now >= plan.slot_start(next_frame) {
+                let seq = session.transmit(&frames[next_frame]);
+                payloads.insert(seq, plan.payload(next_frame));
+                println!("  [HIL] Frame {} queued at {:.2} s", next_frame, now);
+                next_frame += 1;
+            }
+
+            // Receive path: whatever the callbacks delivered since the last poll
+            report.max_poll_gap_s = report.max_poll_gap_s.max(now - last_poll);
+            last_poll = now;
+            let (start_s, chunk) = session.take_capture();
+            let origin = *stream_origin.get_or_insert(start_s);
+            for event in demod.push(&chunk) {
+                match event {
+                    StreamEvent::Synced { data_start } => {
+                        let detected = origin + (data_start as f64 - preamble_len as f64) / FS;
+                        let seq = session.match_rx(detected).map(|m| m.seq);
+                        println!("  [HIL] Preamble at {:.3} s: {:?}", detected, seq);
+                        reception = Some(Reception { seq, llrs: Vec::new() });
+                    }
+                    StreamEvent::Llrs(llrs) => {
+                        if let Some(reception) = reception.as_mut() {
+                            reception.llrs.extend(llrs);
+                        }
+                    }
+                    StreamEvent::FrameComplete => {
+                        let Some(Reception { seq, llrs }) = reception.take() else { continue };
+                        let decoded = decode_llrs(device, &pool, &Tensor::<B, 1>::from_floats(llrs.as_slice(), device));
+                        match (seq.and_then(|seq| payloads.get(&seq)), decoded) {
+                            (Some(payload), Ok(bytes)) if bytes.starts_with(payload) => report.decoded += 1,
+                            (_, result) => {
+                                println!("  [HIL] Frame {:?} failed: {:?}", seq, result);
+                                report.failed += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            report.max_buffered = report.max_buffered.max(demod.buffered_samples());
+            report.lost.extend(session.expire());
+
+            if next_frame == plan.frames && now > plan.end() && !demod.is_receiving() {
+                break;
+            }
+            std::thread::sleep(Duration::from_secs_f64(plan.poll_interval_s));
+        }
+
+        report.latency = session.latency_stats();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_slots_and_report() {
+        let plan = LoopbackPlan::new(ModemConfig::default(), 3).with_gap(1.5);
+        let period = ModemConfig::default().frame_duration() + 1.5;
+        assert_eq!(plan.slot_start(0), 1.0);
+        assert!((plan.slot_start(2) - (1.0 + 2.0 * period)).abs() < 1e-9);
+        assert_ne!(plan.payload(1), plan.payload(2));
+
+        let mut report = LoopbackReport { sent: 3, decoded: 3, ..Default::default() };
+        assert!(report.passed());
+        report.lost.push(2);
+        assert!(!report.passed());
+    }
+}
@@ -0,0 +1,188 @@
+/// Hopping Pattern Search
+///
+/// The built-in hopping patterns (`HOPPING_PATTERN*`) were written by hand:
+/// melodic leaps that also move far enough in frequency that consecutive
+/// symbols fade independently on a selective channel. This module states
+/// that trade-off as a cost and searches permutations of the tone alphabet
+/// for low-cost themes with simulated annealing.
+///
+/// The pattern repeats, so every hop is scored cyclically (last -> first):
+/// - separation: `(1 - Δf / target_separation_hz)²` for hops closer than the
+///   target, 0 beyond (the channel's coherence bandwidth is the natural target)
+/// - melody: dissonance of the interval class (semitones mod 12, from the
+///   tone frequencies), 0 for a fifth up to 1 for a minor second or major
+///   seventh
+/// - constraint: `forbidden_penalty` per hop whose interval class is not in
+///   `allowed_intervals` (a repeated tone is always forbidden)
+///
+/// The search keeps the first tone fixed (rotations are the same theme),
+/// swaps two other slots per step and accepts worse patterns with
+/// probability `exp(-Δcost / T)` while T cools geometrically. The random
+/// stream is SplitMix64 from `seed`, so a run is reproducible. Found themes
+/// are used with `ModemConfig::with_hopping_pattern` (`--example hop_search`
+/// prints them); transmitter and receiver must agree on the pattern.
+
+use crate::modem_rng::{ModemRng, SplitMix64};
+
+/// Dissonance per interval class (semitones mod 12), 0 = octave
+pub const INTERVAL_DISSONANCE: [f64; 12] = [0.1, 1.0, 0.6, 0.25, 0.2, 0.15, 0.9, 0.0, 0.3, 0.25, 0.6, 1.0];
+
+/// Consonant interval classes: octave, thirds, fourth, fifth, sixths
+pub const CONSONANT_INTERVALS: [u32; 7] = [0, 3, 4, 5, 7, 8, 9];
+
+/// Cost of a hopping pattern
+#[derive(Clone, Debug)]
+pub struct HopCostConfig {
+    /// Hops at least this far apart cost nothing for separation (Hz)
+    pub target_separation_hz: f64,
+
+    /// Interval classes (semitones mod 12) a hop may use
+    pub allowed_intervals: Vec<u32>,
+
+    pub separation_weight: f64,
+    pub melody_weight: f64,
+
+    /// Added per hop outside `allowed_intervals`
+    pub forbidden_penalty: f64,
+}
+
+impl Default for HopCostConfig {
+    fn default() -> Self {
+        Self {
+            target_separation_hz: 250.0,
+            allowed_intervals: CONSONANT_INTERVALS.to_vec(),
+            separation_weight: 1.0,
+            melody_weight: 1.0,
+            forbidden_penalty: 2.0,
+        }
+    }
+}
+
+/// Cost terms of one pattern (unweighted sums over the hops)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HopCost {
+    pub separation: f64,
+    pub melody: f64,
+
+    /// Hops outside the allowed interval set
+    pub forbidden: usize,
+
+    /// Weighted total
+    pub total: f64,
+}
+
+/// Score `pattern` (tone indices into `frequencies`), cyclically
+pub fn hopping_pattern_cost(pattern: &[usize], frequencies: &[f64], config: &HopCostConfig) -> HopCost {
+    let (mut separation, mut melody, mut forbidden) = (0.0, 0.0, 0);
+    for (i, &tone) in pattern.iter().enumerate() {
+        let (from, to) = (frequencies[tone], frequencies[pattern[(i + 1) % pattern.len()]]);
+
+        let shortfall = 1.0 - (to - from).abs() / config.target_separation_hz;
+        separation += shortfall.max(0.0).powi(2);
+
+        let semitones = (12.0 * (to / from).log2()).round().abs() as u32;
+        melody += INTERVAL_DISSONANCE[(semitones % 12) as usize];
+        if semitones == 0 || !config.allowed_intervals.contains(&(semitones % 12)) {
+            forbidden += 1;
+        }
+    }
+
+    HopCost {
+        separation,
+        melody,
+        forbidden,
+        total: config.separation_weight * separation + config.melody_weight * melody
+            + config.forbidden_penalty * forbidden as f64,
+    }
+}
+
+/// Annealing schedule
+#[derive(Clone, Debug)]
+pub struct AnnealConfig {
+    pub iterations: usize,
+    pub start_temperature: f64,
+    pub end_temperature: f64,
+    pub seed: u64,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        Self { iterations: 20_000, start_temperature: 1.0, end_temperature: 0.01, seed: 1 }
+    }
+}
+
+/// Best pattern found by one run
+#[derive(Clone, Debug)]
+pub struct AnnealResult {
+    /// Permutation of the alphabet starting with tone 0
+    pub pattern: Vec<usize>,
+
+    pub cost: HopCost,
+
+    /// Swaps accepted (out of `iterations`)
+    pub accepted: usize,
+}
+
+/// Simulated annealing over permutations of `frequencies`, from the scale order
+pub fn anneal_hopping_pattern(frequencies: &[f64], cost: &HopCostConfig, schedule: &AnnealConfig) -> AnnealResult {
+    let n = frequencies.len();
+    assert!(n >= 3, "need at least three tones to hop over");
+
+    let mut rng = SplitMix64::new(schedule.seed);
+
+    let mut pattern: Vec<usize> = (0..n).collect();
+    let mut current = hopping_pattern_cost(&pattern, frequencies, cost);
+    let mut best = AnnealResult { pattern: pattern.clone(), cost: current, accepted: 0 };
+    let cooling = schedule.end_temperature / schedule.start_temperature;
+
+    for step in 0..schedule.iterations {
+        let temperature = schedule.start_temperature * cooling.powf(step as f64 / schedule.iterations as f64);
+        let i = 1 + rng.below(n - 1);
+        let j = 1 + rng.below(n - 1);
+        if i == j {
+            continue;
+        }
+
+        pattern.swap(i, j);
+        let candidate = hopping_pattern_cost(&pattern, frequencies, cost);
+        if candidate.total <= current.total || rng.uniform() < ((current.total - candidate.total) / temperature).exp() {
+            current = candidate;
+            best.accepted += 1;
+            if current.total < best.cost.total {
+                best.pattern.clone_from(&pattern);
+                best.cost = current;
+            }
+        } else {
+            pattern.swap(i, j);
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wavelet::{BACH_FREQUENCIES, HOPPING_PATTERN};
+
+    #[test]
+    fn test_anneal_beats_hand_pattern() {
+        let cost = HopCostConfig::default();
+        let hand = hopping_pattern_cost(&HOPPING_PATTERN, &BACH_FREQUENCIES, &cost);
+        let scale = hopping_pattern_cost(&(0..16).collect::<Vec<_>>(), &BACH_FREQUENCIES, &cost);
+        assert_eq!(scale.forbidden, 16);
+
+        let result = anneal_hopping_pattern(&BACH_FREQUENCIES, &cost, &AnnealConfig::default());
+        println!("{:?} {:?} (hand {:?})", result.pattern, result.cost, hand);
+
+        let mut sorted = result.pattern.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..16).collect::<Vec<_>>());
+        assert_eq!(result.pattern[0], 0);
+        assert_eq!(result.cost.forbidden, 0);
+        assert!(result.cost.total < hand.total);
+
+        // Seeded: the same run finds the same theme
+        let again = anneal_hopping_pattern(&BACH_FREQUENCIES, &cost, &AnnealConfig::default());
+        assert_eq!(again.pattern, result.pattern);
+    }
+}
@@ -0,0 +1,465 @@
+/// HTTP / WebSocket Status and Control
+///
+/// Remote access to a headless monitor daemon (e.g. a Raspberry Pi next to
+/// the rig). The daemon loop owns a `DaemonControl` and reports into it;
+/// the server reads and steers the same handle:
+///
+/// ```text
+/// GET  /status           live snapshot: noise floor, input health, decodes, TX queue
+/// GET  /decodes?since=N  decode log entries with seq > N (last DECODE_LOG_LEN kept)
+/// GET  /ws               WebSocket: status and decode events as they happen
+/// POST /monitor/start    resume capture processing
+/// POST /monitor/stop     pause capture processing
+/// POST /profile          {"name": "robust"}: switch the modem profile
+/// POST /transmit         {"text": "CQ DE N0CALL"}: queue a message
+/// ```
+///
+/// All bodies are JSON. Control requests are validated here (profile names,
+/// message length against the profile) and only recorded; the daemon picks
+/// them up on its next turn (`is_running`, `config`, `take_transmissions`),
+/// so GPU work never runs on a request handler.
+///
+/// There is no authentication: bind to localhost or a trusted network.
+/// Feature `http`.
+
+use std::fmt;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use axum::Router;
+use axum::extract::{Json, Query, State};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use crate::async_ops::MonitorUpdate;
+use crate::config::{ModemConfig, PROFILE_NAMES};
+use crate::receiver_pool::{DecodeError, DecodedFrame};
+use crate::transmitter::{BachTransmitter, TransmitterError};
+
+/// Decode log entries kept for `/decodes`
+pub const DECODE_LOG_LEN: usize = 500;
+
+/// Events buffered per WebSocket client before it starts missing some
+const EVENT_BUFFER: usize = 64;
+
+/// One decode attempt
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DecodeLogEntry {
+    /// Increasing sequence number
+    pub seq: u64,
+
+    /// Wall-clock time (Unix seconds)
+    pub time_s: f64,
+
+    /// Payload as text, zero padding removed (None on failure)
+    pub text: Option<String>,
+
+    /// `DecodedFrame::snr_db`: against the noise floor, in 2500 Hz
+    pub snr_db: Option<f32>,
+
+    /// Why the capture did not decode
+    pub error: Option<String>,
+}
+
+/// Live snapshot served by `/status`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DaemonStatus {
+    pub running: bool,
+    pub profile: String,
+
+    /// Noise floor in the SNR reference bandwidth (dBFS)
+    pub noise_floor_db: Option<f64>,
+
+    /// Peak-hold power (dBFS)
+    pub peak_db: Option<f64>,
+
+    pub dc_offset: Option<f64>,
+
+    /// Measured capture sample rate (Hz)
+    pub measured_rate: Option<f64>,
+
+    /// Input faults of the most recent block
+    pub alerts: Vec<String>,
+
+    /// Decode attempts so far
+    pub decodes: u64,
+
+    /// Messages waiting for the transmitter
+    pub queued_transmissions: usize,
+}
+
+/// Pushed to WebSocket clients
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonEvent {
+    Status(DaemonStatus),
+    Decode(DecodeLogEntry),
+}
+
+/// Message accepted by `/transmit`
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueuedTransmission {
+    /// Profile at the time the message was queued
+    pub profile: String,
+
+    /// Frame payloads (`BachTransmitter::message_payloads`)
+    pub payloads: Vec<Vec<u8>>,
+}
+
+/// Rejected control request
+#[derive(Clone, Debug, PartialEq)]
+pub enum ControlError {
+    UnknownProfile(String),
+    Transmit(TransmitterError),
+}
+
+impl fmt::Display for ControlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlError::UnknownProfile(name) => {
+                write!(f, "unknown profile {:?} (available: {})", name, PROFILE_NAMES.join(", "))
+            }
+            ControlError::Transmit(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ControlError {}
+
+struct Inner {
+    running: bool,
+    profile: String,
+    config: ModemConfig,
+    monitor: Option<MonitorUpdate>,
+    log: VecDeque<DecodeLogEntry>,
+    decodes: u64,
+    queue: Vec<QueuedTransmission>,
+}
+
+/// Shared state between the daemon loop and the HTTP server
+#[derive(Clone)]
+pub struct DaemonControl {
+    inner: Arc<Mutex<Inner>>,
+    events: broadcast::Sender<DaemonEvent>,
+}
+
+impl DaemonControl {
+    /// Stopped daemon on `profile` (one of `PROFILE_NAMES`)
+    pub fn new(profile: &str) -> Result<Self, ControlError> {
+        let config = ModemConfig::profile(profile).ok_or_else(|| ControlError::UnknownProfile(profile.to_string()))?;
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Inner {
+                running: false,
+                profile: profile.to_string(),
+                config,
+                monitor: None,
+                log: VecDeque::with_capacity(DECODE_LOG_LEN),
+                decodes: 0,
+                queue: Vec::new(),
+            })),
+            events,
+        })
+    }
+
+    // ---- Daemon side ----
+
+    pub fn is_running(&self) -> bool {
+        self.inner.lock().unwrap().running
+    }
+
+    /// Current profile name and configuration
+    pub fn config(&self) -> (String, ModemConfig) {
+        let inner = self.inner.lock().unwrap();
+        (inner.profile.clone(), inner.config.clone())
+    }
+
+    /// Store the monitor state after a capture block
+    pub fn update_monitor(&self, update: MonitorUpdate) {
+        self.inner.lock().unwrap().monitor = Some(update);
+        self.publish_status();
+    }
+
+    /// Log a decode attempt
+    pub fn record_decode(&self, frame: &Result<DecodedFrame, DecodeError>) -> DecodeLogEntry {
+        let entry = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.decodes += 1;
+
+            let entry = DecodeLogEntry {
+                seq: inner.decodes,
+                time_s: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64()),
+                text: frame.as_ref().ok()
+                    .map(|f| String::from_utf8_lossy(&f.payload).trim_end_matches('\0').to_string()),
+                snr_db: frame.as_ref().ok().map(|f| f.snr_db),
+                error: frame.as_ref().err().map(|e| e.to_string()),
+            };
+            if inner.log.len() == DECODE_LOG_LEN {
+                inner.log.pop_front();
+            }
+            inner.log.push_back(entry.clone());
+            entry
+        };
+
+        let _ = self.events.send(DaemonEvent::Decode(entry.clone()));
+        entry
+    }
+
+    /// Messages queued since the last call, oldest first
+    pub fn take_transmissions(&self) -> Vec<QueuedTransmission> {
+        let queue = std::mem::take(&mut self.inner.lock().unwrap().queue);
+        if !queue.is_empty() {
+            self.publish_status();
+        }
+        queue
+    }
+
+    // ---- Control side ----
+
+    pub fn set_running(&self, running: bool) {
+        self.inner.lock().unwrap().running = running;
+        self.publish_status();
+    }
+
+    pub fn set_profile(&self, name: &str) -> Result<(), ControlError> {
+        let config = ModemConfig::profile(name).ok_or_else(|| ControlError::UnknownProfile(name.to_string()))?;
+        {
+            let mut inner = self.inner.lock().unwrap();
+            inner.profile = name.to_string();
+            inner.config = config;
+        }
+        self.publish_status();
+        Ok(())
+    }
+
+    /// Split `text` into frames of the current profile and queue them
+    ///
+    /// Returns the number of frames.
+    pub fn queue_transmission(&self, text: &str) -> Result<usize, ControlError> {
+        let frames = {
+            let mut inner = self.inner.lock().unwrap();
+            let payloads = BachTransmitter::new(inner.config.clone())
+                .message_payloads(text.as_bytes())
+                .map_err(ControlError::Transmit)?;
+            let frames = payloads.len();
+            let profile = inner.profile.clone();
+            inner.queue.push(QueuedTransmission { profile, payloads });
+            frames
+        };
+        self.publish_status();
+        Ok(frames)
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        let inner = self.inner.lock().unwrap();
+        let monitor = inner.monitor.as_ref();
+        let noise = monitor.and_then(|m| m.noise_floor.as_ref());
+
+        DaemonStatus {
+            running: inner.running,
+            profile: inner.profile.clone(),
+            noise_floor_db: noise.map(|n| n.floor_db),
+            peak_db: noise.map(|n| n.peak_db),
+            dc_offset: monitor.map(|m| m.input_health.dc_offset),
+            measured_rate: monitor.and_then(|m| m.input_health.measured_rate),
+            alerts: monitor.map_or_else(Vec::new, |m| m.alerts.iter().map(|a| a.to_string()).collect()),
+            decodes: inner.decodes,
+            queued_transmissions: inner.queue.len(),
+        }
+    }
+
+    /// Logged decodes with `seq > since`
+    pub fn decodes_since(&self, since: u64) -> Vec<DecodeLogEntry> {
+        self.inner.lock().unwrap().log.iter().filter(|e| e.seq > since).cloned().collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish_status(&self) {
+        // No subscribers is not an error
+        let _ = self.events.send(DaemonEvent::Status(self.status()));
+    }
+}
+
+#[derive(Deserialize)]
+struct DecodesQuery {
+    #[serde(default)]
+    since: u64,
+}
+
+#[derive(Deserialize)]
+struct ProfileRequest {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct TransmitRequest {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct TransmitResponse {
+    frames: usize,
+}
+
+type ControlResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+/// Routes of the status and control API
+pub fn router(control: DaemonControl) -> Router {
+    Router::new()
+        .route("/status", get(get_status))
+        .route("/decodes", get(get_decodes))
+        .route("/ws", get(websocket))
+        .route("/monitor/start", post(start_monitor))
+        .route("/monitor/stop", post(stop_monitor))
+        .route("/profile", post(set_profile))
+        .route("/transmit", post(transmit))
+        .with_state(control)
+}
+
+/// Serve the API until the listener fails
+pub async fn serve(listener: tokio::net::TcpListener, control: DaemonControl) -> std::io::Result<()> {
+    axum::serve(listener, router(control)).await
+}
+
+async fn get_status(State(control): State<DaemonControl>) -> Json<DaemonStatus> {
+    Json(control.status())
+}
+
+async fn get_decodes(State(control): State<DaemonControl>, Query(query): Query<DecodesQuery>) -> Json<Vec<DecodeLogEntry>> {
+    Json(control.decodes_since(query.since))
+}
+
+async fn start_monitor(State(control): State<DaemonControl>) -> Json<DaemonStatus> {
+    control.set_running(true);
+    Json(control.status())
+}
+
+async fn stop_monitor(State(control): State<DaemonControl>) -> Json<DaemonStatus> {
+    control.set_running(false);
+    Json(control.status())
+}
+
+async fn set_profile(State(control): State<DaemonControl>, Json(request): Json<ProfileRequest>) -> ControlResult<DaemonStatus> {
+    control.set_profile(&request.name).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(control.status()))
+}
+
+async fn transmit(State(control): State<DaemonControl>, Json(request): Json<TransmitRequest>) -> ControlResult<TransmitResponse> {
+    let frames = control.queue_transmission(&request.text).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(TransmitResponse { frames }))
+}
+
+async fn websocket(State(control): State<DaemonControl>, upgrade: WebSocketUpgrade) -> Response {
+    upgrade.on_upgrade(move |socket| stream_events(socket, control))
+}
+
+/// Current status, then every event until the client goes away
+async fn stream_events(mut socket: WebSocket, control: DaemonControl) {
+    let mut events = control.subscribe();
+    let mut event = DaemonEvent::Status(control.status());
+
+    loop {
+        let text = serde_json::to_string(&event).expect("events serialize");
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            return;
+        }
+
+        event = loop {
+            match events.recv().await {
+                Ok(event) => break event,
+                // A slow client skips events; the next status catches it up
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn request(addr: std::net::SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+               method, path, body.len(), body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_control_requests_reach_the_daemon() {
+        let control = DaemonControl::new("standard").unwrap();
+        assert!(DaemonControl::new("unknown").is_err());
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let daemon = control.clone();
+        runtime.block_on(async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(serve(listener, control));
+
+            let responses = tokio::task::spawn_blocking(move || {
+                [
+                    request(addr, "POST", "/monitor/start", ""),
+                    request(addr, "POST", "/profile", r#"{"name": "robust"}"#),
+                    request(addr, "POST", "/profile", r#"{"name": "loud"}"#),
+                    request(addr, "POST", "/transmit", r#"{"text": "CQ DE N0CALL"}"#),
+                    request(addr, "GET", "/status", ""),
+                ]
+            }).await.unwrap();
+
+            assert!(responses[0].starts_with("HTTP/1.1 200"));
+            assert!(responses[1].contains(r#""profile":"robust""#));
+            assert!(responses[2].starts_with("HTTP/1.1 400"), "{}", responses[2]);
+            assert!(responses[3].contains(r#""frames":"#));
+            assert!(responses[4].contains(r#""queued_transmissions":1"#));
+        });
+
+        // The daemon sees the requests
+        assert!(daemon.is_running());
+        assert_eq!(daemon.config().0, "robust");
+        let queued = daemon.take_transmissions();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].profile, "robust");
+        assert!(daemon.take_transmissions().is_empty());
+    }
+
+    #[test]
+    fn test_decode_log_and_events() {
+        let control = DaemonControl::new("standard").unwrap();
+        let mut events = control.subscribe();
+
+        let mut payload = b"73 DE N0CALL".to_vec();
+        payload.resize(16, 0);
+        control.record_decode(&Ok(DecodedFrame { payload, snr_db: -21.5 }));
+        control.record_decode(&Err(DecodeError::NoSync));
+
+        let log = control.decodes_since(0);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].text.as_deref(), Some("73 DE N0CALL"));
+        assert_eq!(log[1].error.as_deref(), Some("no preamble found"));
+        assert_eq!(control.decodes_since(1), log[1..]);
+
+        let json = serde_json::to_string(&events.try_recv().unwrap()).unwrap();
+        assert!(json.starts_with(r#"{"type":"decode","seq":1"#), "{}", json);
+        assert!(matches!(events.try_recv(), Ok(DaemonEvent::Decode(entry)) if entry.seq == 2));
+
+        // The log keeps the most recent entries
+        for _ in 0..DECODE_LOG_LEN {
+            control.record_decode(&Err(DecodeError::NoSync));
+        }
+        let log = control.decodes_since(0);
+        assert_eq!(log.len(), DECODE_LOG_LEN);
+        assert_eq!(log[0].seq, 3);
+    }
+}
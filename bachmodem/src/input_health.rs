@@ -0,0 +1,350 @@
+/// Audio Input Health Monitor
+///
+/// Capture faults look like bad propagation to the decoder: a USB interface
+/// dropping buffers, a soundcard with a DC offset eating headroom, or a
+/// codec running at 7999 Hz instead of 8000 Hz (which smears every symbol
+/// over a long transmission). The monitor watches the raw input stream and
+/// raises an alert once per fault onset:
+/// - Dropped buffers: a block arrives later than the previous block's
+///   length accounts for (timestamps against sample count)
+/// - DC offset: slow average of the samples beyond a threshold
+/// - Sample-rate mismatch: least-squares fit of capture time vs sample
+///   index over the whole session, compared with the nominal rate
+///
+/// Intended to sit in the capture loop next to `NoiseFloorTracker`: feed
+/// every block with the capture timestamp of its first sample (seconds on a
+/// monotonic clock). Alerts are returned from `push_block`, or delivered to
+/// an `Observer<HealthAlert>` (any `FnMut(&HealthAlert)`) with `push_block_observed`.
+///
+/// `to_record()` / `restore()` keep the DC estimate and the measured rate
+/// across restarts (see `receiver_state`). Capture timestamps start over, so
+/// the rate fit itself restarts; the saved rate is reported until it settles.
+
+use std::fmt;
+use burn::record::Record;
+use crate::observer::Observer;
+use crate::wavelet::FS;
+
+/// Input monitor thresholds
+#[derive(Clone, Debug)]
+pub struct InputHealthConfig {
+    /// Sample rate the stream claims (Hz)
+    pub nominal_rate: f64,
+
+    /// Arrival lateness tolerated before counting dropped samples (seconds)
+    ///
+    /// Covers scheduler jitter of the capture callback.
+    pub gap_tolerance: f64,
+
+    /// |DC| above this (full scale = 1.0) raises an alert
+    pub dc_threshold: f64,
+
+    /// Averaging time constant of the DC estimate (seconds)
+    pub dc_time_constant: f64,
+
+    /// Rate error above this raises an alert (ppm)
+    pub rate_tolerance_ppm: f64,
+
+    /// Capture time needed before the rate estimate is trusted (seconds)
+    pub rate_settle_time: f64,
+}
+
+impl Default for InputHealthConfig {
+    fn default() -> Self {
+        Self {
+            nominal_rate: FS,
+            gap_tolerance: 0.05,
+            dc_threshold: 0.01,
+            dc_time_constant: 2.0,
+            rate_tolerance_ppm: 200.0,
+            rate_settle_time: 30.0,
+        }
+    }
+}
+
+/// Detected capture fault
+#[derive(Clone, Debug, PartialEq)]
+pub enum HealthAlert {
+    /// Samples missing before the block captured at `at_s`
+    DroppedSamples { at_s: f64, missing: usize },
+
+    /// Persistent DC offset (full scale = 1.0)
+    DcOffset { level: f64 },
+
+    /// Measured sample rate differs from the nominal rate
+    SampleRateMismatch { measured_hz: f64, nominal_hz: f64, ppm: f64 },
+}
+
+impl fmt::Display for HealthAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthAlert::DroppedSamples { at_s, missing } => {
+                write!(f, "{} samples dropped before {:.3} s", missing, at_s)
+            }
+            HealthAlert::DcOffset { level } => write!(f, "DC offset {:+.4}", level),
+            HealthAlert::SampleRateMismatch { measured_hz, nominal_hz, ppm } => {
+                write!(f, "sample rate {:.1} Hz vs {:.0} Hz nominal ({:+.0} ppm)", measured_hz, nominal_hz, ppm)
+            }
+        }
+    }
+}
+
+/// Point-in-time view of the monitor state
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputHealthSnapshot {
+    /// Samples received (excluding dropped ones)
+    pub samples_received: u64,
+
+    /// Samples estimated lost in dropped buffers
+    pub samples_dropped: u64,
+
+    /// Current DC estimate
+    pub dc_offset: f64,
+
+    /// Measured sample rate, once settled (Hz)
+    pub measured_rate: Option<f64>,
+}
+
+/// Adaptive monitor state, for persistence
+#[derive(Record, Clone, Debug, PartialEq)]
+pub struct InputHealthRecord {
+    /// DC estimate (full scale = 1.0)
+    pub dc_offset: f64,
+
+    /// Last measured sample rate (Hz)
+    pub measured_rate: Option<f64>,
+}
+
+/// Running input stream checks
+#[derive(Clone, Debug)]
+pub struct InputHealthMonitor {
+    pub config: InputHealthConfig,
+
+    /// Timestamp and length of the previous block
+    last_block: Option<(f64, usize)>,
+
+    /// First timestamp (origin of the rate fit)
+    origin: Option<f64>,
+
+    /// Sample index of the next block, dropped samples included
+    sample_index: u64,
+
+    samples_dropped: u64,
+
+    /// Least-squares sums over (sample index, seconds since origin)
+    fit_n: f64,
+    fit_x: f64,
+    fit_y: f64,
+    fit_xx: f64,
+    fit_xy: f64,
+
+    dc_offset: f64,
+
+    /// Rate measured before a restart, reported until the fit settles
+    rate_prior: Option<f64>,
+
+    /// Latched fault states (alert on onset only)
+    dc_alerting: bool,
+    rate_alerting: bool,
+}
+
+impl InputHealthMonitor {
+    pub fn new(config: InputHealthConfig) -> Self {
+        assert!(config.nominal_rate > 0.0);
+        Self {
+            config,
+            last_block: None,
+            origin: None,
+            sample_index: 0,
+            samples_dropped: 0,
+            fit_n: 0.0,
+            fit_x: 0.0,
+            fit_y: 0.0,
+            fit_xx: 0.0,
+            fit_xy: 0.0,
+            dc_offset: 0.0,
+            rate_prior: None,
+            dc_alerting: false,
+            rate_alerting: false,
+        }
+    }
+
+    /// Check one captured block
+    ///
+    /// `timestamp`: capture time of the block's first sample (seconds)
+    pub fn push_block(&mut self, samples: &[f32], timestamp: f64) -> Vec<HealthAlert> {
+        let mut alerts = Vec::new();
+        let rate = self.config.nominal_rate;
+
+        // Dropped buffers: the gap since the last block exceeds its length
+        if let Some((last_time, last_len)) = self.last_block {
+            let late = timestamp - (last_time + last_len as f64 / rate);
+            if late > self.config.gap_tolerance {
+                let missing = (late * rate).round() as usize;
+                self.sample_index += missing as u64;
+                self.samples_dropped += missing as u64;
+                alerts.push(HealthAlert::DroppedSamples { at_s: timestamp, missing });
+            }
+        }
+        self.last_block = Some((timestamp, samples.len()));
+
+        // Rate fit: time = index / rate (+ constant)
+        let origin = *self.origin.get_or_insert(timestamp);
+        let x = self.sample_index as f64;
+        let y = timestamp - origin;
+        self.fit_n += 1.0;
+        self.fit_x += x;
+        self.fit_y += y;
+        self.fit_xx += x * x;
+        self.fit_xy += x * y;
+        self.sample_index += samples.len() as u64;
+
+        if let Some(measured) = self.measured_rate() {
+            let ppm = (measured / rate - 1.0) * 1e6;
+            let mismatch = ppm.abs() > self.config.rate_tolerance_ppm;
+            if mismatch && !self.rate_alerting {
+                alerts.push(HealthAlert::SampleRateMismatch { measured_hz: measured, nominal_hz: rate, ppm });
+            }
+            self.rate_alerting = mismatch;
+        }
+
+        // DC: exponential average of the block means
+        if !samples.is_empty() {
+            let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+            let alpha = (samples.len() as f64 / rate / self.config.dc_time_constant).min(1.0);
+            self.dc_offset += alpha * (mean - self.dc_offset);
+
+            let offset = self.dc_offset.abs() > self.config.dc_threshold;
+            if offset && !self.dc_alerting {
+                alerts.push(HealthAlert::DcOffset { level: self.dc_offset });
+            }
+            self.dc_alerting = offset;
+        }
+
+        alerts
+    }
+
+    /// Check one captured block, delivering alerts to `observer`
+    pub fn push_block_observed<O: Observer<HealthAlert>>(&mut self, samples: &[f32], timestamp: f64, observer: &mut O) {
+        for alert in self.push_block(samples, timestamp) {
+            observer.notify(&alert);
+        }
+    }
+
+    /// Sample rate from the timestamp fit, once `rate_settle_time` is covered
+    ///
+    /// Before that, the rate restored from a saved state (if any).
+    pub fn measured_rate(&self) -> Option<f64> {
+        self.fitted_rate().or(self.rate_prior)
+    }
+
+    /// The DC offset estimate and the measured sample rate, for the next
+    /// session; alert counters and the timestamp fit are not kept
+    pub fn to_record(&self) -> InputHealthRecord {
+        InputHealthRecord {
+            dc_offset: self.dc_offset,
+            measured_rate: self.measured_rate(),
+        }
+    }
+
+    /// Resume from a saved state
+    ///
+    /// Session counters and the rate fit start over; the DC estimate and
+    /// the measured rate carry on.
+    pub fn restore(&mut self, record: InputHealthRecord) {
+        self.dc_offset = record.dc_offset;
+        self.rate_prior = record.measured_rate;
+    }
+
+    /// Rate of the current session's timestamp fit, once settled
+    fn fitted_rate(&self) -> Option<f64> {
+        let elapsed = self.elapsed()?;
+        if elapsed < self.config.rate_settle_time || self.fit_n < 3.0 {
+            return None;
+        }
+
+        let denom = self.fit_n * self.fit_xx - self.fit_x * self.fit_x;
+        let slope = (self.fit_n * self.fit_xy - self.fit_x * self.fit_y) / denom;
+        (slope > 0.0).then(|| 1.0 / slope)
+    }
+
+    /// Current DC estimate
+    pub fn dc_offset(&self) -> f64 {
+        self.dc_offset
+    }
+
+    pub fn snapshot(&self) -> InputHealthSnapshot {
+        InputHealthSnapshot {
+            samples_received: self.sample_index - self.samples_dropped,
+            samples_dropped: self.samples_dropped,
+            dc_offset: self.dc_offset,
+            measured_rate: self.measured_rate(),
+        }
+    }
+
+    /// Seconds covered by the blocks seen so far
+    fn elapsed(&self) -> Option<f64> {
+        let (last_time, _) = self.last_block?;
+        Some(last_time - self.origin?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: usize = 1024;
+
+    /// Feed `seconds` of blocks captured at `true_rate`, with callback jitter
+    fn run(monitor: &mut InputHealthMonitor, true_rate: f64, seconds: f64, dc: f32, skip_block: Option<usize>) -> Vec<HealthAlert> {
+        let mut alerts = Vec::new();
+        let num_blocks = (seconds * true_rate) as usize / BLOCK;
+        let samples: Vec<f32> = (0..BLOCK).map(|i| dc + 0.1 * ((i % 17) as f32 - 8.0) / 8.0).collect();
+
+        for k in 0..num_blocks {
+            if skip_block == Some(k) {
+                continue;
+            }
+            let jitter = 0.004 * ((k * 7919) % 11) as f64 / 10.0;
+            let timestamp = (k * BLOCK) as f64 / true_rate + jitter;
+            monitor.push_block_observed(&samples, timestamp, &mut |alert: &HealthAlert| alerts.push(alert.clone()));
+        }
+        alerts
+    }
+
+    #[test]
+    fn test_clean_stream_raises_nothing() {
+        let mut monitor = InputHealthMonitor::new(InputHealthConfig::default());
+        let alerts = run(&mut monitor, FS, 60.0, 0.0, None);
+
+        assert!(alerts.is_empty(), "{:?}", alerts);
+        let rate = monitor.measured_rate().unwrap();
+        assert!((rate - FS).abs() < 0.2, "rate {}", rate);
+    }
+
+    #[test]
+    fn test_dropped_buffer() {
+        let mut monitor = InputHealthMonitor::new(InputHealthConfig::default());
+        let alerts = run(&mut monitor, FS, 10.0, 0.0, Some(20));
+
+        assert_eq!(alerts.len(), 1);
+        let HealthAlert::DroppedSamples { missing, .. } = alerts[0] else { panic!("{:?}", alerts) };
+        assert!(missing.abs_diff(BLOCK) < 40);
+        assert_eq!(monitor.snapshot().samples_dropped, missing as u64);
+    }
+
+    #[test]
+    fn test_dc_offset_and_rate_mismatch() {
+        let mut monitor = InputHealthMonitor::new(InputHealthConfig::default());
+
+        // 1000 ppm fast codec with a -34 dBFS DC offset
+        let alerts = run(&mut monitor, FS * 1.001, 60.0, 0.02, None);
+
+        // Alert as the average crosses the threshold; the estimate settles on the offset
+        assert!(matches!(alerts[0], HealthAlert::DcOffset { level } if level > 0.01));
+        assert!((monitor.dc_offset() - 0.02).abs() < 1e-3);
+        let HealthAlert::SampleRateMismatch { ppm, .. } = alerts[1] else { panic!("{:?}", alerts) };
+        assert!((ppm - 1000.0).abs() < 50.0, "ppm {}", ppm);
+        assert_eq!(alerts.len(), 2);
+    }
+}
@@ -0,0 +1,204 @@
+/// Jammer Resistance Analysis
+///
+/// The melodic hopping pattern is public, so a follower jammer can predict
+/// the tone of every symbol and park its carrier on it. This tool compares
+/// that against a jammer picking a random tone each symbol, for:
+/// - `HopMode::Melodic`: the fixed musical pattern (`ModemConfig::hopping_pattern`)
+/// - `HopMode::Seeded`: the modem's seeded permutation
+///   (`ModemConfig::with_hopping_seed`), unknown to the jammer (it keeps
+///   predicting the public melody)
+///
+/// Symbol-level model of the lag-differential DPSK receiver:
+///   z_i = e^{jφ_i} + n_i + hit_i · √JSR · e^{jθ_i}
+/// with n_i complex Gaussian at the given per-symbol SNR (Es/N0) and a random
+/// jammer phase θ_i. A hit corrupts the symbol both as data and as the phase
+/// reference for the next block, exactly like in the real demodulator.
+///
+/// Bits, noise and jammer draw from separate `SimSeed` streams of the seed,
+/// so a report replays exactly.
+///
+/// The result quantifies what the music costs: a tracking jammer hits every
+/// symbol of a melodic transmission but only the symbols where the seeded
+/// permutation happens to agree with the melody (1/N on average).
+
+use std::f64::consts::PI;
+use crate::config::ModemConfig;
+use crate::modem_rng::{ModemRng, RngStream, SimSeed};
+
+/// Tone sequence used by the transmitter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HopMode {
+    /// Public melodic pattern
+    Melodic,
+
+    /// Seeded permutation (`ModemConfig::with_hopping_seed`)
+    Seeded(u64),
+}
+
+/// Jammer tone selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JammerStrategy {
+    /// Jam the tone the public melody predicts for each symbol
+    Tracking,
+
+    /// Jam a uniformly random tone each symbol
+    RandomTone,
+}
+
+/// Outcome of one jamming scenario
+#[derive(Clone, Debug)]
+pub struct JammingReport {
+    pub mode: HopMode,
+    pub strategy: JammerStrategy,
+
+    /// Jammer-to-signal ratio on the jammed tone (dB)
+    pub jsr_db: f64,
+
+    /// Fraction of symbols whose tone was jammed
+    pub hit_rate: f64,
+
+    /// Bit error rate after differential decoding (before FEC)
+    pub ber: f64,
+}
+
+/// Tone index of every symbol for a hopping mode
+///
+/// Seeded mode is exactly what a modem configured with the seed transmits.
+pub fn hop_sequence(config: &ModemConfig, mode: HopMode, num_symbols: usize) -> Vec<usize> {
+    match mode {
+        HopMode::Melodic => config.melody_indices(num_symbols),
+        HopMode::Seeded(seed) => config.clone().with_hopping_seed(seed).melody_indices(num_symbols),
+    }
+}
+
+/// Simulate `num_bits` differential bits under a jammer
+///
+/// `snr_db` is the per-symbol Es/N0 at the matched filter output.
+pub fn simulate_jamming(
+    config: &ModemConfig,
+    mode: HopMode,
+    strategy: JammerStrategy,
+    jsr_db: f64,
+    snr_db: f64,
+    num_bits: usize,
+    seed: u64,
+) -> JammingReport {
+    let lag = config.lag();
+    let num_symbols = num_bits + lag; // Plus reference block
+    let seed = SimSeed(seed);
+    let mut rng = seed.rng(RngStream::Scenario);
+    let mut noise = seed.rng(RngStream::Noise);
+    let mut jammer = seed.rng(RngStream::Jammer);
+
+    let tones = hop_sequence(config, mode, num_symbols);
+    let predicted = config.melody_indices(num_symbols);
+
+    let noise_sigma = (10f64.powf(-snr_db / 10.0) / 2.0).sqrt(); // Per quadrature
+    let jammer_amp = 10f64.powf(jsr_db / 20.0);
+
+    // Transmit phases: reference block at 0, bit 1 flips by π
+    let bits: Vec<u8> = (0..num_bits).map(|_| rng.below(2) as u8).collect();
+    let mut phases = vec![0.0f64; lag];
+    for (j, &bit) in bits.iter().enumerate() {
+        phases.push(phases[j] + if bit == 1 { PI } else { 0.0 });
+    }
+
+    let mut hits = 0;
+    let received: Vec<(f64, f64)> = (0..num_symbols)
+        .map(|i| {
+            let jammed_tone = match strategy {
+                JammerStrategy::Tracking => predicted[i],
+                JammerStrategy::RandomTone => jammer.below(config.num_tones),
+            };
+
+            let (mut re, mut im) = (phases[i].cos(), phases[i].sin());
+            re += noise_sigma * noise.gaussian();
+            im += noise_sigma * noise.gaussian();
+
+            if jammed_tone == tones[i] {
+                hits += 1;
+                let theta = 2.0 * PI * jammer.uniform();
+                re += jammer_amp * theta.cos();
+                im += jammer_amp * theta.sin();
+            }
+            (re, im)
+        })
+        .collect();
+
+    let errors = (0..num_bits)
+        .filter(|&j| {
+            let (re_prev, im_prev) = received[j];
+            let (re_curr, im_curr) = received[j + lag];
+            let decided = (re_curr * re_prev + im_curr * im_prev < 0.0) as u8;
+            decided != bits[j]
+        })
+        .count();
+
+    JammingReport {
+        mode,
+        strategy,
+        jsr_db,
+        hit_rate: hits as f64 / num_symbols as f64,
+        ber: errors as f64 / num_bits as f64,
+    }
+}
+
+/// Run every mode/strategy combination at one operating point
+pub fn jamming_matrix(
+    config: &ModemConfig,
+    jsr_db: f64,
+    snr_db: f64,
+    num_bits: usize,
+    seed: u64,
+) -> Vec<JammingReport> {
+    let mut reports = Vec::new();
+    for mode in [HopMode::Melodic, HopMode::Seeded(seed ^ 0x5EED)] {
+        for strategy in [JammerStrategy::Tracking, JammerStrategy::RandomTone] {
+            reports.push(simulate_jamming(config, mode, strategy, jsr_db, snr_db, num_bits, seed));
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_hops_visit_every_tone_per_block() {
+        let config = ModemConfig::default();
+        let tones = hop_sequence(&config, HopMode::Seeded(7), 64);
+
+        for block in tones.chunks(16) {
+            let mut sorted = block.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, (0..16).collect::<Vec<_>>());
+        }
+        assert_ne!(tones, config.melody_indices(64));
+        assert_eq!(tones, config.clone().with_hopping_seed(7).melody_indices(64));
+    }
+
+    #[test]
+    fn test_tracking_jammer_hurts_melodic_mode_most() {
+        let config = ModemConfig::default();
+        let reports = jamming_matrix(&config, 0.0, 10.0, 4000, 1);
+        let find = |mode: fn(&HopMode) -> bool, strategy| {
+            reports.iter().find(|r| mode(&r.mode) && r.strategy == strategy).unwrap()
+        };
+
+        let melodic_tracked = find(|m| *m == HopMode::Melodic, JammerStrategy::Tracking);
+        let seeded_tracked = find(|m| matches!(m, HopMode::Seeded(_)), JammerStrategy::Tracking);
+        let melodic_random = find(|m| *m == HopMode::Melodic, JammerStrategy::RandomTone);
+
+        // The tracker only hits where the seeded pattern agrees with the melody
+        let seeded = hop_sequence(&config, seeded_tracked.mode, 16);
+        let agree = (0..16).filter(|&i| seeded[i] == config.melody_indices(16)[i]).count();
+        assert_eq!(melodic_tracked.hit_rate, 1.0);
+        assert!((seeded_tracked.hit_rate - agree as f64 / 16.0).abs() < 0.01);
+        assert!((melodic_random.hit_rate - 1.0 / 16.0).abs() < 0.02);
+
+        // Follower jamming at 0 dB JSR wrecks the melody, dents seeded hops
+        assert!(melodic_tracked.ber > 0.2);
+        assert!(seeded_tracked.ber < melodic_tracked.ber / 2.0);
+    }
+}
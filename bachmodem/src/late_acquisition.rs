@@ -0,0 +1,324 @@
+/// Late Acquisition
+///
+/// A capture that starts after the preamble has nothing for the preamble
+/// correlator to find, yet the rest of the transmission may still decode:
+/// the symbols lost with the preamble are only a fraction of the codeword.
+/// The fallback recovers the data grid from what was received:
+///
+/// 1. Symbol timing and hop phase from the data itself. Windows cut at
+///    `LATE_TIMING_STEPS` offsets within one symbol are correlated with the
+///    whole matched filter bank; for every phase of the hopping pattern the
+///    fraction of the energy on the tones it predicts is measured. The best
+///    (offset, phase) fixes the symbol clock and the slot of each window.
+/// 2. A flourish, if the transmitter inserts them, anchors the grid
+///    instead: the symbol after it starts a multiple of the flourish
+///    interval, and its hop phase must agree with that.
+///
+/// Either way the frame position is only known modulo the pattern length
+/// (or the flourish interval), so every block position that fits the frame
+/// is returned as a `LateStart`, the ones missing the fewest symbols first.
+/// Frames carry no CRC: a hypothesis is accepted when its decode's
+/// `codeword_agreement` reaches the ladder's `min_agreement`
+/// (`RetryRung::late`).
+/// Symbols the capture missed are erasures; the postamble is not used, so a
+/// capture cut at both ends works too.
+
+use burn::tensor::{Tensor, backend::Backend, ElementConversion};
+use crate::complex::ComplexTensor;
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{demodulate_fhdpsk_stats_with_config, measure_flourish_offset, DemodStatistics};
+use crate::receiver_pool::{CaptureLlrs, DecodeError, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+use crate::transmitter::CODE_N;
+use crate::wavelet::{generate_bach_flourish_with_config, matched_filter_bank};
+
+/// Symbol timing offsets searched per symbol
+pub const LATE_TIMING_STEPS: usize = 16;
+
+/// Hopping pattern periods measured per timing hypothesis
+pub const LATE_SEARCH_BLOCKS: usize = 4;
+
+/// Hop purity a grid needs, as a multiple of the 1 / num_tones noise scores
+pub const LATE_MIN_PURITY_GAIN: f32 = 4.0;
+
+/// What fixed the symbol grid of a late start
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LateAnchor {
+    /// Hop-sequence correlation over the start of the capture
+    HopStructure,
+
+    /// A flourish found in the capture
+    Flourish,
+}
+
+/// One hypothesis for where the data of a transmission began
+#[derive(Clone, Debug, PartialEq)]
+pub struct LateStart {
+    /// First data sample relative to the capture (negative = before it)
+    pub data_start: isize,
+
+    /// Transmitted symbol index of the anchor window
+    pub symbol_index: usize,
+
+    pub anchor: LateAnchor,
+
+    /// Fraction of the anchor grid's matched-filter energy on the hopping tones
+    pub hop_purity: f32,
+}
+
+/// Data start hypotheses for a capture without a preamble, the ones
+/// missing the fewest symbols first
+///
+/// `num_bits` is the codeword length; hypotheses whose anchor would lie past
+/// the end of the frame are dropped. Empty if neither a flourish nor the
+/// hop structure stands out from noise.
+///
+/// ⚠️ **SYNC POINT**: Downloads correlation peaks and window energies
+pub fn late_start_candidates<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Vec<LateStart> {
+    let flourish_interval = config.flourish_interval;
+    let bank = matched_filter_bank::<B>(device, config);
+    let total_symbols = config.reference_layout().num_symbols(num_bits);
+    let period = config.hopping_pattern().len();
+    let min_purity = LATE_MIN_PURITY_GAIN / config.num_tones as f32;
+
+    // Flourish first: it pins the symbol clock and narrows the positions
+    if flourish_interval > 0 {
+        if let Some((anchor, phase, purity)) = flourish_anchor(device, signal, &bank, config) {
+            if purity >= min_purity {
+                return (flourish_interval..total_symbols)
+                    .step_by(flourish_interval)
+                    .filter(|&s| s % period == phase)
+                    .map(|s| late_start(anchor, s, LateAnchor::Flourish, purity, config))
+                    .collect();
+            }
+        }
+    }
+
+    let Some((anchor, phase, purity)) = hop_structure_anchor(signal, &bank, config) else {
+        return Vec::new();
+    };
+    if purity < min_purity {
+        return Vec::new();
+    }
+    (phase..total_symbols)
+        .step_by(period)
+        .map(|s| late_start(anchor, s, LateAnchor::HopStructure, purity, config))
+        .collect()
+}
+
+/// Detector statistics of exactly `num_bits` bits from a late start
+///
+/// The missing beginning is zero-filled and every symbol whose window
+/// starts before the capture is erased. None if the start lies past the
+/// end of the capture.
+pub fn demodulate_late_stats_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    start: &LateStart,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Option<DemodStatistics<B>> {
+    let signal_len = signal.dims()[0];
+    let data = if start.data_start < 0 {
+        let missing = start.data_start.unsigned_abs();
+        Tensor::cat(vec![Tensor::zeros([missing], device), signal.clone()], 0)
+    } else if (start.data_start as usize) < signal_len {
+        signal.clone().slice([start.data_start as usize..signal_len])
+    } else {
+        return None;
+    };
+
+    let mut stats = demodulate_fhdpsk_stats_with_config::<B>(device, &data, false, config, num_bits)?;
+    for (i, erased) in stats.erased_symbols.iter_mut().enumerate() {
+        *erased |= start.data_start + symbol_offset(i, config) < 0;
+    }
+    Some(stats)
+}
+
+/// `capture_llrs_at` for a late start: no RAKE and no partial-band
+/// detection, both need the preamble
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR
+pub(crate) fn capture_llrs_late<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    start: &LateStart,
+) -> Result<CaptureLlrs<B>, DecodeError> {
+    let stats = demodulate_late_stats_with_config::<B>(device, signal, start, &config.modem, CODE_N)
+        .ok_or(DecodeError::NoSync)?;
+    let llrs = state.llr_mapping().llrs(&stats) * config.tuning.llr_scale;
+    let snr_db: f32 = stats.snr_db.into_scalar().elem();
+    Ok(CaptureLlrs { llrs, snr_db })
+}
+
+/// Start of symbol `index` relative to the data start (samples)
+fn symbol_offset(index: usize, config: &ModemConfig) -> isize {
+    let flourishes = if config.flourish_interval > 0 { index / config.flourish_interval } else { 0 };
+    // A flourish is one up/down sweep of the alphabet
+    let flourish_len = 2 * config.num_tones * config.preamble_note_samples();
+    (index * config.symbol_samples() + flourishes * flourish_len) as isize
+}
+
+fn late_start(anchor: usize, symbol_index: usize, kind: LateAnchor, purity: f32, config: &ModemConfig) -> LateStart {
+    LateStart {
+        data_start: anchor as isize - symbol_offset(symbol_index, config),
+        symbol_index,
+        anchor: kind,
+        hop_purity: purity,
+    }
+}
+
+/// Strongest flourish in the capture: (first symbol after it, hop phase, purity)
+///
+/// ⚠️ **SYNC POINT**
+fn flourish_anchor<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    bank: &ComplexTensor<B, 2>,
+    config: &ModemConfig,
+) -> Option<(usize, usize, f32)> {
+    let flourish = generate_bach_flourish_with_config::<B>(device, config);
+    let (position, _) = measure_flourish_offset(device, signal, &flourish, 0, signal.dims()[0])?;
+    let anchor = position as usize + flourish.dims()[0];
+
+    let windows = config.flourish_interval.min(LATE_SEARCH_BLOCKS * config.lag());
+    let (phase, purity) = hop_phase(signal, bank, anchor, windows, config)?;
+    Some((anchor, phase, purity))
+}
+
+/// Best symbol grid within the first symbol of the capture:
+/// (first window start, hop phase, purity)
+///
+/// ⚠️ **SYNC POINT**
+fn hop_structure_anchor<B: Backend>(
+    signal: &Tensor<B, 1>,
+    bank: &ComplexTensor<B, 2>,
+    config: &ModemConfig,
+) -> Option<(usize, usize, f32)> {
+    let symbol_len = config.symbol_samples();
+    let windows = LATE_SEARCH_BLOCKS * config.lag();
+
+    (0..LATE_TIMING_STEPS)
+        .map(|step| step * symbol_len / LATE_TIMING_STEPS)
+        .filter_map(|start| hop_phase(signal, bank, start, windows, config).map(|(phase, purity)| (start, phase, purity)))
+        .max_by(|a, b| a.2.total_cmp(&b.2))
+}
+
+/// Hop phase of the symbol grid starting at `start`
+///
+/// Up to `windows` consecutive symbol windows go through the whole bank;
+/// phase h scores the fraction of their energy on the tones the hopping
+/// pattern predicts if the first window is slot h. Returns the best
+/// (phase, purity), None if no whole window fits.
+///
+/// ⚠️ **SYNC POINT**: Downloads the window energies
+fn hop_phase<B: Backend>(
+    signal: &Tensor<B, 1>,
+    bank: &ComplexTensor<B, 2>,
+    start: usize,
+    windows: usize,
+    config: &ModemConfig,
+) -> Option<(usize, f32)> {
+    let symbol_len = config.symbol_samples();
+    let num_tones = config.num_tones;
+    let windows = windows.min(signal.dims()[0].saturating_sub(start) / symbol_len);
+    if windows == 0 {
+        return None;
+    }
+
+    // [windows, symbol_len] against the whole bank
+    let batch = signal.clone().slice([start..start + windows * symbol_len]).reshape([windows, symbol_len]);
+    let power: Vec<f32> = bank.clone()
+        .map(|bank| batch.clone().matmul(bank.transpose()))
+        .norm_sqr()
+        .into_data().to_vec().unwrap();
+    let total: f32 = power.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let pattern = config.hopping_pattern();
+    (0..pattern.len())
+        .map(|phase| {
+            let on_tone: f32 = (0..windows)
+                .map(|j| power[j * num_tones + pattern[(j + phase) % pattern.len()]])
+                .sum();
+            (phase, on_tone / total)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry_ladder::{decode_with_retries, RetryLadder, RetryRung, MIN_CODEWORD_AGREEMENT};
+    use crate::transmitter::BachTransmitter;
+    use crate::wavelet::generate_bach_preamble_with_config;
+    use crate::TestBackend;
+
+    /// The transmission from two blocks and a fraction of a symbol into the data
+    fn late_capture(device: &<TestBackend as Backend>::Device, tx: &BachTransmitter, payload: &[u8]) -> (Tensor<TestBackend, 1>, usize) {
+        let signal = tx.build::<TestBackend>(device, payload).unwrap();
+        let preamble_len = generate_bach_preamble_with_config::<TestBackend>(device, &tx.config).dims()[0];
+        let missed = 2 * tx.config.lag() * tx.config.symbol_samples() + 333;
+        let cut = preamble_len + missed;
+        (signal.clone().slice([cut..signal.dims()[0]]), missed)
+    }
+
+    #[test]
+    fn test_hop_structure_recovers_capture_without_preamble() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig::default();
+        let tx = BachTransmitter::new(config.modem.clone());
+        let (capture, missed) = late_capture(&device, &tx, b"LATE START");
+
+        // The preamble correlator has nothing to lock onto
+        let strict = RetryLadder { rungs: vec![RetryRung::fast()], min_agreement: MIN_CODEWORD_AGREEMENT };
+        let mut state = ReceiverState::<TestBackend>::default();
+        assert!(decode_with_retries(&device, &mut state, &config, &strict, &capture).is_err());
+
+        let candidates = late_start_candidates(&device, &capture, &config.modem, CODE_N);
+        assert!(candidates.iter().all(|c| c.anchor == LateAnchor::HopStructure));
+        let symbol_len = config.modem.symbol_samples() as isize;
+        assert!(candidates.iter().any(|c| (c.data_start + missed as isize).abs() <= symbol_len / LATE_TIMING_STEPS as isize));
+
+        let late = RetryLadder { rungs: vec![RetryRung::late()], min_agreement: MIN_CODEWORD_AGREEMENT };
+        let decoded = decode_with_retries(&device, &mut state, &config, &late, &capture).unwrap();
+        assert!(decoded.frame.payload.starts_with(b"LATE START"));
+    }
+
+    #[test]
+    fn test_flourish_anchors_late_capture() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig { modem: ModemConfig::default().with_flourish_interval(64), ..ReceiverPoolConfig::default() };
+        let tx = BachTransmitter::new(config.modem.clone());
+        let (capture, missed) = late_capture(&device, &tx, b"FLOURISH");
+
+        // Any of the flourishes may be the strongest; one hypothesis is exact
+        let candidates = late_start_candidates(&device, &capture, &config.modem, CODE_N);
+        assert!(candidates.iter().all(|c| c.anchor == LateAnchor::Flourish && c.symbol_index % 64 == 0));
+        assert!(candidates.iter().any(|c| (c.data_start + missed as isize).abs() <= 2));
+
+        let mut state = ReceiverState::<TestBackend>::default();
+        let late = RetryLadder { rungs: vec![RetryRung::late()], min_agreement: MIN_CODEWORD_AGREEMENT };
+        let decoded = decode_with_retries(&device, &mut state, &config, &late, &capture).unwrap();
+        assert!(decoded.frame.payload.starts_with(b"FLOURISH"));
+    }
+
+    #[test]
+    fn test_noise_has_no_late_start() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let noise: Vec<f32> = (0..80_000u32).map(|i| ((i.wrapping_mul(2_654_435_761) >> 16) as f32 / 65536.0) - 0.5).collect();
+        let noise = Tensor::<TestBackend, 1>::from_floats(noise.as_slice(), &device);
+        assert!(late_start_candidates(&device, &noise, &config, CODE_N).is_empty());
+    }
+}
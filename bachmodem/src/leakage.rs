@@ -0,0 +1,302 @@
+/// Matched Filter Leakage Compensation
+///
+/// The 0.1 s Morlet wavelets of neighbouring scale notes overlap in
+/// frequency: a whole-tone step leaks about -24 dB into the neighbouring
+/// filter, the E-F and B-C semitone steps about -9 dB. One hop carries one
+/// tone, but multipath echoes of the previous hop and other tones in the
+/// passband land in the current symbol window, and part of their energy
+/// shows up in the expected tone's correlation.
+///
+/// With every symbol correlated against the whole bank, the outputs are
+/// c = M a, where a holds the amplitudes of the tones actually present and
+/// M_ij = ⟨t_j, r_i⟩ is the response of receive filter i to transmitted
+/// tone j (tone gains included). The compensated correlation
+///
+/// ```text
+/// ĉ = diag(M) · M⁻¹ · c
+/// ```
+///
+/// removes the other tones' contributions and leaves a lone tone's
+/// correlation unchanged, so LLR scales stay as before. M and its inverse
+/// are computed on the CPU once per filter bank design (tones, gains, symbol
+/// length, pulse shape) and cached; the correction is two small complex
+/// matmuls per transmission. Enabled by `ModemConfig::leakage_compensation`;
+/// a bank too dense to invert is rejected by `with_leakage_compensation`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use burn::tensor::{Tensor, backend::Backend};
+use bachmodem_core::matched_filter::shaped_wavelet_f32;
+use crate::complex::ComplexTensor;
+use crate::config::{ConfigError, ModemConfig};
+use crate::wavelet::FS;
+
+/// Complex matrix as (real, imag), row-major [num_tones × num_tones]
+pub type ComplexMatrix = (Vec<f64>, Vec<f64>);
+
+/// Response of every receive filter to every transmitted tone
+///
+/// Entry (i, j): correlation of filter i (wavelet / gain_i) with the analytic
+/// transmit atom of tone j (wavelet · gain_j).
+pub fn filter_bank_gram(config: &ModemConfig) -> ComplexMatrix {
+    let n = config.num_tones;
+    let wavelets: Vec<(Vec<f32>, Vec<f32>)> = config.frequencies().iter()
+        .map(|&f| shaped_wavelet_f32(f, config.symbol_duration, config.wavelet_shape(), FS))
+        .collect();
+
+    let mut re = vec![0.0; n * n];
+    let mut im = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..n {
+            // Σ ψ_j · conj(ψ_i)
+            let (mut sum_re, mut sum_im) = (0.0f64, 0.0f64);
+            for ((&jr, &ji), (&ir, &ii)) in wavelets[j].0.iter().zip(&wavelets[j].1).zip(wavelets[i].0.iter().zip(&wavelets[i].1)) {
+                sum_re += (jr * ir + ji * ii) as f64;
+                sum_im += (ji * ir - jr * ii) as f64;
+            }
+            let ratio = config.tone_gain(j) / config.tone_gain(i);
+            re[i * n + j] = sum_re * ratio;
+            im[i * n + j] = sum_im * ratio;
+        }
+    }
+    (re, im)
+}
+
+/// Compensation matrices by filter bank design (see `bank_key`)
+type CompensationCache = HashMap<Vec<u64>, Result<Arc<ComplexMatrix>, ConfigError>>;
+
+static COMPENSATION: OnceLock<Mutex<CompensationCache>> = OnceLock::new();
+
+/// Everything `filter_bank_gram` depends on, as exact bit patterns
+fn bank_key(config: &ModemConfig) -> Vec<u64> {
+    let shape = config.wavelet_shape();
+    let mut key: Vec<u64> = config.frequencies().iter().map(|f| f.to_bits()).collect();
+    key.extend((0..config.num_tones).map(|i| config.tone_gain(i).to_bits()));
+    key.extend([config.symbol_duration.to_bits(), shape.sigmas.to_bits(), shape.sweep_hz.to_bits()]);
+    key
+}
+
+/// Compensation matrix diag(M) · M⁻¹ of the config's filter bank
+///
+/// Computed on the first call for a bank design, then served from a
+/// process-wide cache. `ConfigError::SingularFilterBank` if M can't be
+/// inverted (tones closer than the wavelet resolution).
+pub fn leakage_compensation_matrix(config: &ModemConfig) -> Result<Arc<ComplexMatrix>, ConfigError> {
+    let cache = COMPENSATION.get_or_init(Default::default);
+    let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.entry(bank_key(config))
+        .or_insert_with(|| compute_compensation(config).map(Arc::new))
+        .clone()
+}
+
+fn compute_compensation(config: &ModemConfig) -> Result<ComplexMatrix, ConfigError> {
+    let n = config.num_tones;
+    let (re, im) = filter_bank_gram(config);
+    let (inv_re, inv_im) = invert_complex(&re, &im, n)
+        .ok_or(ConfigError::SingularFilterBank { num_tones: n })?;
+
+    // Row k scaled by M_kk (real: the filter's own energy)
+    let scale = |idx: usize| re[(idx / n) * n + idx / n];
+    Ok((
+        inv_re.iter().enumerate().map(|(idx, &v)| v * scale(idx)).collect(),
+        inv_im.iter().enumerate().map(|(idx, &v)| v * scale(idx)).collect(),
+    ))
+}
+
+/// Pivots below this fraction of the largest diagonal entry count as zero:
+/// the inverse would amplify the filter noise by more than 10⁴
+const SINGULAR_PIVOT: f64 = 1e-4;
+
+/// Inverse of a complex matrix via Gauss-Jordan on its real form [[A, -B], [B, A]]
+fn invert_complex(re: &[f64], im: &[f64], n: usize) -> Option<ComplexMatrix> {
+    let m = 2 * n;
+    let mut a = vec![0.0; m * m];
+    let mut inv = vec![0.0; m * m];
+    for i in 0..n {
+        for j in 0..n {
+            a[i * m + j] = re[i * n + j];
+            a[i * m + n + j] = -im[i * n + j];
+            a[(n + i) * m + j] = im[i * n + j];
+            a[(n + i) * m + n + j] = re[i * n + j];
+        }
+    }
+    for i in 0..m {
+        inv[i * m + i] = 1.0;
+    }
+    let tolerance = SINGULAR_PIVOT * (0..m).map(|i| a[i * m + i].abs()).fold(0.0, f64::max);
+
+    for col in 0..m {
+        let pivot = (col..m).max_by(|&x, &y| a[x * m + col].abs().total_cmp(&a[y * m + col].abs()))?;
+        if a[pivot * m + col].abs() < tolerance {
+            return None;
+        }
+        for k in 0..m {
+            a.swap(col * m + k, pivot * m + k);
+            inv.swap(col * m + k, pivot * m + k);
+        }
+
+        let p = a[col * m + col];
+        for k in 0..m {
+            a[col * m + k] /= p;
+            inv[col * m + k] /= p;
+        }
+        for row in (0..m).filter(|&r| r != col) {
+            let f = a[row * m + col];
+            if f != 0.0 {
+                for k in 0..m {
+                    a[row * m + k] -= f * a[col * m + k];
+                    inv[row * m + k] -= f * inv[col * m + k];
+                }
+            }
+        }
+    }
+
+    // Top-left block is Re(M⁻¹), bottom-left is Im(M⁻¹)
+    let mut out_re = vec![0.0; n * n];
+    let mut out_im = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..n {
+            out_re[i * n + j] = inv[i * m + j];
+            out_im[i * n + j] = inv[(n + i) * m + j];
+        }
+    }
+    Some((out_re, out_im))
+}
+
+/// Leakage-compensated correlation of every symbol with its hopping tone
+///
+/// `symbols`: [num_symbols, symbol_len]; `bank`: the demodulator's
+/// conjugated, gain-divided filter bank [num_tones, symbol_len].
+///
+/// Returns [num_symbols]
+pub(crate) fn compensated_correlations<B: Backend>(
+    device: &B::Device,
+    symbols: &Tensor<B, 2>,
+    bank: &ComplexTensor<B, 2>,
+    melody_indices: &[usize],
+    compensation: &ComplexMatrix,
+) -> ComplexTensor<B, 1> {
+    let num_symbols = melody_indices.len();
+    let num_tones = bank.dims()[0];
+
+    // Every symbol against the whole bank: [num_symbols, num_tones]
+    let c = bank.clone().map(|b| symbols.clone().matmul(b.transpose()));
+
+    // ĉ = C · Kᵀ
+    let to_tensor = |m: &[f64]| {
+        let values: Vec<f32> = m.iter().map(|&v| v as f32).collect();
+        Tensor::<B, 1>::from_floats(values.as_slice(), device).reshape([num_tones, num_tones])
+    };
+    let k = ComplexTensor::new(to_tensor(&compensation.0), to_tensor(&compensation.1));
+    let out = c.matmul(k.transpose());
+
+    // Pick each symbol's hopping tone
+    let mut mask = vec![0.0f32; num_symbols * num_tones];
+    for (s, &tone) in melody_indices.iter().enumerate() {
+        mask[s * num_tones + tone] = 1.0;
+    }
+    let mask = Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([num_symbols, num_tones]);
+
+    out.mul_real(mask).map(|t| t.sum_dim(1).reshape([num_symbols]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use crate::modem_rng::{ModemRng, SplitMix64};
+    use crate::modulation::{demodulate_fhdpsk_ex_with_config, modulate_fhdpsk_with_config};
+    use crate::tone_plan::TonePlan;
+    use crate::wavelet::{generate_shaped_tone, generate_symbol_with_config};
+    use crate::TestBackend;
+
+    #[test]
+    fn test_compensation_removes_neighbour_tone() {
+        let config = ModemConfig::default();
+        let n = config.num_tones;
+        let (gram_re, gram_im) = filter_bank_gram(&config);
+        let compensation = leakage_compensation_matrix(&config).expect("singular bank");
+        let (k_re, k_im) = &*compensation;
+
+        // Adjacent tones leak, the diagonal dominates
+        let leak = gram_re[1].hypot(gram_im[1]) / gram_re[0];
+        assert!(leak > 1e-3 && leak < 0.2, "adjacent leakage {}", leak);
+
+        // F (tone 3) at unit amplitude plus the echo of E a semitone below at 4x
+        let a: Vec<(f64, f64)> = (0..n)
+            .map(|j| match j { 3 => (1.0, 0.0), 2 => (0.0, 4.0), _ => (0.0, 0.0) })
+            .collect();
+        let c: Vec<(f64, f64)> = (0..n)
+            .map(|i| (0..n).fold((0.0, 0.0), |(sr, si), j| {
+                let (mr, mi) = (gram_re[i * n + j], gram_im[i * n + j]);
+                (sr + mr * a[j].0 - mi * a[j].1, si + mr * a[j].1 + mi * a[j].0)
+            }))
+            .collect();
+
+        let k = 3;
+        let compensated = (0..n).fold((0.0, 0.0), |(sr, si), i| {
+            let (mr, mi) = (k_re[k * n + i], k_im[k * n + i]);
+            (sr + mr * c[i].0 - mi * c[i].1, si + mr * c[i].1 + mi * c[i].0)
+        });
+
+        // Raw output is pulled off by the neighbour; compensated is M_kk · a_k
+        let diag = gram_re[k * n + k];
+        let raw_err = (c[k].0 - diag).hypot(c[k].1) / diag;
+        let comp_err = (compensated.0 - diag).hypot(compensated.1) / diag;
+        assert!(raw_err > 0.5, "raw error {}", raw_err);
+        assert!(comp_err < 1e-6, "compensated error {}", comp_err);
+
+        // Same design, same matrix
+        assert!(Arc::ptr_eq(&compensation, &leakage_compensation_matrix(&config.clone()).unwrap()));
+    }
+
+    #[test]
+    fn test_singular_bank_is_reported() {
+        // Eight tones within 0.1 Hz: one filter in a 0.1 s window
+        let plan = TonePlan::custom((0..8).map(|i| 1000.0 + 0.01 * i as f64).collect()).unwrap();
+        let config = ModemConfig::default().with_tone_plan(plan);
+        assert!(matches!(leakage_compensation_matrix(&config), Err(ConfigError::SingularFilterBank { num_tones: 8 })));
+        assert!(config.clone().with_leakage_compensation(true).is_err());
+        assert!(config.with_leakage_compensation(false).is_ok());
+    }
+
+    #[test]
+    fn test_demodulator_removes_semitone_echoes() {
+        let device = Default::default();
+        let plain = ModemConfig::default();
+        let compensated = plain.clone().with_leakage_compensation(true).unwrap();
+        let clean = modulate_fhdpsk_with_config::<TestBackend>(&device, b"LEAKAGE", false, &plain);
+        let truth = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &clean, false, &plain);
+
+        // Alone in its window a tone comes through unchanged
+        assert_eq!(demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &clean, false, &compensated), truth);
+
+        // Every symbol on E, F, B or C gets an echo of its semitone neighbour
+        // at 4x the symbol amplitude with a random phase (-9 dB leakage)
+        let symbol_len = plain.symbol_samples();
+        let num_symbols = clean.dims()[0] / symbol_len;
+        let peak = |t: Tensor<TestBackend, 1>| -> f32 { t.abs().max().into_scalar() };
+        let scale = peak(clean.clone()) as f64 / peak(generate_symbol_with_config::<TestBackend>(&device, 0, 0.0, &plain)) as f64;
+        let freqs = plain.frequencies();
+        let mut rng = SplitMix64::new(18);
+        let echoes: Vec<Tensor<TestBackend, 1>> = plain.melody_indices(num_symbols).iter()
+            .map(|&tone| match tone {
+                2 | 6 | 9 | 13 => Some(tone + 1),
+                3 | 7 | 10 | 14 => Some(tone - 1),
+                _ => None,
+            }.map_or_else(
+                || Tensor::zeros([symbol_len], &device),
+                |echo| generate_shaped_tone::<TestBackend>(&device, freqs[echo], 2.0 * PI * rng.uniform(), 4.0 * scale * plain.tone_gain(echo),
+                                                          plain.symbol_duration, plain.wavelet_shape(), FS),
+            ))
+            .collect();
+        let rx = clean.clone().slice([0..num_symbols * symbol_len]) + Tensor::cat(echoes, 0);
+
+        let errors = |config: &ModemConfig| {
+            let bits = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &rx, false, config);
+            bits.iter().zip(&truth).filter(|(a, b)| a != b).count()
+        };
+        assert!(errors(&plain) > 0);
+        assert_eq!(errors(&compensated), 0);
+    }
+}
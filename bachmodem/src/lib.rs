@@ -6,33 +6,242 @@
 //! 
 //! Based on WaveletsJAX Python/JAX implementation, achieving -30 dB SNR
 //! communication over HF-Watterson channels.
+//! 
+//! Cargo features:
+//! - `wgpu` / `cuda` / `ndarray`: Burn backends
+//! - `cubecl`: long-duration soak harness with CubeCL device memory probes
+//!   (`soak`; enabled by `wgpu` and `cuda`)
+//! - `wav`: WAV file I/O (hound)
+//! - `mmap`: memory-mapped WAV archives scanned and decoded window by window (`wav_mmap`)
+//! - `channel-sim`: Watterson HF channel simulator, jammer analysis, seeded
+//!   `SimSeed` streams (rand_chacha)
+//! - `export`: `.safetensors` export of the reference waveforms
+//! - `autodiff`: Burn autodiff backend for the differentiable modem (`differentiable`)
+//! - `audio`: soundcard enumeration and selection, full-duplex bench sessions and
+//!   virtual-cable loopback runs (cpal)
+//! - `dataset`: Burn `Dataset` impl for the simulated channel datasets (`dataset`)
+//! - `async`: tokio wrappers for decode and monitor operations (`async_ops`)
+//! - `http`: HTTP/WebSocket status and control API for headless monitors (`http_api`)
+//! - `reporter`: HTTP spot uploads to reporting networks (`reporter::HttpSpotSink`)
+//! - `mqtt`: MQTT publishing of decodes, SNR reports and spots (`mqtt::MqttPublisher`)
+//! 
+//! With `--no-default-features --features ndarray` only the DSP/FEC core is built.
+//! 
+//! The non-tensor receive path (bits, interleaver, CRC, polar SC, frame
+//! parsing, scalar and Q15 matched filters) lives in the no_std `bachmodem-core` crate.
 
 pub mod wavelet;
 pub mod modulation;
+pub mod reference_blocks;
+pub mod dpsk;
+pub mod mfsk;
+pub mod chords;
+pub mod link_adaptation;
+pub mod negotiation;
+pub mod llr_quant;
+pub mod observer;
+pub mod sync_lock;
+pub mod afc;
+pub mod clock_drift;
+pub mod timing_recovery;
+#[cfg(feature = "wav")]
 pub mod wav;
+#[cfg(feature = "mmap")]
+pub mod wav_mmap;
+#[cfg(feature = "channel-sim")]
 pub mod watterson;
+#[cfg(feature = "channel-sim")]
+pub mod jammer;
 pub mod repetition;
+pub mod slot_jitter;
+pub mod slot_schedule;
+pub mod modem_rng;
+pub mod rf_hop;
+pub mod spot;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod reporter;
 pub mod interleaver;
 pub mod polar;
 pub mod polar_bp;
+pub mod polar_scl_gpu;
 pub mod rake;
 pub mod gpu_ops;
 pub mod deinterleave_gpu;
 pub mod gpu_test_utils;
 pub mod gpu_math;
+pub mod complex;
 pub mod fft_correlation;
+pub mod config;
+pub mod tone_mapping;
+pub mod tone_plan;
+pub mod leakage;
+pub mod partial_band;
+pub mod chirp;
+pub mod sync_ambiguity;
+pub mod coarse_sync;
+pub mod offset_sync;
+pub mod spectral_mask;
+pub mod spectrogram;
+pub mod presence;
+pub mod zoom_fft;
+pub mod dropout;
+pub mod noise_floor;
+pub mod input_health;
+pub mod audio;
+pub mod duplex;
+pub mod hil;
+pub mod transmitter;
+pub mod cw_id;
+pub mod sounder;
+pub mod export;
+#[cfg(feature = "cubecl")]
+pub mod soak;
+pub mod differentiable;
+pub mod enhancer;
+pub mod llr_calibrator;
+pub mod receiver_state;
+pub mod front_end;
+pub mod noise_calibration;
+pub mod tuning;
+pub mod receiver_pool;
+pub mod decision_trace;
+pub mod retry_ladder;
+pub mod late_acquisition;
+pub mod self_similarity;
+pub mod consolidation;
+pub mod hop_search;
+pub mod skimmer;
+#[cfg(feature = "async")]
+pub mod async_ops;
+#[cfg(feature = "http")]
+pub mod http_api;
+#[cfg(feature = "channel-sim")]
+pub mod dataset;
+#[cfg(feature = "channel-sim")]
+pub mod network_sim;
+#[cfg(feature = "channel-sim")]
+pub mod autotune;
+pub mod tx_level;
 
-pub use wavelet::{BACH_FREQUENCIES, HOPPING_PATTERN, FS, SYMBOL_DURATION, generate_bach_flourish};
-pub use modulation::{modulate_fhdpsk, modulate_fhdpsk_with_flourishes, demodulate_fhdpsk, demodulate_fhdpsk_ex, demodulate_fhdpsk_soft, synchronize_signal, synchronize_signal_gpu, encode_bits, pack_bits};
+pub use wavelet::{BACH_FREQUENCIES, BACH_FREQUENCIES_8, BACH_FREQUENCIES_32, HOPPING_PATTERN, FS, get_hopping_indices, seeded_hopping_pattern, hopping_cross_correlation, SYMBOL_DURATION, DEFAULT_WAVELET_SIGMAS, WaveletShape, generate_bach_flourish, preamble_note_phases, preamble_tone_sequence, matched_filter_bank};
+pub use config::{ModemConfig, ConfigError, PROFILE_NAMES, ROBUST_RS_PARITY, DOPPLER_CHIRP_SPAN_HZ, LOWBAND_MAX_FREQUENCY_HZ, CHAT_SYMBOL_DURATION, CHAT_NOTE_DURATION};
+pub use tone_mapping::{ToneMapping, gray_encode, gray_decode, tone_llrs};
+pub use tone_plan::{TonePlan, TonePlanError, MAJOR_STEPS, NATURAL_MINOR_STEPS, CHROMATIC_STEPS};
+pub use leakage::{filter_bank_gram, leakage_compensation_matrix};
+pub use partial_band::{MISSING_TONE_DB, preamble_tone_levels, missing_tones, detect_missing_tones};
+pub use chirp::{estimate_chirp_offset, chirp_peak_shift, CHIRP_OFFSET_RANGE};
+pub use sync_ambiguity::{resolve_sync_ambiguity, reference_block_quality, preamble_cycle_samples, SYNC_AMBIGUITY_RATIO, REFERENCE_QUALITY_RATIO};
+pub use coarse_sync::{DecimationPlan, CoarsePeak, decimate_to_baseband, coarse_sync, COARSE_BLOCK, MAX_DECIMATION, FINE_SEARCH_STEPS};
+pub use offset_sync::{OffsetSyncMetrics, offset_correlation_gpu, measure_sync_with_offsets, synchronize_data_start_with_offsets, DEFAULT_OFFSET_RANGE_HZ, DEFAULT_OFFSET_STEP_HZ};
+pub use modulation::{modulate_fhdpsk, modulate_fhdpsk_with_flourishes, modulate_fhdpsk_with_config, demodulate_fhdpsk, demodulate_fhdpsk_ex, demodulate_fhdpsk_ex_with_config, demodulate_fhdpsk_soft, demodulate_fhdpsk_soft_with_config, demodulate_fhdpsk_soft_erasures_with_config, demodulate_fhdpsk_soft_enhanced_with_config, demodulate_fhdpsk_stats_with_config, DemodStatistics, synchronize_signal, synchronize_signal_with_config, synchronize_data_start_with_config, synchronize_signal_with_thresholds, synchronize_data_start_with_thresholds, SyncThresholds, SyncConfig, SyncMetrics, SyncResult, DEFAULT_SYNC_CANDIDATES, measure_sync, StreamingDemodulator, StreamEvent, synchronize_signal_gpu, measure_flourish_offset, encode_bits, pack_bits};
+#[cfg(feature = "wav")]
 pub use wav::{write_wav, read_wav, prepare_wav_signal_gpu};
-pub use watterson::WattersonChannel;
-pub use repetition::{TimeSlotConfig, generate_repetition_transmission, CombiningStrategy, DecodedCopy, combine_decoded_copies};
+#[cfg(feature = "mmap")]
+pub use wav_mmap::{MappedWav, WavFormat, WavMapError};
+#[cfg(feature = "channel-sim")]
+pub use watterson::{WattersonChannel, FadingTaps, FrequencyResponse};
+#[cfg(feature = "channel-sim")]
+pub use jammer::{HopMode, JammerStrategy, JammingReport, simulate_jamming, jamming_matrix};
+pub use repetition::{TimeSlotConfig, generate_repetition_transmission, generate_repetition_transmission_with_config, CombiningStrategy, DecodedCopy, combine_decoded_copies, InterleavedSchedule, StreamAccumulator, generate_interleaved_transmission, generate_interleaved_transmission_with_config, SLOT_FLOURISH_INTERVAL, OWNER_HEADER_LEN};
+pub use slot_jitter::{SlotJitter, CollisionStats, simulate_slot_collisions};
+pub use slot_schedule::{ScheduleHeader, ScheduleError, split_schedule_header, generate_signalled_transmission, generate_signalled_transmission_with_config, SCHEDULE_HEADER_LEN, SCHEDULE_GAP_STEP};
+pub use modem_rng::{ModemRng, SplitMix64, gaussian_noise, splitmix64};
+#[cfg(feature = "channel-sim")]
+pub use modem_rng::{RngStream, SimSeed, ChaCha20Rng};
+pub use rf_hop::{RfHopPlan, Retune, ChannelScanner, ScanState, RigCtl, RigError};
+pub use spot::{Spot, is_callsign, is_locator, SPOT_MODE};
+pub use reporter::{SpotReporter, ReporterConfig, ReporterStats, ReceiverInfo, SpotSink, UploadError};
+#[cfg(feature = "reporter")]
+pub use reporter::HttpSpotSink;
+#[cfg(feature = "mqtt")]
+pub use mqtt::{MqttConfig, MqttPublisher, MqttError, DecodeReport, SnrReport, MQTT_DEFAULT_PORT};
 pub use interleaver::{interleave, deinterleave};
 pub use polar::{PolarCode, soft_bits_to_llrs, compute_soft_bits, crc8, encode_with_crc, verify_crc};
 pub use polar_bp::PolarCodeBP;
+pub use polar_scl_gpu::PolarCodeSCL;
 pub use rake::{RakeReceiver, RakeFinger, estimate_rake_gain};
-pub use gpu_ops::{cross_correlation_gpu, soft_combine_gpu, coherent_combine_symbols, estimate_snr_from_correlation, estimate_snr_from_correlation_gpu};
-pub use deinterleave_gpu::{deinterleave_gpu, interleave_gpu};
+pub use observer::Observer;
+pub use sync_lock::{SyncLock, SyncLockConfig, SyncLockEvent, SyncLockUpdate, LockState, synchronize_data_start_tracked};
+pub use afc::{estimate_frequency_offset, correct_frequency_offset, spectral_frequency_offset, preamble_frequency_offset, DEFAULT_AFC_RANGE_HZ};
+pub use clock_drift::{ClockDriftTracker, measure_postamble_drift, MAX_CLOCK_DRIFT_PPM};
+pub use timing_recovery::{TimingLoop, timing_error, recover_symbol_timing, interpolate_symbols, TIMING_PROBE_FRACTION, DEFAULT_TIMING_LOOP_GAIN};
+pub use llr_quant::{QuantizedLlrCombiner, QuantizedSlot, QUANT_CLIP_PERCENTILE};
+pub use gpu_ops::{cross_correlation_gpu, soft_combine_gpu, coherent_combine_symbols, estimate_snr_from_correlation, estimate_snr_from_correlation_gpu, estimate_snr_from_correlation_batch_gpu, mrc_weights_from_snr_db_gpu, mrc_combine_gpu, histogram_gpu, percentile_gpu, median_gpu, power_percentile_gpu, topk_gpu, topk_separated_gpu, PERCENTILE_BINS};
+pub use deinterleave_gpu::{deinterleave_gpu, interleave_gpu, deinterleave_auto, interleave_auto, InterleaveDispatch, InterleavePath, Llrs};
 pub use gpu_test_utils::{assert_approx_eq_gpu, assert_approx_eq_scalar, validate_roundtrip, assert_normalized};
 pub use gpu_math::{atan2_fast_gpu};
+pub use complex::{ComplexTensor, shift_frequency};
 pub use fft_correlation::{fft_cross_correlation, cross_correlation_fft, FftBackend};
+pub use spectral_mask::{PowerSpectrum, SpectralMask, MaskReport, power_spectrum_gpu};
+pub use spectrogram::{Spectrogram, spectrogram_gpu};
+pub use presence::{PresenceConfig, PresenceBin, PresenceTimeline, presence_timeline};
+#[cfg(feature = "png")]
+pub use presence::{PNG_ROW_BINS, PNG_CELL_PIXELS};
+#[cfg(feature = "mmap")]
+pub use presence::presence_timeline_mapped;
+pub use zoom_fft::{ZoomSpectrum, zoom_spectrum_gpu, zoom_around_gpu};
+pub use reference_blocks::{ReferenceLayout, DifferentialPairs};
+pub use dpsk::DpskOrder;
+pub use mfsk::{modulate_mfsk, demodulate_mfsk_soft, mfsk_tones, mfsk_num_symbols};
+pub use chords::{ChordConfig, ChordError, modulate_chords, demodulate_chords_soft, papr_db};
+pub use link_adaptation::{LinkAdaptation, LinkMessage, LinkMessageError, RateMode, RateTable, measure_link_snr, LINK_MESSAGE_LEN};
+pub use negotiation::{Capabilities, NegotiationMessage, NegotiationMessageError, NegotiationRole, NegotiationError, NegotiationState, Outgoing, ProfileNegotiator};
+pub use dropout::{Dropout, detect_dropouts_gpu, group_dropouts, llr_erasure_mask, null_dropout_llrs, DROPOUT_THRESHOLD};
+pub use noise_floor::{NoiseFloorTracker, NoiseFloorConfig, NoiseFloorSnapshot, NoiseFloorRecord, SNR_REFERENCE_BANDWIDTH};
+pub use input_health::{InputHealthMonitor, InputHealthConfig, InputHealthSnapshot, InputHealthRecord, HealthAlert};
+pub use audio::{AudioSettings, DEFAULT_TX_LEVEL, AudioDeviceInfo, AudioError, FormatRange, Direction, match_device_name};
+#[cfg(feature = "audio")]
+pub use audio::{list_devices, open_device};
+pub use duplex::{TimestampCorrelator, TxRecord, LinkMatch, StreamingResampler, resample_linear};
+#[cfg(feature = "audio")]
+pub use duplex::DuplexSession;
+pub use hil::{LoopbackPlan, LoopbackReport, DEFAULT_HIL_GAP, DEFAULT_HIL_POLL_INTERVAL};
+#[cfg(feature = "audio")]
+pub use hil::run_loopback;
+pub use tx_level::{LevelCalConfig, LevelPoint, LevelCalibration, generate_level_steps, measure_level_steps, analyze_linearity};
+#[cfg(feature = "audio")]
+pub use tx_level::calibrate_tx_level;
+pub use transmitter::{BachTransmitter, TransmitterError};
+pub use cw_id::{CwIdConfig, CwIdError, CwIdPlacement, add_cw_id, notch_cw_id_gpu, DEFAULT_CW_ID_TONE_HZ};
+pub use sounder::{SounderConfig, SoundingMeasurement, generate_sounding, measure_channel_gpu, write_sounding_csv};
+pub use export::{ReferenceSet, ReferenceArray, ReferenceData};
+#[cfg(feature = "cubecl")]
+pub use soak::{SoakConfig, SoakReport, SoakViolation, WatermarkMonitor, MemoryProbe, MemorySample, ProcessMemory, CubeMemory, run_soak};
+pub use differentiable::{DiffModemParams, DiffLearningRates, wavelet_bank_diff, modulate_diff, soft_demodulate_diff, ber_surrogate_loss, diff_num_symbols};
+pub use enhancer::{SignalEnhancer, NoEnhancer, ConvDenoiser, ConvDenoiserConfig};
+#[cfg(feature = "channel-sim")]
+pub use enhancer::denoiser_batch;
+pub use llr_calibrator::{LlrCalibrator, LlrCalibratorConfig, LlrMapping, calibrator_features, llr_calibration_loss, CALIBRATOR_FEATURES};
+pub use receiver_state::{ReceiverState, ReceiverStateRecord};
+pub use front_end::{FrontEnd, FrontEndReport, HumComb, notch_filter, DEFAULT_NOTCH_WIDTH_HZ, DEFAULT_HUM_HARMONICS, DEFAULT_HUM_WIDTH_HZ};
+pub use noise_calibration::{NoiseCalibration, NoiseCalibrationConfig, HumLine, analyze_noise};
+pub use receiver_pool::{ReceiverPool, ReceiverPoolConfig, DecodeSource, DecodeResult, DecodedFrame, DecodeError, CaptureLlrs, decode_capture, capture_llrs, decode_llrs, front_end_report};
+pub use consolidation::{MessageConsolidator, ConsolidatedMessage, Vote, ByteVote, vote_bytes, DEFAULT_MIN_AGREEMENT};
+pub use hop_search::{HopCostConfig, HopCost, AnnealConfig, AnnealResult, hopping_pattern_cost, anneal_hopping_pattern, INTERVAL_DISSONANCE, CONSONANT_INTERVALS};
+pub use retry_ladder::{RetryLadder, RetryRung, RetryDecode, decode_with_retries, codeword_agreement, offset_search, MIN_CODEWORD_AGREEMENT};
+pub use late_acquisition::{LateStart, LateAnchor, late_start_candidates, demodulate_late_stats_with_config, LATE_TIMING_STEPS, LATE_SEARCH_BLOCKS, LATE_MIN_PURITY_GAIN};
+pub use self_similarity::{RepetitionStride, self_similarity_gpu, detect_repetition_stride, stack_repetitions, blind_stack, MIN_STRIDE_SCORE};
+pub use tuning::{ReceiverTuning, ProfileError, RAKE_MAX_DELAY};
+pub use decision_trace::{DecisionTrace, TraceDivergence, TraceError, decode_capture_traced, first_divergence};
+pub use skimmer::{SyncPeak, SkimmedFrame, find_preamble_peaks, find_all_preambles, skim, MULTI_SYNC_MIN_SCORE};
+#[cfg(feature = "async")]
+pub use async_ops::{AsyncReceiver, DecodeProgress, CaptureBlock, MonitorUpdate, decode_batch, monitor};
+#[cfg(feature = "http")]
+pub use http_api::{DaemonControl, DaemonStatus, DaemonEvent, DecodeLogEntry, QueuedTransmission, ControlError, DECODE_LOG_LEN};
+#[cfg(feature = "channel-sim")]
+pub use llr_calibrator::calibration_frame;
+#[cfg(feature = "channel-sim")]
+pub use dataset::{DatasetConfig, DatasetGenerator, ChannelDataset, ChannelExample, SimChannel, ShardFormat, write_dataset_shards};
+#[cfg(feature = "channel-sim")]
+pub use network_sim::{NetworkScenario, VirtualStation, NetworkReport, StationOutcome, FADED_TONE_DB};
+#[cfg(feature = "channel-sim")]
+pub use autotune::{LabelledCapture, TuningScore, TuningResult, TuningGrid, TuningBounds, DeConfig, simulate_corpus, evaluate_tuning, grid_search, differential_evolution};
+#[cfg(all(feature = "channel-sim", feature = "wav"))]
+pub use autotune::{load_corpus, write_corpus};
+pub use bachmodem_core::{shaped_wavelet_f32, ReedSolomon, RsError, OuterCode, OuterDecode, whitening_sequence, scramble_bits, descramble_llrs, ScalarDemodulator, Q15Demodulator, encode_frame, encode_frame_with_code, encode_frame_with_version, decode_frame, decode_frame_with_code, parse_frame, FrameCode, FrameError, WIRE_FORMAT_VERSION, SUPPORTED_WIRE_VERSIONS, negotiate_version, LlrContribution, LlrExchangeError, merge_contributions};
+
+/// Backend of the unit tests: the raw CubeBackend, as the Fusion wrapper
+/// doesn't implement `FftBackend`
+#[cfg(test)]
+pub(crate) type TestBackend = burn::backend::wgpu::CubeBackend<burn::backend::wgpu::WgpuRuntime, f32, i32, u32>;
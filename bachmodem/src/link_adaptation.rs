@@ -0,0 +1,465 @@
+/// Link Adaptation
+///
+/// A long QSO shouldn't stay at the rate its first, worst-case exchange
+/// needed. The receiver measures the preamble's correlation SNR of every
+/// transmission (`measure_link_snr`, built on `estimate_snr_from_correlation`)
+/// and `LinkAdaptation` picks a mode from a `RateTable`, slowest first:
+/// symbol duration, differential order, inner and outer code, repetitions.
+///
+/// Both ends hold the same table and agree on the active mode with two
+/// messages, sent as the payload of ordinary frames:
+/// - `Request { mode, snr_db }`: the receiver asks for a mode and reports
+///   the SNR it measured
+/// - `Ack { mode }`: the transmitter switched; the receiver follows
+///
+/// Switching up is cautious, switching down is not: the smoothed SNR must
+/// clear the next mode's threshold by `hysteresis_db`, and the link climbs
+/// one mode per request. A fade drops straight to the fastest mode the
+/// latest measurement supports. Until the `Ack` arrives both ends keep the
+/// old mode, so a lost request costs nothing but the upgrade.
+///
+/// Thresholds are in the estimator's own units (preamble peak power over
+/// the mean off-peak correlation power), not channel SNR in 2.5 kHz. Noise
+/// alone peaks around 14 dB.
+
+use std::fmt;
+use burn::tensor::{Tensor, ElementConversion, backend::Backend};
+use crate::config::{ModemConfig, ROBUST_RS_PARITY};
+use crate::dpsk::DpskOrder;
+use crate::fft_correlation::FftBackend;
+use crate::gpu_ops::estimate_snr_from_correlation;
+use crate::modulation::synchronize_signal_gpu;
+use crate::wavelet::{generate_bach_preamble_with_config, SYMBOL_DURATION};
+use bachmodem_core::frame::{FrameCode, CODE_K, CODE_N};
+
+/// First byte of every link message
+pub const LINK_MESSAGE_TAG: u8 = b'L';
+
+/// Link message format version
+pub const LINK_MESSAGE_VERSION: u8 = 1;
+
+/// Encoded link message size (bytes)
+pub const LINK_MESSAGE_LEN: usize = 5;
+
+/// Default margin a mode's threshold must be cleared by to switch up (dB)
+pub const DEFAULT_HYSTERESIS_DB: f32 = 3.0;
+
+/// Default weight of the newest measurement in the smoothed SNR
+pub const DEFAULT_SNR_SMOOTHING: f32 = 0.5;
+
+/// One entry of the rate table
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateMode {
+    pub name: &'static str,
+
+    /// Data symbol duration (seconds)
+    pub symbol_duration: f64,
+
+    /// Differential phase points per data symbol
+    pub dpsk_order: DpskOrder,
+
+    /// Inner code of a frame
+    pub frame_code: FrameCode,
+
+    /// Reed-Solomon parity bytes of the outer code, 0 = none
+    pub rs_parity: usize,
+
+    /// Transmissions of every frame (`TimeSlotConfig` slots; not part of
+    /// `ModemConfig`, the caller schedules them)
+    pub repetitions: usize,
+
+    /// Lowest correlation SNR the mode is used at (dB)
+    pub min_snr_db: f32,
+}
+
+impl RateMode {
+    /// `base` with this mode's rate settings
+    pub fn apply(&self, base: &ModemConfig) -> ModemConfig {
+        ModemConfig { symbol_duration: self.symbol_duration, ..base.clone() }
+            .with_dpsk_order(self.dpsk_order)
+            .with_frame_code(self.frame_code)
+            .with_outer_code(self.rs_parity)
+    }
+
+    /// Information bits per second of data airtime
+    ///
+    /// Ignores preamble, reference blocks and flourishes, which every mode
+    /// pays alike.
+    pub fn info_bit_rate(&self) -> f64 {
+        let outer = (255 - self.rs_parity) as f64 / 255.0;
+        let inner = CODE_K as f64 / CODE_N as f64;
+        self.dpsk_order.bits_per_symbol() as f64 / self.symbol_duration * inner * outer / self.repetitions as f64
+    }
+}
+
+/// Modes to choose from, slowest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateTable {
+    pub modes: Vec<RateMode>,
+}
+
+impl Default for RateTable {
+    /// Five modes spanning 50x in information rate
+    fn default() -> Self {
+        Self {
+            modes: vec![
+                RateMode {
+                    name: "crawl",
+                    symbol_duration: 4.0 * SYMBOL_DURATION,
+                    dpsk_order: DpskOrder::Binary,
+                    frame_code: FrameCode::Polar,
+                    rs_parity: ROBUST_RS_PARITY,
+                    repetitions: 3,
+                    min_snr_db: f32::NEG_INFINITY,
+                },
+                RateMode {
+                    name: "robust",
+                    symbol_duration: SYMBOL_DURATION,
+                    dpsk_order: DpskOrder::Binary,
+                    frame_code: FrameCode::Polar,
+                    rs_parity: ROBUST_RS_PARITY,
+                    repetitions: 2,
+                    min_snr_db: 17.0,
+                },
+                RateMode {
+                    name: "standard",
+                    symbol_duration: SYMBOL_DURATION,
+                    dpsk_order: DpskOrder::Binary,
+                    frame_code: FrameCode::Polar,
+                    rs_parity: 0,
+                    repetitions: 1,
+                    min_snr_db: 22.0,
+                },
+                RateMode {
+                    name: "fast",
+                    symbol_duration: SYMBOL_DURATION,
+                    dpsk_order: DpskOrder::Quaternary,
+                    frame_code: FrameCode::Polar,
+                    rs_parity: 0,
+                    repetitions: 1,
+                    min_snr_db: 28.0,
+                },
+                RateMode {
+                    name: "sprint",
+                    symbol_duration: SYMBOL_DURATION / 2.0,
+                    dpsk_order: DpskOrder::Quaternary,
+                    frame_code: FrameCode::Convolutional,
+                    rs_parity: 0,
+                    repetitions: 1,
+                    min_snr_db: 34.0,
+                },
+            ],
+        }
+    }
+}
+
+impl RateTable {
+    /// Fastest mode whose threshold `snr_db` reaches (mode 0 if none does)
+    pub fn best_mode(&self, snr_db: f32) -> usize {
+        self.modes.iter().rposition(|m| m.min_snr_db <= snr_db).unwrap_or(0)
+    }
+}
+
+/// Negotiation message exchanged between the two ends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkMessage {
+    /// Receiver -> transmitter: switch to `mode`; `snr_db` as measured
+    Request { mode: u8, snr_db: i8 },
+
+    /// Transmitter -> receiver: now sending in `mode`
+    Ack { mode: u8 },
+}
+
+/// Bytes that are not a valid link message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkMessageError {
+    /// Fewer than `LINK_MESSAGE_LEN` bytes
+    TooShort(usize),
+
+    /// First byte is not `LINK_MESSAGE_TAG`
+    NotLinkMessage,
+
+    /// Version byte this build doesn't read
+    UnsupportedVersion(u8),
+
+    /// Unknown message kind
+    UnknownKind(u8),
+
+    /// Mode index outside the rate table
+    UnknownMode(u8),
+}
+
+impl fmt::Display for LinkMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkMessageError::TooShort(len) => write!(f, "link message too short ({} bytes)", len),
+            LinkMessageError::NotLinkMessage => write!(f, "not a link message"),
+            LinkMessageError::UnsupportedVersion(v) => write!(f, "unsupported link message version {}", v),
+            LinkMessageError::UnknownKind(k) => write!(f, "unknown link message kind {}", k),
+            LinkMessageError::UnknownMode(m) => write!(f, "mode {} not in the rate table", m),
+        }
+    }
+}
+
+impl std::error::Error for LinkMessageError {}
+
+const KIND_REQUEST: u8 = 0;
+const KIND_ACK: u8 = 1;
+
+impl LinkMessage {
+    /// Wire format: tag, version, kind, mode, SNR (dB, two's complement;
+    /// 0 for `Ack`)
+    pub fn encode(&self) -> [u8; LINK_MESSAGE_LEN] {
+        let (kind, mode, snr) = match *self {
+            LinkMessage::Request { mode, snr_db } => (KIND_REQUEST, mode, snr_db as u8),
+            LinkMessage::Ack { mode } => (KIND_ACK, mode, 0),
+        };
+        [LINK_MESSAGE_TAG, LINK_MESSAGE_VERSION, kind, mode, snr]
+    }
+
+    /// Parse the leading bytes of a payload (zero padding after the message is ignored)
+    pub fn parse(bytes: &[u8]) -> Result<Self, LinkMessageError> {
+        if bytes.len() < LINK_MESSAGE_LEN {
+            return Err(LinkMessageError::TooShort(bytes.len()));
+        }
+        if bytes[0] != LINK_MESSAGE_TAG {
+            return Err(LinkMessageError::NotLinkMessage);
+        }
+        if bytes[1] != LINK_MESSAGE_VERSION {
+            return Err(LinkMessageError::UnsupportedVersion(bytes[1]));
+        }
+        match bytes[2] {
+            KIND_REQUEST => Ok(LinkMessage::Request { mode: bytes[3], snr_db: bytes[4] as i8 }),
+            KIND_ACK => Ok(LinkMessage::Ack { mode: bytes[3] }),
+            kind => Err(LinkMessageError::UnknownKind(kind)),
+        }
+    }
+}
+
+/// Rate negotiation state of one end of a link
+#[derive(Clone, Debug)]
+pub struct LinkAdaptation {
+    pub table: RateTable,
+
+    /// Margin above the next mode's threshold before switching up (dB)
+    pub hysteresis_db: f32,
+
+    /// Weight of the newest measurement in the smoothed SNR (0, 1]
+    pub smoothing: f32,
+
+    current: usize,
+    requested: Option<usize>,
+    snr_db: Option<f32>,
+}
+
+impl Default for LinkAdaptation {
+    fn default() -> Self {
+        Self::new(RateTable::default())
+    }
+}
+
+impl LinkAdaptation {
+    /// Start in the slowest mode of `table`
+    pub fn new(table: RateTable) -> Self {
+        assert!(!table.modes.is_empty(), "Rate table needs at least one mode");
+        assert!(table.modes.len() <= 256, "Mode indices are sent as one byte");
+        Self {
+            table,
+            hysteresis_db: DEFAULT_HYSTERESIS_DB,
+            smoothing: DEFAULT_SNR_SMOOTHING,
+            current: 0,
+            requested: None,
+            snr_db: None,
+        }
+    }
+
+    /// Set the switch-up margin (dB)
+    pub fn with_hysteresis(mut self, hysteresis_db: f32) -> Self {
+        assert!(hysteresis_db >= 0.0, "Hysteresis must not be negative");
+        self.hysteresis_db = hysteresis_db;
+        self
+    }
+
+    /// Set the weight of the newest measurement in the smoothed SNR
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        assert!(smoothing > 0.0 && smoothing <= 1.0, "Smoothing must be in (0, 1]");
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Index of the mode both ends agreed on
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// The mode both ends agreed on
+    pub fn mode(&self) -> &RateMode {
+        &self.table.modes[self.current]
+    }
+
+    /// `base` configured for the agreed mode
+    pub fn config(&self, base: &ModemConfig) -> ModemConfig {
+        self.mode().apply(base)
+    }
+
+    /// Smoothed SNR of the measurements so far (dB)
+    pub fn smoothed_snr_db(&self) -> Option<f32> {
+        self.snr_db
+    }
+
+    /// Mode the measurements call for, one step up at most
+    fn target(&self, latest_db: f32, smoothed_db: f32) -> usize {
+        let down = self.table.best_mode(latest_db.min(smoothed_db));
+        if down < self.current {
+            return down;
+        }
+        match self.table.modes.get(self.current + 1) {
+            Some(next) if smoothed_db >= next.min_snr_db + self.hysteresis_db => self.current + 1,
+            _ => self.current,
+        }
+    }
+
+    /// Receiver: record the SNR of a transmission and request a mode change
+    /// if it calls for one
+    ///
+    /// Returns `None` if the current mode fits or the same change is
+    /// already waiting for its `Ack`.
+    pub fn observe(&mut self, snr_db: f32) -> Option<LinkMessage> {
+        let smoothed = match self.snr_db {
+            Some(previous) => previous + self.smoothing * (snr_db - previous),
+            None => snr_db,
+        };
+        self.snr_db = Some(smoothed);
+
+        let target = self.target(snr_db, smoothed);
+        if target == self.current {
+            self.requested = None;
+            return None;
+        }
+        if self.requested == Some(target) {
+            return None;
+        }
+        self.requested = Some(target);
+        Some(LinkMessage::Request {
+            mode: target as u8,
+            snr_db: snr_db.round().clamp(i8::MIN as f32, i8::MAX as f32) as i8,
+        })
+    }
+
+    /// Handle a message from the other end; returns the reply to send
+    ///
+    /// A `Request` switches the transmitter and is answered with an `Ack`;
+    /// an `Ack` switches the receiver.
+    pub fn handle(&mut self, message: &LinkMessage) -> Result<Option<LinkMessage>, LinkMessageError> {
+        match *message {
+            LinkMessage::Request { mode, .. } => {
+                self.switch_to(mode)?;
+                Ok(Some(LinkMessage::Ack { mode }))
+            }
+            LinkMessage::Ack { mode } => {
+                self.switch_to(mode)?;
+                self.requested = None;
+                Ok(None)
+            }
+        }
+    }
+
+    fn switch_to(&mut self, mode: u8) -> Result<(), LinkMessageError> {
+        if mode as usize >= self.table.modes.len() {
+            return Err(LinkMessageError::UnknownMode(mode));
+        }
+        self.current = mode as usize;
+        Ok(())
+    }
+}
+
+/// Correlation SNR of the strongest preamble in `signal` (dB)
+///
+/// Noise is taken outside ± one preamble length of the peak, so the
+/// preamble's own sweep sidelobes don't count against it. Returns `None` if
+/// the signal is shorter than the preamble.
+///
+/// ⚠️ **SYNC POINT**: Downloads the peak position and the SNR
+pub fn measure_link_snr<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<f32> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let preamble_len = preamble.dims()[0];
+    if signal.dims()[0] < preamble_len {
+        return None;
+    }
+    let (correlations, peak, _) = synchronize_signal_gpu::<B>(device, signal, &preamble);
+    let peak_idx = peak.into_scalar().elem::<i64>() as usize;
+    Some(estimate_snr_from_correlation(&correlations, peak_idx, preamble_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_message_roundtrip_and_errors() {
+        for message in [LinkMessage::Request { mode: 3, snr_db: -12 }, LinkMessage::Ack { mode: 1 }] {
+            let mut payload = message.encode().to_vec();
+            payload.resize(16, 0);
+            assert_eq!(LinkMessage::parse(&payload), Ok(message));
+        }
+        assert_eq!(LinkMessage::parse(b"L\x01"), Err(LinkMessageError::TooShort(2)));
+        assert_eq!(LinkMessage::parse(b"X\x01\x00\x00\x00"), Err(LinkMessageError::NotLinkMessage));
+        assert_eq!(LinkMessage::parse(b"L\x02\x00\x00\x00"), Err(LinkMessageError::UnsupportedVersion(2)));
+        assert_eq!(LinkMessage::parse(b"L\x01\x07\x00\x00"), Err(LinkMessageError::UnknownKind(7)));
+    }
+
+    #[test]
+    fn test_negotiation_climbs_with_hysteresis_and_drops_at_once() {
+        let mut rx = LinkAdaptation::default().with_smoothing(1.0);
+        let mut tx = LinkAdaptation::default();
+        let rates: Vec<f64> = rx.table.modes.iter().map(|m| m.info_bit_rate()).collect();
+        assert!(rates.windows(2).all(|w| w[0] < w[1]), "{:?}", rates);
+
+        // Just above "robust" but inside the hysteresis: stay
+        assert_eq!(rx.observe(18.0), None);
+
+        // Excellent conditions: one mode per request
+        let request = rx.observe(40.0).unwrap();
+        assert_eq!(request, LinkMessage::Request { mode: 1, snr_db: 40 });
+        assert_eq!(rx.observe(40.0), None, "same request is pending");
+        let ack = tx.handle(&LinkMessage::parse(&request.encode()).unwrap()).unwrap().unwrap();
+        assert_eq!(tx.current(), 1);
+        assert_eq!(rx.handle(&ack).unwrap(), None);
+        assert_eq!(rx.current(), 1);
+        assert_eq!(rx.observe(40.0), Some(LinkMessage::Request { mode: 2, snr_db: 40 }));
+        rx.handle(&LinkMessage::Ack { mode: 2 }).unwrap();
+
+        // Fade: straight down to the mode the measurement supports
+        assert_eq!(rx.observe(10.0), Some(LinkMessage::Request { mode: 0, snr_db: 10 }));
+        assert_eq!(tx.handle(&LinkMessage::Request { mode: 9, snr_db: 0 }), Err(LinkMessageError::UnknownMode(9)));
+
+        let config = rx.config(&ModemConfig::default());
+        assert_eq!(config.dpsk_order, DpskOrder::Binary);
+        assert_eq!(config.rs_parity, 0);
+    }
+
+    #[test]
+    fn test_measured_snr_tracks_noise_level() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_flourish_interval(16);
+        let clean = modulate_fhdpsk_with_config::<TestBackend>(&device, b"LINK", true, &config);
+        let len = clean.dims()[0];
+
+        let mut rng = SplitMix64::new(13);
+        let mut snr_at = |std: f32| {
+            let noise = gaussian_noise::<TestBackend, _>(&device, len, std, &mut rng);
+            measure_link_snr::<TestBackend>(&device, &(clean.clone() + noise), &config).unwrap()
+        };
+        let strong = snr_at(0.05);
+        let weak = snr_at(1.0);
+        assert!(strong > weak + 3.0, "strong {} weak {}", strong, weak);
+        assert!(measure_link_snr::<TestBackend>(&device, &Tensor::zeros([100], &device), &config).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+/// Neural LLR Calibration
+///
+/// The analytic soft output `cos(Δφ) · |reference|` has an arbitrary scale and
+/// ignores how reliable each phase difference actually is: a faded reference
+/// symbol or a low-SNR transmission gives confident-looking values that are
+/// close to coin flips. `LlrCalibrator` is a tiny MLP that maps the raw
+/// detector statistics of each bit to a calibrated LLR (natural log of
+/// P(0)/P(1)):
+///
+/// ```text
+/// [dot/S², |curr|/S, |ref|/S, SNR/10 dB] -> Linear -> ReLU -> Linear -> ReLU -> Linear -> LLR
+/// ```
+///
+/// S is the RMS matched-filter amplitude of the transmission, so the
+/// features don't depend on the receive level. The model is trained with the
+/// logistic loss on channel-simulator traffic (`calibration_frame`,
+/// `--example train_llr_calibrator`). `LlrMapping` selects the analytic
+/// formula or a trained calibrator at runtime.
+
+use burn::config::Config;
+use burn::module::Module;
+use burn::nn::{Linear, LinearConfig, Relu};
+use burn::tensor::{Tensor, activation, backend::Backend};
+use crate::dpsk::DpskOrder;
+use crate::modulation::DemodStatistics;
+
+#[cfg(feature = "channel-sim")]
+use crate::{fft_correlation::FftBackend, modem_rng::ModemRng, transmitter::BachTransmitter, watterson::WattersonChannel};
+
+/// Input features per bit
+pub const CALIBRATOR_FEATURES: usize = 4;
+
+/// LLR calibrator configuration
+#[derive(Config, Debug)]
+pub struct LlrCalibratorConfig {
+    /// Width of the two hidden layers
+    #[config(default = 16)]
+    pub hidden: usize,
+}
+
+impl LlrCalibratorConfig {
+    pub fn init<B: Backend>(&self, device: &B::Device) -> LlrCalibrator<B> {
+        LlrCalibrator {
+            input: LinearConfig::new(CALIBRATOR_FEATURES, self.hidden).init(device),
+            hidden: LinearConfig::new(self.hidden, self.hidden).init(device),
+            output: LinearConfig::new(self.hidden, 1).init(device),
+            activation: Relu::new(),
+        }
+    }
+}
+
+/// MLP from detector statistics to calibrated LLRs
+#[derive(Module, Debug)]
+pub struct LlrCalibrator<B: Backend> {
+    input: Linear<B>,
+    hidden: Linear<B>,
+    output: Linear<B>,
+    activation: Relu,
+}
+
+impl<B: Backend> LlrCalibrator<B> {
+    /// Features: [num_bits, CALIBRATOR_FEATURES] from `calibrator_features`
+    ///
+    /// Returns: LLRs [num_bits] (positive = bit 0)
+    pub fn forward(&self, features: Tensor<B, 2>) -> Tensor<B, 1> {
+        let num_bits = features.dims()[0];
+        let x = self.activation.forward(self.input.forward(features));
+        let x = self.activation.forward(self.hidden.forward(x));
+        self.output.forward(x).reshape([num_bits])
+    }
+
+    /// Width of the hidden layers
+    pub fn hidden_size(&self) -> usize {
+        self.input.weight.dims()[1]
+    }
+
+    /// Calibrated LLRs with erased positions zeroed
+    pub fn calibrate(&self, stats: &DemodStatistics<B>) -> Tensor<B, 1> {
+        stats.erase(self.forward(calibrator_features(stats)))
+    }
+}
+
+/// Level-independent per-bit features
+///
+/// Returns: [num_bits, CALIBRATOR_FEATURES]
+pub fn calibrator_features<B: Backend>(stats: &DemodStatistics<B>) -> Tensor<B, 2> {
+    let num_bits = stats.num_bits();
+
+    let scale_sq = stats.amp_curr.clone().powf_scalar(2.0).mean().add_scalar(1e-12);
+    let scale = scale_sq.clone().sqrt();
+    let snr = stats.snr_db.clone().div_scalar(10.0).expand([num_bits]);
+
+    Tensor::stack::<2>(vec![
+        stats.dot.clone() / scale_sq,
+        stats.amp_curr.clone() / scale.clone(),
+        stats.amp_prev.clone() / scale,
+        snr,
+    ], 1)
+}
+
+/// Logistic loss of LLRs against the transmitted bits
+///
+/// mean(log(1 + exp(-s·LLR))), s = +1 for bit 0. Minimized by LLRs equal to
+/// the true log-likelihood ratio. Evaluated as max(x, 0) + log(1 + exp(-|x|))
+/// so the unscaled analytic LLRs don't overflow.
+///
+/// Returns: [1]
+pub fn llr_calibration_loss<B: Backend>(llrs: Tensor<B, 1>, bits: &[u8]) -> Tensor<B, 1> {
+    let device = llrs.device();
+    let signs: Vec<f32> = bits.iter().map(|&b| if b == 0 { 1.0 } else { -1.0 }).collect();
+    let signs = Tensor::<B, 1>::from_floats(signs.as_slice(), &device);
+
+    let x = llrs.slice([0..bits.len()]).mul(signs).neg();
+    (activation::relu(x.clone()) + x.abs().neg().exp().log1p()).mean()
+}
+
+/// LLR mapping of the receive chain, chosen at runtime
+#[derive(Clone, Debug)]
+pub enum LlrMapping<B: Backend> {
+    /// cos(Δφ) · |reference| (`demodulate_fhdpsk_soft`)
+    Analytic,
+
+    /// Trained `LlrCalibrator`
+    Neural(LlrCalibrator<B>),
+}
+
+impl<B: Backend> LlrMapping<B> {
+    /// LLRs from detector statistics, erasures applied
+    ///
+    /// The calibrator is trained on binary DPSK statistics; DQPSK and
+    /// 8-DPSK statistics use the analytic max-log LLRs.
+    pub fn llrs(&self, stats: &DemodStatistics<B>) -> Tensor<B, 1> {
+        match self {
+            LlrMapping::Neural(calibrator) if stats.order == DpskOrder::Binary => calibrator.calibrate(stats),
+            _ => stats.erase(stats.analytic_llrs()),
+        }
+    }
+}
+
+/// One simulated frame for calibrator training
+///
+/// `payload` through `tx`, `channel` and AWGN at `snr_db` (relative to the
+/// faded signal; fading and noise from `rng`), demodulated to CODE_N bit
+/// statistics. Returns the
+/// statistics and the transmitted (interleaved) code bits, or None if the
+/// preamble wasn't found.
+#[cfg(feature = "channel-sim")]
+pub fn calibration_frame<B: Backend + FftBackend, R: ModemRng + ?Sized>(
+    device: &B::Device,
+    tx: &BachTransmitter,
+    channel: &WattersonChannel,
+    payload: &[u8],
+    snr_db: f32,
+    rng: &mut R,
+) -> Option<(DemodStatistics<B>, Vec<u8>)> {
+    use burn::tensor::ElementConversion;
+    use crate::modem_rng::gaussian_noise;
+    use crate::modulation::{demodulate_fhdpsk_stats_with_config, encode_bits};
+    use crate::transmitter::CODE_N;
+
+    let bits = encode_bits(&tx.encode_frame(payload).expect("payload fits the frame"));
+
+    let signal = tx.build::<B>(device, payload).expect("payload fits the frame");
+    let faded = channel.apply_with_rng::<B, R>(device, &signal, rng);
+
+    let signal_power: f32 = faded.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+    let rx = faded.clone() + gaussian_noise::<B, R>(device, faded.dims()[0], noise_std, rng);
+
+    let stats = demodulate_fhdpsk_stats_with_config::<B>(device, &rx, tx.add_preamble, &tx.config, CODE_N)?;
+    Some((stats, bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modulation::{demodulate_fhdpsk_soft_erasures_with_config, demodulate_fhdpsk_stats_with_config, modulate_fhdpsk_with_config};
+    use crate::TestBackend;
+
+    #[test]
+    fn test_analytic_mapping_matches_soft_demodulator() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Calibrate", true, &config);
+        let num_bits = 9 * 8;
+
+        let stats = demodulate_fhdpsk_stats_with_config::<TestBackend>(&device, &signal, true, &config, num_bits)
+            .expect("sync failed");
+        let reference = demodulate_fhdpsk_soft_erasures_with_config::<TestBackend>(&device, &signal, true, &config, num_bits);
+
+        let analytic = LlrMapping::Analytic.llrs(&stats);
+        let max_err: f32 = (analytic - reference).abs().max().into_scalar();
+        assert!(max_err < 1e-4, "max error {}", max_err);
+
+        let features = calibrator_features(&stats);
+        assert_eq!(features.dims(), [num_bits, CALIBRATOR_FEATURES]);
+
+        let calibrator = LlrCalibratorConfig::new().init::<TestBackend>(&device);
+        assert_eq!(LlrMapping::Neural(calibrator).llrs(&stats).dims(), [num_bits]);
+    }
+}
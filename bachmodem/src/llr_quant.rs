@@ -0,0 +1,224 @@
+/// Quantized LLR Combining
+///
+/// Deep-space links repeat a frame 100+ times and combine the slots' LLRs
+/// by their SNR weights (MRC). Keeping every slot instead of a running sum
+/// lets the receiver revise a weight after the fact (`set_weight`) or drop a
+/// slot found corrupted - but at 4 bytes per f32 LLR per slot. The
+/// `QuantizedLlrCombiner` keeps each slot as i8 with one f32 scale, about a
+/// quarter of the memory.
+///
+/// Scale: the slot's `QUANT_CLIP_PERCENTILE` percentile of |LLR| maps to
+/// ±127. Scaling to the maximum instead would let one huge LLR (an impulse,
+/// a badly weighted symbol) squeeze every other value of the slot into a
+/// few quantization steps. The few LLRs above the percentile saturate at
+/// ±127 and are counted per slot; they were confident anyway, and their
+/// sign survives.
+///
+/// Rounding adds at most half a step per value, independent between slots,
+/// so it averages out in the sum like channel noise does, far below it.
+/// The combined LLRs are Σ wᵢ·sᵢ·qᵢ, summed on the host in f32.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::gpu_ops::percentile_gpu;
+
+/// |LLR| percentile of a slot that maps to the largest i8 value
+pub const QUANT_CLIP_PERCENTILE: f32 = 0.999;
+
+/// Largest quantized magnitude
+const QUANT_MAX: f32 = 127.0;
+
+/// LLRs of one slot as i8
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantizedSlot {
+    /// LLR / scale, rounded and clamped to ±127
+    pub values: Vec<i8>,
+
+    /// LLR per quantization step
+    pub scale: f32,
+
+    /// Combining weight (MRC)
+    pub weight: f32,
+
+    /// Values clamped at ±127
+    pub saturated: usize,
+}
+
+impl QuantizedSlot {
+    /// Quantize `llrs` with an explicit scale
+    pub fn quantize(llrs: &[f32], scale: f32, weight: f32) -> Self {
+        let scale = scale.max(f32::MIN_POSITIVE);
+        let mut saturated = 0;
+        let values = llrs.iter()
+            .map(|&llr| {
+                let q = (llr / scale).round();
+                if q.abs() > QUANT_MAX {
+                    saturated += 1;
+                }
+                q.clamp(-QUANT_MAX, QUANT_MAX) as i8
+            })
+            .collect();
+        Self { values, scale, weight, saturated }
+    }
+
+    /// Dequantized LLRs
+    pub fn llrs(&self) -> Vec<f32> {
+        self.values.iter().map(|&q| q as f32 * self.scale).collect()
+    }
+}
+
+/// Per-slot LLR store combining in i8
+#[derive(Clone, Debug)]
+pub struct QuantizedLlrCombiner {
+    /// |LLR| percentile mapped to ±127
+    pub clip_percentile: f32,
+
+    slots: Vec<QuantizedSlot>,
+}
+
+impl Default for QuantizedLlrCombiner {
+    fn default() -> Self {
+        Self { clip_percentile: QUANT_CLIP_PERCENTILE, slots: Vec::new() }
+    }
+}
+
+impl QuantizedLlrCombiner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the `q` percentile of |LLR| to ±127 (1.0 = the maximum, no saturation)
+    pub fn with_clip_percentile(mut self, q: f32) -> Self {
+        assert!(q > 0.0 && q <= 1.0, "Clip percentile must be in (0, 1]");
+        self.clip_percentile = q;
+        self
+    }
+
+    /// Quantize and store one slot's LLRs; returns its slot index
+    ///
+    /// The scale is found on the device (`percentile_gpu`).
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads the slot's LLRs and its scale
+    pub fn push<B: Backend>(&mut self, llrs: &Tensor<B, 1>, weight: f32) -> usize {
+        let clip = percentile_gpu(llrs.clone().abs(), self.clip_percentile);
+        let scale: f32 = (clip / QUANT_MAX).into_data().to_vec::<f32>().unwrap()[0];
+        let values: Vec<f32> = llrs.clone().into_data().to_vec().unwrap();
+        self.slots.push(QuantizedSlot::quantize(&values, scale, weight));
+        self.slots.len() - 1
+    }
+
+    /// Change the weight of a stored slot (0.0 drops it from the sum)
+    pub fn set_weight(&mut self, slot: usize, weight: f32) {
+        self.slots[slot].weight = weight;
+    }
+
+    pub fn slot(&self, slot: usize) -> &QuantizedSlot {
+        &self.slots[slot]
+    }
+
+    pub fn num_slots(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Saturated values over all slots
+    pub fn saturated(&self) -> usize {
+        self.slots.iter().map(|s| s.saturated).sum()
+    }
+
+    /// Bytes held by the stored slots (values, scale, weight)
+    pub fn memory_bytes(&self) -> usize {
+        self.slots.iter().map(|s| s.values.len() + 2 * std::mem::size_of::<f32>()).sum()
+    }
+
+    /// Bytes the same slots take as f32 LLRs
+    pub fn f32_memory_bytes(&self) -> usize {
+        self.slots.iter().map(|s| s.values.len() * std::mem::size_of::<f32>()).sum()
+    }
+
+    /// Weighted sum Σ wᵢ·sᵢ·qᵢ over the common length of all slots
+    ///
+    /// Slots cut at slightly different boundaries may hold a few more or
+    /// fewer LLRs, as in `StreamAccumulator`. `None` before the first slot.
+    pub fn combined_llrs(&self) -> Option<Vec<f32>> {
+        let len = self.slots.iter().map(|s| s.values.len()).min()?;
+        let mut sum = vec![0.0f32; len];
+        for slot in &self.slots {
+            let step = slot.weight * slot.scale;
+            for (acc, &q) in sum.iter_mut().zip(&slot.values) {
+                *acc += step * q as f32;
+            }
+        }
+        Some(sum)
+    }
+
+    /// `combined_llrs` uploaded to `device`
+    pub fn combined<B: Backend>(&self, device: &B::Device) -> Option<Tensor<B, 1>> {
+        self.combined_llrs().map(|sum| Tensor::from_floats(sum.as_slice(), device))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpu_ops::soft_combine_gpu;
+    use crate::modem_rng::{ModemRng, SplitMix64};
+    use crate::polar::PolarCode;
+    use bachmodem_core::frame::{CODE_K, CODE_N};
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_quantize_saturates_and_keeps_sign() {
+        let slot = QuantizedSlot::quantize(&[0.5, -1.0, 30.0, -0.04, -500.0], 0.1, 1.0);
+        assert_eq!(slot.values, vec![5, -10, 127, 0, -127]);
+        assert_eq!(slot.saturated, 2);
+        assert_eq!(slot.llrs()[1], -1.0);
+    }
+
+    #[test]
+    fn test_int8_combining_decodes_like_f32() {
+        let device = Default::default();
+        let code = PolarCode::new(CODE_N, CODE_K);
+        let mut scenario = SplitMix64::new(11);
+        let mut noise = SplitMix64::new(12);
+        let num_slots = 128;
+
+        for _frame in 0..4 {
+            let info: Vec<u8> = (0..CODE_K).map(|_| scenario.below(2) as u8).collect();
+            let codeword = code.encode(&info);
+
+            // BPSK LLRs at about -15 dB per slot: undecodable alone, clean after 128
+            let mut rows = Vec::with_capacity(num_slots * CODE_N);
+            let mut weights = Vec::with_capacity(num_slots);
+            let mut combiner = QuantizedLlrCombiner::new();
+            for _ in 0..num_slots {
+                let amplitude = 0.18 * (0.5 + scenario.uniform()) as f32;
+                let sigma = 1.0f32;
+                let llrs: Vec<f32> = codeword.iter()
+                    .map(|&b| {
+                        let x = if b == 0 { amplitude } else { -amplitude } + sigma * noise.gaussian() as f32;
+                        2.0 * x / (sigma * sigma)
+                    })
+                    .collect();
+                let weight = amplitude * amplitude;
+                combiner.push(&Tensor::<TestBackend, 1>::from_floats(llrs.as_slice(), &device), weight);
+                rows.extend(llrs);
+                weights.push(weight);
+            }
+
+            let stack = Tensor::<TestBackend, 1>::from_floats(rows.as_slice(), &device).reshape([num_slots, CODE_N]);
+            let weight_tensor = Tensor::<TestBackend, 1>::from_floats(weights.as_slice(), &device);
+            let float_llrs: Vec<f32> = soft_combine_gpu(&stack, &weight_tensor).into_data().to_vec().unwrap();
+            let int8_llrs = combiner.combined_llrs().unwrap();
+
+            assert_eq!(code.decode_sc(&float_llrs), info);
+            assert_eq!(code.decode_sc(&int8_llrs), info);
+            let peak = float_llrs.iter().fold(0.0f32, |m, l| m.max(l.abs()));
+            let error = float_llrs.iter().zip(&int8_llrs).fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+            assert!(error < 0.02 * peak, "quantization error {} of {}", error, peak);
+
+            assert!(combiner.memory_bytes() * 3 < combiner.f32_memory_bytes());
+            assert!(combiner.saturated() <= num_slots * CODE_N / 500);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/// Noncoherent MFSK Fallback
+///
+/// For signals too weak to hold a phase reference, the data picks the tone
+/// instead of its phase: each symbol sends one tone of the alphabet, carrying
+/// `bits_per_tone` bits through `ModemConfig::tone_mapping`. The receiver
+/// needs no reference block and no phase continuity between symbols - it
+/// correlates each symbol with the whole Morlet bank (`matched_filter_bank`)
+/// and keeps only the energies |z|².
+///
+/// Frames use the same preamble, postamble and sync (`synchronize_data_start_with_config`)
+/// as FH-DPSK, so scanners and front ends need no changes. There is no
+/// frequency hopping - the data already spreads the tones - and `usable_tones`
+/// does not apply: every tone of the alphabet is a symbol value.
+///
+/// Soft output is `tone_llrs` per symbol, scaled by the noise energy of a
+/// tone without signal, estimated as the median energy over all tones and
+/// symbols (one tone in `num_tones` carries signal).
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::config::ModemConfig;
+use crate::dropout::{detect_dropouts_gpu, DROPOUT_THRESHOLD};
+use crate::fft_correlation::FftBackend;
+use crate::gpu_ops::median_gpu;
+use crate::modulation::{encode_bits, synchronize_data_start_with_config};
+use crate::tone_mapping::tone_llrs;
+use crate::wavelet::{generate_symbol_with_config, generate_bach_preamble_with_config, generate_bach_postamble_with_config, matched_filter_bank};
+use bachmodem_core::scrambler::{descramble_llrs, scramble_bits};
+
+/// Data symbols for `num_bits` bits (the last one zero-padded)
+pub fn mfsk_num_symbols(config: &ModemConfig, num_bits: usize) -> usize {
+    num_bits.div_ceil(config.bits_per_tone())
+}
+
+/// Tone index of every data symbol for `bits`, MSB first per symbol
+pub fn mfsk_tones(bits: &[u8], config: &ModemConfig) -> Vec<usize> {
+    let bpt = config.bits_per_tone();
+    bits.chunks(bpt)
+        .map(|chunk| {
+            // Zero-pad the last symbol at the LSB end
+            let value = (0..bpt).fold(0, |value, i| (value << 1) | chunk.get(i).copied().unwrap_or(0) as usize);
+            config.tone_mapping.tone_for_value(value)
+        })
+        .collect()
+}
+
+/// Modulates data as noncoherent MFSK on the tone alphabet of `config`
+pub fn modulate_mfsk<B: Backend>(
+    device: &B::Device,
+    data_bytes: &[u8],
+    add_preamble: bool,
+    config: &ModemConfig,
+) -> Tensor<B, 1> {
+    let mut bits = encode_bits(data_bytes);
+    if config.scrambler {
+        scramble_bits(&mut bits);
+    }
+
+    let mut parts = Vec::new();
+    if add_preamble {
+        parts.push(generate_bach_preamble_with_config::<B>(device, config));
+    }
+    parts.extend(mfsk_tones(&bits, config).into_iter().map(|tone| generate_symbol_with_config::<B>(device, tone, 0.0, config)));
+    if add_preamble {
+        parts.push(generate_bach_postamble_with_config::<B>(device, config));
+    }
+
+    if parts.is_empty() {
+        return Tensor::from_floats([0.0f32], device);
+    }
+    Tensor::cat(parts, 0)
+}
+
+/// Soft MFSK demodulation into exactly `num_bits` LLRs (positive -> bit 0)
+///
+/// Symbols past the end of the signal or inside a dropout are erased
+/// (0.0); if synchronization fails every position is erased.
+///
+/// ⚠️ **SYNC POINT**: Downloads the symbol energies [symbols, num_tones]
+pub fn demodulate_mfsk_soft<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Tensor<B, 1> {
+    let erased = || Tensor::zeros([num_bits], device);
+
+    let start = if use_sync {
+        match synchronize_data_start_with_config::<B>(device, signal, config) {
+            Some(start) => start,
+            None => return erased(),
+        }
+    } else {
+        0
+    };
+
+    let symbol_len = config.symbol_samples();
+    let num_tones = config.num_tones;
+    let available = signal.dims()[0].saturating_sub(start) / symbol_len;
+    let num_symbols = available.min(mfsk_num_symbols(config, num_bits));
+    if num_symbols == 0 {
+        return erased();
+    }
+
+    // Contiguous symbols: [NumSymbols, SymbolLen]
+    let symbols_batch = signal.clone()
+        .slice([start..start + num_symbols * symbol_len])
+        .reshape([num_symbols, symbol_len]);
+    let dropped = detect_dropouts_gpu(&symbols_batch, DROPOUT_THRESHOLD);
+
+    // Every symbol against every tone: [NumSymbols, NumTones]
+    let bank = matched_filter_bank::<B>(device, config);
+    let energies = bank.map(|b| symbols_batch.clone().matmul(b.transpose())).norm_sqr();
+    let noise_energy = median_gpu(energies.clone().reshape([num_symbols * num_tones]));
+
+    let energies: Vec<f32> = energies.into_data().to_vec().unwrap();
+    let noise_energy: f32 = noise_energy.into_data().to_vec::<f32>().unwrap()[0];
+
+    let bpt = config.bits_per_tone();
+    let mut llrs = vec![0.0f32; num_symbols * bpt];
+    for (s, (chunk, symbol_llrs)) in energies.chunks(num_tones).zip(llrs.chunks_mut(bpt)).enumerate() {
+        if !dropped[s] {
+            symbol_llrs.copy_from_slice(&tone_llrs(chunk, config.tone_mapping, noise_energy));
+        }
+    }
+    llrs.resize(num_bits.max(llrs.len()), 0.0);
+    llrs.truncate(num_bits);
+    if config.scrambler {
+        descramble_llrs(&mut llrs);
+    }
+
+    Tensor::from_floats(llrs.as_slice(), device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::tone_mapping::ToneMapping;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_tones_follow_mapping() {
+        let config = ModemConfig::default().with_tone_mapping(ToneMapping::Gray);
+        assert_eq!(config.bits_per_tone(), 4);
+        // 0011 -> tone 2, 1000 -> tone 15, 101 (padded to 1010) -> tone 12
+        assert_eq!(mfsk_tones(&[0, 0, 1, 1, 1, 0, 0, 0, 1, 0, 1], &config), vec![2, 15, 12]);
+        assert_eq!(mfsk_num_symbols(&config, 11), 3);
+    }
+
+    #[test]
+    fn test_mfsk_roundtrip_in_noise() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_tone_mapping(ToneMapping::Gray);
+        let payload = b"MFSK FALLBACK";
+        let num_bits = payload.len() * 8;
+
+        let clean = modulate_mfsk::<TestBackend>(&device, payload, true, &config);
+        let noise = gaussian_noise::<TestBackend, _>(&device, clean.dims()[0], 0.2, &mut SplitMix64::new(17));
+        let signal = clean + noise;
+
+        let llrs: Vec<f32> = demodulate_mfsk_soft::<TestBackend>(&device, &signal, true, &config, num_bits)
+            .into_data().to_vec().unwrap();
+        assert_eq!(llrs.len(), num_bits);
+        let bits: Vec<u8> = llrs.iter().map(|&l| (l < 0.0) as u8).collect();
+        assert_eq!(bits, encode_bits(payload));
+
+        // Data symbols only, no sync: bits past the end of the capture are erased
+        let data = modulate_mfsk::<TestBackend>(&device, payload, false, &config);
+        let llrs: Vec<f32> = demodulate_mfsk_soft::<TestBackend>(&device, &data, false, &config, num_bits + 64)
+            .into_data().to_vec().unwrap();
+        assert!(llrs[..num_bits].iter().all(|&l| l != 0.0));
+        assert!(llrs[num_bits..].iter().all(|&l| l == 0.0));
+    }
+}
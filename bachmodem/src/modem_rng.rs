@@ -0,0 +1,198 @@
+/// Modem RNG
+///
+/// Noise, fading, slot jitter and seeded hopping draw from `ModemRng`, so a
+/// simulated scenario replays bit for bit from one seed. The trait is
+/// `RngCore` plus the draws the modem needs (uniform, Gaussian); every
+/// `RngCore` implements it, so a test can plug in its own generator.
+///
+/// Components both ends derive from a shared seed (hopping patterns, RF
+/// hop order, slot jitter) draw from `SplitMix64`: a fixed generator
+/// defined here, so a seed gives the same pattern on every build of
+/// transmitter and receiver, with or without `channel-sim`.
+///
+/// `SimSeed` (`channel-sim`) hands out the simulation generators: ChaCha20,
+/// one stream per component (`RngStream`, with an index for per-station
+/// streams). Streams share the seed and differ in ChaCha's stream number,
+/// so extra draws in one component never shift another's sequence, and the
+/// output is the same on every platform and release of the ChaCha crate.
+///
+/// Device noise (`gaussian_noise`) is drawn on the host and uploaded:
+/// `Tensor::random` depends on the backend's own generator and is not
+/// reproducible across backends.
+
+use burn::tensor::{Tensor, backend::Backend};
+use rand_core::{impls, RngCore};
+use std::f64::consts::PI;
+
+#[cfg(feature = "channel-sim")]
+use rand_core::SeedableRng;
+#[cfg(feature = "channel-sim")]
+pub use rand_chacha::ChaCha20Rng;
+
+/// Random source of the stochastic components
+pub trait ModemRng: RngCore {
+    /// Uniform in [0, 1), 53 bits
+    fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in [0, n)
+    fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "empty range");
+        ((self.uniform() * n as f64) as usize).min(n - 1)
+    }
+
+    /// Standard normal sample (Box-Muller)
+    fn gaussian(&mut self) -> f64 {
+        let u1 = 1.0 - self.uniform(); // (0, 1]: ln stays finite
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+
+    /// Seed for a seeded component (`SlotJitter::from_rng`, `ModemConfig::with_hopping_seed`)
+    fn seed(&mut self) -> u64 {
+        self.next_u64()
+    }
+}
+
+impl<R: RngCore + ?Sized> ModemRng for R {}
+
+/// SplitMix64 finalizer
+///
+/// Fixed here rather than taken from `rand`, so seeded schedules, patterns
+/// and test fingerprints stay the same across releases.
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64 generator of the seeded link components
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+}
+
+impl RngCore for SplitMix64 {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let out = splitmix64(self.0);
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        out
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Component drawing from a scenario's randomness
+#[cfg(feature = "channel-sim")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    /// Payload bytes and scenario choices (SNR, channel, offsets)
+    Scenario,
+
+    /// Additive channel noise
+    Noise,
+
+    /// Watterson fading phases
+    Fading,
+
+    /// Slot jitter seeds
+    Jitter,
+
+    /// Seeded hopping patterns
+    Hopping,
+
+    /// Jammer tone choices and phases
+    Jammer,
+}
+
+#[cfg(feature = "channel-sim")]
+impl RngStream {
+    fn id(&self) -> u64 {
+        match self {
+            RngStream::Scenario => 0,
+            RngStream::Noise => 1,
+            RngStream::Fading => 2,
+            RngStream::Jitter => 3,
+            RngStream::Hopping => 4,
+            RngStream::Jammer => 5,
+        }
+    }
+}
+
+/// Master seed of a simulated scenario
+#[cfg(feature = "channel-sim")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SimSeed(pub u64);
+
+#[cfg(feature = "channel-sim")]
+impl SimSeed {
+    /// Generator of one component
+    pub fn rng(&self, stream: RngStream) -> ChaCha20Rng {
+        self.rng_for(stream, 0)
+    }
+
+    /// Generator of one component for item `index` (station, example, ...)
+    pub fn rng_for(&self, stream: RngStream, index: u32) -> ChaCha20Rng {
+        let mut rng = ChaCha20Rng::seed_from_u64(self.0);
+        rng.set_stream((stream.id() << 32) | index as u64);
+        rng
+    }
+}
+
+/// `len` samples of zero-mean Gaussian noise with standard deviation `std`
+pub fn gaussian_noise<B: Backend, R: ModemRng + ?Sized>(
+    device: &B::Device,
+    len: usize,
+    std: f32,
+    rng: &mut R,
+) -> Tensor<B, 1> {
+    let samples: Vec<f32> = (0..len).map(|_| (rng.gaussian() * std as f64) as f32).collect();
+    Tensor::from_floats(samples.as_slice(), device)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "channel-sim")]
+    #[test]
+    fn test_streams_replay_and_stay_independent() {
+        let seed = SimSeed(42);
+        let draw = |rng: &mut ChaCha20Rng| (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>();
+
+        assert_eq!(draw(&mut seed.rng(RngStream::Noise)), draw(&mut seed.rng(RngStream::Noise)));
+        assert_ne!(draw(&mut seed.rng(RngStream::Noise)), draw(&mut seed.rng(RngStream::Fading)));
+        assert_ne!(draw(&mut seed.rng_for(RngStream::Fading, 0)), draw(&mut seed.rng_for(RngStream::Fading, 1)));
+        assert_ne!(draw(&mut seed.rng(RngStream::Noise)), draw(&mut SimSeed(43).rng(RngStream::Noise)));
+    }
+
+    #[test]
+    fn test_gaussian_moments() {
+        let mut rng = SplitMix64::new(7);
+        let samples: Vec<f64> = (0..20_000).map(|_| rng.gaussian()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.03, "mean {}", mean);
+        assert!((var - 1.0).abs() < 0.05, "variance {}", var);
+
+        let picks: Vec<usize> = (0..1000).map(|_| rng.below(3)).collect();
+        assert!(picks.iter().all(|&p| p < 3) && (0..3).all(|v| picks.contains(&v)));
+    }
+}
@@ -1,28 +1,26 @@
-use burn::tensor::{Tensor, backend::Backend, ElementConversion};
-use crate::wavelet::{generate_symbol, generate_bach_preamble, generate_bach_flourish, generate_bach_postamble, get_melody_indices, morlet_wavelet, FS, SYMBOL_DURATION, BACH_FREQUENCIES};
-use crate::gpu_ops::cross_correlation_gpu;
+use burn::tensor::{Tensor, Int, backend::Backend, ElementConversion};
+use crate::wavelet::{generate_symbol_with_config, generate_bach_preamble_with_config, generate_bach_flourish_with_config, generate_bach_postamble_with_config, shaped_wavelet, matched_filter_bank, preamble_tone_sequence};
+use crate::config::ModemConfig;
+use crate::gpu_ops::{cross_correlation_gpu, estimate_snr_from_correlation};
 use crate::fft_correlation::{fft_cross_correlation, FftBackend};
 use crate::gpu_math::atan2_fast_gpu;
+use crate::dropout::{detect_dropouts_gpu, group_dropouts, DROPOUT_THRESHOLD};
+use crate::enhancer::SignalEnhancer;
+use crate::leakage::{compensated_correlations, leakage_compensation_matrix};
+use crate::chirp::estimate_chirp_offset;
+use crate::sync_ambiguity::resolve_sync_ambiguity;
+use crate::reference_blocks::{DifferentialPairs, ReferenceLayout};
+use crate::dpsk::DpskOrder;
+use crate::afc::{correct_frequency_offset, preamble_frequency_offset};
+use crate::clock_drift::{measure_postamble_drift, ClockDriftTracker};
+use crate::timing_recovery::{interpolate_symbols, recover_symbol_timing, TimingLoop};
+use crate::coarse_sync::{measure_sync_two_stage, MAX_DECIMATION};
+use crate::complex::ComplexTensor;
+use crate::sync_lock::SyncLockConfig;
 use std::f64::consts::PI;
 
-/// Encodes bytes into a sequence of bits
-pub fn encode_bits(data_bytes: &[u8]) -> Vec<u8> {
-    data_bytes
-        .iter()
-        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1))
-        .collect()
-}
-
-/// Packs bits back into bytes
-pub fn pack_bits(bits: &[u8]) -> Vec<u8> {
-    bits.chunks(8)
-        .map(|chunk| {
-            chunk.iter().enumerate().fold(0u8, |acc, (i, &bit)| {
-                acc | (bit << (7 - i))
-            })
-        })
-        .collect()
-}
+pub use bachmodem_core::bits::{encode_bits, pack_bits};
+use bachmodem_core::scrambler::{scramble_bits, whitening_sequence};
 
 /// Modulates data using Frequency-Hopping Differential Phase Shift Keying (FH-DPSK)
 pub fn modulate_fhdpsk<B: Backend>(
@@ -40,66 +38,49 @@ pub fn modulate_fhdpsk_with_flourishes<B: Backend>(
     add_preamble: bool,
     flourish_interval: usize, // Insert flourish every N symbols (0 = disabled)
 ) -> Tensor<B, 1> {
-    let bits = encode_bits(data_bytes);
-    
+    modulate_fhdpsk_with_config::<B>(device, data_bytes, add_preamble, &ModemConfig::default().with_flourish_interval(flourish_interval))
+}
+
+/// Modulates using the tone alphabet, hopping pattern and lag from `config`
+///
+/// With `ModemConfig::with_pilots` the reference groups become pilots and
+/// the data symbols carry absolute phases for coherent detection.
+pub fn modulate_fhdpsk_with_config<B: Backend>(
+    device: &B::Device,
+    data_bytes: &[u8],
+    add_preamble: bool,
+    config: &ModemConfig,
+) -> Tensor<B, 1> {
+    let mut bits = encode_bits(data_bytes);
+    if config.scrambler {
+        scramble_bits(&mut bits);
+    }
     if bits.is_empty() {
         if add_preamble {
-            return generate_bach_preamble::<B>(device);
+            return generate_bach_preamble_with_config::<B>(device, config);
         } else {
             return Tensor::from_floats([0.0f32], device);
         }
     }
     
-    // Pad bits to multiple of 16 for block processing
-    let mut padded_bits = bits.clone();
-    let pad_len = (16 - (bits.len() % 16)) % 16;
-    padded_bits.extend(vec![0; pad_len]);
-    
-    // Prepend reference block (16 zeros) to establish phase reference
-    let mut bits_with_ref = vec![0u8; 16];
-    bits_with_ref.extend(padded_bits);
-    
-    // Reshape for Inter-Hop Differential Encoding (Lag 16)
-    let num_blocks = bits_with_ref.len() / 16;
-    let mut phases = Vec::new();
-    
-    // Cumulative sum along time axis for differential encoding
-    for block_idx in 0..num_blocks {
-        let block_start = block_idx * 16;
-        for freq_idx in 0..16 {
-            let bit_idx = block_start + freq_idx;
-            let bit = bits_with_ref[bit_idx];
-            
-            // Phase shift: bit * π
-            let phase_shift = if bit == 1 { PI } else { 0.0 };
-            
-            // Cumulative phase for this frequency
-            let prev_phase = if block_idx == 0 {
-                0.0
-            } else {
-                phases[(block_idx - 1) * 16 + freq_idx]
-            };
-            
-            phases.push(prev_phase + phase_shift);
-        }
-    }
+    let phases = config.reference_layout().phases(&bits);
     
     // Generate melody sequence
     let num_symbols = phases.len();
-    let melody_indices = get_melody_indices(num_symbols);
+    let melody_indices = config.melody_indices(num_symbols);
     
     // Generate waveforms with optional musical flourishes
     let mut waveforms = Vec::new();
     
     for (i, &melody_idx) in melody_indices.iter().enumerate() {
         // Insert Bach Sweep flourish periodically (if enabled)
-        if flourish_interval > 0 && i > 0 && i % flourish_interval == 0 {
-            let flourish = generate_bach_flourish::<B>(device);
+        if config.flourish_interval > 0 && i > 0 && i % config.flourish_interval == 0 {
+            let flourish = generate_bach_flourish_with_config::<B>(device, config);
             waveforms.push(flourish);
         }
         
         let phase = phases[i];
-        let waveform = generate_symbol::<B>(device, melody_idx, phase, SYMBOL_DURATION, FS);
+        let waveform = generate_symbol_with_config::<B>(device, melody_idx, phase, config);
         waveforms.push(waveform);
     }
     
@@ -108,18 +89,28 @@ pub fn modulate_fhdpsk_with_flourishes<B: Backend>(
     let mut parts = Vec::new();
     
     if add_preamble {
-        parts.push(generate_bach_preamble::<B>(device));
+        parts.push(generate_bach_preamble_with_config::<B>(device, config));
     }
     
     parts.push(data_waveform);
     
     if add_preamble {
-        parts.push(generate_bach_postamble::<B>(device));
+        parts.push(generate_bach_postamble_with_config::<B>(device, config));
     }
     
     Tensor::cat(parts, 0)
 }
 
+/// Symbol phases for lag-differential encoding
+/// 
+/// Bits are padded to a multiple of `lag` and preceded by one all-zero
+/// reference block; each symbol adds `bit * π` to the phase of the symbol
+/// `lag` positions earlier. Returns one phase per symbol (radians).
+/// `ReferenceLayout::phases` adds longer and periodic reference blocks.
+pub(crate) fn differential_phases(bits: &[u8], lag: usize) -> Vec<f64> {
+    ReferenceLayout::single(lag).phases(bits)
+}
+
 /// GPU-only synchronization - returns tensors without sync
 /// 
 /// **NO SYNC POINT**: Returns (correlation_tensor, best_idx_tensor, best_val_tensor)
@@ -138,7 +129,7 @@ pub fn synchronize_signal_gpu<B: Backend + FftBackend>(
 }
 
 /// Synchronizes signal by finding the Bach Preamble via cross-correlation
-/// ⚠️ **SYNC POINT**: Returns scalar position, downloads from GPU
+/// ⚠️ **SYNC POINT**: Returns the peak position and its quality metrics, downloads from GPU
 /// 
 /// For GPU-only pipelines, use synchronize_signal_gpu() instead
 /// 
@@ -146,38 +137,248 @@ pub fn synchronize_signal_gpu<B: Backend + FftBackend>(
 pub fn synchronize_signal<B: Backend + FftBackend>(
     device: &B::Device,
     signal: &Tensor<B, 1>,
-) -> Option<usize> {
-    println!("    [Sync] Starting synchronization...");
-    let preamble = generate_bach_preamble::<B>(device);
+) -> Option<SyncResult> {
+    synchronize_signal_with_config::<B>(device, signal, &ModemConfig::default())
+}
+
+/// Preamble detection thresholds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncThresholds {
+    /// Peak correlation over the preamble norm
+    ///
+    /// In signal units (it grows with the received preamble amplitude), so
+    /// it gates on capture level as well as on shape.
+    pub min_correlation: f32,
+
+    /// Squared-correlation peak over its mean
+    pub min_peak_to_noise: f32,
+}
+
+impl Default for SyncThresholds {
+    /// WSPR-style thresholds for -30 dB
+    fn default() -> Self {
+        Self {
+            min_correlation: 0.025, // Very aggressive for -30 dB
+            min_peak_to_noise: 1.3, // Relaxed (weak signal)
+        }
+    }
+}
+
+/// Sweep-cycle candidates re-scored around a preamble peak by default
+///
+/// Every start of a preamble of up to 8 sweeps.
+pub const DEFAULT_SYNC_CANDIDATES: usize = 7;
+
+/// Preamble search parameters of a deployment (`ModemConfig::sync`)
+///
+/// The defaults are tuned for -30 dB. A station on a busy band raises the
+/// thresholds to cut false alarms; one listening for a known schedule
+/// narrows the search window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncConfig {
+    /// Detection thresholds of `synchronize_signal_with_config`
+    pub thresholds: SyncThresholds,
+
+    /// Preamble starts searched from the capture start (samples), None =
+    /// the whole capture
+    pub search_window: Option<usize>,
+
+    /// Largest coarse-stage decimation factor of two-stage sync (power of
+    /// two, 1 = full rate only; see `coarse_sync`)
+    pub max_decimation: usize,
+
+    /// Sweep-cycle candidates re-scored around the peak, nearest first
+    /// (1 = take the peak; see `sync_ambiguity`)
+    pub max_candidates: usize,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            thresholds: SyncThresholds::default(),
+            search_window: None,
+            max_decimation: MAX_DECIMATION,
+            max_candidates: DEFAULT_SYNC_CANDIDATES,
+        }
+    }
+}
+
+impl SyncConfig {
+    pub fn with_thresholds(mut self, thresholds: SyncThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// Search preamble starts in the first `samples` of a capture only
+    pub fn with_search_window(mut self, samples: usize) -> Self {
+        self.search_window = Some(samples);
+        self
+    }
+
+    pub fn with_max_decimation(mut self, factor: usize) -> Self {
+        assert!(factor >= 1, "Decimation factor must be at least 1");
+        self.max_decimation = factor;
+        self
+    }
+
+    pub fn with_max_candidates(mut self, candidates: usize) -> Self {
+        assert!(candidates >= 1, "Need at least the peak as a candidate");
+        self.max_candidates = candidates;
+        self
+    }
+}
+
+/// Synchronizes against the preamble of the configured tone alphabet,
+/// with `config.sync.thresholds`
+pub fn synchronize_signal_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<SyncResult> {
+    synchronize_signal_with_thresholds::<B>(device, signal, config, &config.sync.thresholds)
+}
+
+/// `synchronize_signal_with_config` with explicit detection thresholds
+pub fn synchronize_signal_with_thresholds<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    thresholds: &SyncThresholds,
+) -> Option<SyncResult> {
+    let metrics = measure_sync::<B>(device, signal, config)?;
+    if !thresholds.accepts(&metrics) {
+        return None;
+    }
+    Some(SyncResult::measure(device, signal, &metrics, config))
+}
+
+/// Detection metrics of the strongest preamble correlation peak
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncMetrics {
+    /// Peak position (preamble start, samples)
+    pub position: usize,
+
+    /// |Correlation| at the peak over the preamble norm
+    pub correlation: f32,
+
+    /// Squared-correlation peak over its mean
+    pub peak_to_noise: f32,
+}
+
+/// Accepted preamble detection with the quality of the lock
+///
+/// The detection metrics plus what `measure` adds on top of them. Lets
+/// callers weigh a lock instead of re-measuring it: the repetition
+/// combiner and the RAKE can down-weight a marginal slot, logs get the
+/// numbers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncResult {
+    /// Preamble start, correlation and peak-to-noise of the accepted peak
+    pub metrics: SyncMetrics,
+
+    /// Peak power over the correlation floor around it (dB)
+    pub snr_estimate_db: f32,
+
+    /// Residual carrier offset from the preamble notes (Hz, positive =
+    /// received high); None if the preamble runs off the signal
+    pub cfo_estimate: Option<f64>,
+}
+
+impl SyncResult {
+    /// Quality of the lock at `metrics.position`
+    ///
+    /// The SNR compares the peak with the correlation one to three preamble
+    /// lengths either side of it (`estimate_snr_from_correlation`), so a
+    /// long capture costs no extra full-length pass.
+    ///
+    /// ⚠️ **SYNC POINT**
+    pub fn measure<B: Backend + FftBackend>(
+        device: &B::Device,
+        signal: &Tensor<B, 1>,
+        metrics: &SyncMetrics,
+        config: &ModemConfig,
+    ) -> Self {
+        let preamble = generate_bach_preamble_with_config::<B>(device, config);
+        let preamble_len = preamble.dims()[0];
+        let signal_len = signal.dims()[0];
+
+        let start = metrics.position.saturating_sub(3 * preamble_len);
+        let end = (metrics.position + 4 * preamble_len).min(signal_len);
+        let window = signal.clone().slice([start..end]);
+        let (correlations, _, _) = synchronize_signal_gpu(device, &window, &preamble);
+        let snr_estimate_db = estimate_snr_from_correlation(&correlations, metrics.position - start, preamble_len);
+
+        Self {
+            metrics: *metrics,
+            snr_estimate_db,
+            cfo_estimate: preamble_frequency_offset(device, signal, metrics.position, config),
+        }
+    }
+}
+
+impl SyncThresholds {
+    /// True if `metrics` reach both thresholds
+    pub fn accepts(&self, metrics: &SyncMetrics) -> bool {
+        metrics.correlation >= self.min_correlation && metrics.peak_to_noise >= self.min_peak_to_noise
+    }
+}
+
+/// Correlate against the preamble and measure the strongest peak, without
+/// a detection decision; None if the signal is shorter than the preamble
+/// 
+/// With `ModemConfig::two_stage_sync` the peak is searched on a decimated
+/// copy first (see `coarse_sync`). Only starts within
+/// `config.sync.search_window` are searched.
+/// 
+/// ⚠️ **SYNC POINT**: Downloads the peak metrics
+pub fn measure_sync<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<SyncMetrics> {
+    let signal = match config.sync.search_window {
+        Some(window) => {
+            let preamble_len = preamble_tone_sequence(config).len() * config.preamble_note_samples();
+            let end = (window + preamble_len).min(signal.dims()[0]);
+            signal.clone().slice([0..end])
+        }
+        None => signal.clone(),
+    };
+    if config.two_stage_sync {
+        return measure_sync_two_stage::<B>(device, &signal, config);
+    }
+    measure_sync_full_rate::<B>(device, &signal, config)
+}
+
+/// `measure_sync` at the full sample rate over the whole signal
+/// 
+/// ⚠️ **SYNC POINT**: Downloads the peak metrics
+pub(crate) fn measure_sync_full_rate<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<SyncMetrics> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
     let preamble_len = preamble.dims()[0];
     let signal_len = signal.dims()[0];
     
-    println!("    [Sync] Signal len: {}, Preamble len: {}", signal_len, preamble_len);
-    
     if signal_len < preamble_len {
-        println!("    [Sync] Signal too short!");
         return None;
     }
     
     // FFT correlation is fast enough - no need to decimate!
-    // This avoids aliasing (max freq 1174 Hz > 1000 Hz Nyquist at decimation 4)
+    // This avoids aliasing (max freq 1174 Hz > 1000 Hz Nyquist at decimation 4);
+    // long recordings decimate with a proper low-pass in `coarse_sync`
     let decim_factor = 1; 
-    println!("    [Sync] No decimation for full accuracy...");
     
     let decim_signal_tensor = signal.clone();
     let decim_preamble_tensor = preamble.clone();
     
-    println!("    [Sync] Coarse correlation: {} samples vs {} preamble", 
-             decim_signal_tensor.dims()[0], decim_preamble_tensor.dims()[0]);
-    
     // Coarse correlation on decimated signal
     let (correlations_coarse, _, _) = synchronize_signal_gpu(device, &decim_signal_tensor, &decim_preamble_tensor);
     
-    println!("    [Sync] Finding coarse peak...");
-    
-    println!("    [Sync] Non-coherent integration (GPU-only for speed)...");
     // Square for non-coherent integration - STAY ON GPU to avoid CPU bottleneck
-    let correlations_squared: Tensor<B, 1> = correlations_coarse.clone().powf_scalar(2.0);
+    let correlations_squared: Tensor<B, 1> = correlations_coarse.powf_scalar(2.0);
     
     // Find max on GPU (avoids slow CPU download + sorting)
     let (max_val_tensor, max_idx_tensor) = correlations_squared.clone().max_dim_with_indices(0);
@@ -189,30 +390,163 @@ pub fn synchronize_signal<B: Backend + FftBackend>(
     let mean_val: f32 = correlations_squared.clone().mean().into_scalar().elem::<f32>();
     let peak_to_noise_ratio = peak_val / (mean_val + 1e-10);
     
-    // Extract metrics for threshold check  
-    let (max_corr_tensor, _) = correlations_coarse.max_dim_with_indices(0);
-    let max_corr_val: f32 = max_corr_tensor.into_scalar().elem::<f32>();
+    // Extract metrics for threshold check: |correlation| at the power peak,
+    // so a phase-inverted preamble rates the same (as in `find_all_preambles`)
     let preamble_energy: f32 = decim_preamble_tensor.clone().powf_scalar(2.0).sum().into_scalar().elem::<f32>();
-    let normalized_correlation = max_corr_val / preamble_energy.sqrt();
+    let normalized_correlation = (peak_val / preamble_energy).sqrt();
     
-    println!("    [Sync] Corr: {:.4}, Peak: {:.6}, P/N: {:.2}, Pos: {}", 
-             normalized_correlation, peak_val, peak_to_noise_ratio, best_position);
+    Some(SyncMetrics { position: best_position, correlation: normalized_correlation, peak_to_noise: peak_to_noise_ratio })
+}
+
+/// First data sample after the preamble, with sweep-cycle ambiguity resolved
+/// 
+/// Runs `measure_sync` against `config.sync.thresholds` (no `SyncResult`
+/// lock-quality measurement), then checks the starts one preamble
+/// sweep cycle away from the peak (see `sync_ambiguity`). Also finds frames
+/// whose preamble began before the capture.
+/// 
+/// ⚠️ **SYNC POINT**
+pub fn synchronize_data_start_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<usize> {
+    synchronize_data_start_with_thresholds::<B>(device, signal, config, &config.sync.thresholds)
+}
 
-    // WSPR-style adaptive threshold for -30 dB (relaxed for 4x decimation)
-    const CORRELATION_THRESHOLD: f32 = 0.025;  // Very aggressive for -30 dB
-    const PEAK_TO_NOISE_THRESHOLD: f32 = 1.3; // Relaxed (weak signal)
+/// `synchronize_data_start_with_config` with explicit detection thresholds
+/// 
+/// ⚠️ **SYNC POINT**
+pub fn synchronize_data_start_with_thresholds<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    thresholds: &SyncThresholds,
+) -> Option<usize> {
+    // Only the position is needed: skip `SyncResult::measure`
+    let metrics = measure_sync::<B>(device, signal, config).filter(|metrics| thresholds.accepts(metrics))?;
+    let sync_pos = metrics.position;
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    Some(resolve_sync_ambiguity(device, signal, &preamble, sync_pos, config))
+}
+
+/// Minimum correlation z-score (ρ·√N) to trust a flourish timing measurement
+const FLOURISH_RESYNC_MIN_Z: f32 = 5.0;
+
+/// Measures the timing offset of a flourish near its expected position
+/// 
+/// Correlates the flourish template against a window of ± `search` samples
+/// around `expected_pos` (expected flourish start in `signal`).
+/// 
+/// Returns: (offset in samples, normalized correlation ρ), or None if the
+/// window runs off the signal or the peak is indistinguishable from noise.
+/// 
+/// ⚠️ **SYNC POINT**: Downloads the correlation peak
+pub fn measure_flourish_offset<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    flourish: &Tensor<B, 1>,
+    expected_pos: usize,
+    search: usize,
+) -> Option<(isize, f32)> {
+    let signal_len = signal.dims()[0];
+    let flourish_len = flourish.dims()[0];
     
-    if normalized_correlation < CORRELATION_THRESHOLD {
-        println!("    [Sync] Failed: correlation {:.4} < {:.4}", normalized_correlation, CORRELATION_THRESHOLD);
+    let win_start = expected_pos.saturating_sub(search);
+    let win_end = (expected_pos + flourish_len + search).min(signal_len);
+    if win_end < win_start + flourish_len {
         return None;
     }
     
-    if peak_to_noise_ratio < PEAK_TO_NOISE_THRESHOLD {
-        println!("    [Sync] Failed: peak/noise {:.2} < {:.2}", peak_to_noise_ratio, PEAK_TO_NOISE_THRESHOLD);
+    let window = signal.clone().slice([win_start..win_end]);
+    let correlations = fft_cross_correlation(device, &window, flourish);
+    
+    // Non-coherent peak (carrier phase unknown after the SSB chain)
+    let (max_val, max_idx) = correlations.powf_scalar(2.0).max_dim_with_indices(0);
+    let peak: f32 = max_val.into_scalar().elem::<f32>().sqrt();
+    let best: usize = max_idx.into_scalar().elem::<i32>() as usize;
+    
+    // Normalized correlation against the aligned segment
+    let segment_energy: f32 = window.slice([best..best + flourish_len])
+        .powf_scalar(2.0).sum().into_scalar().elem::<f32>();
+    let flourish_energy: f32 = flourish.clone().powf_scalar(2.0).sum().into_scalar().elem::<f32>();
+    let rho = peak / ((segment_energy * flourish_energy).sqrt() + 1e-10);
+    
+    if rho * (flourish_len as f32).sqrt() < FLOURISH_RESYNC_MIN_Z {
         return None;
     }
     
-    Some(best_position)
+    Some(((win_start + best) as isize - expected_pos as isize, rho))
+}
+
+/// Flourish search each side of its expected position for overlap detection (symbols)
+const FLOURISH_OVERLAP_SEARCH_SYMBOLS: usize = 2;
+
+/// Where a flourish really is, searched wider than the re-sync window
+/// 
+/// Drift that accumulates between anchors moves the flourish into the
+/// data windows laid out by dead reckoning around it. The whole template is
+/// correlated ± `FLOURISH_OVERLAP_SEARCH_SYMBOLS` symbols around
+/// `expected_pos`; returns the flourish span [start, end) in `signal`, or
+/// None if it isn't distinguishable from noise.
+/// 
+/// ⚠️ **SYNC POINT**: Downloads the correlation peak
+fn locate_flourish<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    flourish: &Tensor<B, 1>,
+    expected_pos: usize,
+    config: &ModemConfig,
+) -> Option<std::ops::Range<isize>> {
+    let search = FLOURISH_OVERLAP_SEARCH_SYMBOLS * config.symbol_samples();
+    let (offset, _) = measure_flourish_offset(device, signal, flourish, expected_pos, search)?;
+    let start = expected_pos as isize + offset;
+    Some(start..start + flourish.dims()[0] as isize)
+}
+
+/// Symbols whose window overlaps a flourish span by more than 1/8 symbol
+/// 
+/// The Gaussian tails of a window carry little energy, so a few samples of
+/// overlap don't matter; more puts structured flourish notes into the
+/// matched filters.
+fn flourish_overlaps(starts: &[isize], symbol_len: usize, spans: &[std::ops::Range<isize>]) -> Vec<bool> {
+    let min_overlap = (symbol_len / 8) as isize;
+    starts.iter()
+        .map(|&start| {
+            let end = start + symbol_len as isize;
+            spans.iter().any(|span| end.min(span.end) - start.max(span.start) > min_overlap)
+        })
+        .collect()
+}
+
+/// Re-aligns the symbol clock on a flourish
+/// 
+/// Returns the measured flourish start, or the dead-reckoned `expected_pos`
+/// if the flourish could not be found, and whether the symbol clock can be
+/// trusted afterwards. A peak on the edge of the search window means the
+/// true flourish lies outside it: the following symbols are misaligned.
+fn resync_on_flourish<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    flourish: &Tensor<B, 1>,
+    expected_pos: usize,
+    config: &ModemConfig,
+) -> (usize, bool) {
+    // Search ± half a symbol: covers clock drift and short sample slips
+    let search = config.symbol_samples() / 2;
+    
+    match measure_flourish_offset(device, signal, flourish, expected_pos, search) {
+        Some((offset, rho)) => {
+            let aligned = offset.unsigned_abs() < search;
+            if !aligned {
+                println!("  [Decoder] Flourish at edge of search window ({:+} samples): erasing segment", offset);
+            } else if offset != 0 {
+                println!("  [Decoder] Flourish re-sync: {:+} samples (ρ = {:.3})", offset, rho);
+            }
+            ((expected_pos as isize + offset).max(0) as usize, aligned)
+        }
+        None => (expected_pos, true),
+    }
 }
 
 /// Demodulates FH-DPSK signal with proper synchronization and matched filtering
@@ -223,19 +557,38 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
     use_sync: bool,
     flourish_interval: usize,
 ) -> Vec<u8> {
-    let symbol_len = (SYMBOL_DURATION * FS) as usize;
-    let flourish_len = generate_bach_flourish::<B>(device).dims()[0];
+    demodulate_fhdpsk_ex_with_config::<B>(device, signal, use_sync, &ModemConfig::default().with_flourish_interval(flourish_interval))
+}
+
+/// Hard-decision demodulation for the tone alphabet described by `config`
+pub fn demodulate_fhdpsk_ex_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+) -> Vec<u8> {
+    // Mistuned transmitter: remove the carrier offset ahead of sync
+    let corrected;
+    let signal = if config.afc_range_hz > 0.0 {
+        corrected = correct_frequency_offset::<B>(device, signal, config);
+        &corrected
+    } else {
+        signal
+    };
+    
+    let symbol_len = config.symbol_samples();
+    let flourish = generate_bach_flourish_with_config::<B>(device, config);
+    let flourish_len = flourish.dims()[0];
+    let lag = config.lag();
     
     let mut signal_data = signal.clone();
     
     if use_sync {
         // Find preamble via correlation
-        match synchronize_signal::<B>(device, signal) {
-            Some(sync_pos) => {
-                println!("  [Decoder] Found preamble at position {}", sync_pos);
+        match synchronize_data_start_with_config::<B>(device, signal, config) {
+            Some(start_pos) => {
+                println!("  [Decoder] Data starts at position {}", start_pos);
                 
-                let preamble_len = generate_bach_preamble::<B>(device).dims()[0];
-                let start_pos = sync_pos + preamble_len;
                 let signal_len = signal.dims()[0];
                 
                 if signal_len <= start_pos {
@@ -254,24 +607,40 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
     
     let signal_len = signal_data.dims()[0];
     
+    // Chirp symbols: frequency offset moves the correlation peak
+    let bank = (config.chirp_span_hz > 0.0 || config.timing_recovery).then(|| matched_filter_bank::<B>(device, config));
+    let chirp_offset = match &bank {
+        Some(bank) if config.chirp_span_hz > 0.0 => estimate_chirp_offset(device, &signal_data, bank, config),
+        _ => 0,
+    };
+    let end = |pos: usize| pos as isize + chirp_offset + symbol_len as isize;
+    
     // Extract symbols, skipping flourishes at expected positions
     let mut symbol_chunks = Vec::new();
+    let mut starts = Vec::new();
     let mut pos = 0;
     let mut symbol_idx = 0;
     
-    while pos + symbol_len <= signal_len {
+    while end(pos) <= signal_len as isize {
         // Check if we should skip a flourish here
-        if flourish_interval > 0 && symbol_idx > 0 && symbol_idx % flourish_interval == 0 {
-            // Skip the flourish
+        if config.flourish_interval > 0 && symbol_idx > 0 && symbol_idx % config.flourish_interval == 0 {
+            // Re-align on the flourish, then skip it
+            pos = resync_on_flourish(device, &signal_data, &flourish, pos, config).0;
             pos += flourish_len;
-            if pos + symbol_len > signal_len {
+            if end(pos) > signal_len as isize {
                 break;
             }
             println!("  [Decoder] Skipping flourish at symbol position {}", symbol_idx);
         }
         
-        let chunk = signal_data.clone().slice([pos..pos + symbol_len]);
+        let start = pos as isize + chirp_offset;
+        let chunk = if start >= 0 {
+            signal_data.clone().slice([start as usize..start as usize + symbol_len])
+        } else {
+            Tensor::zeros([symbol_len], device)
+        };
         symbol_chunks.push(chunk);
+        starts.push(start);
         pos += symbol_len;
         symbol_idx += 1;
     }
@@ -285,8 +654,20 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
     
     println!("  [Decoder] Extracted {} data symbols", num_symbols);
     
+    // Sub-sample timing: every window re-cut at its symbol's peak
+    if let Some(bank) = bank.filter(|_| config.timing_recovery) {
+        let melody = config.melody_indices(num_symbols);
+        let valid: Vec<bool> = starts.iter().map(|&start| start >= 0).collect();
+        let offsets = recover_symbol_timing(device, &signal_data, &starts, &valid, &bank, &melody, &mut TimingLoop::default());
+        println!("  [Decoder] Symbol timing {:+.2} samples at the start, {:+.2} at the end",
+                 offsets[0], offsets[num_symbols - 1]);
+        let windows = interpolate_symbols(&signal_data, &starts, &offsets, Tensor::stack(symbol_chunks, 0));
+        symbol_chunks = windows.chunk(num_symbols, 0).into_iter().map(|w| w.reshape([symbol_len])).collect();
+    }
+    
     // Matched filtering: correlate each symbol with expected wavelet
-    let melody_indices = get_melody_indices(num_symbols);
+    let melody_indices = config.melody_indices(num_symbols);
+    let frequencies = config.frequencies();
     
     println!("  [Decoder] Performing matched filtering...");
     
@@ -296,11 +677,12 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
     
     for (sym_idx, &melody_idx) in melody_indices.iter().enumerate() {
         // Generate reference wavelet (conjugate for correlation)
-        let (real_ref, imag_ref) = morlet_wavelet::<B>(
+        let (real_ref, imag_ref) = shaped_wavelet::<B>(
             device,
-            BACH_FREQUENCIES[melody_idx],
-            SYMBOL_DURATION,
-            FS,
+            frequencies[melody_idx],
+            config.symbol_duration,
+            config.wavelet_shape(),
+            config.sample_rate,
         );
         
         // Get this symbol's chunk
@@ -322,51 +704,53 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
     
     // Single sync at the end to get all angles
     let angles_data = angles_tensor.into_data();
-    let mut correlations: Vec<f64> = angles_data.to_vec::<f32>().unwrap()
+    let correlations: Vec<f64> = angles_data.to_vec::<f32>().unwrap()
         .iter().map(|&x| x as f64).collect();
     
-    // Differential decoding with Lag 16
-    println!("  [Decoder] Differential decoding (Lag-16)...");
+    // Differential decoding with Lag = num_tones
+    println!("  [Decoder] Differential decoding (Lag-{})...", lag);
     
-    let trunc_len = (correlations.len() / 16) * 16;
-    correlations.truncate(trunc_len);
+    let layout = config.reference_layout();
+    let pairs = layout.pairs(correlations.len());
     
-    if trunc_len < 32 {
-        println!("  [Decoder] Insufficient symbols for decoding (need at least 32)");
+    if pairs.is_empty() {
+        println!("  [Decoder] Insufficient symbols for decoding (need at least {})", layout.num_symbols(1));
         return Vec::new();
     }
     
-    let num_blocks = trunc_len / 16;
-    println!("  [Decoder] Processing {} blocks", num_blocks);
+    println!("  [Decoder] Processing {} blocks", correlations.len() / lag);
     
     // Calculate phase differences
     let mut detected_bits = Vec::new();
     
-    for block_idx in 1..num_blocks {
-        for freq_idx in 0..16 {
-            let curr_idx = block_idx * 16 + freq_idx;
-            let prev_idx = (block_idx - 1) * 16 + freq_idx;
-            
-            let mut diff = correlations[curr_idx] - correlations[prev_idx];
-            
-            // Wrap to [-π, π]
-            while diff > PI {
-                diff -= 2.0 * PI;
-            }
-            while diff < -PI {
-                diff += 2.0 * PI;
-            }
-            
-            // Decision: |diff| > π/2 => bit = 1
-            let bit = if diff.abs() > PI / 2.0 { 1 } else { 0 };
-            detected_bits.push(bit);
+    for (k, &curr_idx) in pairs.current.iter().enumerate() {
+        // Baseline: weighted circular mean over the reference group (or the
+        // previous block, or the pilots around it)
+        let (sin, cos) = pairs.references.iter()
+            .zip(&pairs.weights)
+            .map(|(row, weights)| (correlations[row[k]], weights[k] as f64))
+            .fold((0.0, 0.0), |(sin, cos), (angle, w): (f64, f64)| (sin + w * angle.sin(), cos + w * angle.cos()));
+        
+        let mut diff = correlations[curr_idx] - sin.atan2(cos);
+        
+        // Wrap to [-π, π]
+        while diff > PI {
+            diff -= 2.0 * PI;
         }
+        while diff < -PI {
+            diff += 2.0 * PI;
+        }
+        
+        // Decision: nearest phase point (binary: |diff| > π/2 => bit = 1)
+        detected_bits.extend(config.dpsk_order.decide(diff));
     }
     
-    // Note: The first differential block (block_1 - block_0) represents the FIRST 16 data bits
-    // We don't need to remove anything - the reference block (block 0) is never output
+    // Note: reference blocks are never output, only the data blocks' decisions
     
     println!("  [Decoder] Decoded {} bits", detected_bits.len());
+    if config.scrambler {
+        scramble_bits(&mut detected_bits);
+    }
 
     
     let decoded_bytes = pack_bits(&detected_bits);
@@ -380,35 +764,197 @@ pub fn demodulate_fhdpsk_ex<B: Backend + FftBackend>(
 /// Returns: Tensor of LLRs [NumBits]
 /// Positive LLR -> Bit 0
 /// Negative LLR -> Bit 1
+/// 
+/// DQPSK / 8-DPSK configurations (`ModemConfig::dpsk_order`) return
+/// `bits_per_symbol` max-log LLRs per data symbol through the `_with_config`
+/// variants (see `dpsk`).
 pub fn demodulate_fhdpsk_soft<B: Backend + FftBackend>(
     device: &B::Device,
     signal: &Tensor<B, 1>,
     use_sync: bool,
     flourish_interval: usize,
 ) -> Tensor<B, 1> {
-    let symbol_len = (SYMBOL_DURATION * FS) as usize;
-    let flourish_len = generate_bach_flourish::<B>(device).dims()[0];
+    demodulate_fhdpsk_soft_with_config::<B>(device, signal, use_sync, &ModemConfig::default().with_flourish_interval(flourish_interval))
+}
+
+/// Soft demodulation for the tone alphabet described by `config`
+/// 
+/// Returns: Tensor of LLRs [NumBits], differential lag = `config.lag()`
+/// 
+/// Symbols hit by an audio dropout (energy gap) are detected and every LLR
+/// touching them is nulled to an erasure (see `dropout`), as are symbols
+/// demodulated on a symbol clock lost at a flourish.
+pub fn demodulate_fhdpsk_soft_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+) -> Tensor<B, 1> {
+    demodulate_soft_impl::<B>(device, signal, use_sync, config, None)
+}
+
+/// Soft demodulator core
+/// 
+/// `expected_symbols`: extract exactly this many symbols, erasing the ones
+/// the signal doesn't cover (None = stop at the end of the signal)
+fn demodulate_soft_impl<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    expected_symbols: Option<usize>,
+) -> Tensor<B, 1> {
+    match demodulate_stats_impl::<B>(device, signal, use_sync, config, expected_symbols) {
+        Some(stats) => {
+            let llrs = stats.analytic_llrs();
+            stats.erase(llrs)
+        }
+        None => Tensor::zeros([1], device), // Return dummy small tensor on failure
+    }
+}
+
+/// Per-bit differential detector statistics, before the LLR mapping
+#[derive(Clone, Debug)]
+pub struct DemodStatistics<B: Backend> {
+    /// Phasor dot product of each data symbol with its reference [num_decisions]
+    ///
+    /// The reference is the symbol `lag` earlier, or the mean of the
+    /// reference group before it (see `reference_blocks`). One decision per
+    /// bit for binary DPSK.
+    pub dot: Tensor<B, 1>,
+
+    /// Imaginary part of the same product, the quadrature of the phase
+    /// step (used by DQPSK / 8-DPSK) [num_decisions]
+    pub cross: Tensor<B, 1>,
+
+    /// Matched-filter amplitude of the current symbol [num_decisions]
+    pub amp_curr: Tensor<B, 1>,
+
+    /// Matched-filter amplitude of the reference symbol [num_decisions]
+    pub amp_prev: Tensor<B, 1>,
+
+    /// Blind (M2M4) matched-filter SNR of the whole transmission in dB [1]
+    pub snr_db: Tensor<B, 1>,
+
+    /// Symbols in a dropout or not received intact
+    pub erased_symbols: Vec<bool>,
+
+    /// Window start of every symbol against its nominal position (samples):
+    /// chirp offset, flourish re-syncs, drift tracking and, with timing
+    /// recovery, the fractional correction
+    pub symbol_offsets: Vec<f32>,
+
+    /// Symbols each bit compares, from the configuration's reference layout
+    pub pairs: DifferentialPairs,
+
+    /// Phase points of the differential step
+    pub order: DpskOrder,
+
+    /// Bits the decisions carry: `bits_per_symbol` per decision, less the
+    /// padding of a partly used last symbol
+    pub bits: usize,
+
+    /// Whitening signs of the bit LLRs of multi-bit symbols [bits]
+    ///
+    /// Binary DPSK flips the sign of `dot` instead, so `dot` stays the
+    /// descrambled statistic a calibrator learns from.
+    pub bit_signs: Option<Tensor<B, 1>>,
+}
+
+impl<B: Backend> DemodStatistics<B> {
+    pub fn num_bits(&self) -> usize {
+        self.bits
+    }
+
+    /// LLR = cos(Δφ) · |reference|, the formula of `demodulate_fhdpsk_soft`
+    ///
+    /// Multi-bit symbols: the max-log LLRs of `DpskOrder::soft_bits`, which
+    /// reduce to the same formula for binary DPSK.
+    pub fn analytic_llrs(&self) -> Tensor<B, 1> {
+        if self.order == DpskOrder::Binary {
+            // Add epsilon to avoid division by zero
+            return self.dot.clone() / (self.amp_prev.clone() + 1e-6);
+        }
+        let llrs = self.order
+            .soft_bits(self.dot.clone(), self.cross.clone(), self.amp_prev.clone())
+            .slice([0..self.bits]);
+        match &self.bit_signs {
+            Some(signs) => llrs * signs.clone(),
+            None => llrs,
+        }
+    }
+
+    /// Erase every symbol on a tone marked missing (see `partial_band`)
+    pub fn erase_tones(&mut self, missing: &[bool], config: &ModemConfig) {
+        let melody = config.melody_indices(self.erased_symbols.len());
+        for (erased, tone) in self.erased_symbols.iter_mut().zip(melody) {
+            *erased |= missing[tone];
+        }
+    }
+
+    /// Zero the LLRs that touch an erased symbol
+    /// 
+    /// The next intact symbol of each tone slot re-anchors the phase chain.
+    pub fn erase(&self, llrs: Tensor<B, 1>) -> Tensor<B, 1> {
+        if self.erased_symbols.iter().any(|&e| e) {
+            let mask = self.pairs.erasure_mask(&self.erased_symbols, llrs.dims()[0]);
+            llrs * Tensor::<B, 1>::from_floats(mask.as_slice(), &llrs.device())
+        } else {
+            llrs
+        }
+    }
+}
+
+/// Sync, symbol extraction and matched filtering up to the differential
+/// detector statistics; None if sync fails or too few symbols remain
+fn demodulate_stats_impl<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    expected_symbols: Option<usize>,
+) -> Option<DemodStatistics<B>> {
+    // Mistuned transmitter: remove the carrier offset ahead of sync
+    let corrected;
+    let signal = if config.afc_range_hz > 0.0 {
+        corrected = correct_frequency_offset::<B>(device, signal, config);
+        &corrected
+    } else {
+        signal
+    };
+    
+    let symbol_len = config.symbol_samples();
+    let flourish = generate_bach_flourish_with_config::<B>(device, config);
+    let flourish_len = flourish.dims()[0];
+    let lag = config.lag();
     
     let mut signal_data = signal.clone();
     
     if use_sync {
-        match synchronize_signal::<B>(device, signal) {
-            Some(sync_pos) => {
-                let preamble_len = generate_bach_preamble::<B>(device).dims()[0];
-                let start_pos = sync_pos + preamble_len;
+        match synchronize_data_start_with_config::<B>(device, signal, config) {
+            Some(start_pos) => {
                 let signal_len = signal.dims()[0];
                 if signal_len > start_pos {
                     signal_data = signal.clone().slice([start_pos..signal_len]);
                 } else {
-                    return Tensor::zeros([1], device); // Return dummy small tensor on failure
+                    return None;
                 }
             }
-            None => return Tensor::zeros([1], device),
+            None => return None,
         }
     }
     
     let signal_len = signal_data.dims()[0];
     
+    // Receive filters: [NumTones, SymbolLen], inverse pre-emphasis, conjugated
+    let bank = matched_filter_bank::<B>(device, config);
+    
+    // Chirp symbols: frequency offset moves the correlation peak
+    let chirp_offset = estimate_chirp_offset(device, &signal_data, &bank, config);
+    if chirp_offset != 0 {
+        println!("  [Decoder] Chirp peak offset: {} samples", chirp_offset);
+    }
+    
     // 1. Extract Symbols into a Batch Tensor
     // We have to handle flourishes, so we can't just reshape.
     // We'll collect valid symbol segments.
@@ -417,107 +963,275 @@ pub fn demodulate_fhdpsk_soft<B: Backend + FftBackend>(
     let mut pos = 0;
     let mut symbol_idx = 0;
     
-    while pos + symbol_len <= signal_len {
-        if flourish_interval > 0 && symbol_idx > 0 && symbol_idx % flourish_interval == 0 {
-            pos += flourish_len;
-            if pos + symbol_len > signal_len { break; }
+    // Per-symbol validity: covered by the signal and on a trusted symbol clock
+    let mut valid = Vec::new();
+    let mut aligned = true;
+    
+    // Window starts and the flourishes actually found, for overlap erasure
+    let mut starts = Vec::new();
+    let mut flourish_spans = Vec::new();
+    let mut symbol_offsets = Vec::new();
+    
+    // Sample clock drift: windows placed on the rate through the anchors,
+    // the postamble one known before the first window if the length is
+    let mut drift = config.clock_drift_tracking.then(ClockDriftTracker::new);
+    let mut nominal = 0;
+    if let (Some(drift), Some(n)) = (drift.as_mut(), expected_symbols) {
+        let flourishes = if config.flourish_interval > 0 { n.saturating_sub(1) / config.flourish_interval } else { 0 };
+        let nominal_end = n * symbol_len + flourishes * flourish_len;
+        if let Some(measured) = measure_postamble_drift(device, &signal_data, nominal_end, config) {
+            drift.record(nominal_end, measured);
+        }
+    }
+    
+    loop {
+        if config.flourish_interval > 0 && symbol_idx > 0 && symbol_idx % config.flourish_interval == 0 {
+            if let Some(drift) = &drift {
+                pos = drift.position(nominal);
+            }
+            if let Some(span) = locate_flourish(device, &signal_data, &flourish, pos, config) {
+                flourish_spans.push(span);
+            }
+            
+            // Flourishes double as timing anchors for long transmissions
+            let (flourish_pos, flourish_aligned) = resync_on_flourish(device, &signal_data, &flourish, pos, config);
+            if let Some(drift) = drift.as_mut().filter(|_| flourish_aligned) {
+                drift.record(nominal, flourish_pos);
+            }
+            pos = flourish_pos + flourish_len;
+            nominal += flourish_len;
+            aligned = flourish_aligned;
+        }
+        if let Some(drift) = drift.as_ref().filter(|_| aligned) {
+            pos = drift.position(nominal);
         }
         
-        let segment = signal_data.clone().slice([pos..pos + symbol_len]);
-        segments.push(segment);
+        let start = pos as isize + chirp_offset;
+        let available = start >= 0 && start as usize + symbol_len <= signal_len;
+        match expected_symbols {
+            None if !available && start >= 0 => break,
+            Some(n) if symbol_idx >= n => break,
+            _ => {}
+        }
+        
+        // Symbols the signal doesn't cover are zero-filled and erased below
+        if available {
+            let start = start as usize;
+            segments.push(signal_data.clone().slice([start..start + symbol_len]));
+        } else {
+            segments.push(Tensor::zeros([symbol_len], device));
+        }
+        valid.push(available && aligned);
+        starts.push(start);
+        symbol_offsets.push((start - nominal as isize) as f32);
         pos += symbol_len;
+        nominal += symbol_len;
         symbol_idx += 1;
     }
     
     let num_symbols = segments.len();
-    if num_symbols == 0 { return Tensor::zeros([1], device); }
+    if num_symbols == 0 { return None; }
+    if let Some(drift) = drift.as_ref().filter(|d| d.num_anchors() > 0) {
+        println!("  [Drift] Sample clock {:+.1} ppm from {} anchor(s)", drift.ppm(), drift.num_anchors());
+    }
+    
+    // Drifted flourishes inside data windows: erase those symbols, so each
+    // slot only contributes clean LLRs to the combiner
+    let overlapped = flourish_overlaps(&starts, symbol_len, &flourish_spans);
+    let num_overlapped = overlapped.iter().filter(|&&o| o).count();
+    if num_overlapped > 0 {
+        println!("  [Decoder] Flourish inside {} data windows: symbols erased", num_overlapped);
+    }
+    for (valid, overlapped) in valid.iter_mut().zip(overlapped) {
+        *valid &= !overlapped;
+    }
     
     // Stack: [NumSymbols, SymbolLen]
     let symbols_batch: Tensor<B, 2> = Tensor::stack(segments, 0);
     
+    // Sub-sample timing: every window re-cut at its symbol's peak
+    let symbols_batch = if config.timing_recovery {
+        let melody = config.melody_indices(num_symbols);
+        let offsets = recover_symbol_timing(device, &signal_data, &starts, &valid, &bank, &melody, &mut TimingLoop::default());
+        println!("  [Decoder] Symbol timing {:+.2} samples at the start, {:+.2} at the end",
+                 offsets[0], offsets[num_symbols - 1]);
+        for (offset, fraction) in symbol_offsets.iter_mut().zip(&offsets) {
+            *offset += *fraction as f32;
+        }
+        interpolate_symbols(&signal_data, &starts, &offsets, symbols_batch)
+    } else {
+        symbols_batch
+    };
+    
+    // Dropout detection (energy gaps) - LLRs touching these are erased below
+    let dropped = detect_dropouts_gpu(&symbols_batch, DROPOUT_THRESHOLD);
+    for gap in group_dropouts(&dropped) {
+        println!("  [Decoder] Dropout: symbols {}..{} ({} symbols erased)", 
+                 gap.start_symbol, gap.end_symbol, gap.len());
+    }
+    
     // 2. Matched Filtering on GPU
     // We need the reference wavelet for each symbol position.
     // The melody sequence is deterministic.
-    let melody_indices = get_melody_indices(num_symbols);
-    
-    // Pre-generate all 16 unique wavelets: [16, SymbolLen]
-    let mut unique_wavelets_real = Vec::new();
-    let mut unique_wavelets_imag = Vec::new();
-    
-    for i in 0..16 {
-        let (r, im) = morlet_wavelet::<B>(device, BACH_FREQUENCIES[i], SYMBOL_DURATION, FS);
-        unique_wavelets_real.push(r);
-        unique_wavelets_imag.push(im.neg()); // Conjugate for correlation
-    }
-    let bank_real: Tensor<B, 2> = Tensor::stack(unique_wavelets_real, 0);
-    let bank_imag: Tensor<B, 2> = Tensor::stack(unique_wavelets_imag, 0);
-    
-    // Create indices tensor for gather
-    // gather expects indices of same dim as output? No, gather is tricky in Burn.
-    // Instead, let's just construct the reference batch by stacking clones (inefficient but easy)
-    // or better: use matrix multiplication if we can map symbols to frequencies.
-    
-    // Let's construct the batch of references.
-    // Since we are on CPU for the loop setup, we can just pick the right tensors from the bank.
-    // Actually, we can't easily index into the bank tensor from CPU indices without pulling data.
-    // But we have the bank tensors on GPU.
-    // We can just rebuild the list of references.
-    
-    // Optimization: Group symbols by frequency? No, order matters.
-    // Let's just stack the correct wavelets.
-    let mut refs_real_list = Vec::with_capacity(num_symbols);
-    let mut refs_imag_list = Vec::with_capacity(num_symbols);
-    
-    // We can reuse the bank slices to avoid re-generating, but we need to clone them to stack.
-    // Burn tensors are cheap to clone (Arc).
-    // But we need to slice the bank.
-    // bank_real.slice([idx..idx+1])
-    
-    for &idx in &melody_indices {
-        refs_real_list.push(bank_real.clone().slice([idx..idx+1]).reshape([symbol_len]));
-        refs_imag_list.push(bank_imag.clone().slice([idx..idx+1]).reshape([symbol_len]));
-    }
-    
-    let refs_real: Tensor<B, 2> = Tensor::stack(refs_real_list, 0); // [NumSymbols, SymbolLen]
-    let refs_imag: Tensor<B, 2> = Tensor::stack(refs_imag_list, 0);
-    
-    // Dot product along dim 1
-    // symbols_batch * refs
-    let corr_real = (symbols_batch.clone() * refs_real).sum_dim(1).reshape([num_symbols]);
-    let corr_imag = (symbols_batch * refs_imag).sum_dim(1).reshape([num_symbols]);
-    
-    // 3. Phase Extraction & Differential Decoding (Lag 16)
+    let melody_indices = config.melody_indices(num_symbols);
+    
+    // Leakage compensation correlates every symbol with the whole bank and
+    // removes the other tones' contributions (see `leakage`)
+    let compensation = if config.leakage_compensation {
+        leakage_compensation_matrix(config)
+            .inspect_err(|err| println!("  [Decoder] Leakage compensation off: {}", err))
+            .ok()
+    } else {
+        None
+    };
+    let corr = if let Some(compensation) = &compensation {
+        compensated_correlations(device, &symbols_batch, &bank, &melody_indices, compensation)
+    } else {
+        // Each symbol against its own hopping tone: pick the bank rows in
+        // melody order, then a row-wise dot product
+        let melody: Vec<i32> = melody_indices.iter().map(|&i| i as i32).collect();
+        let melody = Tensor::<B, 1, Int>::from_ints(melody.as_slice(), device);
+        bank.map(|b| (symbols_batch.clone() * b.select(0, melody.clone())).sum_dim(1).reshape([num_symbols]))
+    };
+    
+    // 3. Phase Extraction & Differential Decoding (Lag = num_tones)
     // We avoid explicit atan2 by using trigonometric identities.
     // LLR = cos(angle_curr - angle_prev) * amplitude_curr
     // cos(a - b) = cos(a)cos(b) + sin(a)sin(b)
     // cos(angle) = real / amp, sin(angle) = imag / amp
     // LLR = (real_curr*real_prev + imag_curr*imag_prev) / amp_prev
     
-    let trunc_len = (num_symbols / 16) * 16;
-    if trunc_len < 32 { return Tensor::zeros([1], device); }
-    
-    let corr_real_trunc = corr_real.slice([0..trunc_len]);
-    let corr_imag_trunc = corr_imag.slice([0..trunc_len]);
-    
-    // Current symbols: start at index 16
-    let real_curr = corr_real_trunc.clone().slice([16..trunc_len]);
-    let imag_curr = corr_imag_trunc.clone().slice([16..trunc_len]);
+    let trunc_len = (num_symbols / lag) * lag;
+    let pairs = config.reference_layout().pairs(trunc_len);
+    if pairs.is_empty() { return None; }
+    
+    let corr_trunc = corr.map(|c| c.slice([0..trunc_len]));
+    
+    // Data symbols, and their references: the symbol lag earlier, the
+    // mean over the reference group before them, or the pilot phase
+    // interpolated between the groups around them
+    let select = |indices: &[usize]| {
+        let indices: Vec<i32> = indices.iter().map(|&i| i as i32).collect();
+        let indices = Tensor::<B, 1, Int>::from_ints(indices.as_slice(), device);
+        corr_trunc.clone().map(|c| c.select(0, indices.clone()))
+    };
+    let curr = select(&pairs.current);
+    let prev = pairs.references.iter()
+        .zip(&pairs.weights)
+        .map(|(row, weights)| select(row).mul_real(Tensor::from_floats(weights.as_slice(), device)))
+        .reduce(|sum, row| sum + row)
+        .expect("at least one reference block");
+    
+    // Amplitudes of previous and current symbols
+    let amp_prev = prev.clone().abs();
+    let amp_curr = curr.clone().abs();
+    
+    // Dot product of phasors, whitening removed by flipping its sign (per
+    // bit LLR for multi-bit symbols)
+    let order = config.dpsk_order;
+    let bits = pairs.len() * order.bits_per_symbol();
+    let cross = curr.clone().dot_conj_im(prev.clone());
+    let mut dot = curr.dot_conj_re(prev);
+    let mut bit_signs = None;
+    if config.scrambler {
+        let signs: Vec<f32> = whitening_sequence(bits).iter()
+            .map(|&w| if w == 1 { -1.0 } else { 1.0 })
+            .collect();
+        let signs = Tensor::<B, 1>::from_floats(signs.as_slice(), device);
+        if order == DpskOrder::Binary {
+            dot = dot * signs;
+        } else {
+            bit_signs = Some(signs);
+        }
+    }
     
-    // Previous symbols: start at index 0, end at len-16
-    let real_prev = corr_real_trunc.slice([0..trunc_len - 16]);
-    let imag_prev = corr_imag_trunc.slice([0..trunc_len - 16]);
+    // M2M4 estimator on |c|²: constant-envelope PSK in complex Gaussian noise
+    // has M2 = S + N and M4 = S² + 4SN + 2N², so S = sqrt(2·M2² - M4)
+    let power = corr_trunc.norm_sqr();
+    let m2 = power.clone().mean();
+    let m4 = power.powf_scalar(2.0).mean();
+    let signal_power = (m2.clone().powf_scalar(2.0).mul_scalar(2.0) - m4).clamp_min(0.0).sqrt();
+    let noise_power = (m2 - signal_power.clone()).clamp_min(1e-12);
+    let snr_db = (signal_power / noise_power).clamp(1e-3, 1e4).log() * 10.0 / 2.302585;
     
-    // Amplitude of previous symbols
-    let amp_prev = (real_prev.clone().powf_scalar(2.0) + imag_prev.clone().powf_scalar(2.0)).sqrt();
+    // LLRs whose current or reference symbol fell in a dropout or was never
+    // received intact are erased
+    let erased_symbols: Vec<bool> = dropped.iter().zip(&valid).map(|(&d, &v)| d || !v).collect();
     
-    // Dot product of phasors
-    let dot_prod = real_curr * real_prev + imag_curr * imag_prev;
+    Some(DemodStatistics { dot, cross, amp_curr, amp_prev, snr_db, erased_symbols, symbol_offsets, pairs, order, bits, bit_signs })
+}
+
+/// Soft demodulation into exactly `num_bits` LLRs
+/// 
+/// Positions the received signal cannot support become erasures (0.0)
+/// instead of confidently wrong LLRs:
+/// - symbols past the end of the signal (truncated capture, RAKE output
+///   shortened by the longest finger delay)
+/// - symbols after a flourish whose re-sync peak sits on the edge of the
+///   search window (symbol clock lost until the next flourish)
+/// 
+/// If synchronization fails every position is erased.
+pub fn demodulate_fhdpsk_soft_erasures_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Tensor<B, 1> {
+    let expected_symbols = config.reference_layout().num_symbols(num_bits);
     
-    // LLR calculation
-    // Add epsilon to avoid division by zero
-    let llrs = dot_prod / (amp_prev + 1e-6);
+    let llrs = demodulate_soft_impl::<B>(device, signal, use_sync, config, Some(expected_symbols));
+    let len = llrs.dims()[0];
     
-    llrs
+    if len >= num_bits {
+        llrs.slice([0..num_bits])
+    } else {
+        Tensor::zeros([num_bits], device)
+    }
+}
+
+/// Differential detector statistics for exactly `num_bits` bits
+/// 
+/// Same sync, flourish re-sync and erasure bookkeeping as
+/// `demodulate_fhdpsk_soft_erasures_with_config`, stopping before the LLR
+/// mapping so a trained `LlrCalibrator` can replace the analytic formula.
+/// None if synchronization fails.
+pub fn demodulate_fhdpsk_stats_with_config<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    use_sync: bool,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Option<DemodStatistics<B>> {
+    let expected_symbols = config.reference_layout().num_symbols(num_bits);
+    
+    let stats = demodulate_stats_impl::<B>(device, signal, use_sync, config, Some(expected_symbols))?;
+    let decisions = num_bits.div_ceil(config.dpsk_order.bits_per_symbol());
+    Some(DemodStatistics {
+        dot: stats.dot.slice([0..decisions]),
+        cross: stats.cross.slice([0..decisions]),
+        amp_curr: stats.amp_curr.slice([0..decisions]),
+        amp_prev: stats.amp_prev.slice([0..decisions]),
+        bits: num_bits,
+        bit_signs: stats.bit_signs.map(|signs| signs.slice([0..num_bits])),
+        ..stats
+    })
+}
+
+/// `demodulate_fhdpsk_soft_erasures_with_config` on the output of `enhancer`
+/// 
+/// The enhancer (e.g. a trained `ConvDenoiser`) sees the whole slot, so it
+/// runs ahead of preamble sync as well as the data matched filters.
+pub fn demodulate_fhdpsk_soft_enhanced_with_config<B: Backend + FftBackend, E: SignalEnhancer<B>>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    enhancer: &E,
+    use_sync: bool,
+    config: &ModemConfig,
+    num_bits: usize,
+) -> Tensor<B, 1> {
+    let enhanced = enhancer.enhance(signal.clone());
+    demodulate_fhdpsk_soft_erasures_with_config::<B>(device, &enhanced, use_sync, config, num_bits)
 }
 
 /// Convenience wrapper for backwards compatibility
@@ -529,9 +1243,253 @@ pub fn demodulate_fhdpsk<B: Backend + FftBackend>(
     demodulate_fhdpsk_ex::<B>(device, signal, use_sync, 0)
 }
 
+/// Output of `StreamingDemodulator::push`, in stream order
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    /// Preamble found; first data sample, counted from the start of the stream
+    Synced { data_start: u64 },
+
+    /// Next LLRs of the current frame (positive -> bit 0)
+    Llrs(Vec<f32>),
+
+    /// All `num_bits` LLRs of the frame were emitted; searching again
+    FrameComplete,
+}
+
+/// Receive phase of a `StreamingDemodulator`
+#[derive(Clone, Debug)]
+enum StreamState {
+    Searching,
+    Receiving {
+        /// Matched-filter outputs of the frame's symbols received so far
+        corr_re: Vec<f32>,
+        corr_im: Vec<f32>,
+
+        /// Decisions emitted so far
+        decisions: usize,
+    },
+}
+
+/// Demodulator for audio arriving in chunks
+///
+/// The other demodulators need the whole capture as one tensor; a
+/// 15-minute capture is 7.2M samples. The stream keeps a bounded buffer
+/// instead: three preamble lengths while searching, less than one symbol
+/// (plus the newest chunk) while receiving. Per frame it holds one
+/// complex matched-filter output per symbol - the phase references of later
+/// decisions - and emits a decision's LLRs as soon as its symbol and all its
+/// references have arrived (with pilots: the group after it). After
+/// `num_bits` LLRs it skips the postamble and searches for the next
+/// preamble.
+///
+/// A preamble must pass `thresholds`, by default the strict acquisition
+/// thresholds of `SyncLockConfig`: a stream sees far more noise than a
+/// capture cut around one frame.
+///
+/// Plain matched filters only: flourishes, chirp offset search, leakage
+/// compensation and dropout erasure need the whole frame.
+pub struct StreamingDemodulator<B: Backend + FftBackend> {
+    pub config: ModemConfig,
+    pub thresholds: SyncThresholds,
+
+    /// Data bits per frame
+    pub num_bits: usize,
+
+    device: B::Device,
+    preamble: Tensor<B, 1>,
+    bank: ComplexTensor<B, 2>,
+    melody: Vec<usize>,
+    pairs: DifferentialPairs,
+    bit_signs: Vec<f32>,
+    postamble_len: usize,
+    buffer: Vec<f32>,
+    buffer_start: u64,
+
+    /// Samples still to drop before searching (the last frame's postamble)
+    skip: usize,
+    state: StreamState,
+}
+
+impl<B: Backend + FftBackend> StreamingDemodulator<B> {
+    pub fn new(device: &B::Device, config: ModemConfig, num_bits: usize) -> Self {
+        assert!(num_bits > 0, "Frames must carry at least one bit");
+        let layout = config.reference_layout();
+        let num_symbols = layout.num_symbols(num_bits);
+        let pairs = layout.pairs(num_symbols);
+        let bits = pairs.len() * pairs.bits_per_symbol;
+        let bit_signs = if config.scrambler {
+            whitening_sequence(bits).iter().map(|&w| if w == 1 { -1.0 } else { 1.0 }).collect()
+        } else {
+            vec![1.0; bits]
+        };
+        Self {
+            thresholds: SyncLockConfig::default().acquisition,
+            num_bits,
+            device: device.clone(),
+            preamble: generate_bach_preamble_with_config::<B>(device, &config),
+            bank: matched_filter_bank::<B>(device, &config),
+            melody: config.melody_indices(num_symbols),
+            pairs,
+            bit_signs,
+            postamble_len: generate_bach_postamble_with_config::<B>(device, &config).dims()[0],
+            buffer: Vec::new(),
+            buffer_start: 0,
+            skip: 0,
+            state: StreamState::Searching,
+            config,
+        }
+    }
+
+    pub fn with_thresholds(mut self, thresholds: SyncThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// True between `Synced` and `FrameComplete`
+    pub fn is_receiving(&self) -> bool {
+        matches!(self.state, StreamState::Receiving { .. })
+    }
+
+    /// Samples held for the next `push`
+    pub fn buffered_samples(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Feed the next chunk of audio
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads sync metrics while searching, the
+    /// matched-filter outputs and LLRs while receiving
+    pub fn push(&mut self, chunk: &[f32]) -> Vec<StreamEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        loop {
+            let progressed = match self.state {
+                StreamState::Searching => self.search(&mut events),
+                StreamState::Receiving { .. } => self.receive(&mut events),
+            };
+            if !progressed {
+                return events;
+            }
+        }
+    }
+
+    /// Drop the oldest `count` buffered samples
+    fn consume(&mut self, count: usize) {
+        self.buffer.drain(..count);
+        self.buffer_start += count as u64;
+    }
+
+    /// Look for a preamble whose peak lies at least two preamble lengths
+    /// before the buffer end
+    ///
+    /// A preamble cut off by the buffer end can still correlate at an
+    /// earlier sweep cycle; with the margin, any preamble that could alias
+    /// to the peak is entirely in the buffer and outscores the alias.
+    fn search(&mut self, events: &mut Vec<StreamEvent>) -> bool {
+        // The postamble is a shifted preamble sweep: never search it
+        let skipped = self.skip.min(self.buffer.len());
+        self.consume(skipped);
+        self.skip -= skipped;
+
+        let preamble_len = self.preamble.dims()[0];
+        if self.buffer.len() < 3 * preamble_len {
+            return false;
+        }
+        let signal = Tensor::<B, 1>::from_floats(self.buffer.as_slice(), &self.device);
+        let keep = 2 * preamble_len;
+        match measure_sync::<B>(&self.device, &signal, &self.config) {
+            Some(metrics) if self.thresholds.accepts(&metrics) && metrics.position + keep <= self.buffer.len() => {
+                let data_start = resolve_sync_ambiguity(&self.device, &signal, &self.preamble, metrics.position, &self.config);
+                events.push(StreamEvent::Synced { data_start: self.buffer_start + data_start as u64 });
+                self.consume(data_start);
+                self.state = StreamState::Receiving { corr_re: Vec::new(), corr_im: Vec::new(), decisions: 0 };
+                true
+            }
+            _ => {
+                self.consume(self.buffer.len() - keep);
+                false
+            }
+        }
+    }
+
+    /// Matched-filter the complete symbols in the buffer and emit the
+    /// decisions they complete; true once the frame is done
+    fn receive(&mut self, events: &mut Vec<StreamEvent>) -> bool {
+        let symbol_len = self.config.symbol_samples();
+        let StreamState::Receiving { corr_re, corr_im, decisions } = &mut self.state else {
+            return false;
+        };
+
+        // Each symbol against its own hopping tone, as in the batch path
+        let received = corr_re.len();
+        let count = (self.buffer.len() / symbol_len).min(self.melody.len() - received);
+        if count > 0 {
+            let symbols = Tensor::<B, 1>::from_floats(&self.buffer[..count * symbol_len], &self.device)
+                .reshape([count, symbol_len]);
+            let melody: Vec<i32> = self.melody[received..received + count].iter().map(|&i| i as i32).collect();
+            let melody = Tensor::<B, 1, Int>::from_ints(melody.as_slice(), &self.device);
+            let corr = self.bank.clone().map(|b| (symbols.clone() * b.select(0, melody.clone())).sum_dim(1).reshape([count]));
+            corr_re.extend(corr.re.into_data().to_vec::<f32>().unwrap());
+            corr_im.extend(corr.im.into_data().to_vec::<f32>().unwrap());
+            self.buffer.drain(..count * symbol_len);
+            self.buffer_start += (count * symbol_len) as u64;
+        }
+
+        // Decisions whose symbol and references have all arrived
+        let received = corr_re.len();
+        let pairs = &self.pairs;
+        let ready = (*decisions..pairs.len())
+            .take_while(|&k| pairs.current[k] < received && pairs.references.iter().all(|row| row[k] < received))
+            .count();
+        if ready > 0 {
+            let mut dot = Vec::with_capacity(ready);
+            let mut cross = Vec::with_capacity(ready);
+            let mut amp_prev = Vec::with_capacity(ready);
+            for k in *decisions..*decisions + ready {
+                let (mut prev_re, mut prev_im) = (0.0f32, 0.0f32);
+                for (row, weights) in pairs.references.iter().zip(&pairs.weights) {
+                    prev_re += weights[k] * corr_re[row[k]];
+                    prev_im += weights[k] * corr_im[row[k]];
+                }
+                let (curr_re, curr_im) = (corr_re[pairs.current[k]], corr_im[pairs.current[k]]);
+                dot.push(curr_re * prev_re + curr_im * prev_im);
+                cross.push(curr_im * prev_re - curr_re * prev_im);
+                amp_prev.push(prev_re.hypot(prev_im));
+            }
+            let llrs: Vec<f32> = self.config.dpsk_order.soft_bits(
+                Tensor::<B, 1>::from_floats(dot.as_slice(), &self.device),
+                Tensor::from_floats(cross.as_slice(), &self.device),
+                Tensor::from_floats(amp_prev.as_slice(), &self.device),
+            ).into_data().to_vec().unwrap();
+
+            // Whitening removed per bit; padding bits of the last symbol dropped
+            let first_bit = *decisions * pairs.bits_per_symbol;
+            let llrs: Vec<f32> = llrs.iter()
+                .zip(&self.bit_signs[first_bit..])
+                .take(self.num_bits.saturating_sub(first_bit))
+                .map(|(llr, sign)| llr * sign)
+                .collect();
+            *decisions += ready;
+            if !llrs.is_empty() {
+                events.push(StreamEvent::Llrs(llrs));
+            }
+        }
+
+        if *decisions < pairs.len() {
+            return false;
+        }
+        events.push(StreamEvent::FrameComplete);
+        self.state = StreamState::Searching;
+        self.skip = self.postamble_len;
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::TestBackend as FftTestBackend;
+    use crate::wavelet::{SYMBOL_DURATION, FS};
     use burn::backend::Wgpu;
     
     type TestBackend = Wgpu;
@@ -558,4 +1516,267 @@ mod tests {
         println!("Signal length: {}, expected: {}", signal.dims()[0], expected_len);
         assert_eq!(signal.dims()[0], expected_len);
     }
+    
+    #[test]
+    fn test_modulate_across_alphabets() {
+        let device = Default::default();
+        let data = b"Test"; // 32 bits
+        let symbol_len = (SYMBOL_DURATION * FS) as usize;
+        
+        for (num_tones, expected_symbols) in [(8, 40), (16, 48), (32, 64)] {
+            let config = ModemConfig::new(num_tones);
+            let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, data, false, &config);
+            
+            // Padded data plus one reference block of `num_tones` symbols
+            assert_eq!(signal.dims()[0], expected_symbols * symbol_len);
+        }
+    }
+    
+    #[test]
+    fn test_flourish_resync_after_sample_slip() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_flourish_interval(32);
+        let symbol_len = config.symbol_samples();
+        let data = b"Flourish anchors"; // 128 bits -> 144 symbols
+        
+        let signal = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, false, &config);
+        let mut samples: Vec<f32> = signal.into_data().to_vec().unwrap();
+        
+        // USB glitch drops 120 samples inside symbol 40 (after the first flourish)
+        let flourish_len = generate_bach_flourish_with_config::<FftTestBackend>(&device, &config).dims()[0];
+        let slip_at = 40 * symbol_len + flourish_len + symbol_len / 2;
+        samples.drain(slip_at..slip_at + 120);
+        
+        let slipped = Tensor::<FftTestBackend, 1>::from_floats(samples.as_slice(), &device);
+        let llrs: Vec<f32> = demodulate_fhdpsk_soft_with_config::<FftTestBackend>(&device, &slipped, false, &config)
+            .into_data().to_vec().unwrap();
+        
+        // Symbols from the flourish at 64 onward are re-aligned: bits 64.. decode cleanly
+        let bits = encode_bits(data);
+        let errors = (64..bits.len())
+            .filter(|&i| (llrs[i] < 0.0) as u8 != bits[i])
+            .count();
+        assert_eq!(errors, 0);
+    }
+    
+    #[test]
+    fn test_drifted_flourish_erases_overlapped_symbols() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_flourish_interval(16);
+        let symbol_len = config.symbol_samples();
+        let data = b"Flourish overlap"; // 128 bits -> 144 symbols
+        let signal = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, false, &config);
+        
+        // On time: nothing erased
+        let stats = demodulate_fhdpsk_stats_with_config::<FftTestBackend>(&device, &signal, false, &config, 128).unwrap();
+        assert!(stats.erased_symbols.iter().all(|&e| !e));
+        
+        // 240 samples lost in symbol 12: the flourish after symbol 15 starts
+        // inside its dead-reckoned window
+        let mut samples: Vec<f32> = signal.into_data().to_vec().unwrap();
+        samples.drain(12 * symbol_len..12 * symbol_len + 240);
+        let drifted = Tensor::<FftTestBackend, 1>::from_floats(samples.as_slice(), &device);
+        
+        let stats = demodulate_fhdpsk_stats_with_config::<FftTestBackend>(&device, &drifted, false, &config, 128).unwrap();
+        assert!(stats.erased_symbols[15]);
+        assert!(!stats.erased_symbols[14] && !stats.erased_symbols[16]);
+        
+        let llrs: Vec<f32> = stats.erase(stats.analytic_llrs()).into_data().to_vec().unwrap();
+        assert_eq!(llrs[15], 0.0);
+    }
+    
+    #[test]
+    fn test_reference_refresh_roundtrip() {
+        let device = Default::default();
+        let config = ModemConfig::narrowband().with_reference_blocks(2, 3).with_scrambler(true);
+        let data = b"Refreshed references"; // 160 bits -> 20 data blocks of 8
+        let bits = encode_bits(data);
+        
+        // 20 data blocks, 7 groups of 2 reference blocks
+        let signal = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, false, &config);
+        assert_eq!(signal.dims()[0], 34 * 8 * config.symbol_samples());
+        
+        let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<FftTestBackend>(
+            &device, &signal, false, &config, bits.len(),
+        ).into_data().to_vec().unwrap();
+        let errors = (0..bits.len()).filter(|&i| (llrs[i] < 0.0) as u8 != bits[i]).count();
+        assert_eq!(errors, 0);
+        
+        assert_eq!(demodulate_fhdpsk_ex_with_config::<FftTestBackend>(&device, &signal, false, &config), data.to_vec());
+    }
+    
+    #[test]
+    fn test_pilots_beat_differential_in_noise() {
+        use crate::modem_rng::{gaussian_noise, SplitMix64};
+        
+        let device = Default::default();
+        let differential = ModemConfig::narrowband().with_scrambler(true);
+        let coherent = differential.clone().with_reference_blocks(2, 4).with_pilots(4);
+        let data: Vec<u8> = (0..64u8).map(|i| i.wrapping_mul(37)).collect(); // 512 bits
+        let bits = encode_bits(&data);
+        
+        // 64 data blocks of 8: 16 intervals of 2 pilot + 4 data blocks, closing group
+        let clean = modulate_fhdpsk_with_config::<FftTestBackend>(&device, &data, false, &coherent);
+        assert_eq!(clean.dims()[0], 98 * 8 * coherent.symbol_samples());
+        assert_eq!(demodulate_fhdpsk_ex_with_config::<FftTestBackend>(&device, &clean, false, &coherent), data);
+        
+        let mut errors = [0usize; 2];
+        let mut rng = SplitMix64::new(3);
+        for std in [3.0f32, 5.0, 8.0] {
+            for (config, errors) in [&differential, &coherent].into_iter().zip(errors.iter_mut()) {
+                let clean = modulate_fhdpsk_with_config::<FftTestBackend>(&device, &data, false, config);
+                let signal = clean.clone() + gaussian_noise(&device, clean.dims()[0], std, &mut rng);
+                let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<FftTestBackend>(
+                    &device, &signal, false, config, bits.len(),
+                ).into_data().to_vec().unwrap();
+                *errors += (0..bits.len()).filter(|&i| (llrs[i] < 0.0) as u8 != bits[i]).count();
+            }
+        }
+        assert!(errors[0] > 0 && errors[1] < errors[0], "differential {} coherent {}", errors[0], errors[1]);
+    }
+    
+    #[test]
+    fn test_higher_order_dpsk_roundtrip() {
+        let device = Default::default();
+        let data = b"Two and three bits"; // 144 bits
+        let bits = encode_bits(data);
+        
+        // 72 / 48 data symbols: 9 / 6 data blocks of 8 plus the reference block
+        for (order, blocks) in [(DpskOrder::Quaternary, 10), (DpskOrder::Octal, 7)] {
+            let config = ModemConfig::narrowband().with_dpsk_order(order).with_scrambler(true);
+            let signal = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, false, &config);
+            assert_eq!(signal.dims()[0], blocks * 8 * config.symbol_samples());
+            
+            let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<FftTestBackend>(
+                &device, &signal, false, &config, bits.len(),
+            ).into_data().to_vec().unwrap();
+            let errors = (0..bits.len()).filter(|&i| (llrs[i] < 0.0) as u8 != bits[i]).count();
+            assert_eq!(errors, 0, "{:?}", order);
+            
+            let hard = demodulate_fhdpsk_ex_with_config::<FftTestBackend>(&device, &signal, false, &config);
+            assert_eq!(&hard[..data.len()], data, "{:?}", order);
+        }
+    }
+    
+    #[test]
+    fn test_truncated_signal_yields_erasures() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let symbol_len = config.symbol_samples();
+        let data = b"Erasure marking!"; // 128 bits -> 144 symbols
+        let bits = encode_bits(data);
+        
+        // Capture cut off half way through symbol 100
+        let signal = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, false, &config);
+        let truncated = signal.slice([0..100 * symbol_len + symbol_len / 2]);
+        
+        let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<FftTestBackend>(
+            &device, &truncated, false, &config, bits.len(),
+        ).into_data().to_vec().unwrap();
+        assert_eq!(llrs.len(), bits.len());
+        
+        // LLR j compares symbol j + 16 against symbol j
+        let errors = (0..84).filter(|&i| (llrs[i] < 0.0) as u8 != bits[i]).count();
+        assert_eq!(errors, 0);
+        assert!(llrs[84..].iter().all(|&l| l == 0.0));
+    }
+    
+    #[test]
+    fn test_sync_result_reports_lock_quality() {
+        use crate::modem_rng::{gaussian_noise, SplitMix64};
+        
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let mut rng = SplitMix64::new(11);
+        let tx = modulate_fhdpsk_with_config::<FftTestBackend>(&device, b"Quality", true, &config);
+        
+        // Same frame, clean and buried in noise, after a stretch of silence
+        let lead = 3000;
+        let mut snrs = Vec::new();
+        for sigma in [0.01, 0.3] {
+            let rx = Tensor::cat(vec![Tensor::zeros([lead], &device), tx.clone()], 0);
+            let rx = rx.clone() + gaussian_noise(&device, rx.dims()[0], sigma, &mut rng);
+            let sync = synchronize_signal_with_config::<FftTestBackend>(&device, &rx, &config).unwrap();
+            assert!(sync.metrics.position.abs_diff(lead) < config.preamble_note_samples() / 2);
+            assert!(sync.cfo_estimate.unwrap().abs() < 1.0);
+            assert_eq!(sync.metrics, measure_sync::<FftTestBackend>(&device, &rx, &config).unwrap());
+            snrs.push(sync.snr_estimate_db);
+        }
+        assert!(snrs[0] > snrs[1] + 3.0, "SNR estimates {:?}", snrs);
+    }
+    
+    #[test]
+    fn test_sync_config_limits_the_search() {
+        use crate::coarse_sync::DecimationPlan;
+        
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let tx = modulate_fhdpsk_with_config::<FftTestBackend>(&device, b"Window", true, &config);
+        let lead = 60000;
+        let rx = Tensor::cat(vec![Tensor::zeros([lead], &device), tx], 0);
+        
+        let found = synchronize_signal_with_config::<FftTestBackend>(&device, &rx, &config).unwrap();
+        let wide = config.clone().with_sync(SyncConfig::default().with_search_window(lead + 1000));
+        assert_eq!(synchronize_signal_with_config::<FftTestBackend>(&device, &rx, &wide).unwrap().metrics.position, found.metrics.position);
+        
+        // A window closing well before the frame, or thresholds above the lock, find nothing
+        let early = config.clone().with_sync(SyncConfig::default().with_search_window(1000));
+        assert!(synchronize_signal_with_config::<FftTestBackend>(&device, &rx, &early).is_none());
+        let strict = SyncThresholds { min_correlation: 2.0 * found.metrics.correlation, ..SyncThresholds::default() };
+        let strict = config.clone().with_sync(SyncConfig::default().with_thresholds(strict));
+        assert!(synchronize_signal_with_config::<FftTestBackend>(&device, &rx, &strict).is_none());
+        
+        // Decimation capped at 1: two-stage sync stays at the full rate
+        assert!(DecimationPlan::for_config(&config.clone().with_sync(SyncConfig::default().with_max_decimation(1))).is_none());
+        assert!(DecimationPlan::for_config(&config.with_sync(SyncConfig::default().with_max_decimation(4))).unwrap().factor <= 4);
+    }
+    
+    #[test]
+    fn test_streaming_matches_transmitted_bits() {
+        use crate::modem_rng::{gaussian_noise, SplitMix64};
+        
+        let device = Default::default();
+        let mut rng = SplitMix64::new(5);
+        let plain = ModemConfig::default().with_scrambler(true);
+        let pilots = ModemConfig::narrowband().with_reference_blocks(2, 4).with_pilots(4);
+        for config in [plain, pilots] {
+            let data = b"Streamed in small chunks";
+            let bits = encode_bits(data);
+            let tx = modulate_fhdpsk_with_config::<FftTestBackend>(&device, data, true, &config);
+            let preamble_len = generate_bach_preamble_with_config::<FftTestBackend>(&device, &config).dims()[0];
+            
+            // Two frames between stretches of noise
+            let lead = 5000;
+            let mut stream: Vec<f32> = gaussian_noise::<FftTestBackend, _>(&device, lead, 0.05, &mut rng).into_data().to_vec().unwrap();
+            for _ in 0..2 {
+                let noisy = tx.clone() + gaussian_noise(&device, tx.dims()[0], 0.05, &mut rng);
+                stream.extend(noisy.into_data().to_vec::<f32>().unwrap());
+                stream.extend(gaussian_noise::<FftTestBackend, _>(&device, 3 * preamble_len, 0.05, &mut rng).into_data().to_vec::<f32>().unwrap());
+            }
+            
+            let mut demod = StreamingDemodulator::<FftTestBackend>::new(&device, config.clone(), bits.len());
+            let mut events = Vec::new();
+            for chunk in stream.chunks(777) {
+                events.extend(demod.push(chunk));
+                assert!(demod.buffered_samples() < 3 * preamble_len + 777);
+            }
+            
+            let mut frames = Vec::new();
+            for event in events {
+                match event {
+                    StreamEvent::Synced { data_start } => frames.push((data_start, Vec::new(), false)),
+                    StreamEvent::Llrs(llrs) => frames.last_mut().unwrap().1.extend(llrs),
+                    StreamEvent::FrameComplete => frames.last_mut().unwrap().2 = true,
+                }
+            }
+            assert_eq!(frames.len(), 2);
+            let frame_len = (tx.dims()[0] + 3 * preamble_len) as u64;
+            for (i, (data_start, llrs, complete)) in frames.iter().enumerate() {
+                assert_eq!(*data_start, (lead + preamble_len) as u64 + i as u64 * frame_len);
+                assert!(complete);
+                let decoded: Vec<u8> = llrs.iter().map(|&l| (l < 0.0) as u8).collect();
+                assert_eq!(decoded, bits);
+            }
+        }
+    }
 }
@@ -0,0 +1,482 @@
+/// MQTT Publishing of Decodes and Spots
+///
+/// Feeds receiver output to an MQTT broker (Mosquitto etc.) for ham
+/// telemetry dashboards and aggregation services. `MqttPublisher` runs a
+/// `rumqttc` client over plain TCP (MQTT 3.1.1): a last will, QoS 0
+/// publishes, and an event-loop thread that keeps the connection alive,
+/// reconnects and re-announces the station after a drop. Messages are
+/// serialized with `serde_json`.
+///
+/// Topics, below `<prefix>/<station>` (default prefix `bachmodem`):
+///
+/// ```text
+/// status   retained "online"; the broker publishes "offline" when the
+///          connection drops (last will)
+/// decode   {"time": 1767225600.25, "profile": "standard", "dial_hz": 14074000,
+///           "snr_db": -21.5, "text": "CQ N0CALL FN31", "hex": "4351..."}
+/// snr      {"time": ..., "dial_hz": ..., "snr_db": -21.5 | null,
+///           "noise_floor_db": -42.1 | null}      one per decode attempt
+/// spot     {"time": ..., "callsign": "N0CALL", "locator": "FN31" | null,
+///           "dial_hz": 14074000 | null, "snr_db": -21.5, "mode": "BACHMODEM"}
+///          (`Spot::to_json`)
+/// ```
+///
+/// `time` is Unix seconds, `dial_hz` the USB dial frequency (null if the
+/// receiver doesn't know it), SNR in the 2500 Hz reference bandwidth,
+/// `text` the payload without zero padding (invalid UTF-8 replaced), `hex`
+/// the exact payload bytes. Messages are published as they are decoded;
+/// dashboards subscribe to `bachmodem/+/spot` and so on.
+
+use std::fmt;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use rumqttc::{Client, ClientError, ConnectReturnCode, ConnectionError, Event, LastWill, MqttOptions, Outgoing, Packet, QoS};
+use serde::Serialize;
+use crate::noise_floor::NoiseFloorTracker;
+use crate::receiver_pool::{DecodeError, DecodedFrame};
+use crate::spot::{Spot, SPOT_MODE};
+
+/// MQTT's default port, used when `MqttConfig::broker` names none
+pub const MQTT_DEFAULT_PORT: u16 = 1883;
+
+/// Queued requests between the publisher and the event loop
+const REQUEST_CAPACITY: usize = 64;
+
+/// Pause before the event loop retries a dropped connection
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Broker connection settings
+#[derive(Clone, Debug)]
+pub struct MqttConfig {
+    /// Broker address ("host:port" or "host" for port 1883)
+    pub broker: String,
+
+    pub client_id: String,
+
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// First topic level
+    pub topic_prefix: String,
+
+    /// Second topic level, usually the receiving station's callsign
+    pub station: String,
+
+    /// Keep-alive interval agreed with the broker (seconds)
+    pub keep_alive_s: u16,
+}
+
+impl MqttConfig {
+    pub fn new(broker: &str, station: &str) -> Self {
+        Self {
+            broker: broker.to_string(),
+            client_id: format!("bachmodem-{}", station.to_ascii_lowercase()),
+            username: None,
+            password: None,
+            topic_prefix: "bachmodem".to_string(),
+            station: station.to_ascii_uppercase(),
+            keep_alive_s: 60,
+        }
+    }
+
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.username = Some(username.to_string());
+        self.password = Some(password.to_string());
+        self
+    }
+
+    pub fn with_topic_prefix(mut self, prefix: &str) -> Self {
+        self.topic_prefix = prefix.to_string();
+        self
+    }
+
+    /// `<prefix>/<station>/<kind>`
+    pub fn topic(&self, kind: &str) -> String {
+        format!("{}/{}/{}", self.topic_prefix, self.station, kind)
+    }
+
+    /// Client options: clean session, last will "offline" (QoS 0, retained)
+    fn options(&self) -> Result<MqttOptions, MqttError> {
+        let (host, port) = match self.broker.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| MqttError::InvalidBroker(self.broker.clone()))?),
+            None => (self.broker.as_str(), MQTT_DEFAULT_PORT),
+        };
+        let mut options = MqttOptions::new(&self.client_id, host, port);
+        options.set_keep_alive(Duration::from_secs(self.keep_alive_s as u64));
+        options.set_clean_session(true);
+        options.set_last_will(LastWill::new(self.topic("status"), "offline", QoS::AtMostOnce, true));
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            options.set_credentials(username, password);
+        }
+        Ok(options)
+    }
+}
+
+/// MQTT client errors
+#[derive(Debug)]
+pub enum MqttError {
+    /// `MqttConfig::broker` is not "host" or "host:port"
+    InvalidBroker(String),
+
+    /// CONNACK return code (1-5: protocol, client id, unavailable, credentials, not authorized)
+    Refused(u8),
+
+    /// Connecting to the broker failed
+    Connection(ConnectionError),
+
+    /// The event loop has stopped, nothing can be queued
+    Client(ClientError),
+}
+
+impl fmt::Display for MqttError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MqttError::InvalidBroker(broker) => write!(f, "MQTT broker address '{}' is not host[:port]", broker),
+            MqttError::Refused(code) => write!(f, "MQTT broker refused the connection (code {})", code),
+            MqttError::Connection(e) => write!(f, "MQTT connection error: {}", e),
+            MqttError::Client(e) => write!(f, "MQTT client error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MqttError {}
+
+impl From<ConnectionError> for MqttError {
+    fn from(e: ConnectionError) -> Self {
+        match e {
+            ConnectionError::ConnectionRefused(code) => MqttError::Refused(refusal_code(code)),
+            e => MqttError::Connection(e),
+        }
+    }
+}
+
+impl From<ClientError> for MqttError {
+    fn from(e: ClientError) -> Self {
+        MqttError::Client(e)
+    }
+}
+
+/// CONNACK return code on the wire
+fn refusal_code(code: ConnectReturnCode) -> u8 {
+    match code {
+        ConnectReturnCode::Success => 0,
+        ConnectReturnCode::RefusedProtocolVersion => 1,
+        ConnectReturnCode::BadClientId => 2,
+        ConnectReturnCode::ServiceUnavailable => 3,
+        ConnectReturnCode::BadUserNamePassword => 4,
+        ConnectReturnCode::NotAuthorized => 5,
+    }
+}
+
+/// One decoded frame, for the `decode` topic
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeReport {
+    pub time_s: f64,
+    pub profile: String,
+    pub dial_hz: Option<u64>,
+
+    /// `DecodedFrame::snr_db`: against the noise floor, in 2500 Hz
+    pub snr_db: f32,
+
+    /// Payload bytes (trailing zero padding is dropped)
+    pub payload: Vec<u8>,
+}
+
+impl DecodeReport {
+    /// Report of a frame decoded at `time_s`
+    pub fn new(time_s: f64, profile: &str, dial_hz: Option<u64>, frame: &DecodedFrame) -> Self {
+        Self { time_s, profile: profile.to_string(), dial_hz, snr_db: frame.snr_db, payload: frame.payload.clone() }
+    }
+}
+
+/// Channel report of one decode attempt, for the `snr` topic
+#[derive(Clone, Debug, PartialEq)]
+pub struct SnrReport {
+    pub time_s: f64,
+    pub dial_hz: Option<u64>,
+
+    /// SNR of the decode, None if nothing decoded
+    pub snr_db: Option<f32>,
+
+    /// Band noise floor (dBFS, `NoiseFloorTracker`)
+    pub noise_floor_db: Option<f64>,
+}
+
+impl SnrReport {
+    /// Report of one decode attempt, with the floor its SNR is measured against
+    pub fn new(time_s: f64, dial_hz: Option<u64>, attempt: &Result<DecodedFrame, DecodeError>, noise_floor: &NoiseFloorTracker) -> Self {
+        Self {
+            time_s,
+            dial_hz,
+            snr_db: attempt.as_ref().ok().map(|frame| frame.snr_db),
+            noise_floor_db: noise_floor.snapshot().map(|snapshot| snapshot.floor_db),
+        }
+    }
+}
+
+/// `decode` message
+#[derive(Serialize)]
+struct DecodeMessage<'a> {
+    time: f64,
+    profile: &'a str,
+    dial_hz: Option<u64>,
+    snr_db: Option<f32>,
+    text: String,
+    hex: String,
+}
+
+/// `snr` message
+#[derive(Serialize)]
+struct SnrMessage {
+    time: f64,
+    dial_hz: Option<u64>,
+    snr_db: Option<f32>,
+    noise_floor_db: Option<f64>,
+}
+
+/// `spot` message (the fields of `Spot::to_json`)
+#[derive(Serialize)]
+struct SpotMessage<'a> {
+    time: f64,
+    callsign: &'a str,
+    locator: Option<&'a str>,
+    dial_hz: Option<u64>,
+    snr_db: Option<f32>,
+    mode: &'static str,
+}
+
+/// Publish-only MQTT 3.1.1 client
+pub struct MqttPublisher {
+    config: MqttConfig,
+    client: Client,
+    event_loop: JoinHandle<()>,
+}
+
+impl MqttPublisher {
+    /// Connect and announce the station as online
+    ///
+    /// Blocks until the broker accepts the connection, then hands the
+    /// connection to the event-loop thread.
+    pub fn connect(config: MqttConfig) -> Result<Self, MqttError> {
+        let (client, mut connection) = Client::new(config.options()?, REQUEST_CAPACITY);
+        for event in connection.iter() {
+            if let Event::Incoming(Packet::ConnAck(_)) = event? {
+                break;
+            }
+        }
+
+        let status = config.topic("status");
+        let announcer = client.clone();
+        let event_loop = std::thread::spawn(move || {
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    // Clean session: the retained status is ours to restore
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        announcer.try_publish(&status, QoS::AtMostOnce, true, "online").ok();
+                    }
+                    Ok(_) => {}
+                    // The next poll reconnects
+                    Err(_) => std::thread::sleep(RECONNECT_DELAY),
+                }
+            }
+        });
+
+        let publisher = Self { config, client, event_loop };
+        publisher.client.publish(publisher.config.topic("status"), QoS::AtMostOnce, true, "online")?;
+        Ok(publisher)
+    }
+
+    pub fn config(&self) -> &MqttConfig {
+        &self.config
+    }
+
+    pub fn publish_decode(&mut self, report: &DecodeReport) -> Result<(), MqttError> {
+        let payload = trim_padding(&report.payload);
+        self.publish_json("decode", &DecodeMessage {
+            time: report.time_s,
+            profile: &report.profile,
+            dial_hz: report.dial_hz,
+            snr_db: tenth_db(report.snr_db),
+            text: String::from_utf8_lossy(payload).into_owned(),
+            hex: payload.iter().map(|b| format!("{:02x}", b)).collect(),
+        })
+    }
+
+    pub fn publish_snr(&mut self, report: &SnrReport) -> Result<(), MqttError> {
+        self.publish_json("snr", &SnrMessage {
+            time: report.time_s,
+            dial_hz: report.dial_hz,
+            snr_db: report.snr_db.and_then(tenth_db),
+            noise_floor_db: report.noise_floor_db.map(|db| (db * 10.0).round() / 10.0),
+        })
+    }
+
+    pub fn publish_spot(&mut self, spot: &Spot) -> Result<(), MqttError> {
+        self.publish_json("spot", &SpotMessage {
+            time: spot.time_s,
+            callsign: &spot.callsign,
+            locator: spot.locator.as_deref(),
+            dial_hz: spot.dial_hz,
+            snr_db: tenth_db(spot.snr_db),
+            mode: SPOT_MODE,
+        })
+    }
+
+    /// Publish on `<prefix>/<station>/<kind>` at QoS 0
+    ///
+    /// Queued for the event loop; while the broker is unreachable messages
+    /// wait in the queue (up to `REQUEST_CAPACITY`, then this blocks).
+    pub fn publish(&mut self, kind: &str, payload: &[u8], retain: bool) -> Result<(), MqttError> {
+        self.client.publish(self.config.topic(kind), QoS::AtMostOnce, retain, payload.to_vec())?;
+        Ok(())
+    }
+
+    /// Announce offline and close the connection
+    pub fn disconnect(mut self) -> Result<(), MqttError> {
+        self.publish("status", b"offline", true)?;
+        self.client.disconnect()?;
+        self.event_loop.join().ok();
+        Ok(())
+    }
+
+    fn publish_json<T: Serialize>(&mut self, kind: &str, message: &T) -> Result<(), MqttError> {
+        let json = serde_json::to_vec(message).expect("MQTT messages serialize");
+        self.publish(kind, &json, false)
+    }
+}
+
+/// SNR to 0.1 dB; NaN / infinite (no estimate) become null
+fn tenth_db(db: f32) -> Option<f32> {
+    db.is_finite().then(|| (db * 10.0).round() / 10.0)
+}
+
+fn trim_padding(payload: &[u8]) -> &[u8] {
+    let end = payload.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    &payload[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Fake broker side: one packet's type byte and body
+    fn read_packet<R: Read>(stream: &mut R) -> (u8, Vec<u8>) {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).unwrap();
+        let kind = byte[0];
+
+        // Remaining length: 7 bits per byte, continuation in bit 7
+        let mut len = 0usize;
+        for shift in (0..28).step_by(7) {
+            stream.read_exact(&mut byte).unwrap();
+            len |= ((byte[0] & 0x7f) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).unwrap();
+        (kind, body)
+    }
+
+    #[test]
+    fn test_publishes_to_documented_topics() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Fake broker: accept, CONNACK, collect PUBLISH packets until DISCONNECT
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (kind, connect) = read_packet(&mut stream);
+            assert_eq!(kind, 0x10);
+            stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap();
+
+            let mut published = Vec::new();
+            loop {
+                let (kind, body) = read_packet(&mut stream);
+                match kind & 0xf0 {
+                    0x30 => {
+                        let len = u16::from_be_bytes([body[0], body[1]]) as usize;
+                        let topic = String::from_utf8(body[2..2 + len].to_vec()).unwrap();
+                        let payload = String::from_utf8(body[2 + len..].to_vec()).unwrap();
+                        published.push((topic, payload, kind & 0x01 == 1));
+                    }
+                    0xe0 => break,
+                    other => panic!("unexpected packet {:#04x}", other),
+                }
+            }
+            (connect, published)
+        });
+
+        let config = MqttConfig::new(&addr.to_string(), "n0call").with_credentials("user", "secret");
+        let mut mqtt = MqttPublisher::connect(config).unwrap();
+
+        let mut payload = b"CQ K1ABC \"FN42\"".to_vec();
+        payload.resize(16, 0);
+        mqtt.publish_decode(&DecodeReport {
+            time_s: 1767225600.25, profile: "standard".to_string(), dial_hz: Some(14_074_000), snr_db: -21.54, payload,
+        }).unwrap();
+        mqtt.publish_snr(&SnrReport { time_s: 1767225601.0, dial_hz: None, snr_db: None, noise_floor_db: Some(-42.13) }).unwrap();
+        let spot = Spot::from_decode("CQ K1ABC FN42", -21.5, Some(14_074_000), 1767225600.25).unwrap();
+        mqtt.publish_spot(&spot).unwrap();
+        mqtt.disconnect().unwrap();
+
+        let (connect, published) = broker.join().unwrap();
+        assert_eq!(&connect[..7], b"\x00\x04MQTT\x04");
+        assert_eq!(connect[7], 0x02 | 0x04 | 0x20 | 0x80 | 0x40);
+
+        let topics: Vec<&str> = published.iter().map(|(t, _, _)| t.as_str()).collect();
+        assert_eq!(topics, [
+            "bachmodem/N0CALL/status", "bachmodem/N0CALL/decode", "bachmodem/N0CALL/snr",
+            "bachmodem/N0CALL/spot", "bachmodem/N0CALL/status",
+        ]);
+        assert_eq!((published[0].1.as_str(), published[0].2), ("online", true));
+        assert_eq!((published[4].1.as_str(), published[4].2), ("offline", true));
+
+        assert_eq!(published[1].1, concat!(
+            r#"{"time":1767225600.25,"profile":"standard","dial_hz":14074000,"snr_db":-21.5,"#,
+            r#""text":"CQ K1ABC \"FN42\"","hex":"4351204b314142432022464e343222"}"#,
+        ));
+        assert_eq!(published[2].1, r#"{"time":1767225601.0,"dial_hz":null,"snr_db":null,"noise_floor_db":-42.1}"#);
+        assert_eq!(published[3].1, concat!(
+            r#"{"time":1767225600.25,"callsign":"K1ABC","locator":"FN42","dial_hz":14074000,"#,
+            r#""snr_db":-21.5,"mode":"BACHMODEM"}"#,
+        ));
+    }
+
+    #[test]
+    fn test_reports_from_decode_attempts() {
+        let frame = DecodedFrame { payload: b"CQ K1ABC FN42\0\0".to_vec(), snr_db: -18.25 };
+        let decode = DecodeReport::new(1767225600.25, "standard", Some(14_074_000), &frame);
+        assert_eq!((decode.snr_db, decode.payload.as_slice()), (-18.25, frame.payload.as_slice()));
+
+        let mut noise_floor = NoiseFloorTracker::default();
+        assert_eq!(SnrReport::new(0.0, None, &Ok(frame.clone()), &noise_floor).noise_floor_db, None);
+
+        noise_floor.update_from_window_powers(&[1e-4, 2e-4, 3e-4]);
+        let floor_db = noise_floor.snapshot().unwrap().floor_db;
+        let decoded = SnrReport::new(1.0, None, &Ok(frame), &noise_floor);
+        assert_eq!((decoded.snr_db, decoded.noise_floor_db), (Some(-18.25), Some(floor_db)));
+        let failed = SnrReport::new(2.0, None, &Err(DecodeError::NoSync), &noise_floor);
+        assert_eq!((failed.snr_db, failed.noise_floor_db), (None, Some(floor_db)));
+    }
+
+    #[test]
+    fn test_refused_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_packet(&mut stream);
+            stream.write_all(&[0x20, 0x02, 0x00, 0x05]).unwrap();
+        });
+
+        let result = MqttPublisher::connect(MqttConfig::new(&addr.to_string(), "N0CALL"));
+        assert!(matches!(result, Err(MqttError::Refused(5))));
+        broker.join().unwrap();
+    }
+}
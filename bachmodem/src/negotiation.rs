@@ -0,0 +1,710 @@
+/// Profile Negotiation and Graceful Degradation for Two-Way Sessions
+///
+/// `LinkAdaptation` moves a link between the modes of a shared `RateTable`
+/// once both ends run it. Two stations that meet on the air don't know
+/// that much about each other: one may lack the convolutional decoder,
+/// another may not run 32 tones or half-length symbols. Before data flows
+/// they exchange what they can do:
+///
+/// ```text
+/// initiator                              responder
+///   Capabilities  ─────────────────────►
+///                 ◄─────────────────────  Capabilities
+///   Select{mode, tones}  ──────────────►
+///                 ◄─────────────────────  Confirm{mode, tones}
+/// ```
+///
+/// `Capabilities` advertise the tone counts, FEC schemes and the range of
+/// symbol durations an end decodes; both ends reduce the rate table to the
+/// modes both support, and the initiator selects the fastest of them with
+/// the most tones both have. Unanswered messages are resent after
+/// `timeout_s`, up to `max_attempts` times. Handshake messages go out in
+/// the calling profile (the table's most robust mode at 16 tones), which
+/// every station decodes.
+///
+/// Once negotiated, each end reports every frame it expected
+/// (`frame_result`: decoded, or missed / failed its CRC). After
+/// `degrade_after` failures in a row the end steps down to the next more
+/// robust common mode - longer symbols, more repetitions - and announces it
+/// with a `Select` sent in the new mode. Ends listen in their mode and the
+/// next lower one (`listen_modes`), so whichever end degrades first, the
+/// other follows as soon as it decodes a frame in the lower mode.
+///
+/// Everything runs on the caller's clock (`start(now_s)`, `poll(now_s)`),
+/// so sessions are simulated on virtual time, as in the tests.
+
+use std::fmt;
+use crate::config::{ConfigError, ModemConfig, SUPPORTED_TONE_COUNTS};
+use crate::link_adaptation::{RateMode, RateTable};
+use bachmodem_core::frame::FrameCode;
+
+/// First byte of every negotiation message
+pub const NEGOTIATION_TAG: u8 = b'N';
+
+/// Negotiation message format version
+pub const NEGOTIATION_VERSION: u8 = 1;
+
+/// Default wait for an answer before resending (seconds)
+pub const DEFAULT_NEGOTIATION_TIMEOUT_S: f64 = 30.0;
+
+/// Default sends of a handshake message before giving up
+pub const DEFAULT_NEGOTIATION_ATTEMPTS: usize = 4;
+
+/// Default consecutive frame failures before stepping down
+pub const DEFAULT_DEGRADE_AFTER: usize = 3;
+
+/// Tone count of the calling profile
+const CALLING_TONES: usize = 16;
+
+const FEC_POLAR: u8 = 1 << 0;
+const FEC_CONVOLUTIONAL: u8 = 1 << 1;
+const FEC_REED_SOLOMON: u8 = 1 << 2;
+
+const KIND_CAPABILITIES: u8 = 0;
+const KIND_SELECT: u8 = 1;
+const KIND_CONFIRM: u8 = 2;
+
+/// What one end can decode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Bit i set = `SUPPORTED_TONE_COUNTS[i]` tones
+    pub tone_counts: u8,
+
+    /// Inner codes and the Reed-Solomon outer code
+    pub fec: u8,
+
+    /// Shortest and longest data symbol (ms)
+    pub min_symbol_ms: u16,
+    pub max_symbol_ms: u16,
+}
+
+impl Default for Capabilities {
+    /// Everything this build decodes
+    fn default() -> Self {
+        Self {
+            tone_counts: (1 << SUPPORTED_TONE_COUNTS.len()) - 1,
+            fec: FEC_POLAR | FEC_CONVOLUTIONAL | FEC_REED_SOLOMON,
+            min_symbol_ms: 1,
+            max_symbol_ms: u16::MAX,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Only the given tone counts
+    pub fn with_tone_counts(mut self, counts: &[usize]) -> Self {
+        self.tone_counts = SUPPORTED_TONE_COUNTS.iter()
+            .enumerate()
+            .filter(|(_, n)| counts.contains(n))
+            .fold(0, |mask, (i, _)| mask | 1u8 << i);
+        self
+    }
+
+    /// Enable or disable the convolutional inner code
+    pub fn with_convolutional(mut self, enabled: bool) -> Self {
+        self.fec = if enabled { self.fec | FEC_CONVOLUTIONAL } else { self.fec & !FEC_CONVOLUTIONAL };
+        self
+    }
+
+    /// Enable or disable the Reed-Solomon outer code
+    pub fn with_reed_solomon(mut self, enabled: bool) -> Self {
+        self.fec = if enabled { self.fec | FEC_REED_SOLOMON } else { self.fec & !FEC_REED_SOLOMON };
+        self
+    }
+
+    /// Only symbols of `min_s`..=`max_s` seconds
+    pub fn with_symbol_range(mut self, min_s: f64, max_s: f64) -> Self {
+        self.min_symbol_ms = (min_s * 1000.0).round().clamp(0.0, u16::MAX as f64) as u16;
+        self.max_symbol_ms = (max_s * 1000.0).round().clamp(0.0, u16::MAX as f64) as u16;
+        self
+    }
+
+    pub fn supports_tones(&self, num_tones: usize) -> bool {
+        SUPPORTED_TONE_COUNTS.iter().position(|&n| n == num_tones).is_some_and(|i| self.tone_counts & 1u8 << i != 0)
+    }
+
+    /// True if this end decodes `mode`
+    pub fn supports(&self, mode: &RateMode) -> bool {
+        let code = match mode.frame_code {
+            FrameCode::Polar => FEC_POLAR,
+            FrameCode::Convolutional => FEC_CONVOLUTIONAL,
+        };
+        let symbol_ms = (mode.symbol_duration * 1000.0).round() as u16;
+        self.fec & code != 0
+            && (mode.rs_parity == 0 || self.fec & FEC_REED_SOLOMON != 0)
+            && (self.min_symbol_ms..=self.max_symbol_ms).contains(&symbol_ms)
+    }
+
+    /// Capabilities both ends share
+    pub fn common(&self, other: &Capabilities) -> Capabilities {
+        Capabilities {
+            tone_counts: self.tone_counts & other.tone_counts,
+            fec: self.fec & other.fec,
+            min_symbol_ms: self.min_symbol_ms.max(other.min_symbol_ms),
+            max_symbol_ms: self.max_symbol_ms.min(other.max_symbol_ms),
+        }
+    }
+}
+
+/// Handshake message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NegotiationMessage {
+    Capabilities(Capabilities),
+
+    /// Use rate table `mode` with `tones` tones from now on
+    Select { mode: u8, tones: u8 },
+
+    /// `Select` accepted
+    Confirm { mode: u8, tones: u8 },
+}
+
+/// Bytes that are not a valid negotiation message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NegotiationMessageError {
+    /// Fewer bytes than the message kind needs
+    TooShort(usize),
+
+    /// First byte is not `NEGOTIATION_TAG`
+    NotNegotiation,
+
+    /// Version byte this build doesn't read
+    UnsupportedVersion(u8),
+
+    /// Unknown message kind
+    UnknownKind(u8),
+}
+
+impl fmt::Display for NegotiationMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NegotiationMessageError::TooShort(len) => write!(f, "negotiation message too short ({} bytes)", len),
+            NegotiationMessageError::NotNegotiation => write!(f, "not a negotiation message"),
+            NegotiationMessageError::UnsupportedVersion(v) => write!(f, "unsupported negotiation message version {}", v),
+            NegotiationMessageError::UnknownKind(k) => write!(f, "unknown negotiation message kind {}", k),
+        }
+    }
+}
+
+impl std::error::Error for NegotiationMessageError {}
+
+impl NegotiationMessage {
+    /// Wire format: tag, version, kind, then per kind
+    /// - Capabilities: tone mask, FEC mask, min / max symbol (ms, big endian)
+    /// - Select / Confirm: mode, tones
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![NEGOTIATION_TAG, NEGOTIATION_VERSION];
+        match *self {
+            NegotiationMessage::Capabilities(caps) => {
+                bytes.extend([KIND_CAPABILITIES, caps.tone_counts, caps.fec]);
+                bytes.extend(caps.min_symbol_ms.to_be_bytes());
+                bytes.extend(caps.max_symbol_ms.to_be_bytes());
+            }
+            NegotiationMessage::Select { mode, tones } => bytes.extend([KIND_SELECT, mode, tones]),
+            NegotiationMessage::Confirm { mode, tones } => bytes.extend([KIND_CONFIRM, mode, tones]),
+        }
+        bytes
+    }
+
+    /// Parse the leading bytes of a payload (zero padding after the message is ignored)
+    pub fn parse(bytes: &[u8]) -> Result<Self, NegotiationMessageError> {
+        let need = |len: usize| if bytes.len() < len { Err(NegotiationMessageError::TooShort(bytes.len())) } else { Ok(()) };
+        need(3)?;
+        if bytes[0] != NEGOTIATION_TAG {
+            return Err(NegotiationMessageError::NotNegotiation);
+        }
+        if bytes[1] != NEGOTIATION_VERSION {
+            return Err(NegotiationMessageError::UnsupportedVersion(bytes[1]));
+        }
+        match bytes[2] {
+            KIND_CAPABILITIES => {
+                need(9)?;
+                Ok(NegotiationMessage::Capabilities(Capabilities {
+                    tone_counts: bytes[3],
+                    fec: bytes[4],
+                    min_symbol_ms: u16::from_be_bytes([bytes[5], bytes[6]]),
+                    max_symbol_ms: u16::from_be_bytes([bytes[7], bytes[8]]),
+                }))
+            }
+            KIND_SELECT => {
+                need(5)?;
+                Ok(NegotiationMessage::Select { mode: bytes[3], tones: bytes[4] })
+            }
+            KIND_CONFIRM => {
+                need(5)?;
+                Ok(NegotiationMessage::Confirm { mode: bytes[3], tones: bytes[4] })
+            }
+            kind => Err(NegotiationMessageError::UnknownKind(kind)),
+        }
+    }
+}
+
+/// Which end opens the handshake
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NegotiationRole {
+    Initiator,
+    Responder,
+}
+
+/// Why a session could not be negotiated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NegotiationError {
+    /// No answer after `max_attempts` sends
+    NoAnswer,
+
+    /// No rate table mode or tone count both ends decode
+    NoCommonProfile,
+}
+
+impl fmt::Display for NegotiationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NegotiationError::NoAnswer => write!(f, "peer did not answer"),
+            NegotiationError::NoCommonProfile => write!(f, "no profile both ends decode"),
+        }
+    }
+}
+
+impl std::error::Error for NegotiationError {}
+
+/// Where one end of the handshake stands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NegotiationState {
+    Idle,
+
+    /// Initiator: capabilities sent, waiting for the peer's
+    Advertising,
+
+    /// Responder: capabilities sent, waiting for the selection
+    AwaitingSelect,
+
+    /// Initiator: selection sent, waiting for the confirmation
+    Selecting,
+
+    /// Both ends run rate table `mode` with `tones` tones
+    Negotiated { mode: usize, tones: usize },
+
+    Failed(NegotiationError),
+}
+
+/// Message to send and the rate table mode to send it in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Outgoing {
+    pub message: NegotiationMessage,
+    pub mode: usize,
+    pub tones: usize,
+}
+
+/// Handshake and degradation state of one end of a session
+#[derive(Clone, Debug)]
+pub struct ProfileNegotiator {
+    pub role: NegotiationRole,
+    pub local: Capabilities,
+    pub table: RateTable,
+
+    /// Wait for an answer before resending (seconds)
+    pub timeout_s: f64,
+
+    /// Sends of a handshake message before giving up
+    pub max_attempts: usize,
+
+    /// Consecutive frame failures before stepping down
+    pub degrade_after: usize,
+
+    state: NegotiationState,
+
+    /// Rate table modes both ends decode, most robust first
+    common_modes: Vec<usize>,
+
+    /// Last handshake message, its sends so far and when to resend
+    pending: Option<(Outgoing, usize, f64)>,
+
+    failures: usize,
+}
+
+impl ProfileNegotiator {
+    pub fn new(role: NegotiationRole, local: Capabilities, table: RateTable) -> Self {
+        assert!(!table.modes.is_empty(), "Rate table needs at least one mode");
+        assert!(table.modes.len() <= 256, "Mode indices are sent as one byte");
+        Self {
+            role,
+            local,
+            table,
+            timeout_s: DEFAULT_NEGOTIATION_TIMEOUT_S,
+            max_attempts: DEFAULT_NEGOTIATION_ATTEMPTS,
+            degrade_after: DEFAULT_DEGRADE_AFTER,
+            state: NegotiationState::Idle,
+            common_modes: Vec::new(),
+            pending: None,
+            failures: 0,
+        }
+    }
+
+    /// Set the answer timeout (seconds)
+    pub fn with_timeout(mut self, timeout_s: f64) -> Self {
+        assert!(timeout_s > 0.0, "Timeout must be positive");
+        self.timeout_s = timeout_s;
+        self
+    }
+
+    /// Set the consecutive failures before stepping down
+    pub fn with_degrade_after(mut self, failures: usize) -> Self {
+        assert!(failures > 0, "Need at least one failure to degrade");
+        self.degrade_after = failures;
+        self
+    }
+
+    pub fn state(&self) -> NegotiationState {
+        self.state
+    }
+
+    /// Agreed (mode, tones), once negotiated
+    pub fn profile(&self) -> Option<(usize, usize)> {
+        match self.state {
+            NegotiationState::Negotiated { mode, tones } => Some((mode, tones)),
+            _ => None,
+        }
+    }
+
+    /// `base` configured for the agreed profile (the calling profile before)
+    ///
+    /// An agreed tone count other than `base`'s starts over from
+    /// `ModemConfig::new`, whose per-tone settings (gains, hopping pattern,
+    /// usable tones) fit the alphabet; `base` with an explicit tone plan
+    /// can't change size and is refused.
+    pub fn config(&self, base: &ModemConfig) -> Result<ModemConfig, ConfigError> {
+        let (mode, tones) = self.profile().unwrap_or((0, CALLING_TONES));
+        let alphabet = if tones == base.num_tones {
+            base.clone()
+        } else if base.tone_plan.is_some() {
+            return Err(ConfigError::FixedTonePlan { num_tones: base.num_tones });
+        } else {
+            ModemConfig::new(tones)
+        };
+        Ok(self.table.modes[mode].apply(&alphabet))
+    }
+
+    /// Rate table modes to try decoding in: the agreed one, the next more
+    /// robust in case the peer stepped down first, and the calling profile
+    /// for a resent handshake message
+    pub fn listen_modes(&self) -> Vec<usize> {
+        match self.state {
+            NegotiationState::Negotiated { mode, .. } => {
+                let mut modes = vec![mode];
+                modes.extend(self.lower_mode(mode));
+                if !modes.contains(&0) {
+                    modes.push(0);
+                }
+                modes
+            }
+            _ => vec![0],
+        }
+    }
+
+    /// Initiator: open the handshake
+    pub fn start(&mut self, now_s: f64) -> Option<Outgoing> {
+        if self.role != NegotiationRole::Initiator || self.state != NegotiationState::Idle {
+            return None;
+        }
+        self.state = NegotiationState::Advertising;
+        Some(self.send(now_s, self.calling(NegotiationMessage::Capabilities(self.local))))
+    }
+
+    /// Resend an unanswered handshake message when its timeout ran out;
+    /// gives up after `max_attempts` sends
+    pub fn poll(&mut self, now_s: f64) -> Option<Outgoing> {
+        let (outgoing, attempts, resend_s) = self.pending?;
+        if now_s < resend_s {
+            return None;
+        }
+        if attempts >= self.max_attempts {
+            self.pending = None;
+            self.state = NegotiationState::Failed(NegotiationError::NoAnswer);
+            return None;
+        }
+        self.pending = Some((outgoing, attempts + 1, now_s + self.timeout_s));
+        Some(outgoing)
+    }
+
+    /// Handle a message from the peer, decoded in rate table mode
+    /// `received_mode`; returns the answer to send
+    pub fn handle(&mut self, now_s: f64, message: &NegotiationMessage, received_mode: usize) -> Option<Outgoing> {
+        match (*message, self.state) {
+            (NegotiationMessage::Capabilities(peer), NegotiationState::Advertising) => {
+                self.pending = None;
+                match self.choose(&peer) {
+                    Some((mode, tones)) => {
+                        self.state = NegotiationState::Selecting;
+                        let select = NegotiationMessage::Select { mode: mode as u8, tones: tones as u8 };
+                        Some(self.send(now_s, self.calling(select)))
+                    }
+                    None => {
+                        self.state = NegotiationState::Failed(NegotiationError::NoCommonProfile);
+                        None
+                    }
+                }
+            }
+            (NegotiationMessage::Capabilities(peer), NegotiationState::Idle | NegotiationState::AwaitingSelect)
+                if self.role == NegotiationRole::Responder =>
+            {
+                // A resent advertisement gets the answer again
+                self.common_modes = self.common_modes_with(&peer);
+                self.state = NegotiationState::AwaitingSelect;
+                Some(self.calling(NegotiationMessage::Capabilities(self.local)))
+            }
+            (NegotiationMessage::Select { mode, tones }, state) => {
+                let (mode, tones) = (mode as usize, tones as usize);
+                let acceptable = self.common_modes.contains(&mode) && self.local.supports_tones(tones);
+                let answering = matches!(state, NegotiationState::AwaitingSelect | NegotiationState::Negotiated { .. });
+                if !acceptable || !answering {
+                    return None;
+                }
+                self.state = NegotiationState::Negotiated { mode, tones };
+                self.failures = 0;
+                let confirm = NegotiationMessage::Confirm { mode: mode as u8, tones: tones as u8 };
+                // A Select in the calling profile (first or resent after a lost
+                // Confirm) comes from an initiator that only listens there
+                Some(match received_mode {
+                    0 => self.calling(confirm),
+                    _ => Outgoing { message: confirm, mode, tones },
+                })
+            }
+            (NegotiationMessage::Confirm { mode, tones }, NegotiationState::Selecting) => {
+                let selected = self.pending.map(|(outgoing, _, _)| outgoing.message);
+                if selected == Some(NegotiationMessage::Select { mode, tones }) {
+                    self.pending = None;
+                    self.state = NegotiationState::Negotiated { mode: mode as usize, tones: tones as usize };
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Report an expected frame: decoded in rate table mode `decoded`, or
+    /// None if it was missed or failed its CRC
+    ///
+    /// Returns the step-down announcement when failures persist.
+    pub fn frame_result(&mut self, decoded: Option<usize>) -> Option<Outgoing> {
+        let NegotiationState::Negotiated { mode, tones } = self.state else {
+            return None;
+        };
+        match decoded {
+            Some(peer_mode) => {
+                self.failures = 0;
+                // The peer stepped down first: follow
+                if peer_mode < mode && self.common_modes.contains(&peer_mode) {
+                    self.state = NegotiationState::Negotiated { mode: peer_mode, tones };
+                }
+                None
+            }
+            None => {
+                self.failures += 1;
+                if self.failures < self.degrade_after {
+                    return None;
+                }
+                self.failures = 0;
+                let lower = self.lower_mode(mode)?;
+                self.state = NegotiationState::Negotiated { mode: lower, tones };
+                Some(Outgoing { message: NegotiationMessage::Select { mode: lower as u8, tones: tones as u8 }, mode: lower, tones })
+            }
+        }
+    }
+
+    /// Next more robust common mode below `mode`
+    fn lower_mode(&self, mode: usize) -> Option<usize> {
+        self.common_modes.iter().rev().copied().find(|&m| m < mode)
+    }
+
+    fn common_modes_with(&self, peer: &Capabilities) -> Vec<usize> {
+        let common = self.local.common(peer);
+        (0..self.table.modes.len()).filter(|&m| common.supports(&self.table.modes[m])).collect()
+    }
+
+    /// Fastest common mode and the most tones both decode
+    fn choose(&mut self, peer: &Capabilities) -> Option<(usize, usize)> {
+        self.common_modes = self.common_modes_with(peer);
+        let common = self.local.common(peer);
+        let tones = SUPPORTED_TONE_COUNTS.iter().rev().copied().find(|&n| common.supports_tones(n))?;
+        Some((*self.common_modes.last()?, tones))
+    }
+
+    fn calling(&self, message: NegotiationMessage) -> Outgoing {
+        Outgoing { message, mode: 0, tones: CALLING_TONES }
+    }
+
+    fn send(&mut self, now_s: f64, outgoing: Outgoing) -> Outgoing {
+        self.pending = Some((outgoing, 1, now_s + self.timeout_s));
+        outgoing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tone_plan::TonePlan;
+
+    /// Two ends on virtual time: every message and data frame arrives
+    /// `latency_s` later if the channel carries its mode then and the
+    /// receiving end listens in it
+    struct SimSession {
+        ends: [ProfileNegotiator; 2],
+        in_flight: Vec<(f64, usize, Outgoing)>,
+        latency_s: f64,
+    }
+
+    impl SimSession {
+        fn new(initiator: Capabilities, responder: Capabilities) -> Self {
+            Self {
+                ends: [
+                    ProfileNegotiator::new(NegotiationRole::Initiator, initiator, RateTable::default()).with_timeout(10.0),
+                    ProfileNegotiator::new(NegotiationRole::Responder, responder, RateTable::default()),
+                ],
+                in_flight: Vec::new(),
+                latency_s: 2.0,
+            }
+        }
+
+        fn send(&mut self, now_s: f64, from: usize, outgoing: Option<Outgoing>) {
+            if let Some(outgoing) = outgoing {
+                self.in_flight.push((now_s + self.latency_s, 1 - from, outgoing));
+            }
+        }
+
+        /// Run one second of virtual time; `carries(mode, t)` is the channel,
+        /// data frames go out every `frame_s` once negotiated
+        fn step(&mut self, now_s: f64, carries: &impl Fn(usize, f64) -> bool, frame_s: f64) {
+            for end in 0..2 {
+                let outgoing = self.ends[end].poll(now_s);
+                self.send(now_s, end, outgoing);
+            }
+
+            let (due, later): (Vec<_>, Vec<_>) = self.in_flight.drain(..).partition(|(at, _, _)| *at <= now_s);
+            self.in_flight = later;
+            for (_, to, outgoing) in due {
+                if carries(outgoing.mode, now_s) && self.ends[to].listen_modes().contains(&outgoing.mode) {
+                    let answer = self.ends[to].handle(now_s, &outgoing.message, outgoing.mode);
+                    self.send(now_s, to, answer);
+                }
+            }
+
+            // Data frames both ways; the other end expects each one
+            if now_s % frame_s == 0.0 {
+                for from in 0..2 {
+                    let Some((mode, _)) = self.ends[from].profile() else { continue };
+                    let to = 1 - from;
+                    if self.ends[to].profile().is_none() {
+                        continue;
+                    }
+                    let heard = carries(mode, now_s) && self.ends[to].listen_modes().contains(&mode);
+                    let announcement = self.ends[to].frame_result(heard.then_some(mode));
+                    self.send(now_s, to, announcement);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_message_roundtrip_and_errors() {
+        let caps = Capabilities::default().with_tone_counts(&[8, 16]).with_convolutional(false).with_symbol_range(0.1, 0.4);
+        for message in [
+            NegotiationMessage::Capabilities(caps),
+            NegotiationMessage::Select { mode: 3, tones: 16 },
+            NegotiationMessage::Confirm { mode: 1, tones: 8 },
+        ] {
+            let mut payload = message.encode();
+            assert!(payload.len() <= bachmodem_core::frame::MAX_PAYLOAD);
+            payload.resize(15, 0);
+            assert_eq!(NegotiationMessage::parse(&payload), Ok(message));
+        }
+        assert!(caps.supports_tones(8) && !caps.supports_tones(32));
+        assert_eq!(NegotiationMessage::parse(b"N\x01"), Err(NegotiationMessageError::TooShort(2)));
+        assert_eq!(NegotiationMessage::parse(b"L\x01\x00"), Err(NegotiationMessageError::NotNegotiation));
+        assert_eq!(NegotiationMessage::parse(b"N\x02\x00"), Err(NegotiationMessageError::UnsupportedVersion(2)));
+        assert_eq!(NegotiationMessage::parse(b"N\x01\x07"), Err(NegotiationMessageError::UnknownKind(7)));
+    }
+
+    #[test]
+    fn test_handshake_survives_loss_and_picks_common_profile() {
+        // The responder has no Viterbi decoder and no 32 tones: "sprint" is out
+        let responder = Capabilities::default().with_convolutional(false).with_tone_counts(&[8, 16]);
+        let mut sim = SimSession::new(Capabilities::default(), responder);
+
+        // The first advertisement is lost, the resend goes through
+        let channel = |_: usize, t: f64| t > 5.0;
+        let start = sim.ends[0].start(0.0);
+        sim.send(0.0, 0, start);
+        for t in 1..60 {
+            sim.step(t as f64, &channel, 1000.0);
+        }
+
+        let fast = sim.ends[0].table.modes.iter().position(|m| m.name == "fast").unwrap();
+        assert_eq!(sim.ends[0].state(), NegotiationState::Negotiated { mode: fast, tones: 16 });
+        assert_eq!(sim.ends[1].state(), NegotiationState::Negotiated { mode: fast, tones: 16 });
+        assert_eq!(sim.ends[1].config(&ModemConfig::default()).unwrap().num_tones, 16);
+    }
+
+    #[test]
+    fn test_lost_confirm_is_answered_again() {
+        let mut sim = SimSession::new(Capabilities::default(), Capabilities::default());
+        let start = sim.ends[0].start(0.0);
+        sim.send(0.0, 0, start);
+
+        // Advertisements cross by 4 s, the Select arrives at 6 s and its Confirm would at 8 s
+        let channel = |_: usize, t: f64| t != 8.0;
+        for t in 1..=9 {
+            sim.step(t as f64, &channel, 1000.0);
+        }
+        assert_eq!(sim.ends[0].state(), NegotiationState::Selecting);
+        assert!(sim.ends[1].profile().is_some());
+
+        // The resent Select reaches a negotiated responder, which confirms again
+        for t in 10..40 {
+            sim.step(t as f64, &channel, 1000.0);
+        }
+        assert_eq!(sim.ends[0].profile(), sim.ends[1].profile());
+        assert!(sim.ends[0].profile().is_some(), "{:?}", sim.ends[0].state());
+    }
+
+    #[test]
+    fn test_persistent_failures_step_both_ends_down() {
+        let mut sim = SimSession::new(Capabilities::default(), Capabilities::default());
+        let start = sim.ends[0].start(0.0);
+        sim.send(0.0, 0, start);
+
+        // Clear until 100 s, then only "robust" (mode 1) and slower get through
+        let channel = |mode: usize, t: f64| t < 100.0 || mode <= 1;
+        for t in 1..400 {
+            sim.step(t as f64, &channel, 5.0);
+        }
+
+        for end in &sim.ends {
+            assert_eq!(end.profile(), Some((1, 32)), "{:?}", end.state());
+            assert_eq!(end.listen_modes(), vec![1, 0]);
+        }
+        let config = sim.ends[0].config(&ModemConfig::default().with_hopping_seed(7)).unwrap();
+        assert_eq!((config.num_tones, config.hopping_seed), (32, None));
+
+        // A 16-tone plan can't follow to 32 tones
+        let planned = ModemConfig::default().with_tone_plan(TonePlan::builtin(16).unwrap());
+        assert_eq!(sim.ends[0].config(&planned), Err(ConfigError::FixedTonePlan { num_tones: 16 }));
+
+        // An end that never hears back gives up
+        let mut lonely = ProfileNegotiator::new(NegotiationRole::Initiator, Capabilities::default(), RateTable::default());
+        lonely.start(0.0);
+        let sends = (1..=200).filter(|&t| lonely.poll(t as f64).is_some()).count();
+        assert_eq!(sends, DEFAULT_NEGOTIATION_ATTEMPTS - 1);
+        assert_eq!(lonely.state(), NegotiationState::Failed(NegotiationError::NoAnswer));
+    }
+
+    #[test]
+    fn test_no_common_profile_fails() {
+        let short_only = Capabilities::default().with_symbol_range(0.01, 0.06).with_convolutional(false);
+        let mut sim = SimSession::new(Capabilities::default(), short_only);
+        let start = sim.ends[0].start(0.0);
+        sim.send(0.0, 0, start);
+        for t in 1..30 {
+            sim.step(t as f64, &|_, _| true, 1000.0);
+        }
+        assert_eq!(sim.ends[0].state(), NegotiationState::Failed(NegotiationError::NoCommonProfile));
+    }
+}
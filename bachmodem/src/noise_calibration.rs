@@ -0,0 +1,362 @@
+/// Noise-Only Calibration
+///
+/// A minute of receiver noise (antenna on a quiet frequency, no signal)
+/// says what every later capture at this station will look like:
+/// - floor shape: Welch spectrum (Hann, 50 % overlap, batched STFT on the
+///   device) in bands across the passband, relative to the in-band floor
+/// - impulsiveness: kurtosis (3 for Gaussian noise) and the rate of samples
+///   beyond `impulse_rms` × RMS, measured after the notches
+/// - hum lines: spectral lines standing `line_threshold_db` above the median
+///   of their neighbourhood, e.g. power-line harmonics or a birdie
+/// - what noise alone does to preamble detection: the largest normalized
+///   correlation and peak-to-noise ratio over `sync_window_s` windows
+///
+/// `station_profile` turns the measurement into receiver defaults: a notch
+/// per hum line (`FrontEnd`), a squelch (`sync_min_correlation`, in signal
+/// units, so it follows the station's noise level) and a CFAR factor
+/// (`sync_min_peak_to_noise`: the detector compares the correlation peak
+/// with the capture's own mean, so noise alone never crosses it) each set
+/// `margin` above the worst noise window. `save_profile` writes them into
+/// the profile `ReceiverPoolConfig::load_profile` reads (`bachmodem
+/// --calibrate noise.wav`).
+
+use std::path::Path;
+use burn::tensor::{Tensor, backend::Backend, ElementConversion};
+use fft_gpu::spectral::{hann_window, stft_magnitude};
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::front_end::{FrontEnd, DEFAULT_NOTCH_WIDTH_HZ};
+use crate::modulation::synchronize_signal_gpu;
+use crate::noise_floor::SNR_REFERENCE_BANDWIDTH;
+use crate::tuning::ReceiverTuning;
+use crate::wavelet::{generate_bach_preamble_with_config, FS};
+
+/// Analysis parameters
+#[derive(Clone, Debug)]
+pub struct NoiseCalibrationConfig {
+    /// Welch segment length (power of two), ~1 Hz line resolution at 8192
+    pub fft_len: usize,
+
+    /// Band searched for lines and reported as floor shape (Hz)
+    pub band_low_hz: f64,
+    pub band_high_hz: f64,
+
+    /// Width of the floor-shape bands (Hz)
+    pub shape_band_hz: f64,
+
+    /// A line stands this far above its neighbourhood median (dB)
+    pub line_threshold_db: f64,
+
+    /// Neighbourhood the local floor of a line is taken over, each side (Hz)
+    pub line_neighbourhood_hz: f64,
+
+    /// Samples beyond this many RMS count as impulses
+    pub impulse_rms: f64,
+
+    /// Capture length the detection statistics are taken over (seconds)
+    ///
+    /// Noise peaks grow with the capture, so this should match the captures
+    /// decoded later (a frame is ~30 s with the default config).
+    pub sync_window_s: f64,
+
+    /// Squelch and CFAR factor over the worst noise window
+    pub margin: f32,
+
+    /// Width of the notches placed on the lines (Hz)
+    pub notch_width_hz: f64,
+}
+
+impl Default for NoiseCalibrationConfig {
+    fn default() -> Self {
+        Self {
+            fft_len: 8192,
+            band_low_hz: 40.0,
+            band_high_hz: 2800.0,
+            shape_band_hz: 200.0,
+            line_threshold_db: 10.0,
+            line_neighbourhood_hz: 20.0,
+            impulse_rms: 5.0,
+            sync_window_s: 30.0,
+            margin: 1.1,
+            notch_width_hz: DEFAULT_NOTCH_WIDTH_HZ,
+        }
+    }
+}
+
+/// Narrow spectral line in the noise
+#[derive(Clone, Debug, PartialEq)]
+pub struct HumLine {
+    pub frequency_hz: f64,
+
+    /// Level above the neighbourhood median (dB)
+    pub level_db: f64,
+}
+
+/// Measured station noise
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseCalibration {
+    /// Median in-band noise in the SNR reference bandwidth (dBFS, see `noise_floor`)
+    pub floor_db: f64,
+
+    /// (band centre Hz, median level relative to `floor_db` in dB) across the band
+    pub shape_db: Vec<(f64, f64)>,
+
+    /// E[x⁴] / E[x²]², 3 for Gaussian noise, larger when impulsive
+    pub kurtosis: f64,
+
+    /// Samples beyond `impulse_rms` × RMS per second (Gaussian: ~0.005 at 5 RMS)
+    pub impulse_rate: f64,
+
+    /// Lines, lowest frequency first
+    pub hum_lines: Vec<HumLine>,
+
+    /// Largest normalized preamble correlation in a noise window
+    pub noise_correlation: f32,
+
+    /// Largest preamble correlation peak-to-noise ratio in a noise window
+    pub noise_peak_to_noise: f32,
+
+    /// Analysed duration (seconds)
+    pub duration_s: f64,
+}
+
+/// Measure a noise-only capture
+///
+/// The capture needs at least one `fft_len` segment; the detection
+/// statistics use windows of `sync_window_s` (or the whole capture if it is
+/// shorter) every quarter window.
+///
+/// ⚠️ **SYNC POINT**: Downloads the averaged spectrum and a few scalars per window
+pub fn analyze_noise<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    modem: &ModemConfig,
+    config: &NoiseCalibrationConfig,
+) -> NoiseCalibration {
+    let n = signal.dims()[0];
+    assert!(n >= config.fft_len, "calibration capture shorter than one FFT segment");
+    let signal = signal.clone() - signal.clone().mean();
+
+    // Welch spectrum, one-sided density 2|X|² / (FS Σw²)
+    let window = hann_window::<B>(config.fft_len, device);
+    let window_power: f32 = window.clone().powf_scalar(2.0).sum().into_scalar().elem();
+    let density: Vec<f32> = stft_magnitude(signal.clone(), window, config.fft_len / 2)
+        .powf_scalar(2.0)
+        .mean_dim(0)
+        .mul_scalar(2.0 / (FS as f32 * window_power))
+        .into_data().to_vec().unwrap();
+    let bin_hz = FS / config.fft_len as f64;
+    let low_bin = (config.band_low_hz / bin_hz).ceil() as usize;
+    let high_bin = ((config.band_high_hz / bin_hz).floor() as usize).min(density.len() - 1);
+
+    let floor_density = median(&density[low_bin..=high_bin]);
+    let floor_db = 10.0 * (floor_density as f64 * SNR_REFERENCE_BANDWIDTH).max(1e-30).log10();
+
+    let bins_per_band = ((config.shape_band_hz / bin_hz).round() as usize).max(1);
+    let shape_db = density[low_bin..=high_bin].chunks(bins_per_band)
+        .enumerate()
+        .map(|(i, band)| {
+            let centre = (low_bin + i * bins_per_band) as f64 * bin_hz + band.len() as f64 * bin_hz / 2.0;
+            (centre, 10.0 * (median(band) / floor_density).max(1e-30).log10() as f64)
+        })
+        .collect();
+
+    let hum_lines = find_lines(&density, low_bin, high_bin, bin_hz, config);
+
+    // Impulses and detection statistics on what the receiver will see
+    let front_end = FrontEnd {
+        notches_hz: hum_lines.iter().map(|line| line.frequency_hz).collect(),
+        notch_width_hz: config.notch_width_hz,
+        hum: None,
+        cw_id_hz: None,
+    };
+    let cleaned = front_end.apply(device, &signal);
+
+    let moments: Vec<f32> = Tensor::cat(vec![
+        cleaned.clone().powf_scalar(2.0).mean(),
+        cleaned.clone().powf_scalar(4.0).mean(),
+    ], 0).into_data().to_vec().unwrap();
+    let rms = moments[0].sqrt();
+    let kurtosis = moments[1] as f64 / (moments[0] as f64).powi(2).max(1e-30);
+    let impulses: f32 = cleaned.clone().abs()
+        .greater_elem(config.impulse_rms as f32 * rms)
+        .float()
+        .sum()
+        .into_scalar()
+        .elem();
+    let duration_s = n as f64 / FS;
+
+    let (noise_correlation, noise_peak_to_noise) = detection_statistics(device, &cleaned, modem, config);
+
+    NoiseCalibration {
+        floor_db,
+        shape_db,
+        kurtosis,
+        impulse_rate: impulses as f64 / duration_s,
+        hum_lines,
+        noise_correlation,
+        noise_peak_to_noise,
+        duration_s,
+    }
+}
+
+impl NoiseCalibration {
+    /// Receiver defaults for this station on top of `base`
+    ///
+    /// Notches on every hum line; squelch and CFAR factor `margin` above the
+    /// worst noise window (never below the base thresholds).
+    pub fn station_profile(&self, base: &ReceiverTuning, config: &NoiseCalibrationConfig) -> (ReceiverTuning, FrontEnd) {
+        let mut tuning = *base;
+        tuning.sync.min_correlation = (self.noise_correlation * config.margin).max(base.sync.min_correlation);
+        tuning.sync.min_peak_to_noise = (self.noise_peak_to_noise * config.margin).max(base.sync.min_peak_to_noise);
+
+        let front_end = FrontEnd {
+            notches_hz: self.hum_lines.iter().map(|line| (line.frequency_hz * 10.0).round() / 10.0).collect(),
+            notch_width_hz: config.notch_width_hz,
+            hum: None,
+            cw_id_hz: None,
+        };
+        (tuning, front_end)
+    }
+
+    /// Measurement summary, one line each (profile header, console)
+    pub fn summary(&self) -> String {
+        let mut text = format!(
+            "noise calibration over {:.0} s\nfloor {:.1} dBFS in {} Hz, kurtosis {:.2}, {:.3} impulses/s\nnoise preamble correlation {:.4}, peak/noise {:.2}\n",
+            self.duration_s, self.floor_db, SNR_REFERENCE_BANDWIDTH, self.kurtosis, self.impulse_rate,
+            self.noise_correlation, self.noise_peak_to_noise,
+        );
+        if self.hum_lines.is_empty() {
+            text.push_str("no lines\n");
+        }
+        for line in &self.hum_lines {
+            text.push_str(&format!("line {:.1} Hz {:+.1} dB\n", line.frequency_hz, line.level_db));
+        }
+        text
+    }
+
+    /// Write `station_profile` as a receiver profile with the summary as header
+    pub fn save_profile<P: AsRef<Path>>(
+        &self,
+        path: P,
+        base: &ReceiverTuning,
+        config: &NoiseCalibrationConfig,
+    ) -> std::io::Result<()> {
+        let (tuning, front_end) = self.station_profile(base, config);
+        std::fs::write(path, tuning.to_text(&self.summary()) + &front_end.to_text())
+    }
+}
+
+/// Local maxima standing `line_threshold_db` above the median of their
+/// neighbourhood, parabolically refined, closer ones than a notch width merged
+fn find_lines(density: &[f32], low_bin: usize, high_bin: usize, bin_hz: f64, config: &NoiseCalibrationConfig) -> Vec<HumLine> {
+    let reach = ((config.line_neighbourhood_hz / bin_hz).round() as usize).max(2);
+    let threshold = 10f32.powf(config.line_threshold_db as f32 / 10.0);
+    let db = |p: f32| 10.0 * (p.max(1e-30) as f64).log10();
+
+    let mut lines: Vec<HumLine> = Vec::new();
+    for k in low_bin.max(1)..=high_bin.min(density.len() - 2) {
+        let peak = density[k];
+        if peak <= density[k - 1] || peak < density[k + 1] {
+            continue;
+        }
+        let local = median(&density[k.saturating_sub(reach)..(k + reach + 1).min(density.len())]);
+        if peak < threshold * local {
+            continue;
+        }
+
+        let (left, centre, right) = (db(density[k - 1]), db(peak), db(density[k + 1]));
+        let curvature = left - 2.0 * centre + right;
+        let offset = if curvature < 0.0 { 0.5 * (left - right) / curvature } else { 0.0 };
+        let line = HumLine { frequency_hz: (k as f64 + offset) * bin_hz, level_db: centre - db(local) };
+
+        match lines.last_mut() {
+            Some(last) if line.frequency_hz - last.frequency_hz < config.notch_width_hz => {
+                if line.level_db > last.level_db {
+                    *last = line;
+                }
+            }
+            _ => lines.push(line),
+        }
+    }
+    lines
+}
+
+/// Worst normalized correlation and peak-to-noise ratio of the preamble
+/// detector over noise windows (same metrics as the synchronizer)
+///
+/// ⚠️ **SYNC POINT**: Downloads three scalars per window
+fn detection_statistics<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    modem: &ModemConfig,
+    config: &NoiseCalibrationConfig,
+) -> (f32, f32) {
+    let preamble = generate_bach_preamble_with_config::<B>(device, modem);
+    let preamble_norm: f32 = preamble.clone().powf_scalar(2.0).sum().sqrt().into_scalar().elem();
+    let n = signal.dims()[0];
+    if n < preamble.dims()[0] {
+        return (0.0, 0.0);
+    }
+
+    let window = ((config.sync_window_s * FS) as usize).clamp(preamble.dims()[0], n);
+    let hop = (window / 4).max(1);
+    let (mut correlation, mut peak_to_noise) = (0.0f32, 0.0f32);
+    for start in (0..=n - window).step_by(hop) {
+        let (correlations, _, _) = synchronize_signal_gpu(device, &signal.clone().slice([start..start + window]), &preamble);
+        let squared = correlations.clone().powf_scalar(2.0);
+        let stats: Vec<f32> = Tensor::cat(vec![correlations.max(), squared.clone().max(), squared.mean()], 0)
+            .into_data().to_vec().unwrap();
+        correlation = correlation.max(stats[0] / preamble_norm);
+        peak_to_noise = peak_to_noise.max(stats[1] / (stats[2] + 1e-10));
+    }
+    (correlation, peak_to_noise)
+}
+
+fn median(values: &[f32]) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted[sorted.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+
+    #[test]
+    fn test_hum_lines_become_notches() {
+        let device = Default::default();
+        TestBackend::seed(&device, 11);
+
+        // 40 s of white noise (σ = 0.01) with 50 Hz hum: 100 and 150 Hz harmonics
+        let len = 40 * FS as usize;
+        let hum: Vec<f32> = (0..len)
+            .map(|t| {
+                let t = t as f64 / FS;
+                (0.01 * (2.0 * std::f64::consts::PI * 100.0 * t).sin() + 0.005 * (2.0 * std::f64::consts::PI * 150.0 * t).sin()) as f32
+            })
+            .collect();
+        let noise = gaussian_noise::<TestBackend, _>(&device, len, 0.01, &mut SplitMix64::new(29))
+            + Tensor::from_floats(hum.as_slice(), &device);
+
+        let config = NoiseCalibrationConfig::default();
+        let calibration = analyze_noise(&device, &noise, &ModemConfig::default(), &config);
+        println!("{}", calibration.summary());
+
+        let lines: Vec<f64> = calibration.hum_lines.iter().map(|l| l.frequency_hz).collect();
+        assert_eq!(lines.len(), 2, "lines {:?}", lines);
+        assert!((lines[0] - 100.0).abs() < 0.2 && (lines[1] - 150.0).abs() < 0.2);
+
+        // σ² = 1e-4 over 4 kHz: 2.5e-8 /Hz, -42 dBFS in 2500 Hz; white
+        assert!((calibration.floor_db + 42.0).abs() < 0.5, "floor {}", calibration.floor_db);
+        assert!(calibration.shape_db.iter().all(|&(_, db)| db.abs() < 0.5));
+        assert!((calibration.kurtosis - 3.0).abs() < 0.1);
+
+        let (tuning, front_end) = calibration.station_profile(&ReceiverTuning::default(), &config);
+        assert_eq!(front_end.notches_hz.len(), 2);
+        assert!(front_end.notches_hz.iter().zip([100.0, 150.0]).all(|(f, hum)| (f - hum).abs() < 0.2));
+        assert!(tuning.sync.min_peak_to_noise > calibration.noise_peak_to_noise);
+    }
+}
@@ -0,0 +1,334 @@
+/// Noise-Floor Tracker
+///
+/// Long-running estimate of the band noise floor for monitoring sessions.
+///
+/// Each audio block is cut into windows; the band power of every window is
+/// measured on the GPU with one batched FFT, and the block's median is taken
+/// there too (`power_percentile_gpu`, no sort). The median window power
+/// rejects signal bursts and QRN crashes, and the floor adapts slowly
+/// (exponential smoothing) so a single block cannot drag it around. A
+/// peak-hold value with slow decay tracks the loudest window seen.
+///
+/// SNR is reported in the WSJT convention: signal power over noise power in a
+/// 2500 Hz reference bandwidth, so every decode is calibrated against the same
+/// floor instead of an ad-hoc per-decode estimate.
+///
+/// Intended to be owned by a monitoring loop: feed it every captured block
+/// with `update_gpu()` and read the state with `snapshot()`. `to_record()` /
+/// `restore()` carry the estimate across restarts (see `receiver_state`).
+
+use burn::record::Record;
+use burn::tensor::{Tensor, TensorPrimitive, backend::Backend};
+use crate::fft_correlation::FftBackend;
+use crate::gpu_ops::power_percentile_gpu;
+use crate::wavelet::FS;
+
+/// Reference bandwidth for SNR reporting (Hz)
+pub const SNR_REFERENCE_BANDWIDTH: f64 = 2500.0;
+
+/// Noise-floor tracker parameters
+#[derive(Clone, Debug)]
+pub struct NoiseFloorConfig {
+    /// Window length in samples (power of two)
+    pub window_len: usize,
+
+    /// Lower edge of the measured band (Hz)
+    pub band_low_hz: f64,
+
+    /// Upper edge of the measured band (Hz)
+    pub band_high_hz: f64,
+
+    /// Smoothing factor per update (0..1, small = slow adaptation)
+    pub adaptation: f64,
+
+    /// Peak-hold decay per update (dB)
+    pub peak_decay_db: f64,
+}
+
+impl Default for NoiseFloorConfig {
+    fn default() -> Self {
+        Self {
+            window_len: 1024,
+            band_low_hz: 200.0,
+            band_high_hz: 2800.0,
+            adaptation: 0.05,
+            peak_decay_db: 0.5,
+        }
+    }
+}
+
+/// Point-in-time view of the tracker state
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoiseFloorSnapshot {
+    /// Noise power in the SNR reference bandwidth (dBFS)
+    pub floor_db: f64,
+
+    /// Peak-hold window power in the reference bandwidth (dBFS)
+    pub peak_db: f64,
+
+    /// Median window power of the most recent block (dBFS)
+    pub last_block_db: f64,
+
+    /// Number of blocks absorbed
+    pub updates: u64,
+}
+
+/// Adaptive tracker state, for persistence
+#[derive(Record, Clone, Debug, PartialEq)]
+pub struct NoiseFloorRecord {
+    /// Smoothed noise density (power per Hz)
+    pub floor_density: Option<f64>,
+
+    /// Peak-hold density (power per Hz)
+    pub peak_density: Option<f64>,
+
+    /// Median density of the last block (power per Hz)
+    pub last_density: Option<f64>,
+
+    /// Number of blocks absorbed
+    pub updates: u64,
+}
+
+/// Median/peak-hold noise-floor estimator
+#[derive(Clone, Debug)]
+pub struct NoiseFloorTracker {
+    pub config: NoiseFloorConfig,
+
+    /// Smoothed noise density (power per Hz)
+    floor_density: Option<f64>,
+
+    /// Peak-hold density (power per Hz)
+    peak_density: Option<f64>,
+
+    /// Median density of the last block (power per Hz)
+    last_density: Option<f64>,
+
+    updates: u64,
+}
+
+impl NoiseFloorTracker {
+    pub fn new(config: NoiseFloorConfig) -> Self {
+        assert!(config.window_len.is_power_of_two(), "window_len must be a power of two");
+        assert!(config.band_low_hz < config.band_high_hz);
+
+        Self {
+            config,
+            floor_density: None,
+            peak_density: None,
+            last_density: None,
+            updates: 0,
+        }
+    }
+
+    /// Measured bandwidth (Hz)
+    pub fn bandwidth_hz(&self) -> f64 {
+        self.config.band_high_hz - self.config.band_low_hz
+    }
+
+    /// Band power of each window of `samples`, None without a full window
+    ///
+    /// Trailing samples that don't fill a window are ignored.
+    ///
+    /// **NO SYNC POINT**
+    pub fn window_band_powers_tensor<B: Backend + FftBackend>(
+        &self,
+        device: &B::Device,
+        samples: &Tensor<B, 1>,
+    ) -> Option<Tensor<B, 1>> {
+        let n = self.config.window_len;
+        let num_windows = samples.dims()[0] / n;
+        if num_windows == 0 {
+            return None;
+        }
+
+        let real = match samples.clone().slice([0..num_windows * n]).reshape([num_windows, n]).into_primitive() {
+            TensorPrimitive::Float(t) => t,
+            _ => panic!("Expected float tensor"),
+        };
+        let imag = match Tensor::<B, 2>::zeros([num_windows, n], device).into_primitive() {
+            TensorPrimitive::Float(t) => t,
+            _ => panic!("Expected float tensor"),
+        };
+
+        let (fft_real_t, fft_imag_t) = B::fft_1d_batch_impl(real, imag, n);
+        let fft_real: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_real_t));
+        let fft_imag: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_imag_t));
+
+        // One-sided band power: 2 * sum(|X_k|²) / N² over the band bins
+        let bin_hz = FS / n as f64;
+        let low_bin = (self.config.band_low_hz / bin_hz).ceil() as usize;
+        let high_bin = ((self.config.band_high_hz / bin_hz).floor() as usize).min(n / 2);
+
+        Some(fft_real.slice([0..num_windows, low_bin..high_bin + 1]).powf_scalar(2.0)
+            .add(fft_imag.slice([0..num_windows, low_bin..high_bin + 1]).powf_scalar(2.0))
+            .sum_dim(1)
+            .mul_scalar(2.0 / (n as f32 * n as f32))
+            .reshape([num_windows]))
+    }
+
+    /// Band power of each window of `samples`
+    ///
+    /// Trailing samples that don't fill a window are ignored.
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads one power value per window
+    pub fn window_band_powers_gpu<B: Backend + FftBackend>(
+        &self,
+        device: &B::Device,
+        samples: &Tensor<B, 1>,
+    ) -> Vec<f64> {
+        self.window_band_powers_tensor(device, samples).map_or_else(Vec::new, |powers| {
+            powers.into_data().to_vec::<f32>().unwrap()
+                .iter().map(|&p| p as f64).collect()
+        })
+    }
+
+    /// Absorb one block of captured audio
+    ///
+    /// Median and loudest window power are taken on the device
+    /// (`power_percentile_gpu`), so long blocks don't download every window.
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads two values
+    pub fn update_gpu<B: Backend + FftBackend>(&mut self, device: &B::Device, samples: &Tensor<B, 1>) {
+        let Some(powers) = self.window_band_powers_tensor(device, samples) else {
+            return;
+        };
+        let stats: Vec<f32> = Tensor::cat(vec![power_percentile_gpu(powers.clone(), 0.5), powers.max()], 0)
+            .into_data().to_vec().unwrap();
+        self.absorb(stats[0] as f64, stats[1] as f64);
+    }
+
+    /// Absorb the window band powers of one block
+    pub fn update_from_window_powers(&mut self, powers: &[f64]) {
+        if powers.is_empty() {
+            return;
+        }
+
+        let mut sorted = powers.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        self.absorb(sorted[sorted.len() / 2], sorted[sorted.len() - 1]);
+    }
+
+    /// Absorb the median and loudest window power of one block
+    fn absorb(&mut self, median_power: f64, loudest_power: f64) {
+        let bandwidth = self.bandwidth_hz();
+        let median = median_power / bandwidth;
+        let loudest = loudest_power / bandwidth;
+
+        let alpha = self.config.adaptation;
+        self.floor_density = Some(match self.floor_density {
+            Some(floor) => floor * (1.0 - alpha) + median * alpha,
+            None => median,
+        });
+
+        let decay = 10f64.powf(-self.config.peak_decay_db / 10.0);
+        self.peak_density = Some(match self.peak_density {
+            Some(peak) => (peak * decay).max(loudest),
+            None => loudest,
+        });
+
+        self.last_density = Some(median);
+        self.updates += 1;
+    }
+
+    /// Noise power in the SNR reference bandwidth (linear), once initialized
+    pub fn noise_power(&self) -> Option<f64> {
+        self.floor_density.map(|d| d * SNR_REFERENCE_BANDWIDTH)
+    }
+
+    /// Calibrated SNR (dB in 2500 Hz) of a signal with measured band power
+    ///
+    /// `band_power` is the in-band power of the received segment (signal plus
+    /// noise) measured like the tracker does, e.g. the mean of
+    /// `window_band_powers_gpu()` over the decoded transmission.
+    ///
+    /// Power subtraction loses accuracy below roughly -15 dB, where the
+    /// signal is smaller than the variance of the noise estimate.
+    pub fn snr_db(&self, band_power: f64) -> Option<f64> {
+        let density = self.floor_density?;
+        let signal_power = (band_power - density * self.bandwidth_hz()).max(1e-20);
+        Some(10.0 * (signal_power / (density * SNR_REFERENCE_BANDWIDTH)).log10())
+    }
+
+    /// The floor, peak and last window densities with the update count, so a
+    /// restart skips the warm-up; the tracker settings are not part of it
+    pub fn to_record(&self) -> NoiseFloorRecord {
+        NoiseFloorRecord {
+            floor_density: self.floor_density,
+            peak_density: self.peak_density,
+            last_density: self.last_density,
+            updates: self.updates,
+        }
+    }
+
+    /// Resume from a saved state
+    ///
+    /// The configuration is kept; the next block adapts the restored floor
+    /// like any other.
+    pub fn restore(&mut self, record: NoiseFloorRecord) {
+        self.floor_density = record.floor_density;
+        self.peak_density = record.peak_density;
+        self.last_density = record.last_density;
+        self.updates = record.updates;
+    }
+
+    /// Current state, or None before the first update
+    pub fn snapshot(&self) -> Option<NoiseFloorSnapshot> {
+        let to_db = |density: f64| 10.0 * (density * SNR_REFERENCE_BANDWIDTH).max(1e-20).log10();
+
+        Some(NoiseFloorSnapshot {
+            floor_db: to_db(self.floor_density?),
+            peak_db: to_db(self.peak_density?),
+            last_block_db: to_db(self.last_density?),
+            updates: self.updates,
+        })
+    }
+}
+
+impl Default for NoiseFloorTracker {
+    fn default() -> Self {
+        Self::new(NoiseFloorConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_rejects_bursts_and_adapts_slowly() {
+        let mut tracker = NoiseFloorTracker::default();
+        let bw = tracker.bandwidth_hz();
+
+        // Noise at 1e-4 per window with one loud burst
+        let mut powers = vec![1e-4; 31];
+        powers.push(1.0);
+        tracker.update_from_window_powers(&powers);
+
+        let snap = tracker.snapshot().unwrap();
+        let expected_floor = 10.0 * (1e-4 / bw * SNR_REFERENCE_BANDWIDTH).log10();
+        assert!((snap.floor_db - expected_floor).abs() < 1e-9);
+        assert!(snap.peak_db > snap.floor_db + 30.0);
+
+        // Band gets 10 dB louder: one block moves the floor only a little
+        tracker.update_from_window_powers(&vec![1e-3; 32]);
+        let snap = tracker.snapshot().unwrap();
+        assert!(snap.floor_db > expected_floor && snap.floor_db < expected_floor + 2.0);
+        assert!((snap.last_block_db - (expected_floor + 10.0)).abs() < 1e-9);
+        assert_eq!(snap.updates, 2);
+    }
+
+    #[test]
+    fn test_calibrated_snr() {
+        let mut tracker = NoiseFloorTracker::default();
+        assert!(tracker.snr_db(1.0).is_none());
+
+        let bw = tracker.bandwidth_hz();
+        tracker.update_from_window_powers(&[bw * 1e-6; 8]);
+
+        // Signal power equal to the noise in 2500 Hz -> 0 dB
+        let noise_in_band = bw * 1e-6;
+        let signal = SNR_REFERENCE_BANDWIDTH * 1e-6;
+        let snr = tracker.snr_db(noise_in_band + signal).unwrap();
+        assert!(snr.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,17 @@
+/// Event Observers
+///
+/// Monitors that sit in a capture loop (`SyncLock`, `InputHealthMonitor`)
+/// return their events from the plain update call and deliver them to an
+/// `Observer` from the `_observed` variant. Any `FnMut(&E)` is an observer,
+/// so a closure that logs or forwards to a channel is enough.
+
+/// Receiver of events of type `E`
+pub trait Observer<E> {
+    fn notify(&mut self, event: &E);
+}
+
+impl<E, F: FnMut(&E)> Observer<E> for F {
+    fn notify(&mut self, event: &E) {
+        self(event)
+    }
+}
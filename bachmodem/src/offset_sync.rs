@@ -0,0 +1,248 @@
+/// Frequency-Offset-Tolerant Preamble Search
+///
+/// The preamble correlation only peaks when the received notes line up with
+/// the template's. A rig a few tens of hertz off moves every note away from
+/// its template tone, the notes stop adding up and sync fails before AFC
+/// (see `afc`) or the retry ladder's offset search get a frame to work on.
+///
+/// `measure_sync_with_offsets` searches time and frequency together: the
+/// analytic preamble is shifted to each candidate offset (e.g. ±50 Hz in
+/// 5 Hz steps, `offset_search`), the replicas' spectra are stacked into one
+/// batch and correlated against the capture's spectrum with a single
+/// batched inverse FFT per `OFFSET_BATCH` offsets, all on the device. The
+/// envelope of each row is immune to the carrier phase, the strongest cell
+/// is the (time, frequency) hypothesis, and the offset is refined by a
+/// parabola through the neighbouring offsets' peaks.
+///
+/// `synchronize_data_start_with_offsets` then removes the offset and runs
+/// the full-rate sync around the hypothesis, with sweep-cycle ambiguity
+/// resolution, for a data start on the corrected capture. It is the offset
+/// search of the retry ladder's single-peak rungs; AFC refines what is left
+/// inside the demodulators.
+
+use std::f64::consts::PI;
+use burn::tensor::{Tensor, Int, ElementConversion, backend::Backend};
+use crate::complex::{ComplexTensor, shift_frequency};
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{measure_sync, SyncMetrics, SyncThresholds};
+use crate::sync_ambiguity::resolve_sync_ambiguity;
+use crate::wavelet::generate_bach_preamble_with_config;
+
+/// Offset search range for off-air recordings (Hz)
+pub const DEFAULT_OFFSET_RANGE_HZ: f64 = 50.0;
+
+/// Offset grid step (Hz): well inside a preamble note's frequency mainlobe
+pub const DEFAULT_OFFSET_STEP_HZ: f64 = 5.0;
+
+/// Offsets correlated per batched inverse FFT
+const OFFSET_BATCH: usize = 8;
+
+/// Full-rate refinement window around the hypothesis (± samples)
+const OFFSET_FINE_SEARCH: usize = 32;
+
+/// Best (time, frequency) hypothesis of the preamble
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OffsetSyncMetrics {
+    /// Peak position (preamble start, samples)
+    pub position: usize,
+
+    /// Carrier offset (Hz, positive = received high), interpolated between
+    /// the grid offsets
+    pub offset_hz: f64,
+
+    /// Envelope peak over the preamble norm
+    pub correlation: f32,
+
+    /// Squared-envelope peak over its mean, on the real-correlation scale
+    pub peak_to_noise: f32,
+}
+
+impl OffsetSyncMetrics {
+    /// The hypothesis as plain sync metrics, for `SyncThresholds::accepts`
+    pub fn sync_metrics(&self) -> SyncMetrics {
+        SyncMetrics { position: self.position, correlation: self.correlation, peak_to_noise: self.peak_to_noise }
+    }
+}
+
+/// Squared correlation envelope of `signal` against `preamble` shifted by
+/// each of `offsets_hz`, valid lags only: [offsets, N - M + 1]
+///
+/// **NO SYNC POINT**
+pub fn offset_correlation_gpu<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    preamble: &Tensor<B, 1>,
+    offsets_hz: &[f64],
+    sample_rate: f64,
+) -> Tensor<B, 2> {
+    let (n, m) = (signal.dims()[0], preamble.dims()[0]);
+    let fft_len = n.next_power_of_two();
+    let rows = offsets_hz.len();
+
+    // Phases reduced modulo one cycle in f64, like `shift_frequency`
+    let (cos, sin): (Vec<f32>, Vec<f32>) = offsets_hz.iter()
+        .flat_map(|&offset| (0..m).map(move |i| {
+            let phase = 2.0 * PI * (offset * i as f64 / sample_rate).rem_euclid(1.0);
+            (phase.cos() as f32, phase.sin() as f32)
+        }))
+        .unzip();
+    let rotation = ComplexTensor::new(
+        Tensor::<B, 1>::from_floats(cos.as_slice(), device).reshape([rows, m]),
+        Tensor::<B, 1>::from_floats(sin.as_slice(), device).reshape([rows, m]),
+    );
+    let analytic = ComplexTensor::analytic(preamble.clone()).map(|t| t.reshape([1, m]));
+    let replicas = (analytic * rotation)
+        .map(|t| Tensor::cat(vec![t, Tensor::zeros([rows, fft_len - m], device)], 1))
+        .fft();
+
+    let padded = Tensor::cat(vec![signal.clone(), Tensor::zeros([fft_len - n], device)], 0).reshape([1, fft_len]);
+    let spectrum = ComplexTensor::from_real(padded).fft();
+    (spectrum * replicas.conj())
+        .ifft()
+        .norm_sqr()
+        .slice([0..rows, 0..n - m + 1])
+}
+
+/// Vertex of the parabola through three (offset, peak) points, kept
+/// between the outer two
+fn parabola_vertex((x0, y0): (f64, f64), (x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> f64 {
+    let numerator = (x1 - x0).powi(2) * (y1 - y2) - (x1 - x2).powi(2) * (y1 - y0);
+    let denominator = (x1 - x0) * (y1 - y2) - (x1 - x2) * (y1 - y0);
+    if denominator.abs() < 1e-12 {
+        return x1;
+    }
+    (x1 - 0.5 * numerator / denominator).clamp(x0, x2)
+}
+
+/// Strongest preamble hypothesis over `offsets_hz` (any order, see
+/// `offset_search`), without a detection decision; None if the signal is
+/// shorter than the preamble or no offsets are given
+///
+/// ⚠️ **SYNC POINT**: Downloads the peak of every offset
+pub fn measure_sync_with_offsets<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    offsets_hz: &[f64],
+) -> Option<OffsetSyncMetrics> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let (n, m) = (signal.dims()[0], preamble.dims()[0]);
+    if n < m || offsets_hz.is_empty() {
+        return None;
+    }
+    let mut offsets = offsets_hz.to_vec();
+    offsets.sort_by(|a, b| a.total_cmp(b));
+
+    // Peak, its lag and the mean of every row; one download
+    let (peaks, lags, means): (Vec<_>, Vec<_>, Vec<_>) = offsets.chunks(OFFSET_BATCH)
+        .map(|batch| {
+            let power = offset_correlation_gpu(device, signal, &preamble, batch, config.sample_rate);
+            let rows = batch.len();
+            let (peak, lag) = power.clone().max_dim_with_indices(1);
+            (peak.reshape([rows]), lag.reshape([rows]), power.mean_dim(1).reshape([rows]))
+        })
+        .fold((Vec::new(), Vec::new(), Vec::new()), |(mut p, mut l, mut a), (peak, lag, mean)| {
+            p.push(peak);
+            l.push(lag);
+            a.push(mean);
+            (p, l, a)
+        });
+    let rows = offsets.len();
+    let stats: Vec<f32> = Tensor::cat(vec![Tensor::cat(peaks, 0), Tensor::cat(means, 0)], 0).into_data().to_vec().unwrap();
+    let lags: Vec<i64> = Tensor::<B, 1, Int>::cat(lags, 0).into_data().convert::<i64>().to_vec().unwrap();
+    let (peaks, means) = stats.split_at(rows);
+
+    let best = (0..rows).max_by(|&a, &b| peaks[a].total_cmp(&peaks[b]))?;
+    let envelope = |k: usize| (peaks[k] as f64).sqrt();
+    let offset_hz = if best > 0 && best + 1 < rows {
+        parabola_vertex(
+            (offsets[best - 1], envelope(best - 1)),
+            (offsets[best], envelope(best)),
+            (offsets[best + 1], envelope(best + 1)),
+        )
+    } else {
+        offsets[best]
+    };
+
+    let preamble_norm: f32 = preamble.powf_scalar(2.0).sum().sqrt().into_scalar().elem();
+    let metrics = OffsetSyncMetrics {
+        position: lags[best] as usize,
+        offset_hz,
+        correlation: peaks[best].sqrt() / preamble_norm,
+        // The envelope's noise power is the in-phase plus the quadrature
+        // part: twice the real correlation's for the same peak
+        peak_to_noise: 2.0 * peaks[best] / (means[best] + 1e-10),
+    };
+    Some(metrics)
+}
+
+/// First data sample after the preamble and the carrier offset (Hz) it was
+/// found at; the data start is on the capture with the offset removed
+/// (`shift_frequency` by minus the offset)
+///
+/// ⚠️ **SYNC POINT**
+pub fn synchronize_data_start_with_offsets<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    thresholds: &SyncThresholds,
+    offsets_hz: &[f64],
+) -> Option<(usize, f64)> {
+    let hypothesis = measure_sync_with_offsets::<B>(device, signal, config, offsets_hz)?;
+    if !thresholds.accepts(&hypothesis.sync_metrics()) {
+        return None;
+    }
+
+    // Full-rate peak on the corrected capture, around the hypothesis
+    let corrected = shift_frequency(device, signal, -hypothesis.offset_hz, config.sample_rate);
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let n = signal.dims()[0];
+    let start = hypothesis.position.saturating_sub(OFFSET_FINE_SEARCH);
+    let end = (hypothesis.position + OFFSET_FINE_SEARCH + preamble.dims()[0]).min(n);
+    let fine = measure_sync::<B>(device, &corrected.clone().slice([start..end]), config)?;
+
+    let data_start = resolve_sync_ambiguity(device, &corrected, &preamble, start + fine.position, config);
+    Some((data_start, hypothesis.offset_hz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::{modulate_fhdpsk_with_config, synchronize_data_start_with_config};
+    use crate::retry_ladder::offset_search;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_finds_mistuned_preamble() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let frame = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Off the dial", true, &config);
+        let lead_in = 12_345;
+        let clean = Tensor::cat(vec![Tensor::zeros([lead_in], &device), frame, Tensor::zeros([4000], &device)], 0);
+        let len = clean.dims()[0];
+        let expected = synchronize_data_start_with_config::<TestBackend>(&device, &clean, &config).unwrap();
+
+        let mut rng = SplitMix64::new(17);
+        let rx = shift_frequency(&device, &clean, 23.0, config.sample_rate) + gaussian_noise::<TestBackend, _>(&device, len, 0.3, &mut rng);
+        let offsets = offset_search(DEFAULT_OFFSET_RANGE_HZ, DEFAULT_OFFSET_STEP_HZ);
+
+        let hypothesis = measure_sync_with_offsets::<TestBackend>(&device, &rx, &config, &offsets).unwrap();
+        assert!((hypothesis.offset_hz - 23.0).abs() < DEFAULT_OFFSET_STEP_HZ / 2.0, "{:?}", hypothesis);
+        assert!(hypothesis.position.abs_diff(lead_in) < 50, "{:?}", hypothesis);
+
+        let (data_start, offset_hz) = synchronize_data_start_with_offsets::<TestBackend>(
+            &device, &rx, &config, &SyncThresholds::default(), &offsets,
+        ).unwrap();
+        assert!(data_start.abs_diff(expected) <= 2, "data start {} expected {}", data_start, expected);
+        assert_eq!(offset_hz, hypothesis.offset_hz);
+    }
+
+    #[test]
+    fn test_parabola_vertex() {
+        let f = |x: f64| 4.0 - (x - 1.3).powi(2);
+        assert!((parabola_vertex((0.0, f(0.0)), (2.0, f(2.0)), (5.0, f(5.0))) - 1.3).abs() < 1e-9);
+        assert_eq!(parabola_vertex((0.0, 1.0), (1.0, 1.0), (2.0, 1.0)), 1.0);
+    }
+}
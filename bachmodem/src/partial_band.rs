@@ -0,0 +1,224 @@
+/// Partial-Band Reception
+///
+/// A receive filter narrower than the alphabet (or one shifted off it) cuts
+/// the outer tones. The lag-differential detector still produces a phase
+/// for every slot on those tones, and the polar decoder takes the noise as
+/// confident bits, so decoding degrades unpredictably with the filter edge.
+///
+/// The preamble sweeps every tone four times at a known position, which
+/// makes it a cheap passband measurement: the matched-filter power of each
+/// tone's notes is compared against the median tone, and tones more than
+/// `MISSING_TONE_DB` below it are declared missing. Every bit whose symbol
+/// or phase reference sits on a missing tone becomes an erasure instead of
+/// a confident wrong LLR. The median is the reference, so detection works
+/// as long as most of the alphabet gets through. Enabled by
+/// `ModemConfig::partial_band`; the receive chain (`capture_llrs`) measures
+/// the preamble once per capture, at the data start sync found.
+///
+/// The erasures only help if the frame code can fill a missing tone in.
+/// With the interleaver width equal to the tone count, each tone slot
+/// carries an aligned row of the (256, 128) polar codeword, and the code
+/// has codewords supported inside every such row: one erased tone leaves
+/// the frame ambiguous for any decoder. Partial-band mode therefore gives
+/// the interleaver half as many rows as there are tones
+/// (`ModemConfig::interleaver_columns`), so every tone slot carries
+/// alternate bits of a row and one missing tone costs each codeword pair at
+/// most one bit. The transmitter must set the
+/// mode too. A transmitter that knows its passband avoids the top tones
+/// instead (`ModemConfig::with_max_frequency`, the `lowband` profile),
+/// which needs no erasures at all.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::config::ModemConfig;
+use crate::wavelet::{shaped_wavelet, preamble_tone_sequence, WaveletShape, FS};
+
+/// Tones this far below the median preamble tone are missing (dB)
+pub const MISSING_TONE_DB: f32 = 10.0;
+
+/// Matched-filter power of every tone over a received preamble (dB)
+///
+/// `preamble_start` is the first preamble sample in `signal`. Each tone's
+/// notes are averaged with the tone gain removed; tones the preamble never
+/// transmits (see `ModemConfig::usable_tones`) come out as -inf. None if the
+/// preamble doesn't lie entirely inside the signal.
+///
+/// ⚠️ **SYNC POINT**: Downloads one power per note
+pub fn preamble_tone_levels<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    preamble_start: usize,
+    config: &ModemConfig,
+) -> Option<Vec<f32>> {
+    let note_len = config.preamble_note_samples();
+    let tones: Vec<usize> = preamble_tone_sequence(config).into_iter()
+        .map(|slot| config.transmit_tone(slot))
+        .collect();
+    let num_notes = tones.len();
+    if preamble_start + num_notes * note_len > signal.dims()[0] {
+        return None;
+    }
+
+    // Note references: [num_notes, note_len]
+    let frequencies = config.frequencies();
+    let (refs_real, refs_imag): (Vec<_>, Vec<_>) = tones.iter()
+        .map(|&tone| shaped_wavelet::<B>(device, frequencies[tone], config.preamble_note_duration, WaveletShape::morlet(config.wavelet_sigmas), FS))
+        .unzip();
+    let refs_real: Tensor<B, 2> = Tensor::stack(refs_real, 0);
+    let refs_imag: Tensor<B, 2> = Tensor::stack(refs_imag, 0);
+
+    let notes = signal.clone()
+        .slice([preamble_start..preamble_start + num_notes * note_len])
+        .reshape([num_notes, note_len]);
+    let real = (notes.clone() * refs_real).sum_dim(1).reshape([num_notes]);
+    let imag = (notes * refs_imag).sum_dim(1).reshape([num_notes]);
+    let power: Vec<f32> = (real.powf_scalar(2.0) + imag.powf_scalar(2.0)).into_data().to_vec().unwrap();
+
+    let mut total = vec![0.0f64; config.num_tones];
+    let mut count = vec![0usize; config.num_tones];
+    for (&tone, &p) in tones.iter().zip(&power) {
+        total[tone] += p as f64 / config.tone_gain(tone).powi(2);
+        count[tone] += 1;
+    }
+
+    Some(total.iter().zip(&count)
+        .map(|(&t, &n)| if n > 0 { (10.0 * (t / n as f64).max(1e-30).log10()) as f32 } else { f32::NEG_INFINITY })
+        .collect())
+}
+
+/// Tones more than `MISSING_TONE_DB` below the median of the measured ones
+///
+/// Unmeasured tones (-inf) are never missing: nothing was sent on them.
+pub fn missing_tones(levels_db: &[f32]) -> Vec<bool> {
+    let mut measured: Vec<f32> = levels_db.iter().copied().filter(|l| l.is_finite()).collect();
+    if measured.is_empty() {
+        return vec![false; levels_db.len()];
+    }
+    measured.sort_by(|a, b| a.total_cmp(b));
+    let median = measured[measured.len() / 2];
+
+    levels_db.iter().map(|&l| l.is_finite() && l < median - MISSING_TONE_DB).collect()
+}
+
+/// Missing tones of the frame whose data starts at `data_start`
+///
+/// None if the preamble began before the capture.
+///
+/// ⚠️ **SYNC POINT**
+pub fn detect_missing_tones<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    data_start: usize,
+    config: &ModemConfig,
+) -> Option<Vec<bool>> {
+    let preamble_len = preamble_tone_sequence(config).len() * config.preamble_note_samples();
+    let preamble_start = data_start.checked_sub(preamble_len)?;
+    let levels = preamble_tone_levels::<B>(device, signal, preamble_start, config)?;
+    Some(missing_tones(&levels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::receiver_pool::{decode_capture, ReceiverPoolConfig};
+    use crate::receiver_state::ReceiverState;
+    use crate::transmitter::BachTransmitter;
+    use crate::wavelet::generate_bach_preamble_with_config;
+    use bachmodem_core::bits::encode_bits;
+    use bachmodem_core::frame::{decode_frame, encode_frame};
+    use crate::TestBackend;
+
+    #[test]
+    fn test_missing_tones_against_median() {
+        let mut levels = vec![-3.0f32; 16];
+        levels[14] = -20.0;
+        levels[15] = -40.0;
+        levels[2] = -9.0; // Filter ripple, not missing
+
+        let missing = missing_tones(&levels);
+        assert_eq!(missing.iter().filter(|&&m| m).count(), 2);
+        assert!(missing[14] && missing[15]);
+
+        levels[15] = f32::NEG_INFINITY;
+        assert!(!missing_tones(&levels)[15]);
+    }
+
+    #[test]
+    fn test_fec_fills_one_missing_tone() {
+        // Bit k rides tone slot k mod num_tones (one reference block, BPSK)
+        for config in [ModemConfig::narrowband(), ModemConfig::default(), ModemConfig::wideband()] {
+            let num_tones = config.num_tones;
+            for (partial_band, decodes) in [(false, num_tones == 32), (true, true)] {
+                let columns = config.clone().with_partial_band(partial_band).interleaver_columns();
+                let bits = encode_bits(&encode_frame(b"CUT BAND", columns));
+                for slot in 0..num_tones {
+                    let llrs: Vec<f32> = bits.iter().enumerate()
+                        .map(|(k, &b)| if k % num_tones == slot { 0.0 } else if b == 0 { 1.0 } else { -1.0 })
+                        .collect();
+                    let decoded = decode_frame(&llrs, columns).is_ok_and(|payload| payload.starts_with(b"CUT BAND"));
+                    assert_eq!(decoded, decodes, "{} tones, partial band {}, slot {} erased", num_tones, partial_band, slot);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_preamble_levels_show_cut_tones() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let note_len = config.preamble_note_samples();
+
+        // Preamble behind 400 samples of lead-in, top two tones' notes muted
+        let preamble: Vec<f32> = generate_bach_preamble_with_config::<TestBackend>(&device, &config)
+            .into_data().to_vec().unwrap();
+        let mut samples = vec![0.0f32; 400];
+        for (note, &slot) in preamble.chunks(note_len).zip(&preamble_tone_sequence(&config)) {
+            let gain = if slot >= 14 { 0.0 } else { 1.0 };
+            samples.extend(note.iter().map(|&x| x * gain));
+        }
+        let signal = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device);
+
+        let data_start = 400 + preamble.len();
+        let missing = detect_missing_tones::<TestBackend>(&device, &signal, data_start, &config).unwrap();
+        assert_eq!(missing.iter().filter(|&&m| m).count(), 2);
+        assert!(missing[14] && missing[15]);
+        assert!(detect_missing_tones::<TestBackend>(&device, &signal, 200, &config).is_none());
+
+        // A folded transmitter sends nothing on its top tones
+        let lowband = ModemConfig::profile("lowband").unwrap();
+        let preamble = generate_bach_preamble_with_config::<TestBackend>(&device, &lowband);
+        let levels = preamble_tone_levels::<TestBackend>(&device, &preamble, 0, &lowband).unwrap();
+        assert!(levels[..13].iter().all(|l| l.is_finite()));
+        assert!(levels[13..].iter().all(|l| *l == f32::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_frame_decodes_through_cut_band() {
+        let device = Default::default();
+        let modem = ModemConfig::default().with_partial_band(true);
+
+        // The filter takes the top tone 80 dB down, preamble and data alike
+        let mut gains = vec![1.0; modem.num_tones];
+        gains[modem.num_tones - 1] = 1e-4;
+        let tx = BachTransmitter::new(modem.clone().with_tone_gains(gains));
+        let frame = tx.build::<TestBackend>(&device, b"CUT BAND").unwrap();
+        let rx = Tensor::cat(vec![Tensor::zeros([1500], &device), frame, Tensor::zeros([1000], &device)], 0);
+
+        let config = ReceiverPoolConfig { modem, ..ReceiverPoolConfig::default() };
+        let mut state = ReceiverState::<TestBackend>::default();
+        let decoded = decode_capture(&device, &mut state, &config, &rx).unwrap();
+        assert_eq!(&decoded.payload[..8], b"CUT BAND");
+    }
+
+    #[test]
+    fn test_lowband_frame_decodes() {
+        let device = Default::default();
+        let modem = ModemConfig::profile("lowband").unwrap();
+        let frame = BachTransmitter::new(modem.clone()).build::<TestBackend>(&device, b"LOWBAND").unwrap();
+        let rx = Tensor::cat(vec![Tensor::zeros([1500], &device), frame, Tensor::zeros([1000], &device)], 0);
+
+        let config = ReceiverPoolConfig { modem, ..ReceiverPoolConfig::default() };
+        let mut state = ReceiverState::<TestBackend>::default();
+        let decoded = decode_capture(&device, &mut state, &config, &rx).unwrap();
+        assert_eq!(&decoded.payload[..7], b"LOWBAND");
+    }
+}
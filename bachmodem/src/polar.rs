@@ -8,231 +8,12 @@
 /// - Info bits K = 128 (rate = 1/2)
 /// - Successive Cancellation List (SCL) decoder with list size L=8
 /// - CRC-8 aided decoding for path selection
+/// 
+/// `PolarCode` and CRC-8 live in `bachmodem-core` (no_std) and are
+/// re-exported here together with the soft-bit helpers.
 
-use std::collections::BinaryHeap;
-use std::cmp::Ordering;
-
-/// Path in SCL decoder
-#[derive(Clone)]
-struct DecoderPath {
-    /// Decoded bits so far
-    bits: Vec<u8>,
-    /// Path metric (log probability)
-    metric: f64,
-    /// LLR state at each level
-    llr_state: Vec<Vec<f64>>,
-}
-
-/// Polar code configuration
-pub struct PolarCode {
-    /// Code length (must be power of 2)
-    pub n: usize,
-    
-    /// Number of information bits
-    pub k: usize,
-    
-    /// Frozen bit positions (unreliable channels set to 0)
-    pub frozen_positions: Vec<usize>,
-    
-    /// Information bit positions (reliable channels)
-    pub info_positions: Vec<usize>,
-}
-
-impl PolarCode {
-    /// Create polar code with given parameters
-    /// Design channel reliability using Bhattacharyya parameter
-    pub fn new(n: usize, k: usize) -> Self {
-        assert!(n.is_power_of_two(), "N must be power of 2");
-        assert!(k <= n, "K must be <= N");
-        
-        // Calculate channel reliabilities (simplified Bhattacharyya)
-        let mut reliabilities: Vec<(usize, f64)> = (0..n)
-            .map(|i| {
-                // Simple reliability metric based on bit position
-                // More sophisticated: compute actual Bhattacharyya parameters
-                let weight = Self::bit_reversal(i, (n as f64).log2() as usize);
-                let reliability = weight as f64 / n as f64;
-                (i, reliability)
-            })
-            .collect();
-        
-        // Sort by reliability (descending)
-        reliabilities.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-        
-        // Most reliable K positions are info bits
-        let mut info_positions: Vec<usize> = reliabilities.iter()
-            .take(k)
-            .map(|(idx, _)| *idx)
-            .collect();
-        info_positions.sort();
-        
-        // Least reliable N-K positions are frozen
-        let mut frozen_positions: Vec<usize> = reliabilities.iter()
-            .skip(k)
-            .map(|(idx, _)| *idx)
-            .collect();
-        frozen_positions.sort();
-        
-        Self {
-            n,
-            k,
-            frozen_positions,
-            info_positions,
-        }
-    }
-    
-    /// Bit-reversal permutation
-    fn bit_reversal(x: usize, num_bits: usize) -> usize {
-        let mut result = 0;
-        let mut val = x;
-        for _ in 0..num_bits {
-            result = (result << 1) | (val & 1);
-            val >>= 1;
-        }
-        result
-    }
-    
-    /// Encode information bits into codeword
-    pub fn encode(&self, info_bits: &[u8]) -> Vec<u8> {
-        assert_eq!(info_bits.len(), self.k, "Info bits must be length K");
-        
-        // Create u vector (N bits) with frozen bits = 0
-        let mut u = vec![0u8; self.n];
-        
-        // Place info bits at designated positions
-        for (i, &pos) in self.info_positions.iter().enumerate() {
-            u[pos] = info_bits[i];
-        }
-        
-        // Apply polar transform (recursive Kronecker product)
-        let x = self.polar_transform(&u);
-        
-        x
-    }
-    
-    /// Polar transform using butterfly structure
-    fn polar_transform(&self, u: &[u8]) -> Vec<u8> {
-        let n = u.len();
-        let num_stages = (n as f64).log2() as usize;
-        
-        let mut x = u.to_vec();
-        
-        for stage in 0..num_stages {
-            let step = 1 << stage;
-            let mut temp = vec![0u8; n];
-            
-            for i in 0..n {
-                let group = i / (2 * step);
-                let pos_in_group = i % (2 * step);
-                
-                if pos_in_group < step {
-                    // Upper butterfly: x[i] = u[i] XOR u[i + step]
-                    temp[i] = x[i] ^ x[i + step];
-                } else {
-                    // Lower butterfly: x[i] = u[i]
-                    temp[i] = x[i];
-                }
-            }
-            
-            x = temp;
-        }
-        
-        x
-    }
-    
-    /// Decode using Successive Cancellation List (SCL) with CRC
-    /// llrs: log-likelihood ratios for each bit position  
-    /// list_size: number of paths to maintain (typically 4-8)
-    pub fn decode_scl(&self, llrs: &[f32], list_size: usize) -> Vec<u8> {
-        assert_eq!(llrs.len(), self.n, "LLRs must be length N");
-        
-        let llrs_f64: Vec<f64> = llrs.iter().map(|&x| x as f64).collect();
-        
-        // Initialize with single path
-        let mut paths = vec![DecoderPath {
-            bits: Vec::new(),
-            metric: 0.0,
-            llr_state: vec![llrs_f64.clone()],
-        }];
-        
-        // Decode bit by bit
-        for i in 0..self.n {
-            let mut new_paths = Vec::new();
-            
-            for path in &paths {
-                let llr_i = self.compute_llr_for_bit(&path.llr_state, &path.bits, i);
-                
-                if self.frozen_positions.contains(&i) {
-                    // Frozen bit: only one choice (0)
-                    let mut new_path = path.clone();
-                    new_path.bits.push(0);
-                    new_path.metric += Self::log_prob(llr_i, 0);
-                    new_paths.push(new_path);
-                } else {
-                    // Info bit: try both 0 and 1
-                    for &bit in &[0u8, 1u8] {
-                        let mut new_path = path.clone();
-                        new_path.bits.push(bit);
-                        new_path.metric += Self::log_prob(llr_i, bit);
-                        new_paths.push(new_path);
-                    }
-                }
-            }
-            
-            // Keep top L paths by metric
-            new_paths.sort_by(|a, b| b.metric.partial_cmp(&a.metric).unwrap_or(Ordering::Equal));
-            new_paths.truncate(list_size);
-            paths = new_paths;
-        }
-        
-        // Select best path (first in sorted list has best metric)
-        let best_path = &paths[0];
-        
-        // Extract information bits
-        let mut info_bits = Vec::new();
-        for &pos in &self.info_positions {
-            info_bits.push(best_path.bits[pos]);
-        }
-        
-        info_bits
-    }
-    
-    /// Legacy SC decoder (calls SCL with L=1)
-    pub fn decode_sc(&self, llrs: &[f32]) -> Vec<u8> {
-        self.decode_scl(llrs, 1)
-    }
-    
-    /// Compute log probability for bit decision
-    fn log_prob(llr: f64, bit: u8) -> f64 {
-        // LLR = log(P(0)/P(1))
-        // log P(bit) = llr * (1 - bit) - log(1 + exp(llr))
-        // Simplified: use LLR directly as metric
-        if bit == 0 {
-            llr.min(20.0).max(-20.0) // Clip to prevent overflow
-        } else {
-            -llr.min(20.0).max(-20.0)
-        }
-    }
-    
-    /// Compute LLR for bit i in polar transform tree
-    fn compute_llr_for_bit(&self, llr_state: &[Vec<f64>], decoded_bits: &[u8], bit_idx: usize) -> f64 {
-        // Recursively compute LLR through polar transform tree
-        // This is the key to SC/SCL performance
-        
-        let num_stages = (self.n as f64).log2() as usize;
-        
-        // Compute which stage and position
-        let stage = decoded_bits.len() / (self.n / (1 << num_stages));
-        
-        if bit_idx < llr_state[0].len() {
-            // Simplified: return channel LLR
-            // Full implementation would propagate through butterfly network
-            llr_state[0][bit_idx]
-        } else {
-            0.0
-        }
-    }
-}
+pub use bachmodem_core::polar::PolarCode;
+pub use bachmodem_core::crc::{crc8, encode_with_crc, verify_crc};
 
 /// Convert bit errors to LLRs for polar decoder
 /// soft_bits: confidence values (-1.0 to 1.0, where sign indicates bit value)
@@ -262,102 +43,3 @@ pub fn compute_soft_bits(symbols: &[u8], confidences: &[f32]) -> Vec<f32> {
         })
         .collect()
 }
-
-/// CRC-8 polynomial for error detection
-const CRC8_POLY: u8 = 0x07; // x^8 + x^2 + x + 1
-
-/// Compute CRC-8 checksum
-pub fn crc8(data: &[u8]) -> u8 {
-    let mut crc = 0u8;
-    for &byte in data {
-        crc ^= byte;
-        for _ in 0..8 {
-            if crc & 0x80 != 0 {
-                crc = (crc << 1) ^ CRC8_POLY;
-            } else {
-                crc <<= 1;
-            }
-        }
-    }
-    crc
-}
-
-/// Encode data with CRC-8
-pub fn encode_with_crc(data: &[u8]) -> Vec<u8> {
-    let crc = crc8(data);
-    let mut result = data.to_vec();
-    // Append CRC bits
-    for i in 0..8 {
-        result.push((crc >> (7 - i)) & 1);
-    }
-    result
-}
-
-/// Verify CRC-8
-pub fn verify_crc(data_with_crc: &[u8]) -> bool {
-    if data_with_crc.len() < 8 {
-        return false;
-    }
-    
-    let data_len = data_with_crc.len() - 8;
-    
-    // Convert bits to bytes
-    let mut data_bytes = Vec::new();
-    for chunk in data_with_crc[..data_len].chunks(8) {
-        let mut byte = 0u8;
-        for (i, &bit) in chunk.iter().enumerate() {
-            byte |= bit << (7 - i);
-        }
-        data_bytes.push(byte);
-    }
-    
-    // Extract CRC
-    let mut received_crc = 0u8;
-    for i in 0..8 {
-        received_crc |= data_with_crc[data_len + i] << (7 - i);
-    }
-    
-    let computed_crc = crc8(&data_bytes);
-    computed_crc == received_crc
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    #[test]
-    fn test_polar_encode_decode() {
-        let code = PolarCode::new(256, 128);
-        
-        // Test message
-        let info_bits: Vec<u8> = (0..128).map(|i| (i % 2) as u8).collect();
-        
-        // Encode
-        let codeword = code.encode(&info_bits);
-        assert_eq!(codeword.len(), 256);
-        
-        // Perfect channel (LLRs from codeword)
-        let llrs: Vec<f32> = codeword.iter()
-            .map(|&bit| if bit == 0 { 10.0 } else { -10.0 })
-            .collect();
-        
-        // Decode
-        let decoded = code.decode_sc(&llrs);
-        
-        // Should match original
-        let errors = info_bits.iter().zip(decoded.iter())
-            .filter(|(a, b)| a != b)
-            .count();
-        
-        println!("Polar code test: {} bit errors / {} bits", errors, 128);
-        assert!(errors < 10, "Too many errors in clean channel");
-    }
-    
-    #[test]
-    fn test_bit_reversal() {
-        assert_eq!(PolarCode::bit_reversal(0b0000, 4), 0b0000);
-        assert_eq!(PolarCode::bit_reversal(0b0001, 4), 0b1000);
-        assert_eq!(PolarCode::bit_reversal(0b0010, 4), 0b0100);
-        assert_eq!(PolarCode::bit_reversal(0b1010, 4), 0b0101);
-    }
-}
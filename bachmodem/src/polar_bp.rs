@@ -31,6 +31,37 @@ impl PolarCodeBP {
         device: &B::Device,
         llrs: &Tensor<B, 1>,
         iterations: usize,
+    ) -> Tensor<B, 1> {
+        self.decode_bp_impl(device, llrs, iterations, None)
+    }
+    
+    /// `decode_bp`, also returning the hard decisions on u after every
+    /// iteration: [iterations, N], 1.0 = bit 1 (see `decision_trace`)
+    /// 
+    /// **NO SYNC POINT**
+    pub fn decode_bp_traced<B: Backend>(
+        &self,
+        device: &B::Device,
+        llrs: &Tensor<B, 1>,
+        iterations: usize,
+    ) -> (Tensor<B, 1>, Tensor<B, 2>) {
+        let mut decisions = Vec::with_capacity(iterations);
+        let final_llr = self.decode_bp_impl(device, llrs, iterations, Some(&mut decisions));
+        let n = self.n;
+        let decisions = if decisions.is_empty() {
+            Tensor::zeros([0, n], device)
+        } else {
+            Tensor::stack(decisions, 0)
+        };
+        (final_llr, decisions)
+    }
+    
+    fn decode_bp_impl<B: Backend>(
+        &self,
+        device: &B::Device,
+        llrs: &Tensor<B, 1>,
+        iterations: usize,
+        mut decisions: Option<&mut Vec<Tensor<B, 1>>>,
     ) -> Tensor<B, 1> {
         let n = self.n;
         let stages = (n as f64).log2() as usize;
@@ -174,6 +205,11 @@ impl PolarCodeBP {
                 let r_out_stacked: Tensor<B, 3> = Tensor::stack(vec![r_out_u, r_out_l], 1);
                 r_stages[s] = r_out_stacked.reshape([n]);
             }
+            
+            if let Some(decisions) = decisions.as_mut() {
+                let llr = l_stages[stages].clone() + r_stages[stages].clone();
+                decisions.push(llr.lower_elem(0.0).float());
+            }
         }
         
         // Final decision based on L at last stage + R at last stage (priors)
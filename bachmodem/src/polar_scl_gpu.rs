@@ -0,0 +1,248 @@
+/// Polar Codes with Successive Cancellation List (SCL) Decoder on GPU
+///
+/// All L paths are decoded in lockstep: LLR (alpha) and partial-sum (beta)
+/// states live in `[L, len]` tensors per tree depth, path metrics in `[L]`.
+/// At every information bit the 2L candidate metrics are sorted on the GPU
+/// and the surviving paths are gathered with `select` - no per-bit cloning
+/// on the CPU, so L = 8..32 stays practical.
+///
+/// Tree recursion matches `PolarCode::encode`:
+///   x = [T(u_first) ⊕ T(u_second), T(u_second)]
+/// - f (upper): alpha_u' = minsum(alpha_u, alpha_l)
+/// - g (lower): alpha_l' = alpha_l + (1 - 2·beta_u) · alpha_u
+///
+/// Path metric (lower = better): add |LLR| whenever a decision disagrees
+/// with the LLR sign (min-sum approximation of -log P).
+
+use burn::tensor::{Tensor, Int, backend::Backend, activation::relu};
+use crate::polar::{PolarCode, verify_crc};
+
+/// Metric assigned to unused list slots
+const INACTIVE_METRIC: f32 = 1e9;
+
+pub struct PolarCodeSCL {
+    pub n: usize,
+    pub k: usize,
+    pub frozen_mask: Vec<bool>, // True if frozen (0)
+    pub info_positions: Vec<usize>,
+}
+
+/// Per-depth decoder state shared by all paths
+struct ListState<B: Backend> {
+    /// LLRs entering each depth: [L, n >> d]
+    alpha: Vec<Tensor<B, 2>>,
+    /// Partial sums leaving each depth: [L, n >> d]
+    beta: Vec<Tensor<B, 2>>,
+    /// Left-child partial sums kept while the right child decodes: [L, n >> (d + 1)]
+    beta_left: Vec<Tensor<B, 2>>,
+    /// Decoded u vector: [L, n]
+    u_hat: Tensor<B, 2>,
+    /// Path metrics: [L]
+    metrics: Tensor<B, 1>,
+}
+
+impl<B: Backend> ListState<B> {
+    /// Keep only the surviving paths (gather every state tensor by parent)
+    fn select_paths(&mut self, parents: Tensor<B, 1, Int>) {
+        for t in self.alpha.iter_mut()
+            .chain(self.beta.iter_mut())
+            .chain(self.beta_left.iter_mut())
+        {
+            *t = t.clone().select(0, parents.clone());
+        }
+        self.u_hat = self.u_hat.clone().select(0, parents);
+    }
+}
+
+impl PolarCodeSCL {
+    pub fn new(n: usize, k: usize) -> Self {
+        // Reuse existing PolarCode logic for construction
+        let pc = PolarCode::new(n, k);
+        let mut frozen_mask = vec![false; n];
+        for &idx in &pc.frozen_positions {
+            frozen_mask[idx] = true;
+        }
+
+        Self { n, k, frozen_mask, info_positions: pc.info_positions }
+    }
+
+    /// Decode with a list of `list_size` paths on GPU
+    ///
+    /// llrs: [N] channel LLRs (positive -> bit 0)
+    /// Returns: info bits of every surviving path, best metric first
+    ///
+    /// ⚠️ **SYNC POINT**: Downloads the final [L, N] decisions once
+    pub fn decode_scl_gpu<B: Backend>(
+        &self,
+        device: &B::Device,
+        llrs: &Tensor<B, 1>,
+        list_size: usize,
+    ) -> Vec<Vec<u8>> {
+        let n = self.n;
+        let l = list_size.max(1);
+        let depths = (n as f64).log2() as usize;
+
+        // All paths start from the channel LLRs; only path 0 is active
+        let mut metrics_init = vec![INACTIVE_METRIC; l];
+        metrics_init[0] = 0.0;
+
+        let mut state = ListState {
+            alpha: (0..=depths).map(|d| Tensor::zeros([l, n >> d], device)).collect(),
+            beta: (0..=depths).map(|d| Tensor::zeros([l, n >> d], device)).collect(),
+            beta_left: (0..depths).map(|d| Tensor::zeros([l, n >> (d + 1)], device)).collect(),
+            u_hat: Tensor::zeros([l, n], device),
+            metrics: Tensor::from_floats(metrics_init.as_slice(), device),
+        };
+        state.alpha[0] = llrs.clone().reshape([1, n]).repeat_dim(0, l);
+
+        self.decode_node(device, &mut state, 0, 0, l);
+
+        // Order paths by metric and download once
+        let (_, order) = state.metrics.clone().sort_with_indices(0);
+        let u_hat: Vec<f32> = state.u_hat.select(0, order).into_data().to_vec().unwrap();
+
+        u_hat.chunks(n)
+            .map(|u| self.info_positions.iter().map(|&pos| (u[pos] > 0.5) as u8).collect())
+            .collect()
+    }
+
+    /// CRC-aided list decoding
+    ///
+    /// Info bits are data bits followed by 8 CRC-8 bits (see `verify_crc`).
+    /// Returns the best path whose CRC checks, or the best-metric path if none does.
+    pub fn decode_scl_gpu_crc<B: Backend>(
+        &self,
+        device: &B::Device,
+        llrs: &Tensor<B, 1>,
+        list_size: usize,
+    ) -> Vec<u8> {
+        let candidates = self.decode_scl_gpu(device, llrs, list_size);
+
+        candidates.iter()
+            .find(|bits| verify_crc(bits))
+            .unwrap_or(&candidates[0])
+            .clone()
+    }
+
+    /// Decode the subtree at `depth` whose first leaf is `offset`
+    fn decode_node<B: Backend>(
+        &self,
+        device: &B::Device,
+        state: &mut ListState<B>,
+        depth: usize,
+        offset: usize,
+        l: usize,
+    ) {
+        let len = self.n >> depth;
+
+        if len == 1 {
+            self.decode_leaf(device, state, depth, offset, l);
+            return;
+        }
+
+        let half = len / 2;
+
+        // Left child: f(alpha_u, alpha_l)
+        let alpha = state.alpha[depth].clone();
+        let alpha_u = alpha.clone().slice([0..l, 0..half]);
+        let alpha_l = alpha.slice([0..l, half..len]);
+        state.alpha[depth + 1] = min_sum(alpha_u, alpha_l);
+        self.decode_node(device, state, depth + 1, offset, l);
+        state.beta_left[depth] = state.beta[depth + 1].clone();
+
+        // Right child: g(alpha_u, alpha_l, beta_left)
+        // Re-read alpha: the left subtree may have reordered the paths
+        let alpha = state.alpha[depth].clone();
+        let alpha_u = alpha.clone().slice([0..l, 0..half]);
+        let alpha_l = alpha.slice([0..l, half..len]);
+        let sign = state.beta_left[depth].clone().mul_scalar(-2.0).add_scalar(1.0);
+        state.alpha[depth + 1] = alpha_l + sign * alpha_u;
+        self.decode_node(device, state, depth + 1, offset + half, l);
+
+        // Combine partial sums: [beta_left ⊕ beta_right, beta_right]
+        let left = state.beta_left[depth].clone();
+        let right = state.beta[depth + 1].clone();
+        let xor = left.clone() + right.clone() - left * right.clone() * 2.0;
+        state.beta[depth] = Tensor::cat(vec![xor, right], 1);
+    }
+
+    /// Decide bit `idx` for every path, forking on information bits
+    fn decode_leaf<B: Backend>(
+        &self,
+        device: &B::Device,
+        state: &mut ListState<B>,
+        depth: usize,
+        idx: usize,
+        l: usize,
+    ) {
+        let llr = state.alpha[depth].clone().reshape([l]);
+
+        if self.frozen_mask[idx] {
+            // Frozen bit is 0: penalize paths whose LLR says 1
+            state.metrics = state.metrics.clone() + relu(llr.neg());
+            state.beta[depth] = Tensor::zeros([l, 1], device);
+            return;
+        }
+
+        // Candidates [bit 0 for all paths, bit 1 for all paths]: [2L]
+        let metric_0 = state.metrics.clone() + relu(llr.clone().neg());
+        let metric_1 = state.metrics.clone() + relu(llr);
+        let candidates = Tensor::cat(vec![metric_0, metric_1], 0);
+
+        // Keep the L best (lowest metric)
+        let (sorted, order) = candidates.sort_with_indices(0);
+        let survivors = order.slice([0..l]);
+        let parents = survivors.clone().remainder_scalar(l as i32);
+        let bits = survivors.greater_equal_elem(l as i32).float().reshape([l, 1]);
+
+        state.select_paths(parents);
+        state.metrics = sorted.slice([0..l]);
+        state.u_hat = state.u_hat.clone().slice_assign([0..l, idx..idx + 1], bits.clone());
+        state.beta[depth] = bits;
+    }
+}
+
+/// Min-Sum approximation: f(a, b) ≈ sign(a)sign(b) min(|a|, |b|)
+fn min_sum<B: Backend>(a: Tensor<B, 2>, b: Tensor<B, 2>) -> Tensor<B, 2> {
+    let sign = a.clone().sign() * b.clone().sign();
+    let abs_a = a.abs();
+    let abs_b = b.abs();
+
+    // min(x, y) = 0.5 * (x + y - |x - y|)
+    let min_abs = (abs_a.clone() + abs_b.clone() - (abs_a - abs_b).abs()) * 0.5;
+
+    sign * min_abs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polar::crc8;
+    use crate::modulation::encode_bits;
+    use burn::backend::Wgpu;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_scl_gpu_noisy_roundtrip() {
+        let device = Default::default();
+        let polar = PolarCode::new(64, 32);
+        let scl = PolarCodeSCL::new(64, 32);
+
+        // 3 data bytes + CRC-8 = 32 info bits
+        let mut info_bits = encode_bits(b"BWV");
+        info_bits.extend(encode_bits(&[crc8(b"BWV")]));
+        let codeword = polar.encode(&info_bits);
+
+        // BPSK LLRs with a few weak/flipped positions
+        let mut llrs: Vec<f32> = codeword.iter().map(|&b| if b == 0 { 2.0 } else { -2.0 }).collect();
+        for i in [3, 17, 40] {
+            llrs[i] = -0.3 * llrs[i];
+        }
+
+        let llr_tensor = Tensor::<TestBackend, 1>::from_floats(llrs.as_slice(), &device);
+        let decoded = scl.decode_scl_gpu_crc::<TestBackend>(&device, &llr_tensor, 8);
+
+        assert_eq!(decoded, info_bits);
+    }
+}
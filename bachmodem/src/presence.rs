@@ -0,0 +1,323 @@
+/// Signal Presence Timeline
+///
+/// A multi-hour recording is too long to decode blind. `presence_timeline`
+/// scans it once for the two known waveforms of a transmission, the
+/// preamble and the flourish, and keeps per time bin (default 1 s) the best
+/// normalized correlation ρ = |⟨x, t⟩| / (‖x‖·‖t‖) of any window starting in
+/// the bin. ρ does not depend on the receive level: noise alone stays near
+/// a few / √(template samples), a preamble lifts its bin towards 1.
+///
+/// The recording is processed in chunks (default 60 s) so the FFT size stays
+/// bounded; each chunk reads one template length past its end, so windows
+/// straddling a chunk boundary are not lost. Only one value per bin and
+/// template is downloaded.
+///
+/// The timeline exports as CSV (`write_csv`) and, with the `png` feature,
+/// as a heatmap (`write_png`): one row per minute, one cell per bin.
+/// `decode_segments` turns its active bins into sample ranges worth
+/// decoding; with the `mmap` feature `presence_timeline_mapped` scans a
+/// memory-mapped recording (see `wav_mmap`) one chunk at a time.
+
+use std::io::Write;
+use std::ops::Range;
+use burn::tensor::{Tensor, ElementConversion, backend::Backend};
+use crate::config::ModemConfig;
+use crate::fft_correlation::{fft_cross_correlation, FftBackend};
+use crate::wavelet::{generate_bach_preamble_with_config, generate_bach_flourish_with_config, FS};
+
+/// Segments quieter than this fraction of the chunk's mean energy are
+/// treated as that quiet, so digital silence can't divide its rounding
+/// noise up to ρ = 1
+const SILENCE_FLOOR: f32 = 1e-4;
+
+/// Heatmap cells per PNG row
+#[cfg(feature = "png")]
+pub const PNG_ROW_BINS: usize = 60;
+
+/// Heatmap cell size (pixels)
+#[cfg(feature = "png")]
+pub const PNG_CELL_PIXELS: u32 = 8;
+
+/// Timeline resolution and scan chunking
+#[derive(Clone, Debug, PartialEq)]
+pub struct PresenceConfig {
+    /// Time bin (seconds)
+    pub bin_seconds: f64,
+
+    /// Samples correlated per FFT (seconds, rounded up to whole bins)
+    pub chunk_seconds: f64,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self { bin_seconds: 1.0, chunk_seconds: 60.0 }
+    }
+}
+
+/// Detection metrics of one time bin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PresenceBin {
+    /// Bin start (seconds into the recording)
+    pub time_s: f64,
+
+    /// Best normalized preamble correlation of a window starting in the bin
+    pub preamble_rho: f32,
+
+    /// Best normalized flourish correlation of a window starting in the bin
+    pub flourish_rho: f32,
+}
+
+impl PresenceBin {
+    /// The stronger of the two metrics
+    pub fn rho(&self) -> f32 {
+        self.preamble_rho.max(self.flourish_rho)
+    }
+}
+
+/// Detection metric per time bin of a whole recording
+#[derive(Clone, Debug)]
+pub struct PresenceTimeline {
+    pub bins: Vec<PresenceBin>,
+
+    /// Bin length (seconds)
+    pub bin_seconds: f64,
+}
+
+impl PresenceTimeline {
+    /// Runs of consecutive bins whose stronger metric reaches `min_rho`
+    /// (bin indices)
+    pub fn active_spans(&self, min_rho: f32) -> Vec<Range<usize>> {
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for (index, bin) in self.bins.iter().enumerate() {
+            if bin.rho() < min_rho {
+                continue;
+            }
+            match spans.last_mut() {
+                Some(span) if span.end == index => span.end = index + 1,
+                _ => spans.push(index..index + 1),
+            }
+        }
+        spans
+    }
+
+    /// Sample ranges around the active spans (`active_spans(min_rho)`) that
+    /// hold whole frames, merged where they overlap
+    ///
+    /// A flourish bin can lie a frame after its preamble, so each span is
+    /// widened by one frame airtime before it and two after (room for the
+    /// flourishes). Ranges may run past the end of the recording.
+    pub fn decode_segments(&self, min_rho: f32, config: &ModemConfig) -> Vec<Range<usize>> {
+        let bin_len = (self.bin_seconds * FS).round() as usize;
+        let frame_len = (config.frame_duration() * FS).ceil() as usize;
+        let mut segments: Vec<Range<usize>> = Vec::new();
+        for span in self.active_spans(min_rho) {
+            let start = (span.start * bin_len).saturating_sub(frame_len);
+            let end = span.end * bin_len + 2 * frame_len;
+            match segments.last_mut() {
+                Some(last) if last.end >= start => last.end = last.end.max(end),
+                _ => segments.push(start..end),
+            }
+        }
+        segments
+    }
+
+    /// Write the timeline as CSV: time_s, preamble_rho, flourish_rho
+    pub fn write_csv(&self, path: &str) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(file, "time_s,preamble_rho,flourish_rho")?;
+        for bin in &self.bins {
+            writeln!(file, "{:.3},{:.4},{:.4}", bin.time_s, bin.preamble_rho, bin.flourish_rho)?;
+        }
+        file.flush()
+    }
+
+    /// Write the timeline as a heatmap PNG
+    ///
+    /// `PNG_ROW_BINS` bins per row (a minute at 1 s bins), top to bottom;
+    /// each cell shows the stronger metric from black (0) through red to
+    /// yellow (`full_scale` and above).
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, path: &str, full_scale: f32) -> image::ImageResult<()> {
+        let rows = self.bins.len().div_ceil(PNG_ROW_BINS).max(1);
+        let mut img = image::RgbImage::new(PNG_ROW_BINS as u32 * PNG_CELL_PIXELS, rows as u32 * PNG_CELL_PIXELS);
+
+        for (index, bin) in self.bins.iter().enumerate() {
+            let level = (bin.rho() / full_scale).clamp(0.0, 1.0);
+            // Black -> red over the first half, red -> yellow over the second
+            let red = (2.0 * level).min(1.0);
+            let green = (2.0 * level - 1.0).max(0.0);
+            let color = image::Rgb([(red * 255.0) as u8, (green * 255.0) as u8, 0]);
+
+            let x0 = (index % PNG_ROW_BINS) as u32 * PNG_CELL_PIXELS;
+            let y0 = (index / PNG_ROW_BINS) as u32 * PNG_CELL_PIXELS;
+            for y in y0..y0 + PNG_CELL_PIXELS {
+                for x in x0..x0 + PNG_CELL_PIXELS {
+                    img.put_pixel(x, y, color);
+                }
+            }
+        }
+        img.save(path)
+    }
+}
+
+/// Scan a whole recording for preambles and flourishes
+///
+/// ⚠️ **SYNC POINT**: Downloads two values per bin, once per chunk
+pub fn presence_timeline<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    presence: &PresenceConfig,
+) -> PresenceTimeline {
+    scan_presence(device, signal.dims()[0], |range| signal.clone().slice([range]), config, presence)
+}
+
+/// `presence_timeline` of a memory-mapped recording; only one chunk is
+/// uploaded at a time
+///
+/// ⚠️ **SYNC POINT**: Downloads two values per bin, once per chunk
+#[cfg(feature = "mmap")]
+pub fn presence_timeline_mapped<B: Backend + FftBackend>(
+    device: &B::Device,
+    wav: &crate::wav_mmap::MappedWav,
+    config: &ModemConfig,
+    presence: &PresenceConfig,
+) -> PresenceTimeline {
+    scan_presence(device, wav.len(), |range| wav.window::<B>(device, range), config, presence)
+}
+
+/// Chunked scan over `signal_len` samples, each chunk fetched by `read`
+fn scan_presence<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal_len: usize,
+    mut read: impl FnMut(Range<usize>) -> Tensor<B, 1>,
+    config: &ModemConfig,
+    presence: &PresenceConfig,
+) -> PresenceTimeline {
+    let bin_len = ((presence.bin_seconds * FS).round() as usize).max(1);
+    let bins_per_chunk = (presence.chunk_seconds / presence.bin_seconds).ceil().max(1.0) as usize;
+    let chunk_len = bins_per_chunk * bin_len;
+    let num_bins = signal_len.div_ceil(bin_len);
+
+    let templates = [
+        generate_bach_preamble_with_config::<B>(device, config),
+        generate_bach_flourish_with_config::<B>(device, config),
+    ];
+    let longest = templates.iter().map(|t| t.dims()[0]).max().unwrap_or(0);
+
+    let mut metrics = [Vec::with_capacity(num_bins), Vec::with_capacity(num_bins)];
+    let mut start = 0;
+    while start < signal_len {
+        // Chunk lags [start, start + chunk_len), zero-padded past the end
+        let bins = bins_per_chunk.min(num_bins - start / bin_len);
+        let needed = bins * bin_len + longest;
+        let end = (start + needed).min(signal_len);
+        let mut chunk = read(start..end);
+        if end - start < needed {
+            chunk = Tensor::cat(vec![chunk, Tensor::zeros([needed - (end - start)], device)], 0);
+        }
+
+        for (template, metric) in templates.iter().zip(metrics.iter_mut()) {
+            metric.extend(bin_max_rho(device, &chunk, template, bins, bin_len));
+        }
+        start += chunk_len;
+    }
+
+    let [preamble, flourish] = metrics;
+    let bins = preamble.into_iter()
+        .zip(flourish)
+        .enumerate()
+        .map(|(index, (preamble_rho, flourish_rho))| PresenceBin {
+            time_s: (index * bin_len) as f64 / FS,
+            preamble_rho,
+            flourish_rho,
+        })
+        .collect();
+    PresenceTimeline { bins, bin_seconds: bin_len as f64 / FS }
+}
+
+/// Best ρ of `template` for the windows starting in each of the first
+/// `bins` bins of `chunk`
+///
+/// ⚠️ **SYNC POINT**: Downloads `bins` values
+fn bin_max_rho<B: Backend + FftBackend>(
+    device: &B::Device,
+    chunk: &Tensor<B, 1>,
+    template: &Tensor<B, 1>,
+    bins: usize,
+    bin_len: usize,
+) -> Vec<f32> {
+    let template_len = template.dims()[0];
+    let lags = bins * bin_len;
+
+    // Non-coherent: carrier phase unknown after the SSB chain
+    let corr = fft_cross_correlation(device, chunk, template).slice([0..lags]).abs();
+
+    // Window energies: sliding sum of x², also by FFT
+    let squared = chunk.clone().powf_scalar(2.0);
+    let floor = squared.clone().mean().into_scalar().elem::<f32>() * template_len as f32 * SILENCE_FLOOR + 1e-20;
+    let ones = Tensor::<B, 1>::ones([template_len], device);
+    let energy = fft_cross_correlation(device, &squared, &ones).slice([0..lags]).clamp_min(floor);
+
+    let template_energy = template.clone().powf_scalar(2.0).sum().into_scalar().elem::<f32>();
+    let rho = (corr / energy.mul_scalar(template_energy).sqrt()).clamp_max(1.0);
+    rho.reshape([bins, bin_len]).max_dim(1).reshape([bins]).into_data().to_vec().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_timeline_locates_transmission() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_flourish_interval(16);
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, b"PRESENCE", true, &config);
+        let gap = 5 * FS as usize;
+
+        // 5 s of noise, the transmission, 5 s of noise
+        let clean = Tensor::cat(vec![Tensor::zeros([gap], &device), tx, Tensor::zeros([gap], &device)], 0);
+        let len = clean.dims()[0];
+        let signal = clean + gaussian_noise::<TestBackend, _>(&device, len, 0.05, &mut SplitMix64::new(23));
+
+        // Short chunks: the preamble straddles a chunk boundary
+        let presence = PresenceConfig { bin_seconds: 1.0, chunk_seconds: 3.0 };
+        let timeline = presence_timeline::<TestBackend>(&device, &signal, &config, &presence);
+        assert_eq!(timeline.bins.len(), len.div_ceil(FS as usize));
+
+        let best = (0..timeline.bins.len())
+            .max_by(|&a, &b| timeline.bins[a].preamble_rho.total_cmp(&timeline.bins[b].preamble_rho))
+            .unwrap();
+        assert_eq!(best, 5);
+        let noise_rho = timeline.bins[..4].iter().map(|b| b.rho()).fold(0.0, f32::max);
+        assert!(timeline.bins[5].preamble_rho > 5.0 * noise_rho, "{:?}", &timeline.bins[..6]);
+
+        // Flourishes keep the data part visible after the preamble bin
+        assert!(timeline.bins[6..len / FS as usize - 5].iter().any(|b| b.flourish_rho > 3.0 * noise_rho));
+
+        let path = std::env::temp_dir().join("bachmodem_presence_test.csv");
+        let path = path.to_str().unwrap();
+        timeline.write_csv(path).unwrap();
+        let csv = std::fs::read_to_string(path).unwrap();
+        assert_eq!(csv.lines().next(), Some("time_s,preamble_rho,flourish_rho"));
+        assert_eq!(csv.lines().count(), timeline.bins.len() + 1);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_active_spans_merge_consecutive_bins() {
+        let rhos = [0.01, 0.4, 0.02, 0.3, 0.5, 0.01, 0.2];
+        let timeline = PresenceTimeline {
+            bins: rhos.iter()
+                .enumerate()
+                .map(|(i, &rho)| PresenceBin { time_s: i as f64, preamble_rho: rho, flourish_rho: if i == 6 { 0.6 } else { 0.0 } })
+                .collect(),
+            bin_seconds: 1.0,
+        };
+        assert_eq!(timeline.active_spans(0.25), vec![1..2, 3..5, 6..7]);
+        assert!(timeline.active_spans(0.7).is_empty());
+    }
+}
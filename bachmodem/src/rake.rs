@@ -5,8 +5,8 @@
 /// 
 /// Named after a garden rake - each "finger" collects energy from one path
 
-use burn::tensor::{Tensor, backend::Backend, ElementConversion};
-use crate::gpu_ops::cross_correlation_gpu;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::gpu_ops::{cross_correlation_gpu, topk_separated_gpu};
 
 /// RAKE finger - tracks one multipath component
 #[derive(Clone, Debug)]
@@ -47,9 +47,7 @@ impl RakeReceiver {
     }
     
     /// Detect multipath components using correlation
-    /// ⚠️ Contains SYNC POINTS in peak-finding loop
-    /// 
-    /// TODO: Replace with GPU-native topk operation when available
+    /// ⚠️ **SYNC POINT**: Downloads the `num_fingers` strongest peaks
     pub fn detect_paths<B: Backend>(
         &mut self,
         device: &<B as Backend>::Device,
@@ -74,40 +72,22 @@ impl RakeReceiver {
         // Compute all correlations in one go on GPU
         let correlations_tensor = cross_correlation_gpu(device, &search_signal, reference);
         
-        // Find top peaks on GPU using iterative argmax
+        // Strongest peaks, at least 5 samples apart, in one download
+        let (top_values, top_delays) = topk_separated_gpu(&correlations_tensor, self.num_fingers, 5);
+        let top_values: Vec<f32> = top_values.into_data().to_vec().unwrap();
+        let top_delays: Vec<i64> = top_delays.into_data().convert::<i64>().to_vec().unwrap();
+
         self.fingers.clear();
-        let mut remaining_corr = correlations_tensor.clone();
-        
-        for _ in 0..self.num_fingers {
-            // ⚠️ SYNC POINT: Extract peak value
-            // TODO: Use GPU topk/nlargest when Burn adds it
-            let max_val: f32 = remaining_corr.clone().max().into_scalar().elem();
-            
+        for (max_val, delay) in top_values.into_iter().zip(top_delays) {
             if max_val < 0.1 {
                 break; // No more significant peaks
             }
-            
-            // ⚠️ SYNC POINT: Extract peak index
-            let argmax_val: i64 = remaining_corr.clone().argmax(0).into_scalar().elem();
-            let delay = argmax_val as usize;
-            
-            let finger = RakeFinger {
-                delay,
+            self.fingers.push(RakeFinger {
+                delay: delay as usize,
                 amplitude: max_val,
                 phase: 0.0, // Simplified: assume zero phase
                 weight: max_val.abs(), // MRC weighting
-            };
-            
-            self.fingers.push(finger);
-            
-            // Zero out region around this peak to find next peak
-            let suppress_start = delay.saturating_sub(5);
-            let suppress_end = (delay + 5).min(remaining_corr.dims()[0]);
-            
-            if suppress_end > suppress_start {
-                let zeros = Tensor::zeros([suppress_end - suppress_start], device);
-                remaining_corr = remaining_corr.slice_assign([suppress_start..suppress_end], zeros);
-            }
+            });
         }
         
         println!("  [RAKE] Detected {} paths:", self.fingers.len());
@@ -0,0 +1,816 @@
+/// Receiver Pool
+///
+/// Decodes independent captures concurrently: a skimmer watching several
+/// slots or RF channels, or a batch re-run over an archive of recordings.
+///
+/// Each worker thread owns one `ReceiverState` and the device it runs on, so
+/// workers never share adaptive estimates or GPU streams. Workers on one
+/// device overlap host-side work (WAV reading, frame parsing, list decoding
+/// bookkeeping) with each other's kernels; a list of devices spreads them
+/// across GPUs.
+///
+/// ```text
+/// submit(source) -> id ──► job channel ──► worker k: read, sync, demod, decode
+///                                                      │
+/// results() ◄──────────── result channel ◄─────────────┘  (completion order)
+/// ```
+///
+/// Jobs are taken by whichever worker is idle, so results arrive in
+/// completion order and carry the id `submit` returned. `shutdown()` lets the
+/// queued jobs finish and hands back the worker states (e.g. to `save_file`
+/// them).
+
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use burn::tensor::{Tensor, backend::Backend, ElementConversion};
+use crate::config::{ConfigError, ModemConfig};
+use crate::decision_trace::DecisionTrace;
+use crate::deinterleave_gpu::{deinterleave_auto, Llrs};
+use crate::fft_correlation::FftBackend;
+use crate::front_end::{FrontEnd, FrontEndReport};
+use crate::noise_floor::NoiseFloorTracker;
+use crate::modulation::{demodulate_fhdpsk_stats_with_config, synchronize_data_start_with_thresholds, pack_bits};
+use crate::partial_band::detect_missing_tones;
+use crate::polar::PolarCode;
+use crate::polar_bp::PolarCodeBP;
+use crate::polar_scl_gpu::PolarCodeSCL;
+use crate::rake::{RakeFinger, RakeReceiver};
+use crate::receiver_state::ReceiverState;
+use crate::transmitter::{CODE_K, CODE_N};
+use crate::tuning::{ReceiverTuning, RAKE_MAX_DELAY};
+use crate::wavelet::generate_bach_preamble_with_config;
+
+use bachmodem_core::frame::{parse_frame, FrameCode, FrameError};
+
+/// Receive chain settings shared by all workers
+#[derive(Clone, Debug)]
+pub struct ReceiverPoolConfig {
+    /// Physical layer configuration of the monitored link
+    pub modem: ModemConfig,
+
+    /// Captures start with a preamble (false = sample-aligned slots)
+    pub use_sync: bool,
+
+    /// Polar list size
+    pub list_size: usize,
+
+    /// Sync thresholds, LLR scaling, decoder and RAKE settings
+    pub tuning: ReceiverTuning,
+
+    /// Station notches applied to every capture first
+    pub front_end: FrontEnd,
+}
+
+impl Default for ReceiverPoolConfig {
+    fn default() -> Self {
+        Self {
+            modem: ModemConfig::default(),
+            use_sync: true,
+            list_size: 8,
+            tuning: ReceiverTuning::default(),
+            front_end: FrontEnd::default(),
+        }
+    }
+}
+
+impl ReceiverPoolConfig {
+    /// Tuning and front end from a receiver profile (`autotune`, `noise_calibration`)
+    pub fn load_profile<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        self.tuning = ReceiverTuning::parse(&text)?;
+        self.front_end = FrontEnd::parse(&text)?;
+        Ok(())
+    }
+}
+
+/// Capture to decode
+#[derive(Clone, Debug)]
+pub enum DecodeSource {
+    /// Samples at `FS`
+    Samples(Vec<f32>),
+
+    /// WAV recording, read by the worker
+    #[cfg(feature = "wav")]
+    WavFile(std::path::PathBuf),
+
+    /// Sample range of a memory-mapped recording, read by the worker (see
+    /// `PresenceTimeline::decode_segments`)
+    #[cfg(feature = "mmap")]
+    MappedSegment { wav: Arc<crate::wav_mmap::MappedWav>, range: std::ops::Range<usize> },
+}
+
+/// Why a capture did not decode
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeError {
+    /// The capture could not be read
+    Read { source: String, reason: String },
+
+    /// No preamble found
+    NoSync,
+
+    /// The decoded header is not a frame this receiver understands
+    Frame(FrameError),
+
+    /// The receive chain panicked on this capture (message of the panic)
+    Panicked(String),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Read { source, reason } => write!(f, "failed to read {}: {}", source, reason),
+            DecodeError::NoSync => write!(f, "no preamble found"),
+            DecodeError::Frame(err) => write!(f, "frame rejected: {}", err),
+            DecodeError::Panicked(message) => write!(f, "receiver panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decoded frame of one capture
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedFrame {
+    /// `MAX_PAYLOAD` payload bytes (payload plus zero padding)
+    pub payload: Vec<u8>,
+
+    /// SNR of the transmission against the noise floor (dB in the 2500 Hz
+    /// reference bandwidth, see `noise_floor`); the blind matched-filter
+    /// estimate until the floor is known
+    pub snr_db: f32,
+}
+
+/// Outcome of one job
+#[derive(Clone, Debug)]
+pub struct DecodeResult {
+    /// Id returned by `submit`
+    pub id: u64,
+
+    /// Worker that ran the job
+    pub worker: usize,
+
+    pub frame: Result<DecodedFrame, DecodeError>,
+
+    /// Read + decode time on the worker
+    pub elapsed: Duration,
+}
+
+struct DecodeJob {
+    id: u64,
+    source: DecodeSource,
+}
+
+/// Worker threads decoding captures from a shared job queue
+pub struct ReceiverPool<B: Backend> {
+    jobs: Option<mpsc::Sender<DecodeJob>>,
+    results: mpsc::Receiver<DecodeResult>,
+    /// Finished jobs `decode_all` received outside its batch, for `results`
+    pending: VecDeque<DecodeResult>,
+    workers: Vec<JoinHandle<ReceiverState<B>>>,
+    next_id: u64,
+}
+
+impl<B: Backend + FftBackend> ReceiverPool<B> {
+    /// `workers` cold receivers on one device
+    ///
+    /// `ConfigError` if the modem configuration can't be received.
+    pub fn new(config: ReceiverPoolConfig, device: &B::Device, workers: usize) -> Result<Self, ConfigError> {
+        let receivers = (0..workers.max(1))
+            .map(|_| (device.clone(), ReceiverState::default()))
+            .collect();
+        Self::spawn(config, receivers)
+    }
+
+    /// One worker per (device, state) pair, e.g. states restored with
+    /// `ReceiverState::load_file` or devices of several GPUs
+    ///
+    /// `ConfigError` if the modem configuration can't be received; no
+    /// worker is started then.
+    pub fn spawn(config: ReceiverPoolConfig, receivers: Vec<(B::Device, ReceiverState<B>)>) -> Result<Self, ConfigError> {
+        assert!(!receivers.is_empty(), "a pool needs at least one receiver");
+        config.modem.validate()?;
+
+        let (job_tx, job_rx) = mpsc::channel::<DecodeJob>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let workers = receivers.into_iter().enumerate()
+            .map(|(worker, (device, mut state))| {
+                let jobs = Arc::clone(&job_rx);
+                let results = result_tx.clone();
+                let config = config.clone();
+
+                std::thread::spawn(move || {
+                    loop {
+                        // The lock is only held while waiting; the sender closing ends the loop
+                        let Ok(job) = jobs.lock().unwrap().recv() else { break };
+
+                        // A panicking capture still gets a result, or `decode_all` would wait forever
+                        let started = Instant::now();
+                        let frame = panic::catch_unwind(AssertUnwindSafe(|| decode_source(&device, &mut state, &config, job.source)))
+                            .unwrap_or_else(|payload| Err(DecodeError::Panicked(panic_message(payload.as_ref()))));
+                        let result = DecodeResult { id: job.id, worker, frame, elapsed: started.elapsed() };
+                        if results.send(result).is_err() {
+                            break;
+                        }
+                    }
+                    state
+                })
+            })
+            .collect();
+
+        Ok(Self { jobs: Some(job_tx), results: result_rx, pending: VecDeque::new(), workers, next_id: 0 })
+    }
+
+    /// Number of worker threads
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queue a capture; returns the id its result will carry
+    pub fn submit(&mut self, source: DecodeSource) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // The receiving end lives as long as any worker
+        self.jobs.as_ref()
+            .expect("pool is running")
+            .send(DecodeJob { id, source })
+            .expect("receiver pool workers have exited");
+        id
+    }
+
+    /// Results in completion order, blocking for the next one
+    ///
+    /// Results of `submit` jobs that finished during a `decode_all` come
+    /// first. Ends once every worker has exited.
+    pub fn results(&mut self) -> impl Iterator<Item = DecodeResult> + '_ {
+        std::iter::from_fn(move || self.pending.pop_front().or_else(|| self.results.recv().ok()))
+    }
+
+    /// Decode a batch and wait for all of it, results in submission order
+    ///
+    /// Earlier `submit` jobs that finish meanwhile stay queued for `results`.
+    pub fn decode_all(&mut self, sources: Vec<DecodeSource>) -> Vec<DecodeResult> {
+        let ids: Vec<u64> = sources.into_iter().map(|source| self.submit(source)).collect();
+
+        let mut results: Vec<DecodeResult> = Vec::with_capacity(ids.len());
+        while results.len() < ids.len() {
+            match self.results.recv() {
+                Ok(result) if ids.contains(&result.id) => results.push(result),
+                Ok(result) => self.pending.push_back(result),
+                Err(_) => break,
+            }
+        }
+        results.sort_by_key(|r| r.id);
+        results
+    }
+
+    /// Finish the queued jobs and return each worker's state
+    ///
+    /// Workers that panicked are left out.
+    pub fn shutdown(mut self) -> Vec<ReceiverState<B>> {
+        self.jobs = None;
+        self.workers.drain(..).filter_map(|worker| worker.join().ok()).collect()
+    }
+}
+
+impl<B: Backend> Drop for ReceiverPool<B> {
+    fn drop(&mut self) {
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Text of a panic payload (`panic!` with a literal or a format string)
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown panic".to_string(),
+    }
+}
+
+/// Read a capture and run it through `decode_capture`
+pub(crate) fn decode_source<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    source: DecodeSource,
+) -> Result<DecodedFrame, DecodeError> {
+    let signal = match source {
+        DecodeSource::Samples(samples) => Tensor::<B, 1>::from_floats(samples.as_slice(), device),
+        #[cfg(feature = "wav")]
+        DecodeSource::WavFile(path) => crate::wav::read_wav::<B>(device, &path)
+            .map_err(|err| DecodeError::Read { source: path.display().to_string(), reason: err.to_string() })?,
+        #[cfg(feature = "mmap")]
+        DecodeSource::MappedSegment { wav, range } => wav.window::<B>(device, range),
+    };
+    decode_capture(device, state, config, &signal)
+}
+
+/// Full receive chain for one capture on a worker's state
+///
+/// `capture_llrs`, then `decode_llrs`.
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR and the decoder decisions
+pub fn decode_capture<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+) -> Result<DecodedFrame, DecodeError> {
+    decode_capture_impl(device, state, config, signal, None, None)
+}
+
+/// `decode_capture`, pinning the data start and RAKE fingers of `replay`
+/// and recording every decision into `trace` (see `decision_trace`)
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR and the decoder decisions (and the
+/// LLR signs when tracing)
+pub(crate) fn decode_capture_impl<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    replay: Option<&DecisionTrace>,
+    mut trace: Option<&mut DecisionTrace>,
+) -> Result<DecodedFrame, DecodeError> {
+    let slot = capture_llrs_impl(device, state, config, signal, replay, trace.as_deref_mut())?;
+    let info_bits = decode_info_bits(device, config, &slot.llrs, trace.as_deref_mut().map(|trace| &mut trace.bp_decisions));
+    let payload = parse_frame(&pack_bits(&info_bits)).map_err(DecodeError::Frame);
+    if let Some(trace) = trace {
+        trace.info_bits = info_bits;
+    }
+    Ok(DecodedFrame { payload: payload?, snr_db: slot.snr_db })
+}
+
+/// Soft output of one capture, before the polar decoder
+#[derive(Clone, Debug)]
+pub struct CaptureLlrs<B: Backend> {
+    /// Interleaved LLRs of one codeword [CODE_N] (positive -> bit 0, 0 = erasure)
+    pub llrs: Tensor<B, 1>,
+
+    /// SNR of the capture, as in `DecodedFrame::snr_db`
+    pub snr_db: f32,
+}
+
+/// Receive chain up to the LLRs
+///
+/// Applies the station front end, updates the noise floor from the capture, syncs with the tuned thresholds
+/// (optionally RAKE-combining from the preamble on), erases the tones the
+/// preamble shows missing in partial-band mode and maps the detector
+/// statistics to LLRs with the state's calibrator (or the analytic formula),
+/// scaled by the tuning. The SNR is the frame's band power against the noise
+/// floor (`frame_snr_db`) once the floor is known. Receivers that combine
+/// with other sites export these (`LlrContribution::quantize`) instead of
+/// decoding.
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR
+pub fn capture_llrs<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+) -> Result<CaptureLlrs<B>, DecodeError> {
+    capture_llrs_impl(device, state, config, signal, None, None)
+}
+
+/// `capture_llrs` with the replay and tracing of `decode_capture_impl`
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR (and the LLR signs when tracing)
+fn capture_llrs_impl<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    replay: Option<&DecisionTrace>,
+    mut trace: Option<&mut DecisionTrace>,
+) -> Result<CaptureLlrs<B>, DecodeError> {
+    let signal = &config.front_end.apply(device, signal);
+    state.noise_floor.update_gpu::<B>(device, signal);
+
+    let data_start = match replay {
+        Some(recorded) => recorded.data_start,
+        None if config.use_sync => {
+            let start = synchronize_data_start_with_thresholds::<B>(device, signal, &config.modem, &config.tuning.sync)
+                .ok_or(DecodeError::NoSync)?;
+            Some(start)
+        }
+        None => None,
+    };
+    if let Some(trace) = trace.as_deref_mut() {
+        trace.data_start = data_start;
+    }
+
+    let pinned = replay.map(DecisionTrace::pinned_fingers);
+    let mut slot = capture_llrs_at_impl(device, state, config, signal, data_start, pinned.as_deref(), trace.as_deref_mut())?;
+    if let Some(snr_db) = frame_snr_db(device, &state.noise_floor, &config.modem, signal, data_start) {
+        slot.snr_db = snr_db;
+    }
+    if let Some(trace) = trace {
+        trace.snr_db = slot.snr_db;
+        let llrs: Vec<f32> = slot.llrs.clone().into_data().to_vec().unwrap();
+        trace.llr_signs = llrs.iter().map(|&l| (l < 0.0) as u8).collect();
+    }
+    Ok(slot)
+}
+
+/// Calibrated SNR of the frame whose data starts at `data_start` (None =
+/// `signal` is the data): mean band power over its data symbols against the
+/// tracker's floor, in dB in `SNR_REFERENCE_BANDWIDTH`
+///
+/// None before the floor is known or without a full window of data.
+///
+/// ⚠️ **SYNC POINT**: Downloads the band power
+pub(crate) fn frame_snr_db<B: Backend + FftBackend>(
+    device: &B::Device,
+    noise_floor: &NoiseFloorTracker,
+    modem: &ModemConfig,
+    signal: &Tensor<B, 1>,
+    data_start: Option<usize>,
+) -> Option<f32> {
+    noise_floor.noise_power()?;
+    let start = data_start.unwrap_or(0);
+    let data_len = modem.reference_layout().num_symbols(CODE_N) * modem.symbol_samples();
+    let end = (start + data_len).min(signal.dims()[0]);
+    if start >= end {
+        return None;
+    }
+    let powers = noise_floor.window_band_powers_tensor(device, &signal.clone().slice([start..end]))?;
+    let band_power: f32 = powers.mean().into_scalar().elem();
+    noise_floor.snr_db(band_power as f64).map(|snr| snr as f32)
+}
+
+/// Blind SNR of one capture demodulated without and with the station front
+/// end, both from the data start found on the filtered capture
+///
+/// The noise floor is left alone. Reports what a notch list or hum comb buys
+/// on a station's captures (see `front_end`).
+///
+/// ⚠️ **SYNC POINT**: Downloads both SNRs
+pub fn front_end_report<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+) -> Result<FrontEndReport, DecodeError> {
+    let filtered = config.front_end.apply(device, signal);
+    let data_start = if config.use_sync {
+        let start = synchronize_data_start_with_thresholds::<B>(device, &filtered, &config.modem, &config.tuning.sync)
+            .ok_or(DecodeError::NoSync)?;
+        Some(start)
+    } else {
+        None
+    };
+
+    let before = capture_llrs_at(device, state, config, signal, data_start)?;
+    let after = capture_llrs_at(device, state, config, &filtered, data_start)?;
+    Ok(FrontEndReport { before_db: before.snr_db, after_db: after.snr_db })
+}
+
+/// `capture_llrs` from a data start found elsewhere (None = `signal` is the
+/// data), without the front end and the noise floor update
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR
+pub(crate) fn capture_llrs_at<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    data_start: Option<usize>,
+) -> Result<CaptureLlrs<B>, DecodeError> {
+    capture_llrs_at_impl(device, state, config, signal, data_start, None, None)
+}
+
+/// `capture_llrs_at` combining the `pinned` RAKE fingers instead of
+/// detecting them, recording the fingers and symbol timing into `trace`
+///
+/// ⚠️ **SYNC POINT**: Downloads the SNR
+fn capture_llrs_at_impl<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    data_start: Option<usize>,
+    pinned: Option<&[RakeFinger]>,
+    trace: Option<&mut DecisionTrace>,
+) -> Result<CaptureLlrs<B>, DecodeError> {
+    let (data, fingers) = match data_start {
+        Some(start) => data_after_sync::<B>(device, signal, start, config, pinned).ok_or(DecodeError::NoSync)?,
+        None => (signal.clone(), Vec::new()),
+    };
+
+    let mut stats = demodulate_fhdpsk_stats_with_config::<B>(
+        device,
+        &data,
+        false,
+        &config.modem,
+        CODE_N,
+    ).ok_or(DecodeError::NoSync)?;
+
+    // Partial band: the preamble shows which tones made it through the filter
+    if config.modem.partial_band {
+        if let Some(missing) = data_start.and_then(|start| detect_missing_tones::<B>(device, signal, start, &config.modem)) {
+            stats.erase_tones(&missing, &config.modem);
+        }
+    }
+
+    if let Some(trace) = trace {
+        trace.rake_fingers = fingers.iter().map(|finger| (finger.delay, finger.weight)).collect();
+        trace.symbol_offsets = stats.symbol_offsets.clone();
+    }
+
+    let llrs = state.llr_mapping().llrs(&stats) * config.tuning.llr_scale;
+    let snr_db: f32 = stats.snr_db.into_scalar().elem();
+    Ok(CaptureLlrs { llrs, snr_db })
+}
+
+/// Deinterleave, decode (list or BP, see `ReceiverTuning`) and parse one
+/// codeword of interleaved LLRs, e.g. the `merge` of several receivers'
+/// contributions
+///
+/// Convolutional frames (`ModemConfig::frame_code`) are Viterbi-decoded on
+/// the host instead.
+///
+/// ⚠️ **SYNC POINT**: Downloads the decoder decisions
+pub fn decode_llrs<B: Backend>(
+    device: &B::Device,
+    config: &ReceiverPoolConfig,
+    llrs: &Tensor<B, 1>,
+) -> Result<Vec<u8>, DecodeError> {
+    let info_bits = decode_info_bits(device, config, llrs, None);
+    parse_frame(&pack_bits(&info_bits)).map_err(DecodeError::Frame)
+}
+
+/// Info bits of `decode_llrs` before parsing; BP pushes its hard decisions
+/// on u after every iteration to `bp_decisions` if given
+///
+/// ⚠️ **SYNC POINT**: Downloads the decoder decisions
+pub(crate) fn decode_info_bits<B: Backend>(
+    device: &B::Device,
+    config: &ReceiverPoolConfig,
+    llrs: &Tensor<B, 1>,
+    bp_decisions: Option<&mut Vec<Vec<u8>>>,
+) -> Vec<u8> {
+    let columns = config.modem.interleaver_columns();
+    if config.modem.frame_code == FrameCode::Convolutional {
+        // Viterbi runs on the host: download once, permute there
+        let host: Vec<f32> = llrs.clone().into_data().to_vec().unwrap();
+        return FrameCode::Convolutional.decode(&deinterleave_auto::<B>(device, Llrs::Host(host), columns).into_vec());
+    }
+
+    let codeword_llrs = deinterleave_auto::<B>(device, Llrs::Device(llrs.clone()), columns).into_tensor(device);
+    if config.tuning.bp_iterations > 0 {
+        let bp = PolarCodeBP::new(CODE_N, CODE_K);
+        let u = match bp_decisions {
+            Some(bp_decisions) => {
+                let (u, decisions) = bp.decode_bp_traced::<B>(device, &codeword_llrs, config.tuning.bp_iterations);
+                let decisions: Vec<f32> = decisions.into_data().to_vec().unwrap();
+                bp_decisions.extend(decisions.chunks(CODE_N).map(|row| row.iter().map(|&d| (d > 0.5) as u8).collect()));
+                u
+            }
+            None => bp.decode_bp::<B>(device, &codeword_llrs, config.tuning.bp_iterations),
+        };
+        let u: Vec<f32> = u.into_data().to_vec().unwrap();
+        PolarCode::new(CODE_N, CODE_K).info_positions.iter().map(|&p| (u[p] < 0.0) as u8).collect()
+    } else {
+        PolarCodeSCL::new(CODE_N, CODE_K)
+            .decode_scl_gpu::<B>(device, &codeword_llrs, config.list_size)
+            .swap_remove(0)
+    }
+}
+
+/// Data samples from `data_start` on, RAKE-combined if the tuning asks for
+/// it, and the fingers combined
+///
+/// The fingers are found by correlating the preamble against the first
+/// `RAKE_MAX_DELAY` samples after its start, unless `pinned`. None if no
+/// data is left.
+fn data_after_sync<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    data_start: usize,
+    config: &ReceiverPoolConfig,
+    pinned: Option<&[RakeFinger]>,
+) -> Option<(Tensor<B, 1>, Vec<RakeFinger>)> {
+    let (signal, data_start, fingers) = if config.tuning.rake_fingers > 0 {
+        let preamble = generate_bach_preamble_with_config::<B>(device, &config.modem);
+        let preamble_start = data_start.saturating_sub(preamble.dims()[0]);
+        let from_preamble = signal.clone().slice([preamble_start..signal.dims()[0]]);
+        let mut rake = RakeReceiver::new(config.tuning.rake_fingers, RAKE_MAX_DELAY);
+        let combined = match pinned {
+            Some(fingers) => {
+                rake.fingers = fingers.to_vec();
+                rake.combine_paths::<B>(device, &from_preamble)
+            }
+            None => rake.process::<B>(device, &from_preamble, &preamble),
+        };
+        (combined, data_start - preamble_start, rake.fingers)
+    } else {
+        (signal.clone(), data_start, Vec::new())
+    };
+
+    let signal_len = signal.dims()[0];
+    (data_start < signal_len).then(|| (signal.slice([data_start..signal_len]), fingers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::transmitter::BachTransmitter;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_pool_decodes_independent_captures() {
+        let device = Default::default();
+        let tx = BachTransmitter::new(ModemConfig::default());
+        let payloads: [&[u8]; 3] = [b"POOL ONE", b"POOL TWO", b"POOL THREE"];
+
+        let sources: Vec<DecodeSource> = payloads.iter()
+            .map(|payload| {
+                let signal: Vec<f32> = tx.build::<TestBackend>(&device, payload).unwrap()
+                    .into_data().to_vec().unwrap();
+                DecodeSource::Samples(signal)
+            })
+            .collect();
+
+        let mut pool = ReceiverPool::<TestBackend>::new(ReceiverPoolConfig::default(), &device, 2).unwrap();
+        assert_eq!(pool.workers(), 2);
+
+        let results = pool.decode_all(sources);
+        assert_eq!(results.len(), 3);
+        for (result, payload) in results.iter().zip(payloads) {
+            let frame = result.frame.as_ref().unwrap();
+            assert_eq!(&frame.payload[..payload.len()], payload);
+        }
+
+        // A silent capture fails cleanly without taking the worker down
+        let id = pool.submit(DecodeSource::Samples(vec![0.0; 8000]));
+        let result = pool.results().next().unwrap();
+        assert_eq!(result.id, id);
+        assert_eq!(result.frame, Err(DecodeError::NoSync));
+
+        // A job submitted before a batch is kept for `results`, not dropped
+        let early = pool.submit(DecodeSource::Samples(vec![0.0; 8000]));
+        let batch = pool.decode_all(vec![DecodeSource::Samples(vec![0.0; 8000])]);
+        assert_eq!(batch.len(), 1);
+        assert_ne!(batch[0].id, early);
+        assert_eq!(pool.results().next().unwrap().id, early);
+
+        // The worker states come back with the noise floor of their captures
+        let states = pool.shutdown();
+        assert_eq!(states.len(), 2);
+        assert!(states.iter().any(|state| state.noise_floor.snapshot().is_some()));
+    }
+
+    #[test]
+    fn test_decoded_snr_is_measured_against_the_noise_floor() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig::default();
+        let capture = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"FLOOR").unwrap();
+
+        // A floor far below the frame: the capture itself lifts it to 5% of the
+        // frame's band power, so the frame reads 10·log10(0.95 / 0.05 · 2600 / 2500)
+        let mut state = ReceiverState::<TestBackend>::default();
+        state.noise_floor.update_from_window_powers(&[1e-12]);
+        let decoded = decode_capture(&device, &mut state, &config, &capture).unwrap();
+        assert_eq!(&decoded.payload[..5], b"FLOOR");
+        assert!((decoded.snr_db - 13.0).abs() < 1.5, "{} dB", decoded.snr_db);
+    }
+
+    #[test]
+    fn test_bp_decodes_a_noisy_capture() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig {
+            tuning: ReceiverTuning { bp_iterations: 30, ..ReceiverTuning::default() },
+            ..ReceiverPoolConfig::default()
+        };
+        let frame = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"BELIEF").unwrap();
+        let frame_len = frame.dims()[0];
+        let mut rng = SplitMix64::new(3);
+        let rx = Tensor::cat(vec![Tensor::zeros([2000], &device), frame, Tensor::zeros([1000], &device)], 0)
+            + gaussian_noise::<TestBackend, _>(&device, frame_len + 3000, 0.1, &mut rng);
+
+        // BP pushes one decision row per iteration
+        let mut state = ReceiverState::<TestBackend>::default();
+        let mut trace = DecisionTrace::default();
+        let decoded = decode_capture_impl(&device, &mut state, &config, &rx, None, Some(&mut trace)).unwrap();
+        assert_eq!(&decoded.payload[..6], b"BELIEF");
+        assert_eq!(trace.bp_decisions.len(), 30);
+    }
+
+    #[test]
+    fn test_rake_combines_a_delayed_path() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig {
+            tuning: ReceiverTuning { rake_fingers: 2, ..ReceiverTuning::default() },
+            ..ReceiverPoolConfig::default()
+        };
+        let frame = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"ECHO ECHO").unwrap();
+        let frame_len = frame.dims()[0];
+
+        // Direct path plus an echo 37 samples (4.6 ms) later at 60%
+        let direct = Tensor::cat(vec![Tensor::zeros([3000], &device), frame.clone(), Tensor::zeros([1000], &device)], 0);
+        let echo = Tensor::cat(vec![Tensor::zeros([3037], &device), frame, Tensor::zeros([963], &device)], 0);
+        let mut rng = SplitMix64::new(5);
+        let rx = direct + echo * 0.6 + gaussian_noise::<TestBackend, _>(&device, frame_len + 4000, 0.05, &mut rng);
+
+        let mut state = ReceiverState::<TestBackend>::default();
+        let mut trace = DecisionTrace::default();
+        let decoded = decode_capture_impl(&device, &mut state, &config, &rx, None, Some(&mut trace)).unwrap();
+        assert_eq!(&decoded.payload[..9], b"ECHO ECHO");
+
+        // The direct path is the strongest finger, at the preamble start
+        assert!(!trace.rake_fingers.is_empty() && trace.rake_fingers.len() <= 2);
+        assert!(trace.rake_fingers[0].0 <= 2, "{:?}", trace.rake_fingers);
+    }
+
+    #[test]
+    fn test_front_end_strips_an_overlaid_cw_id() {
+        use crate::cw_id::{add_cw_id, CwIdConfig, CwIdPlacement};
+        use crate::repetition::TimeSlotConfig;
+
+        let device = Default::default();
+        let config = ReceiverPoolConfig {
+            front_end: FrontEnd { cw_id_hz: Some(crate::cw_id::DEFAULT_CW_ID_TONE_HZ), ..FrontEnd::default() },
+            ..ReceiverPoolConfig::default()
+        };
+        let frame = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"DL1ABC DE").unwrap();
+        let frame_len = frame.dims()[0];
+
+        // ID keyed over the preamble at the data level
+        let slots = TimeSlotConfig {
+            transmission_duration: frame_len as f64 / crate::wavelet::FS,
+            listening_gap: 0.0,
+            num_repetitions: 1,
+            slot_starts: vec![0.0],
+            flourish_interval: 0,
+        };
+        let id = CwIdConfig::new("DL1ABC").with_placement(CwIdPlacement::Overlay).with_level_db(0.0);
+        let rx = add_cw_id::<TestBackend>(&device, frame, &slots, &id).unwrap();
+
+        let mut state = ReceiverState::<TestBackend>::default();
+        let decoded = decode_capture(&device, &mut state, &config, &rx).unwrap();
+        assert_eq!(&decoded.payload[..9], b"DL1ABC DE");
+    }
+
+    #[cfg(all(feature = "mmap", feature = "wav"))]
+    #[test]
+    fn test_pool_decodes_a_mapped_segment() {
+        let device = Default::default();
+        let frame = BachTransmitter::new(ModemConfig::default()).build::<TestBackend>(&device, b"ARCHIVE").unwrap();
+        let frame_len = frame.dims()[0];
+        let start = 5 * crate::wavelet::FS as usize;
+        let recording = Tensor::cat(vec![Tensor::zeros([start], &device), frame, Tensor::zeros([start], &device)], 0);
+        let path = std::env::temp_dir().join("bachmodem_pool_mapped_segment.wav");
+        crate::wav::write_wav(&recording, &path).unwrap();
+
+        // The worker reads only the window around the frame
+        let wav = Arc::new(crate::wav_mmap::MappedWav::open(&path).unwrap());
+        let range = start - 2000..start + frame_len + 2000;
+        let mut pool = ReceiverPool::<TestBackend>::new(ReceiverPoolConfig::default(), &device, 1).unwrap();
+        let results = pool.decode_all(vec![DecodeSource::MappedSegment { wav, range }]);
+        std::fs::remove_file(&path).ok();
+
+        let frame = results[0].frame.as_ref().unwrap();
+        assert_eq!(&frame.payload[..7], b"ARCHIVE");
+    }
+
+    #[test]
+    fn test_unsupported_tone_count_is_rejected_before_spawning() {
+        let device = Default::default();
+
+        // No tone table for 5 tones: the pool refuses the config instead of
+        // starting workers whose receive chain would panic on every capture
+        let mut config = ReceiverPoolConfig::default();
+        config.modem.num_tones = 5;
+        let pool = ReceiverPool::<TestBackend>::new(config, &device, 1);
+        assert_eq!(pool.err(), Some(ConfigError::UnsupportedToneCount { num_tones: 5 }));
+    }
+
+    #[test]
+    fn test_chat_frame_decodes_after_single_sweep_preamble() {
+        let device = Default::default();
+        let modem = ModemConfig::chat();
+        let frame = BachTransmitter::new(modem.clone()).build::<TestBackend>(&device, b"HI FROM K1ABC").unwrap();
+        assert!((frame.dims()[0] as f64 / crate::wavelet::FS - modem.frame_duration()).abs() < 1e-6);
+
+        // One sweep: no sweep-cycle candidates, the frame is found where it starts
+        let rx = Tensor::cat(vec![Tensor::zeros([2345], &device), frame, Tensor::zeros([1000], &device)], 0);
+        assert_eq!(crate::sync_ambiguity::preamble_cycle_samples(&modem), 16 * modem.preamble_note_samples());
+
+        let config = ReceiverPoolConfig { modem, ..ReceiverPoolConfig::default() };
+        let mut state = ReceiverState::<TestBackend>::default();
+        let decoded = decode_capture(&device, &mut state, &config, &rx).unwrap();
+        assert_eq!(&decoded.payload[..13], b"HI FROM K1ABC");
+    }
+}
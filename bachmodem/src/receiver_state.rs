@@ -0,0 +1,160 @@
+/// Receiver Adaptive State Persistence
+///
+/// A monitoring receiver warms up over minutes: the noise floor adapts from
+/// the first block, the DC estimate from zero, the sample-rate fit needs
+/// `rate_settle_time` of capture before it reports, and a trained LLR
+/// calibrator has to be loaded by hand. `ReceiverState` bundles these
+/// adaptive components and saves them with burn's record system, so a
+/// restarted daemon resumes with warmed-up estimates:
+///
+/// ```text
+/// startup:   state = ReceiverState::new(..).load_file(path, device)  (cold on error)
+/// capture:   state.noise_floor.update_gpu(..), state.input_health.push_block(..)
+/// decode:    state.llr_mapping().llrs(&stats)
+/// periodic:  state.save_file(path)
+/// ```
+///
+/// Only adaptive estimates are stored; thresholds and time constants come
+/// from the configs the daemon starts with, so changed settings take effect
+/// on restart. The file is a `CompactRecorder` record (named MessagePack,
+/// `.mpk`, half-precision weights), the format of the trained models
+/// (`--example train_llr_calibrator`).
+
+use std::path::PathBuf;
+use burn::module::Module;
+use burn::record::{CompactRecorder, Record, Recorder, RecorderError};
+use burn::tensor::backend::Backend;
+use crate::input_health::{InputHealthConfig, InputHealthMonitor, InputHealthRecord};
+use crate::llr_calibrator::{LlrCalibrator, LlrCalibratorConfig, LlrCalibratorRecord, LlrMapping};
+use crate::noise_floor::{NoiseFloorConfig, NoiseFloorRecord, NoiseFloorTracker};
+
+/// Saved adaptive state of a `ReceiverState`
+#[derive(Record)]
+pub struct ReceiverStateRecord<B: Backend> {
+    pub noise_floor: NoiseFloorRecord,
+    pub input_health: InputHealthRecord,
+
+    /// Hidden width and weights of the LLR calibrator, if one is in use
+    pub calibrator_hidden: usize,
+    pub calibrator: Option<LlrCalibratorRecord<B>>,
+}
+
+/// Adaptive components of a long-running receiver
+#[derive(Clone, Debug)]
+pub struct ReceiverState<B: Backend> {
+    pub noise_floor: NoiseFloorTracker,
+    pub input_health: InputHealthMonitor,
+
+    /// Trained LLR calibrator; None = analytic LLRs
+    pub calibrator: Option<LlrCalibrator<B>>,
+}
+
+impl<B: Backend> ReceiverState<B> {
+    /// Cold state with the given tracker settings
+    pub fn new(noise_floor: NoiseFloorConfig, input_health: InputHealthConfig) -> Self {
+        Self {
+            noise_floor: NoiseFloorTracker::new(noise_floor),
+            input_health: InputHealthMonitor::new(input_health),
+            calibrator: None,
+        }
+    }
+
+    /// Use a trained LLR calibrator
+    pub fn with_calibrator(mut self, calibrator: LlrCalibrator<B>) -> Self {
+        self.calibrator = Some(calibrator);
+        self
+    }
+
+    /// LLR mapping of the current state
+    pub fn llr_mapping(&self) -> LlrMapping<B> {
+        match &self.calibrator {
+            Some(calibrator) => LlrMapping::Neural(calibrator.clone()),
+            None => LlrMapping::Analytic,
+        }
+    }
+
+    /// Everything the receiver learned on air: the noise floor and input
+    /// health records plus the LLR calibrator weights (and hidden size)
+    pub fn to_record(&self) -> ReceiverStateRecord<B> {
+        ReceiverStateRecord {
+            noise_floor: self.noise_floor.to_record(),
+            input_health: self.input_health.to_record(),
+            calibrator_hidden: self.calibrator.as_ref().map_or(0, |c| c.hidden_size()),
+            calibrator: self.calibrator.clone().map(|c| c.into_record()),
+        }
+    }
+
+    /// Resume from a saved state, keeping the tracker settings
+    pub fn load_record(mut self, record: ReceiverStateRecord<B>, device: &B::Device) -> Self {
+        self.noise_floor.restore(record.noise_floor);
+        self.input_health.restore(record.input_health);
+        self.calibrator = record.calibrator.map(|weights| {
+            LlrCalibratorConfig::new()
+                .with_hidden(record.calibrator_hidden)
+                .init::<B>(device)
+                .load_record(weights)
+        });
+        self
+    }
+
+    /// Save the adaptive state (the recorder adds the file extension)
+    pub fn save_file(&self, path: impl Into<PathBuf>) -> Result<(), RecorderError> {
+        CompactRecorder::new().record(self.to_record(), path.into())
+    }
+
+    /// Resume from a file written by `save_file`
+    pub fn load_file(self, path: impl Into<PathBuf>, device: &B::Device) -> Result<Self, RecorderError> {
+        let record = Recorder::<B>::load(&CompactRecorder::new(), path.into(), device)?;
+        Ok(self.load_record(record, device))
+    }
+}
+
+impl<B: Backend> Default for ReceiverState<B> {
+    fn default() -> Self {
+        Self::new(NoiseFloorConfig::default(), InputHealthConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::backend::Wgpu;
+    use burn::tensor::Tensor;
+    use crate::llr_calibrator::CALIBRATOR_FEATURES;
+
+    type TestBackend = Wgpu;
+
+    #[test]
+    fn test_restart_resumes_warm_estimates() {
+        let device = Default::default();
+        let path = std::env::temp_dir().join("bachmodem_receiver_state_test");
+
+        // Warm up: a noise floor, a DC offset and a settled rate 500 ppm fast
+        let mut state = ReceiverState::<TestBackend>::default();
+        state.noise_floor.update_from_window_powers(&[2.6e-5; 16]);
+        let rate = 8004.0;
+        for block in 0..400 {
+            state.input_health.push_block(&[0.02; 800], block as f64 * 800.0 / rate);
+        }
+        let calibrator = LlrCalibratorConfig::new().with_hidden(8).init::<TestBackend>(&device);
+        let state = state.with_calibrator(calibrator);
+        state.save_file(&path).unwrap();
+
+        let restored = ReceiverState::<TestBackend>::default().load_file(&path, &device).unwrap();
+        assert_eq!(restored.noise_floor.snapshot().unwrap().floor_db, state.noise_floor.snapshot().unwrap().floor_db);
+        assert!((restored.input_health.dc_offset() - state.input_health.dc_offset()).abs() < 1e-12);
+        assert!((restored.input_health.measured_rate().unwrap() - rate).abs() < 0.1);
+
+        // Calibrator weights survive (half precision on disk)
+        let features = Tensor::<TestBackend, 2>::ones([3, CALIBRATOR_FEATURES], &device);
+        let before: Vec<f32> = state.calibrator.as_ref().unwrap().forward(features.clone()).into_data().to_vec().unwrap();
+        let after: Vec<f32> = restored.calibrator.as_ref().unwrap().forward(features).into_data().to_vec().unwrap();
+        assert_eq!(restored.calibrator.as_ref().unwrap().hidden_size(), 8);
+        for (a, b) in before.iter().zip(&after) {
+            assert!((a - b).abs() < 1e-2, "{} vs {}", a, b);
+        }
+
+        // A missing file leaves the caller's cold state to fall back on
+        assert!(ReceiverState::<TestBackend>::default().load_file(path.with_file_name("missing_state"), &device).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+/// Reference Blocks
+///
+/// Differential detection compares every data symbol with the symbol `lag`
+/// positions earlier on the same tone slot, so a transmission starts with a
+/// reference block: `lag` symbols of known phase 0. After it, the phase of
+/// each slot is the running sum of its bits, and phase noise between
+/// transmitter and receiver accumulates along the chain unchecked.
+///
+/// `ReferenceLayout` generalizes the single block:
+/// - `blocks`: reference group length in differential blocks. The data
+///   block after a group is compared against the slot-wise mean of the
+///   group's phasors, so a longer group gives a cleaner phase baseline.
+/// - `interval`: data blocks between reference groups, 0 = one group at the
+///   start only. At every group the transmitter resets all slots to phase 0
+///   and the receiver restarts its differential baseline there.
+///
+/// Reference blocks carry no data: `blocks / (blocks + interval)` of the
+/// airtime goes to known symbols. The default (1 block, no refresh) is the
+/// original layout. Transmitter and receiver must agree on it.
+///
+/// `order` sets the bits per data symbol (see `dpsk`): a symbol adds the
+/// Gray-coded phase step of its bits instead of `bit * π`.
+///
+/// `pilots` turns the groups into pilots for coherent detection: a data
+/// symbol carries the phase step of its bits as an absolute phase against
+/// the pilots' phase 0, not added to the symbol before it. Each data block
+/// is compared with the pilot phase interpolated linearly (per tone slot)
+/// between the group before it and the group after it, so the reference is
+/// an average over several known symbols instead of one noisy data symbol -
+/// up to the 3 dB differential detection loses. Data is padded with zero
+/// bits to whole intervals and closed by a pilot group, so every data block
+/// has a group on both sides; pilots need `interval` > 0.
+
+use crate::dpsk::DpskOrder;
+
+/// Positions of the known reference blocks among the transmitted blocks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReferenceLayout {
+    /// Symbols per differential block
+    pub lag: usize,
+
+    /// Reference blocks per group (at least 1)
+    pub blocks: usize,
+
+    /// Data blocks between groups, 0 = no refresh
+    pub interval: usize,
+
+    /// Differential phase points (bits per data symbol)
+    pub order: DpskOrder,
+
+    /// Groups are pilots: coherent detection against interpolated pilot phases
+    pub pilots: bool,
+}
+
+/// Symbols compared by each differential decision
+#[derive(Clone, Debug, PartialEq)]
+pub struct DifferentialPairs {
+    /// Current (data) symbol of every decision
+    pub current: Vec<usize>,
+
+    /// Reference symbols of every bit, one row per reference block of a
+    /// group: row j holds bit k's j-th reference (rows repeat the previous
+    /// symbol between groups) [blocks][num_bits]
+    ///
+    /// Pilots: the group before the block, then the group after it
+    /// [2 · blocks][num_bits]
+    pub references: Vec<Vec<usize>>,
+
+    /// Weight of each reference in the baseline, same shape as
+    /// `references`; every decision's weights sum to 1
+    pub weights: Vec<Vec<f32>>,
+
+    /// Bits each decision carries (LLRs per pair)
+    pub bits_per_symbol: usize,
+}
+
+impl DifferentialPairs {
+    /// Number of decisions (data symbols)
+    pub fn len(&self) -> usize {
+        self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_empty()
+    }
+
+    /// LLR erasure mask: bit k is erased (0.0) if its decision's current
+    /// symbol or any of its reference symbols is flagged
+    pub fn erasure_mask(&self, flags: &[bool], num_llrs: usize) -> Vec<f32> {
+        let flagged = |symbol: usize| flags.get(symbol).copied().unwrap_or(false);
+        (0..num_llrs)
+            .map(|bit| {
+                let k = bit / self.bits_per_symbol;
+                let erased = self.current.get(k).is_some_and(|&c| flagged(c))
+                    || self.references.iter().any(|row| row.get(k).is_some_and(|&r| flagged(r)));
+                if erased { 0.0 } else { 1.0 }
+            })
+            .collect()
+    }
+}
+
+impl ReferenceLayout {
+    /// One reference block at the start, binary DPSK (the original layout)
+    pub fn single(lag: usize) -> Self {
+        Self { lag, blocks: 1, interval: 0, order: DpskOrder::Binary, pilots: false }
+    }
+
+    /// True if transmitted block `block` is a known reference block
+    pub fn is_reference_block(&self, block: usize) -> bool {
+        if self.interval == 0 {
+            block < self.blocks
+        } else {
+            block % (self.blocks + self.interval) < self.blocks
+        }
+    }
+
+    /// Transmitted symbols for `num_bits` data bits, references included
+    pub fn num_symbols(&self, num_bits: usize) -> usize {
+        let data_blocks = num_bits.div_ceil(self.order.bits_per_symbol()).div_ceil(self.lag);
+        if self.pilots {
+            // Whole intervals, closed by a pilot group
+            assert!(self.interval > 0, "Pilots need a pilot interval");
+            let intervals = data_blocks.div_ceil(self.interval);
+            return (intervals * (self.blocks + self.interval) + self.blocks) * self.lag;
+        }
+        let groups = if self.interval == 0 { 1 } else { 1 + data_blocks.saturating_sub(1) / self.interval };
+        (data_blocks + groups * self.blocks) * self.lag
+    }
+
+    /// Symbol phases (radians) for `bits`, zero-padded to whole blocks
+    ///
+    /// Reference blocks are phase 0; each data symbol adds the phase step
+    /// of its bits (`bit * π` for binary DPSK) to the symbol `lag`
+    /// positions earlier.
+    pub fn phases(&self, bits: &[u8]) -> Vec<f64> {
+        let lag = self.lag;
+        let bps = self.order.bits_per_symbol();
+        let data_blocks = bits.len().div_ceil(bps).div_ceil(lag);
+        let mut phases = Vec::with_capacity(self.num_symbols(bits.len()));
+
+        let mut next_symbol = 0;
+        let mut block = 0;
+        let mut symbol_bits = vec![0u8; bps];
+        // The leading group always goes out; later groups only ahead of data
+        // (pilots: up to the closing group)
+        let total_blocks = if self.pilots { self.num_symbols(bits.len()) / lag } else { 0 };
+        while block < self.blocks || next_symbol < data_blocks * lag || block < total_blocks {
+            for slot in 0..lag {
+                if self.is_reference_block(block) {
+                    phases.push(0.0);
+                } else {
+                    for (i, bit) in symbol_bits.iter_mut().enumerate() {
+                        *bit = bits.get(next_symbol * bps + i).copied().unwrap_or(0);
+                    }
+                    next_symbol += 1;
+                    let step = self.order.phase_step(&symbol_bits);
+                    phases.push(if self.pilots { step } else { phases[(block - 1) * lag + slot] + step });
+                }
+            }
+            block += 1;
+        }
+        phases
+    }
+
+    /// Decisions available from the first `num_symbols` received symbols
+    /// (whole blocks only), in bit order
+    pub fn pairs(&self, num_symbols: usize) -> DifferentialPairs {
+        if self.pilots {
+            return self.pilot_pairs(num_symbols);
+        }
+        let lag = self.lag;
+        let mut current = Vec::new();
+        let mut references = vec![Vec::new(); self.blocks];
+
+        for block in 0..num_symbols / lag {
+            if self.is_reference_block(block) {
+                continue;
+            }
+            let after_group = self.is_reference_block(block - 1);
+            for slot in 0..lag {
+                current.push(block * lag + slot);
+                for (j, row) in references.iter_mut().enumerate() {
+                    let reference = if after_group { block - self.blocks + j } else { block - 1 };
+                    row.push(reference * lag + slot);
+                }
+            }
+        }
+        let weights = vec![vec![1.0 / self.blocks as f32; current.len()]; self.blocks];
+        DifferentialPairs { current, references, weights, bits_per_symbol: self.order.bits_per_symbol() }
+    }
+
+    /// Pilot references of every data symbol: the group before its block
+    /// and the group after, weighted by distance to the group centers
+    ///
+    /// Blocks whose next group lies past `num_symbols` hold the previous
+    /// group's phase (the second half of the rows repeats it at weight 0).
+    fn pilot_pairs(&self, num_symbols: usize) -> DifferentialPairs {
+        let lag = self.lag;
+        let blocks = self.blocks;
+        let period = blocks + self.interval;
+        let received = num_symbols / lag;
+        let center_offset = (blocks - 1) as f32 / 2.0;
+
+        let mut current = Vec::new();
+        let mut references = vec![Vec::new(); 2 * blocks];
+        let mut weights = vec![Vec::new(); 2 * blocks];
+        for block in 0..received {
+            if self.is_reference_block(block) {
+                continue;
+            }
+            let before = block / period * period;
+            let after = before + period;
+            let (after, t) = if after + blocks <= received {
+                let t = (block - before) as f32 - center_offset;
+                (after, t / period as f32)
+            } else {
+                (before, 0.0)
+            };
+            for slot in 0..lag {
+                current.push(block * lag + slot);
+                for j in 0..blocks {
+                    references[j].push((before + j) * lag + slot);
+                    weights[j].push((1.0 - t) / blocks as f32);
+                    references[blocks + j].push((after + j) * lag + slot);
+                    weights[blocks + j].push(t / blocks as f32);
+                }
+            }
+        }
+        DifferentialPairs { current, references, weights, bits_per_symbol: self.order.bits_per_symbol() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_single_block_is_plain_lag_differential() {
+        let layout = ReferenceLayout::single(4);
+        let bits = [1, 0, 1, 1, 0, 1];
+        let phases = layout.phases(&bits);
+
+        assert_eq!(phases.len(), 12);
+        assert_eq!(layout.num_symbols(bits.len()), 12);
+        assert_eq!(&phases[..4], &[0.0; 4]);
+        assert_eq!(&phases[4..8], &[PI, 0.0, PI, PI]);
+        assert_eq!(&phases[8..], &[PI, PI, PI, PI]);
+
+        let pairs = layout.pairs(12);
+        assert_eq!(pairs.current, (4..12).collect::<Vec<_>>());
+        assert_eq!(pairs.references, vec![(0..8).collect::<Vec<_>>()]);
+    }
+
+    #[test]
+    fn test_refresh_resets_baseline() {
+        // Blocks: R R D D R R D
+        let layout = ReferenceLayout { lag: 2, blocks: 2, interval: 2, order: DpskOrder::Binary, pilots: false };
+        let bits = [1, 1, 1, 0, 1, 0];
+        assert_eq!(layout.num_symbols(bits.len()), 14);
+
+        let phases = layout.phases(&bits);
+        assert_eq!(phases, vec![0.0, 0.0, 0.0, 0.0, PI, PI, 2.0 * PI, PI, 0.0, 0.0, 0.0, 0.0, PI, 0.0]);
+
+        let pairs = layout.pairs(14);
+        assert_eq!(pairs.current, vec![4, 5, 6, 7, 12, 13]);
+        // After a group: both reference blocks; otherwise the previous block twice
+        assert_eq!(pairs.references[0], vec![0, 1, 4, 5, 8, 9]);
+        assert_eq!(pairs.references[1], vec![2, 3, 4, 5, 10, 11]);
+
+        let mask = pairs.erasure_mask(&[false, false, false, false, false, false, false, false, false, false, true, false, false, false], 6);
+        assert_eq!(mask, vec![1.0, 1.0, 1.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_quaternary_packs_two_bits_per_symbol() {
+        let layout = ReferenceLayout { order: DpskOrder::Quaternary, ..ReferenceLayout::single(2) };
+        // Gray: 00 -> 0, 01 -> π/2, 11 -> π, 10 -> 3π/2
+        let bits = [0, 1, 1, 1, 1, 0, 0, 0];
+        assert_eq!(layout.num_symbols(bits.len()), 6);
+
+        let phases = layout.phases(&bits);
+        let expected = [0.0, 0.0, PI / 2.0, PI, PI / 2.0 + 1.5 * PI, PI];
+        assert!(phases.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-12), "{:?}", phases);
+
+        // Two LLRs per decision: erasing symbol 3 erases bits 2 and 3
+        let pairs = layout.pairs(6);
+        assert_eq!(pairs.len(), 4);
+        let mask = pairs.erasure_mask(&[false, false, false, true, false, false], 8);
+        assert_eq!(mask, vec![1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_pilots_carry_absolute_phases_and_interpolate() {
+        // Blocks: P D D D P D D D P (6 data blocks, the last two padding)
+        let layout = ReferenceLayout { lag: 2, blocks: 1, interval: 3, order: DpskOrder::Binary, pilots: true };
+        let bits = [1, 1, 0, 1, 1, 0, 0, 1];
+        assert_eq!(layout.num_symbols(bits.len()), 18);
+
+        let phases = layout.phases(&bits);
+        assert_eq!(phases.len(), 18);
+        assert_eq!(&phases[..8], &[0.0, 0.0, PI, PI, 0.0, PI, PI, 0.0]);
+        assert_eq!(&phases[8..12], &[0.0, 0.0, 0.0, PI]);
+        assert!(phases[12..].iter().all(|&p| p == 0.0));
+
+        // Block 1: a quarter of the way from pilot 0 to pilot 4
+        let pairs = layout.pairs(18);
+        assert_eq!(pairs.len(), 12);
+        assert_eq!((pairs.current[0], pairs.references[0][0], pairs.references[1][0]), (2, 0, 8));
+        assert!((pairs.weights[0][0] - 0.75).abs() < 1e-6 && (pairs.weights[1][0] - 0.25).abs() < 1e-6);
+        for k in 0..pairs.len() {
+            assert!((pairs.weights[0][k] + pairs.weights[1][k] - 1.0).abs() < 1e-6);
+        }
+
+        // Closing group not received: hold the pilot before
+        let truncated = layout.pairs(16);
+        assert_eq!(truncated.references[1][8], 8);
+        assert_eq!(truncated.weights[1][8], 0.0);
+    }
+}
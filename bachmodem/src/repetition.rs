@@ -8,8 +8,16 @@
 /// - Multipath mitigation via diversity
 
 use burn::tensor::{Tensor, backend::Backend, ElementConversion};
-use crate::modulation::{modulate_fhdpsk_with_flourishes, encode_bits};
-use crate::wavelet::{FS, SYMBOL_DURATION};
+use crate::consolidation::vote_bytes;
+use crate::config::ModemConfig;
+use crate::modulation::{modulate_fhdpsk_with_config, encode_bits};
+use crate::polar::crc8;
+
+/// Data symbols between flourishes of a slot transmission (`TimeSlotConfig::new`)
+pub const SLOT_FLOURISH_INTERVAL: usize = 32;
+
+/// Owner header bytes in front of every shared slot's message
+pub const OWNER_HEADER_LEN: usize = 2;
 
 /// Time slot configuration for repetition protocol
 #[derive(Clone, Debug)]
@@ -25,24 +33,42 @@ pub struct TimeSlotConfig {
     
     /// Time slot start times (seconds from beginning)
     pub slot_starts: Vec<f64>,
+    
+    /// Data symbols between flourishes of each transmission (0 = none)
+    pub flourish_interval: usize,
 }
 
 impl TimeSlotConfig {
-    /// Create time slot configuration for given message length
+    /// Create time slot configuration for given message length, with a
+    /// flourish every `SLOT_FLOURISH_INTERVAL` symbols
     pub fn new(message_bytes: usize, num_repetitions: usize, listening_gap: f64) -> Self {
-        // Calculate transmission duration
-        // Each byte = 8 bits, pad to multiple of 16, add reference block
-        let total_bits = ((message_bytes * 8 + 15) / 16) * 16 + 16;
-        let num_symbols = total_bits;
-        let flourish_interval = 64;
-        let num_flourishes = num_symbols / flourish_interval;
-        
-        let data_duration = num_symbols as f64 * SYMBOL_DURATION;
-        let flourish_duration = num_flourishes as f64 * 1.6; // 1.6 seconds each (2 cycles * 16 notes * 0.05)
-        let preamble_duration = 3.2; // 3.2 seconds (4 cycles * 16 notes * 0.05)
-        let postamble_duration = 1.6; // 1.6 seconds (2 cycles * 16 notes * 0.05)
+        Self::flourished(message_bytes, num_repetitions, listening_gap, SLOT_FLOURISH_INTERVAL)
+    }
+    
+    /// `new` with a flourish every `flourish_interval` symbols (0 = none)
+    pub fn flourished(message_bytes: usize, num_repetitions: usize, listening_gap: f64, flourish_interval: usize) -> Self {
+        Self::with_config(message_bytes, num_repetitions, listening_gap, flourish_interval, &ModemConfig::default())
+    }
+    
+    /// `flourished` for frames of `config` (`generate_repetition_transmission_with_config`)
+    /// 
+    /// Data symbols follow the reference layout of the alphabet; the preamble
+    /// is `preamble_sweeps` sweeps of `num_tones` notes, each flourish and the
+    /// postamble one up/down sweep.
+    pub fn with_config(
+        message_bytes: usize,
+        num_repetitions: usize,
+        listening_gap: f64,
+        flourish_interval: usize,
+        config: &ModemConfig,
+    ) -> Self {
+        let num_symbols = config.reference_layout().num_symbols(message_bytes * 8);
+        let num_flourishes = if flourish_interval > 0 { (num_symbols - 1) / flourish_interval } else { 0 };
         
-        let transmission_duration = preamble_duration + data_duration + flourish_duration + postamble_duration;
+        let sweep_notes = 2 * config.num_tones;
+        let notes = config.preamble_sweeps * config.num_tones + (num_flourishes + 1) * sweep_notes;
+        let samples = notes * config.preamble_note_samples() + num_symbols * config.symbol_samples();
+        let transmission_duration = samples as f64 / config.sample_rate;
         
         // Calculate slot start times
         let mut slot_starts = Vec::new();
@@ -58,6 +84,7 @@ impl TimeSlotConfig {
             listening_gap,
             num_repetitions,
             slot_starts,
+            flourish_interval,
         }
     }
     
@@ -75,24 +102,34 @@ pub fn generate_repetition_transmission<B: Backend>(
     device: &B::Device,
     message: &[u8],
     config: &TimeSlotConfig,
+) -> Tensor<B, 1> {
+    generate_repetition_transmission_with_config::<B>(device, message, config, &ModemConfig::default())
+}
+
+/// Time-slotted repetition of `modem` frames (slots from `TimeSlotConfig::with_config`)
+pub fn generate_repetition_transmission_with_config<B: Backend>(
+    device: &B::Device,
+    message: &[u8],
+    config: &TimeSlotConfig,
+    modem: &ModemConfig,
 ) -> Tensor<B, 1> {
     // Generate one clean transmission
-    let single_transmission = modulate_fhdpsk_with_flourishes::<B>(
+    let single_transmission = modulate_fhdpsk_with_config::<B>(
         device,
         message,
         true,  // Add preamble
-        32,    // Flourish interval (more frequent inter-ambles)
+        &modem.clone().with_flourish_interval(config.flourish_interval),
     );
     
     let transmission_len = single_transmission.dims()[0];
-    let gap_len = (config.listening_gap * FS) as usize;
+    let gap_len = (config.listening_gap * modem.sample_rate) as usize;
     
     // Create empty buffer for all repetitions
-    let total_samples = (config.total_duration() * FS) as usize;
+    let total_samples = (config.total_duration() * modem.sample_rate) as usize;
     let mut output = Tensor::<B, 1>::zeros([total_samples], device);
     
     for (rep_idx, &slot_start) in config.slot_starts.iter().enumerate() {
-        let start_sample = (slot_start * FS) as usize;
+        let start_sample = (slot_start * modem.sample_rate) as usize;
         
         println!("  Repetition {}/{}: starts at {:.1}s (sample {})", 
             rep_idx + 1, config.num_repetitions, slot_start, start_sample);
@@ -111,6 +148,190 @@ pub fn generate_repetition_transmission<B: Backend>(
     output
 }
 
+/// Shared-slot schedule: several stations taking turns on one slot grid
+/// 
+/// Slot `i` belongs to stream `(i + 1) % stations.len()`, so with two
+/// stations station A owns the odd slots 1, 3, 5, ... and station B the
+/// even slots 0, 2, 4, ... Every slot also declares its owner in a
+/// CRC-protected header (`owner_header`), so a receiver routes the slots it
+/// decodes without knowing where the schedule started.
+#[derive(Clone, Debug)]
+pub struct InterleavedSchedule {
+    /// Station identifiers (callsigns), in slot order
+    pub stations: Vec<String>,
+}
+
+impl InterleavedSchedule {
+    /// Create a schedule alternating between the given stations
+    pub fn new(stations: Vec<String>) -> Self {
+        assert!(!stations.is_empty(), "Schedule needs at least one station");
+        assert!(stations.len() <= u8::MAX as usize, "Too many stations for an owner header");
+        Self { stations }
+    }
+    
+    /// Number of interleaved streams
+    pub fn num_streams(&self) -> usize {
+        self.stations.len()
+    }
+    
+    /// Stream index that owns a given slot
+    pub fn owner(&self, slot_idx: usize) -> usize {
+        (slot_idx + 1) % self.stations.len()
+    }
+    
+    /// Stream index for a station identifier (e.g. as declared in a slot header)
+    pub fn stream_of(&self, station: &str) -> Option<usize> {
+        self.stations.iter().position(|s| s == station)
+    }
+    
+    /// Slot indices belonging to one stream within the first `num_slots` slots
+    pub fn slots_for(&self, stream: usize, num_slots: usize) -> Vec<usize> {
+        (0..num_slots).filter(|&i| self.owner(i) == stream).collect()
+    }
+    
+    /// Header declaring `stream` as a slot's owner: `[stream: u8][CRC-8]`
+    pub fn owner_header(&self, stream: usize) -> [u8; OWNER_HEADER_LEN] {
+        assert!(stream < self.num_streams(), "Stream outside the schedule");
+        [stream as u8, crc8(&[stream as u8])]
+    }
+    
+    /// Stream declared by the owner header at the start of a decoded slot,
+    /// None if the header is damaged or names no stream of this schedule
+    pub fn declared_owner(&self, decoded: &[u8]) -> Option<usize> {
+        match decoded {
+            [stream, crc, ..] if crc8(&[*stream]) == *crc && (*stream as usize) < self.num_streams() => Some(*stream as usize),
+            _ => None,
+        }
+    }
+}
+
+/// Generate a shared-slot transmission carrying one message per station
+/// 
+/// Each slot of `config` carries the message of the station owning it,
+/// behind its owner header. `config` should be sized for the longest
+/// message plus `OWNER_HEADER_LEN`.
+pub fn generate_interleaved_transmission<B: Backend>(
+    device: &B::Device,
+    messages: &[&[u8]],
+    schedule: &InterleavedSchedule,
+    config: &TimeSlotConfig,
+) -> Tensor<B, 1> {
+    generate_interleaved_transmission_with_config::<B>(device, messages, schedule, config, &ModemConfig::default())
+}
+
+/// Shared-slot transmission of `modem` frames (slots from `TimeSlotConfig::with_config`)
+pub fn generate_interleaved_transmission_with_config<B: Backend>(
+    device: &B::Device,
+    messages: &[&[u8]],
+    schedule: &InterleavedSchedule,
+    config: &TimeSlotConfig,
+    modem: &ModemConfig,
+) -> Tensor<B, 1> {
+    assert_eq!(messages.len(), schedule.num_streams(), "One message per station required");
+    
+    let slot_modem = modem.clone().with_flourish_interval(config.flourish_interval);
+    let transmissions: Vec<Tensor<B, 1>> = messages.iter()
+        .enumerate()
+        .map(|(stream, msg)| {
+            let mut slot_bytes = schedule.owner_header(stream).to_vec();
+            slot_bytes.extend_from_slice(msg);
+            modulate_fhdpsk_with_config::<B>(device, &slot_bytes, true, &slot_modem)
+        })
+        .collect();
+    
+    let total_samples = (config.total_duration() * modem.sample_rate) as usize;
+    let mut output = Tensor::<B, 1>::zeros([total_samples], device);
+    
+    for (slot_idx, &slot_start) in config.slot_starts.iter().enumerate() {
+        let stream = schedule.owner(slot_idx);
+        let transmission = &transmissions[stream];
+        let start_sample = (slot_start * modem.sample_rate) as usize;
+        
+        println!("  Slot {}/{}: station {} at {:.1}s",
+            slot_idx + 1, config.num_repetitions, schedule.stations[stream], slot_start);
+        
+        let end_sample = (start_sample + transmission.dims()[0]).min(total_samples);
+        let len = end_sample.saturating_sub(start_sample);
+        
+        if len > 0 {
+            let values = transmission.clone().slice([0..len]);
+            output = output.slice_assign([start_sample..start_sample + len], values);
+        }
+    }
+    
+    output
+}
+
+/// Per-stream LLR accumulator for demultiplexed shared-slot reception
+/// 
+/// Soft LLRs from each decoded slot are routed to the stream its owner
+/// header declares (the schedule's owner if the header is damaged) and
+/// combined there (Maximum Ratio Combining), so two stations sharing
+/// alternating slots never contaminate each other's LLRs.
+pub struct StreamAccumulator<B: Backend> {
+    /// Slot ownership
+    pub schedule: InterleavedSchedule,
+    
+    /// Weighted LLR sum per stream (None until the first slot arrives)
+    sums: Vec<Option<Tensor<B, 1>>>,
+    
+    /// Number of slots combined per stream
+    counts: Vec<usize>,
+}
+
+impl<B: Backend> StreamAccumulator<B> {
+    pub fn new(schedule: InterleavedSchedule) -> Self {
+        let n = schedule.num_streams();
+        Self {
+            schedule,
+            sums: vec![None; n],
+            counts: vec![0; n],
+        }
+    }
+    
+    /// Add the LLRs decoded from `slot_idx`, routed by slot ownership
+    pub fn accumulate(&mut self, slot_idx: usize, llrs: Tensor<B, 1>, weight: f32) {
+        let stream = self.schedule.owner(slot_idx);
+        self.accumulate_stream(stream, llrs, weight);
+    }
+    
+    /// Add the LLRs of one slot to the stream its owner header declares
+    /// 
+    /// `decoded` is the slot's hard-decision bytes; a damaged header falls
+    /// back to the schedule's owner of `slot_idx`. Returns the stream.
+    pub fn accumulate_declared(&mut self, slot_idx: usize, decoded: &[u8], llrs: Tensor<B, 1>, weight: f32) -> usize {
+        let stream = self.schedule.declared_owner(decoded).unwrap_or_else(|| self.schedule.owner(slot_idx));
+        self.accumulate_stream(stream, llrs, weight);
+        stream
+    }
+    
+    /// Add LLRs to an explicit stream (e.g. ownership taken from a slot header)
+    pub fn accumulate_stream(&mut self, stream: usize, llrs: Tensor<B, 1>, weight: f32) {
+        let weighted = llrs * weight;
+        
+        self.sums[stream] = Some(match self.sums[stream].take() {
+            None => weighted,
+            Some(sum) => {
+                // Slots cut at slightly different boundaries may yield a few
+                // more or fewer symbols; combine over the common length
+                let len = sum.dims()[0].min(weighted.dims()[0]);
+                sum.slice([0..len]) + weighted.slice([0..len])
+            }
+        });
+        self.counts[stream] += 1;
+    }
+    
+    /// Combined LLRs for one stream
+    pub fn combined(&self, stream: usize) -> Option<Tensor<B, 1>> {
+        self.sums[stream].clone()
+    }
+    
+    /// Number of slots combined into one stream
+    pub fn num_slots(&self, stream: usize) -> usize {
+        self.counts[stream]
+    }
+}
+
 /// Multi-copy combining strategies
 #[derive(Clone, Copy, Debug)]
 pub enum CombiningStrategy {
@@ -147,43 +368,14 @@ pub struct DecodedCopy {
 }
 
 /// Combine multiple decoded copies using voting
+///
+/// Byte-wise majority weighted by the SNR estimates (`consolidation::vote_bytes`)
 pub fn combine_decoded_copies(copies: &[DecodedCopy]) -> Vec<u8> {
-    if copies.is_empty() {
-        return Vec::new();
-    }
-    
-    // Find maximum length
-    let max_len = copies.iter().map(|c| c.data.len()).max().unwrap_or(0);
-    
-    if max_len == 0 {
-        return Vec::new();
-    }
-    
-    let mut combined = Vec::new();
-    
-    // For each byte position
-    for byte_idx in 0..max_len {
-        // Collect all valid bytes at this position with their SNR weights
-        let mut byte_votes: std::collections::HashMap<u8, f32> = std::collections::HashMap::new();
-        
-        for copy in copies {
-            if byte_idx < copy.data.len() {
-                let byte_val = copy.data[byte_idx];
-                let weight = copy.snr_estimate.max(0.1); // Minimum weight
-                *byte_votes.entry(byte_val).or_insert(0.0) += weight;
-            }
-        }
-        
-        // Select byte with highest weighted vote
-        let best_byte = byte_votes.iter()
-            .max_by(|(_, w1), (_, w2)| w1.partial_cmp(w2).unwrap())
-            .map(|(b, _)| *b)
-            .unwrap_or(0);
-        
-        combined.push(best_byte);
-    }
+    let voters: Vec<(&[u8], f32, f32)> = copies.iter()
+        .map(|c| (c.data.as_slice(), c.snr_estimate.max(0.1), c.snr_estimate)) // Minimum weight
+        .collect();
     
-    combined
+    vote_bytes(&voters).into_iter().map(|byte| byte.value).collect()
 }
 
 /// Multipath mitigation using frequency diversity
@@ -238,6 +430,8 @@ pub fn estimate_time_diversity(repetitions: &[DecodedCopy]) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::TestBackend;
+    use crate::wavelet::FS;
     
     #[test]
     fn test_time_slot_config() {
@@ -251,6 +445,137 @@ mod tests {
         println!("Time slot config: {:#?}", config);
     }
     
+    #[test]
+    fn test_repetition_roundtrip_across_alphabets() {
+        use crate::modulation::demodulate_fhdpsk_ex_with_config;
+        
+        let device = Default::default();
+        let message = b"Eight and 32 tones";
+        for num_tones in [8, 16, 32] {
+            let modem = ModemConfig::new(num_tones).with_flourish_interval(SLOT_FLOURISH_INTERVAL);
+            let config = TimeSlotConfig::with_config(message.len(), 2, 1.0, SLOT_FLOURISH_INTERVAL, &modem);
+            
+            // Slots sized to the frame of this alphabet
+            let frame = modulate_fhdpsk_with_config::<TestBackend>(&device, message, true, &modem);
+            let slot_len = frame.dims()[0];
+            assert_eq!((config.transmission_duration * FS).round() as usize, slot_len, "{} tones", num_tones);
+            
+            let signal = generate_repetition_transmission_with_config::<TestBackend>(&device, message, &config, &modem);
+            for &slot_start in &config.slot_starts {
+                let start = (slot_start * FS).round() as usize;
+                let end = (start + slot_len).min(signal.dims()[0]);
+                let slot = signal.clone().slice([start..end]);
+                let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &slot, true, &modem);
+                assert_eq!(&decoded[..message.len()], message, "{} tones", num_tones);
+            }
+        }
+        
+        // The default schedule is the 16-tone one
+        let default = TimeSlotConfig::new(message.len(), 2, 1.0);
+        let sixteen = TimeSlotConfig::with_config(message.len(), 2, 1.0, SLOT_FLOURISH_INTERVAL, &ModemConfig::new(16));
+        assert_eq!(default.transmission_duration, sixteen.transmission_duration);
+    }
+    
+    #[test]
+    fn test_interleaved_transmission_follows_the_modem_config() {
+        use crate::modulation::demodulate_fhdpsk_ex_with_config;
+        
+        let device = Default::default();
+        let schedule = InterleavedSchedule::new(vec!["A1AAA".to_string(), "B1BBB".to_string()]);
+        let messages: [&[u8]; 2] = [b"EIGHT A", b"EIGHT B"];
+        let modem = ModemConfig::new(8);
+        let config = TimeSlotConfig::with_config(messages[0].len() + OWNER_HEADER_LEN, 2, 1.0, SLOT_FLOURISH_INTERVAL, &modem);
+        let signal = generate_interleaved_transmission_with_config::<TestBackend>(&device, &messages, &schedule, &config, &modem);
+        
+        let slot_modem = modem.with_flourish_interval(config.flourish_interval);
+        let slot_len = (config.transmission_duration * FS).round() as usize;
+        for (slot_idx, &slot_start) in config.slot_starts.iter().enumerate() {
+            let start = (slot_start * FS).round() as usize;
+            let end = (start + slot_len).min(signal.dims()[0]);
+            let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &signal.clone().slice([start..end]), true, &slot_modem);
+            let stream = schedule.declared_owner(&decoded).unwrap();
+            assert_eq!(stream, schedule.owner(slot_idx));
+            assert_eq!(&decoded[OWNER_HEADER_LEN..OWNER_HEADER_LEN + messages[stream].len()], messages[stream]);
+        }
+    }
+    
+    #[test]
+    fn test_interleaved_schedule() {
+        let schedule = InterleavedSchedule::new(vec!["A1AAA".to_string(), "B1BBB".to_string()]);
+        
+        // Station A on the odd slots, B on the even ones
+        assert_eq!(schedule.owner(0), 1);
+        assert_eq!(schedule.owner(1), 0);
+        assert_eq!(schedule.owner(4), 1);
+        assert_eq!(schedule.slots_for(0, 6), vec![1, 3, 5]);
+        assert_eq!(schedule.slots_for(1, 6), vec![0, 2, 4]);
+        assert_eq!(schedule.stream_of("B1BBB"), Some(1));
+        assert_eq!(schedule.stream_of("C1CCC"), None);
+        
+        let header = schedule.owner_header(1);
+        assert_eq!(schedule.declared_owner(&[header[0], header[1], b'x']), Some(1));
+        assert_eq!(schedule.declared_owner(&[header[0] ^ 1, header[1]]), None);
+        assert_eq!(schedule.declared_owner(&[2, crc8(&[2])]), None);
+        assert_eq!(schedule.declared_owner(&header[..1]), None);
+    }
+    
+    #[test]
+    fn test_stream_accumulator() {
+        use burn::backend::Wgpu;
+        
+        let device = Default::default();
+        let schedule = InterleavedSchedule::new(vec!["A".to_string(), "B".to_string()]);
+        let mut acc = StreamAccumulator::<Wgpu>::new(schedule);
+        
+        for slot in 0..4 {
+            let sign = if slot % 2 == 1 { 1.0 } else { -1.0 };
+            let llrs = Tensor::<Wgpu, 1>::from_floats([sign, sign, sign], &device);
+            acc.accumulate(slot, llrs, 2.0);
+        }
+        
+        assert_eq!(acc.num_slots(0), 2);
+        assert_eq!(acc.num_slots(1), 2);
+        
+        let a: Vec<f32> = acc.combined(0).unwrap().into_data().to_vec().unwrap();
+        let b: Vec<f32> = acc.combined(1).unwrap().into_data().to_vec().unwrap();
+        assert_eq!(a, vec![4.0, 4.0, 4.0]);
+        assert_eq!(b, vec![-4.0, -4.0, -4.0]);
+    }
+    
+    #[test]
+    fn test_declared_owners_route_slots_of_a_late_receiver() {
+        use crate::modulation::{demodulate_fhdpsk_ex, demodulate_fhdpsk_soft, pack_bits};
+        
+        let device = Default::default();
+        let schedule = InterleavedSchedule::new(vec!["A1AAA".to_string(), "B1BBB".to_string()]);
+        let messages: [&[u8]; 2] = [b"FROM A1AAA", b"FROM B1BBB"];
+        let config = TimeSlotConfig::new(messages[0].len() + OWNER_HEADER_LEN, 5, 1.0);
+        let signal = generate_interleaved_transmission::<TestBackend>(&device, &messages, &schedule, &config);
+        
+        // The receiver tunes in one slot late: its slot 0 is the schedule's
+        // slot 1, so its own slot count has the wrong parity
+        let mut acc = StreamAccumulator::<TestBackend>::new(schedule.clone());
+        let slot_len = (config.transmission_duration * FS) as usize;
+        for (rx_slot, &slot_start) in config.slot_starts[1..].iter().enumerate() {
+            let start = (slot_start * FS) as usize;
+            let end = (start + slot_len).min(signal.dims()[0]);
+            let slot = signal.clone().slice([start..end]);
+            let decoded = demodulate_fhdpsk_ex::<TestBackend>(&device, &slot, true, config.flourish_interval);
+            let llrs = demodulate_fhdpsk_soft::<TestBackend>(&device, &slot, true, config.flourish_interval);
+            let stream = acc.accumulate_declared(rx_slot, &decoded, llrs, 1.0);
+            assert_eq!(stream, schedule.owner(rx_slot + 1));
+        }
+        assert_eq!((acc.num_slots(0), acc.num_slots(1)), (2, 2));
+        
+        for (stream, message) in messages.iter().enumerate() {
+            let llrs: Vec<f32> = acc.combined(stream).unwrap().into_data().to_vec().unwrap();
+            let bits: Vec<u8> = llrs.iter().map(|&llr| (llr < 0.0) as u8).collect();
+            let bytes = pack_bits(&bits);
+            assert_eq!(&bytes[..OWNER_HEADER_LEN], schedule.owner_header(stream));
+            assert_eq!(&bytes[OWNER_HEADER_LEN..OWNER_HEADER_LEN + message.len()], *message);
+        }
+    }
+    
     #[test]
     fn test_combine_decoded_copies() {
         let copies = vec![
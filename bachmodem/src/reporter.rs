@@ -0,0 +1,424 @@
+/// Spot Reporter
+///
+/// Uploads beacon spots to an aggregation service (PSK Reporter / WSPRnet
+/// style) without hammering it:
+/// - repeats of a station are dropped (one spot per callsign and dial
+///   frequency per `dedup_window_s`): each spot votes its station key into a
+///   `MessageConsolidator` that only merges exact matches
+/// - spots are batched and uploaded at most once per `upload_interval_s`
+/// - a failed upload keeps its batch and backs off exponentially from
+///   `retry_initial_s` to `retry_max_s`; the queue is bounded, the oldest
+///   spots go first
+/// - a rejected upload (the service refused the data) is dropped, not retried
+///
+/// The reporter runs on the caller's clock (`add`, `poll(now_s)`), like
+/// `ChannelScanner`, and hands batches to a `SpotSink`. `HttpSpotSink`
+/// (feature `reporter`) POSTs them as JSON:
+///
+/// ```text
+/// {"receiver": {"callsign": "N0CALL", "locator": "FN31", "software": "bachmodem 0.1.0"},
+///  "spots": [Spot::to_json(), ...]}
+/// ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use crate::consolidation::{MessageConsolidator, Vote};
+use crate::spot::{json_str, Spot};
+
+/// The reporting station
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReceiverInfo {
+    pub callsign: String,
+    pub locator: Option<String>,
+
+    /// Software name and version sent with every upload
+    pub software: String,
+}
+
+impl ReceiverInfo {
+    pub fn new(callsign: &str, locator: Option<&str>) -> Self {
+        Self {
+            callsign: callsign.to_ascii_uppercase(),
+            locator: locator.map(str::to_string),
+            software: format!("bachmodem {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// `{"callsign", "locator" | null, "software"}`
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"callsign\":{},\"locator\":{},\"software\":{}}}",
+            json_str(&self.callsign),
+            self.locator.as_deref().map_or("null".to_string(), json_str),
+            json_str(&self.software),
+        )
+    }
+}
+
+/// Batching, dedup and retry settings
+#[derive(Clone, Debug)]
+pub struct ReporterConfig {
+    pub receiver: ReceiverInfo,
+
+    /// Repeats of a station within this window are not reported (seconds)
+    pub dedup_window_s: f64,
+
+    /// Minimum time between uploads (seconds)
+    pub upload_interval_s: f64,
+
+    /// Spots per upload
+    pub max_batch: usize,
+
+    /// Spots held while the service is unreachable
+    pub max_queue: usize,
+
+    /// Back-off after the first failed upload, doubled per failure (seconds)
+    pub retry_initial_s: f64,
+    pub retry_max_s: f64,
+}
+
+impl ReporterConfig {
+    pub fn new(receiver: ReceiverInfo) -> Self {
+        Self {
+            receiver,
+            dedup_window_s: 300.0,
+            upload_interval_s: 300.0,
+            max_batch: 200,
+            max_queue: 2000,
+            retry_initial_s: 60.0,
+            retry_max_s: 3600.0,
+        }
+    }
+}
+
+/// Upload failures
+#[derive(Clone, Debug, PartialEq)]
+pub enum UploadError {
+    /// Network error or server trouble; the batch is retried
+    Transient(String),
+
+    /// The service refused the data; the batch is dropped
+    Rejected(String),
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::Transient(reason) => write!(f, "spot upload failed, will retry: {}", reason),
+            UploadError::Rejected(reason) => write!(f, "spot upload rejected: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+/// Transport of spot batches
+pub trait SpotSink {
+    fn upload(&mut self, receiver: &ReceiverInfo, spots: &[Spot]) -> Result<(), UploadError>;
+}
+
+impl<F: FnMut(&ReceiverInfo, &[Spot]) -> Result<(), UploadError>> SpotSink for F {
+    fn upload(&mut self, receiver: &ReceiverInfo, spots: &[Spot]) -> Result<(), UploadError> {
+        self(receiver, spots)
+    }
+}
+
+/// Spot counts since start
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReporterStats {
+    pub uploaded: u64,
+    pub duplicates: u64,
+
+    /// Dropped because the queue was full
+    pub overflowed: u64,
+
+    /// Dropped because the service rejected their batch
+    pub rejected: u64,
+
+    /// Failed upload attempts
+    pub failures: u64,
+}
+
+/// Deduplicating, rate-limited spot uploader
+pub struct SpotReporter<S: SpotSink> {
+    pub config: ReporterConfig,
+    sink: S,
+
+    /// One message per station heard within the dedup window
+    heard: MessageConsolidator,
+
+    /// Time each `heard` message was first reported
+    heard_s: Vec<f64>,
+    queue: VecDeque<Spot>,
+    next_upload_s: f64,
+
+    /// Current back-off (0 = last upload succeeded)
+    backoff_s: f64,
+    stats: ReporterStats,
+}
+
+impl<S: SpotSink> SpotReporter<S> {
+    pub fn new(config: ReporterConfig, sink: S) -> Self {
+        Self {
+            heard: MessageConsolidator::new(1.0),
+            heard_s: Vec::new(),
+            config,
+            sink,
+            queue: VecDeque::new(),
+            next_upload_s: f64::NEG_INFINITY,
+            backoff_s: 0.0,
+            stats: ReporterStats::default(),
+        }
+    }
+
+    /// Queue a spot; false if it repeats one reported within the dedup window
+    pub fn add(&mut self, spot: Spot) -> bool {
+        // Forget stations not heard for a window
+        let (now_s, window_s) = (spot.time_s, self.config.dedup_window_s);
+        let heard_s = &self.heard_s;
+        self.heard.retain(|i, _| now_s - heard_s[i] < window_s);
+        self.heard_s.retain(|&t| now_s - t < window_s);
+
+        let station = format!("{} {}", spot.callsign, spot.dial_hz.map_or("-".to_string(), |hz| hz.to_string()));
+        let known = self.heard.messages().len();
+        let vote = Vote { source: "spot".to_string(), payload: station.into_bytes(), weight: 1.0, snr_db: spot.snr_db };
+        if self.heard.add(vote) < known {
+            self.stats.duplicates += 1;
+            return false;
+        }
+        self.heard_s.push(spot.time_s);
+        if self.queue.len() >= self.config.max_queue {
+            self.queue.pop_front();
+            self.stats.overflowed += 1;
+        }
+        self.queue.push_back(spot);
+        true
+    }
+
+    /// Upload a batch if one is due
+    ///
+    /// Returns the number of spots uploaded, the upload error, or None if
+    /// nothing was due.
+    pub fn poll(&mut self, now_s: f64) -> Option<Result<usize, UploadError>> {
+        if self.queue.is_empty() || now_s < self.next_upload_s {
+            return None;
+        }
+
+        let count = self.queue.len().min(self.config.max_batch);
+        let batch = &self.queue.make_contiguous()[..count];
+        let result = self.sink.upload(&self.config.receiver, batch);
+
+        match &result {
+            Ok(()) => {
+                self.queue.drain(..count);
+                self.stats.uploaded += count as u64;
+                self.backoff_s = 0.0;
+                self.next_upload_s = now_s + self.config.upload_interval_s;
+            }
+            Err(UploadError::Transient(_)) => {
+                self.stats.failures += 1;
+                self.backoff_s = if self.backoff_s == 0.0 {
+                    self.config.retry_initial_s
+                } else {
+                    (2.0 * self.backoff_s).min(self.config.retry_max_s)
+                };
+                self.next_upload_s = now_s + self.backoff_s;
+            }
+            Err(UploadError::Rejected(_)) => {
+                self.queue.drain(..count);
+                self.stats.failures += 1;
+                self.stats.rejected += count as u64;
+                self.next_upload_s = now_s + self.config.upload_interval_s;
+            }
+        }
+        Some(result.map(|_| count))
+    }
+
+    /// Spots waiting for upload
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn stats(&self) -> ReporterStats {
+        self.stats
+    }
+}
+
+/// POSTs batches as JSON to an HTTP(S) endpoint
+#[cfg(feature = "reporter")]
+pub struct HttpSpotSink {
+    pub url: String,
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "reporter")]
+impl HttpSpotSink {
+    pub fn new(url: &str) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(std::time::Duration::from_secs(30)).build();
+        Self { url: url.to_string(), agent }
+    }
+
+    /// Request body of one batch
+    pub fn body(receiver: &ReceiverInfo, spots: &[Spot]) -> String {
+        let spots: Vec<String> = spots.iter().map(Spot::to_json).collect();
+        format!("{{\"receiver\":{},\"spots\":[{}]}}", receiver.to_json(), spots.join(","))
+    }
+}
+
+#[cfg(feature = "reporter")]
+impl SpotSink for HttpSpotSink {
+    fn upload(&mut self, receiver: &ReceiverInfo, spots: &[Spot]) -> Result<(), UploadError> {
+        let response = self.agent.post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&Self::body(receiver, spots));
+
+        match response {
+            Ok(_) => Ok(()),
+            // Rate limited or server trouble: try again later
+            Err(ureq::Error::Status(code, _)) if code == 429 || code >= 500 => {
+                Err(UploadError::Transient(format!("HTTP {}", code)))
+            }
+            Err(ureq::Error::Status(code, response)) => {
+                let reason = response.into_string().unwrap_or_default();
+                Err(UploadError::Rejected(format!("HTTP {} {}", code, reason.trim())))
+            }
+            Err(ureq::Error::Transport(transport)) => Err(UploadError::Transient(transport.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spot(call: &str, t: f64) -> Spot {
+        Spot::from_decode(&format!("CQ {} FN31", call), -20.0, Some(14_074_000), t).unwrap()
+    }
+
+    #[test]
+    fn test_batching_dedup_and_backoff() {
+        let mut uploads: Vec<(f64, usize)> = Vec::new();
+        let mut fail = vec![false, true, true, false, false];
+        let mut now = 0.0;
+
+        let mut config = ReporterConfig::new(ReceiverInfo::new("n0call", Some("FN31")));
+        config.max_batch = 3;
+        let sink = |receiver: &ReceiverInfo, spots: &[Spot]| {
+            assert_eq!(receiver.callsign, "N0CALL");
+            uploads.push((spots[0].time_s, spots.len()));
+            if fail.remove(0) { Err(UploadError::Transient("HTTP 503".to_string())) } else { Ok(()) }
+        };
+        let mut reporter = SpotReporter::new(config, sink);
+
+        // A beacon heard every slot is queued once per window
+        assert!(reporter.add(spot("K1ABC", 0.0)));
+        assert!(!reporter.add(spot("K1ABC", 30.0)));
+        for (i, call) in ["DL1ABC", "G4XYZ", "VK2AB"].iter().enumerate() {
+            assert!(reporter.add(spot(call, 40.0 + i as f64)));
+        }
+
+        // First poll uploads a full batch, the rest waits for the interval
+        assert_eq!(reporter.poll(now), Some(Ok(3)));
+        assert_eq!(reporter.poll(now + 10.0), None);
+
+        // Two failures back off 60 s, then 120 s
+        now += 300.0;
+        assert!(matches!(reporter.poll(now), Some(Err(UploadError::Transient(_)))));
+        assert_eq!(reporter.poll(now + 59.0), None);
+        now += 60.0;
+        assert!(reporter.poll(now).unwrap().is_err());
+        assert_eq!(reporter.poll(now + 119.0), None);
+        now += 120.0;
+        assert_eq!(reporter.poll(now), Some(Ok(1)));
+        assert_eq!(reporter.pending(), 0);
+
+        let stats = reporter.stats();
+        assert_eq!((stats.uploaded, stats.duplicates, stats.failures), (4, 1, 2));
+        drop(reporter);
+        assert_eq!(uploads.iter().map(|u| u.1).collect::<Vec<_>>(), vec![3, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_dedup_window() {
+        let mut reporter = SpotReporter::new(
+            ReporterConfig::new(ReceiverInfo::new("N0CALL", None)),
+            |_: &ReceiverInfo, _: &[Spot]| Ok(()),
+        );
+        let spot = |call: &str, dial: Option<u64>, t: f64| Spot::from_decode(call, -20.0, dial, t).unwrap();
+
+        assert!(reporter.add(spot("N0CALL", Some(14_074_000), 0.0)));
+        assert!(!reporter.add(spot("N0CALL", Some(14_074_000), 60.0)));
+        assert!(reporter.add(spot("N0CALL", Some(7_074_000), 60.0)));
+        assert!(reporter.add(spot("N0CALL", None, 60.0)));
+        assert!(reporter.add(spot("K1ABC", Some(14_074_000), 60.0)));
+        assert!(!reporter.add(spot("K1ABC", Some(14_074_000), 200.0)));
+        assert!(reporter.add(spot("N0CALL", Some(14_074_000), 300.0)));
+        assert_eq!(reporter.stats().duplicates, 2);
+    }
+
+    #[test]
+    fn test_rejected_batch_and_full_queue() {
+        let mut config = ReporterConfig::new(ReceiverInfo::new("N0CALL", None));
+        config.max_queue = 2;
+        let mut reporter = SpotReporter::new(config, |_: &ReceiverInfo, _: &[Spot]| {
+            Err(UploadError::Rejected("HTTP 400 bad locator".to_string()))
+        });
+
+        for (i, call) in ["K1ABC", "DL1ABC", "G4XYZ"].iter().enumerate() {
+            reporter.add(spot(call, i as f64));
+        }
+        assert_eq!(reporter.pending(), 2);
+
+        assert!(matches!(reporter.poll(10.0), Some(Err(UploadError::Rejected(_)))));
+        assert_eq!(reporter.pending(), 0);
+        let stats = reporter.stats();
+        assert_eq!((stats.overflowed, stats.rejected), (1, 2));
+    }
+
+    #[cfg(feature = "reporter")]
+    #[test]
+    fn test_http_sink() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/spots", listener.local_addr().unwrap());
+
+        // Fake service: accepts, then throttles, then rejects
+        let server = std::thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in ["200 OK", "503 Service Unavailable", "400 Bad Request"] {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).unwrap();
+                bodies.push(String::from_utf8(body).unwrap());
+                write!(&stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+            }
+            bodies
+        });
+
+        let receiver = ReceiverInfo::new("N0CALL", Some("FN31"));
+        let spots = [spot("K1ABC", 1.0)];
+        let mut sink = HttpSpotSink::new(&url);
+        assert_eq!(sink.upload(&receiver, &spots), Ok(()));
+        assert_eq!(sink.upload(&receiver, &spots), Err(UploadError::Transient("HTTP 503".to_string())));
+        assert!(matches!(sink.upload(&receiver, &spots), Err(UploadError::Rejected(_))));
+
+        let bodies = server.join().unwrap();
+        assert_eq!(bodies[0], HttpSpotSink::body(&receiver, &spots));
+        assert!(bodies[0].starts_with(r#"{"receiver":{"callsign":"N0CALL","locator":"FN31","software":"bachmodem "#));
+        assert!(bodies[0].ends_with(r#""spots":[{"time":1,"callsign":"K1ABC","locator":"FN31","dial_hz":14074000,"snr_db":-20.0,"mode":"BACHMODEM"}]}"#));
+    }
+}
@@ -0,0 +1,434 @@
+/// Decode Retry Ladder
+///
+/// One set of receiver settings can't be both fast and thorough: strict sync
+/// thresholds without RAKE decode a strong capture in one pass, while a weak
+/// or mistuned one needs relaxed thresholds, RAKE combining, a frequency
+/// offset search and, as a last resort, a decode attempt at every strong
+/// preamble correlation peak. The ladder tries progressively heavier rungs
+/// and stops at the first that decodes, so routine signals stay cheap:
+///
+/// ```text
+/// rung  name          sync thresholds   RAKE   offsets searched      sync candidates
+///  0    fast          strict            -      0                     best peak
+///  1    relaxed       relaxed           3      0, ±2, ±4 Hz          best peak
+///  2    brute-force   none              3      0, ±1, ... ±10 Hz     4 strongest peaks
+///  +    late          -                 -      0, ±2 Hz              block positions
+///  +    blind         none              -      0                     4 strongest peaks
+/// ```
+///
+/// The late rung is for captures that started after the preamble: the
+/// symbol grid comes from a flourish or the hop structure of the data
+/// (`late_acquisition`) and every block position of the frame is tried, up
+/// to 99 decodes per failing capture. It is not in the default ladder;
+/// receivers that expect late starts add it with `with_rung`.
+/// The blind rung is for a repeated frame whose preambles are all buried:
+/// the capture is folded onto its own repetition stride first
+/// (`self_similarity`) and the stack searched like the brute-force rung.
+/// Deployments that repeat frames opt in the same way as for `late`.
+///
+/// Offsets move the whole capture (SSB mistuning, Doppler shift of the path)
+/// by shifting its analytic signal (`shift_frequency`). Single-peak rungs
+/// find the offset together with the preamble in one batched correlation
+/// (`synchronize_data_start_with_offsets`) and decode once at the
+/// interpolated offset; multi-peak rungs try every offset, nearest first.
+/// AFC (`afc`) is the demodulators' fine correction after either. The decoder
+/// settings (`llr_scale`, `bp_iterations`) and the list size come from the
+/// base config; the front end runs and the noise floor is updated once per
+/// capture, not per attempt.
+///
+/// A frame only carries a version byte, so one attempt in 256 on noise
+/// parses. The brute-force rung makes dozens, so every decode must also
+/// explain its own LLRs: the frame is re-encoded and its LLR-weighted
+/// agreement with them must reach `min_agreement`. Decodes of signal score
+/// above 0.95 down to the decoding threshold, the list decoder's best
+/// codeword in pure noise about 0.5.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::complex::shift_frequency;
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::gpu_ops::topk_separated_gpu;
+use crate::late_acquisition::{capture_llrs_late, late_start_candidates};
+use crate::modulation::{encode_bits, synchronize_data_start_with_thresholds, synchronize_signal_gpu, SyncThresholds};
+use crate::offset_sync::synchronize_data_start_with_offsets;
+use crate::receiver_pool::{capture_llrs_at, decode_llrs, frame_snr_db, CaptureLlrs, DecodeError, DecodedFrame, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+use crate::self_similarity::blind_stack;
+use crate::sync_ambiguity::{preamble_cycle_samples, resolve_sync_ambiguity};
+use crate::transmitter::CODE_N;
+use crate::wavelet::generate_bach_preamble_with_config;
+
+use bachmodem_core::frame::encode_frame_with_version;
+
+/// Codeword agreement a decode needs (`codeword_agreement`)
+pub const MIN_CODEWORD_AGREEMENT: f32 = 0.8;
+
+/// One set of settings on the ladder
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryRung {
+    pub name: &'static str,
+
+    /// Preamble detection thresholds (unused with several sync candidates)
+    pub sync: SyncThresholds,
+
+    /// RAKE fingers combined after sync, 0 = no RAKE
+    pub rake_fingers: usize,
+
+    /// Frequency offsets tried, in order (Hz)
+    pub offsets_hz: Vec<f64>,
+
+    /// Preamble correlation peaks tried per offset
+    ///
+    /// 1 = the best peak, gated by `sync`; more = brute-force acquisition over
+    /// the strongest peaks (one sweep cycle apart) without thresholds. With
+    /// `late_acquisition`, the most late-start hypotheses tried.
+    pub sync_candidates: usize,
+
+    /// Acquire without a preamble (see `late_acquisition`)
+    pub late_acquisition: bool,
+
+    /// Stack the capture on its repetition stride first (see `self_similarity`)
+    pub blind_stacking: bool,
+}
+
+impl RetryRung {
+    /// Strict thresholds, no RAKE, on tune
+    ///
+    /// A whole frame's preamble peaks at a peak-to-noise ratio of several
+    /// hundred down to -20 dB; noise peaks stay near 20.
+    pub fn fast() -> Self {
+        Self {
+            name: "fast",
+            sync: SyncThresholds { min_peak_to_noise: 50.0, ..SyncThresholds::default() },
+            rake_fingers: 0,
+            offsets_hz: vec![0.0],
+            sync_candidates: 1,
+            late_acquisition: false,
+            blind_stacking: false,
+        }
+    }
+
+    /// Default (-30 dB) thresholds, RAKE, offsets up to ±4 Hz
+    pub fn relaxed() -> Self {
+        Self {
+            name: "relaxed",
+            sync: SyncThresholds::default(),
+            rake_fingers: 3,
+            offsets_hz: offset_search(4.0, 2.0),
+            sync_candidates: 1,
+            late_acquisition: false,
+            blind_stacking: false,
+        }
+    }
+
+    /// Every strong correlation peak, RAKE, offsets up to ±10 Hz
+    pub fn brute_force() -> Self {
+        Self {
+            name: "brute-force",
+            sync: SyncThresholds { min_correlation: 0.0, min_peak_to_noise: 0.0 },
+            rake_fingers: 3,
+            offsets_hz: offset_search(10.0, 1.0),
+            sync_candidates: 4,
+            late_acquisition: false,
+            blind_stacking: false,
+        }
+    }
+
+    /// Captures that missed the preamble: grid from flourishes or the hop
+    /// structure, every block position of the frame, offsets up to ±2 Hz
+    ///
+    /// No RAKE: its fingers are found on the preamble.
+    pub fn late() -> Self {
+        Self {
+            name: "late",
+            sync: SyncThresholds::default(),
+            rake_fingers: 0,
+            offsets_hz: offset_search(2.0, 2.0),
+            // Every block of an 8-tone frame (the most blocks per frame)
+            sync_candidates: CODE_N / 8 + 1,
+            late_acquisition: true,
+            blind_stacking: false,
+        }
+    }
+
+    /// Repeated frames with every preamble buried: the capture stacked on
+    /// its self-similarity stride, then the strongest peaks of the stack
+    pub fn blind() -> Self {
+        Self {
+            name: "blind",
+            sync: SyncThresholds { min_correlation: 0.0, min_peak_to_noise: 0.0 },
+            rake_fingers: 0,
+            offsets_hz: vec![0.0],
+            sync_candidates: 4,
+            late_acquisition: false,
+            blind_stacking: true,
+        }
+    }
+
+    /// Decode attempts this rung makes at most
+    pub fn max_attempts(&self) -> usize {
+        self.offsets_hz.len() * self.sync_candidates.max(1)
+    }
+}
+
+/// Offsets 0, +step, -step, +2 step, ... out to ±`max_hz`
+pub fn offset_search(max_hz: f64, step_hz: f64) -> Vec<f64> {
+    let steps = (max_hz / step_hz).round() as usize;
+    let mut offsets = vec![0.0];
+    for k in 1..=steps {
+        offsets.push(k as f64 * step_hz);
+        offsets.push(-(k as f64) * step_hz);
+    }
+    offsets
+}
+
+/// Rungs tried in order until one decodes
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryLadder {
+    pub rungs: Vec<RetryRung>,
+
+    /// Codeword agreement a decode needs to be accepted
+    pub min_agreement: f32,
+}
+
+impl Default for RetryLadder {
+    fn default() -> Self {
+        Self {
+            rungs: vec![RetryRung::fast(), RetryRung::relaxed(), RetryRung::brute_force()],
+            min_agreement: MIN_CODEWORD_AGREEMENT,
+        }
+    }
+}
+
+impl RetryLadder {
+    /// Add `rung` at the top of the ladder (`RetryRung::late()`, `RetryRung::blind()`)
+    pub fn with_rung(mut self, rung: RetryRung) -> Self {
+        self.rungs.push(rung);
+        self
+    }
+}
+
+/// Frame decoded by the ladder, with how it got there
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryDecode {
+    pub frame: DecodedFrame,
+
+    /// Index of the rung that decoded
+    pub rung: usize,
+
+    /// Offset correction that decoded (Hz): the capture was shifted down by it
+    pub offset_hz: f64,
+
+    /// Decode attempts made, over all rungs
+    pub attempts: usize,
+}
+
+/// Run `signal` up the ladder until a rung decodes
+///
+/// Captures without a preamble (`config.use_sync == false`) are demodulated
+/// from the first sample on every attempt and skip the late and blind
+/// rungs. Returns the last frame error if
+/// any attempt got as far as the decoder, otherwise `NoSync`; decodes below
+/// the ladder's `min_agreement` are dropped like failed attempts.
+///
+/// ⚠️ **SYNC POINT**: Downloads per attempt (SNR, LLRs, decoder decisions)
+pub fn decode_with_retries<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    ladder: &RetryLadder,
+    signal: &Tensor<B, 1>,
+) -> Result<RetryDecode, DecodeError> {
+    let signal = &config.front_end.apply(device, signal);
+    state.noise_floor.update_gpu::<B>(device, signal);
+
+    let mut attempts = 0;
+    let mut error = DecodeError::NoSync;
+    for (rung_index, rung) in ladder.rungs.iter().enumerate() {
+        if (rung.late_acquisition || rung.blind_stacking) && !config.use_sync {
+            continue;
+        }
+
+        // Blind rung: the capture folded onto its own repetition stride
+        let stacked;
+        let capture = if rung.blind_stacking {
+            match blind_stack(device, signal, &config.modem) {
+                Some(stack) => {
+                    stacked = stack;
+                    &stacked
+                }
+                None => continue,
+            }
+        } else {
+            signal
+        };
+
+        let mut rung_config = config.clone();
+        rung_config.tuning.sync = rung.sync;
+        rung_config.tuning.rake_fingers = rung.rake_fingers;
+
+        // Single-peak rungs search their offsets jointly with the preamble
+        // (one batched correlation) and try the interpolated offset once;
+        // the others shift the capture to each offset and search it
+        let joint = config.use_sync && !rung.late_acquisition && rung.sync_candidates <= 1 && rung.offsets_hz.len() > 1;
+        let hypotheses: Vec<(f64, Option<usize>)> = if joint {
+            synchronize_data_start_with_offsets::<B>(device, capture, &config.modem, &rung.sync, &rung.offsets_hz)
+                .map(|(data_start, offset_hz)| (offset_hz, Some(data_start)))
+                .into_iter()
+                .collect()
+        } else {
+            rung.offsets_hz.iter().map(|&offset_hz| (offset_hz, None)).collect()
+        };
+
+        for (offset_hz, found_start) in hypotheses {
+            let shifted = shift_frequency(device, capture, -offset_hz, config.modem.sample_rate);
+            let state = &*state;
+            let slots: Box<dyn Iterator<Item = Result<CaptureLlrs<B>, DecodeError>> + '_> = if rung.late_acquisition {
+                let starts = late_start_candidates(device, &shifted, &config.modem, CODE_N);
+                Box::new(starts.into_iter().take(rung.sync_candidates)
+                    .map(|start| capture_llrs_late(device, state, &rung_config, &shifted, &start)))
+            } else {
+                let starts = if found_start.is_some() {
+                    vec![found_start]
+                } else if !config.use_sync {
+                    vec![None]
+                } else if rung.sync_candidates > 1 {
+                    acquisition_candidates(device, &shifted, &config.modem, rung.sync_candidates)
+                        .into_iter().map(Some).collect()
+                } else {
+                    synchronize_data_start_with_thresholds::<B>(device, &shifted, &config.modem, &rung.sync)
+                        .map(Some).into_iter().collect()
+                };
+                Box::new(starts.into_iter()
+                    .map(|data_start| capture_llrs_at(device, state, &rung_config, &shifted, data_start).map(|mut slot| {
+                        if let Some(snr_db) = frame_snr_db(device, &state.noise_floor, &config.modem, &shifted, data_start) {
+                            slot.snr_db = snr_db;
+                        }
+                        slot
+                    })))
+            };
+
+            for slot in slots {
+                attempts += 1;
+                let decoded = slot.and_then(|slot| {
+                    let payload = decode_llrs(device, &rung_config, &slot.llrs)?;
+                    let llrs: Vec<f32> = slot.llrs.into_data().to_vec().unwrap();
+                    Ok((DecodedFrame { payload, snr_db: slot.snr_db }, llrs))
+                });
+                match decoded {
+                    Ok((frame, llrs)) if codeword_agreement(&llrs, &frame.payload, &config.modem) < ladder.min_agreement => {}
+                    Ok((frame, _)) => return Ok(RetryDecode { frame, rung: rung_index, offset_hz, attempts }),
+                    Err(err @ DecodeError::Frame(_)) => error = err,
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+    Err(error)
+}
+
+/// LLR-weighted agreement of a decoded frame, re-encoded, with the
+/// interleaved LLRs it came from
+///
+/// Σ ±|llr| / Σ |llr|, + where the LLR sign matches the codeword bit: 1 when
+/// the codeword explains every LLR, about 0.5 for noise. The frame is
+/// re-encoded with the config's `frame_code` and `wire_version`.
+pub fn codeword_agreement(llrs: &[f32], payload: &[u8], config: &ModemConfig) -> f32 {
+    let frame = encode_frame_with_version(payload, config.interleaver_columns(), config.frame_code, config.wire_version);
+    let codeword = encode_bits(&frame);
+    let (agree, total) = llrs.iter().zip(&codeword).fold((0.0f32, 0.0f32), |(agree, total), (&llr, &bit)| {
+        let signed = if bit == 0 { llr } else { -llr };
+        (agree + signed, total + llr.abs())
+    });
+    if total > 0.0 { agree / total } else { 0.0 }
+}
+
+/// Data starts of the `count` strongest preamble correlation peaks, at least
+/// one sweep cycle apart, each with its ambiguity resolved
+///
+/// No detection thresholds: frames carry no CRC, so a candidate is
+/// accepted when its decode's `codeword_agreement` reaches the ladder's
+/// `min_agreement`.
+///
+/// ⚠️ **SYNC POINT**: Downloads the `count` peaks
+fn acquisition_candidates<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    count: usize,
+) -> Vec<usize> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    if signal.dims()[0] < preamble.dims()[0] {
+        return Vec::new();
+    }
+
+    let (correlations, _, _) = synchronize_signal_gpu(device, signal, &preamble);
+    let (power, positions) = topk_separated_gpu(&correlations.powf_scalar(2.0), count, preamble_cycle_samples(config));
+    let power: Vec<f32> = power.into_data().to_vec().unwrap();
+    let positions: Vec<i64> = positions.into_data().convert::<i64>().to_vec().unwrap();
+    let peaks = positions.into_iter()
+        .zip(power)
+        .filter(|(_, p)| p.is_finite())
+        .map(|(position, _)| position as usize);
+
+    let mut starts: Vec<usize> = Vec::with_capacity(count);
+    for peak in peaks {
+        let start = resolve_sync_ambiguity(device, signal, &preamble, peak, config);
+        if !starts.contains(&start) {
+            starts.push(start);
+        }
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transmitter::BachTransmitter;
+    use bachmodem_core::frame::FrameCode;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_mistuned_capture_climbs_to_offset_search() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig::default();
+        let ladder = RetryLadder::default();
+        assert!(ladder.rungs.iter().all(|rung| !rung.late_acquisition && !rung.blind_stacking));
+        assert_eq!(ladder.clone().with_rung(RetryRung::blind()).rungs.len(), 4);
+        let mut state = ReceiverState::<TestBackend>::default();
+        let signal = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"LADDER").unwrap();
+
+        // On tune: one attempt on the fast rung
+        let decoded = decode_with_retries(&device, &mut state, &config, &ladder, &signal).unwrap();
+        assert!(decoded.frame.payload.starts_with(b"LADDER"));
+        assert_eq!((decoded.rung, decoded.attempts), (0, 1));
+
+        // 4 Hz high: the fast rung fails, the relaxed rung's offset search finds it
+        let mistuned = shift_frequency(&device, &signal, 4.0, config.modem.sample_rate);
+        let decoded = decode_with_retries(&device, &mut state, &config, &ladder, &mistuned).unwrap();
+        assert!(decoded.frame.payload.starts_with(b"LADDER"));
+        assert_eq!(decoded.rung, 1);
+        assert!((decoded.offset_hz - 4.0).abs() < 0.5, "offset {}", decoded.offset_hz);
+        assert_eq!(decoded.attempts, 2);
+    }
+
+    #[test]
+    fn test_convolutional_chat_frames_pass_agreement() {
+        let device = Default::default();
+        let config = ReceiverPoolConfig { modem: ModemConfig::chat(), ..Default::default() };
+        let mut state = ReceiverState::<TestBackend>::default();
+        let signal = BachTransmitter::new(config.modem.clone()).build::<TestBackend>(&device, b"CHAT").unwrap();
+
+        let decoded = decode_with_retries(&device, &mut state, &config, &RetryLadder::default(), &signal).unwrap();
+        assert!(decoded.frame.payload.starts_with(b"CHAT"));
+        assert_eq!((decoded.rung, decoded.attempts), (0, 1));
+
+        // Scored against the polar codeword the same LLRs look like noise
+        let data_start = synchronize_data_start_with_thresholds(&device, &signal, &config.modem, &config.modem.sync.thresholds);
+        let slot = capture_llrs_at(&device, &state, &config, &signal, data_start).unwrap();
+        let llrs: Vec<f32> = slot.llrs.into_data().to_vec().unwrap();
+        assert!(codeword_agreement(&llrs, &decoded.frame.payload, &config.modem) > 0.95);
+        let polar = config.modem.clone().with_frame_code(FrameCode::Polar);
+        assert!(codeword_agreement(&llrs, &decoded.frame.payload, &polar) < 0.8);
+    }
+}
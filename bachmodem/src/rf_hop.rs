@@ -0,0 +1,363 @@
+/// RF-Level Slow Frequency Hopping
+///
+/// The tone hopping inside one transmission spans at most the 2.7 kHz SSB
+/// passband, so a selective fade wider than that (or a channel taken by
+/// another station) hits every repetition alike. The RF hop plan puts
+/// successive repetitions on different dial frequencies:
+/// - `RfHopPlan` maps slot i to one channel of a list, visiting every
+///   channel once per cycle (in list order, or a permutation shared through
+///   a seed)
+/// - `retunes` gives the transmitter its tuning commands, placed in the
+///   listening gap `settle` seconds before each slot
+/// - A receiver on the same epoch-aligned `TimeSlotConfig` follows the plan
+///   with `dial_at`: it retunes as soon as a slot ends, so the whole gap is
+///   available for the rig to settle
+/// - A receiver without slot timing uses `ChannelScanner`: it steps through
+///   the channel list, and the first preamble it finds fixes both the slot
+///   timing and the position in the hop cycle
+///
+/// The rig is driven through `rigctld` (Hamlib's network daemon, default
+/// port 4532) with `RigCtl`.
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use crate::repetition::TimeSlotConfig;
+use crate::modem_rng::{ModemRng, SplitMix64};
+
+/// Dial frequencies and their visiting order
+#[derive(Clone, Debug, PartialEq)]
+pub struct RfHopPlan {
+    /// USB dial frequencies (Hz)
+    pub channels: Vec<u64>,
+
+    /// Channel index per position in the hop cycle
+    order: Vec<usize>,
+}
+
+/// One tuning command of the transmitter
+#[derive(Clone, Debug, PartialEq)]
+pub struct Retune {
+    pub slot: usize,
+
+    /// Schedule time of the command (seconds, negative = before the schedule starts)
+    pub at_s: f64,
+
+    pub dial_hz: u64,
+}
+
+impl RfHopPlan {
+    /// Visit the channels in list order
+    pub fn new(channels: Vec<u64>) -> Self {
+        assert!(!channels.is_empty(), "Hop plan needs at least one channel");
+        let order = (0..channels.len()).collect();
+        Self { channels, order }
+    }
+
+    /// Visit the channels in a permutation drawn from `seed`
+    ///
+    /// Both ends derive the same order from the shared seed; neighbouring
+    /// dial frequencies (likely to fade together) rarely follow each other.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        // Fisher-Yates on a SplitMix64 stream
+        let mut rng = SplitMix64::new(seed);
+        for i in (1..self.order.len()).rev() {
+            self.order.swap(i, rng.below(i + 1));
+        }
+        self
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Channel index of a slot (the plan repeats every `num_channels` slots)
+    pub fn channel_for_slot(&self, slot: usize) -> usize {
+        self.order[slot % self.order.len()]
+    }
+
+    /// Dial frequency of a slot
+    pub fn dial_for_slot(&self, slot: usize) -> u64 {
+        self.channels[self.channel_for_slot(slot)]
+    }
+
+    /// Position in the hop cycle at which a channel is used
+    pub fn cycle_position(&self, channel: usize) -> Option<usize> {
+        self.order.iter().position(|&c| c == channel)
+    }
+
+    /// Transmitter tuning commands, `settle` seconds before each slot
+    pub fn retunes(&self, slots: &TimeSlotConfig, settle: f64) -> Vec<Retune> {
+        assert!(settle <= slots.listening_gap, "Rig settle time exceeds the listening gap");
+
+        slots.slot_starts.iter()
+            .enumerate()
+            .map(|(slot, &start)| Retune { slot, at_s: start - settle, dial_hz: self.dial_for_slot(slot) })
+            .collect()
+    }
+
+    /// Receiver dial at schedule time `t`: (slot, dial)
+    ///
+    /// During a slot the slot's channel; once it ends, the next slot's, so
+    /// the receiver retunes at the start of the gap.
+    pub fn dial_at(&self, slots: &TimeSlotConfig, t: f64) -> (usize, u64) {
+        let slot = slots.slot_starts.iter()
+            .position(|&start| t < start + slots.transmission_duration)
+            .unwrap_or(slots.num_repetitions.saturating_sub(1));
+        (slot, self.dial_for_slot(slot))
+    }
+}
+
+/// Scanner state
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScanState {
+    /// Stepping through the channel list, no slot timing yet
+    Scanning { channel: usize, next_step_s: f64 },
+
+    /// Following the plan from a detected slot
+    Locked { slot: usize, slot_start_s: f64 },
+}
+
+/// Channel acquisition for a receiver without slot timing
+///
+/// Dwells `dwell_s` on each channel in list order. Call `lock` with the
+/// start time of the first transmission detected; from then on the scanner
+/// follows the plan and retunes at the end of every slot.
+#[derive(Clone, Debug)]
+pub struct ChannelScanner {
+    plan: RfHopPlan,
+
+    /// Slot pitch (transmission + gap) and transmission length
+    pitch: f64,
+    transmission_duration: f64,
+
+    dwell_s: f64,
+    state: ScanState,
+}
+
+impl ChannelScanner {
+    /// Start scanning on the first channel at time `now_s`
+    pub fn new(plan: RfHopPlan, slots: &TimeSlotConfig, dwell_s: f64, now_s: f64) -> Self {
+        Self {
+            plan,
+            pitch: slots.transmission_duration + slots.listening_gap,
+            transmission_duration: slots.transmission_duration,
+            dwell_s,
+            state: ScanState::Scanning { channel: 0, next_step_s: now_s + dwell_s },
+        }
+    }
+
+    pub fn state(&self) -> &ScanState {
+        &self.state
+    }
+
+    /// Frequency the receiver should be on now
+    pub fn dial(&self) -> u64 {
+        match self.state {
+            ScanState::Scanning { channel, .. } => self.plan.channels[channel],
+            ScanState::Locked { slot, .. } => self.plan.dial_for_slot(slot),
+        }
+    }
+
+    /// Advance to `now_s`; returns the new dial frequency when a retune is due
+    pub fn poll(&mut self, now_s: f64) -> Option<u64> {
+        let before = self.dial();
+
+        match &mut self.state {
+            ScanState::Scanning { channel, next_step_s } => {
+                while now_s >= *next_step_s {
+                    *channel = (*channel + 1) % self.plan.num_channels();
+                    *next_step_s += self.dwell_s;
+                }
+            }
+            ScanState::Locked { slot, slot_start_s } => {
+                // Retune once the current slot's transmission is over
+                while now_s >= *slot_start_s + self.transmission_duration {
+                    *slot += 1;
+                    *slot_start_s += self.pitch;
+                }
+            }
+        }
+
+        let after = self.dial();
+        (after != before).then_some(after)
+    }
+
+    /// A transmission started at `slot_start_s` on the current channel
+    ///
+    /// The channel fixes the slot's position in the hop cycle (slot numbers
+    /// are counted from that cycle position, modulo the plan length).
+    pub fn lock(&mut self, slot_start_s: f64) {
+        if let ScanState::Scanning { channel, .. } = self.state {
+            let slot = self.plan.cycle_position(channel).expect("channel belongs to the plan");
+            self.state = ScanState::Locked { slot, slot_start_s };
+        }
+    }
+}
+
+/// rigctld client errors
+#[derive(Debug)]
+pub enum RigError {
+    Io(std::io::Error),
+
+    /// `RPRT <code>` with a negative Hamlib error code
+    Rejected { command: String, code: i32 },
+
+    /// Unexpected reply
+    Protocol(String),
+}
+
+impl fmt::Display for RigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RigError::Io(e) => write!(f, "rigctld I/O error: {}", e),
+            RigError::Rejected { command, code } => write!(f, "rigctld rejected {:?} (RPRT {})", command, code),
+            RigError::Protocol(reply) => write!(f, "unexpected rigctld reply {:?}", reply),
+        }
+    }
+}
+
+impl std::error::Error for RigError {}
+
+impl From<std::io::Error> for RigError {
+    fn from(e: std::io::Error) -> Self {
+        RigError::Io(e)
+    }
+}
+
+/// Minimal rigctld client (frequency, mode, PTT)
+pub struct RigCtl {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl RigCtl {
+    /// rigctld's default address
+    pub const DEFAULT_ADDR: &'static str = "127.0.0.1:4532";
+
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, RigError> {
+        let writer = TcpStream::connect(addr)?;
+        writer.set_read_timeout(Some(Duration::from_secs(5)))?;
+        writer.set_nodelay(true)?;
+        Ok(Self { reader: BufReader::new(writer.try_clone()?), writer })
+    }
+
+    /// Send one command, return the first reply line
+    fn command(&mut self, command: &str) -> Result<String, RigError> {
+        self.writer.write_all(format!("{}\n", command).as_bytes())?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(RigError::Protocol("connection closed".to_string()));
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// Commands that answer with `RPRT <code>`
+    fn set(&mut self, command: &str) -> Result<(), RigError> {
+        let reply = self.command(command)?;
+        let code: i32 = reply.strip_prefix("RPRT ")
+            .and_then(|c| c.trim().parse().ok())
+            .ok_or_else(|| RigError::Protocol(reply.clone()))?;
+
+        if code < 0 {
+            return Err(RigError::Rejected { command: command.to_string(), code });
+        }
+        Ok(())
+    }
+
+    pub fn set_frequency(&mut self, hz: u64) -> Result<(), RigError> {
+        self.set(&format!("F {}", hz))
+    }
+
+    pub fn frequency(&mut self) -> Result<u64, RigError> {
+        let reply = self.command("f")?;
+        if let Some(code) = reply.strip_prefix("RPRT ") {
+            return Err(RigError::Rejected { command: "f".to_string(), code: code.trim().parse().unwrap_or(-1) });
+        }
+        reply.parse().map_err(|_| RigError::Protocol(reply))
+    }
+
+    /// Mode name as Hamlib spells it ("USB", "PKTUSB", ...); passband 0 keeps the rig default
+    pub fn set_mode(&mut self, mode: &str, passband_hz: u32) -> Result<(), RigError> {
+        self.set(&format!("M {} {}", mode, passband_hz))
+    }
+
+    pub fn set_ptt(&mut self, transmit: bool) -> Result<(), RigError> {
+        self.set(&format!("T {}", transmit as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    const CHANNELS: [u64; 4] = [7_074_000, 10_136_000, 14_074_000, 18_100_000];
+
+    #[test]
+    fn test_plan_and_scanner_follow_the_same_channels() {
+        let plan = RfHopPlan::new(CHANNELS.to_vec()).with_seed(42);
+        let slots = TimeSlotConfig::new(16, 8, 10.0);
+
+        // Every channel once per cycle, repeating
+        let mut cycle: Vec<usize> = (0..4).map(|i| plan.channel_for_slot(i)).collect();
+        assert_eq!(plan.channel_for_slot(5), cycle[1]);
+        cycle.sort();
+        assert_eq!(cycle, vec![0, 1, 2, 3]);
+
+        let retunes = plan.retunes(&slots, 2.0);
+        assert_eq!(retunes[3].at_s, slots.slot_starts[3] - 2.0);
+        assert_eq!(retunes[3].dial_hz, plan.dial_for_slot(3));
+
+        // Receiver retunes right after slot 2 ends
+        let end_of_2 = slots.slot_starts[2] + slots.transmission_duration;
+        assert_eq!(plan.dial_at(&slots, end_of_2 - 0.1), (2, plan.dial_for_slot(2)));
+        assert_eq!(plan.dial_at(&slots, end_of_2 + 0.1), (3, plan.dial_for_slot(3)));
+
+        // Scanner dwells until it sits on slot 1's channel, then locks there
+        let mut scanner = ChannelScanner::new(plan.clone(), &slots, 1.0, 0.0);
+        let target = plan.dial_for_slot(1);
+        let mut t = 0.0;
+        while scanner.dial() != target {
+            t += 1.0;
+            scanner.poll(t);
+        }
+        scanner.lock(slots.slot_starts[1]);
+        assert_eq!(scanner.state(), &ScanState::Locked { slot: 1, slot_start_s: slots.slot_starts[1] });
+        assert_eq!(scanner.poll(slots.slot_starts[1] + slots.transmission_duration + 0.5), Some(plan.dial_for_slot(2)));
+    }
+
+    #[test]
+    fn test_rigctl_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // Fake rigctld: accepts frequencies, rejects PTT
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut frequency = 0u64;
+            for line in BufReader::new(stream).lines() {
+                let line = line.unwrap();
+                let reply = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                    ["F", hz] => { frequency = hz.parse().unwrap(); "RPRT 0".to_string() }
+                    ["f"] => frequency.to_string(),
+                    ["M", _, _] => "RPRT 0".to_string(),
+                    _ => "RPRT -11".to_string(),
+                };
+                writeln!(writer, "{}", reply).unwrap();
+            }
+        });
+
+        let mut rig = RigCtl::connect(addr).unwrap();
+        rig.set_mode("USB", 0).unwrap();
+        rig.set_frequency(14_074_000).unwrap();
+        assert_eq!(rig.frequency().unwrap(), 14_074_000);
+        assert!(matches!(rig.set_ptt(true), Err(RigError::Rejected { code: -11, .. })));
+
+        drop(rig);
+        server.join().unwrap();
+    }
+}
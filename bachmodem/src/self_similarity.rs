@@ -0,0 +1,238 @@
+/// Blind Repetition Stacking by Self-Similarity
+///
+/// A beacon or a repeated call sends the same frame again and again. The
+/// receiver stacks repetitions at their preambles - but a repetition whose
+/// preamble is buried never gets a slot. The repetitions are still
+/// identical to each other, so the received signal resembles itself shifted
+/// by the repetition stride, whatever the message and wherever the
+/// preambles are.
+///
+/// `detect_repetition_stride` finds that shift from the capture alone: the
+/// envelope of its band-limited analytic autocorrelation (one FFT pair on
+/// the device, noise outside the tone band removed) peaks where every
+/// repetition lines up with the next. The lag search starts at one frame
+/// (shorter lags are the hop pattern repeating inside a frame) and the peak
+/// must stand `MIN_STRIDE_SCORE` times above the median of the searched
+/// lags. The stride is interpolated to a fraction of a sample, so slots
+/// hundreds of seconds apart don't drift.
+///
+/// `stack_repetitions` cuts the capture at multiples of the stride and
+/// averages the slots after rotating each onto the others in phase (the
+/// carrier phase of a rig or path drifts between transmissions). The
+/// signal adds coherently and the noise doesn't, so K slots gain K in SNR
+/// before sync - the stacked window then goes through the ordinary
+/// receiver. `RetryRung::blind` runs this as an opt-in retry ladder rung.
+
+use burn::tensor::{Tensor, backend::Backend};
+use crate::complex::ComplexTensor;
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::gpu_ops::median_gpu;
+use crate::wavelet::FS;
+
+/// Peak-to-median ratio of the autocorrelation envelope a stride needs
+///
+/// The largest of a million lags of noise stands about 4.5 above the median.
+pub const MIN_STRIDE_SCORE: f32 = 8.0;
+
+/// Band kept around the tones (Hz): chirps, Doppler, mistuning
+const SIMILARITY_BAND_MARGIN_HZ: f64 = 50.0;
+
+/// Phase alignment passes of `stack_repetitions`
+const STACK_ALIGN_PASSES: usize = 3;
+
+/// Repetition period found in a capture
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RepetitionStride {
+    /// Stride (samples, fractional)
+    pub stride: f64,
+
+    /// Envelope peak over its median
+    pub score: f32,
+}
+
+/// Tone band of `config` with its margin (Hz)
+fn similarity_band(config: &ModemConfig) -> (f64, f64) {
+    let frequencies = config.frequencies();
+    let low = frequencies.iter().cloned().fold(f64::INFINITY, f64::min);
+    let high = frequencies.iter().cloned().fold(0.0, f64::max);
+    let margin = SIMILARITY_BAND_MARGIN_HZ + config.chirp_span_hz / 2.0;
+    ((low - margin).max(0.0), (high + margin).min(FS / 2.0))
+}
+
+/// Envelope of the analytic autocorrelation of `signal` within `band_hz`,
+/// lags 0..`max_lag`: [max_lag]
+///
+/// Zero-padded to keep the lags linear, not circular.
+///
+/// **NO SYNC POINT**
+pub fn self_similarity_gpu<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    max_lag: usize,
+    band_hz: (f64, f64),
+) -> Tensor<B, 1> {
+    let n = signal.dims()[0];
+    let fft_len = (n + max_lag).next_power_of_two();
+    let low = (band_hz.0 * fft_len as f64 / FS).floor() as usize;
+    let high = ((band_hz.1 * fft_len as f64 / FS).ceil() as usize).min(fft_len / 2);
+
+    // Positive frequencies of the band only: |R(τ)| is then the envelope
+    let mask: Vec<f32> = (0..fft_len).map(|k| if k >= low && k <= high { 1.0 } else { 0.0 }).collect();
+    let mask = Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([1, fft_len]);
+
+    let padded = Tensor::cat(vec![signal.clone(), Tensor::zeros([fft_len - n], device)], 0).reshape([1, fft_len]);
+    let power = ComplexTensor::from_real(padded).fft().norm_sqr() * mask;
+    ComplexTensor::from_real(power)
+        .ifft()
+        .abs()
+        .reshape([fft_len])
+        .slice([0..max_lag])
+}
+
+/// Repetition stride of `signal`, at least one frame of `config`; None if
+/// the capture holds less than two frames or no lag stands out
+///
+/// ⚠️ **SYNC POINT**: Downloads the peak, its neighbours and the median
+pub fn detect_repetition_stride<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<RepetitionStride> {
+    let n = signal.dims()[0];
+    let min_stride = (config.frame_duration() * FS) as usize;
+    if n < 2 * min_stride + 2 {
+        return None;
+    }
+    let max_lag = n - min_stride;
+
+    let envelope = self_similarity_gpu(device, signal, max_lag, similarity_band(config));
+    let searched = envelope.clone().slice([min_stride..max_lag]);
+    let (peak, index) = searched.clone().max_dim_with_indices(0);
+    let stats: Vec<f32> = Tensor::cat(vec![peak, median_gpu(searched)], 0).into_data().to_vec().unwrap();
+    let lag = min_stride + index.into_data().convert::<i64>().to_vec::<i64>().unwrap()[0] as usize;
+    let score = stats[0] / stats[1].max(f32::MIN_POSITIVE);
+
+    println!("  [Stride] Self-similarity peak at {} samples ({:.2} s), score {:.1}", lag, lag as f64 / FS, score);
+    if score < MIN_STRIDE_SCORE {
+        return None;
+    }
+
+    // Parabolic interpolation between the neighbouring lags
+    let mut fraction = 0.0;
+    if lag + 1 < max_lag {
+        let around: Vec<f32> = envelope.slice([lag - 1..lag + 2]).into_data().to_vec().unwrap();
+        let (left, center, right) = (around[0] as f64, around[1] as f64, around[2] as f64);
+        let curvature = left - 2.0 * center + right;
+        if curvature < 0.0 {
+            fraction = 0.5 * (left - right) / curvature;
+        }
+    }
+    Some(RepetitionStride { stride: lag as f64 + fraction, score })
+}
+
+/// Unit phasors c / |c|
+fn unit_phasors<B: Backend, const D: usize>(c: ComplexTensor<B, D>) -> ComplexTensor<B, D> {
+    let amplitude = c.clone().abs().add_scalar(1e-12);
+    c.map(|t| t / amplitude.clone())
+}
+
+/// Mean of the `window`-sample slots starting at multiples of `stride`,
+/// each rotated in phase onto the others; None with fewer than two slots
+///
+/// **NO SYNC POINT**
+pub fn stack_repetitions<B: Backend + FftBackend>(
+    signal: &Tensor<B, 1>,
+    stride: f64,
+    window: usize,
+) -> Option<Tensor<B, 1>> {
+    let n = signal.dims()[0];
+    let starts: Vec<usize> = (0..)
+        .map(|k| (k as f64 * stride).round() as usize)
+        .take_while(|&start| start + window <= n)
+        .collect();
+    if starts.len() < 2 || stride < 1.0 {
+        return None;
+    }
+    let num_slots = starts.len();
+
+    // Slots of the analytic signal: [slots, window]
+    let slots = ComplexTensor::analytic(signal.clone())
+        .map(|t| Tensor::stack::<2>(starts.iter().map(|&s| t.clone().slice([s..s + window])).collect(), 0));
+
+    // First pass against slot 0, then each slot against the sum of the others
+    let first = slots.clone().map(|t| t.slice([0..1, 0..window]));
+    let mut rotation = unit_phasors((first * slots.clone().conj()).map(|t| t.sum_dim(1)));
+    for _ in 1..STACK_ALIGN_PASSES {
+        let aligned = slots.clone() * rotation;
+        let others = aligned.clone().map(|t| t.sum_dim(0)) - aligned;
+        rotation = unit_phasors((others * slots.clone().conj()).map(|t| t.sum_dim(1)));
+    }
+
+    let (stacked, _) = (slots * rotation).map(|t| t.sum_dim(0)).into_parts();
+    Some(stacked.reshape([window]) / num_slots as f32)
+}
+
+/// `signal` folded onto its repetition stride: one stride plus one frame
+/// of stacked slots, so a whole frame lies in it wherever the slots start
+///
+/// ⚠️ **SYNC POINT**: Downloads the stride (`detect_repetition_stride`)
+pub fn blind_stack<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+) -> Option<Tensor<B, 1>> {
+    let stride = detect_repetition_stride(device, signal, config)?;
+    let frame_len = (config.frame_duration() * FS).ceil() as usize;
+    let n = signal.dims()[0];
+    let window = (stride.stride.round() as usize + frame_len).min(n - stride.stride.ceil() as usize);
+    println!("  [Stride] Stacking {} slots of {:.2} s", ((n - window) as f64 / stride.stride) as usize + 1, window as f64 / FS);
+    stack_repetitions(signal, stride.stride, window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::{demodulate_fhdpsk_ex_with_config, modulate_fhdpsk_with_config};
+    use crate::TestBackend;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_repeated_frames_stack_blindly() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let data = b"Again and again";
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, data, true, &config);
+        let frame_len = tx.dims()[0];
+        let (re, im) = ComplexTensor::analytic(tx).into_parts();
+        let (re, im): (Vec<f32>, Vec<f32>) = (re.into_data().to_vec().unwrap(), im.into_data().to_vec().unwrap());
+
+        // Six repetitions 1.37 s apart, each at its own carrier phase
+        let stride = frame_len + 10960;
+        let lead_in = 23456;
+        let mut samples = vec![0.0f32; lead_in + 6 * stride];
+        for k in 0..6 {
+            let phase = 2.0 * PI * 0.37 * k as f64;
+            let (cos, sin) = (phase.cos() as f32, phase.sin() as f32);
+            let start = lead_in + k * stride;
+            for i in 0..frame_len {
+                samples[start + i] += re[i] * cos - im[i] * sin;
+            }
+        }
+        let mut rng = SplitMix64::new(21);
+        let signal = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device)
+            + gaussian_noise::<TestBackend, _>(&device, samples.len(), 0.3, &mut rng);
+
+        let found = detect_repetition_stride(&device, &signal, &config).expect("stride not found");
+        assert!((found.stride - stride as f64).abs() < 0.5, "stride {} found {:?}", stride, found);
+
+        let stacked = blind_stack(&device, &signal, &config).unwrap();
+        let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &stacked, true, &config);
+        assert_eq!(&decoded[..data.len()], data);
+
+        // Noise alone has no stride
+        let noise = gaussian_noise::<TestBackend, _>(&device, samples.len(), 0.3, &mut rng);
+        assert_eq!(detect_repetition_stride(&device, &noise, &config), None);
+    }
+}
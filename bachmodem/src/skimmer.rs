@@ -0,0 +1,304 @@
+/// Multi-Station Skimmer
+///
+/// `synchronize_signal_with_config` locks onto the single strongest preamble
+/// of a capture. A skimmer listening to a busy channel wants every station in
+/// it, so `find_preamble_peaks` keeps all correlation peaks that stand out
+/// from the noise:
+/// - the preamble correlation is computed once and downloaded
+/// - peaks are scored against the median of the squared correlation (taken
+///   on the device), which other stations' frames barely move (unlike the
+///   mean)
+/// - peaks are taken strongest first; everything within one preamble length
+///   of a taken peak is suppressed, which also removes the one-cycle
+///   sidelobes of the repeated sweep (see `sync_ambiguity`)
+///
+/// `find_all_preambles` is the same search for callers that decode the
+/// peaks themselves (a monitor working through a WAV): the peaks that pass
+/// a caller-chosen score and `config.sync`, separated by a caller-chosen
+/// spacing, with the quality metrics of a `SyncResult`, in time order. With
+/// `ModemConfig::two_stage_sync` the peaks are picked on the decimated
+/// baseband and refined at full rate, as `measure_sync` does for one.
+///
+/// `skim` decodes each peak on its own: the sweep-cycle ambiguity is
+/// resolved on the full capture, then the receive chain of `decode_capture`
+/// runs from that data start without searching again, so a louder station
+/// later in the capture cannot steal the sync. Data symbols correlate with
+/// the preamble too: peaks inside a decoded frame are skipped, others come
+/// back as failed decodes.
+
+use burn::tensor::{Tensor, ElementConversion, backend::Backend};
+use crate::coarse_sync::{complex_correlation_power, decimate_to_baseband, DecimationPlan, FINE_SEARCH_STEPS};
+use crate::fft_correlation::{fft_cross_correlation, FftBackend};
+use crate::modulation::{measure_sync_full_rate, SyncMetrics, SyncResult};
+use crate::gpu_ops::{power_percentile_gpu, topk_separated_gpu};
+use crate::receiver_pool::{decode_capture, DecodeError, DecodedFrame, ReceiverPoolConfig};
+use crate::receiver_state::ReceiverState;
+use crate::sync_ambiguity::resolve_sync_ambiguity;
+use crate::wavelet::generate_bach_preamble_with_config;
+use crate::config::ModemConfig;
+use crate::transmitter::BachTransmitter;
+
+/// Smallest peak score (squared correlation over its median) kept as a station
+///
+/// Noise alone peaks around 50 in a 100 s capture; a -10 dB station scores
+/// in the thousands.
+pub const MULTI_SYNC_MIN_SCORE: f32 = 100.0;
+
+/// One preamble candidate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyncPeak {
+    /// Preamble start in the capture (samples)
+    pub position: usize,
+
+    /// Squared correlation over its median
+    pub score: f32,
+}
+
+/// Preamble peaks of all stations in `signal`, strongest first
+///
+/// ⚠️ **SYNC POINT**: Downloads the median and the `max_peaks` peaks
+pub fn find_preamble_peaks<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    max_peaks: usize,
+) -> Vec<SyncPeak> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let preamble_len = preamble.dims()[0];
+    if signal.dims()[0] < preamble_len {
+        return Vec::new();
+    }
+
+    // Non-coherent: carrier phase unknown after the SSB chain
+    let power = fft_cross_correlation(device, signal, &preamble).powf_scalar(2.0);
+    power_peaks(&power, max_peaks, preamble_len, MULTI_SYNC_MIN_SCORE).peaks
+}
+
+/// Peaks of a squared correlation with the statistics they were scored on
+struct PowerPeaks {
+    /// Strongest first
+    peaks: Vec<SyncPeak>,
+    median: f32,
+    mean: f32,
+}
+
+/// Up to `max_peaks` peaks of `power` at least `separation` apart that score
+/// `min_score` against its median
+///
+/// ⚠️ **SYNC POINT**: Downloads the median, the mean and the peaks
+fn power_peaks<B: Backend>(power: &Tensor<B, 1>, max_peaks: usize, separation: usize, min_score: f32) -> PowerPeaks {
+    let stats: Vec<f32> = Tensor::cat(vec![power_percentile_gpu(power.clone(), 0.5), power.clone().mean()], 0)
+        .into_data().to_vec().unwrap();
+    let (median, mean) = (stats[0] + 1e-20, stats[1] + 1e-10);
+
+    let (power, positions) = topk_separated_gpu(power, max_peaks, separation);
+    let power: Vec<f32> = power.into_data().to_vec().unwrap();
+    let positions: Vec<i64> = positions.into_data().convert::<i64>().to_vec().unwrap();
+
+    // Strongest first: stop at the first peak below the score
+    let peaks = positions.into_iter()
+        .zip(power)
+        .map(|(position, power)| SyncPeak { position: position as usize, score: power / median })
+        .take_while(|peak| peak.score >= min_score)
+        .collect();
+    PowerPeaks { peaks, median, mean }
+}
+
+/// Every preamble in `signal` above threshold, in time order
+///
+/// A peak is kept if it scores at least `min_score` against the median of
+/// the squared correlation (`MULTI_SYNC_MIN_SCORE` is the skimmer's) and
+/// passes `config.sync.thresholds`, with the correlation and peak-to-noise
+/// `measure_sync` reports for a single peak; of peaks closer than
+/// `min_spacing` samples (None = one preamble length, which also drops the
+/// sweep-cycle sidelobes) only the strongest is kept. Starts are searched
+/// within `config.sync.search_window`, on the decimated baseband first with
+/// `config.two_stage_sync`.
+///
+/// Peaks are picked on the device with `topk_separated_gpu`, one pass per
+/// `min_spacing` of signal.
+///
+/// ⚠️ **SYNC POINT**: Downloads the peaks, then measures each one
+pub fn find_all_preambles<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    min_score: f32,
+    min_spacing: Option<usize>,
+) -> Vec<SyncResult> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    let preamble_len = preamble.dims()[0];
+    let signal = match config.sync.search_window {
+        Some(window) => signal.clone().slice([0..(window + preamble_len).min(signal.dims()[0])]),
+        None => signal.clone(),
+    };
+    let signal_len = signal.dims()[0];
+    if signal_len < preamble_len {
+        return Vec::new();
+    }
+    let min_spacing = min_spacing.unwrap_or(preamble_len).max(1);
+    let max_peaks = (signal_len - preamble_len) / min_spacing + 1;
+
+    let plan = if config.two_stage_sync { DecimationPlan::for_config(config) } else { None };
+    let candidates: Vec<SyncMetrics> = match plan {
+        Some(plan) => {
+            // Peaks of the baseband envelope, each refined at full rate
+            let baseband = decimate_to_baseband(device, &signal, &plan);
+            let reference = decimate_to_baseband(device, &preamble, &plan);
+            if baseband.dims()[0] < reference.dims()[0] {
+                return Vec::new();
+            }
+            let power = complex_correlation_power(device, baseband, reference);
+            let coarse = power_peaks(&power, max_peaks, (min_spacing / plan.factor).max(1), min_score);
+
+            let search = FINE_SEARCH_STEPS * plan.factor;
+            coarse.peaks.iter()
+                .filter_map(|peak| {
+                    let position = peak.position * plan.factor;
+                    let start = position.saturating_sub(search);
+                    let end = (position + search + preamble_len).min(signal_len);
+                    let fine = measure_sync_full_rate::<B>(device, &signal.clone().slice([start..end]), config)?;
+                    // Envelope noise is in-phase plus quadrature (see `coarse_sync`)
+                    Some(SyncMetrics {
+                        position: start + fine.position,
+                        correlation: fine.correlation,
+                        peak_to_noise: 2.0 * peak.score * coarse.median / coarse.mean,
+                    })
+                })
+                .collect()
+        }
+        None => {
+            // Non-coherent: carrier phase unknown after the SSB chain
+            let power = fft_cross_correlation(device, &signal, &preamble).powf_scalar(2.0);
+            let full = power_peaks(&power, max_peaks, min_spacing, min_score);
+            let preamble_energy: f32 = preamble.powf_scalar(2.0).sum().into_scalar().elem();
+            full.peaks.iter()
+                .map(|peak| {
+                    let power = peak.score * full.median;
+                    SyncMetrics {
+                        position: peak.position,
+                        correlation: (power / preamble_energy).sqrt(),
+                        peak_to_noise: power / full.mean,
+                    }
+                })
+                .collect()
+        }
+    };
+
+    let mut kept: Vec<SyncMetrics> = candidates.into_iter()
+        .filter(|metrics| config.sync.thresholds.accepts(metrics))
+        .collect();
+    kept.sort_by_key(|metrics| metrics.position);
+    kept.iter().map(|metrics| SyncResult::measure(device, &signal, metrics, config)).collect()
+}
+
+/// Decode outcome of one preamble peak
+#[derive(Clone, Debug)]
+pub struct SkimmedFrame {
+    pub peak: SyncPeak,
+
+    /// First data sample after ambiguity resolution
+    pub data_start: usize,
+    pub frame: Result<DecodedFrame, DecodeError>,
+}
+
+/// Find and decode every station in a capture, in time order
+///
+/// Peaks inside a frame that already decoded are that frame's data symbols
+/// and are skipped.
+///
+/// ⚠️ **SYNC POINT**: Runs the full receive chain once per peak
+pub fn skim<B: Backend + FftBackend>(
+    device: &B::Device,
+    state: &mut ReceiverState<B>,
+    config: &ReceiverPoolConfig,
+    signal: &Tensor<B, 1>,
+    max_stations: usize,
+) -> Vec<SkimmedFrame> {
+    let preamble = generate_bach_preamble_with_config::<B>(device, &config.modem);
+    let signal_len = signal.dims()[0];
+
+    // Each frame is demodulated from its own data start
+    let unsynced = ReceiverPoolConfig { use_sync: false, ..config.clone() };
+
+    let mut peaks = find_preamble_peaks::<B>(device, signal, &config.modem, max_stations);
+    peaks.sort_by_key(|peak| peak.position);
+
+    let mut frame_len = None;
+    let mut busy_until = 0;
+    let mut frames = Vec::new();
+    for peak in peaks {
+        if peak.position < busy_until {
+            continue;
+        }
+
+        let data_start = resolve_sync_ambiguity(device, signal, &preamble, peak.position, &config.modem);
+        let frame = if data_start < signal_len {
+            decode_capture(device, state, &unsynced, &signal.clone().slice([data_start..signal_len]))
+        } else {
+            Err(DecodeError::NoSync)
+        };
+
+        if frame.is_ok() {
+            let len = *frame_len.get_or_insert_with(|| transmission_samples::<B>(device, config));
+            busy_until = data_start.saturating_sub(preamble.dims()[0]) + len;
+        }
+        frames.push(SkimmedFrame { peak, data_start, frame });
+    }
+    frames
+}
+
+/// Length of one transmission, preamble to postamble (samples)
+fn transmission_samples<B: Backend>(device: &B::Device, config: &ReceiverPoolConfig) -> usize {
+    BachTransmitter::new(config.modem.clone())
+        .build::<B>(device, b"")
+        .unwrap()
+        .dims()[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_all_preambles_of_two_stations() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let mut rng = SplitMix64::new(3);
+        let a = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Station A", true, &config);
+        let b = modulate_fhdpsk_with_config::<TestBackend>(&device, b"Station B", true, &config).mul_scalar(0.3);
+
+        // A, a gap, then a weaker B
+        let (lead, gap) = (4000, 30000);
+        let starts = [lead, lead + a.dims()[0] + gap];
+        let clean = Tensor::cat(vec![
+            Tensor::zeros([lead], &device), a, Tensor::zeros([gap], &device), b, Tensor::zeros([lead], &device),
+        ], 0);
+        let rx = clean.clone() + gaussian_noise(&device, clean.dims()[0], 0.05, &mut rng);
+
+        // Data symbols correlate with the preamble too: compare preamble starts only
+        let found = find_all_preambles::<TestBackend>(&device, &rx, &config, MULTI_SYNC_MIN_SCORE, None);
+        let note = config.preamble_note_samples();
+        for start in starts {
+            assert!(found.iter().any(|sync| sync.metrics.position.abs_diff(start) < note / 2), "{:?}", found);
+        }
+        assert!(found.windows(2).all(|w| w[1].metrics.position - w[0].metrics.position >= generate_bach_preamble_with_config::<TestBackend>(&device, &config).dims()[0]));
+        assert!(found.windows(2).all(|w| w[0].metrics.position < w[1].metrics.position));
+
+        // A window ending before B keeps only A
+        let early = config.clone().with_sync(crate::modulation::SyncConfig::default().with_search_window(starts[1] - 10 * note));
+        let found = find_all_preambles::<TestBackend>(&device, &rx, &early, MULTI_SYNC_MIN_SCORE, None);
+        assert!(found.iter().all(|sync| sync.metrics.position < starts[1]));
+        assert!(found.iter().any(|sync| sync.metrics.position.abs_diff(starts[0]) < note / 2));
+
+        // Two-stage search lands on the same preambles
+        let two_stage = config.clone().with_two_stage_sync(true);
+        let coarse = find_all_preambles::<TestBackend>(&device, &rx, &two_stage, MULTI_SYNC_MIN_SCORE, None);
+        for start in starts {
+            assert!(coarse.iter().any(|sync| sync.metrics.position.abs_diff(start) < note / 2), "{:?}", coarse);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+/// Randomized Slot Jitter
+///
+/// Stations running the same epoch-aligned `TimeSlotConfig` start every slot
+/// together, so two stations that collide once collide in every repetition
+/// and no amount of combining recovers either message. Jitter delays each
+/// slot by a pseudo-random offset in [0, max_jitter):
+/// - Offsets come from a hash of the callsign and the slot index, so a
+///   receiver that knows the sender can predict every slot start exactly
+/// - The slot pitch grows by `max_jitter`, so the listening gap never shrinks
+/// - Receivers that don't know the sender widen their sync search by
+///   `max_jitter` after each nominal slot start (`search_window`);
+///   `decode_slot` cuts that window and syncs within it
+///
+/// Simulations without real callsigns draw the seed from a `ModemRng`
+/// (`from_rng`, e.g. the `RngStream::Jitter` stream of a `SimSeed`).
+///
+/// `simulate_slot_collisions` estimates how often copies and whole messages
+/// are lost when several stations share one schedule.
+
+use burn::tensor::{Tensor, backend::Backend};
+use std::ops::Range;
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modem_rng::{ModemRng, SplitMix64};
+use crate::modulation::demodulate_fhdpsk_ex_with_config;
+use crate::repetition::TimeSlotConfig;
+
+/// Per-station slot jitter
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlotJitter {
+    /// Largest slot delay (seconds)
+    pub max_jitter: f64,
+
+    /// Hash of the station callsign
+    seed: u64,
+}
+
+impl SlotJitter {
+    /// Jitter derived from a callsign (case-insensitive)
+    pub fn from_callsign(callsign: &str, max_jitter: f64) -> Self {
+        assert!(max_jitter >= 0.0, "Jitter bound must be non-negative");
+
+        // FNV-1a: stable across platforms and releases
+        let seed = callsign.to_uppercase().bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+        Self { max_jitter, seed }
+    }
+
+    /// Jitter with a seed drawn from `rng` (simulated stations)
+    pub fn from_rng<R: ModemRng + ?Sized>(rng: &mut R, max_jitter: f64) -> Self {
+        assert!(max_jitter >= 0.0, "Jitter bound must be non-negative");
+        Self { max_jitter, seed: rng.seed() }
+    }
+
+    /// Delay of one slot (seconds, in [0, max_jitter))
+    pub fn offset(&self, slot_idx: usize) -> f64 {
+        SplitMix64::new(self.seed ^ (slot_idx as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)).uniform() * self.max_jitter
+    }
+
+    /// Distance between nominal slot starts once jitter is allowed for
+    pub fn slot_pitch(&self, config: &TimeSlotConfig) -> f64 {
+        config.transmission_duration + config.listening_gap + self.max_jitter
+    }
+
+    /// This station's schedule: widened pitch plus per-slot delay
+    pub fn apply(&self, config: &TimeSlotConfig) -> TimeSlotConfig {
+        let pitch = self.slot_pitch(config);
+
+        TimeSlotConfig {
+            slot_starts: (0..config.num_repetitions)
+                .map(|i| i as f64 * pitch + self.offset(i))
+                .collect(),
+            ..config.clone()
+        }
+    }
+
+    /// Earliest and latest start of a slot for a receiver that doesn't know
+    /// the sender's callsign (same for every station)
+    pub fn search_window(&self, config: &TimeSlotConfig, slot_idx: usize) -> (f64, f64) {
+        let nominal = slot_idx as f64 * self.slot_pitch(config);
+        (nominal, nominal + self.max_jitter)
+    }
+
+    /// Samples to cut for one slot, and `modem` with the schedule's flourish
+    /// interval and its preamble search limited to the jitter window
+    ///
+    /// The cut runs from the earliest start to the latest start plus the
+    /// transmission; the preamble can only start in its first `max_jitter`.
+    pub fn slot_capture(&self, config: &TimeSlotConfig, slot_idx: usize, modem: &ModemConfig) -> (Range<usize>, ModemConfig) {
+        let (earliest, latest) = self.search_window(config, slot_idx);
+        let start = (earliest * modem.sample_rate) as usize;
+        let end = ((latest + config.transmission_duration) * modem.sample_rate).ceil() as usize;
+
+        // Slot starts round down to a sample, so the last one is the ceiling
+        let window = (self.max_jitter * modem.sample_rate).ceil() as usize + 1;
+        let slot_modem = modem.clone()
+            .with_flourish_interval(config.flourish_interval)
+            .with_sync(modem.sync.with_search_window(window));
+        (start..end, slot_modem)
+    }
+
+    /// Decode one slot of a jittered schedule without knowing the sender
+    ///
+    /// `capture` starts at the schedule origin; a slot cut short by the end
+    /// of the capture decodes from what is there.
+    pub fn decode_slot<B: Backend + FftBackend>(
+        &self,
+        device: &B::Device,
+        capture: &Tensor<B, 1>,
+        config: &TimeSlotConfig,
+        slot_idx: usize,
+        modem: &ModemConfig,
+    ) -> Vec<u8> {
+        let (range, slot_modem) = self.slot_capture(config, slot_idx, modem);
+        let len = capture.dims()[0];
+        if range.start >= len {
+            return Vec::new();
+        }
+
+        let slot = capture.clone().slice([range.start..range.end.min(len)]);
+        demodulate_fhdpsk_ex_with_config::<B>(device, &slot, true, &slot_modem)
+    }
+}
+
+/// Collision statistics of a shared schedule
+#[derive(Clone, Debug)]
+pub struct CollisionStats {
+    /// Fraction of slot copies overlapping another station's copy
+    pub copy_collision_rate: f64,
+
+    /// Fraction of messages with every copy collided
+    pub message_loss_rate: f64,
+}
+
+/// Estimate collision rates for `num_stations` stations on one schedule
+///
+/// Two copies collide when their starts are closer than `guard` seconds
+/// (e.g. the preamble length: the receiver cannot sync on either). Callsigns
+/// are synthetic and differ per trial.
+pub fn simulate_slot_collisions(
+    config: &TimeSlotConfig,
+    num_stations: usize,
+    max_jitter: f64,
+    guard: f64,
+    trials: usize,
+) -> CollisionStats {
+    let mut collided_copies = 0;
+    let mut lost_messages = 0;
+
+    for trial in 0..trials {
+        let schedules: Vec<TimeSlotConfig> = (0..num_stations)
+            .map(|s| SlotJitter::from_callsign(&format!("T{}S{}", trial, s), max_jitter).apply(config))
+            .collect();
+
+        for (s, schedule) in schedules.iter().enumerate() {
+            // Copies of neighbouring slots are at least a full transmission plus gap apart
+            let collided = (0..config.num_repetitions)
+                .filter(|&i| {
+                    schedules.iter().enumerate()
+                        .any(|(o, other)| o != s && (other.slot_starts[i] - schedule.slot_starts[i]).abs() < guard)
+                })
+                .count();
+
+            collided_copies += collided;
+            if collided == config.num_repetitions {
+                lost_messages += 1;
+            }
+        }
+    }
+
+    let num_messages = (trials * num_stations) as f64;
+    CollisionStats {
+        copy_collision_rate: collided_copies as f64 / (num_messages * config.num_repetitions as f64),
+        message_loss_rate: lost_messages as f64 / num_messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repetition::generate_repetition_transmission;
+    use crate::wavelet::FS;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_jitter_bounds_and_gaps() {
+        let config = TimeSlotConfig::new(13, 8, 5.0);
+        let jitter = SlotJitter::from_callsign("dl1abc", 4.0);
+        assert_eq!(jitter, SlotJitter::from_callsign("DL1ABC", 4.0));
+
+        let jittered = jitter.apply(&config);
+        for (i, &start) in jittered.slot_starts.iter().enumerate() {
+            let (earliest, latest) = jitter.search_window(&config, i);
+            assert!(start >= earliest && start < latest);
+        }
+
+        // Listening gap never shrinks
+        for pair in jittered.slot_starts.windows(2) {
+            assert!(pair[1] - pair[0] >= config.transmission_duration + config.listening_gap);
+        }
+
+        // Other callsigns land elsewhere
+        let other = SlotJitter::from_callsign("W1AW", 4.0).apply(&config);
+        assert_ne!(jittered.slot_starts, other.slot_starts);
+
+        // Simulated stations replay from the scenario seed
+        let drawn = SlotJitter::from_rng(&mut SplitMix64::new(3), 4.0);
+        assert_eq!(drawn, SlotJitter::from_rng(&mut SplitMix64::new(3), 4.0));
+    }
+
+    #[test]
+    fn test_jitter_breaks_systematic_collisions() {
+        let config = TimeSlotConfig::new(13, 5, 5.0);
+
+        // Epoch-aligned: every copy of every station collides
+        let aligned = simulate_slot_collisions(&config, 3, 0.0, 3.2, 50);
+        assert_eq!(aligned.copy_collision_rate, 1.0);
+        assert_eq!(aligned.message_loss_rate, 1.0);
+
+        let jittered = simulate_slot_collisions(&config, 3, 20.0, 3.2, 200);
+        assert!(jittered.copy_collision_rate < 0.7, "{:?}", jittered);
+        assert!(jittered.message_loss_rate < 0.2, "{:?}", jittered);
+    }
+
+    #[test]
+    fn test_receiver_finds_jittered_slots() {
+        let device = Default::default();
+        let message = b"Jittered slot";
+        let config = TimeSlotConfig::new(message.len(), 3, 2.0);
+        let sender = SlotJitter::from_callsign("DL1ABC", 3.0).apply(&config);
+        let signal = generate_repetition_transmission::<TestBackend>(&device, message, &sender);
+
+        // The receiver only knows the jitter bound, not the callsign
+        let receiver = SlotJitter::from_callsign("W1AW", 3.0);
+        let modem = ModemConfig::default();
+        for i in 0..config.num_repetitions {
+            let (range, slot_modem) = receiver.slot_capture(&config, i, &modem);
+            assert!(range.start <= (sender.slot_starts[i] * FS) as usize);
+            assert!(range.start + slot_modem.sync.search_window.unwrap() > (sender.slot_starts[i] * FS) as usize);
+
+            let decoded = receiver.decode_slot::<TestBackend>(&device, &signal, &config, i, &modem);
+            assert_eq!(&decoded[..message.len()], message, "slot {}", i);
+        }
+    }
+}
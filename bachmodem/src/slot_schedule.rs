@@ -0,0 +1,316 @@
+/// In-Band Repetition Schedule
+///
+/// A receiver can only target the slots of a repeated message if it knows
+/// the `TimeSlotConfig` the sender runs - so far agreed out of band. With a
+/// schedule header the slots announce it themselves: every slot starts its
+/// message with `SCHEDULE_HEADER_LEN` bytes
+///
+/// ```text
+/// [repetitions: u8][flourish interval: u8][listening gap: u16, 0.1 s, big endian][CRC-8]
+/// ```
+///
+/// (the CRC-8 covers the four bytes before it, as in the owner header of
+/// shared slots), so a receiver that decodes any single slot learns how many copies there
+/// are and - with the message length it just decoded - how long each one
+/// is. The header is the same in every slot, so copies still vote and stack
+/// byte for byte, header included.
+///
+/// Which slot a receiver caught follows from timing: a signalled schedule
+/// starts on a multiple of its cycle (repetitions × slot pitch) of the
+/// shared clock, like every epoch-aligned `TimeSlotConfig`, so the start
+/// time of a slot gives its index (`ScheduleHeader::slot_index_at`) and
+/// with it where every remaining slot starts (`remaining_slot_starts`).
+/// The checksum and the range checks reject damaged headers and ones that
+/// can't describe a schedule; combine copies before trusting a header from
+/// a weak one. Slot lengths follow the sender's `ModemConfig`
+/// (`schedule_with_config`); the plain methods assume the default one. Jittered schedules (see
+/// `slot_jitter`) still need the sender's callsign to place each slot
+/// within its window.
+
+use std::fmt;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::config::ModemConfig;
+use crate::modulation::modulate_fhdpsk_with_config;
+use crate::polar::crc8;
+use crate::repetition::{TimeSlotConfig, SLOT_FLOURISH_INTERVAL};
+
+/// Schedule header bytes in front of every slot's message
+pub const SCHEDULE_HEADER_LEN: usize = 5;
+
+/// Listening gap resolution of the header (seconds)
+pub const SCHEDULE_GAP_STEP: f64 = 0.1;
+
+/// Why a schedule header was rejected
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleError {
+    /// Fewer bytes than a header
+    Short { len: usize },
+
+    /// CRC-8 mismatch: the header was damaged
+    Checksum,
+
+    /// Header fields that can't describe a schedule
+    Invalid { repetitions: u8 },
+
+    /// Schedule whose fields don't fit a header
+    Unrepresentable { repetitions: usize, flourish_interval: usize, listening_gap: f64 },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Short { len } => write!(f, "got {} bytes, a schedule header needs {}", len, SCHEDULE_HEADER_LEN),
+            ScheduleError::Checksum => write!(f, "schedule header checksum mismatch"),
+            ScheduleError::Invalid { repetitions } => write!(f, "{} repetitions is not a schedule", repetitions),
+            ScheduleError::Unrepresentable { repetitions, flourish_interval, listening_gap } => write!(
+                f,
+                "{} repetitions, flourish interval {} and a {} s gap don't fit a schedule header",
+                repetitions, flourish_interval, listening_gap,
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+/// Repetition schedule as announced by every slot
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduleHeader {
+    /// Total number of repetitions
+    pub repetitions: u8,
+
+    /// Data symbols between flourishes (0 = none)
+    pub flourish_interval: u8,
+
+    /// Listening gap between transmissions (units of `SCHEDULE_GAP_STEP`)
+    pub gap_steps: u16,
+}
+
+impl ScheduleHeader {
+    /// Header announcing `config`
+    pub fn for_schedule(config: &TimeSlotConfig) -> Result<Self, ScheduleError> {
+        let gap_steps = (config.listening_gap / SCHEDULE_GAP_STEP).round();
+        let unrepresentable = ScheduleError::Unrepresentable {
+            repetitions: config.num_repetitions,
+            flourish_interval: config.flourish_interval,
+            listening_gap: config.listening_gap,
+        };
+        let repetitions = u8::try_from(config.num_repetitions).ok().filter(|&r| r > 0).ok_or(unrepresentable.clone())?;
+        let flourish_interval = u8::try_from(config.flourish_interval).map_err(|_| unrepresentable.clone())?;
+        if !(0.0..=u16::MAX as f64).contains(&gap_steps) {
+            return Err(unrepresentable);
+        }
+        Ok(Self { repetitions, flourish_interval, gap_steps: gap_steps as u16 })
+    }
+
+    /// Listening gap (seconds)
+    pub fn listening_gap(&self) -> f64 {
+        self.gap_steps as f64 * SCHEDULE_GAP_STEP
+    }
+
+    pub fn encode(&self) -> [u8; SCHEDULE_HEADER_LEN] {
+        let [gap_hi, gap_lo] = self.gap_steps.to_be_bytes();
+        let fields = [self.repetitions, self.flourish_interval, gap_hi, gap_lo];
+        [fields[0], fields[1], fields[2], fields[3], crc8(&fields)]
+    }
+
+    /// Header at the start of `bytes`
+    pub fn parse(bytes: &[u8]) -> Result<Self, ScheduleError> {
+        if bytes.len() < SCHEDULE_HEADER_LEN {
+            return Err(ScheduleError::Short { len: bytes.len() });
+        }
+        if crc8(&bytes[..SCHEDULE_HEADER_LEN - 1]) != bytes[SCHEDULE_HEADER_LEN - 1] {
+            return Err(ScheduleError::Checksum);
+        }
+        if bytes[0] == 0 {
+            return Err(ScheduleError::Invalid { repetitions: bytes[0] });
+        }
+        Ok(Self { repetitions: bytes[0], flourish_interval: bytes[1], gap_steps: u16::from_be_bytes([bytes[2], bytes[3]]) })
+    }
+
+    /// The sender's whole schedule for a `message_bytes`-byte message (not
+    /// counting the header), slot 0 at time 0
+    pub fn schedule(&self, message_bytes: usize) -> TimeSlotConfig {
+        self.schedule_with_config(message_bytes, &ModemConfig::default())
+    }
+
+    /// `schedule` of a sender running `modem`
+    pub fn schedule_with_config(&self, message_bytes: usize, modem: &ModemConfig) -> TimeSlotConfig {
+        TimeSlotConfig::with_config(
+            message_bytes + SCHEDULE_HEADER_LEN,
+            self.repetitions as usize,
+            self.listening_gap(),
+            self.flourish_interval as usize,
+            modem,
+        )
+    }
+
+    /// Index of the slot starting at `slot_start` (seconds on the shared
+    /// clock the schedule is aligned to)
+    pub fn slot_index_at(&self, slot_start: f64, message_bytes: usize) -> usize {
+        self.slot_index_at_with_config(slot_start, message_bytes, &ModemConfig::default())
+    }
+
+    /// `slot_index_at` for a sender running `modem`
+    pub fn slot_index_at_with_config(&self, slot_start: f64, message_bytes: usize, modem: &ModemConfig) -> usize {
+        let schedule = self.schedule_with_config(message_bytes, modem);
+        let pitch = schedule.transmission_duration + schedule.listening_gap;
+        let cycle = pitch * schedule.num_repetitions as f64;
+        ((slot_start.rem_euclid(cycle) / pitch).round() as usize) % schedule.num_repetitions
+    }
+
+    /// Start times (seconds) of the slots after the one starting at
+    /// `slot_start`
+    pub fn remaining_slot_starts(&self, slot_start: f64, message_bytes: usize) -> Vec<f64> {
+        self.remaining_slot_starts_with_config(slot_start, message_bytes, &ModemConfig::default())
+    }
+
+    /// `remaining_slot_starts` for a sender running `modem`
+    pub fn remaining_slot_starts_with_config(&self, slot_start: f64, message_bytes: usize, modem: &ModemConfig) -> Vec<f64> {
+        let schedule = self.schedule_with_config(message_bytes, modem);
+        let slot_index = self.slot_index_at_with_config(slot_start, message_bytes, modem);
+        let origin = slot_start - schedule.slot_starts[slot_index];
+        schedule.slot_starts[slot_index + 1..].iter().map(|&start| origin + start).collect()
+    }
+}
+
+impl TimeSlotConfig {
+    /// Schedule whose slots carry a `ScheduleHeader` in front of
+    /// `message_bytes` bytes; the gap is rounded to `SCHEDULE_GAP_STEP`
+    pub fn signalled(message_bytes: usize, num_repetitions: usize, listening_gap: f64) -> Self {
+        Self::signalled_with_config(message_bytes, num_repetitions, listening_gap, &ModemConfig::default())
+    }
+
+    /// `signalled` with slots sized to `modem` frames
+    pub fn signalled_with_config(message_bytes: usize, num_repetitions: usize, listening_gap: f64, modem: &ModemConfig) -> Self {
+        let gap = (listening_gap / SCHEDULE_GAP_STEP).round() * SCHEDULE_GAP_STEP;
+        Self::with_config(message_bytes + SCHEDULE_HEADER_LEN, num_repetitions, gap, SLOT_FLOURISH_INTERVAL, modem)
+    }
+}
+
+/// Schedule header and message of one decoded slot
+pub fn split_schedule_header(decoded: &[u8]) -> Result<(ScheduleHeader, &[u8]), ScheduleError> {
+    let header = ScheduleHeader::parse(decoded)?;
+    Ok((header, &decoded[SCHEDULE_HEADER_LEN..]))
+}
+
+/// `generate_repetition_transmission` with each slot announcing the
+/// schedule; `config` from `TimeSlotConfig::signalled`
+pub fn generate_signalled_transmission<B: Backend>(
+    device: &B::Device,
+    message: &[u8],
+    config: &TimeSlotConfig,
+) -> Result<Tensor<B, 1>, ScheduleError> {
+    generate_signalled_transmission_with_config::<B>(device, message, config, &ModemConfig::default())
+}
+
+/// Signalled repetition of `modem` frames (slots from
+/// `TimeSlotConfig::signalled_with_config`)
+pub fn generate_signalled_transmission_with_config<B: Backend>(
+    device: &B::Device,
+    message: &[u8],
+    config: &TimeSlotConfig,
+    modem: &ModemConfig,
+) -> Result<Tensor<B, 1>, ScheduleError> {
+    let mut slot_bytes = ScheduleHeader::for_schedule(config)?.encode().to_vec();
+    slot_bytes.extend_from_slice(message);
+    let slot_modem = modem.clone().with_flourish_interval(config.flourish_interval);
+    let transmission = modulate_fhdpsk_with_config::<B>(device, &slot_bytes, true, &slot_modem);
+
+    let total_samples = (config.total_duration() * modem.sample_rate) as usize;
+    let mut output = Tensor::<B, 1>::zeros([total_samples], device);
+    for &slot_start in &config.slot_starts {
+        let start_sample = (slot_start * modem.sample_rate) as usize;
+        let end_sample = (start_sample + transmission.dims()[0]).min(total_samples);
+        let len = end_sample.saturating_sub(start_sample);
+        if len > 0 {
+            output = output.slice_assign([start_sample..end_sample], transmission.clone().slice([0..len]));
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modulation::{demodulate_fhdpsk_ex, demodulate_fhdpsk_ex_with_config};
+    use crate::wavelet::FS;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_header_round_trip_and_rejection() {
+        let config = TimeSlotConfig::signalled(13, 6, 7.26);
+        assert!((config.listening_gap - 7.3).abs() < 1e-9);
+        let header = ScheduleHeader::for_schedule(&config).unwrap();
+        let bytes = header.encode();
+        assert_eq!(ScheduleHeader::parse(&bytes), Ok(header));
+        assert_eq!(header.schedule(13).slot_starts, config.slot_starts);
+        assert_eq!(header.schedule(13).flourish_interval, config.flourish_interval);
+
+        assert_eq!(ScheduleHeader::parse(&bytes[..3]), Err(ScheduleError::Short { len: 3 }));
+        assert_eq!(ScheduleHeader::parse(&[0, 32, 0, 50, crc8(&[0, 32, 0, 50])]), Err(ScheduleError::Invalid { repetitions: 0 }));
+
+        // Any flipped bit fails the checksum
+        for bit in 0..8 * SCHEDULE_HEADER_LEN {
+            let mut damaged = bytes;
+            damaged[bit / 8] ^= 1 << (bit % 8);
+            assert_eq!(ScheduleHeader::parse(&damaged), Err(ScheduleError::Checksum), "bit {}", bit);
+        }
+
+        let too_many = TimeSlotConfig::signalled(13, 300, 5.0);
+        assert!(matches!(ScheduleHeader::for_schedule(&too_many), Err(ScheduleError::Unrepresentable { repetitions: 300, .. })));
+    }
+
+    #[test]
+    fn test_slot_index_follows_timing() {
+        let config = TimeSlotConfig::signalled(11, 4, 3.0);
+        let header = ScheduleHeader::for_schedule(&config).unwrap();
+        let cycle = config.slot_starts[1] * 4.0;
+
+        // Schedule aligned to the third cycle of the shared clock
+        for (slot_index, &start) in config.slot_starts.iter().enumerate() {
+            assert_eq!(header.slot_index_at(3.0 * cycle + start, 11), slot_index);
+        }
+        assert_eq!(header.remaining_slot_starts(3.0 * cycle + config.slot_starts[3], 11), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn test_any_slot_reveals_the_schedule() {
+        let device = Default::default();
+        let message = b"Slots ahead";
+        let config = TimeSlotConfig::signalled(message.len(), 3, 4.0);
+        let signal = generate_signalled_transmission::<TestBackend>(&device, message, &config).unwrap();
+
+        // A receiver that only catches the middle slot
+        let start = (config.slot_starts[1] * FS) as usize;
+        let end = start + (config.transmission_duration * FS) as usize;
+        let slot = signal.slice([start..end.min((config.total_duration() * FS) as usize)]);
+        let decoded = demodulate_fhdpsk_ex::<TestBackend>(&device, &slot, true, config.flourish_interval);
+
+        let (header, payload) = split_schedule_header(&decoded).unwrap();
+        assert_eq!(header.slot_index_at(config.slot_starts[1], message.len()), 1);
+        assert_eq!(&payload[..message.len()], message);
+        assert_eq!(header.remaining_slot_starts(config.slot_starts[1], message.len()), config.slot_starts[2..].to_vec());
+    }
+
+    #[test]
+    fn test_schedule_follows_the_modem_config() {
+        let device = Default::default();
+        let message = b"Eight tones";
+        let modem = ModemConfig::narrowband();
+        let config = TimeSlotConfig::signalled_with_config(message.len(), 3, 2.0, &modem);
+        assert_ne!(config.slot_starts, TimeSlotConfig::signalled(message.len(), 3, 2.0).slot_starts);
+        let signal = generate_signalled_transmission_with_config::<TestBackend>(&device, message, &config, &modem).unwrap();
+
+        let start = (config.slot_starts[2] * modem.sample_rate) as usize;
+        let end = start + (config.transmission_duration * modem.sample_rate) as usize;
+        let slot = signal.slice([start..end.min(signal.dims()[0])]);
+        let slot_modem = modem.clone().with_flourish_interval(config.flourish_interval);
+        let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &slot, true, &slot_modem);
+
+        let (header, payload) = split_schedule_header(&decoded).unwrap();
+        assert_eq!(header.schedule_with_config(message.len(), &modem).slot_starts, config.slot_starts);
+        assert_eq!(header.slot_index_at_with_config(config.slot_starts[2], message.len(), &modem), 2);
+        assert_eq!(&payload[..message.len()], message);
+    }
+}
@@ -0,0 +1,461 @@
+/// Long-Duration Soak Test Harness
+///
+/// The examples release tensors by hand (`drop(rx_signal)`) because long
+/// runs used to grow GPU memory. The soak harness replays hours of
+/// synthesized traffic through a slot-based streaming receiver and fails as
+/// soon as a resource bound is crossed:
+/// - Host memory (resident set) and device memory under absolute watermarks
+/// - Growth over the level measured after a warm-up period (the leak check:
+///   allocator pools settle during warm-up, steady state must stay flat)
+/// - Decode latency per slot (wall time from the slot's last sample to the
+///   decoded payload)
+///
+/// Traffic: one transmission per slot (`SOAK nnnnnn` payloads) at a fixed
+/// per-sample SNR, written into continuous noise and handed to the receiver
+/// in capture-sized blocks. The receiver buffers blocks, decodes each full
+/// slot and drains it, like a live capture loop.
+///
+/// Memory is read through a `MemoryProbe`: `ProcessMemory` reports the
+/// resident set (Linux), `CubeMemory` adds the bytes a CubeCL runtime holds
+/// in use on the device; any `FnMut() -> MemorySample` works.
+///
+/// The full run is an ignored test:
+/// `cargo test --release -p bachmodem soak -- --ignored`
+
+use burn::tensor::{ElementConversion, Tensor, backend::Backend};
+use burn_cubecl::CubeRuntime;
+use std::fmt;
+use std::time::{Duration, Instant};
+use crate::config::ModemConfig;
+use crate::deinterleave_gpu::{deinterleave_auto, Llrs};
+use crate::fft_correlation::FftBackend;
+use crate::modem_rng::{gaussian_noise, ModemRng, SplitMix64};
+use crate::modulation::{demodulate_fhdpsk_soft_erasures_with_config, pack_bits};
+use crate::polar_scl_gpu::PolarCodeSCL;
+use crate::transmitter::{BachTransmitter, CODE_K, CODE_N};
+use crate::wavelet::FS;
+
+use bachmodem_core::frame::parse_frame;
+
+/// Memory in use at one point in time (bytes)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MemorySample {
+    pub host_bytes: Option<u64>,
+    pub device_bytes: Option<u64>,
+}
+
+/// Source of memory readings
+pub trait MemoryProbe {
+    fn sample(&mut self) -> MemorySample;
+}
+
+impl<F: FnMut() -> MemorySample> MemoryProbe for F {
+    fn sample(&mut self) -> MemorySample {
+        self()
+    }
+}
+
+/// Resident set of this process, no device reading
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessMemory;
+
+impl MemoryProbe for ProcessMemory {
+    fn sample(&mut self) -> MemorySample {
+        MemorySample { host_bytes: process_rss_bytes(), device_bytes: None }
+    }
+}
+
+/// Resident set of this process and device memory in use on a CubeCL runtime
+pub struct CubeMemory<R: CubeRuntime> {
+    pub device: R::Device,
+}
+
+impl<R: CubeRuntime> CubeMemory<R> {
+    pub fn new(device: &R::Device) -> Self {
+        Self { device: device.clone() }
+    }
+}
+
+impl<R: CubeRuntime> MemoryProbe for CubeMemory<R> {
+    fn sample(&mut self) -> MemorySample {
+        let usage = R::client(&self.device).memory_usage();
+        MemorySample { host_bytes: process_rss_bytes(), device_bytes: Some(usage.bytes_in_use) }
+    }
+}
+
+/// Resident set size from `/proc/self/status` (None off Linux)
+pub fn process_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Soak run parameters
+#[derive(Clone, Debug)]
+pub struct SoakConfig {
+    /// Traffic to replay (seconds)
+    pub duration: f64,
+
+    pub modem: ModemConfig,
+
+    /// Per-sample SNR of the traffic (dB)
+    pub snr_db: f32,
+
+    /// Listening gap after each transmission (seconds)
+    pub gap: f64,
+
+    /// Capture block handed to the receiver (samples)
+    pub block_len: usize,
+
+    /// Resident set limit (bytes)
+    pub host_watermark: u64,
+
+    /// Device memory limit (bytes)
+    pub device_watermark: u64,
+
+    /// Allowed growth over the post-warm-up level (bytes, host and device each)
+    pub max_growth: u64,
+
+    /// Slots decoded before the growth baseline is taken
+    pub warmup_slots: usize,
+
+    /// Slowest acceptable slot decode
+    pub max_decode_latency: Duration,
+
+    /// Progress line every N slots (0 = silent)
+    pub report_interval: usize,
+
+    /// Seed of the channel noise
+    pub seed: u64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: 8.0 * 3600.0,
+            modem: ModemConfig::default(),
+            snr_db: -10.0,
+            gap: 5.0,
+            block_len: 4096,
+            host_watermark: 4 << 30,
+            device_watermark: 2 << 30,
+            max_growth: 64 << 20,
+            warmup_slots: 5,
+            max_decode_latency: Duration::from_secs(30),
+            report_interval: 50,
+            seed: 0,
+        }
+    }
+}
+
+/// Resource bound crossed during a soak run
+#[derive(Clone, Debug, PartialEq)]
+pub enum SoakViolation {
+    HostWatermark { at_s: f64, bytes: u64, limit: u64 },
+    DeviceWatermark { at_s: f64, bytes: u64, limit: u64 },
+    HostGrowth { at_s: f64, bytes: u64, baseline: u64, limit: u64 },
+    DeviceGrowth { at_s: f64, bytes: u64, baseline: u64, limit: u64 },
+    DecodeLatency { at_s: f64, latency: Duration, limit: Duration },
+}
+
+impl fmt::Display for SoakViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mib = |b: u64| b as f64 / (1 << 20) as f64;
+        match self {
+            SoakViolation::HostWatermark { at_s, bytes, limit } => {
+                write!(f, "{:.0} s: host memory {:.1} MiB over {:.1} MiB", at_s, mib(*bytes), mib(*limit))
+            }
+            SoakViolation::DeviceWatermark { at_s, bytes, limit } => {
+                write!(f, "{:.0} s: device memory {:.1} MiB over {:.1} MiB", at_s, mib(*bytes), mib(*limit))
+            }
+            SoakViolation::HostGrowth { at_s, bytes, baseline, limit } => {
+                write!(f, "{:.0} s: host memory grew {:.1} MiB since warm-up (limit {:.1} MiB)",
+                       at_s, mib(bytes - baseline), mib(*limit))
+            }
+            SoakViolation::DeviceGrowth { at_s, bytes, baseline, limit } => {
+                write!(f, "{:.0} s: device memory grew {:.1} MiB since warm-up (limit {:.1} MiB)",
+                       at_s, mib(bytes - baseline), mib(*limit))
+            }
+            SoakViolation::DecodeLatency { at_s, latency, limit } => {
+                write!(f, "{:.0} s: slot decode took {:?} (limit {:?})", at_s, latency, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SoakViolation {}
+
+/// Checks memory samples and decode latencies against the configured bounds
+#[derive(Clone, Debug)]
+pub struct WatermarkMonitor {
+    pub host_watermark: u64,
+    pub device_watermark: u64,
+    pub max_growth: u64,
+    pub warmup_slots: usize,
+    pub max_decode_latency: Duration,
+
+    slots: usize,
+    baseline: Option<MemorySample>,
+    peak: MemorySample,
+}
+
+impl WatermarkMonitor {
+    pub fn new(config: &SoakConfig) -> Self {
+        Self {
+            host_watermark: config.host_watermark,
+            device_watermark: config.device_watermark,
+            max_growth: config.max_growth,
+            warmup_slots: config.warmup_slots,
+            max_decode_latency: config.max_decode_latency,
+            slots: 0,
+            baseline: None,
+            peak: MemorySample::default(),
+        }
+    }
+
+    /// Check the state after one decoded slot at traffic time `at_s`
+    pub fn check(&mut self, at_s: f64, memory: MemorySample, latency: Duration) -> Result<(), SoakViolation> {
+        self.slots += 1;
+        self.peak.host_bytes = self.peak.host_bytes.max(memory.host_bytes);
+        self.peak.device_bytes = self.peak.device_bytes.max(memory.device_bytes);
+
+        if latency > self.max_decode_latency {
+            return Err(SoakViolation::DecodeLatency { at_s, latency, limit: self.max_decode_latency });
+        }
+        if let Some(bytes) = memory.host_bytes.filter(|&b| b > self.host_watermark) {
+            return Err(SoakViolation::HostWatermark { at_s, bytes, limit: self.host_watermark });
+        }
+        if let Some(bytes) = memory.device_bytes.filter(|&b| b > self.device_watermark) {
+            return Err(SoakViolation::DeviceWatermark { at_s, bytes, limit: self.device_watermark });
+        }
+
+        if self.slots == self.warmup_slots.max(1) {
+            self.baseline = Some(memory);
+        }
+        let Some(baseline) = self.baseline else { return Ok(()) };
+        let limit = self.max_growth;
+
+        if let (Some(bytes), Some(base)) = (memory.host_bytes, baseline.host_bytes) {
+            if bytes > base + limit {
+                return Err(SoakViolation::HostGrowth { at_s, bytes, baseline: base, limit });
+            }
+        }
+        if let (Some(bytes), Some(base)) = (memory.device_bytes, baseline.device_bytes) {
+            if bytes > base + limit {
+                return Err(SoakViolation::DeviceGrowth { at_s, bytes, baseline: base, limit });
+            }
+        }
+        Ok(())
+    }
+
+    /// Growth baseline, once the warm-up is over
+    pub fn baseline(&self) -> Option<MemorySample> {
+        self.baseline
+    }
+
+    /// Highest readings so far
+    pub fn peak(&self) -> MemorySample {
+        self.peak
+    }
+}
+
+/// Outcome of a completed soak run
+#[derive(Clone, Debug)]
+pub struct SoakReport {
+    /// Traffic replayed (seconds)
+    pub traffic_s: f64,
+
+    pub slots: usize,
+    pub decoded: usize,
+
+    pub baseline: Option<MemorySample>,
+    pub peak: MemorySample,
+
+    pub mean_decode_latency: Duration,
+    pub max_decode_latency: Duration,
+
+    /// Wall time of the whole run
+    pub elapsed: Duration,
+}
+
+/// Replay `config.duration` seconds of traffic through the streaming receiver
+///
+/// Returns the first violated bound, or the run statistics.
+///
+/// ⚠️ **SYNC POINT**: Downloads every slot of traffic and every decode
+pub fn run_soak<B: Backend + FftBackend, P: MemoryProbe>(
+    device: &B::Device,
+    config: &SoakConfig,
+    probe: &mut P,
+) -> Result<SoakReport, SoakViolation> {
+    let start = Instant::now();
+    let tx = BachTransmitter::new(config.modem.clone());
+    let decoder = PolarCodeSCL::new(CODE_N, CODE_K);
+    let mut monitor = WatermarkMonitor::new(config);
+    let mut noise_rng = SplitMix64::new(config.seed);
+
+    // Slot length from a representative transmission
+    let tx_len = tx.build::<B>(device, b"SOAK 000000").unwrap().dims()[0];
+    let lead = (0.5 * FS) as usize;
+    let slot_len = lead + tx_len + (config.gap * FS) as usize;
+    let num_slots = ((config.duration * FS) as usize / slot_len).max(1);
+
+    println!("[Soak] {} slots of {:.1} s, {:.1} h of traffic at {} dB",
+             num_slots, slot_len as f64 / FS, (num_slots * slot_len) as f64 / FS / 3600.0, config.snr_db);
+
+    let mut rx_buffer: Vec<f32> = Vec::with_capacity(2 * slot_len);
+    let mut decoded = 0;
+    let mut total_latency = Duration::ZERO;
+    let mut max_latency = Duration::ZERO;
+
+    for slot in 0..num_slots {
+        let payload = format!("SOAK {:06}", slot % 1_000_000);
+        let traffic = synthesize_slot::<B, _>(device, &tx, payload.as_bytes(), slot_len, lead, config.snr_db, &mut noise_rng);
+
+        // Capture loop: blocks arrive, full slots are decoded and drained
+        for block in traffic.chunks(config.block_len) {
+            rx_buffer.extend_from_slice(block);
+            if rx_buffer.len() < slot_len {
+                continue;
+            }
+
+            let t0 = Instant::now();
+            let received = decode_slot::<B>(device, &config.modem, &decoder, &rx_buffer[..slot_len]);
+            let latency = t0.elapsed();
+            rx_buffer.drain(..slot_len);
+
+            if received.as_deref().is_some_and(|r| r.starts_with(payload.as_bytes())) {
+                decoded += 1;
+            }
+            total_latency += latency;
+            max_latency = max_latency.max(latency);
+
+            B::memory_cleanup(device);
+            let at_s = ((slot + 1) * slot_len) as f64 / FS;
+            let memory = probe.sample();
+            monitor.check(at_s, memory, latency)?;
+
+            if config.report_interval > 0 && (slot + 1) % config.report_interval == 0 {
+                println!("[Soak] {:.2} h: {}/{} decoded, host {:?} MiB, device {:?} MiB, decode {:?}",
+                         at_s / 3600.0, decoded, slot + 1,
+                         memory.host_bytes.map(|b| b >> 20), memory.device_bytes.map(|b| b >> 20), latency);
+            }
+        }
+    }
+
+    Ok(SoakReport {
+        traffic_s: (num_slots * slot_len) as f64 / FS,
+        slots: num_slots,
+        decoded,
+        baseline: monitor.baseline(),
+        peak: monitor.peak(),
+        mean_decode_latency: total_latency / num_slots as u32,
+        max_decode_latency: max_latency,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// One slot of capture: noise with the transmission `lead` samples in
+fn synthesize_slot<B: Backend, R: ModemRng + ?Sized>(
+    device: &B::Device,
+    tx: &BachTransmitter,
+    payload: &[u8],
+    slot_len: usize,
+    lead: usize,
+    snr_db: f32,
+    rng: &mut R,
+) -> Vec<f32> {
+    let signal = tx.build::<B>(device, payload).unwrap();
+    let len = signal.dims()[0].min(slot_len - lead);
+
+    let signal_power: f32 = signal.clone().powf_scalar(2.0).mean().into_scalar().elem();
+    let noise_std = (signal_power / 10f32.powf(snr_db / 10.0)).sqrt();
+    let noise = gaussian_noise::<B, R>(device, slot_len, noise_std, rng);
+
+    let window = noise.clone().slice([lead..lead + len]) + signal.slice([0..len]);
+    noise.slice_assign([lead..lead + len], window)
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap()
+}
+
+/// Sync, demodulate and decode one slot; None if the frame doesn't parse
+fn decode_slot<B: Backend + FftBackend>(
+    device: &B::Device,
+    config: &ModemConfig,
+    decoder: &PolarCodeSCL,
+    samples: &[f32],
+) -> Option<Vec<u8>> {
+    let rx = Tensor::<B, 1>::from_floats(samples, device);
+    let llrs = demodulate_fhdpsk_soft_erasures_with_config::<B>(device, &rx, true, config, CODE_N);
+
+    let codeword = deinterleave_auto::<B>(device, Llrs::Device(llrs), config.interleaver_columns()).into_tensor(device);
+    let bits = decoder.decode_scl_gpu::<B>(device, &codeword, 8).swap_remove(0);
+    parse_frame(&pack_bits(&bits)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+    use burn::backend::wgpu::WgpuRuntime;
+
+    const MIB: u64 = 1 << 20;
+
+    #[test]
+    fn test_watermark_monitor() {
+        let config = SoakConfig {
+            host_watermark: 1000 * MIB,
+            max_growth: 50 * MIB,
+            warmup_slots: 2,
+            ..Default::default()
+        };
+        let mut monitor = WatermarkMonitor::new(&config);
+        let host = |mib: u64| MemorySample { host_bytes: Some(mib * MIB), device_bytes: None };
+        let fast = Duration::from_millis(500);
+
+        // Warm-up growth is allowed, steady state must stay within the budget
+        assert!(monitor.check(10.0, host(200), fast).is_ok());
+        assert!(monitor.check(20.0, host(400), fast).is_ok());
+        assert!(monitor.check(30.0, host(440), fast).is_ok());
+        assert!(matches!(monitor.check(40.0, host(460), fast),
+                         Err(SoakViolation::HostGrowth { baseline, .. }) if baseline == 400 * MIB));
+
+        assert!(matches!(monitor.check(50.0, host(1200), fast), Err(SoakViolation::HostWatermark { .. })));
+        assert!(matches!(monitor.check(60.0, host(400), Duration::from_secs(31)),
+                         Err(SoakViolation::DecodeLatency { .. })));
+        assert_eq!(monitor.peak().host_bytes, Some(1200 * MIB));
+
+        // Device readings have their own watermark
+        let config = SoakConfig { device_watermark: 500 * MIB, ..Default::default() };
+        let mut monitor = WatermarkMonitor::new(&config);
+        let device = |mib: u64| MemorySample { host_bytes: None, device_bytes: Some(mib * MIB) };
+        assert!(monitor.check(10.0, device(300), fast).is_ok());
+        assert!(matches!(monitor.check(20.0, device(600), fast), Err(SoakViolation::DeviceWatermark { .. })));
+    }
+
+    #[test]
+    fn test_cube_memory_reads_the_device() {
+        let device = Default::default();
+        let mut probe = CubeMemory::<WgpuRuntime>::new(&device);
+        let _held = Tensor::<TestBackend, 1>::ones([1 << 20], &device);
+        let sample = probe.sample();
+        assert!(sample.device_bytes.is_some_and(|bytes| bytes >= 4 << 20), "{:?}", sample);
+    }
+
+    #[test]
+    #[ignore = "8 hours of traffic: cargo test --release -p bachmodem soak -- --ignored"]
+    fn test_soak_8h() {
+        let device = Default::default();
+        let config = SoakConfig::default();
+
+        let report = match run_soak::<TestBackend, _>(&device, &config, &mut CubeMemory::<WgpuRuntime>::new(&device)) {
+            Ok(report) => report,
+            Err(violation) => panic!("Soak failed: {}", violation),
+        };
+
+        assert!(report.peak.device_bytes.is_some_and(|bytes| bytes <= config.device_watermark));
+        assert!(report.decoded as f64 >= 0.99 * report.slots as f64, "{}/{} decoded", report.decoded, report.slots);
+    }
+}
@@ -0,0 +1,249 @@
+/// Spectral Mask Tool
+///
+/// Measures the occupied bandwidth of a transmission and checks it against
+/// a receiver filter mask (e.g. a 500 Hz CW filter).
+///
+/// Occupied bandwidth follows the usual definition: the band containing
+/// `power_fraction` (99%) of the total power, with 0.5% left outside on
+/// each side. The spread beyond the tone span follows the Morlet shape
+/// (`ModemConfig::wavelet_sigmas`, see `ModemConfig::tone_bandwidth`).
+
+use burn::tensor::{Tensor, TensorPrimitive, backend::Backend};
+use crate::fft_correlation::FftBackend;
+use crate::wavelet::FS;
+
+/// One-sided power spectrum of a real signal
+#[derive(Clone, Debug)]
+pub struct PowerSpectrum {
+    /// Power per bin, DC to Nyquist
+    pub power: Vec<f32>,
+
+    /// Bin spacing (Hz)
+    pub bin_hz: f64,
+}
+
+impl PowerSpectrum {
+    /// Total power across all bins
+    pub fn total_power(&self) -> f64 {
+        self.power.iter().map(|&p| p as f64).sum()
+    }
+
+    /// Power between two frequencies (Hz)
+    pub fn band_power(&self, low_hz: f64, high_hz: f64) -> f64 {
+        self.power.iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                let f = *i as f64 * self.bin_hz;
+                f >= low_hz && f <= high_hz
+            })
+            .map(|(_, &p)| p as f64)
+            .sum()
+    }
+
+    /// Lower and upper edge (Hz) of the band holding `fraction` of the power
+    pub fn occupied_band(&self, fraction: f64) -> (f64, f64) {
+        let total = self.total_power();
+        let tail = total * (1.0 - fraction) / 2.0;
+
+        let mut acc = 0.0;
+        let mut low_bin = 0;
+        for (i, &p) in self.power.iter().enumerate() {
+            acc += p as f64;
+            if acc > tail {
+                low_bin = i;
+                break;
+            }
+        }
+
+        let mut acc = 0.0;
+        let mut high_bin = self.power.len() - 1;
+        for (i, &p) in self.power.iter().enumerate().rev() {
+            acc += p as f64;
+            if acc > tail {
+                high_bin = i;
+                break;
+            }
+        }
+
+        (low_bin as f64 * self.bin_hz, high_bin as f64 * self.bin_hz)
+    }
+
+    /// Occupied bandwidth (Hz) holding `fraction` of the power
+    pub fn occupied_bandwidth(&self, fraction: f64) -> f64 {
+        let (low, high) = self.occupied_band(fraction);
+        high - low
+    }
+}
+
+/// Compute the power spectrum of a signal on the GPU
+///
+/// Zero-pads to the next power of two and takes |FFT|².
+///
+/// **SYNC POINT**: Downloads the one-sided spectrum for analysis
+pub fn power_spectrum_gpu<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+) -> PowerSpectrum {
+    let sig_len = signal.dims()[0];
+    let fft_size = sig_len.next_power_of_two();
+
+    let padded = if sig_len < fft_size {
+        let zeros = Tensor::zeros([fft_size - sig_len], device);
+        Tensor::cat(vec![signal.clone(), zeros], 0)
+    } else {
+        signal.clone()
+    };
+
+    let real = match padded.reshape([1, fft_size]).into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+    let imag = match Tensor::<B, 2>::zeros([1, fft_size], device).into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+
+    let (fft_real_t, fft_imag_t) = B::fft_1d_batch_impl(real, imag, fft_size);
+    let fft_real: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_real_t));
+    let fft_imag: Tensor<B, 2> = Tensor::from_primitive(TensorPrimitive::Float(fft_imag_t));
+
+    // |X|² on GPU, keep DC..Nyquist
+    let power = fft_real.powf_scalar(2.0)
+        .add(fft_imag.powf_scalar(2.0))
+        .reshape([fft_size])
+        .slice([0..fft_size / 2 + 1]);
+
+    PowerSpectrum {
+        power: power.into_data().to_vec::<f32>().unwrap(),
+        bin_hz: FS / fft_size as f64,
+    }
+}
+
+/// Receiver filter mask
+#[derive(Clone, Debug)]
+pub struct SpectralMask {
+    /// Filter center frequency (Hz, audio)
+    pub center_hz: f64,
+
+    /// Filter bandwidth (Hz)
+    pub bandwidth_hz: f64,
+
+    /// Fraction of power that must fall inside the filter
+    pub power_fraction: f64,
+}
+
+/// Result of checking a spectrum against a mask
+#[derive(Clone, Debug)]
+pub struct MaskReport {
+    /// Lower edge of the occupied band (Hz)
+    pub occupied_low_hz: f64,
+
+    /// Upper edge of the occupied band (Hz)
+    pub occupied_high_hz: f64,
+
+    /// Fraction of total power inside the filter
+    pub in_band_fraction: f64,
+
+    /// Occupied band lies within the filter
+    pub passes: bool,
+}
+
+impl MaskReport {
+    /// Occupied bandwidth (Hz)
+    pub fn occupied_bandwidth(&self) -> f64 {
+        self.occupied_high_hz - self.occupied_low_hz
+    }
+}
+
+impl SpectralMask {
+    /// 500 Hz CW filter centered on `center_hz`
+    pub fn cw_500hz(center_hz: f64) -> Self {
+        Self {
+            center_hz,
+            bandwidth_hz: 500.0,
+            power_fraction: 0.99,
+        }
+    }
+
+    /// Filter edges (Hz)
+    pub fn edges(&self) -> (f64, f64) {
+        (
+            self.center_hz - self.bandwidth_hz / 2.0,
+            self.center_hz + self.bandwidth_hz / 2.0,
+        )
+    }
+
+    /// Check a spectrum against this mask
+    pub fn check(&self, spectrum: &PowerSpectrum) -> MaskReport {
+        let (low, high) = self.edges();
+        let (occupied_low_hz, occupied_high_hz) = spectrum.occupied_band(self.power_fraction);
+
+        let total = spectrum.total_power();
+        let in_band_fraction = if total > 0.0 {
+            spectrum.band_power(low, high) / total
+        } else {
+            0.0
+        };
+
+        MaskReport {
+            occupied_low_hz,
+            occupied_high_hz,
+            in_band_fraction,
+            passes: occupied_low_hz >= low && occupied_high_hz <= high,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_narrow500_fits_cw_filter() {
+        let device = Default::default();
+
+        let config = ModemConfig::narrowband_500hz();
+        let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"CQ", true, &config);
+        let spectrum = power_spectrum_gpu::<TestBackend>(&device, &signal);
+
+        let report = SpectralMask::cw_500hz(config.center_frequency()).check(&spectrum);
+
+        println!("narrow500: {:.0}-{:.0} Hz ({:.0} Hz), {:.2}% in filter",
+                 report.occupied_low_hz, report.occupied_high_hz,
+                 report.occupied_bandwidth(), report.in_band_fraction * 100.0);
+        assert!(report.passes);
+        assert!(report.occupied_bandwidth() < 500.0);
+    }
+
+    #[test]
+    fn test_standard_exceeds_cw_filter() {
+        let device = Default::default();
+
+        let config = ModemConfig::default();
+        let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"CQ", true, &config);
+        let spectrum = power_spectrum_gpu::<TestBackend>(&device, &signal);
+
+        let report = SpectralMask::cw_500hz(config.center_frequency()).check(&spectrum);
+
+        assert!(!report.passes);
+        assert!(report.occupied_bandwidth() > 500.0);
+    }
+
+    #[test]
+    fn test_occupied_bandwidth_follows_wavelet_sigmas() {
+        let device = Default::default();
+
+        let bandwidth = |sigmas: f64| {
+            let config = ModemConfig::narrowband_500hz().with_wavelet_sigmas(sigmas);
+            let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"CQ", true, &config);
+            let spectrum = power_spectrum_gpu::<TestBackend>(&device, &signal);
+            SpectralMask::cw_500hz(config.center_frequency()).check(&spectrum).occupied_bandwidth()
+        };
+
+        // Shorter pulses spread wider
+        assert!(bandwidth(10.0) > bandwidth(4.0) + 20.0);
+    }
+}
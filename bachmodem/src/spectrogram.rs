@@ -0,0 +1,109 @@
+/// Spectrogram
+///
+/// Short-time power spectra of a capture, for plots, band-occupancy checks
+/// and interference hunting. Framing, Hann window and FFT run as one batched
+/// STFT on the device (`fft_gpu::spectral::stft_magnitude`); only the
+/// finished one-sided power is downloaded.
+
+use burn::tensor::{Tensor, backend::Backend};
+use fft_gpu::spectral::{hann_window, stft_magnitude};
+use crate::fft_correlation::FftBackend;
+use crate::wavelet::FS;
+
+/// Power per frame and bin
+#[derive(Clone, Debug)]
+pub struct Spectrogram {
+    /// `frames` x `bins`, row-major; |X|² of the Hann-windowed frame
+    pub power: Vec<f32>,
+
+    pub frames: usize,
+
+    /// Bins per frame, DC to Nyquist (`fft_len / 2 + 1`)
+    pub bins: usize,
+
+    /// Bin spacing (Hz)
+    pub bin_hz: f64,
+
+    /// Time between frames (seconds)
+    pub hop_seconds: f64,
+}
+
+impl Spectrogram {
+    /// Power of frame `index`, DC to Nyquist
+    pub fn frame(&self, index: usize) -> &[f32] {
+        &self.power[index * self.bins..(index + 1) * self.bins]
+    }
+
+    /// Frequency (Hz) of the strongest bin of frame `index`
+    pub fn peak_frequency(&self, index: usize) -> f64 {
+        let peak = self.frame(index).iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map_or(0, |(bin, _)| bin);
+        peak as f64 * self.bin_hz
+    }
+
+    /// Start time (seconds) of frame `index`
+    pub fn frame_time(&self, index: usize) -> f64 {
+        index as f64 * self.hop_seconds
+    }
+}
+
+/// Spectrogram of a signal at `FS`: `fft_len`-sample frames every `hop` samples
+///
+/// `fft_len` must be a power of two; signals shorter than one frame give an
+/// empty spectrogram.
+///
+/// **SYNC POINT**: Downloads the power
+pub fn spectrogram_gpu<B: Backend + FftBackend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    fft_len: usize,
+    hop: usize,
+) -> Spectrogram {
+    let bins = fft_len / 2 + 1;
+    let mut spectrogram = Spectrogram {
+        power: Vec::new(),
+        frames: 0,
+        bins,
+        bin_hz: FS / fft_len as f64,
+        hop_seconds: hop as f64 / FS,
+    };
+    if signal.dims()[0] < fft_len {
+        return spectrogram;
+    }
+
+    let power = stft_magnitude(signal.clone(), hann_window(fft_len, device), hop).powf_scalar(2.0);
+    spectrogram.frames = power.dims()[0];
+    spectrogram.power = power.into_data().to_vec::<f32>().unwrap();
+    spectrogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_tone_steps_between_frames() {
+        let device = Default::default();
+
+        // 500 Hz for 0.5 s, then 1500 Hz for 0.5 s
+        let samples: Vec<f32> = (0..FS as usize)
+            .map(|t| {
+                let f = if t < FS as usize / 2 { 500.0 } else { 1500.0 };
+                (2.0 * std::f64::consts::PI * f * t as f64 / FS).sin() as f32
+            })
+            .collect();
+        let signal = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device);
+
+        let spectrogram = spectrogram_gpu::<TestBackend>(&device, &signal, 256, 128);
+        assert_eq!(spectrogram.bins, 129);
+        assert_eq!(spectrogram.frames, (8000 - 256) / 128 + 1);
+
+        let bin_hz = spectrogram.bin_hz;
+        assert!((spectrogram.peak_frequency(0) - 500.0).abs() <= bin_hz);
+        assert!((spectrogram.peak_frequency(spectrogram.frames - 1) - 1500.0).abs() <= bin_hz);
+        assert!(spectrogram.frame_time(1) == 128.0 / FS);
+    }
+}
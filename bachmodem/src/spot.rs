@@ -0,0 +1,156 @@
+/// Beacon Spots
+///
+/// A spot records that one station's beacon was heard: callsign, Maidenhead
+/// locator, dial frequency, SNR and time, the record reporting networks
+/// aggregate. Beacon payloads are free text; `Spot::from_decode` recognizes
+/// the usual forms:
+///
+/// ```text
+/// CQ N0CALL FN31        CQ [modifier] <call> [locator]
+/// DE N0CALL FN31pr      ... DE <call> [locator]
+/// N0CALL FN31           <call> [locator]
+/// ```
+///
+/// Text that names no plausible callsign is not a spot (telemetry, chat).
+///
+/// A beacon repeats every slot; `SpotReporter` passes one spot per callsign
+/// and dial frequency per window to reporting networks.
+
+/// Mode name in spot reports
+pub const SPOT_MODE: &str = "BACHMODEM";
+
+/// One heard beacon
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spot {
+    /// Wall-clock time of the decode (Unix seconds)
+    pub time_s: f64,
+
+    /// Callsign of the heard station (upper case)
+    pub callsign: String,
+
+    /// 4- or 6-character Maidenhead locator, if sent
+    pub locator: Option<String>,
+
+    /// USB dial frequency of the receiver (Hz), if known
+    pub dial_hz: Option<u64>,
+
+    /// SNR of the decode (dB)
+    pub snr_db: f32,
+}
+
+impl Spot {
+    /// Spot from a decoded beacon text, None if it names no callsign
+    pub fn from_decode(text: &str, snr_db: f32, dial_hz: Option<u64>, time_s: f64) -> Option<Self> {
+        let tokens: Vec<String> = text.split_whitespace().map(|t| t.to_ascii_uppercase()).collect();
+
+        let call_at = match tokens.iter().position(|t| t == "DE") {
+            Some(de) => de + 1,
+            None if tokens.first().is_some_and(|t| t == "CQ") => {
+                // "CQ DX N0CALL", "CQ POTA N0CALL": skip one modifier
+                if tokens.get(1).is_some_and(|t| !is_callsign(t)) { 2 } else { 1 }
+            }
+            None => 0,
+        };
+        let callsign = tokens.get(call_at).filter(|t| is_callsign(t))?.clone();
+        let locator = tokens.get(call_at + 1).filter(|t| is_locator(t)).map(|t| normalize_locator(t));
+
+        Some(Self { time_s, callsign, locator, dial_hz, snr_db })
+    }
+
+    /// JSON object as published on MQTT and uploaded by `SpotReporter`
+    ///
+    /// `{"time", "callsign", "locator" | null, "dial_hz" | null, "snr_db", "mode"}`
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"time\":{},\"callsign\":{},\"locator\":{},\"dial_hz\":{},\"snr_db\":{},\"mode\":\"{}\"}}",
+            self.time_s, json_str(&self.callsign),
+            self.locator.as_deref().map_or("null".to_string(), json_str),
+            json_opt(self.dial_hz), json_f32(self.snr_db), SPOT_MODE,
+        )
+    }
+}
+
+/// JSON string literal
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub(crate) fn json_opt(value: Option<u64>) -> String {
+    value.map_or("null".to_string(), |v| v.to_string())
+}
+
+/// SNR to 0.1 dB; NaN / infinite (no estimate) become null
+pub(crate) fn json_f32(value: f32) -> String {
+    if value.is_finite() { format!("{:.1}", value) } else { "null".to_string() }
+}
+
+/// 3 to 10 characters of letters, digits and '/', with both a letter and a digit
+pub fn is_callsign(token: &str) -> bool {
+    (3..=10).contains(&token.len())
+        && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '/')
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().any(|c| c.is_ascii_alphabetic())
+        && !token.starts_with('/')
+        && !token.ends_with('/')
+}
+
+/// Maidenhead field + square, optionally subsquare (case-insensitive)
+pub fn is_locator(token: &str) -> bool {
+    let c: Vec<char> = token.to_ascii_uppercase().chars().collect();
+    (c.len() == 4 || c.len() == 6)
+        && c[..2].iter().all(|ch| ('A'..='R').contains(ch))
+        && c[2..4].iter().all(|ch| ch.is_ascii_digit())
+        && c[4..].iter().all(|ch| ('A'..='X').contains(ch))
+}
+
+/// Field and square upper case, subsquare lower case ("FN31pr")
+fn normalize_locator(token: &str) -> String {
+    let (square, subsquare) = token.split_at(4);
+    format!("{}{}", square.to_ascii_uppercase(), subsquare.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beacon_forms() {
+        let spot = Spot::from_decode("CQ N0CALL FN31", -18.0, Some(14_074_000), 1.0).unwrap();
+        assert_eq!(spot.callsign, "N0CALL");
+        assert_eq!(spot.locator.as_deref(), Some("FN31"));
+        assert_eq!(spot.dial_hz, Some(14_074_000));
+
+        let spot = Spot::from_decode("cq dx dl1abc jo62qm", -25.0, None, 1.0).unwrap();
+        assert_eq!((spot.callsign.as_str(), spot.locator.as_deref()), ("DL1ABC", Some("JO62qm")));
+
+        let spot = Spot::from_decode("TEST DE VK2/G4XYZ", -20.0, None, 1.0).unwrap();
+        assert_eq!((spot.callsign.as_str(), spot.locator), ("VK2/G4XYZ", None));
+
+        assert_eq!(Spot::from_decode("K1ABC EM12", 0.0, None, 1.0).unwrap().callsign, "K1ABC");
+
+        // Not beacons
+        assert!(Spot::from_decode("T=21.5C", 0.0, None, 1.0).is_none());
+        assert!(Spot::from_decode("HELLO WORLD", 0.0, None, 1.0).is_none());
+        assert!(Spot::from_decode("", 0.0, None, 1.0).is_none());
+    }
+
+    #[test]
+    fn test_locator_syntax() {
+        assert!(is_locator("FN31") && is_locator("fn31pr") && is_locator("RR99xx"));
+        assert!(!is_locator("SN31") && !is_locator("FN3") && !is_locator("FN31pz") && !is_locator("FNAB"));
+    }
+}
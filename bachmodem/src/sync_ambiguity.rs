@@ -0,0 +1,222 @@
+/// Sync Ambiguity Resolution
+///
+/// The preamble repeats its UP-DOWN sweep, so the preamble template also
+/// matches one sweep cycle (2 × num_tones notes) early or late on half its
+/// notes. Noise and fading can lift such a sidelobe above the true peak, and
+/// a receiver that joined after the first notes never sees the true peak at
+/// all: sync then silently lands one cycle off and every frame fails CRC.
+///
+/// Every start spaced by whole cycles from the peak is re-scored on its
+/// preamble correlation; starts before the capture are scored on the part of
+/// the preamble that was received, which usually beats the late peak
+/// outright. When several candidates are near-equal (`SYNC_AMBIGUITY_RATIO`)
+/// their reference blocks (the first `lag` data symbols, all phase 0) are run
+/// through the matched filter bank: at the true start nearly all of the
+/// energy sits on the expected hopping tones, one cycle early it reads
+/// preamble notes instead. The earliest candidate with a good reference
+/// block wins; one cycle late reads the second data block, which looks just
+/// as clean but always comes later.
+
+use burn::tensor::{Tensor, backend::Backend, ElementConversion};
+use crate::config::ModemConfig;
+use crate::wavelet::matched_filter_bank;
+
+/// Candidates within this fraction of the largest correlation are near-equal
+///
+/// Above the 0.5 one-cycle sidelobe of a clean plain preamble, so only noise,
+/// fading or a partly received preamble make a start ambiguous.
+pub const SYNC_AMBIGUITY_RATIO: f32 = 0.75;
+
+/// Reference block tone purity a candidate needs, relative to the best candidate
+pub const REFERENCE_QUALITY_RATIO: f32 = 0.7;
+
+/// UP-DOWN sweep period of the preamble (samples)
+///
+/// A single-sweep preamble (`chat`) never repeats: its period is its own
+/// length, so there is exactly one candidate start.
+pub fn preamble_cycle_samples(config: &ModemConfig) -> usize {
+    config.preamble_sweeps.min(2) * config.num_tones * config.preamble_note_samples()
+}
+
+/// Fraction of each reference block's matched-filter energy on its hopping tones
+///
+/// One value per data start in `starts`; blocks running off the signal score 0.
+///
+/// ⚠️ **SYNC POINT**: Downloads one purity per start
+pub fn reference_block_quality<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    starts: &[usize],
+    config: &ModemConfig,
+) -> Vec<f32> {
+    let symbol_len = config.symbol_samples();
+    let lag = config.lag();
+    let num_tones = config.num_tones;
+    let signal_len = signal.dims()[0];
+
+    let fits: Vec<usize> = starts.iter().copied()
+        .filter(|&start| start + lag * symbol_len <= signal_len)
+        .collect();
+    if fits.is_empty() {
+        return vec![0.0; starts.len()];
+    }
+
+    // [candidates × lag, symbol_len] against the whole bank
+    let mut segments = Vec::with_capacity(fits.len() * lag);
+    for &start in &fits {
+        for i in 0..lag {
+            let pos = start + i * symbol_len;
+            segments.push(signal.clone().slice([pos..pos + symbol_len]));
+        }
+    }
+    let batch: Tensor<B, 2> = Tensor::stack(segments, 0);
+    let power = matched_filter_bank::<B>(device, config)
+        .map(|bank| batch.clone().matmul(bank.transpose()))
+        .norm_sqr();
+
+    let melody = config.melody_indices(lag);
+    let mut mask = vec![0.0f32; fits.len() * lag * num_tones];
+    for c in 0..fits.len() {
+        for (i, &tone) in melody.iter().enumerate() {
+            mask[(c * lag + i) * num_tones + tone] = 1.0;
+        }
+    }
+    let mask = Tensor::<B, 1>::from_floats(mask.as_slice(), device).reshape([fits.len() * lag, num_tones]);
+
+    let on_tone = (power.clone() * mask).reshape([fits.len(), lag * num_tones]).sum_dim(1);
+    let total = power.reshape([fits.len(), lag * num_tones]).sum_dim(1);
+    let purity: Vec<f32> = (on_tone / (total + 1e-12)).into_data().to_vec().unwrap();
+
+    let mut purity = purity.into_iter();
+    starts.iter()
+        .map(|&start| if start + lag * symbol_len <= signal_len { purity.next().unwrap_or(0.0) } else { 0.0 })
+        .collect()
+}
+
+/// Data start (first sample after the preamble) chosen among the sweep-cycle
+/// candidates around the preamble peak at `sync_pos`
+///
+/// Returns `sync_pos + preamble length` unless another candidate correlates
+/// better or ties with a cleaner (or equally clean, earlier) reference block.
+/// At most `config.sync.max_candidates` starts, the nearest to the peak, are
+/// scored.
+///
+/// ⚠️ **SYNC POINT**: Downloads candidate correlations and purities
+pub fn resolve_sync_ambiguity<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    preamble: &Tensor<B, 1>,
+    sync_pos: usize,
+    config: &ModemConfig,
+) -> usize {
+    let preamble_len = preamble.dims()[0];
+    let signal_len = signal.dims()[0] as isize;
+    let cycle = preamble_cycle_samples(config) as isize;
+    let cycles = (preamble_len as isize / cycle).max(1);
+    let data_start = sync_pos + preamble_len;
+
+    // The `max_candidates` cycle offsets nearest the peak, in time order
+    let mut offsets: Vec<isize> = (-(cycles - 1)..cycles).collect();
+    offsets.sort_by_key(|k| k.abs());
+    offsets.truncate(config.sync.max_candidates.max(1));
+    offsets.sort_unstable();
+
+    // Preamble starts whose data lies in the signal, with their correlation
+    let mut candidates: Vec<(isize, f32)> = Vec::new();
+    for k in offsets {
+        let start = sync_pos as isize + k * cycle;
+        if start + (preamble_len as isize) < 0 || start >= signal_len {
+            continue;
+        }
+        candidates.push((start, partial_correlation(signal, preamble, start)));
+    }
+
+    let Some(&(strongest, peak)) = candidates.iter().max_by(|a, b| a.1.total_cmp(&b.1)) else {
+        return data_start;
+    };
+    let strongest = (strongest + preamble_len as isize) as usize;
+    candidates.retain(|&(_, corr)| corr >= SYNC_AMBIGUITY_RATIO * peak);
+
+    let starts: Vec<usize> = candidates.iter().map(|&(start, _)| (start + preamble_len as isize) as usize).collect();
+    let quality = if starts.len() > 1 { reference_block_quality(device, signal, &starts, config) } else { Vec::new() };
+    let best = quality.iter().copied().fold(0.0f32, f32::max);
+
+    // Candidates are in time order: the earliest clean reference block
+    let chosen = starts.iter().zip(&quality)
+        .find(|&(_, &q)| best > 0.0 && q >= REFERENCE_QUALITY_RATIO * best)
+        .map_or(strongest, |(&start, _)| start);
+
+    if chosen != data_start {
+        let cycles_off = (chosen as isize - data_start as isize) / cycle;
+        println!("    [Sync] Ambiguity: {} near-equal candidate(s), moved {:+} sweep cycle(s) (purity {:?})",
+                 candidates.len(), cycles_off, quality.iter().map(|q| (q * 100.0).round() / 100.0).collect::<Vec<_>>());
+    }
+    chosen
+}
+
+/// |⟨signal, preamble⟩| over the part of the preamble at `start` that the
+/// signal covers
+fn partial_correlation<B: Backend>(signal: &Tensor<B, 1>, preamble: &Tensor<B, 1>, start: isize) -> f32 {
+    let signal_len = signal.dims()[0] as isize;
+    let preamble_len = preamble.dims()[0] as isize;
+    let from = (-start).max(0);
+    let to = preamble_len.min(signal_len - start);
+    if to <= from {
+        return 0.0;
+    }
+
+    let received = signal.clone().slice([(start + from) as usize..(start + to) as usize]);
+    let template = preamble.clone().slice([from as usize..to as usize]);
+    let corr: f32 = (received * template).sum().into_scalar().elem();
+    corr.abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modulation::{modulate_fhdpsk_with_config, synchronize_signal_with_config};
+    use crate::wavelet::generate_bach_preamble_with_config;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_late_join_resolves_to_true_start() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let signal = modulate_fhdpsk_with_config::<TestBackend>(&device, b"LATE JOIN", true, &config);
+        let preamble = generate_bach_preamble_with_config::<TestBackend>(&device, &config);
+        let preamble_len = preamble.dims()[0];
+        let cycle = preamble_cycle_samples(&config);
+
+        // Receiver joins 4 notes into the preamble: the largest peak is a cycle late
+        let missed = 4 * config.preamble_note_samples();
+        let len = signal.dims()[0];
+        let rx = signal.slice([missed..len]);
+        let sync_pos = synchronize_signal_with_config::<TestBackend>(&device, &rx, &config).unwrap().metrics.position;
+        assert_eq!(sync_pos, cycle - missed);
+
+        let data_start = resolve_sync_ambiguity(&device, &rx, &preamble, sync_pos, &config);
+        assert_eq!(data_start, preamble_len - missed);
+    }
+
+    #[test]
+    fn test_true_peak_is_kept() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let lead = 1234;
+        let frame = modulate_fhdpsk_with_config::<TestBackend>(&device, b"ON TIME", true, &config);
+        let rx = Tensor::cat(vec![Tensor::zeros([lead], &device), frame], 0);
+        let preamble = generate_bach_preamble_with_config::<TestBackend>(&device, &config);
+
+        let sync_pos = synchronize_signal_with_config::<TestBackend>(&device, &rx, &config).unwrap().metrics.position;
+        assert_eq!(sync_pos, lead);
+
+        // The one-cycle sidelobes are candidates but read worse reference blocks
+        let cycle = preamble_cycle_samples(&config);
+        let starts = [lead + preamble.dims()[0] - cycle, lead + preamble.dims()[0]];
+        let quality = reference_block_quality(&device, &rx, &starts, &config);
+        assert!(quality[1] > 0.9, "true reference block purity {}", quality[1]);
+        assert!(quality[0] < 0.5 * quality[1], "early candidate purity {}", quality[0]);
+
+        assert_eq!(resolve_sync_ambiguity(&device, &rx, &preamble, sync_pos, &config), lead + preamble.dims()[0]);
+    }
+}
@@ -0,0 +1,262 @@
+/// Acquisition / Tracking Sync Lock
+///
+/// A one-shot file decode can afford the relaxed -30 dB thresholds: there is
+/// one capture and the CRC judges the result. A receiver monitoring a
+/// channel for hours can't - at one correlation peak per capture, loose
+/// thresholds turn noise into a steady trickle of false acquisitions. And a
+/// single threshold tuned for a marginal station flaps: the station fades
+/// a little, sync drops, comes back, drops.
+///
+/// `SyncLock` uses two threshold sets with hysteresis between them:
+/// - Searching: a capture must pass the strict `acquisition` thresholds to
+///   lock
+/// - Locked: the relaxed `tracking` thresholds keep the lock; it is only
+///   released after `unlock_after` consecutive captures fail them
+///
+/// Lock and unlock are reported once per transition, returned from
+/// `update` or delivered to an `Observer<SyncLockEvent>` (any
+/// `FnMut(&SyncLockEvent)`) with `update_observed`, the same way
+/// `InputHealthMonitor` reports its alerts.
+
+use std::fmt;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::config::ModemConfig;
+use crate::fft_correlation::FftBackend;
+use crate::modulation::{measure_sync, SyncMetrics, SyncThresholds};
+use crate::observer::Observer;
+use crate::sync_ambiguity::resolve_sync_ambiguity;
+use crate::wavelet::generate_bach_preamble_with_config;
+
+/// Thresholds and release delay of a `SyncLock`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncLockConfig {
+    /// Thresholds to lock on a preamble while searching
+    pub acquisition: SyncThresholds,
+
+    /// Thresholds that keep the lock (at most the acquisition ones)
+    pub tracking: SyncThresholds,
+
+    /// Consecutive captures failing `tracking` before the lock is released
+    pub unlock_after: usize,
+}
+
+impl Default for SyncLockConfig {
+    /// Strict acquisition (noise peaks stay near 20 peak-to-noise), default
+    /// -30 dB tracking
+    fn default() -> Self {
+        Self {
+            acquisition: SyncThresholds { min_peak_to_noise: 50.0, ..SyncThresholds::default() },
+            tracking: SyncThresholds::default(),
+            unlock_after: 3,
+        }
+    }
+}
+
+/// Lock state of a monitored channel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockState {
+    Searching,
+    Locked,
+}
+
+/// Lock transition
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyncLockEvent {
+    /// A capture passed the acquisition thresholds
+    Locked { metrics: SyncMetrics },
+
+    /// `misses` consecutive captures failed the tracking thresholds
+    Unlocked { misses: usize },
+}
+
+impl fmt::Display for SyncLockEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncLockEvent::Locked { metrics } => write!(
+                f,
+                "sync locked at {} (correlation {:.4}, peak/noise {:.1})",
+                metrics.position, metrics.correlation, metrics.peak_to_noise
+            ),
+            SyncLockEvent::Unlocked { misses } => write!(f, "sync lost after {} missed captures", misses),
+        }
+    }
+}
+
+/// Outcome of one capture
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncLockUpdate {
+    /// The capture counts as a detection under the thresholds in force
+    pub detected: bool,
+
+    /// Lock transition caused by the capture
+    pub event: Option<SyncLockEvent>,
+}
+
+/// Two-threshold sync lock of one monitored channel
+#[derive(Clone, Debug)]
+pub struct SyncLock {
+    pub config: SyncLockConfig,
+    state: LockState,
+    misses: usize,
+}
+
+impl SyncLock {
+    pub fn new(config: SyncLockConfig) -> Self {
+        assert!(
+            config.tracking.min_correlation <= config.acquisition.min_correlation
+                && config.tracking.min_peak_to_noise <= config.acquisition.min_peak_to_noise,
+            "Tracking thresholds must not exceed the acquisition thresholds"
+        );
+        assert!(config.unlock_after >= 1, "unlock_after must be at least 1");
+        Self { config, state: LockState::Searching, misses: 0 }
+    }
+
+    pub fn state(&self) -> LockState {
+        self.state
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.state == LockState::Locked
+    }
+
+    /// Consecutive captures that failed the tracking thresholds while locked
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Thresholds the next capture is judged by
+    pub fn thresholds(&self) -> &SyncThresholds {
+        match self.state {
+            LockState::Searching => &self.config.acquisition,
+            LockState::Locked => &self.config.tracking,
+        }
+    }
+
+    /// Judge one capture's preamble metrics (None = no preamble measured)
+    pub fn update(&mut self, metrics: Option<&SyncMetrics>) -> SyncLockUpdate {
+        let detected = metrics.is_some_and(|m| self.thresholds().accepts(m));
+        let event = match (self.state, metrics) {
+            (LockState::Searching, Some(metrics)) if detected => {
+                self.state = LockState::Locked;
+                self.misses = 0;
+                Some(SyncLockEvent::Locked { metrics: *metrics })
+            }
+            (LockState::Locked, _) if detected => {
+                self.misses = 0;
+                None
+            }
+            (LockState::Locked, _) => {
+                self.misses += 1;
+                if self.misses >= self.config.unlock_after {
+                    self.state = LockState::Searching;
+                    let misses = std::mem::take(&mut self.misses);
+                    Some(SyncLockEvent::Unlocked { misses })
+                } else {
+                    None
+                }
+            }
+            (LockState::Searching, _) => None,
+        };
+        SyncLockUpdate { detected, event }
+    }
+
+    /// Judge one capture, delivering a lock transition to `observer`;
+    /// returns whether the capture counts as a detection
+    pub fn update_observed<O: Observer<SyncLockEvent>>(&mut self, metrics: Option<&SyncMetrics>, observer: &mut O) -> bool {
+        let update = self.update(metrics);
+        if let Some(event) = &update.event {
+            observer.notify(event);
+        }
+        update.detected
+    }
+}
+
+impl Default for SyncLock {
+    fn default() -> Self {
+        Self::new(SyncLockConfig::default())
+    }
+}
+
+/// Data start of one monitored capture under the lock's thresholds
+///
+/// Measures the preamble peak, updates `lock` (reporting transitions to
+/// `observer`) and, if the capture counts as a detection, resolves the
+/// sweep-cycle ambiguity like `synchronize_data_start_with_thresholds`.
+///
+/// ⚠️ **SYNC POINT**: Downloads the peak metrics
+pub fn synchronize_data_start_tracked<B: Backend + FftBackend, O: Observer<SyncLockEvent>>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    config: &ModemConfig,
+    lock: &mut SyncLock,
+    observer: &mut O,
+) -> Option<usize> {
+    let metrics = measure_sync::<B>(device, signal, config);
+    if !lock.update_observed(metrics.as_ref(), observer) {
+        return None;
+    }
+    let preamble = generate_bach_preamble_with_config::<B>(device, config);
+    metrics.map(|m| resolve_sync_ambiguity(device, signal, &preamble, m.position, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::{gaussian_noise, SplitMix64};
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::TestBackend;
+
+    fn metrics(peak_to_noise: f32) -> SyncMetrics {
+        SyncMetrics { position: 100, correlation: 0.5, peak_to_noise }
+    }
+
+    #[test]
+    fn test_hysteresis_prevents_flapping() {
+        let mut lock = SyncLock::default();
+        let mut events = Vec::new();
+        let mut observer = |event: &SyncLockEvent| events.push(event.clone());
+
+        // Marginal peaks don't acquire
+        assert!(!lock.update_observed(Some(&metrics(20.0)), &mut observer));
+        assert!(!lock.update_observed(None, &mut observer));
+        assert_eq!(lock.state(), LockState::Searching);
+
+        // A strong one does; marginal ones then keep the lock
+        assert!(lock.update_observed(Some(&metrics(80.0)), &mut observer));
+        for pn in [20.0, 1.0, 20.0, 0.5, 0.5, 5.0] {
+            lock.update_observed(Some(&metrics(pn)), &mut observer);
+            assert!(lock.is_locked());
+        }
+
+        // Three misses in a row release it, once
+        for _ in 0..3 {
+            assert!(!lock.update_observed(Some(&metrics(0.5)), &mut observer));
+        }
+        assert_eq!(lock.state(), LockState::Searching);
+        assert!(!lock.update_observed(Some(&metrics(20.0)), &mut observer));
+
+        assert_eq!(events, vec![
+            SyncLockEvent::Locked { metrics: metrics(80.0) },
+            SyncLockEvent::Unlocked { misses: 3 },
+        ]);
+    }
+
+    #[test]
+    fn test_tracked_sync_locks_on_transmission() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, b"LOCK", true, &config);
+        let noise = gaussian_noise::<TestBackend, _>(&device, tx.dims()[0], 0.1, &mut SplitMix64::new(11));
+
+        let mut lock = SyncLock::default();
+        let mut events = Vec::new();
+        let mut observer = |event: &SyncLockEvent| events.push(event.clone());
+
+        assert_eq!(synchronize_data_start_tracked::<TestBackend, _>(&device, &noise, &config, &mut lock, &mut observer), None);
+        let start = synchronize_data_start_tracked::<TestBackend, _>(&device, &(tx + noise.clone()), &config, &mut lock, &mut observer);
+        assert_eq!(start, Some(generate_bach_preamble_with_config::<TestBackend>(&device, &config).dims()[0]));
+        assert!(lock.is_locked());
+
+        assert!(matches!(events.as_slice(), [SyncLockEvent::Locked { .. }]));
+    }
+}
@@ -0,0 +1,250 @@
+/// Fractional Symbol Timing Recovery
+///
+/// Sync places the data start to the nearest sample and the windows follow
+/// at whole-sample steps. Half a sample of error is already 0.46 rad at the
+/// top tone (2π·1175 Hz·0.5/8000), and a drifted or resampled recording
+/// adds error that grows across the frame.
+///
+/// Timing recovery in the manner of Gardner, per symbol:
+/// - Timing error detector: each symbol's matched filter runs on its window
+///   and on windows `symbol / TIMING_PROBE_FRACTION` samples early and
+///   late. The symbols have Gaussian envelopes, so the log magnitude of the
+///   correlation is a parabola in the shift and the three points give the
+///   peak offset in samples, whatever the data phase (the DPSK data leaves
+///   the magnitudes alone).
+/// - Loop filter: a second-order (proportional-integral) `TimingLoop`
+///   smooths the per-symbol measurements and follows a constant clock rate
+///   without lag. Erased and faded symbols are coasted over on the rate.
+/// - Interpolator: the windows are re-cut at their fractional starts by
+///   linear interpolation between neighbouring samples.
+///
+/// All three correlation batches run on the device with one download; the
+/// loop runs on the host. Opt-in with `ModemConfig::with_timing_recovery`,
+/// used by the soft and hard-decision demodulators.
+
+use burn::tensor::{Tensor, Int, backend::Backend};
+use crate::complex::ComplexTensor;
+
+/// Early/late probe distance as a fraction of a symbol (1/8)
+pub const TIMING_PROBE_FRACTION: usize = 8;
+
+/// Proportional gain of the default loop
+pub const DEFAULT_TIMING_LOOP_GAIN: f64 = 0.1;
+
+/// Symbols whose on-time magnitude is below this share of the median don't
+/// steer the loop
+const MIN_TIMING_MAGNITUDE: f32 = 0.3;
+
+/// Second-order timing loop: offset and its rate per symbol
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingLoop {
+    /// Proportional gain (share of each error taken into the offset)
+    pub alpha: f64,
+
+    /// Integral gain (share taken into the rate)
+    pub beta: f64,
+
+    /// Offset predicted for the next symbol (samples)
+    offset: f64,
+
+    /// Offset change per symbol (samples)
+    rate: f64,
+}
+
+impl Default for TimingLoop {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMING_LOOP_GAIN)
+    }
+}
+
+impl TimingLoop {
+    /// Critically damped loop with proportional gain `alpha`
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "Loop gain must be in (0, 1]");
+        Self { alpha, beta: alpha * alpha / 4.0, offset: 0.0, rate: 0.0 }
+    }
+
+    /// Offset of the current symbol given its measured offset (None =
+    /// coast on the rate); advances to the next symbol
+    pub fn update(&mut self, measured: Option<f64>) -> f64 {
+        if let Some(measured) = measured {
+            let error = measured - self.offset;
+            self.offset += self.alpha * error;
+            self.rate += self.beta * error;
+        }
+        let current = self.offset;
+        self.offset += self.rate;
+        current
+    }
+
+    /// Offset change per symbol (samples)
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+/// Peak offset (samples) of a Gaussian envelope sampled `delta` early, on
+/// time and late; None unless the center is a maximum
+pub fn timing_error(early: f32, center: f32, late: f32, delta: f64) -> Option<f64> {
+    if early <= 0.0 || center <= 0.0 || late <= 0.0 {
+        return None;
+    }
+    let (early, center, late) = ((early as f64).ln(), (center as f64).ln(), (late as f64).ln());
+    let curvature = early - 2.0 * center + late;
+    if curvature >= 0.0 {
+        return None;
+    }
+    Some((0.5 * delta * (early - late) / curvature).clamp(-delta, delta))
+}
+
+/// Symbol windows of `signal` at `starts` + `shift`, zeros where they run
+/// off it: [num_symbols, symbol_len]
+fn shifted_windows<B: Backend>(signal: &Tensor<B, 1>, starts: &[isize], shift: isize, symbol_len: usize) -> Tensor<B, 2> {
+    let len = signal.dims()[0] as isize;
+    let windows = starts.iter()
+        .map(|&start| {
+            let start = start + shift;
+            if start >= 0 && start + symbol_len as isize <= len {
+                signal.clone().slice([start as usize..start as usize + symbol_len])
+            } else {
+                Tensor::zeros([symbol_len], &signal.device())
+            }
+        })
+        .collect();
+    Tensor::stack(windows, 0)
+}
+
+/// Fractional offset (samples) of every symbol window at `starts`, tracked
+/// by a `TimingLoop`
+///
+/// `bank` is the receive filter bank, `melody` each symbol's tone. Symbols
+/// that aren't `valid` or barely above the others' median don't steer.
+///
+/// ⚠️ **SYNC POINT**: Downloads three magnitudes per symbol
+pub fn recover_symbol_timing<B: Backend>(
+    device: &B::Device,
+    signal: &Tensor<B, 1>,
+    starts: &[isize],
+    valid: &[bool],
+    bank: &ComplexTensor<B, 2>,
+    melody: &[usize],
+    timing_loop: &mut TimingLoop,
+) -> Vec<f64> {
+    let num_symbols = starts.len();
+    let symbol_len = bank.re.dims()[1];
+    let delta = (symbol_len / TIMING_PROBE_FRACTION) as isize;
+
+    // Each symbol against its own tone, early / on time / late
+    let indices: Vec<i32> = melody.iter().map(|&i| i as i32).collect();
+    let indices = Tensor::<B, 1, Int>::from_ints(indices.as_slice(), device);
+    let filters = bank.clone().map(|b| b.select(0, indices.clone()));
+    let magnitudes: Vec<f32> = Tensor::cat(
+        [-delta, 0, delta].iter()
+            .map(|&shift| {
+                let windows = shifted_windows(signal, starts, shift, symbol_len);
+                filters.clone().map(|f| (windows.clone() * f).sum_dim(1).reshape([num_symbols])).abs()
+            })
+            .collect(),
+        0,
+    ).into_data().to_vec().unwrap();
+    let (early, rest) = magnitudes.split_at(num_symbols);
+    let (center, late) = rest.split_at(num_symbols);
+
+    let mut sorted: Vec<f32> = center.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let floor = MIN_TIMING_MAGNITUDE * sorted[num_symbols / 2];
+
+    (0..num_symbols)
+        .map(|k| {
+            let measured = (valid[k] && center[k] > floor)
+                .then(|| timing_error(early[k], center[k], late[k], delta as f64))
+                .flatten();
+            timing_loop.update(measured)
+        })
+        .collect()
+}
+
+/// Symbol windows re-cut at `starts` + `offsets` by linear interpolation:
+/// [num_symbols, symbol_len]
+///
+/// Windows that would run off the signal stay where they were (`fallback`).
+///
+/// **NO SYNC POINT**
+pub fn interpolate_symbols<B: Backend>(
+    signal: &Tensor<B, 1>,
+    starts: &[isize],
+    offsets: &[f64],
+    fallback: Tensor<B, 2>,
+) -> Tensor<B, 2> {
+    let len = signal.dims()[0] as isize;
+    let symbol_len = fallback.dims()[1];
+    let windows = starts.iter()
+        .zip(offsets)
+        .enumerate()
+        .map(|(k, (&start, &offset))| {
+            let whole = offset.floor();
+            let fraction = (offset - whole) as f32;
+            let first = start + whole as isize;
+            if first >= 0 && first + symbol_len as isize + 1 <= len {
+                let first = first as usize;
+                signal.clone().slice([first..first + symbol_len]).mul_scalar(1.0 - fraction)
+                    + signal.clone().slice([first + 1..first + 1 + symbol_len]).mul_scalar(fraction)
+            } else {
+                fallback.clone().slice([k..k + 1, 0..symbol_len]).reshape([symbol_len])
+            }
+        })
+        .collect();
+    Tensor::stack(windows, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::duplex::resample_linear;
+    use crate::modulation::{demodulate_fhdpsk_ex_with_config, demodulate_fhdpsk_soft_erasures_with_config, encode_bits, modulate_fhdpsk_with_config};
+    use crate::wavelet::FS;
+    use crate::TestBackend;
+
+    #[test]
+    fn test_error_detector_and_loop() {
+        // Gaussian envelope peaking 3 samples late
+        let envelope = |t: f64| (-(t - 3.0).powi(2) / (2.0 * 130.0f64.powi(2))).exp() as f32;
+        let error = timing_error(envelope(-100.0), envelope(0.0), envelope(100.0), 100.0).unwrap();
+        assert!((error - 3.0).abs() < 1e-3, "{}", error);
+        assert_eq!(timing_error(1.0, 0.5, 1.0, 100.0), None);
+
+        // A clock ramp of 0.1 sample per symbol is followed without lag
+        let mut timing_loop = TimingLoop::default();
+        let offsets: Vec<f64> = (0..300).map(|k| timing_loop.update(Some(0.1 * k as f64))).collect();
+        assert!((offsets[299] - 29.9).abs() < 0.05, "{}", offsets[299]);
+        assert!((timing_loop.rate() - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_resampled_recording_decodes() {
+        let device = Default::default();
+        let data = b"Off-grid clock";
+        let bits = encode_bits(data);
+        let base = ModemConfig::default();
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, data, true, &base);
+        let samples: Vec<f32> = tx.into_data().to_vec().unwrap();
+
+        // Recorded 150 ppm fast: symbols drift 0.12 samples each
+        let drifted = resample_linear(&samples, FS, FS * (1.0 + 150e-6));
+        let rx = Tensor::<TestBackend, 1>::from_floats(drifted.as_slice(), &device);
+
+        let errors = |config: &ModemConfig| {
+            let llrs: Vec<f32> = demodulate_fhdpsk_soft_erasures_with_config::<TestBackend>(&device, &rx, true, config, bits.len())
+                .into_data().to_vec().unwrap();
+            bits.iter().zip(&llrs).filter(|(&b, &l)| (l <= 0.0) as u8 != b).count()
+        };
+        assert!(errors(&base) > 0);
+        assert_eq!(errors(&base.clone().with_timing_recovery(true)), 0);
+
+        // The hard-decision demodulator re-cuts its windows the same way
+        let recovered = base.clone().with_timing_recovery(true);
+        let decoded = demodulate_fhdpsk_ex_with_config::<TestBackend>(&device, &rx, true, &recovered);
+        assert_eq!(&decoded[..data.len()], data);
+    }
+}
@@ -0,0 +1,121 @@
+/// Tone Mapping (Natural / Gray)
+///
+/// Tone-keyed modes (noncoherent M-FSK, multi-tone) carry log2(num_tones)
+/// bits per symbol in the choice of tone. On a fading, drifting channel the
+/// typical decision error lands on a neighbouring tone; with Gray mapping
+/// neighbouring tones differ in exactly one bit, so that error costs one bit
+/// instead of up to log2(num_tones).
+///
+/// The mapping lives on `ModemConfig` next to the tone alphabet and hopping
+/// pattern. The FH-DPSK modes carry data in phase and ignore it; tone-keyed
+/// receivers turn per-tone energies into bit LLRs with `tone_llrs`, which
+/// reads the bit labels through the same mapping.
+
+/// Data value <-> tone index assignment
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+    /// Value v on tone v
+    #[default]
+    Natural,
+
+    /// Value v on tone gray⁻¹(v): adjacent tones differ in one bit
+    Gray,
+}
+
+impl ToneMapping {
+    /// Tone index carrying data `value`
+    pub fn tone_for_value(&self, value: usize) -> usize {
+        match self {
+            ToneMapping::Natural => value,
+            ToneMapping::Gray => gray_decode(value),
+        }
+    }
+
+    /// Data value carried by tone `tone`
+    pub fn value_for_tone(&self, tone: usize) -> usize {
+        match self {
+            ToneMapping::Natural => tone,
+            ToneMapping::Gray => gray_encode(tone),
+        }
+    }
+}
+
+/// Binary-reflected Gray code of `n`
+pub fn gray_encode(n: usize) -> usize {
+    n ^ (n >> 1)
+}
+
+/// Inverse of `gray_encode`
+pub fn gray_decode(g: usize) -> usize {
+    let mut n = g;
+    let mut shift = g >> 1;
+    while shift != 0 {
+        n ^= shift;
+        shift >>= 1;
+    }
+    n
+}
+
+/// Max-log bit LLRs of one tone-keyed symbol (positive -> bit 0, MSB first)
+///
+/// `energies` holds the noncoherent matched-filter energy |z|² of every tone
+/// (a power of two), `noise_energy` the expected energy of a tone without
+/// signal. Under the square-law approximation tone k has log-likelihood
+/// energies[k] / noise_energy; each bit's LLR is the best tone whose value
+/// has a 0 there minus the best tone with a 1.
+pub fn tone_llrs(energies: &[f32], mapping: ToneMapping, noise_energy: f32) -> Vec<f32> {
+    let num_tones = energies.len();
+    assert!(num_tones.is_power_of_two() && num_tones >= 2, "tone count must be a power of two");
+    let bits = num_tones.trailing_zeros() as usize;
+    let scale = 1.0 / noise_energy.max(1e-12);
+
+    (0..bits)
+        .map(|b| {
+            let mask = 1 << (bits - 1 - b);
+            let (mut best0, mut best1) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+            for (tone, &energy) in energies.iter().enumerate() {
+                let metric = energy * scale;
+                if mapping.value_for_tone(tone) & mask == 0 {
+                    best0 = best0.max(metric);
+                } else {
+                    best1 = best1.max(metric);
+                }
+            }
+            best0 - best1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gray_neighbours_differ_in_one_bit() {
+        for num_tones in [8usize, 16, 32] {
+            for tone in 0..num_tones {
+                let gray = ToneMapping::Gray;
+                assert_eq!(gray.tone_for_value(gray.value_for_tone(tone)), tone);
+                if tone + 1 < num_tones {
+                    let diff = gray.value_for_tone(tone) ^ gray.value_for_tone(tone + 1);
+                    assert_eq!(diff.count_ones(), 1);
+                }
+            }
+        }
+
+        // Value 5 (101) sent, energy leaks into the next tone up
+        for (mapping, max_errors) in [(ToneMapping::Gray, 1), (ToneMapping::Natural, 2)] {
+            let sent = mapping.tone_for_value(5);
+            let mut energies = vec![1.0f32; 8];
+            energies[sent] = 6.0;
+            energies[sent + 1] = 9.0;
+
+            let llrs = tone_llrs(&energies, mapping, 1.0);
+            let errors = llrs.iter()
+                .enumerate()
+                .filter(|&(b, &l)| (l < 0.0) != ((5 >> (2 - b)) & 1 == 1))
+                .count();
+            assert_eq!(errors, max_errors, "{:?}", mapping);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+/// Tone Plans
+///
+/// The carrier frequencies of the tone alphabet. The built-in plans are the
+/// C-Major tables in `wavelet` (8 and 16 tones) and the chromatic 32-tone
+/// table; a `TonePlan` replaces them with another scale or an arbitrary
+/// list, e.g. to fit a narrow-band allocation.
+///
+/// Scales are built from a root and a repeating pattern of semitone steps
+/// (`MAJOR_STEPS`, `NATURAL_MINOR_STEPS`, `CHROMATIC_STEPS`) in equal
+/// temperament. The tone count must still be one of `SUPPORTED_TONE_COUNTS`:
+/// the differential lag, interleaver width and hopping patterns follow it.
+///
+/// Plans take effect through `ModemConfig::with_tone_plan`; the transmitter
+/// and receiver must use the same plan, it is not signalled on the air.
+
+use std::fmt;
+
+use crate::config::SUPPORTED_TONE_COUNTS;
+use crate::wavelet::{BACH_FREQUENCIES, BACH_FREQUENCIES_8, BACH_FREQUENCIES_32, FS};
+
+/// Middle C, the root of the built-in plans (Hz)
+pub const C4_HZ: f64 = 261.63;
+
+/// Major scale (Ionian) in semitones
+pub const MAJOR_STEPS: [u32; 7] = [2, 2, 1, 2, 2, 2, 1];
+
+/// Natural minor scale (Aeolian) in semitones
+pub const NATURAL_MINOR_STEPS: [u32; 7] = [2, 1, 2, 2, 1, 2, 2];
+
+/// Chromatic scale in semitones
+pub const CHROMATIC_STEPS: [u32; 1] = [1];
+
+/// Invalid tone plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum TonePlanError {
+    /// Tone count outside `SUPPORTED_TONE_COUNTS`
+    UnsupportedCount(usize),
+
+    /// Frequencies not strictly ascending at this index
+    NotAscending { index: usize },
+
+    /// Frequency at or below 0 Hz or at or above Nyquist
+    OutOfBand { index: usize, frequency: f64 },
+}
+
+impl fmt::Display for TonePlanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TonePlanError::UnsupportedCount(n) => {
+                write!(f, "unsupported tone count {} (expected one of {:?})", n, SUPPORTED_TONE_COUNTS)
+            }
+            TonePlanError::NotAscending { index } => {
+                write!(f, "tone {} is not above tone {}", index, index - 1)
+            }
+            TonePlanError::OutOfBand { index, frequency } => {
+                write!(f, "tone {} at {} Hz is outside 0-{} Hz", index, frequency, FS / 2.0)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TonePlanError {}
+
+/// Carrier frequencies of a tone alphabet, ascending
+#[derive(Clone, Debug, PartialEq)]
+pub struct TonePlan {
+    frequencies: Vec<f64>,
+}
+
+impl TonePlan {
+    /// Plan from an explicit frequency list (Hz)
+    pub fn custom(frequencies: Vec<f64>) -> Result<Self, TonePlanError> {
+        if !SUPPORTED_TONE_COUNTS.contains(&frequencies.len()) {
+            return Err(TonePlanError::UnsupportedCount(frequencies.len()));
+        }
+        for (index, &frequency) in frequencies.iter().enumerate() {
+            if !(frequency > 0.0 && frequency < FS / 2.0) {
+                return Err(TonePlanError::OutOfBand { index, frequency });
+            }
+            if index > 0 && frequency <= frequencies[index - 1] {
+                return Err(TonePlanError::NotAscending { index });
+            }
+        }
+        Ok(Self { frequencies })
+    }
+
+    /// Built-in plan of an alphabet size (C-Major for 8 and 16, chromatic for 32)
+    pub fn builtin(num_tones: usize) -> Result<Self, TonePlanError> {
+        let frequencies = match num_tones {
+            8 => BACH_FREQUENCIES_8.to_vec(),
+            16 => BACH_FREQUENCIES.to_vec(),
+            32 => BACH_FREQUENCIES_32.to_vec(),
+            n => return Err(TonePlanError::UnsupportedCount(n)),
+        };
+        Ok(Self { frequencies })
+    }
+
+    /// Equal-tempered scale from `root_hz`, cycling through `steps` (semitones)
+    pub fn scale(root_hz: f64, steps: &[u32], num_tones: usize) -> Result<Self, TonePlanError> {
+        assert!(!steps.is_empty() && steps.iter().all(|&s| s > 0), "Scale steps must be positive semitones");
+        let mut semitones = 0;
+        let frequencies = (0..num_tones)
+            .map(|i| {
+                if i > 0 {
+                    semitones += steps[(i - 1) % steps.len()];
+                }
+                root_hz * 2f64.powf(semitones as f64 / 12.0)
+            })
+            .collect();
+        Self::custom(frequencies)
+    }
+
+    /// Natural minor scale from C4
+    pub fn minor(num_tones: usize) -> Result<Self, TonePlanError> {
+        Self::scale(C4_HZ, &NATURAL_MINOR_STEPS, num_tones)
+    }
+
+    /// Chromatic scale from C4
+    pub fn chromatic(num_tones: usize) -> Result<Self, TonePlanError> {
+        Self::scale(C4_HZ, &CHROMATIC_STEPS, num_tones)
+    }
+
+    /// Carrier frequencies (Hz), one per tone
+    pub fn frequencies(&self) -> &[f64] {
+        &self.frequencies
+    }
+
+    /// Number of tones
+    pub fn len(&self) -> usize {
+        self.frequencies.len()
+    }
+
+    /// Always false: a valid plan has at least 8 tones
+    pub fn is_empty(&self) -> bool {
+        self.frequencies.is_empty()
+    }
+
+    /// Closest spacing between neighbouring tones (Hz)
+    ///
+    /// Compare it with `ModemConfig::tone_bandwidth`: tones closer than
+    /// about one bandwidth leak into each other's matched filters.
+    pub fn min_spacing_hz(&self) -> f64 {
+        self.frequencies.windows(2).map(|w| w[1] - w[0]).fold(f64::INFINITY, f64::min)
+    }
+}
+
+impl Default for TonePlan {
+    fn default() -> Self {
+        Self { frequencies: BACH_FREQUENCIES.to_vec() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scales_match_builtin_tables() {
+        // The built-in tables are these scales, rounded to 0.01 Hz from A4
+        for (scale, builtin) in [
+            (TonePlan::scale(C4_HZ, &MAJOR_STEPS, 16).unwrap(), TonePlan::builtin(16).unwrap()),
+            (TonePlan::chromatic(32).unwrap(), TonePlan::builtin(32).unwrap()),
+        ] {
+            for (a, b) in scale.frequencies().iter().zip(builtin.frequencies()) {
+                assert!((a - b).abs() < 0.05, "{} vs {}", a, b);
+            }
+        }
+
+        // Minor: E-flat instead of E
+        let minor = TonePlan::minor(8).unwrap();
+        assert!((minor.frequencies()[2] - 311.13).abs() < 0.02);
+        assert!((minor.frequencies()[7] - 2.0 * C4_HZ).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_custom_validation() {
+        let plan = TonePlan::custom((0..8).map(|i| 1000.0 + 50.0 * i as f64).collect()).unwrap();
+        assert_eq!(plan.len(), 8);
+        assert!((plan.min_spacing_hz() - 50.0).abs() < 1e-9);
+
+        assert_eq!(TonePlan::custom(vec![500.0; 4]), Err(TonePlanError::UnsupportedCount(4)));
+        assert_eq!(TonePlan::custom(vec![500.0; 8]), Err(TonePlanError::NotAscending { index: 1 }));
+        assert!(matches!(
+            TonePlan::scale(3000.0, &MAJOR_STEPS, 8),
+            Err(TonePlanError::OutOfBand { index: 3, .. })
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+/// Receiver Tuning Profiles
+///
+/// The receive chain has a handful of knobs that used to be hand-picked
+/// constants: preamble detection thresholds, LLR scaling, the polar decoder
+/// (list decoding or BP with an iteration count) and RAKE combining. A
+/// `ReceiverTuning` bundles them; `ReceiverPoolConfig::tuning` applies it in
+/// `decode_capture`, and `autotune` searches it against a labelled corpus.
+///
+/// Profiles are stored as `key = value` text, like `AudioSettings`:
+///
+/// ```text
+/// # BachModem receiver profile
+/// sync_min_correlation = 0.025
+/// sync_min_peak_to_noise = 1.3
+/// llr_scale = 1
+/// bp_iterations = 0
+/// rake_fingers = 0
+/// ```
+///
+/// The defaults reproduce the untuned receiver.
+
+use std::fmt;
+use std::path::Path;
+use crate::modulation::SyncThresholds;
+
+/// Longest RAKE finger delay searched after the preamble (samples, 25 ms)
+pub const RAKE_MAX_DELAY: usize = 200;
+
+/// Tunable receive chain parameters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReceiverTuning {
+    pub sync: SyncThresholds,
+
+    /// Factor on the LLRs ahead of the polar decoder
+    ///
+    /// List decoding only ranks paths and is scale-invariant; BP is not.
+    pub llr_scale: f32,
+
+    /// BP decoder iterations, 0 = list decoding (`ReceiverPoolConfig::list_size`)
+    pub bp_iterations: usize,
+
+    /// RAKE fingers combined after sync, 0 = no RAKE
+    pub rake_fingers: usize,
+}
+
+impl Default for ReceiverTuning {
+    fn default() -> Self {
+        Self {
+            sync: SyncThresholds::default(),
+            llr_scale: 1.0,
+            bp_iterations: 0,
+            rake_fingers: 0,
+        }
+    }
+}
+
+/// Profile parse error
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProfileError {
+    /// 1-based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiver profile line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+/// Walk the `key = value` lines of a profile, trace or settings file
+///
+/// Blank and `#` lines are skipped; keys and values come trimmed. A message
+/// returned by `entry` becomes a `ProfileError` on that line.
+pub(crate) fn parse_entries(
+    text: &str,
+    mut entry: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<(), ProfileError> {
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| ProfileError { line: i + 1, message };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error("expected key = value".to_string()));
+        };
+        entry(key.trim(), value.trim()).map_err(error)?;
+    }
+    Ok(())
+}
+
+impl ReceiverTuning {
+    /// Parse `key = value` lines; `#` lines are comments, unknown keys are ignored
+    pub fn parse(text: &str) -> Result<Self, ProfileError> {
+        let mut tuning = Self::default();
+
+        parse_entries(text, |key, value| {
+            let positive = |value: &str| value.parse::<f32>().ok()
+                .filter(|v| v.is_finite() && *v > 0.0)
+                .ok_or_else(|| format!("{} must be a positive number, got '{}'", key, value));
+            let count = |value: &str| value.parse::<usize>()
+                .map_err(|_| format!("{} must be a whole number, got '{}'", key, value));
+
+            match key {
+                "sync_min_correlation" => tuning.sync.min_correlation = positive(value)?,
+                "sync_min_peak_to_noise" => tuning.sync.min_peak_to_noise = positive(value)?,
+                "llr_scale" => tuning.llr_scale = positive(value)?,
+                "bp_iterations" => tuning.bp_iterations = count(value)?,
+                "rake_fingers" => tuning.rake_fingers = count(value)?,
+                _ => {}
+            }
+            Ok(())
+        })?;
+        Ok(tuning)
+    }
+
+    /// Profile text; `comment` lines go into the header
+    pub fn to_text(&self, comment: &str) -> String {
+        let mut text = String::from("# BachModem receiver profile\n");
+        for line in comment.lines() {
+            text.push_str(&format!("# {}\n", line));
+        }
+        text.push_str(&format!("sync_min_correlation = {}\n", self.sync.min_correlation));
+        text.push_str(&format!("sync_min_peak_to_noise = {}\n", self.sync.min_peak_to_noise));
+        text.push_str(&format!("llr_scale = {}\n", self.llr_scale));
+        text.push_str(&format!("bp_iterations = {}\n", self.bp_iterations));
+        text.push_str(&format!("rake_fingers = {}\n", self.rake_fingers));
+        text
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, comment: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_text(comment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_round_trip() {
+        let tuning = ReceiverTuning {
+            sync: SyncThresholds { min_correlation: 0.031, min_peak_to_noise: 1.7 },
+            llr_scale: 2.5,
+            bp_iterations: 40,
+            rake_fingers: 3,
+        };
+        let text = tuning.to_text("autotune at -20 dB\n48/50 decoded");
+        assert!(text.contains("# 48/50 decoded\n"));
+        assert_eq!(ReceiverTuning::parse(&text), Ok(tuning));
+
+        // Missing keys keep their defaults
+        let partial = ReceiverTuning::parse("# hand-edited\nrake_fingers = 2\nfuture_key = 1\n").unwrap();
+        assert_eq!(partial, ReceiverTuning { rake_fingers: 2, ..Default::default() });
+
+        let err = ReceiverTuning::parse("llr_scale = 1\nllr_scale = -3\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(ReceiverTuning::parse("bp_iterations 40").is_err());
+    }
+}
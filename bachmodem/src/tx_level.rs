@@ -0,0 +1,322 @@
+/// Transmit Level Calibration
+///
+/// Driving an SSB transmitter into ALC compression distorts the waveform,
+/// splatters and costs SNR at the far end; driving it too softly wastes
+/// power. The calibration plays a staircase of test tones with rising
+/// amplitude and measures what comes back, either off the air through a
+/// second receiver or from the rig's monitor/ALC metering output:
+///
+/// ```text
+/// gap | tone -30 dBFS | gap | tone -28 dBFS | gap | ... | tone 0 dBFS | gap
+/// ```
+///
+/// - The capture is aligned to the staircase by its on/off pattern, so the
+///   path latency doesn't need to be known
+/// - The tone level of the middle of each step is measured with a single-bin
+///   DFT at the tone frequency
+/// - The path gain is fitted over the lower steps, where the chain is linear;
+///   the compression point is the drive where the response falls
+///   `compression_db` below that line
+/// - The recommended level sits `backoff_db` below the compression point
+///
+/// The modem normalizes each transmission to its peak and sends one tone at a
+/// time, so a test tone with the same peak has the same PEP: the recommended
+/// level applies directly as the transmit peak level (`AudioSettings::tx_level`).
+
+use crate::wavelet::FS;
+
+#[cfg(feature = "audio")]
+use crate::audio::{AudioError, AudioSettings};
+
+/// Alignment resolution (samples)
+const BLOCK: usize = 80;
+
+/// Least on/off level difference that counts as a heard staircase (dB)
+const MIN_CONTRAST_DB: f32 = 6.0;
+
+/// Fraction of each step skipped at both ends before measuring
+const STEP_GUARD: f64 = 0.2;
+
+/// Tone on/off ramp (seconds)
+const RAMP: f64 = 0.005;
+
+/// Test tone staircase and analysis settings
+#[derive(Clone, Debug)]
+pub struct LevelCalConfig {
+    /// Test tone frequency (Hz, audio)
+    pub tone_hz: f64,
+
+    /// Lowest drive level (dBFS, tone peak)
+    pub start_dbfs: f32,
+
+    /// Highest drive level (dBFS, tone peak)
+    pub stop_dbfs: f32,
+
+    /// Drive increment per step (dB)
+    pub step_db: f32,
+
+    /// Duration of each tone (seconds)
+    pub step_duration: f64,
+
+    /// Silence before and between tones (seconds)
+    pub gap: f64,
+
+    /// Gain loss that counts as compression (dB)
+    pub compression_db: f32,
+
+    /// Margin below the compression point (dB)
+    pub backoff_db: f32,
+}
+
+impl Default for LevelCalConfig {
+    fn default() -> Self {
+        Self {
+            tone_hz: 1000.0,
+            start_dbfs: -30.0,
+            stop_dbfs: 0.0,
+            step_db: 2.0,
+            step_duration: 0.4,
+            gap: 0.2,
+            compression_db: 1.0,
+            backoff_db: 1.0,
+        }
+    }
+}
+
+impl LevelCalConfig {
+    /// Drive level of every step (dBFS)
+    pub fn drive_levels(&self) -> Vec<f32> {
+        let steps = ((self.stop_dbfs - self.start_dbfs) / self.step_db).floor() as usize + 1;
+        (0..steps).map(|i| self.start_dbfs + i as f32 * self.step_db).collect()
+    }
+
+    fn step_samples(&self) -> usize {
+        (self.step_duration * FS).round() as usize
+    }
+
+    fn gap_samples(&self) -> usize {
+        (self.gap * FS).round() as usize
+    }
+
+    /// First sample of step `i` in the staircase
+    fn step_start(&self, i: usize) -> usize {
+        self.gap_samples() + i * (self.step_samples() + self.gap_samples())
+    }
+
+    /// Staircase length (samples)
+    pub fn total_samples(&self) -> usize {
+        self.step_start(self.drive_levels().len())
+    }
+}
+
+/// Measured response to one step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelPoint {
+    /// Tone peak sent (dBFS)
+    pub drive_dbfs: f32,
+
+    /// Tone peak received (dB, relative to full scale of the capture)
+    pub measured_db: f32,
+}
+
+/// Linearity analysis of a staircase
+#[derive(Clone, Debug)]
+pub struct LevelCalibration {
+    pub points: Vec<LevelPoint>,
+
+    /// Small-signal path gain (dB)
+    pub gain_db: f32,
+
+    /// Drive where the response is `compression_db` below linear (dBFS), if reached
+    pub compression_dbfs: Option<f32>,
+
+    /// Recommended transmit peak level (dBFS)
+    pub recommended_dbfs: f32,
+
+    /// Recommended transmit peak level (linear, full scale = 1.0)
+    pub recommended_scale: f32,
+}
+
+/// Test tone staircase at `FS`
+pub fn generate_level_steps(config: &LevelCalConfig) -> Vec<f32> {
+    let step_len = config.step_samples();
+    let ramp_len = ((RAMP * FS) as usize).clamp(1, step_len / 2);
+    let omega = 2.0 * std::f64::consts::PI * config.tone_hz / FS;
+    let mut signal = vec![0.0f32; config.total_samples()];
+
+    for (i, drive) in config.drive_levels().into_iter().enumerate() {
+        let amplitude = 10f64.powf(drive as f64 / 20.0);
+        let start = config.step_start(i);
+
+        for n in 0..step_len {
+            // Raised-cosine edges keep the steps free of clicks
+            let edge = n.min(step_len - 1 - n);
+            let ramp = if edge < ramp_len {
+                0.5 - 0.5 * (std::f64::consts::PI * edge as f64 / ramp_len as f64).cos()
+            } else {
+                1.0
+            };
+            signal[start + n] = (amplitude * ramp * (omega * n as f64).sin()) as f32;
+        }
+    }
+    signal
+}
+
+/// Peak amplitude of the `tone_hz` component of `samples`
+fn tone_amplitude(samples: &[f32], tone_hz: f64) -> f64 {
+    let omega = 2.0 * std::f64::consts::PI * tone_hz / FS;
+    let (re, im) = samples.iter().enumerate().fold((0.0, 0.0), |(re, im), (n, &x)| {
+        let phase = omega * n as f64;
+        (re + x as f64 * phase.cos(), im - x as f64 * phase.sin())
+    });
+    2.0 * (re * re + im * im).sqrt() / samples.len().max(1) as f64
+}
+
+fn to_db(amplitude: f64) -> f32 {
+    (20.0 * amplitude.max(1e-10).log10()) as f32
+}
+
+/// Locate the staircase in `captured` (samples at `FS`) and measure each step
+///
+/// Returns None if the capture is shorter than the staircase or no on/off
+/// pattern stands out of the noise.
+pub fn measure_level_steps(captured: &[f32], config: &LevelCalConfig) -> Option<Vec<LevelPoint>> {
+    let total = config.total_samples();
+    if captured.len() < total {
+        return None;
+    }
+
+    // Tone level per block, and which blocks of the staircase hold tone or silence
+    let block_db: Vec<f32> = captured.chunks_exact(BLOCK)
+        .map(|block| to_db(tone_amplitude(block, config.tone_hz)))
+        .collect();
+
+    let drives = config.drive_levels();
+    let schedule_blocks = total / BLOCK;
+    let mut on = Vec::new();
+    let mut off = Vec::new();
+    for i in 0..drives.len() {
+        // One block of margin on either side of each edge
+        let start = config.step_start(i).div_ceil(BLOCK) + 1;
+        let end = (config.step_start(i) + config.step_samples()) / BLOCK;
+        on.extend(start..end.saturating_sub(1));
+        off.extend((end + 1)..(config.step_start(i + 1) / BLOCK).saturating_sub(1));
+    }
+    off.extend(1..(config.gap_samples() / BLOCK).saturating_sub(1));
+
+    let mean = |blocks: &[usize], offset: usize| {
+        blocks.iter().map(|&b| block_db[offset + b]).sum::<f32>() / blocks.len().max(1) as f32
+    };
+    let (offset, contrast) = (0..=block_db.len().saturating_sub(schedule_blocks))
+        .map(|offset| (offset, mean(&on, offset) - mean(&off, offset)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if contrast < MIN_CONTRAST_DB {
+        return None;
+    }
+
+    let step_len = config.step_samples();
+    let guard = (step_len as f64 * STEP_GUARD) as usize;
+    Some(drives.iter().enumerate().map(|(i, &drive_dbfs)| {
+        let start = offset * BLOCK + config.step_start(i) + guard;
+        LevelPoint {
+            drive_dbfs,
+            measured_db: to_db(tone_amplitude(&captured[start..start + step_len - 2 * guard], config.tone_hz)),
+        }
+    }).collect())
+}
+
+/// Fit the path gain and find the compression point
+///
+/// The gain is the median of measured − drive over the lower half of the
+/// steps. The compression point is interpolated between the last step within
+/// `compression_db` of the linear response and the first beyond it. Without
+/// compression the top step is recommended. None without points.
+pub fn analyze_linearity(points: Vec<LevelPoint>, config: &LevelCalConfig) -> Option<LevelCalibration> {
+    if points.is_empty() {
+        return None;
+    }
+    let mut gains: Vec<f32> = points[..points.len().div_ceil(2)].iter()
+        .map(|p| p.measured_db - p.drive_dbfs)
+        .collect();
+    gains.sort_by(f32::total_cmp);
+    let gain_db = gains[gains.len() / 2];
+
+    let deficit = |p: &LevelPoint| p.drive_dbfs + gain_db - p.measured_db;
+    let compression_dbfs = points.iter().position(|p| deficit(p) >= config.compression_db).map(|i| {
+        if i == 0 {
+            return points[0].drive_dbfs;
+        }
+        let (low, high) = (&points[i - 1], &points[i]);
+        let frac = ((config.compression_db - deficit(low)) / (deficit(high) - deficit(low))).clamp(0.0, 1.0);
+        low.drive_dbfs + frac * (high.drive_dbfs - low.drive_dbfs)
+    });
+
+    let top = points.last().map_or(config.stop_dbfs, |p| p.drive_dbfs);
+    let recommended_dbfs = compression_dbfs
+        .map_or(top, |c| c - config.backoff_db)
+        .clamp(config.start_dbfs, top.min(0.0));
+
+    Some(LevelCalibration {
+        points,
+        gain_db,
+        compression_dbfs,
+        recommended_dbfs,
+        recommended_scale: 10f32.powf(recommended_dbfs / 20.0),
+    })
+}
+
+/// Run the staircase through the configured soundcards and analyze it
+///
+/// The output device drives the transmitter; the input device hears the
+/// result (monitor receiver or rig monitor output). Blocks for the length of
+/// the staircase plus `tail` seconds.
+#[cfg(feature = "audio")]
+pub fn calibrate_tx_level(settings: &AudioSettings, config: &LevelCalConfig, tail: f64) -> Result<LevelCalibration, AudioError> {
+    use crate::duplex::DuplexSession;
+
+    let mut session = DuplexSession::start(settings, 1.0)?;
+    session.transmit(&generate_level_steps(config));
+    std::thread::sleep(std::time::Duration::from_secs_f64(session.queued_seconds() + tail));
+
+    let (_, captured) = session.take_capture();
+    measure_level_steps(&captured, config)
+        .and_then(|points| analyze_linearity(points, config))
+        .ok_or_else(|| AudioError::Backend("no test tones in the capture".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modem_rng::splitmix64;
+
+    #[test]
+    fn test_calibration_finds_soft_clipping() {
+        let config = LevelCalConfig::default();
+        let gain = 10f32.powf(-8.0 / 20.0);
+
+        // Latency, -8 dB path, tanh limiter that saturates at 0.5, noise at -60 dBFS
+        let mut captured = vec![0.0f32; 1234];
+        captured.extend(generate_level_steps(&config).iter().map(|&x| gain * 0.5 * (x / 0.5).tanh()));
+        captured.extend(vec![0.0; 4000]);
+        for (i, s) in captured.iter_mut().enumerate() {
+            let unit = (splitmix64(i as u64) >> 11) as f32 / (1u64 << 53) as f32;
+            *s += 2e-3 * (unit - 0.5);
+        }
+
+        let points = measure_level_steps(&captured, &config).expect("staircase not found");
+        assert_eq!(points.len(), 16);
+
+        let cal = analyze_linearity(points, &config).unwrap();
+        assert!((cal.gain_db + 8.0).abs() < 0.3, "gain {}", cal.gain_db);
+
+        // The fundamental of tanh(a·sin) drops 1 dB near a = 0.7, i.e. -9 dBFS drive
+        let compression = cal.compression_dbfs.expect("no compression found");
+        assert!((-11.5..-7.5).contains(&compression), "compression at {} dBFS", compression);
+        assert!((cal.recommended_dbfs - (compression - 1.0)).abs() < 1e-4);
+
+        assert!(measure_level_steps(&captured[..1000], &config).is_none());
+        assert!(measure_level_steps(&vec![0.0; captured.len()], &config).is_none());
+        assert!(analyze_linearity(Vec::new(), &config).is_none());
+    }
+}
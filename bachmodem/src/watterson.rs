@@ -8,6 +8,7 @@
 /// Reference: ITU-R Rec. F.1487, "Testing of HF modems with bandwidths of up to about 12 kHz using ionospheric channel simulators"
 
 use burn::tensor::{Tensor, Distribution, backend::Backend};
+use crate::modem_rng::ModemRng;
 use std::f32::consts::PI;
 
 /// Watterson channel configuration
@@ -62,8 +63,35 @@ impl WattersonChannel {
         }
     }
     
-    /// Apply Watterson channel to signal
-    pub fn apply<B: Backend>(&self, device: &B::Device, signal: &Tensor<B, 1>) -> Tensor<B, 1> {
+    /// Apply the channel with fading phases drawn from `rng` (reproducible
+    /// runs with a `SimSeed` stream)
+    pub fn apply_with_rng<B: Backend, R: ModemRng + ?Sized>(&self, device: &B::Device, signal: &Tensor<B, 1>, rng: &mut R) -> Tensor<B, 1> {
+        let taps = self.draw_taps(rng);
+        self.apply_taps(device, signal, &taps)
+    }
+
+    /// Draw the fading oscillator phases of every path
+    ///
+    /// Same draws as `apply_with_rng`, so the taps of a seeded run can be
+    /// drawn again afterwards for analysis.
+    pub fn draw_taps<R: ModemRng + ?Sized>(&self, rng: &mut R) -> FadingTaps {
+        let phases = (0..self.num_paths)
+            .map(|_| {
+                (0..NUM_OSCILLATORS)
+                    .map(|_| {
+                        // Random phases for I and Q
+                        let phase_i = rng.uniform() as f32 * 2.0 * PI;
+                        let phase_q = rng.uniform() as f32 * 2.0 * PI;
+                        (phase_i, phase_q)
+                    })
+                    .collect()
+            })
+            .collect();
+        FadingTaps { phases }
+    }
+
+    /// Apply the channel with given fading taps
+    pub fn apply_taps<B: Backend>(&self, device: &B::Device, signal: &Tensor<B, 1>, taps: &FadingTaps) -> Tensor<B, 1> {
         let signal_len = signal.dims()[0];
         
         // Initialize output with zeros
@@ -75,7 +103,7 @@ impl WattersonChannel {
             let gain = self.path_gains[path_idx];
             
             // Generate Rayleigh fading for this path (Jakes model)
-            let fading = self.generate_rayleigh_fading::<B>(device, signal_len);
+            let fading = self.generate_rayleigh_fading::<B>(device, signal_len, &taps.phases[path_idx]);
             
             // Create delayed signal using pure tensor operations
             let delayed_signal = if delay == 0 {
@@ -98,16 +126,15 @@ impl WattersonChannel {
         output
     }
     
+    /// Doppler frequency of oscillator `n` (Hz)
+    fn oscillator_doppler(&self, n: usize) -> f32 {
+        self.doppler_spread * (2.0 * PI * n as f32 / NUM_OSCILLATORS as f32).cos()
+    }
+    
     /// Generate Rayleigh fading using Jakes model
-    fn generate_rayleigh_fading<B: Backend>(&self, device: &B::Device, length: usize) -> Tensor<B, 1> {
+    fn generate_rayleigh_fading<B: Backend>(&self, device: &B::Device, length: usize, phases: &[(f32, f32)]) -> Tensor<B, 1> {
         // Jakes model: sum of sinusoids with random phases
-        let num_oscillators = 16; // More = better approximation
-        let fd = self.doppler_spread;
-        
-        // Use random phases to avoid "start at 0" issue
-        // We need two independent processes for Rayleigh (I and Q)
-        // But for amplitude modulation of real signal, one process with non-zero start is enough?
-        // Actually, let's do it properly: Envelope = sqrt(I^2 + Q^2)
+        // Two independent processes (I and Q): Envelope = sqrt(I^2 + Q^2)
         
         let t = Tensor::<B, 1, burn::tensor::Int>::arange(0..length as i64, device)
             .float() / self.sample_rate;
@@ -115,20 +142,8 @@ impl WattersonChannel {
         let mut i_comp = Tensor::<B, 1>::zeros([length], device);
         let mut q_comp = Tensor::<B, 1>::zeros([length], device);
         
-        // We can't easily use rand inside the loop if we want to be pure, 
-        // but for simulation it's fine.
-        // We need to import rand.
-        let mut rng = rand::thread_rng();
-        use rand::Rng;
-        
-        for n in 0..num_oscillators {
-            // Doppler frequency
-            let fn_doppler = fd * (2.0 * PI * n as f32 / num_oscillators as f32).cos();
-            let omega = 2.0 * PI * fn_doppler;
-            
-            // Random phases for I and Q
-            let phase_i = rng.gen::<f32>() * 2.0 * PI;
-            let phase_q = rng.gen::<f32>() * 2.0 * PI;
+        for (n, &(phase_i, phase_q)) in phases.iter().enumerate() {
+            let omega = 2.0 * PI * self.oscillator_doppler(n);
             
             // I component
             let angle_i = t.clone() * omega + phase_i;
@@ -142,13 +157,86 @@ impl WattersonChannel {
         }
         
         // Normalize
-        let norm = (num_oscillators as f32).sqrt();
+        let norm = (NUM_OSCILLATORS as f32).sqrt();
         i_comp = i_comp / norm;
         q_comp = q_comp / norm;
         
         // Envelope = sqrt(I^2 + Q^2)
         (i_comp.powf_scalar(2.0) + q_comp.powf_scalar(2.0)).sqrt()
     }
+
+    /// Gain of every path at `at_time` seconds into the signal: path gain
+    /// times its fading envelope, as `apply_taps` applies it
+    pub fn tap_gains(&self, taps: &FadingTaps, at_time: f64) -> Vec<f32> {
+        let norm = (NUM_OSCILLATORS as f32).sqrt();
+        self.path_gains.iter()
+            .zip(&taps.phases)
+            .map(|(&gain, phases)| {
+                let (i, q) = phases.iter().enumerate().fold((0.0f32, 0.0f32), |(i, q), (n, &(phase_i, phase_q))| {
+                    let angle = (2.0 * std::f64::consts::PI * self.oscillator_doppler(n) as f64 * at_time) as f32;
+                    (i + (angle + phase_i).cos(), q + (angle + phase_q).cos())
+                });
+                gain * ((i / norm).powi(2) + (q / norm).powi(2)).sqrt()
+            })
+            .collect()
+    }
+
+    /// Instantaneous transfer function at `at_time` seconds into the signal,
+    /// at `frequencies` (Hz): H(f) = Σ gₚ·aₚ(t)·exp(-j2πf·dₚ/fs) over the
+    /// paths' gains, fading envelopes and delays
+    ///
+    /// The fading is slow against a symbol (Doppler spread of a few Hz at
+    /// most), so H holds for the symbols around `at_time`.
+    pub fn frequency_response(&self, taps: &FadingTaps, at_time: f64, frequencies: &[f64]) -> FrequencyResponse {
+        let gains = self.tap_gains(taps, at_time);
+        let response = frequencies.iter()
+            .map(|&f| {
+                gains.iter().zip(&self.path_delays).fold((0.0f32, 0.0f32), |(re, im), (&g, &delay)| {
+                    let phase = -2.0 * std::f64::consts::PI * f * delay as f64 / self.sample_rate as f64;
+                    (re + g * phase.cos() as f32, im + g * phase.sin() as f32)
+                })
+            })
+            .collect();
+        FrequencyResponse { frequencies: frequencies.to_vec(), response }
+    }
+}
+
+/// Fading oscillators per path
+const NUM_OSCILLATORS: usize = 16;
+
+/// One fading realization of a `WattersonChannel`: the Jakes oscillator
+/// phases (I, Q) of every path
+#[derive(Clone, Debug, PartialEq)]
+pub struct FadingTaps {
+    phases: Vec<Vec<(f32, f32)>>,
+}
+
+/// Complex channel gain at a set of frequencies
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrequencyResponse {
+    /// Frequencies (Hz)
+    pub frequencies: Vec<f64>,
+
+    /// H(f) as (re, im)
+    pub response: Vec<(f32, f32)>,
+}
+
+impl FrequencyResponse {
+    /// Power gain |H(f)|² of every frequency (dB)
+    pub fn gain_db(&self) -> Vec<f32> {
+        self.response.iter()
+            .map(|&(re, im)| 10.0 * (re * re + im * im).max(1e-12).log10())
+            .collect()
+    }
+
+    /// Indices of the frequencies more than `depth_db` below the mean power
+    /// gain of all of them
+    pub fn faded(&self, depth_db: f32) -> Vec<usize> {
+        let powers: Vec<f32> = self.response.iter().map(|&(re, im)| re * re + im * im).collect();
+        let mean = powers.iter().sum::<f32>() / powers.len().max(1) as f32;
+        let threshold = mean * 10f32.powf(-depth_db / 10.0);
+        (0..powers.len()).filter(|&i| powers[i] < threshold).collect()
+    }
 }
 
 #[cfg(test)]
@@ -160,6 +248,8 @@ mod tests {
     
     #[test]
     fn test_watterson_moderate() {
+        use crate::modem_rng::{RngStream, SimSeed};
+
         let device = Default::default();
         let channel = WattersonChannel::moderate();
         
@@ -167,11 +257,62 @@ mod tests {
         let signal = Tensor::<TestBackend, 1>::ones([16000], &device);
         
         // Apply channel
-        let output = channel.apply::<TestBackend>(&device, &signal);
+        let output = channel.apply_with_rng::<TestBackend, _>(&device, &signal, &mut SimSeed(1).rng(RngStream::Fading));
         
         // Check output has same length
         assert_eq!(output.dims()[0], 16000);
         
         println!("Watterson moderate channel test passed");
     }
+
+    #[test]
+    fn test_frequency_response_matches_faded_tone() {
+        use crate::modem_rng::{RngStream, SimSeed};
+
+        let device = Default::default();
+        let channel = WattersonChannel::gentle();
+        let taps = channel.draw_taps(&mut SimSeed(3).rng(RngStream::Fading));
+
+        // 4 ms echo: notches every 250 Hz
+        let flat = WattersonChannel { num_paths: 1, path_delays: vec![0], path_gains: vec![1.0], ..WattersonChannel::gentle() };
+        let response = channel.frequency_response(&taps, 1.0, &[500.0, 625.0]);
+        let gains = channel.tap_gains(&taps, 1.0);
+        assert!((response.response[0].0 - (gains[0] + gains[1])).abs() < 1e-4);
+        assert!((response.response[1].0 - (gains[0] - gains[1])).abs() < 1e-4);
+        assert_eq!(flat.frequency_response(&taps, 1.0, &[700.0]).response[0].1, 0.0);
+
+        // A 500 Hz tone comes out with |H(500 Hz)|
+        let samples: Vec<f32> = (0..16000).map(|t| (2.0 * PI * 500.0 * t as f32 / 8000.0).sin()).collect();
+        let tone = Tensor::<TestBackend, 1>::from_floats(samples.as_slice(), &device);
+        let faded: Vec<f32> = channel.apply_taps(&device, &tone, &taps).into_data().to_vec().unwrap();
+        let rms = (faded[7600..8400].iter().map(|x| x * x).sum::<f32>() / 800.0).sqrt();
+        let (re, im) = response.response[0];
+        let expected = (re * re + im * im).sqrt();
+        assert!((rms * 2f32.sqrt() - expected).abs() < 0.03 * expected.max(0.1), "{} vs {}", rms * 2f32.sqrt(), expected);
+    }
+
+    #[test]
+    fn test_faded_frequencies() {
+        let response = FrequencyResponse {
+            frequencies: vec![300.0, 400.0, 500.0, 600.0],
+            response: vec![(1.0, 0.0), (0.0, 1.0), (0.05, 0.0), (-0.9, 0.2)],
+        };
+        assert_eq!(response.faded(10.0), vec![2]);
+        assert!((response.gain_db()[2] + 26.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_seeded_fading_replays() {
+        use crate::modem_rng::{RngStream, SimSeed};
+
+        let device = Default::default();
+        let channel = WattersonChannel::severe();
+        let signal = Tensor::<TestBackend, 1>::ones([4000], &device);
+        let faded = |seed: u64| -> Vec<f32> {
+            channel.apply_with_rng::<TestBackend, _>(&device, &signal, &mut SimSeed(seed).rng(RngStream::Fading))
+                .into_data().to_vec().unwrap()
+        };
+        assert_eq!(faded(5), faded(5));
+        assert_ne!(faded(5), faded(6));
+    }
 }
@@ -0,0 +1,286 @@
+/// Memory-Mapped WAV Archives
+///
+/// `read_wav` decodes a whole recording into one tensor: fine for a frame,
+/// hopeless for an overnight capture (8 h at 8 kHz is 230M samples, close
+/// to 1 GB as f32 before the FFT scratch). `MappedWav` maps the file
+/// instead and converts only the windows asked for, so the resident set is
+/// a chunk, not the recording; the OS pages the file in and out.
+///
+/// The header is parsed here rather than by hound, which only streams:
+/// - PCM 16/24/32 bit and IEEE float 32 bit, plain or WAVE_FORMAT_EXTENSIBLE
+/// - multi-channel files read their first channel
+/// - a `data` size of 0xFFFFFFFF or past the end of the file (recorders
+///   that crashed, or captures above 4 GB) runs to the end of the file
+///
+/// Archive re-processing works in two passes, neither holding the recording:
+///
+/// ```text
+/// MappedWav ──► presence_timeline_mapped ──► PresenceTimeline::decode_segments
+///                 (60 s chunks)                  │
+///                                                ▼
+///           ReceiverPool ◄── DecodeSource::MappedSegment (window read by the worker)
+/// ```
+
+use std::fmt;
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+use burn::tensor::{Tensor, backend::Backend};
+use memmap2::Mmap;
+use crate::wavelet::FS;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Why a file can't be mapped as a recording
+#[derive(Debug)]
+pub enum WavMapError {
+    Io(std::io::Error),
+
+    /// Not a RIFF/WAVE file, or a chunk runs off the file
+    NotWav,
+
+    /// No `fmt ` or no `data` chunk
+    MissingChunk(&'static str),
+
+    /// Sample format other than PCM 16/24/32 or float 32
+    UnsupportedFormat { format_tag: u16, bits_per_sample: u16 },
+
+    /// Recorded at another rate than the modem's `FS`
+    SampleRate(u32),
+}
+
+impl fmt::Display for WavMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavMapError::Io(err) => write!(f, "{}", err),
+            WavMapError::NotWav => write!(f, "not a RIFF/WAVE file"),
+            WavMapError::MissingChunk(id) => write!(f, "no '{}' chunk", id),
+            WavMapError::UnsupportedFormat { format_tag, bits_per_sample } => {
+                write!(f, "unsupported sample format {} with {} bits", format_tag, bits_per_sample)
+            }
+            WavMapError::SampleRate(rate) => write!(f, "recorded at {} Hz, the modem runs at {} Hz", rate, FS),
+        }
+    }
+}
+
+impl std::error::Error for WavMapError {}
+
+impl From<std::io::Error> for WavMapError {
+    fn from(err: std::io::Error) -> Self {
+        WavMapError::Io(err)
+    }
+}
+
+/// Sample layout of a mapped recording
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WavFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+
+    /// IEEE float samples (otherwise signed PCM)
+    pub float: bool,
+}
+
+/// WAV recording mapped into memory, read a window at a time
+#[derive(Debug)]
+pub struct MappedWav {
+    map: Mmap,
+    pub format: WavFormat,
+
+    /// Byte offset of the first sample
+    data_offset: usize,
+
+    /// Bytes per frame (all channels)
+    block_align: usize,
+
+    /// Sample frames in the file
+    frames: usize,
+}
+
+impl MappedWav {
+    /// Map `path`; the recording must be at `FS`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WavMapError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only; a recording truncated by another
+        // process while mapped is outside what this reader guards against
+        let map = unsafe { Mmap::map(&file)? };
+        Self::from_map(map)
+    }
+
+    fn from_map(map: Mmap) -> Result<Self, WavMapError> {
+        if map.len() < 12 || &map[0..4] != b"RIFF" || &map[8..12] != b"WAVE" {
+            return Err(WavMapError::NotWav);
+        }
+        let u16_at = |at: usize| u16::from_le_bytes([map[at], map[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes([map[at], map[at + 1], map[at + 2], map[at + 3]]);
+
+        let mut format = None;
+        let mut data = None;
+        let mut at = 12;
+        while at + 8 <= map.len() {
+            let (id, size) = (&map[at..at + 4], u32_at(at + 4));
+            let body = at + 8;
+            if id == b"data" {
+                // Unfinalized or > 4 GB: the samples run to the end of the file
+                let end = if size == u32::MAX { map.len() } else { (body + size as usize).min(map.len()) };
+                data = Some(body..end);
+                break;
+            }
+            let end = body + size as usize;
+            if end > map.len() {
+                return Err(WavMapError::NotWav);
+            }
+            if id == b"fmt " {
+                if size < 16 {
+                    return Err(WavMapError::NotWav);
+                }
+                let mut tag = u16_at(body);
+                if tag == FORMAT_EXTENSIBLE && size >= 26 {
+                    // First two bytes of the sub-format GUID
+                    tag = u16_at(body + 24);
+                }
+                format = Some((tag, u16_at(body + 2), u32_at(body + 4), u16_at(body + 12), u16_at(body + 14)));
+            }
+            at = end + (size as usize & 1);
+        }
+
+        let (tag, channels, sample_rate, block_align, bits_per_sample) = format.ok_or(WavMapError::MissingChunk("fmt "))?;
+        let data = data.ok_or(WavMapError::MissingChunk("data"))?;
+        let float = match (tag, bits_per_sample) {
+            (FORMAT_PCM, 16 | 24 | 32) => false,
+            (FORMAT_FLOAT, 32) => true,
+            _ => return Err(WavMapError::UnsupportedFormat { format_tag: tag, bits_per_sample }),
+        };
+        if channels == 0 || (block_align as usize) < channels as usize * bits_per_sample as usize / 8 {
+            return Err(WavMapError::NotWav);
+        }
+        if sample_rate as f64 != FS {
+            return Err(WavMapError::SampleRate(sample_rate));
+        }
+
+        Ok(Self {
+            format: WavFormat { channels, sample_rate, bits_per_sample, float },
+            data_offset: data.start,
+            block_align: block_align as usize,
+            frames: (data.end - data.start) / block_align as usize,
+            map,
+        })
+    }
+
+    /// Sample frames in the recording
+    pub fn len(&self) -> usize {
+        self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    /// Recording length (seconds)
+    pub fn duration_s(&self) -> f64 {
+        self.frames as f64 / FS
+    }
+
+    /// First-channel samples of `range` in [-1, 1), cut at the end of the
+    /// recording
+    pub fn samples(&self, range: Range<usize>) -> Vec<f32> {
+        let end = range.end.min(self.frames);
+        let start = range.start.min(end);
+        (start..end)
+            .map(|frame| {
+                let at = self.data_offset + frame * self.block_align;
+                let b = &self.map[at..at + self.format.bits_per_sample as usize / 8];
+                match (self.format.float, self.format.bits_per_sample) {
+                    (true, _) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                    (false, 16) => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+                    (false, 24) => i32::from_le_bytes([0, b[0], b[1], b[2]]) as f32 / 2147483648.0,
+                    (false, _) => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
+                }
+            })
+            .collect()
+    }
+
+    /// `samples(range)` uploaded to `device`
+    pub fn window<B: Backend>(&self, device: &B::Device, range: Range<usize>) -> Tensor<B, 1> {
+        Tensor::from_floats(self.samples(range).as_slice(), device)
+    }
+}
+
+// The test recordings are written with hound
+#[cfg(all(test, feature = "wav"))]
+mod tests {
+    use super::*;
+    use crate::config::ModemConfig;
+    use crate::modulation::modulate_fhdpsk_with_config;
+    use crate::presence::{presence_timeline, presence_timeline_mapped, PresenceConfig};
+    use crate::TestBackend;
+
+    fn write_test_wav(path: &Path, samples: &[f32], spec: hound::WavSpec) {
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            for _ in 0..spec.channels {
+                match spec.sample_format {
+                    hound::SampleFormat::Float => writer.write_sample(s).unwrap(),
+                    hound::SampleFormat::Int => writer.write_sample((s * 32767.0) as i16).unwrap(),
+                }
+            }
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_windows_match_the_file() {
+        let samples: Vec<f32> = (0..10000).map(|i| ((i as f32) * 0.01).sin() * 0.5).collect();
+        let path = std::env::temp_dir().join("bachmodem_wav_mmap_test.wav");
+
+        for (channels, sample_format, bits) in [(1, hound::SampleFormat::Int, 16), (2, hound::SampleFormat::Float, 32)] {
+            let spec = hound::WavSpec { channels, sample_rate: FS as u32, bits_per_sample: bits, sample_format };
+            write_test_wav(&path, &samples, spec);
+            let wav = MappedWav::open(&path).unwrap();
+            assert_eq!(wav.len(), samples.len());
+            assert_eq!(wav.format.channels, channels);
+
+            let window = wav.samples(2500..2600);
+            assert!(window.iter().zip(&samples[2500..2600]).all(|(a, b)| (a - b).abs() < 1e-4));
+            assert_eq!(wav.samples(9990..20000).len(), 10);
+        }
+
+        let spec = hound::WavSpec { channels: 1, sample_rate: 48000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        write_test_wav(&path, &samples, spec);
+        assert!(matches!(MappedWav::open(&path), Err(WavMapError::SampleRate(48000))));
+        std::fs::write(&path, b"not a wav file").unwrap();
+        assert!(matches!(MappedWav::open(&path), Err(WavMapError::NotWav)));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_mapped_scan_matches_in_memory_scan() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_flourish_interval(16);
+        let tx = modulate_fhdpsk_with_config::<TestBackend>(&device, b"ARCHIVE", true, &config);
+        let tx_len = tx.dims()[0];
+        let gap = 70 * FS as usize;
+        let signal = Tensor::cat(vec![Tensor::zeros([gap], &device), tx.mul_scalar(0.5), Tensor::zeros([FS as usize], &device)], 0);
+        let samples: Vec<f32> = signal.into_data().to_vec().unwrap();
+
+        let path = std::env::temp_dir().join("bachmodem_wav_mmap_scan.wav");
+        let spec = hound::WavSpec { channels: 1, sample_rate: FS as u32, bits_per_sample: 32, sample_format: hound::SampleFormat::Float };
+        write_test_wav(&path, &samples, spec);
+        let wav = MappedWav::open(&path).unwrap();
+
+        let presence = PresenceConfig::default();
+        let in_memory = presence_timeline::<TestBackend>(&device, &Tensor::from_floats(samples.as_slice(), &device), &config, &presence);
+        let mapped = presence_timeline_mapped::<TestBackend>(&device, &wav, &config, &presence);
+        assert_eq!(mapped.bins.len(), in_memory.bins.len());
+        assert!(mapped.bins.iter().zip(&in_memory.bins).all(|(a, b)| (a.rho() - b.rho()).abs() < 1e-3));
+
+        // The transmission, found in the second chunk, lies inside one decode segment
+        let segments = mapped.decode_segments(0.3, &config);
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].start <= gap && segments[0].end >= gap + tx_len);
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -1,5 +1,12 @@
 use burn::tensor::{Tensor, backend::Backend};
 use std::f64::consts::PI;
+use crate::config::ModemConfig;
+use crate::tone_plan::TonePlan;
+use crate::complex::ComplexTensor;
+use bachmodem_core::scrambler::whitening_sequence;
+use crate::modem_rng::{ModemRng, SplitMix64};
+
+pub use bachmodem_core::matched_filter::{DEFAULT_WAVELET_SIGMAS, WaveletShape};
 
 /// Bach Scale Frequencies (C-Major, C4 to D6)
 pub const BACH_FREQUENCIES: [f64; 16] = [
@@ -21,22 +28,118 @@ pub const BACH_FREQUENCIES: [f64; 16] = [
     1174.66, // D6 - 0xF
 ];
 
+/// Narrowband Scale (C-Major, one octave C4 to C5)
+pub const BACH_FREQUENCIES_8: [f64; 8] = [
+    261.63,  // C4 - 0x0
+    293.66,  // D4 - 0x1
+    329.63,  // E4 - 0x2
+    349.23,  // F4 - 0x3
+    392.00,  // G4 - 0x4
+    440.00,  // A4 - 0x5
+    493.88,  // B4 - 0x6
+    523.25,  // C5 - 0x7
+];
+
+/// Wideband Scale (Chromatic, C4 to G6)
+/// 
+/// 32 diatonic notes would span more than four octaves and leave the SSB
+/// passband, so the wideband alphabet fills in the semitones instead.
+pub const BACH_FREQUENCIES_32: [f64; 32] = [
+    261.63,  // C4
+    277.18,  // C#4
+    293.66,  // D4
+    311.13,  // D#4
+    329.63,  // E4
+    349.23,  // F4
+    369.99,  // F#4
+    392.00,  // G4
+    415.30,  // G#4
+    440.00,  // A4
+    466.16,  // A#4
+    493.88,  // B4
+    523.25,  // C5
+    554.37,  // C#5
+    587.33,  // D5
+    622.25,  // D#5
+    659.25,  // E5
+    698.46,  // F5
+    739.99,  // F#5
+    783.99,  // G5
+    830.61,  // G#5
+    880.00,  // A5
+    932.33,  // A#5
+    987.77,  // B5
+    1046.50, // C6
+    1108.73, // C#6
+    1174.66, // D6
+    1244.51, // D#6
+    1318.51, // E6
+    1396.91, // F6
+    1479.98, // F#6
+    1567.98, // G6
+];
+
 /// Frequency-Hopping Pattern (Melodic Intervals)
 /// Creates pleasant musical jumps instead of linear progression
 pub const HOPPING_PATTERN: [usize; 16] = [0, 7, 4, 12, 2, 9, 5, 14, 1, 8, 3, 11, 6, 13, 10, 15];
 
+/// Narrowband Hopping Pattern (leaps of a fourth around the octave)
+pub const HOPPING_PATTERN_8: [usize; 8] = [0, 3, 6, 1, 4, 7, 2, 5];
+
+/// Wideband Hopping Pattern (circle of fifths: 7 semitones per hop)
+pub const HOPPING_PATTERN_32: [usize; 32] = [
+    0, 7, 14, 21, 28, 3, 10, 17, 24, 31, 6, 13, 20, 27, 2, 9,
+    16, 23, 30, 5, 12, 19, 26, 1, 8, 15, 22, 29, 4, 11, 18, 25,
+];
+
 /// Physical Layer Parameters
-pub const FS: f64 = 8000.0;              // Sampling frequency (Hz)
+pub const FS: f64 = 8000.0;              // Default sampling frequency (Hz), `ModemConfig::sample_rate`
 pub const SYMBOL_DURATION: f64 = 0.1;    // Symbol duration (seconds) - Fast for testing (spec: 2.0s for deep space)
 pub const PREAMBLE_NOTE_DURATION: f64 = 0.05; // Preamble note duration (seconds)
 
 /// Generates the melody hopping sequence for a given number of symbols
 pub fn get_melody_indices(num_symbols: usize) -> Vec<usize> {
+    get_hopping_indices(&HOPPING_PATTERN, num_symbols)
+}
+
+/// Repeats any hopping pattern (built-in, seeded or user-supplied) over
+/// `num_symbols` symbol slots
+pub fn get_hopping_indices(pattern: &[usize], num_symbols: usize) -> Vec<usize> {
     (0..num_symbols)
-        .map(|i| HOPPING_PATTERN[i % HOPPING_PATTERN.len()])
+        .map(|i| pattern[i % pattern.len()])
         .collect()
 }
 
+/// Pseudo-random hopping pattern over `num_tones` tones drawn from `seed`
+///
+/// A permutation of the alphabet (Fisher-Yates on a `SplitMix64` stream),
+/// so each block still visits every tone once and the lag-differential
+/// pairs stay on one tone. `SplitMix64` rather than a `rand` generator,
+/// whose output may change between releases: a seed must give the same
+/// pattern on every build of transmitter and receiver.
+pub fn seeded_hopping_pattern(num_tones: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64::new(seed);
+    let mut pattern: Vec<usize> = (0..num_tones).collect();
+    for i in (1..num_tones).rev() {
+        pattern.swap(i, rng.below(i + 1));
+    }
+    pattern
+}
+
+/// Worst-case tone collisions between two hopping patterns per block
+///
+/// The most symbol slots of one block in which both patterns sit on the
+/// same tone, over every cyclic slot offset between the two stations. A
+/// pattern against itself scores the block length at offset 0; two
+/// stations sharing a band want this low.
+pub fn hopping_cross_correlation(a: &[usize], b: &[usize]) -> usize {
+    assert_eq!(a.len(), b.len(), "Hopping patterns must have the same length");
+    (0..b.len())
+        .map(|shift| (0..a.len()).filter(|&i| a[i] == b[(i + shift) % b.len()]).count())
+        .max()
+        .unwrap_or(0)
+}
+
 /// Generates a Morlet (Gabor) wavelet
 /// 
 /// ψ(t; f, s) = A · exp(-t²/2s²) · exp(i·2πf·t)
@@ -46,14 +149,30 @@ pub fn get_melody_indices(num_symbols: usize) -> Vec<usize> {
 /// - s = duration / 6 [Wavelet width parameter, 6-sigma fits in window]
 /// - f = carrier frequency
 /// - t ∈ [-duration/2, duration/2]
+///
+/// Other widths and chirps: `shaped_wavelet`.
 pub fn morlet_wavelet<B: Backend>(
     device: &B::Device,
     frequency: f64,
     duration: f64,
     fs: f64,
+) -> (Tensor<B, 1>, Tensor<B, 1>) {
+    shaped_wavelet::<B>(device, frequency, duration, WaveletShape::default(), fs)
+}
+
+/// Generates a Morlet wavelet with an optional linear FM sweep (Gaussian chirp)
+/// 
+/// ψ(t) = A · exp(-t²/2s²) · exp(i·2π(f·t + k·t²/2)), k = sweep_hz / duration,
+/// s = duration / sigmas. `WaveletShape::default()` is `morlet_wavelet`.
+pub fn shaped_wavelet<B: Backend>(
+    device: &B::Device,
+    frequency: f64,
+    duration: f64,
+    shape: WaveletShape,
+    fs: f64,
 ) -> (Tensor<B, 1>, Tensor<B, 1>) {
     let num_samples = (duration * fs) as usize;
-    let s = duration / 6.0; // Wavelet width parameter
+    let s = duration / shape.sigmas; // Wavelet width parameter
     
     // Time vector: [-duration/2, duration/2]
     let t_values: Vec<f32> = (0..num_samples)
@@ -76,10 +195,14 @@ pub fn morlet_wavelet<B: Backend>(
         .mul_scalar(norm_factor as f32);
     
     // Oscillatory components
-    // Real: cos(2πft)
-    // Imag: sin(2πft)
+    // Real: cos(2πft + πkt²)
+    // Imag: sin(2πft + πkt²)
     let omega = 2.0 * PI * frequency;
-    let phase = t.mul_scalar(omega as f32);
+    let rate = PI * shape.sweep_hz / duration;
+    let mut phase = t.clone().mul_scalar(omega as f32);
+    if shape.sweep_hz != 0.0 {
+        phase = phase + t.powf_scalar(2.0).mul_scalar(rate as f32);
+    }
     
     let real_part = phase.clone().cos().mul(envelope.clone());
     let imag_part = phase.sin().mul(envelope);
@@ -87,21 +210,98 @@ pub fn morlet_wavelet<B: Backend>(
     (real_part, imag_part)
 }
 
+/// Receive filter bank of the data symbols: [num_tones, symbol_samples]
+/// 
+/// Conjugated wavelets with the inverse of the transmit pre-emphasis, so all
+/// tones yield comparable correlations: `bank.map(|b| symbols.matmul(b.transpose()))`
+/// correlates a batch of symbols with every tone.
+pub fn matched_filter_bank<B: Backend>(device: &B::Device, config: &ModemConfig) -> ComplexTensor<B, 2> {
+    let (real, imag): (Vec<_>, Vec<_>) = config.frequencies().iter()
+        .enumerate()
+        .map(|(i, &freq)| {
+            let (r, im) = shaped_wavelet::<B>(device, freq, config.symbol_duration, config.wavelet_shape(), config.sample_rate);
+            let inv_gain = (1.0 / config.tone_gain(i)) as f32;
+            (r.mul_scalar(inv_gain), im.neg().mul_scalar(inv_gain)) // Conjugate for correlation
+        })
+        .unzip();
+    
+    ComplexTensor::new(Tensor::stack::<2>(real, 0), Tensor::stack::<2>(imag, 0))
+}
+
 /// Generates a single symbol waveform (real part only for transmission)
+/// 
+/// `symbol_idx` indexes the tones of `plan` (`TonePlan::default()` is the
+/// 16-tone C-Major scale).
 pub fn generate_symbol<B: Backend>(
     device: &B::Device,
+    plan: &TonePlan,
     symbol_idx: usize,
     phase_offset: f64,
     duration: f64,
     fs: f64,
 ) -> Tensor<B, 1> {
-    let frequency = BACH_FREQUENCIES[symbol_idx];
-    let (real, imag) = morlet_wavelet::<B>(device, frequency, duration, fs);
+    generate_tone::<B>(device, plan.frequencies()[symbol_idx], phase_offset, duration, fs)
+}
+
+/// Generates a data symbol for a tone of the configured alphabet
+/// 
+/// Applies the configured symbol duration and per-tone gain (pre-emphasis).
+pub fn generate_symbol_with_config<B: Backend>(
+    device: &B::Device,
+    tone_idx: usize,
+    phase_offset: f64,
+    config: &ModemConfig,
+) -> Tensor<B, 1> {
+    generate_shaped_tone::<B>(
+        device,
+        config.frequencies()[tone_idx],
+        phase_offset,
+        config.tone_gain(tone_idx),
+        config.symbol_duration,
+        config.wavelet_shape(),
+        config.sample_rate,
+    )
+}
+
+/// Generates a symbol waveform at an arbitrary carrier frequency
+pub fn generate_tone<B: Backend>(
+    device: &B::Device,
+    frequency: f64,
+    phase_offset: f64,
+    duration: f64,
+    fs: f64,
+) -> Tensor<B, 1> {
+    generate_weighted_tone::<B>(device, frequency, phase_offset, 1.0, duration, fs)
+}
+
+/// Generates a symbol waveform with amplitude scaling
+pub fn generate_weighted_tone<B: Backend>(
+    device: &B::Device,
+    frequency: f64,
+    phase_offset: f64,
+    amplitude: f64,
+    duration: f64,
+    fs: f64,
+) -> Tensor<B, 1> {
+    generate_shaped_tone::<B>(device, frequency, phase_offset, amplitude, duration, WaveletShape::default(), fs)
+}
+
+/// `generate_weighted_tone` with another pulse shape (`ModemConfig::wavelet_shape`)
+pub fn generate_shaped_tone<B: Backend>(
+    device: &B::Device,
+    frequency: f64,
+    phase_offset: f64,
+    amplitude: f64,
+    duration: f64,
+    shape: WaveletShape,
+    fs: f64,
+) -> Tensor<B, 1> {
+    let (real, imag) = shaped_wavelet::<B>(device, frequency, duration, shape, fs);
     
     // Apply phase shift: wavelet * exp(i * phase_offset)
     // Real part: real * cos(phase) - imag * sin(phase)
-    let cos_phase = phase_offset.cos() as f32;
-    let sin_phase = phase_offset.sin() as f32;
+    let cos_phase = (amplitude * phase_offset.cos()) as f32;
+    let sin_phase = (amplitude * phase_offset.sin()) as f32;
     
     real.mul_scalar(cos_phase).sub(imag.mul_scalar(sin_phase))
 }
@@ -111,19 +311,47 @@ pub fn generate_symbol<B: Backend>(
 /// Sweeps UP-DOWN-UP-DOWN (4 cycles).
 /// Standard C-Major scale (Shift 0).
 pub fn generate_bach_preamble<B: Backend>(device: &B::Device) -> Tensor<B, 1> {
-    let note_duration = PREAMBLE_NOTE_DURATION;
+    generate_bach_preamble_with_config::<B>(device, &ModemConfig::default())
+}
+
+/// Generates the Bach Preamble over the configured tone alphabet
+/// 
+/// `preamble_sweeps` alternating sweeps of `preamble_note_duration` notes.
+/// With `preamble_phase_code` the notes carry the phases of
+/// `preamble_note_phases`.
+pub fn generate_bach_preamble_with_config<B: Backend>(device: &B::Device, config: &ModemConfig) -> Tensor<B, 1> {
+    let phases = preamble_note_phases(config);
+    generate_from_sequence::<B>(device, &preamble_tone_sequence(config), &phases, config.preamble_note_duration, config)
+}
+
+/// Alphabet slot of every preamble note (before `ModemConfig::transmit_tone`)
+pub fn preamble_tone_sequence(config: &ModemConfig) -> Vec<usize> {
+    let n = config.num_tones;
     let mut sequence = Vec::new();
     
-    // Cycle 1: Up (Shift 0)
-    sequence.extend(get_shifted_sweep_up(0));
-    // Cycle 2: Down (Shift 0)
-    sequence.extend(get_shifted_sweep_down(0));
-    // Cycle 3: Up (Shift 0)
-    sequence.extend(get_shifted_sweep_up(0));
-    // Cycle 4: Down (Shift 0)
-    sequence.extend(get_shifted_sweep_down(0));
+    // Up, down, up, down, ... (Shift 0)
+    for sweep in 0..config.preamble_sweeps {
+        if sweep % 2 == 0 {
+            sequence.extend(get_shifted_sweep_up(0, n));
+        } else {
+            sequence.extend(get_shifted_sweep_down(0, n));
+        }
+    }
     
-    generate_from_sequence::<B>(device, &sequence, note_duration)
+    sequence
+}
+
+/// Phase (radians) of every preamble note: 0, or π where the m-sequence is 1
+/// 
+/// All zero unless `preamble_phase_code` is set. The 127-chip sequence
+/// covers the 4 × num_tones notes of every alphabet (the 32-tone preamble
+/// wraps by one chip); longer preambles repeat it.
+pub fn preamble_note_phases(config: &ModemConfig) -> Vec<f64> {
+    let num_notes = config.preamble_sweeps * config.num_tones;
+    if !config.preamble_phase_code {
+        return vec![0.0; num_notes];
+    }
+    whitening_sequence(num_notes).into_iter().map(|chip| chip as f64 * PI).collect()
 }
 
 /// Generates Bach Flourish / Inter-amble
@@ -131,15 +359,20 @@ pub fn generate_bach_preamble<B: Backend>(device: &B::Device) -> Tensor<B, 1> {
 /// Shifted UP-DOWN sweep (Shift 8 - Dominant/Fifth).
 /// Distinct from Preamble but musically related.
 pub fn generate_bach_flourish<B: Backend>(device: &B::Device) -> Tensor<B, 1> {
-    let note_duration = PREAMBLE_NOTE_DURATION;
+    generate_bach_flourish_with_config::<B>(device, &ModemConfig::default())
+}
+
+/// Generates Bach Flourish over the configured tone alphabet (shift = half the alphabet)
+pub fn generate_bach_flourish_with_config<B: Backend>(device: &B::Device, config: &ModemConfig) -> Tensor<B, 1> {
+    let n = config.num_tones;
     let mut sequence = Vec::new();
     
-    // Up (Shift 8)
-    sequence.extend(get_shifted_sweep_up(8));
-    // Down (Shift 8)
-    sequence.extend(get_shifted_sweep_down(8));
+    // Up (Shift n/2)
+    sequence.extend(get_shifted_sweep_up(n / 2, n));
+    // Down (Shift n/2)
+    sequence.extend(get_shifted_sweep_down(n / 2, n));
     
-    generate_from_sequence::<B>(device, &sequence, note_duration)
+    generate_from_sequence::<B>(device, &sequence, &[], config.preamble_note_duration, config)
 }
 
 /// Generates Bach Post-amble
@@ -147,25 +380,30 @@ pub fn generate_bach_flourish<B: Backend>(device: &B::Device) -> Tensor<B, 1> {
 /// Shifted UP-DOWN sweep (Shift 4 - Mediant/Third).
 /// Signals end of transmission.
 pub fn generate_bach_postamble<B: Backend>(device: &B::Device) -> Tensor<B, 1> {
-    let note_duration = PREAMBLE_NOTE_DURATION;
+    generate_bach_postamble_with_config::<B>(device, &ModemConfig::default())
+}
+
+/// Generates Bach Post-amble over the configured tone alphabet (shift = quarter of the alphabet)
+pub fn generate_bach_postamble_with_config<B: Backend>(device: &B::Device, config: &ModemConfig) -> Tensor<B, 1> {
+    let n = config.num_tones;
     let mut sequence = Vec::new();
     
-    // Up (Shift 4)
-    sequence.extend(get_shifted_sweep_up(4));
-    // Down (Shift 4)
-    sequence.extend(get_shifted_sweep_down(4));
+    // Up (Shift n/4)
+    sequence.extend(get_shifted_sweep_up(n / 4, n));
+    // Down (Shift n/4)
+    sequence.extend(get_shifted_sweep_down(n / 4, n));
     
-    generate_from_sequence::<B>(device, &sequence, note_duration)
+    generate_from_sequence::<B>(device, &sequence, &[], config.preamble_note_duration, config)
 }
 
 /// Helper: Get indices for a shifted UP sweep
-fn get_shifted_sweep_up(shift: usize) -> Vec<usize> {
-    (0..16).map(|i| (i + shift) % 16).collect()
+fn get_shifted_sweep_up(shift: usize, num_tones: usize) -> Vec<usize> {
+    (0..num_tones).map(|i| (i + shift) % num_tones).collect()
 }
 
 /// Helper: Get indices for a shifted DOWN sweep
-fn get_shifted_sweep_down(shift: usize) -> Vec<usize> {
-    (0..16).rev().map(|i| (i + shift) % 16).collect()
+fn get_shifted_sweep_down(shift: usize, num_tones: usize) -> Vec<usize> {
+    (0..num_tones).rev().map(|i| (i + shift) % num_tones).collect()
 }
 
 /// Generates Bach Sweep UP (Legacy helper, kept for compatibility if needed)
@@ -173,9 +411,9 @@ fn generate_bach_sweep_up<B: Backend>(device: &B::Device, cycles: usize) -> Tens
     let note_duration = PREAMBLE_NOTE_DURATION;
     let mut sequence = Vec::new();
     for _ in 0..cycles {
-        sequence.extend(get_shifted_sweep_up(0));
+        sequence.extend(get_shifted_sweep_up(0, 16));
     }
-    generate_from_sequence::<B>(device, &sequence, note_duration)
+    generate_from_sequence::<B>(device, &sequence, &[], note_duration, &ModemConfig::default())
 }
 
 /// Generates Bach Sweep DOWN (Legacy helper)
@@ -183,16 +421,21 @@ fn generate_bach_sweep_down<B: Backend>(device: &B::Device, cycles: usize) -> Te
     let note_duration = PREAMBLE_NOTE_DURATION;
     let mut sequence = Vec::new();
     for _ in 0..cycles {
-        sequence.extend(get_shifted_sweep_down(0));
+        sequence.extend(get_shifted_sweep_down(0, 16));
     }
-    generate_from_sequence::<B>(device, &sequence, note_duration)
+    generate_from_sequence::<B>(device, &sequence, &[], note_duration, &ModemConfig::default())
 }
 
-fn generate_from_sequence<B: Backend>(device: &B::Device, sequence: &[usize], note_duration: f64) -> Tensor<B, 1> {
+/// Concatenated notes on the transmitted tones of `sequence`; `phases` per note, missing entries are 0
+fn generate_from_sequence<B: Backend>(device: &B::Device, sequence: &[usize], phases: &[f64], note_duration: f64, config: &ModemConfig) -> Tensor<B, 1> {
+    let frequencies = config.frequencies();
+    
     // Generate each note
     let mut waveforms = Vec::new();
-    for &idx in sequence {
-        let waveform = generate_symbol::<B>(device, idx, 0.0, note_duration, FS);
+    for (i, &slot) in sequence.iter().enumerate() {
+        let idx = config.transmit_tone(slot);
+        let phase = phases.get(i).copied().unwrap_or(0.0);
+        let waveform = generate_shaped_tone::<B>(device, frequencies[idx], phase, config.tone_gain(idx), note_duration, WaveletShape::morlet(config.wavelet_sigmas), config.sample_rate);
         waveforms.push(waveform);
     }
     
@@ -222,12 +465,31 @@ mod tests {
     #[test]
     fn test_generate_symbol() {
         let device = Default::default();
-        let waveform = generate_symbol::<TestBackend>(&device, 5, 0.0, 2.0, 8000.0);
+        let waveform = generate_symbol::<TestBackend>(&device, &TonePlan::default(), 5, 0.0, 2.0, 8000.0);
         
+        assert_eq!(waveform.dims()[0], 16000);
+
+        let minor = TonePlan::minor(8).unwrap();
+        let waveform = generate_symbol::<TestBackend>(&device, &minor, 7, 0.0, 2.0, 8000.0);
         assert_eq!(waveform.dims()[0], 16000);
         println!("Symbol generated successfully");
     }
     
+    #[test]
+    fn test_seeded_patterns_rarely_collide() {
+        // A station against itself collides in every slot at offset 0
+        assert_eq!(hopping_cross_correlation(&HOPPING_PATTERN, &HOPPING_PATTERN), 16);
+        assert_eq!(get_hopping_indices(&HOPPING_PATTERN, 40), get_melody_indices(40));
+
+        let patterns: Vec<Vec<usize>> = (1..=8).map(|seed| seeded_hopping_pattern(16, seed)).collect();
+        assert_eq!(patterns[0], seeded_hopping_pattern(16, 1));
+        for (i, a) in patterns.iter().enumerate() {
+            for b in &patterns[i + 1..] {
+                assert!(hopping_cross_correlation(a, b) <= 5, "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
     #[test]
     fn test_bach_preamble() {
         let device = Default::default();
@@ -239,4 +501,78 @@ mod tests {
         
         println!("Bach preamble generated successfully");
     }
+    
+    #[test]
+    fn test_preamble_scales_with_alphabet() {
+        let device = Default::default();
+        let note_len = (PREAMBLE_NOTE_DURATION * FS) as usize;
+        
+        for num_tones in [8, 16, 32] {
+            let config = ModemConfig::new(num_tones);
+            let preamble = generate_bach_preamble_with_config::<TestBackend>(&device, &config);
+            let flourish = generate_bach_flourish_with_config::<TestBackend>(&device, &config);
+            
+            assert_eq!(preamble.dims()[0], 4 * num_tones * note_len);
+            assert_eq!(flourish.dims()[0], 2 * num_tones * note_len);
+        }
+    }
+    
+    #[test]
+    fn test_preamble_phase_code_suppresses_cycle_sidelobe() {
+        let device = Default::default();
+        let config = ModemConfig::default();
+        let note_len = (PREAMBLE_NOTE_DURATION * FS) as usize;
+        let cycle = 2 * config.num_tones * note_len;
+        
+        // Normalized autocorrelation one UP-DOWN cycle out
+        let cycle_sidelobe = |config: &ModemConfig| {
+            let preamble: Vec<f32> = generate_bach_preamble_with_config::<TestBackend>(&device, config).into_data().to_vec().unwrap();
+            let energy: f32 = preamble.iter().map(|x| x * x).sum();
+            let lagged: f32 = preamble.iter().zip(&preamble[cycle..]).map(|(a, b)| a * b).sum();
+            (lagged / energy, energy)
+        };
+        
+        let (plain, plain_energy) = cycle_sidelobe(&config);
+        let (coded, coded_energy) = cycle_sidelobe(&config.clone().with_preamble_phase_code(true));
+        
+        assert!((coded_energy / plain_energy - 1.0).abs() < 1e-3);
+        assert!(plain > 0.45, "plain sidelobe {}", plain);
+        assert!(coded.abs() < 0.2, "coded sidelobe {}", coded);
+        assert_eq!(preamble_note_phases(&config).len(), 4 * config.num_tones);
+    }
+    
+    #[test]
+    fn test_preamble_follows_config() {
+        let device = Default::default();
+        let config = ModemConfig::deep_space();
+        let note_len = config.preamble_note_samples();
+        assert_eq!(note_len, 2000);
+        assert_eq!(config.symbol_samples(), 16000);
+        
+        let preamble = generate_bach_preamble_with_config::<TestBackend>(&device, &config);
+        let flourish = generate_bach_flourish_with_config::<TestBackend>(&device, &config);
+        assert_eq!(preamble.dims()[0], 4 * 16 * note_len);
+        assert_eq!(flourish.dims()[0], 2 * 16 * note_len);
+        
+        // Six sweeps end on a down sweep; the phase code covers every note
+        let long = ModemConfig::default().with_preamble(6, 0.02).with_preamble_phase_code(true);
+        let sequence = preamble_tone_sequence(&long);
+        assert_eq!(sequence.len(), 6 * 16);
+        assert_eq!((sequence[5 * 16], sequence[6 * 16 - 1]), (15, 0));
+        assert_eq!(preamble_note_phases(&long).len(), sequence.len());
+        assert_eq!(generate_bach_preamble_with_config::<TestBackend>(&device, &long).dims()[0], 6 * 16 * 160);
+    }
+    
+    #[test]
+    fn test_symbol_pre_emphasis() {
+        let device = Default::default();
+        let config = ModemConfig::default().with_tone_gains(vec![2.0; 16]);
+        
+        let flat = generate_symbol::<TestBackend>(&device, &TonePlan::default(), 5, 0.3, SYMBOL_DURATION, FS);
+        let weighted = generate_symbol_with_config::<TestBackend>(&device, 5, 0.3, &config);
+        
+        let flat_energy: f32 = flat.powf_scalar(2.0).sum().into_scalar();
+        let weighted_energy: f32 = weighted.powf_scalar(2.0).sum().into_scalar();
+        assert!((weighted_energy / flat_energy - 4.0).abs() < 1e-3);
+    }
 }
@@ -0,0 +1,47 @@
+//! Simulated hardware-in-the-loop over a virtual audio cable
+//!
+//! Plays a few frames into a loopback sink and receives them from its
+//! monitor through the live path (cpal capture, streaming resampler,
+//! `StreamingDemodulator`, slot scheduler). It needs a virtual device and
+//! real time, so it is built only with the `audio` feature and `#[ignore]`d.
+//!
+//! Devices come from `BACHMODEM_HIL_OUTPUT` / `BACHMODEM_HIL_INPUT` (name
+//! or unique substring, see `bachmodem --list-devices`), the rate from
+//! `BACHMODEM_HIL_RATE` (default 48000, so the resampler runs):
+//!
+//! ```bash
+//! pactl load-module module-null-sink sink_name=bachmodem_hil
+//! BACHMODEM_HIL_OUTPUT=bachmodem_hil BACHMODEM_HIL_INPUT="Monitor of bachmodem_hil" \
+//!     cargo test --release -p bachmodem --features audio --test hil_loopback -- --ignored --nocapture
+//! ```
+//!
+//! With ALSA's `snd-aloop` use `hw:Loopback,0` as output and `hw:Loopback,1`
+//! as input.
+
+use bachmodem::{run_loopback, AudioSettings, LoopbackPlan, ModemConfig};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type TestBackend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const FRAMES: usize = 3;
+
+#[test]
+#[ignore]
+fn test_virtual_cable_loopback() {
+    let settings = AudioSettings {
+        output_device: std::env::var("BACHMODEM_HIL_OUTPUT").ok(),
+        input_device: std::env::var("BACHMODEM_HIL_INPUT").ok(),
+        sample_rate: std::env::var("BACHMODEM_HIL_RATE").ok().and_then(|r| r.parse().ok()).unwrap_or(48000),
+        ..Default::default()
+    };
+    let plan = LoopbackPlan::new(ModemConfig::profile("narrowband").unwrap(), FRAMES);
+
+    let device = Default::default();
+    let report = run_loopback::<TestBackend>(&device, &settings, &plan).expect("cannot open the loopback devices");
+    println!("{}", report);
+
+    assert!(report.passed(), "{}", report);
+    let (_, latency_std) = report.latency.unwrap();
+    assert!(latency_std < 0.01, "path latency jitter {:.1} ms", latency_std * 1e3);
+}
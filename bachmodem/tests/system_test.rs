@@ -0,0 +1,100 @@
+//! End-to-end -28 dB system test
+//!
+//! The `final_system_test` scenario as an automated check of the headline
+//! capability: one frame repeated in time slots (5 s listening gaps) over a
+//! gentle Watterson channel at -28 dB SNR (measured over the frames, not
+//! the gaps), received with the library chain:
+//! - the slots are summed stride-aligned and the preamble is searched once
+//!   in the sum (a single -28 dB preamble is below the sync threshold)
+//! - every slot goes through `capture_llrs` from that data start
+//! - the LLRs are summed and `decode_llrs` must return the payload
+//!
+//! Fading phases and noise come from `SimSeed` streams, so a failure
+//! reproduces on any backend. It takes
+//! about 37 minutes of simulated audio, so it is built only with the
+//! `system-test` feature and `#[ignore]`d:
+//!
+//! ```bash
+//! cargo test --release -p bachmodem --features system-test --test system_test -- --ignored --nocapture
+//! ```
+
+use bachmodem::{
+    capture_llrs, decode_llrs, gaussian_noise, synchronize_data_start_with_config, BachTransmitter,
+    ModemConfig, ReceiverPoolConfig, ReceiverState, RngStream, SimSeed, WattersonChannel, FS,
+};
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use burn::tensor::{ElementConversion, Tensor};
+use std::time::{Duration, Instant};
+
+// Raw CubeBackend: the Fusion wrapper doesn't implement FftBackend
+type TestBackend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+const SNR_DB: f32 = -28.0;
+const REPETITIONS: usize = 60;
+const GAP_SECONDS: f64 = 5.0;
+const SEED: u64 = 4;
+const PAYLOAD: &[u8] = b"BachModem Test";
+
+/// Generous bound for a release build on a GPU; catches order-of-magnitude regressions
+const MAX_RUNTIME: Duration = Duration::from_secs(300);
+
+#[test]
+#[ignore = "long-running; needs --features system-test"]
+fn test_minus_28_db_repetitions_decode() {
+    let started = Instant::now();
+    let device = Default::default();
+    let seed = SimSeed(SEED);
+
+    // Transmit: noise lead-in, then the frame once per slot
+    let frame = BachTransmitter::new(ModemConfig::default()).build::<TestBackend>(&device, PAYLOAD).unwrap();
+    let frame_len = frame.dims()[0];
+    let lead_in = FS as usize;
+    let stride = frame_len + (GAP_SECONDS * FS) as usize;
+    let total = lead_in + REPETITIONS * stride;
+
+    let mut clean = Tensor::<TestBackend, 1>::zeros([total], &device);
+    for slot in 0..REPETITIONS {
+        let start = lead_in + slot * stride;
+        clean = clean.slice_assign([start..start + frame_len], frame.clone());
+    }
+
+    // Channel
+    let faded = WattersonChannel::gentle().apply_with_rng::<TestBackend, _>(&device, &clean, &mut seed.rng(RngStream::Fading));
+    let frame_power = faded.clone().powf_scalar(2.0).sum().into_scalar().elem::<f32>() / (REPETITIONS * frame_len) as f32;
+    let noise_std = (frame_power / 10f32.powf(SNR_DB / 10.0)).sqrt();
+    let received = faded + gaussian_noise::<TestBackend, _>(&device, total, noise_std, &mut seed.rng(RngStream::Noise));
+    println!("{:.1} min at {} dB, {} slots of {:.1} s", total as f64 / FS / 60.0, SNR_DB, REPETITIONS, stride as f64 / FS);
+
+    // Sync once on the stride-aligned sum of all slots
+    let config = ReceiverPoolConfig::default();
+    let folded = received.clone()
+        .slice([0..REPETITIONS * stride])
+        .reshape([REPETITIONS, stride])
+        .sum_dim(0)
+        .reshape([stride]);
+    let data_start = synchronize_data_start_with_config::<TestBackend>(&device, &folded, &config.modem)
+        .expect("no sync in the summed slots");
+    let preamble_end = data_start - lead_in;
+    assert!(preamble_end < frame_len, "sync at {} is outside the frame", data_start);
+
+    // Demodulate every slot from the known data start and sum the LLRs
+    let slots = ReceiverPoolConfig { use_sync: false, ..config.clone() };
+    let mut state = ReceiverState::<TestBackend>::default();
+    let mut combined: Option<Tensor<TestBackend, 1>> = None;
+    for slot in 0..REPETITIONS {
+        let start = data_start + slot * stride;
+        let data = received.clone().slice([start..start + frame_len - preamble_end]);
+        let capture = capture_llrs::<TestBackend>(&device, &mut state, &slots, &data).expect("slot demodulation failed");
+        combined = Some(match combined {
+            Some(sum) => sum + capture.llrs,
+            None => capture.llrs,
+        });
+    }
+
+    let payload = decode_llrs::<TestBackend>(&device, &config, &combined.unwrap()).expect("combined decode failed");
+    let elapsed = started.elapsed();
+    println!("decoded {:?} in {:.1} s", String::from_utf8_lossy(&payload), elapsed.as_secs_f64());
+
+    assert!(payload.starts_with(PAYLOAD));
+    assert!(elapsed < MAX_RUNTIME, "took {:.0} s (limit {} s)", elapsed.as_secs_f64(), MAX_RUNTIME.as_secs());
+}
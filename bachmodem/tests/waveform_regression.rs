@@ -0,0 +1,139 @@
+//! Transmit waveform regression per wire-format version
+//!
+//! Deployed receivers decode what today's transmitter sends, so the waveform
+//! of every named profile is pinned: the canonical frame is built and its
+//! fingerprint compared with `tests/fixtures/waveforms.txt` for the current
+//! `WIRE_FORMAT_VERSION`. Any change to tone tables, hopping patterns,
+//! preamble, pulse shape, FEC or frame layout shows up here; intentional ones
+//! bump the wire version (see `bachmodem_core::wire_format`), which starts a
+//! new set of entries.
+//!
+//! The fingerprint is the sample count, the energy and 16 projections onto
+//! seeded ±1 sequences, compared to 1e-4 of the waveform's norm. Exact hashes
+//! of the samples would differ between GPUs (their `sin`/`exp` round
+//! differently); any real waveform change moves the projections by about
+//! the norm of the difference.
+//!
+//! A profile without an entry for the current version fails the test. Run
+//! it with `BACHMODEM_RECORD_WAVEFORMS=1` to append the missing entries to
+//! the fixture instead (commit them); existing entries are never rewritten.
+//!
+//! ```bash
+//! BACHMODEM_RECORD_WAVEFORMS=1 cargo test -p bachmodem --test waveform_regression -- --nocapture
+//! ```
+
+use bachmodem::{BachTransmitter, ModemConfig, PROFILE_NAMES, WIRE_FORMAT_VERSION};
+use bachmodem::modem_rng::splitmix64;
+use burn::backend::wgpu::{CubeBackend, WgpuRuntime};
+use std::io::Write;
+use std::path::PathBuf;
+
+type TestBackend = CubeBackend<WgpuRuntime, f32, i32, u32>;
+
+/// Canonical frame: a full 15-byte payload
+const CANONICAL_PAYLOAD: &[u8] = b"CQ DE BACHMODEM";
+
+const PROJECTIONS: usize = 16;
+
+/// Allowed projection and energy deviation relative to the waveform norm
+const TOLERANCE: f64 = 1e-4;
+
+/// Set to append fingerprints missing from the fixture instead of failing
+const RECORD_ENV: &str = "BACHMODEM_RECORD_WAVEFORMS";
+
+fn fixture_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/waveforms.txt")
+}
+
+/// (samples, energy, projections)
+fn fingerprint(samples: &[f32]) -> (usize, f64, Vec<f64>) {
+    let energy = samples.iter().map(|&x| x as f64 * x as f64).sum();
+    let projections = (0..PROJECTIONS as u64)
+        .map(|k| {
+            samples.iter().enumerate()
+                .map(|(t, &x)| if splitmix64((k << 32) ^ t as u64) & 1 == 0 { x as f64 } else { -(x as f64) })
+                .sum()
+        })
+        .collect();
+    (samples.len(), energy, projections)
+}
+
+fn format_entry(profile: &str, (samples, energy, projections): &(usize, f64, Vec<f64>)) -> String {
+    let projections: Vec<String> = projections.iter().map(|p| format!("{:.6}", p)).collect();
+    format!("{} {} {} {:.6} {}", WIRE_FORMAT_VERSION, profile, samples, energy, projections.join(" "))
+}
+
+/// Fixture entries of the current wire version: profile -> fingerprint
+fn load_fixture() -> Vec<(String, (usize, f64, Vec<f64>))> {
+    let text = std::fs::read_to_string(fixture_path()).unwrap_or_default();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(fields.len(), 4 + PROJECTIONS, "malformed fixture line: {}", line);
+            let version: u8 = fields[0].parse().expect("wire version");
+            (version == WIRE_FORMAT_VERSION).then(|| {
+                let projections = fields[4..].iter().map(|p| p.parse().expect("projection")).collect();
+                (fields[1].to_string(), (fields[2].parse().expect("samples"), fields[3].parse().expect("energy"), projections))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_transmit_waveforms_match_wire_version_fixture() {
+    let device = Default::default();
+    let fixture = load_fixture();
+    let record = std::env::var_os(RECORD_ENV).is_some();
+    let mut recorded = Vec::new();
+    let mut missing = Vec::new();
+    let mut mismatches = Vec::new();
+
+    for profile in PROFILE_NAMES {
+        let config = ModemConfig::profile(profile).unwrap();
+        let waveform = BachTransmitter::new(config).build::<TestBackend>(&device, CANONICAL_PAYLOAD).unwrap();
+        let samples: Vec<f32> = waveform.into_data().to_vec().unwrap();
+        let actual = fingerprint(&samples);
+
+        let Some((_, expected)) = fixture.iter().find(|(name, _)| name == profile) else {
+            if record {
+                recorded.push(format_entry(profile, &actual));
+            } else {
+                missing.push(profile);
+            }
+            continue;
+        };
+
+        let norm = actual.1.sqrt();
+        let matches = actual.0 == expected.0
+            && (actual.1 - expected.1).abs() <= TOLERANCE * actual.1
+            && actual.2.iter().zip(&expected.2).all(|(a, e)| (a - e).abs() <= TOLERANCE * norm);
+        if !matches {
+            mismatches.push(format!("  {}\n    expected {}\n    actual   {}", profile, format_entry(profile, expected), format_entry(profile, &actual)));
+        }
+    }
+
+    if !recorded.is_empty() {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(fixture_path()).expect("open fixture");
+        for line in &recorded {
+            writeln!(file, "{}", line).expect("write fixture");
+        }
+        println!("Recorded {} wire version {} fingerprints into {} - commit them", recorded.len(), WIRE_FORMAT_VERSION, fixture_path().display());
+    }
+
+    assert!(
+        missing.is_empty(),
+        "no wire version {} fingerprint for {:?} in {} - record them with {}=1 and commit the fixture",
+        WIRE_FORMAT_VERSION,
+        missing,
+        fixture_path().display(),
+        RECORD_ENV,
+    );
+    assert!(
+        mismatches.is_empty(),
+        "transmit waveform changed under wire version {} - fix the regression, or bump WIRE_FORMAT_VERSION if the change is intended:\n{}",
+        WIRE_FORMAT_VERSION,
+        mismatches.join("\n"),
+    );
+}
@@ -0,0 +1,107 @@
+//! Headless pipeline benchmark
+//!
+//! Runs synthetic frames through `FramePipeline` with stage timing enabled,
+//! no window or camera, and reports the mean time per stage as JSON so kernel
+//! changes can be compared run to run (and in CI).
+//!
+//! ```bash
+//! cargo run --release -p fft_gpu -- --bench 200 [--on_cpu]
+//! ```
+
+use std::time::Instant;
+use burn::tensor::backend::Backend;
+use crate::cube_fft::FftBackend;
+use crate::cube_ops::OpsBackend;
+use crate::frame_source::{FrameSource, SyntheticSource};
+use crate::pipeline::{FramePipeline, PipelineConfig, STAGES};
+
+/// Frames processed before timing starts (kernel compilation, autotune)
+pub const WARMUP_FRAMES: usize = 5;
+
+/// Benchmark result
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    pub backend: String,
+    pub width: usize,
+    pub height: usize,
+    pub frames: usize,
+
+    /// Frames per second over the timed frames
+    pub fps: f64,
+
+    /// Mean time per frame of each stage (ms), ordered like `STAGES`
+    pub stage_ms: Vec<(&'static str, f64)>,
+}
+
+impl BenchReport {
+    pub fn to_json(&self) -> String {
+        let stages: Vec<String> = self.stage_ms.iter()
+            .map(|(name, ms)| format!("\"{}\": {:.4}", name, ms))
+            .collect();
+        format!(
+            "{{\"backend\": \"{}\", \"width\": {}, \"height\": {}, \"frames\": {}, \"fps\": {:.2}, \"stage_ms\": {{{}}}}}",
+            self.backend, self.width, self.height, self.frames, self.fps, stages.join(", "),
+        )
+    }
+}
+
+/// Process `frames` synthetic frames (after warm-up) and time every stage
+///
+/// Frames are generated up front so pattern generation isn't timed. Stage
+/// times include a device sync after each stage, so their sum is slightly
+/// above the free-running frame time. Stages of panels not in `config` are
+/// skipped (and report ~0 ms).
+pub fn run_bench<B: Backend + FftBackend + OpsBackend>(
+    device: &B::Device,
+    backend: &str,
+    frames: usize,
+    width: usize,
+    height: usize,
+    config: PipelineConfig,
+) -> BenchReport {
+    let mut source = SyntheticSource::new(width, height).with_limit(WARMUP_FRAMES + frames);
+    let mut video = Vec::with_capacity(WARMUP_FRAMES + frames);
+    while let Some(frame) = source.next_frame().unwrap() {
+        video.push(frame);
+    }
+
+    let mut pipeline = FramePipeline::<B>::with_config(device, config).with_timing();
+    for frame in &video[..WARMUP_FRAMES] {
+        pipeline.process(frame);
+    }
+    pipeline.reset_timings();
+
+    let start = Instant::now();
+    for frame in &video[WARMUP_FRAMES..] {
+        pipeline.process(frame);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let timings = pipeline.timings().unwrap();
+    BenchReport {
+        backend: backend.to_string(),
+        width,
+        height,
+        frames,
+        fps: frames as f64 / elapsed.max(1e-9),
+        stage_ms: STAGES.iter().copied().zip(timings.mean_ms()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuBackend;
+
+    #[test]
+    fn test_bench_report() {
+        let report = run_bench::<CpuBackend>(&Default::default(), "ndarray", 2, 32, 32, PipelineConfig::default());
+
+        assert_eq!(report.stage_ms.len(), STAGES.len());
+        assert!(report.fps > 0.0);
+
+        let json = report.to_json();
+        assert!(json.starts_with("{\"backend\": \"ndarray\", \"width\": 32"));
+        assert!(json.contains("\"fft\": ") && json.ends_with("}}"));
+    }
+}
@@ -0,0 +1,69 @@
+//! Live camera `FrameSource` (nokhwa)
+
+use nokhwa::{Camera, utils::{CameraIndex, RequestedFormat, RequestedFormatType}, pixel_format::RgbFormat};
+use rayon::prelude::*;
+use crate::frame_source::{Frame, FrameError, FrameSource};
+
+/// Camera capture, resized to a fixed frame size and converted to grayscale
+///
+/// The camera is opened on the first `next_frame()` call. A capture failure
+/// drops the stream, so the following call reconnects.
+pub struct CameraSource {
+    index: CameraIndex,
+    width: usize,
+    height: usize,
+    camera: Option<Camera>,
+}
+
+impl CameraSource {
+    pub fn new(index: u32, width: usize, height: usize) -> Self {
+        Self { index: CameraIndex::Index(index), width, height, camera: None }
+    }
+
+    fn connect(&mut self) -> Result<&mut Camera, FrameError> {
+        if self.camera.is_none() {
+            let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
+            let mut camera = Camera::new(self.index.clone(), requested)
+                .map_err(|e| FrameError::Camera(format!("could not access camera: {}", e)))?;
+            camera.open_stream()
+                .map_err(|e| FrameError::Camera(format!("camera found but failed to open stream: {}", e)))?;
+
+            println!("Camera connected! Format: {:?}", camera.camera_format());
+            self.camera = Some(camera);
+        }
+        Ok(self.camera.as_mut().unwrap())
+    }
+}
+
+impl FrameSource for CameraSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, FrameError> {
+        let frame = match self.connect()?.frame() {
+            Ok(f) => f,
+            Err(e) => {
+                // Reconnect on the next call
+                self.camera = None;
+                return Err(FrameError::Camera(format!("failed to capture frame: {}", e)));
+            }
+        };
+
+        let decoded = frame.decode_image::<RgbFormat>()
+            .map_err(|e| FrameError::Camera(format!("failed to decode image: {}", e)))?;
+
+        // Resize/Crop to the FFT size
+        let resized = image::imageops::resize(&decoded, self.width as u32, self.height as u32, image::imageops::FilterType::Triangle);
+
+        // Grayscale for FFT
+        let pixels: Vec<f32> = resized.as_raw()
+            .par_chunks(3)
+            .map(|pixel| {
+                let r = pixel[0] as f32;
+                let g = pixel[1] as f32;
+                let b = pixel[2] as f32;
+                let gray = 0.299 * r + 0.587 * g + 0.114 * b;
+                (gray / 255.0).clamp(0.0, 1.0)
+            })
+            .collect();
+
+        Ok(Some(Frame { width: self.width, height: self.height, pixels }))
+    }
+}
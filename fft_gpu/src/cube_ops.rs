@@ -1,5 +1,5 @@
 use cubecl::{cube, prelude::*};
-use burn::tensor::{backend::Backend, ops::FloatTensor, TensorPrimitive};
+use burn::tensor::{backend::Backend, ops::{FloatTensor, IntTensor}, DType, ElementConversion, Int, Shape, TensorPrimitive};
 use burn::tensor::Tensor as BurnTensor;
 use burn_cubecl::{CubeBackend, CubeRuntime, FloatElement, IntElement, BoolElement, tensor::CubeTensor, kernel::into_contiguous};
 use burn_ndarray::{NdArray, NdArrayTensor};
@@ -82,6 +82,47 @@ pub fn temporal_diff_kernel<F: Float>(
     }
 }
 
+/// Shifted, normalized log-magnitude of a complex spectrum as 8-bit levels
+///
+/// One thread per output word: four neighbouring pixels of a row, packed
+/// little-endian. Output pixel (y, x) reads bin ((y + H/2) % H, (x + W/2) % W)
+/// so DC lands in the centre. Levels are (ln(1 + |X|) / max)^gamma * 255,
+/// where max = max(ln(1 + sqrt(peak_power)), 1) as in the CPU path.
+#[cube(launch)]
+pub fn spectrum_levels_kernel<F: Float>(
+    real: &Tensor<F>,
+    imag: &Tensor<F>,
+    peak_power: &Tensor<F>,
+    output: &mut Tensor<u32>,
+    height: u32,
+    width: u32,
+    gamma: F,
+) {
+    let idx = ABSOLUTE_POS;
+
+    if idx < output.len() {
+        let words_per_row = width / 4;
+        let y = idx / words_per_row;
+        let x0 = (idx % words_per_row) * 4;
+        let row = ((y + height / 2) % height) * width;
+
+        let max_log = F::max(F::log(F::new(1.0) + F::sqrt(peak_power[0])), F::new(1.0));
+
+        let mut word = 0u32;
+        for k in 0..4u32 {
+            let src = row + (x0 + k + width / 2) % width;
+            let re = real[src];
+            let im = imag[src];
+
+            let normalized = F::log(F::new(1.0) + F::sqrt(re * re + im * im)) / max_log;
+            let level = F::min(F::powf(normalized, gamma) * F::new(255.0), F::new(255.0));
+
+            word |= u32::cast_from(level) << (k * 8);
+        }
+        output[idx] = word;
+    }
+}
+
 pub trait OpsBackend: Backend {
     fn sobel_impl(
         input: FloatTensor<Self>,
@@ -94,6 +135,16 @@ pub trait OpsBackend: Backend {
         prev: FloatTensor<Self>,
         prev_prev: FloatTensor<Self>,
     ) -> FloatTensor<Self>;
+
+    /// `[H, W / 4]` u32 words of packed 8-bit levels (see `spectrum_levels_kernel`)
+    fn spectrum_levels_impl(
+        real: FloatTensor<Self>,
+        imag: FloatTensor<Self>,
+        peak_power: FloatTensor<Self>,
+        height: usize,
+        width: usize,
+        gamma: f32,
+    ) -> IntTensor<Self>;
 }
 
 impl<R: CubeRuntime, F: FloatElement, I: IntElement, BT: BoolElement> OpsBackend for CubeBackend<R, F, I, BT> {
@@ -171,6 +222,49 @@ impl<R: CubeRuntime, F: FloatElement, I: IntElement, BT: BoolElement> OpsBackend
         
         output_tensor
     }
+    
+    fn spectrum_levels_impl(
+        real: FloatTensor<Self>,
+        imag: FloatTensor<Self>,
+        peak_power: FloatTensor<Self>,
+        height: usize,
+        width: usize,
+        gamma: f32,
+    ) -> IntTensor<Self> {
+        let real = into_contiguous(real);
+        let imag = into_contiguous(imag);
+        let peak_power = into_contiguous(peak_power);
+        
+        let num_words = height * width / 4;
+        let output_handle = real.client.empty(num_words * core::mem::size_of::<u32>());
+        
+        let output_tensor = CubeTensor::new(
+            real.client.clone(),
+            output_handle,
+            Shape::new([height, width / 4]),
+            real.device.clone(),
+            vec![width / 4, 1],
+            DType::U32,
+        );
+        
+        let cube_dim = CubeDim::new_1d(256);
+        let cube_count = CubeCount::Static((num_words as u32 + cube_dim.x - 1) / cube_dim.x, 1, 1);
+        
+        spectrum_levels_kernel::launch::<F, R>(
+            &real.client,
+            cube_count,
+            cube_dim,
+            real.as_tensor_arg(1),
+            imag.as_tensor_arg(1),
+            peak_power.as_tensor_arg(1),
+            output_tensor.as_tensor_arg(1),
+            ScalarArg::new(height as u32),
+            ScalarArg::new(width as u32),
+            ScalarArg::new(gamma.elem::<F>()),
+        ).unwrap();
+        
+        output_tensor
+    }
 }
 
 pub fn compute_sobel<B: Backend + OpsBackend>(input: BurnTensor<B, 2>) -> BurnTensor<B, 2> {
@@ -207,6 +301,32 @@ pub fn compute_temporal_diff<B: Backend + OpsBackend>(
     BurnTensor::from_primitive(TensorPrimitive::Float(out_t))
 }
 
+/// Packed 8-bit spectrum levels of a complex `[H, W]` spectrum, DC centred
+///
+/// Fuses magnitude, log, normalization, gamma and quadrant shift on the
+/// device; the result is `[H, W / 4]` u32 words (four levels each,
+/// little-endian), an eighth of the complex spectrum's size. W must be a
+/// multiple of 4.
+pub fn compute_spectrum_levels<B: Backend + OpsBackend>(
+    real: BurnTensor<B, 2>,
+    imag: BurnTensor<B, 2>,
+    gamma: f32,
+) -> BurnTensor<B, 2, Int> {
+    let [height, width] = real.dims();
+    assert!(width % 4 == 0, "spectrum width must be a multiple of 4");
+
+    // Largest power stays on the device: no sync before the kernel
+    let peak_power = (real.clone().powf_scalar(2.0) + imag.clone().powf_scalar(2.0)).max();
+
+    let real_t = match real.into_primitive() { TensorPrimitive::Float(t) => t, _ => panic!("Expected float tensor") };
+    let imag_t = match imag.into_primitive() { TensorPrimitive::Float(t) => t, _ => panic!("Expected float tensor") };
+    let peak_t = match peak_power.into_primitive() { TensorPrimitive::Float(t) => t, _ => panic!("Expected float tensor") };
+
+    let out_t = B::spectrum_levels_impl(real_t, imag_t, peak_t, height, width, gamma);
+
+    BurnTensor::from_primitive(out_t)
+}
+
 impl OpsBackend for NdArray<f32> {
     fn sobel_impl(
         input: FloatTensor<Self>,
@@ -283,6 +403,40 @@ impl OpsBackend for NdArray<f32> {
             
         NdArrayTensor::from(out_arr.into_shared())
     }
+    
+    fn spectrum_levels_impl(
+        real: FloatTensor<Self>,
+        imag: FloatTensor<Self>,
+        peak_power: FloatTensor<Self>,
+        height: usize,
+        width: usize,
+        gamma: f32,
+    ) -> IntTensor<Self> {
+        let re_arc = match real { NdArrayTensor::F32(s) => s.into_owned(), _ => panic!() };
+        let im_arc = match imag { NdArrayTensor::F32(s) => s.into_owned(), _ => panic!() };
+        let peak_arc = match peak_power { NdArrayTensor::F32(s) => s.into_owned(), _ => panic!() };
+        
+        let re_slice = re_arc.as_slice().expect("spectrum must be contiguous");
+        let im_slice = im_arc.as_slice().expect("spectrum must be contiguous");
+        let max_log = (1.0 + peak_arc.iter().next().unwrap().sqrt()).ln().max(1.0);
+        
+        let mut out_arr = ndarray::Array2::<u32>::zeros((height, width / 4));
+        let out_slice = out_arr.as_slice_mut().unwrap();
+        
+        out_slice.par_chunks_mut(width / 4).enumerate().for_each(|(y, row_out)| {
+            let row = ((y + height / 2) % height) * width;
+            for (w, word) in row_out.iter_mut().enumerate() {
+                for k in 0..4 {
+                    let src = row + (4 * w + k + width / 2) % width;
+                    let mag = (re_slice[src] * re_slice[src] + im_slice[src] * im_slice[src]).sqrt();
+                    let level = (((1.0 + mag).ln() / max_log).powf(gamma) * 255.0).min(255.0);
+                    *word |= (level as u32) << (8 * k);
+                }
+            }
+        });
+        
+        NdArrayTensor::from(out_arr.into_dyn().into_shared())
+    }
 }
 
 
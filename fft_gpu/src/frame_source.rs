@@ -0,0 +1,284 @@
+//! Frame sources for the processing pipeline
+//!
+//! The pipeline only needs grayscale frames of a fixed size; where they come
+//! from is a `FrameSource`:
+//! - `SyntheticSource`: moving-circle test pattern, no hardware needed
+//! - `Y4mSource`: YUV4MPEG2 stream from any reader (a `.y4m` file, stdin)
+//! - `VideoFileSource`: any video file ffmpeg can read, scaled and piped as y4m
+//! - `camera::CameraSource`: live capture through nokhwa
+//!
+//! Headless runs and tests use the first three.
+
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Grayscale frame, row-major values in 0..1
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<f32>,
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// Reading the stream failed
+    Io(std::io::Error),
+
+    /// The stream is not in a supported format
+    Format(String),
+
+    /// The camera could not be opened or stopped delivering frames
+    Camera(String),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "I/O error: {}", e),
+            FrameError::Format(msg) => write!(f, "unsupported stream: {}", msg),
+            FrameError::Camera(msg) => write!(f, "camera error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<std::io::Error> for FrameError {
+    fn from(e: std::io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+/// Producer of grayscale frames
+pub trait FrameSource {
+    /// Next frame, `Ok(None)` at the end of the stream
+    ///
+    /// An error may be transient (camera unplugged): callers can retry.
+    fn next_frame(&mut self) -> Result<Option<Frame>, FrameError>;
+}
+
+/// Moving-circle test pattern
+pub struct SyntheticSource {
+    width: usize,
+    height: usize,
+    frame_idx: usize,
+
+    /// Number of frames before the end of the stream (None = endless)
+    limit: Option<usize>,
+}
+
+impl SyntheticSource {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, frame_idx: 0, limit: None }
+    }
+
+    /// End the stream after `frames` frames
+    pub fn with_limit(mut self, frames: usize) -> Self {
+        self.limit = Some(frames);
+        self
+    }
+}
+
+impl FrameSource for SyntheticSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, FrameError> {
+        if self.limit.is_some_and(|limit| self.frame_idx >= limit) {
+            return Ok(None);
+        }
+
+        let pixels = generate_frame(self.width, self.height, self.frame_idx);
+        self.frame_idx += 1;
+        Ok(Some(Frame { width: self.width, height: self.height, pixels }))
+    }
+}
+
+/// Circle of radius 20 orbiting the centre
+pub fn generate_frame(width: usize, height: usize, frame_idx: usize) -> Vec<f32> {
+    let mut data = Vec::with_capacity(width * height);
+    let time = frame_idx as f32 * 0.1;
+    for y in 0..height {
+        for x in 0..width {
+            // Moving circle pattern
+            let cx = (width as f32 / 2.0) + (time.cos() * width as f32 / 4.0);
+            let cy = (height as f32 / 2.0) + (time.sin() * height as f32 / 4.0);
+            let dx = x as f32 - cx;
+            let dy = y as f32 - cy;
+            let dist = (dx*dx + dy*dy).sqrt();
+
+            let val = if dist < 20.0 { 1.0 } else { 0.0 };
+            data.push(val);
+        }
+    }
+    data
+}
+
+/// YUV4MPEG2 stream reader
+///
+/// Only the luma plane is used; chroma planes are skipped. Supports the
+/// 8-bit `mono`, `420*`, `422` and `444` colorspaces.
+pub struct Y4mSource<R: Read> {
+    reader: BufReader<R>,
+    width: usize,
+    height: usize,
+
+    /// Bytes per frame after the luma plane
+    chroma_len: usize,
+}
+
+impl<R: Read> Y4mSource<R> {
+    /// Parse the stream header
+    pub fn new(reader: R) -> Result<Self, FrameError> {
+        let mut reader = BufReader::new(reader);
+        let header = read_line(&mut reader)?
+            .ok_or_else(|| FrameError::Format("empty stream".to_string()))?;
+
+        let mut params = header.split(' ');
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(FrameError::Format("missing YUV4MPEG2 signature".to_string()));
+        }
+
+        let (mut width, mut height, mut colorspace) = (0usize, 0usize, "420jpeg");
+        for param in params {
+            let (tag, value) = param.split_at(param.len().min(1));
+            match tag {
+                "W" => width = value.parse().map_err(|_| FrameError::Format(format!("bad width {:?}", value)))?,
+                "H" => height = value.parse().map_err(|_| FrameError::Format(format!("bad height {:?}", value)))?,
+                "C" => colorspace = value,
+                _ => {}
+            }
+        }
+        if width == 0 || height == 0 {
+            return Err(FrameError::Format("missing frame size".to_string()));
+        }
+
+        let (chroma_w, chroma_h) = (width.div_ceil(2), height.div_ceil(2));
+        let chroma_len = match colorspace {
+            "mono" => 0,
+            c if c.starts_with("420") => 2 * chroma_w * chroma_h,
+            "422" => 2 * chroma_w * height,
+            "444" => 2 * width * height,
+            other => return Err(FrameError::Format(format!("colorspace {}", other))),
+        };
+
+        Ok(Self { reader, width, height, chroma_len })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl<R: Read> FrameSource for Y4mSource<R> {
+    fn next_frame(&mut self) -> Result<Option<Frame>, FrameError> {
+        let Some(marker) = read_line(&mut self.reader)? else {
+            return Ok(None);
+        };
+        if !marker.starts_with("FRAME") {
+            return Err(FrameError::Format(format!("expected FRAME, got {:?}", marker)));
+        }
+
+        let mut luma = vec![0u8; self.width * self.height];
+        self.reader.read_exact(&mut luma)?;
+        std::io::copy(&mut (&mut self.reader).take(self.chroma_len as u64), &mut std::io::sink())?;
+
+        Ok(Some(Frame {
+            width: self.width,
+            height: self.height,
+            pixels: luma.iter().map(|&v| v as f32 / 255.0).collect(),
+        }))
+    }
+}
+
+/// One header line without the newline, None at end of stream
+fn read_line<R: Read>(reader: &mut BufReader<R>) -> Result<Option<String>, FrameError> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.last() == Some(&b'\n') {
+        line.pop();
+    }
+    String::from_utf8(line).map(Some).map_err(|_| FrameError::Format("header is not text".to_string()))
+}
+
+/// Video file decoded by ffmpeg, scaled to the requested size
+///
+/// Requires `ffmpeg` on the PATH. The decoder runs as a child process piping
+/// grayscale y4m, and is killed when the source is dropped.
+pub struct VideoFileSource {
+    child: Child,
+    stream: Y4mSource<ChildStdout>,
+}
+
+impl VideoFileSource {
+    pub fn open(path: &str, width: usize, height: usize) -> Result<Self, FrameError> {
+        let mut child = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-i", path])
+            .args(["-vf", &format!("scale={}:{}", width, height)])
+            .args(["-pix_fmt", "gray", "-f", "yuv4mpegpipe", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().expect("ffmpeg stdout is piped");
+        match Y4mSource::new(stdout) {
+            Ok(stream) => Ok(Self { child, stream }),
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e)
+            }
+        }
+    }
+}
+
+impl FrameSource for VideoFileSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, FrameError> {
+        self.stream.next_frame()
+    }
+}
+
+impl Drop for VideoFileSource {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_y4m_luma_frames() {
+        // Two 4x2 frames in 4:2:0: 8 luma bytes, then 2 x 2x1 chroma bytes
+        let mut stream = b"YUV4MPEG2 W4 H2 F25:1 Ip A1:1 C420jpeg\n".to_vec();
+        for value in [0u8, 255] {
+            stream.extend_from_slice(b"FRAME\n");
+            stream.extend_from_slice(&[value; 8]);
+            stream.extend_from_slice(&[128; 4]);
+        }
+
+        let mut source = Y4mSource::new(stream.as_slice()).unwrap();
+        assert_eq!((source.width(), source.height()), (4, 2));
+        assert_eq!(source.next_frame().unwrap().unwrap().pixels, vec![0.0; 8]);
+        assert_eq!(source.next_frame().unwrap().unwrap().pixels, vec![1.0; 8]);
+        assert!(source.next_frame().unwrap().is_none());
+
+        assert!(matches!(Y4mSource::new(&b"P5 4 2 255\n"[..]), Err(FrameError::Format(_))));
+    }
+
+    #[test]
+    fn test_synthetic_limit() {
+        let mut source = SyntheticSource::new(64, 64).with_limit(2);
+        let first = source.next_frame().unwrap().unwrap();
+        assert_eq!(first.pixels.len(), 64 * 64);
+        assert_ne!(source.next_frame().unwrap().unwrap(), first);
+        assert!(source.next_frame().unwrap().is_none());
+    }
+}
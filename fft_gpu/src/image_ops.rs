@@ -0,0 +1,153 @@
+//! Image-processing ops on 2-D tensors
+//!
+//! The building blocks of the realtime viewer, usable from other burnApps
+//! binaries without copying kernel code. Images are `[Height, Width]` float
+//! tensors (grayscale, nominally 0..1); every op stays on the device until the
+//! caller downloads the result.
+//!
+//! - `sobel`: edge magnitude (3x3 Sobel, zero border)
+//! - `temporal_diff`: motion energy of three consecutive frames
+//! - `fft2` / `fft2_magnitude`: 2-D FFT (power-of-two sides), unshifted
+//!   (centre with `spectral::fftshift`)
+//! - `spectrum_levels` / `to_levels`: DC-centred log-magnitude as 8-bit
+//!   levels, computed and packed on the device (one fused kernel)
+//! - `fft_rows`: batched 1-D FFT of every row (spectrograms, audio frames)
+//! - `pack_rgb` / `to_pixels`: 0RGB `u32` pixels as used by minifb
+//!
+//! Works on any backend implementing `FftBackend` / `OpsBackend` (CubeCL
+//! runtimes and NdArray).
+
+use burn::tensor::{Int, Tensor, TensorPrimitive, backend::Backend};
+use crate::cube_fft::FftBackend;
+use crate::cube_ops::{compute_sobel, compute_spectrum_levels, compute_temporal_diff, OpsBackend};
+
+/// Sobel edge magnitude, sqrt(Gx² + Gy²); the one-pixel border is zero
+pub fn sobel<B: Backend + OpsBackend>(image: Tensor<B, 2>) -> Tensor<B, 2> {
+    compute_sobel(image)
+}
+
+/// Motion energy: |current - prev| + |prev - prev_prev|
+pub fn temporal_diff<B: Backend + OpsBackend>(
+    current: Tensor<B, 2>,
+    prev: Tensor<B, 2>,
+    prev_prev: Tensor<B, 2>,
+) -> Tensor<B, 2> {
+    compute_temporal_diff(current, prev, prev_prev)
+}
+
+/// 2-D FFT of a real image, returned as (real, imag) `[Height, Width]` tensors
+///
+/// Both sides must be powers of two. The DC term is at [0, 0] (no shift).
+pub fn fft2<B: Backend + FftBackend>(image: Tensor<B, 2>) -> (Tensor<B, 2>, Tensor<B, 2>) {
+    let [height, width] = image.dims();
+    assert!(height.is_power_of_two() && width.is_power_of_two(), "FFT sides must be powers of two");
+
+    // Rows: H batches of size W
+    let imag = image.zeros_like();
+    let (real, imag) = fft_rows(image, imag);
+
+    // Columns: transpose to W batches of size H and back
+    let (real, imag) = fft_rows(real.transpose(), imag.transpose());
+
+    (real.transpose(), imag.transpose())
+}
+
+/// |FFT2(image)|, `[Height, Width]`, unshifted
+pub fn fft2_magnitude<B: Backend + FftBackend>(image: Tensor<B, 2>) -> Tensor<B, 2> {
+    let (real, imag) = fft2(image);
+    (real.powf_scalar(2.0) + imag.powf_scalar(2.0)).sqrt()
+}
+
+/// Log-magnitude spectrum of an image as 8-bit levels, DC in the centre
+///
+/// Level = (ln(1 + |FFT2|) / max)^gamma * 255 with max over the frame (at
+/// least 1). Returns `[Height, Width / 4]` words holding four levels each;
+/// unpack with `to_levels`. Downloading this is 1 byte per pixel instead of
+/// 8 for the complex spectrum.
+pub fn spectrum_levels<B: Backend + FftBackend + OpsBackend>(image: Tensor<B, 2>, gamma: f32) -> Tensor<B, 2, Int> {
+    let (real, imag) = fft2(image);
+    compute_spectrum_levels(real, imag, gamma)
+}
+
+/// Download packed levels as one byte per pixel, row-major
+pub fn to_levels<B: Backend>(packed: Tensor<B, 2, Int>) -> Vec<u8> {
+    let words: Vec<u32> = packed.into_data().convert::<u32>().to_vec().unwrap();
+    words.into_iter().flat_map(u32::to_le_bytes).collect()
+}
+
+/// Pack three 0..1 channel planes into 0RGB pixels (`r << 16 | g << 8 | b`)
+///
+/// Channels are clamped to 0..1 and truncated to 8 bits.
+pub fn pack_rgb<B: Backend>(r: Tensor<B, 2>, g: Tensor<B, 2>, b: Tensor<B, 2>) -> Tensor<B, 2, Int> {
+    let channel = |c: Tensor<B, 2>| c.clamp(0.0, 1.0).mul_scalar(255.0).floor();
+
+    // Largest value is 2^24 - 1, exact in f32
+    (channel(r).mul_scalar(65536.0) + channel(g).mul_scalar(256.0) + channel(b)).int()
+}
+
+/// Download packed pixels in row-major order
+pub fn to_pixels<B: Backend>(packed: Tensor<B, 2, Int>) -> Vec<u32> {
+    packed.into_data().convert::<u32>().to_vec().unwrap()
+}
+
+/// Complex FFT of every row of `[Batch, N]` real/imag tensors, N a power of two
+pub fn fft_rows<B: Backend + FftBackend>(
+    real: Tensor<B, 2>,
+    imag: Tensor<B, 2>,
+) -> (Tensor<B, 2>, Tensor<B, 2>) {
+    let n_fft = real.dims()[1];
+    let real_t = match real.into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+    let imag_t = match imag.into_primitive() {
+        TensorPrimitive::Float(t) => t,
+        _ => panic!("Expected float tensor"),
+    };
+
+    let (real_out, imag_out) = B::fft_1d_batch_impl(real_t, imag_t, n_fft);
+
+    (
+        Tensor::from_primitive(TensorPrimitive::Float(real_out)),
+        Tensor::from_primitive(TensorPrimitive::Float(imag_out)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuBackend;
+
+    #[test]
+    fn test_fft2_magnitude_of_impulse_is_flat() {
+        let device = Default::default();
+        let mut pixels = vec![0.0f32; 8 * 16];
+        pixels[0] = 1.0;
+        let image = Tensor::<CpuBackend, 1>::from_floats(pixels.as_slice(), &device).reshape([8, 16]);
+
+        let magnitude: Vec<f32> = fft2_magnitude(image).into_data().to_vec().unwrap();
+        assert!(magnitude.iter().all(|&m| (m - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_spectrum_levels_centred() {
+        let device = Default::default();
+
+        // Constant image: all energy in DC, which lands at (H/2, W/2)
+        let image = Tensor::<CpuBackend, 2>::ones([8, 16], &device);
+        let levels = to_levels(spectrum_levels(image, 1.0));
+
+        assert_eq!(levels.len(), 8 * 16);
+        assert_eq!(levels[4 * 16 + 8], 255);
+        assert_eq!(levels.iter().filter(|&&l| l != 0).count(), 1);
+    }
+
+    #[test]
+    fn test_pack_rgb() {
+        let device = Default::default();
+        let plane = |v: [f32; 2]| Tensor::<CpuBackend, 1>::from_floats(v, &device).reshape([1, 2]);
+
+        let packed = pack_rgb(plane([1.0, 0.5]), plane([0.0, 2.0]), plane([1.0, -1.0]));
+        assert_eq!(to_pixels(packed), vec![0xFF00FF, 0x7FFF00]);
+    }
+}
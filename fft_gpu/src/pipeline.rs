@@ -0,0 +1,372 @@
+//! FFT / Sobel / motion pipeline, independent of where frames come from
+//!
+//! `FramePipeline::process` turns one grayscale frame into the selected
+//! display panels as 0RGB pixels; panels that aren't selected aren't
+//! computed. It owns the three-frame ring buffer for the temporal
+//! difference, so frames must be fed in order.
+//!
+//! Frame size is taken from each frame (power-of-two sides); the FFT panel's
+//! quadrant shift follows it. The spectrum is reduced to 8-bit levels on the
+//! device (`spectrum_levels`); the host only applies the colormap.
+//!
+//! With `with_timing()` the device is synchronized after every stage and the
+//! wall time of each stage is accumulated (see `bench`).
+
+use std::time::{Duration, Instant};
+use burn::tensor::{Tensor, backend::Backend};
+use rayon::prelude::*;
+use crate::cube_fft::FftBackend;
+use crate::cube_ops::OpsBackend;
+use crate::frame_source::Frame;
+use crate::image_ops::{pack_rgb, sobel, spectrum_levels, temporal_diff, to_levels, to_pixels};
+
+struct GpuRingBuffer<B: Backend> {
+    frames: Vec<Tensor<B, 2>>,
+    capacity: usize,
+}
+
+impl<B: Backend> GpuRingBuffer<B> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            frames: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn push(&mut self, frame: Tensor<B, 2>) {
+        if self.frames.len() >= self.capacity {
+            self.frames.remove(0);
+        }
+        self.frames.push(frame);
+    }
+
+    fn get(&self, index: usize) -> Option<Tensor<B, 2>> {
+        if index < self.frames.len() {
+            // Index 0 is oldest, len-1 is newest
+            // We want get(0) to be newest (current), get(1) to be prev
+            let real_idx = self.frames.len() - 1 - index;
+            Some(self.frames[real_idx].clone())
+        } else {
+            None
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.frames.len() == self.capacity
+    }
+}
+
+/// Display panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Panel {
+    /// Input (grayscale)
+    Input,
+
+    /// FFT log-magnitude, DC in the centre, drawn with the colormap
+    Fft,
+
+    /// Sobel edges (green)
+    Sobel,
+
+    /// Motion energy (red), black until three frames were seen
+    Motion,
+}
+
+impl Panel {
+    pub const ALL: [Panel; 4] = [Panel::Input, Panel::Fft, Panel::Sobel, Panel::Motion];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Panel::Input => "input",
+            Panel::Fft => "fft",
+            Panel::Sobel => "sobel",
+            Panel::Motion => "motion",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+}
+
+/// Colormap for the FFT panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Colormap {
+    Gray,
+
+    /// Black - red - yellow - white
+    Hot,
+
+    /// Blue - cyan - yellow - red
+    Jet,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 3] = [Colormap::Gray, Colormap::Hot, Colormap::Jet];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Gray => "gray",
+            Colormap::Hot => "hot",
+            Colormap::Jet => "jet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    /// 0RGB pixel for each 8-bit level
+    pub fn lut(&self) -> [u32; 256] {
+        std::array::from_fn(|level| {
+            let t = level as f32 / 255.0;
+            let (r, g, b) = match self {
+                Colormap::Gray => (t, t, t),
+                Colormap::Hot => (3.0 * t, 3.0 * t - 1.0, 3.0 * t - 2.0),
+                Colormap::Jet => (
+                    1.5 - (4.0 * t - 3.0).abs(),
+                    1.5 - (4.0 * t - 2.0).abs(),
+                    1.5 - (4.0 * t - 1.0).abs(),
+                ),
+            };
+            let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0) as u32;
+            (channel(r) << 16) | (channel(g) << 8) | channel(b)
+        })
+    }
+}
+
+/// What the pipeline computes and how the spectrum is drawn
+#[derive(Clone, Debug)]
+pub struct PipelineConfig {
+    /// Panels in display order
+    pub panels: Vec<Panel>,
+
+    pub colormap: Colormap,
+
+    /// Exponent applied to the normalized log-magnitude (< 1 lifts weak bins)
+    pub gamma: f32,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            panels: Panel::ALL.to_vec(),
+            colormap: Colormap::Gray,
+            gamma: 1.0,
+        }
+    }
+}
+
+/// Display panels of one frame, row-major 0RGB pixels
+pub struct Panels {
+    pub width: usize,
+    pub height: usize,
+
+    /// Selected panels in display order
+    pub images: Vec<(Panel, Vec<u32>)>,
+}
+
+impl Panels {
+    pub fn get(&self, panel: Panel) -> Option<&[u32]> {
+        self.images.iter().find(|(p, _)| *p == panel).map(|(_, pixels)| pixels.as_slice())
+    }
+}
+
+/// Pipeline stages in processing order
+pub const STAGES: [&str; 7] = ["upload", "fft", "sobel", "temporal", "pack", "download", "postprocess"];
+
+/// Accumulated wall time per stage
+#[derive(Clone, Debug, Default)]
+pub struct StageTimings {
+    /// Indexed like `STAGES`
+    pub totals: [Duration; STAGES.len()],
+
+    /// Frames processed
+    pub frames: usize,
+}
+
+impl StageTimings {
+    /// Mean time per frame of each stage (ms)
+    pub fn mean_ms(&self) -> [f64; STAGES.len()] {
+        self.totals.map(|t| t.as_secs_f64() * 1e3 / self.frames.max(1) as f64)
+    }
+}
+
+pub struct FramePipeline<B: Backend> {
+    device: B::Device,
+    config: PipelineConfig,
+    colormap_lut: [u32; 256],
+    ring_buffer: GpuRingBuffer<B>,
+    timings: Option<StageTimings>,
+}
+
+impl<B: Backend + FftBackend + OpsBackend> FramePipeline<B> {
+    /// All panels, gray spectrum
+    pub fn new(device: &B::Device) -> Self {
+        Self::with_config(device, PipelineConfig::default())
+    }
+
+    pub fn with_config(device: &B::Device, config: PipelineConfig) -> Self {
+        Self {
+            device: device.clone(),
+            colormap_lut: config.colormap.lut(),
+            config,
+            ring_buffer: GpuRingBuffer::new(3),
+            timings: None,
+        }
+    }
+
+    pub fn config(&self) -> &PipelineConfig {
+        &self.config
+    }
+
+    /// Time every stage (synchronizes the device after each one)
+    pub fn with_timing(mut self) -> Self {
+        self.timings = Some(StageTimings::default());
+        self
+    }
+
+    /// Stage times so far, if timing is enabled
+    pub fn timings(&self) -> Option<&StageTimings> {
+        self.timings.as_ref()
+    }
+
+    /// Restart the stage timers (e.g. after warm-up frames)
+    pub fn reset_timings(&mut self) {
+        if let Some(timings) = &mut self.timings {
+            *timings = StageTimings::default();
+        }
+    }
+
+    /// End of `stage`: wait for the device and book the time since `lap`
+    fn lap(&mut self, stage: usize, lap: &mut Instant) {
+        if let Some(timings) = &mut self.timings {
+            B::sync(&self.device).unwrap();
+            timings.totals[stage] += lap.elapsed();
+            *lap = Instant::now();
+        }
+    }
+
+    /// Process one frame (sides must be powers of two)
+    pub fn process(&mut self, frame: &Frame) -> Panels {
+        let (width, height) = (frame.width, frame.height);
+        let wants = |panel: Panel| self.config.panels.contains(&panel);
+        let (want_fft, want_sobel, want_motion) = (wants(Panel::Fft), wants(Panel::Sobel), wants(Panel::Motion));
+        let mut lap = Instant::now();
+
+        // Upload to Device
+        let tensor = Tensor::<B, 1>::from_floats(frame.pixels.as_slice(), &self.device);
+        let tensor_2d = tensor.reshape([height, width]);
+        self.lap(0, &mut lap);
+
+        // Perform 2D FFT, reduced to shifted 8-bit levels on the device
+        let fft_result = want_fft.then(|| spectrum_levels(tensor_2d.clone(), self.config.gamma));
+        self.lap(1, &mut lap);
+
+        // Perform Sobel Edge Detection
+        let sobel_result = want_sobel.then(|| sobel(tensor_2d.clone()));
+        self.lap(2, &mut lap);
+
+        // Perform Temporal Difference (if we have enough frames)
+        let temporal_result = want_motion.then(|| {
+            self.ring_buffer.push(tensor_2d.clone());
+            if self.ring_buffer.is_full() {
+                let current = self.ring_buffer.get(0).unwrap();
+                let prev = self.ring_buffer.get(1).unwrap();
+                let prev_prev = self.ring_buffer.get(2).unwrap();
+                temporal_diff(current, prev, prev_prev)
+            } else {
+                Tensor::zeros_like(&tensor_2d)
+            }
+        });
+        self.lap(3, &mut lap);
+
+        // Pack the pixel panels on the device
+        let zeros = Tensor::zeros_like(&tensor_2d);
+        let mut packed = Vec::new();
+        for &panel in &self.config.panels {
+            let image = match panel {
+                // Gray input
+                Panel::Input => pack_rgb(tensor_2d.clone(), tensor_2d.clone(), tensor_2d.clone()),
+                // Greenish for edges
+                Panel::Sobel => pack_rgb(zeros.clone(), sobel_result.clone().unwrap(), zeros.clone()),
+                // Red for motion (amplified)
+                Panel::Motion => pack_rgb(temporal_result.clone().unwrap().mul_scalar(5.0), zeros.clone(), zeros.clone()),
+                // Colormapped on the host
+                Panel::Fft => continue,
+            };
+            packed.push((panel, image));
+        }
+        self.lap(4, &mut lap);
+
+        // Download Results
+        let mut images: Vec<(Panel, Vec<u32>)> = packed.into_iter()
+            .map(|(panel, image)| (panel, to_pixels(image)))
+            .collect();
+        let fft_levels: Option<Vec<u8>> = fft_result.map(to_levels);
+        self.lap(5, &mut lap);
+
+        if let Some(fft_levels) = fft_levels {
+            let lut = &self.colormap_lut;
+            let fft: Vec<u32> = fft_levels.par_iter().map(|&level| lut[level as usize]).collect();
+            let position = self.config.panels.iter().position(|&p| p == Panel::Fft).unwrap();
+            images.insert(position, (Panel::Fft, fft));
+        }
+        self.lap(6, &mut lap);
+
+        if let Some(timings) = &mut self.timings {
+            timings.frames += 1;
+        }
+
+        Panels { width, height, images }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuBackend;
+    use crate::frame_source::{FrameSource, SyntheticSource};
+
+    #[test]
+    fn test_synthetic_frames_headless() {
+        let device = Default::default();
+        let mut source = SyntheticSource::new(64, 64).with_limit(3);
+        let mut pipeline = FramePipeline::<CpuBackend>::new(&device);
+
+        let mut panels = Vec::new();
+        while let Some(frame) = source.next_frame().unwrap() {
+            panels.push(pipeline.process(&frame));
+        }
+        assert_eq!(panels.len(), 3);
+
+        // Motion appears once the ring buffer holds three frames
+        assert!(panels[1].get(Panel::Motion).unwrap().iter().all(|&p| p == 0));
+        assert!(panels[2].get(Panel::Motion).unwrap().iter().any(|&p| p != 0));
+
+        // Circle edges are green, the spectrum peaks at the centre (DC)
+        let last = &panels[2];
+        assert!(last.get(Panel::Sobel).unwrap().contains(&0x00FF00));
+        assert_eq!(last.get(Panel::Fft).unwrap()[32 * 64 + 32], 0xFFFFFF);
+    }
+
+    #[test]
+    fn test_panel_selection_and_resolution() {
+        let device = Default::default();
+        let config = PipelineConfig {
+            panels: vec![Panel::Fft, Panel::Input],
+            colormap: Colormap::Hot,
+            gamma: 0.5,
+        };
+        let mut pipeline = FramePipeline::<CpuBackend>::with_config(&device, config);
+
+        // Non-square frame: the shift follows each side
+        let frame = SyntheticSource::new(64, 32).next_frame().unwrap().unwrap();
+        let panels = pipeline.process(&frame);
+
+        let order: Vec<Panel> = panels.images.iter().map(|(p, _)| *p).collect();
+        assert_eq!(order, vec![Panel::Fft, Panel::Input]);
+        assert!(panels.get(Panel::Sobel).is_none());
+        assert_eq!(panels.get(Panel::Fft).unwrap()[16 * 64 + 32], 0xFFFFFF);
+    }
+}
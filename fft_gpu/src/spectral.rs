@@ -0,0 +1,123 @@
+//! Spectral analysis helpers
+//!
+//! The FFT kernels return bins in natural order (DC first, negative
+//! frequencies in the upper half). `fftshift` moves DC to the centre for
+//! display or symmetric analysis and `ifftshift` undoes it; both work on
+//! every dimension of a tensor of any rank (1-D spectra, 2-D images) and
+//! stay on the device (slice + cat per dimension).
+//!
+//! Odd lengths follow NumPy: `fftshift` puts DC at index `n / 2`, and only
+//! `ifftshift` brings it back to 0.
+//!
+//! `stft_magnitude` is the short-time transform for audio: frames are
+//! gathered from the signal on the device, weighted by a window
+//! (`hann_window`) and transformed in one batched FFT launch.
+
+use burn::tensor::{Int, Tensor, backend::Backend};
+use crate::cube_fft::FftBackend;
+use crate::image_ops::fft_rows;
+
+/// Move the zero-frequency bin to index `n / 2` of every dimension
+pub fn fftshift<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Tensor<B, D> {
+    // burn's roll_dim(s) reads out[i] = in[i + s]: NumPy's roll by -s
+    let dims = tensor.dims();
+    (0..D).fold(tensor, |t, dim| t.roll_dim(-((dims[dim] / 2) as i64), dim))
+}
+
+/// Inverse of `fftshift`: the bin at `n / 2` goes back to index 0
+pub fn ifftshift<B: Backend, const D: usize>(tensor: Tensor<B, D>) -> Tensor<B, D> {
+    let dims = tensor.dims();
+    (0..D).fold(tensor, |t, dim| t.roll_dim((dims[dim] / 2) as i64, dim))
+}
+
+/// Periodic Hann window, 0.5 - 0.5 cos(2πi / n)
+pub fn hann_window<B: Backend>(n: usize, device: &B::Device) -> Tensor<B, 1> {
+    Tensor::<B, 1, Int>::arange(0..n as i64, device)
+        .float()
+        .mul_scalar(2.0 * std::f32::consts::PI / n as f32)
+        .cos()
+        .mul_scalar(-0.5)
+        .add_scalar(0.5)
+}
+
+/// Magnitude STFT of a real signal, `[Frames, n / 2 + 1]` (DC to Nyquist)
+///
+/// Frame `f` covers samples `f * hop .. f * hop + n`, where `n` is the
+/// window length (a power of two); a trailing partial frame is dropped.
+pub fn stft_magnitude<B: Backend + FftBackend>(
+    signal: Tensor<B, 1>,
+    window: Tensor<B, 1>,
+    hop: usize,
+) -> Tensor<B, 2> {
+    let n = window.dims()[0];
+    let len = signal.dims()[0];
+    assert!(n.is_power_of_two(), "STFT length must be a power of two");
+    assert!(hop > 0 && len >= n, "signal shorter than one frame");
+    let frames = (len - n) / hop + 1;
+    let device = signal.device();
+
+    // Sample index of every frame element
+    let starts = Tensor::<B, 1, Int>::arange(0..frames as i64, &device).mul_scalar(hop as i64).reshape([frames, 1]);
+    let offsets = Tensor::<B, 1, Int>::arange(0..n as i64, &device).reshape([1, n]);
+    let indices = (starts + offsets).reshape([frames * n]);
+
+    let framed = signal.select(0, indices).reshape([frames, n]).mul(window.reshape([1, n]));
+    let (real, imag) = fft_rows(framed.clone(), framed.zeros_like());
+
+    (real.powf_scalar(2.0) + imag.powf_scalar(2.0))
+        .sqrt()
+        .slice([0..frames, 0..n / 2 + 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuBackend;
+
+    #[test]
+    fn test_shift_1d_matches_numpy() {
+        let device = Default::default();
+        let even = Tensor::<CpuBackend, 1>::from_floats([0.0, 1.0, 2.0, 3.0, -4.0, -3.0, -2.0, -1.0], &device);
+        let odd = Tensor::<CpuBackend, 1>::from_floats([0.0, 1.0, 2.0, -2.0, -1.0], &device);
+
+        let shifted: Vec<f32> = fftshift(even.clone()).into_data().to_vec().unwrap();
+        assert_eq!(shifted, vec![-4.0, -3.0, -2.0, -1.0, 0.0, 1.0, 2.0, 3.0]);
+
+        let shifted = fftshift(odd.clone());
+        assert_eq!(shifted.clone().into_data().to_vec::<f32>().unwrap(), vec![-2.0, -1.0, 0.0, 1.0, 2.0]);
+        assert_eq!(ifftshift(shifted).into_data().to_vec::<f32>().unwrap(), vec![0.0, 1.0, 2.0, -2.0, -1.0]);
+    }
+
+    #[test]
+    fn test_stft_tone_bin() {
+        let device = Default::default();
+
+        // Unit sine in bin 4 of a 32-point frame: Hann peak |X| = n / 4
+        let tone: Vec<f32> = (0..100).map(|t| (2.0 * std::f32::consts::PI * 4.0 * t as f32 / 32.0).sin()).collect();
+        let signal = Tensor::<CpuBackend, 1>::from_floats(tone.as_slice(), &device);
+
+        let magnitude = stft_magnitude(signal, hann_window(32, &device), 16);
+        assert_eq!(magnitude.dims(), [5, 17]);
+
+        let values: Vec<f32> = magnitude.into_data().to_vec().unwrap();
+        for frame in values.chunks(17) {
+            assert!((frame[4] - 8.0).abs() < 1e-3);
+            assert!(frame[8] < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_shift_2d_centres_dc() {
+        let device = Default::default();
+        let mut values = vec![0.0f32; 4 * 6];
+        values[0] = 1.0;
+        let image = Tensor::<CpuBackend, 1>::from_floats(values.as_slice(), &device).reshape([4, 6]);
+
+        let shifted = fftshift(image.clone());
+        let pixels: Vec<f32> = shifted.clone().into_data().to_vec().unwrap();
+        assert_eq!(pixels[2 * 6 + 3], 1.0);
+
+        let back: Vec<f32> = ifftshift(shifted).into_data().to_vec().unwrap();
+        assert_eq!(back, values);
+    }
+}
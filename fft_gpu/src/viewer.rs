@@ -0,0 +1,252 @@
+//! Camera/video viewer and video generation behind the `fft_gpu` binary
+//!
+//! Needs the `viewer` feature (window, camera capture, image resizing);
+//! the FFT and pipeline modules build without it.
+
+use burn::tensor::backend::Backend;
+use crate::cube_fft::FftBackend;
+use crate::cube_ops::OpsBackend;
+use crate::camera::CameraSource;
+use crate::frame_source::{FrameSource, SyntheticSource, VideoFileSource};
+use crate::pipeline::{Colormap, FramePipeline, Panel, PipelineConfig};
+use crate::{bench, CpuBackend, MyBackend};
+use std::io::Write;
+use minifb::{Window, WindowOptions, Key, ScaleMode};
+use rayon::prelude::*;
+
+/// Default viewer frame size
+const VIEWER_SIZE: usize = 1024;
+
+/// Viewer and benchmark settings from the command line
+#[derive(Clone, Debug)]
+pub struct ViewerOptions {
+    /// Frame side in pixels (power of two)
+    pub size: usize,
+
+    pub pipeline: PipelineConfig,
+}
+
+impl Default for ViewerOptions {
+    fn default() -> Self {
+        Self { size: VIEWER_SIZE, pipeline: PipelineConfig::default() }
+    }
+}
+
+impl ViewerOptions {
+    /// Parse `--size N`, `--panels input,fft,sobel,motion`, `--colormap gray|hot|jet`, `--gamma G`
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        if let Some(size) = arg_value(args, "--size") {
+            options.size = size.parse().map_err(|_| format!("bad --size {:?}", size))?;
+            if !options.size.is_power_of_two() || options.size < 2 {
+                return Err(format!("--size must be a power of two, got {}", options.size));
+            }
+        }
+
+        if let Some(panels) = arg_value(args, "--panels") {
+            let mut selected = Vec::new();
+            for name in panels.split(',') {
+                let panel = Panel::from_name(name)
+                    .ok_or_else(|| format!("unknown panel {:?} (input, fft, sobel, motion)", name))?;
+                if !selected.contains(&panel) {
+                    selected.push(panel);
+                }
+            }
+            options.pipeline.panels = selected;
+        }
+
+        if let Some(name) = arg_value(args, "--colormap") {
+            options.pipeline.colormap = Colormap::from_name(name)
+                .ok_or_else(|| format!("unknown colormap {:?} (gray, hot, jet)", name))?;
+        }
+
+        if let Some(gamma) = arg_value(args, "--gamma") {
+            options.pipeline.gamma = gamma.parse().ok()
+                .filter(|g: &f32| *g > 0.0)
+                .ok_or_else(|| format!("bad --gamma {:?}", gamma))?;
+        }
+
+        Ok(options)
+    }
+}
+
+/// Value following `flag`, if present
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).map(String::as_str)
+}
+
+pub fn run() {
+    let args: Vec<String> = std::env::args().collect();
+    let generate_video = args.contains(&"--generate-video".to_string());
+    let on_cpu = args.contains(&"--on_cpu".to_string());
+    // --bench [frames]: headless synthetic run, JSON report on stdout
+    let bench_frames = args.iter().position(|a| a == "--bench")
+        .map(|i| args.get(i + 1).and_then(|n| n.parse().ok()).unwrap_or(100));
+
+    let options = match ViewerOptions::from_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    if on_cpu {
+        eprintln!("Running on CPU (NdArray + Rayon + RustFFT)");
+        rayon::ThreadPoolBuilder::new().num_threads(10).build_global().unwrap();
+        let device = burn_ndarray::NdArrayDevice::Cpu;
+        
+        if let Some(frames) = bench_frames {
+            let report = bench::run_bench::<CpuBackend>(&device, "ndarray", frames, options.size, options.size, options.pipeline);
+            println!("{}", report.to_json());
+        } else if generate_video {
+            run_video_generation::<CpuBackend>(&device);
+        } else if let Some(mut source) = open_source(&args, options.size) {
+            run_viewer::<CpuBackend>(&device, source.as_mut(), &options);
+        }
+    } else {
+        let device = burn::backend::wgpu::WgpuDevice::default();
+        eprintln!("Initializing 2D FFT on GPU: {:?}", device);
+
+        if let Some(frames) = bench_frames {
+            let report = bench::run_bench::<MyBackend>(&device, "wgpu", frames, options.size, options.size, options.pipeline);
+            println!("{}", report.to_json());
+        } else if generate_video {
+            run_video_generation::<MyBackend>(&device);
+        } else if let Some(mut source) = open_source(&args, options.size) {
+            run_viewer::<MyBackend>(&device, source.as_mut(), &options);
+        }
+    }
+}
+
+/// Frame source selected on the command line: `--video <path>`, `--synthetic` or the camera
+fn open_source(args: &[String], size: usize) -> Option<Box<dyn FrameSource>> {
+    if let Some(path) = arg_value(args, "--video") {
+        println!("Reading video file {}", path);
+        match VideoFileSource::open(path, size, size) {
+            Ok(source) => Some(Box::new(source)),
+            Err(e) => {
+                eprintln!("Could not open {}: {}", path, e);
+                None
+            }
+        }
+    } else if args.contains(&"--synthetic".to_string()) {
+        Some(Box::new(SyntheticSource::new(size, size)))
+    } else {
+        println!("Starting Realtime Camera Mode...");
+        Some(Box::new(CameraSource::new(0, size, size)))
+    }
+}
+
+pub fn run_realtime_camera<B: Backend + FftBackend + OpsBackend>(device: &B::Device) {
+    println!("Starting Realtime Camera Mode...");
+    run_viewer::<B>(device, &mut CameraSource::new(0, VIEWER_SIZE, VIEWER_SIZE), &ViewerOptions::default());
+}
+
+/// Show the selected panels of `source` side by side in a window
+///
+/// Source errors (camera unplugged) are retried every second; the viewer
+/// closes at the end of the stream or on ESC.
+pub fn run_viewer<B: Backend + FftBackend + OpsBackend>(
+    device: &B::Device,
+    source: &mut dyn FrameSource,
+    options: &ViewerOptions,
+) {
+    // Setup Window
+    let width = options.size;
+    let height = options.size;
+    let window_width = width * options.pipeline.panels.len().max(1);
+    let window_height = height;
+    
+    let mut window = Window::new(
+        "Realtime 2D FFT & Sobel & Temporal - Burn",
+        window_width,
+        window_height,
+        WindowOptions {
+            resize: true,
+            scale_mode: ScaleMode::AspectRatioStretch,
+            ..WindowOptions::default()
+        },
+    ).unwrap_or_else(|e| {
+        panic!("{}", e);
+    });
+    
+    window.limit_update_rate(Some(std::time::Duration::from_micros(16600))); // ~60 FPS
+    let mut buffer: Vec<u32> = vec![0; window_width * window_height];
+
+    let mut pipeline = FramePipeline::<B>::with_config(device, options.pipeline.clone());
+    
+    println!("Press ESC to exit.");
+    
+    let mut frame_count = 0;
+    let mut last_print = std::time::Instant::now();
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let frame = match source.next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => {
+                println!("End of stream.");
+                break;
+            }
+            Err(e) => {
+                eprintln!("{}. Retrying in 1s...", e);
+                // Update window to keep it alive/responsive
+                window.update_with_buffer(&buffer, window_width, window_height).unwrap();
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                continue;
+            }
+        };
+        
+        let panels = pipeline.process(&frame);
+        
+        // Update Window Buffer: panels left to right
+        buffer.par_chunks_mut(window_width).enumerate().for_each(|(y, row)| {
+            let idx = y * width;
+            for (slot, (_, pixels)) in row.chunks_mut(width).zip(&panels.images) {
+                slot.copy_from_slice(&pixels[idx..idx + width]);
+            }
+        });
+        
+        window.update_with_buffer(&buffer, window_width, window_height).unwrap();
+        
+        frame_count += 1;
+        if frame_count % 60 == 0 {
+            let elapsed = last_print.elapsed();
+            println!("FPS: {:.2}", 60.0 / elapsed.as_secs_f64());
+            last_print = std::time::Instant::now();
+        }
+    }
+}
+
+pub fn run_video_generation<B: Backend + FftBackend + OpsBackend>(device: &B::Device) {
+    eprintln!("Generating test video frames...");
+    let width = 256;
+    let height = 256;
+    let frames = 120;
+    
+    let mut source = SyntheticSource::new(width, height).with_limit(frames);
+    let config = PipelineConfig { panels: vec![Panel::Input, Panel::Fft], ..PipelineConfig::default() };
+    let mut pipeline = FramePipeline::<B>::with_config(device, config);
+    
+    eprintln!("Processing {} frames of size {}x{}", frames, width, height);
+
+    let mut stdout = std::io::stdout();
+    
+    while let Some(frame) = source.next_frame().unwrap() {
+        let panels = pipeline.process(&frame);
+        
+        // Side by side: Input | FFT Magnitude (Shifted)
+        let mut rgb_frame = Vec::with_capacity(width * height * 3 * 2);
+        for y in 0..height {
+            for (_, pixels) in &panels.images {
+                for &pixel in &pixels[y * width..(y + 1) * width] {
+                    rgb_frame.extend_from_slice(&[(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8]);
+                }
+            }
+        }
+        
+        stdout.write_all(&rgb_frame).unwrap();
+    }
+    eprintln!("Video generation complete.");
+}
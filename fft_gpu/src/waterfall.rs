@@ -0,0 +1,231 @@
+//! Scrolling audio waterfall
+//!
+//! Audio is cut into Hann-windowed frames (`hop` samples apart) and every
+//! batch of frames goes through the batched STFT (`spectral::stft_magnitude`)
+//! in one launch. Each
+//! frame becomes one row of the image, newest on top, with columns from DC to
+//! half the sample rate; levels are mapped to the colormap over a fixed dB
+//! window.
+//!
+//! Markers (e.g. sync detections reported by a decoder) are tied to a sample
+//! position and drawn on the row containing it, so they scroll with the
+//! spectrum and disappear with it.
+
+use std::collections::VecDeque;
+use burn::tensor::{Tensor, backend::Backend};
+use crate::cube_fft::FftBackend;
+use crate::pipeline::Colormap;
+use crate::spectral::{hann_window, stft_magnitude};
+
+/// Length of the solid part of a marker, from the left edge (pixels)
+const MARKER_TICK: usize = 16;
+
+#[derive(Clone, Debug)]
+pub struct WaterfallConfig {
+    /// FFT length per row (power of two); the image is `fft_len / 2` wide
+    pub fft_len: usize,
+
+    /// Samples between rows
+    pub hop: usize,
+
+    /// Rows of history
+    pub rows: usize,
+
+    pub colormap: Colormap,
+
+    /// Level at the bottom of the colormap (dB re full-scale sine)
+    pub floor_db: f32,
+
+    /// Span of the colormap (dB)
+    pub range_db: f32,
+}
+
+impl Default for WaterfallConfig {
+    fn default() -> Self {
+        Self {
+            fft_len: 512,
+            hop: 256,
+            rows: 600,
+            colormap: Colormap::Hot,
+            floor_db: -90.0,
+            range_db: 70.0,
+        }
+    }
+}
+
+pub struct Waterfall<B: Backend> {
+    device: B::Device,
+    config: WaterfallConfig,
+    lut: [u32; 256],
+    window: Tensor<B, 1>,
+
+    /// Samples not yet consumed by a row
+    pending: Vec<f32>,
+
+    /// Stream position of `pending[0]`
+    pending_start: u64,
+
+    /// `rows` x `fft_len / 2` pixels, newest row first
+    image: Vec<u32>,
+
+    /// Stream position of the first sample of each displayed row, newest first
+    row_starts: VecDeque<u64>,
+
+    /// (stream position, color)
+    markers: Vec<(u64, u32)>,
+}
+
+impl<B: Backend + FftBackend> Waterfall<B> {
+    pub fn new(device: &B::Device, config: WaterfallConfig) -> Self {
+        assert!(config.fft_len.is_power_of_two(), "fft_len must be a power of two");
+        assert!(config.hop > 0 && config.rows > 0);
+
+        let n = config.fft_len;
+
+        Self {
+            device: device.clone(),
+            lut: config.colormap.lut(),
+            window: hann_window(n, device),
+            pending: Vec::new(),
+            pending_start: 0,
+            image: vec![0; config.rows * n / 2],
+            row_starts: VecDeque::with_capacity(config.rows),
+            markers: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.config.fft_len / 2
+    }
+
+    pub fn height(&self) -> usize {
+        self.config.rows
+    }
+
+    /// Samples pushed so far
+    pub fn position(&self) -> u64 {
+        self.pending_start + self.pending.len() as u64
+    }
+
+    /// Append audio; returns the number of rows added
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        self.pending.extend_from_slice(samples);
+
+        let (n, hop, width) = (self.config.fft_len, self.config.hop, self.width());
+        if self.pending.len() < n {
+            return 0;
+        }
+        let new_rows = (self.pending.len() - n) / hop + 1;
+
+        let used = (new_rows - 1) * hop + n;
+        let samples = Tensor::<B, 1>::from_floats(&self.pending[..used], &self.device);
+
+        // Power relative to a full-scale sine, which peaks at |X| = N/4 through the Hann window
+        let power = stft_magnitude(samples, self.window.clone(), hop)
+            .slice([0..new_rows, 0..width])
+            .powf_scalar(2.0)
+            .mul_scalar(16.0 / (n as f32 * n as f32));
+        let level = power.add_scalar(1e-20).log()
+            .mul_scalar(10.0 / std::f32::consts::LN_10)
+            .sub_scalar(self.config.floor_db)
+            .div_scalar(self.config.range_db)
+            .clamp(0.0, 1.0)
+            .mul_scalar(255.0);
+        let levels: Vec<f32> = level.into_data().to_vec().unwrap();
+
+        // Scroll down and write the new rows on top, newest first
+        let keep = self.config.rows.saturating_sub(new_rows);
+        self.image.copy_within(0..keep * width, (self.config.rows - keep) * width);
+        for r in 0..new_rows.min(self.config.rows) {
+            let source = new_rows - 1 - r;
+            for (pixel, &level) in self.image[r * width..(r + 1) * width].iter_mut().zip(&levels[source * width..]) {
+                *pixel = self.lut[level as usize];
+            }
+        }
+
+        for r in 0..new_rows {
+            self.row_starts.push_front(self.pending_start + (r * hop) as u64);
+        }
+        self.row_starts.truncate(self.config.rows);
+
+        self.pending.drain(..new_rows * hop);
+        self.pending_start += (new_rows * hop) as u64;
+
+        // Markers that scrolled out
+        if let Some(&oldest) = self.row_starts.back() {
+            self.markers.retain(|&(position, _)| position >= oldest);
+        }
+
+        new_rows
+    }
+
+    /// Mark a stream position; drawn as a tick and dotted line across its row
+    pub fn mark(&mut self, position: u64, color: u32) {
+        self.markers.push((position, color));
+    }
+
+    /// Row showing `position`, if it is on screen
+    pub fn row_of(&self, position: u64) -> Option<usize> {
+        let newest = *self.row_starts.front()?;
+        if position >= newest + self.config.hop as u64 {
+            return None;
+        }
+        self.row_starts.iter().position(|&start| start <= position)
+    }
+
+    /// Image with markers, row-major 0RGB pixels
+    pub fn render(&self) -> Vec<u32> {
+        let width = self.width();
+        let mut pixels = self.image.clone();
+
+        for &(position, color) in &self.markers {
+            if let Some(row) = self.row_of(position) {
+                let line = &mut pixels[row * width..(row + 1) * width];
+                for (x, pixel) in line.iter_mut().enumerate() {
+                    if x < MARKER_TICK || x % 4 == 0 {
+                        *pixel = color;
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CpuBackend;
+
+    #[test]
+    fn test_tone_column_and_markers() {
+        let device = Default::default();
+        let config = WaterfallConfig { fft_len: 64, hop: 32, rows: 8, colormap: Colormap::Gray, ..Default::default() };
+        let mut waterfall = Waterfall::<CpuBackend>::new(&device, config);
+
+        // Full-scale tone in bin 8
+        let tone: Vec<f32> = (0..256).map(|t| (2.0 * std::f32::consts::PI * 8.0 * t as f32 / 64.0).sin()).collect();
+        assert_eq!(waterfall.push(&tone[..48]), 0);
+        assert_eq!(waterfall.push(&tone[48..]), 7);
+        assert_eq!(waterfall.position(), 256);
+
+        // 0 dB is 90 dB above the floor: saturated; far bins are at the floor
+        let image = waterfall.render();
+        assert_eq!(image[8], 0xFFFFFF);
+        assert_eq!(image[24], 0);
+
+        // Newest row starts at 192; the marker at 100 lands on row (192 - 96) / 32
+        waterfall.mark(100, 0x00FF00);
+        assert_eq!(waterfall.row_of(100), Some(3));
+        assert_eq!(waterfall.render()[3 * 32], 0x00FF00);
+
+        // Not yet on screen
+        assert_eq!(waterfall.row_of(300), None);
+
+        // Scrolled out with its row
+        waterfall.push(&vec![0.0; 32 * 8]);
+        assert_eq!(waterfall.row_of(100), None);
+        assert!(waterfall.render().iter().all(|&p| p != 0x00FF00));
+    }
+}